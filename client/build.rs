@@ -0,0 +1,127 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Generates the typed API of the client from the RPC trait definitions of codechain-rpc.
+
+#[path = "../rpc/rpc_traits.rs"]
+mod rpc_traits;
+
+use rpc_traits::{parse_methods, parse_trait_name, parse_uses};
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const TRAITS_DIR: &str = "../rpc/src/v1/traits";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed={}", TRAITS_DIR);
+    let api = generate(Path::new(TRAITS_DIR))?;
+    fs::write(Path::new(&env::var("OUT_DIR")?).join("api.rs"), api)?;
+    Ok(())
+}
+
+fn generate(traits_dir: &Path) -> io::Result<String> {
+    let mut paths =
+        fs::read_dir(traits_dir)?.map(|entry| entry.map(|entry| entry.path())).collect::<io::Result<Vec<_>>>()?;
+    paths.sort();
+
+    let mut modules = String::new();
+    let mut accessors = String::new();
+    for path in paths {
+        if path.file_name().map_or(true, |name| name == "mod.rs") {
+            continue
+        }
+        let module = path.file_stem().and_then(|stem| stem.to_str()).expect("Trait files have UTF-8 names");
+        let source = fs::read_to_string(&path)?;
+        let trait_name = match parse_trait_name(&source) {
+            Some(trait_name) => trait_name,
+            None => continue,
+        };
+        let api = format!("{}Api", trait_name);
+
+        writeln!(modules, "pub mod {} {{", module).unwrap();
+        for use_declaration in parse_uses(&source).iter().filter_map(|use_declaration| rewrite_use(use_declaration)) {
+            writeln!(modules, "    {}", use_declaration).unwrap();
+        }
+        writeln!(modules, "    use crate::{{Client, Result}};").unwrap();
+        writeln!(modules).unwrap();
+        writeln!(modules, "    /// The methods of the `{}` RPC trait.", trait_name).unwrap();
+        writeln!(modules, "    pub struct {}<'a> {{", api).unwrap();
+        writeln!(modules, "        client: &'a Client,").unwrap();
+        writeln!(modules, "    }}").unwrap();
+        writeln!(modules).unwrap();
+        writeln!(modules, "    impl<'a> {}<'a> {{", api).unwrap();
+        writeln!(modules, "        pub(crate) fn new(client: &'a Client) -> Self {{").unwrap();
+        writeln!(modules, "            Self {{ client }}").unwrap();
+        writeln!(modules, "        }}").unwrap();
+        for method in parse_methods(&source) {
+            let params: Vec<String> = method.params.iter().map(|(name, ty)| format!("{}: {}", name, ty)).collect();
+            let values: Vec<String> =
+                method.params.iter().map(|(name, _)| format!("serde_json::to_value({})?", name)).collect();
+            writeln!(modules).unwrap();
+            if !method.summary.is_empty() {
+                writeln!(modules, "        /// {}", method.summary).unwrap();
+                writeln!(modules, "        ///").unwrap();
+            }
+            writeln!(modules, "        /// Calls `{}`.", method.name).unwrap();
+            writeln!(
+                modules,
+                "        pub async fn {}(&self{}) -> Result<{}> {{",
+                method.function,
+                params.iter().map(|param| format!(", {}", param)).collect::<String>(),
+                method.result
+            )
+            .unwrap();
+            writeln!(modules, "            let params = vec![{}];", values.join(", ")).unwrap();
+            writeln!(modules, "            self.client.call(\"{}\", params).await", method.name).unwrap();
+            writeln!(modules, "        }}").unwrap();
+        }
+        writeln!(modules, "    }}").unwrap();
+        writeln!(modules, "}}").unwrap();
+        writeln!(modules).unwrap();
+
+        writeln!(accessors, "    pub fn {}(&self) -> {}::{} {{", module, module, api).unwrap();
+        writeln!(accessors, "        {}::{}::new(self)", module, api).unwrap();
+        writeln!(accessors, "    }}").unwrap();
+    }
+    Ok(format!("{}impl Client {{\n{}}}\n", modules, accessors))
+}
+
+/// Rewrites a `use` declaration of a trait file so that it resolves in the client crate.
+/// The JSON-RPC result and future types are replaced by the result type of the client.
+fn rewrite_use(use_declaration: &str) -> Option<String> {
+    if use_declaration.starts_with("use super::super::types") {
+        return Some(use_declaration.replacen("super::super::types", "crpc::v1::types", 1))
+    }
+    if use_declaration.starts_with("use jsonrpc_core::") {
+        let imported = use_declaration["use jsonrpc_core::".len()..].trim_end_matches(';');
+        let names: Vec<&str> = imported
+            .trim_start_matches('{')
+            .trim_end_matches('}')
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty() && *name != "Result" && *name != "BoxFuture")
+            .collect();
+        return match names.as_slice() {
+            [] => None,
+            [name] => Some(format!("use serde_json::{};", name)),
+            names => Some(format!("use serde_json::{{{}}};", names.join(", "))),
+        }
+    }
+    Some(use_declaration.to_string())
+}
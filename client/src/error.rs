@@ -0,0 +1,81 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use serde_json::Value;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The request couldn't be delivered or the response couldn't be received.
+    Transport(String),
+    /// The response didn't arrive within the timeout of the retry policy.
+    Timeout,
+    /// The node responded with a JSON-RPC error.
+    Rpc {
+        code: i64,
+        message: String,
+        data: Option<Value>,
+    },
+    /// The response doesn't match the JSON-RPC format or the expected type.
+    InvalidResponse(String),
+    Json(serde_json::Error),
+}
+
+impl Error {
+    /// Returns true if sending the same request again may succeed.
+    pub fn is_retriable(&self) -> bool {
+        match self {
+            Error::Transport(_) | Error::Timeout => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Transport(err) => write!(f, "Transport error: {}", err),
+            Error::Timeout => write!(f, "Request timed out"),
+            Error::Rpc {
+                code,
+                message,
+                ..
+            } => write!(f, "RPC error {}: {}", code, message),
+            Error::InvalidResponse(err) => write!(f, "Invalid response: {}", err),
+            Error::Json(err) => write!(f, "JSON error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl From<hyper::Error> for Error {
+    fn from(err: hyper::Error) -> Self {
+        Error::Transport(err.to_string())
+    }
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for Error {
+    fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
+        Error::Transport(err.to_string())
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! A typed client of the Foundry JSON-RPC.
+//!
+//! The methods are generated from the RPC traits of codechain-rpc, so they take and return the same types as the
+//! server does.
+//!
+//! ```no_run
+//! # async fn example() -> foundry_client::Result<()> {
+//! let client = foundry_client::Client::http("http://127.0.0.1:8080")?;
+//! let best = client.chain().get_best_block_number().await?;
+//! let block = client.chain().get_block_by_number(best).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+extern crate codechain_json as cjson;
+extern crate codechain_key as ckey;
+extern crate codechain_rpc as crpc;
+extern crate codechain_types as ctypes;
+#[macro_use]
+extern crate log;
+
+mod error;
+mod policy;
+mod transport;
+
+pub use crate::error::Error;
+pub use crate::policy::RetryPolicy;
+pub use crpc::v1::types;
+
+use crate::transport::{HttpTransport, Transport, WsTransport};
+use crpc::v1::types::Block;
+use futures::stream::{self, Stream};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::time::{delay_for, timeout};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+include!(concat!(env!("OUT_DIR"), "/api.rs"));
+
+pub struct Client {
+    transport: Transport,
+    policy: RetryPolicy,
+    next_id: AtomicU64,
+}
+
+impl Client {
+    /// Creates a client which sends each request in an HTTP POST request.
+    pub fn http(url: &str) -> Result<Self> {
+        Ok(Self::new(Transport::Http(HttpTransport::new(url)?)))
+    }
+
+    /// Creates a client which sends the requests over a WebSocket connection.
+    /// It must be called in the context of a tokio runtime, which runs the connection.
+    pub async fn ws(url: &str) -> Result<Self> {
+        Ok(Self::new(Transport::Ws(WsTransport::connect(url).await?)))
+    }
+
+    fn new(transport: Transport) -> Self {
+        Self {
+            transport,
+            policy: RetryPolicy::default(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: RetryPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Calls the method with positional parameters, applying the retry policy.
+    pub async fn call<T: DeserializeOwned>(&self, method: &str, params: Vec<Value>) -> Result<T> {
+        let mut attempt = 0;
+        loop {
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+            let request = json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": method,
+                "params": params,
+            })
+            .to_string();
+            let response = match timeout(self.policy.timeout, self.transport.send(id, request)).await {
+                Ok(response) => response,
+                Err(_) => Err(Error::Timeout),
+            };
+            match response {
+                Ok(response) => return parse_response(response),
+                Err(err) if err.is_retriable() && attempt < self.policy.retries => {
+                    attempt += 1;
+                    debug!("{} failed: {}. Retrying({}/{})", method, err, attempt, self.policy.retries);
+                    delay_for(self.policy.retry_interval).await;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Yields the blocks imported after the subscription, in the order of their numbers.
+    /// The node doesn't publish events yet, so the stream polls the best block number at the given interval over
+    /// the transport of the client, whether it is HTTP or WebSocket.
+    pub fn subscribe_new_blocks(&self, interval: Duration) -> impl Stream<Item = Result<Block>> + '_ {
+        stream::unfold((self, None), move |(client, last)| async move {
+            let mut last: Option<u64> = last;
+            loop {
+                let best = match client.chain().get_best_block_number().await {
+                    Ok(best) => best,
+                    Err(err) => return Some((Err(err), (client, last))),
+                };
+                let next = *last.get_or_insert(best) + 1;
+                if next <= best {
+                    return match client.chain().get_block_by_number(next).await {
+                        Ok(Some(block)) => Some((Ok(block), (client, Some(next)))),
+                        Ok(None) => {
+                            Some((Err(Error::InvalidResponse(format!("Block #{} is not found", next))), (client, last)))
+                        }
+                        Err(err) => Some((Err(err), (client, last))),
+                    }
+                }
+                delay_for(interval).await;
+            }
+        })
+    }
+}
+
+fn parse_response<T: DeserializeOwned>(mut response: Value) -> Result<T> {
+    if let Some(error) = response.get_mut("error").map(Value::take) {
+        let code = error.get("code").and_then(Value::as_i64);
+        let message = error.get("message").and_then(Value::as_str);
+        return match (code, message) {
+            (Some(code), Some(message)) => Err(Error::Rpc {
+                code,
+                message: message.to_string(),
+                data: error.get("data").cloned(),
+            }),
+            _ => Err(Error::InvalidResponse(error.to_string())),
+        }
+    }
+    match response.get_mut("result") {
+        Some(result) => Ok(serde_json::from_value(result.take())?),
+        None => Err(Error::InvalidResponse(response.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn result_is_deserialized() {
+        let response = json!({"jsonrpc": "2.0", "id": 1, "result": 3});
+        assert_eq!(3u64, parse_response::<u64>(response).unwrap());
+
+        let response = json!({"jsonrpc": "2.0", "id": 1, "result": null});
+        assert_eq!(None, parse_response::<Option<u64>>(response).unwrap());
+    }
+
+    #[test]
+    fn error_is_not_retriable() {
+        let response = json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32602, "message": "Invalid params"}});
+        match parse_response::<u64>(response) {
+            Err(
+                err @ Error::Rpc {
+                    ..
+                },
+            ) => assert!(!err.is_retriable()),
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn response_without_result_is_invalid() {
+        let response = json!({"jsonrpc": "2.0", "id": 1});
+        match parse_response::<u64>(response) {
+            Err(Error::InvalidResponse(_)) => {}
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+}
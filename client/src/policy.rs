@@ -0,0 +1,50 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+
+/// Decides how long a request may take and how many times it's sent again.
+/// Only transport failures and timeouts are retried; errors returned by the node are not.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    /// The time limit of a single attempt.
+    pub timeout: Duration,
+    /// The number of attempts after the first one fails.
+    pub retries: usize,
+    /// The delay between attempts.
+    pub retry_interval: Duration,
+}
+
+impl RetryPolicy {
+    /// Sends each request once.
+    pub fn no_retry(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            retries: 0,
+            retry_interval: Duration::default(),
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            retries: 2,
+            retry_interval: Duration::from_secs(1),
+        }
+    }
+}
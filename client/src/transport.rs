@@ -0,0 +1,144 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{Error, Result};
+use futures::channel::oneshot;
+use futures::{SinkExt, StreamExt};
+use hyper::client::HttpConnector;
+use hyper::header::CONTENT_TYPE;
+use hyper::{Body, Request, Uri};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>;
+
+pub enum Transport {
+    Http(HttpTransport),
+    Ws(WsTransport),
+}
+
+impl Transport {
+    /// Sends the serialized request and returns the response object whose id is `id`.
+    pub async fn send(&self, id: u64, request: String) -> Result<Value> {
+        match self {
+            Transport::Http(http) => http.send(request).await,
+            Transport::Ws(ws) => ws.send(id, request).await,
+        }
+    }
+}
+
+pub struct HttpTransport {
+    client: hyper::Client<HttpConnector>,
+    url: Uri,
+}
+
+impl HttpTransport {
+    pub fn new(url: &str) -> Result<Self> {
+        let url = url.parse().map_err(|err| Error::Transport(format!("Invalid URL {}: {}", url, err)))?;
+        Ok(Self {
+            client: hyper::Client::new(),
+            url,
+        })
+    }
+
+    async fn send(&self, request: String) -> Result<Value> {
+        let request = Request::post(self.url.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(request))
+            .map_err(|err| Error::Transport(err.to_string()))?;
+        let response = self.client.request(request).await?;
+        if !response.status().is_success() {
+            return Err(Error::Transport(format!("HTTP status {}", response.status())))
+        }
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
+
+/// Multiplexes requests over a single WebSocket connection.
+/// A background task owns the connection and routes the responses by their ids.
+pub struct WsTransport {
+    outgoing: mpsc::UnboundedSender<String>,
+    pending: Pending,
+}
+
+impl WsTransport {
+    pub async fn connect(url: &str) -> Result<Self> {
+        let (stream, _) = tokio_tungstenite::connect_async(url).await?;
+        let (mut sink, mut source) = stream.split();
+        let (outgoing, mut requests) = mpsc::unbounded_channel::<String>();
+        let pending: Pending = Default::default();
+
+        tokio::spawn(async move {
+            while let Some(request) = requests.recv().await {
+                if let Err(err) = sink.send(Message::Text(request)).await {
+                    warn!("Cannot send a request to the WebSocket: {}", err);
+                    break
+                }
+            }
+        });
+
+        let responders = Arc::clone(&pending);
+        tokio::spawn(async move {
+            while let Some(message) = source.next().await {
+                let text = match message {
+                    Ok(Message::Text(text)) => text,
+                    Ok(Message::Close(_)) => break,
+                    Ok(_) => continue,
+                    Err(err) => {
+                        warn!("Cannot receive a response from the WebSocket: {}", err);
+                        break
+                    }
+                };
+                let response: Value = match serde_json::from_str(&text) {
+                    Ok(response) => response,
+                    Err(err) => {
+                        warn!("Invalid response {}: {}", text, err);
+                        continue
+                    }
+                };
+                let responder = response.get("id").and_then(Value::as_u64).and_then(|id| {
+                    let mut responders = responders.lock().expect("Response routing never panics");
+                    responders.remove(&id)
+                });
+                if let Some(responder) = responder {
+                    // The caller may have given up waiting.
+                    let _ = responder.send(response);
+                }
+            }
+            // Dropping the responders wakes up the remaining callers with an error.
+            responders.lock().expect("Response routing never panics").clear();
+        });
+
+        Ok(Self {
+            outgoing,
+            pending,
+        })
+    }
+
+    async fn send(&self, id: u64, request: String) -> Result<Value> {
+        let (responder, response) = oneshot::channel();
+        self.pending.lock().expect("Response routing never panics").insert(id, responder);
+        if self.outgoing.send(request).is_err() {
+            self.pending.lock().expect("Response routing never panics").remove(&id);
+            return Err(Error::Transport("The WebSocket connection is closed".to_string()))
+        }
+        response.await.map_err(|_| Error::Transport("The WebSocket connection is closed".to_string()))
+    }
+}
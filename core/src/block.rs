@@ -22,16 +22,24 @@ use crate::transaction::{SignedTransaction, UnverifiedTransaction};
 use crate::BlockId;
 use ccrypto::BLAKE_NULL_RLP;
 use ckey::Address;
-use cstate::{FindActionHandler, StateDB, StateError, StateWithCache, TopLevelState};
+use cstate::{
+    FindActionHandler, Speculation, StateDB, StateError, StateResult, StateWithCache, TopAccess, TopLevelState,
+};
 use ctypes::errors::HistoryError;
 use ctypes::header::{Header, Seal};
+use ctypes::transaction::ShardTransaction;
 use ctypes::util::unexpected::Mismatch;
 use ctypes::{BlockNumber, CommonParams, TxHash};
 use cvm::ChainTimeInfo;
 use merkle_trie::skewed_merkle_root;
 use primitives::{Bytes, H256};
+use rayon::prelude::*;
 use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
 use std::collections::HashSet;
+use std::iter;
+
+/// Blocks with fewer transactions are executed in order without speculation.
+const MIN_SPECULATED_TRANSACTIONS: usize = 16;
 
 /// A block, encoded as it is on the block chain.
 #[derive(Debug, Clone, PartialEq)]
@@ -162,16 +170,29 @@ impl<'x> OpenBlock<'x> {
         }
 
         let hash = tx.hash();
-        let tracker = tx.tracker();
-        let error = match self.block.state.apply(
+        let result = self.block.state.apply_sponsored(
             &tx,
             &hash,
             &tx.signer_public(),
+            tx.sponsor_public().as_ref(),
             client,
             parent_block_number,
             parent_block_timestamp,
             self.block.header.timestamp(),
-        ) {
+        );
+        self.record_transaction(tx, h, result)
+    }
+
+    /// Adds the transaction and its invoice to the block, given the result of applying it.
+    fn record_transaction(
+        &mut self,
+        tx: SignedTransaction,
+        h: Option<TxHash>,
+        result: StateResult<()>,
+    ) -> Result<(), Error> {
+        let hash = tx.hash();
+        let tracker = tx.tracker();
+        let error = match result {
             Ok(()) => {
                 self.block.transactions_set.insert(h.unwrap_or(hash));
                 self.block.transactions.push(tx);
@@ -192,16 +213,102 @@ impl<'x> OpenBlock<'x> {
     }
 
     /// Push transactions onto the block.
-    pub fn push_transactions<C: ChainTimeInfo + FindActionHandler>(
+    ///
+    /// The payments, the regular keys and the shard stores are executed in parallel first, each against
+    /// a snapshot of the state, so the stores to different shards run on different threads. A transaction
+    /// keeps that result unless it accessed an address written by an earlier transaction in the block, in
+    /// which case it is executed again in order.
+    pub fn push_transactions<C: ChainTimeInfo + FindActionHandler + Sync>(
         &mut self,
         transactions: &[SignedTransaction],
         client: &C,
         parent_block_number: BlockNumber,
         parent_block_timestamp: u64,
     ) -> Result<(), Error> {
-        for tx in transactions {
-            self.push_transaction(tx.clone(), None, client, parent_block_number, parent_block_timestamp)?;
+        self.prefetch_shard_texts(transactions)?;
+        let speculations = self.speculate(transactions, client, parent_block_number, parent_block_timestamp);
+
+        let mut written = TopAccess::default();
+        for (tx, speculation) in transactions.iter().zip(speculations) {
+            match speculation {
+                Some(speculation)
+                    if !speculation.access().depends_on(&written)
+                        && !self.block.transactions_set.contains(&tx.hash()) =>
+                {
+                    let (result, access) = self.block.state.merge_speculation(speculation);
+                    written.extend(access);
+                    self.record_transaction(tx.clone(), None, result)?;
+                }
+                _ => {
+                    self.block.state.start_recording();
+                    let result =
+                        self.push_transaction(tx.clone(), None, client, parent_block_number, parent_block_timestamp);
+                    written.extend(self.block.state.finish_recording());
+                    result?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Executes the transactions in parallel on snapshots of the state, leaving the state untouched.
+    ///
+    /// The result is `None` for the transactions that have to be executed in order.
+    fn speculate<C: ChainTimeInfo + FindActionHandler + Sync>(
+        &self,
+        transactions: &[SignedTransaction],
+        client: &C,
+        parent_block_number: BlockNumber,
+        parent_block_timestamp: u64,
+    ) -> Vec<Option<Speculation>> {
+        let mut speculations: Vec<Option<Speculation>> = iter::repeat_with(|| None).take(transactions.len()).collect();
+        let threads = rayon::current_num_threads();
+        if threads < 2 || transactions.len() < MIN_SPECULATED_TRANSACTIONS {
+            return speculations
         }
+
+        // Each thread works on its own snapshot, which is reverted after every transaction.
+        let chunk_size = (transactions.len() + threads - 1) / threads;
+        let chunks = (transactions.len() + chunk_size - 1) / chunk_size;
+        let snapshots: Vec<_> = (0..chunks).map(|_| self.block.state.clone()).collect();
+        let timestamp = self.block.header.timestamp();
+        let results: Vec<_> = snapshots
+            .into_par_iter()
+            .zip(transactions.par_chunks(chunk_size).enumerate())
+            .flat_map(|(mut state, (chunk_index, chunk))| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .map(|(index, tx)| {
+                        // The sponsored transactions touch the sponsor too, so they are applied in order.
+                        if tx.sponsor_public().is_some() {
+                            return (chunk_index * chunk_size + index, None)
+                        }
+                        let speculation = state.speculate(
+                            tx,
+                            &tx.hash(),
+                            &tx.signer_public(),
+                            client,
+                            parent_block_number,
+                            parent_block_timestamp,
+                            timestamp,
+                        );
+                        (chunk_index * chunk_size + index, speculation)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        for (index, speculation) in results {
+            speculations[index] = speculation;
+        }
+        speculations
+    }
+
+    /// Reads the shard states that the transactions touch in parallel, before applying them.
+    pub fn prefetch_shard_texts(&mut self, transactions: &[SignedTransaction]) -> Result<(), Error> {
+        let shard_transactions: Vec<_> =
+            transactions.iter().filter_map(|tx| Option::<ShardTransaction>::from(tx.action.clone())).collect();
+        self.block.state.prefetch_shard_texts(&shard_transactions)?;
         Ok(())
     }
 
@@ -455,7 +562,7 @@ impl IsBlock for SealedBlock {
 }
 
 /// Enact the block given by block header, transactions and uncles
-pub fn enact<C: ChainTimeInfo + EngineInfo + FindActionHandler + TermInfo>(
+pub fn enact<C: ChainTimeInfo + EngineInfo + FindActionHandler + TermInfo + Sync>(
     header: &Header,
     transactions: &[SignedTransaction],
     engine: &dyn CodeChainEngine,
@@ -473,10 +580,39 @@ pub fn enact<C: ChainTimeInfo + EngineInfo + FindActionHandler + TermInfo>(
     b.close_and_lock(term_common_params.as_ref())
 }
 
+/// Executes the transactions of the block one by one on the state of its parent.
+/// Returns the result of each transaction with the accounts it wrote.
+pub fn execute_each<C: ChainTimeInfo + FindActionHandler>(
+    header: &Header,
+    transactions: &[SignedTransaction],
+    engine: &dyn CodeChainEngine,
+    client: &C,
+    db: StateDB,
+    parent: &Header,
+) -> Result<Vec<(Result<(), Error>, Vec<Address>)>, Error> {
+    let mut b = OpenBlock::try_new(engine, db, parent, Address::default(), vec![])?;
+
+    b.populate_from(header);
+    engine.on_open_block(b.inner_mut())?;
+
+    let mut results = Vec::with_capacity(transactions.len());
+    for tx in transactions {
+        b.block.state.start_recording();
+        let result = b.push_transaction(tx.clone(), None, client, parent.number(), parent.timestamp());
+        let written = b.block.state.finish_recording().written_accounts();
+        results.push((result, written));
+    }
+    Ok(results)
+}
+
 #[cfg(test)]
 mod tests {
+    use ckey::{Generator, KeyPair, Random};
+    use cstate::TopState;
+    use ctypes::transaction::{Action, Transaction};
     use ctypes::CommonParams;
 
+    use crate::client::TestBlockChainClient;
     use crate::scheme::Scheme;
     use crate::tests::helpers::get_temp_state_db;
 
@@ -492,4 +628,112 @@ mod tests {
         let b = b.close_and_lock(Some(&term_common_params)).unwrap();
         let _ = b.seal(&*scheme.engine, vec![]);
     }
+
+    #[test]
+    fn block_is_decoded_only_from_its_exact_encoding() {
+        let tx = Transaction {
+            seq: 0,
+            fee: 10,
+            network_id: "tc".into(),
+            action: Action::Pay {
+                receiver: Address::random(),
+                quantity: 1,
+            },
+        };
+        let tx = SignedTransaction::new_with_sign(tx, Random.generate().unwrap().private());
+        let header = Header::default();
+        // The decoded header has its hash cached.
+        header.hash();
+        let block = Block {
+            header,
+            transactions: vec![tx.into()],
+        };
+        let mut encoded = block.rlp_bytes(&Seal::With);
+        assert_eq!(block, rlp::decode(&encoded).unwrap());
+        for length in 0..encoded.len() {
+            assert!(rlp::decode::<Block>(&encoded[..length]).is_err(), "{} bytes", length);
+        }
+        encoded.push(0x80);
+        assert!(rlp::decode::<Block>(&encoded).is_err());
+    }
+
+    fn sign(signer: &KeyPair, seq: u64, action: Action) -> SignedTransaction {
+        let tx = Transaction {
+            seq,
+            fee: 10,
+            network_id: "tc".into(),
+            action,
+        };
+        SignedTransaction::new_with_sign(tx, signer.private())
+    }
+
+    fn pay(signer: &KeyPair, seq: u64, receiver: &KeyPair, quantity: u64) -> SignedTransaction {
+        sign(signer, seq, Action::Pay {
+            receiver: receiver.address(),
+            quantity,
+        })
+    }
+
+    fn store(signer: &KeyPair, seq: u64, content: &str) -> SignedTransaction {
+        sign(signer, seq, Action::ShardStore {
+            network_id: "tc".into(),
+            shard_id: 0,
+            content: content.to_string(),
+        })
+    }
+
+    /// Returns the state root, the invoices and the error of pushing the transactions to a block on the genesis.
+    fn execute(
+        funded: &[&KeyPair],
+        transactions: &[SignedTransaction],
+        speculative: bool,
+    ) -> (H256, Vec<Invoice>, Option<String>) {
+        let scheme = Scheme::new_test();
+        let genesis_header = scheme.genesis_header();
+        let db = scheme.ensure_genesis_state(get_temp_state_db()).unwrap();
+        let mut b = OpenBlock::try_new(&*scheme.engine, db, &genesis_header, Address::default(), vec![]).unwrap();
+        for account in funded {
+            b.block.state.add_balance(&account.address(), 10_000).unwrap();
+        }
+
+        let client = TestBlockChainClient::new();
+        let result = if speculative {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+            pool.install(|| b.push_transactions(transactions, &client, 0, 0))
+        } else {
+            transactions.iter().try_for_each(|tx| b.push_transaction(tx.clone(), None, &client, 0, 0))
+        };
+        let state_root = b.block.state.commit().unwrap();
+        (state_root, b.block.invoices.clone(), result.err().map(|err| err.to_string()))
+    }
+
+    #[test]
+    fn speculative_execution_matches_serial_execution() {
+        let keys: Vec<_> = (0..8).map(|_| Random.generate().unwrap()).collect();
+        let (a, b, c, d, e) = (&keys[0], &keys[1], &keys[2], &keys[3], &keys[4]);
+        let independents = &keys[5..];
+
+        let mut transactions = Vec::new();
+        // `a` sends transactions in a row, and `b`, `c` and `d` spend what they received earlier in the block.
+        transactions.extend((0..5).map(|seq| pay(a, seq, b, 100)));
+        transactions.push(pay(b, 0, c, 50));
+        transactions.extend(independents.iter().map(|signer| pay(signer, 0, e, 10)));
+        transactions.push(pay(c, 0, d, 20));
+        transactions.extend((5..10).map(|seq| pay(a, seq, b, 100)));
+        transactions.push(store(d, 0, "text of d"));
+        transactions.push(store(e, 0, "text of e"));
+        transactions.push(store(e, 1, "another text of e"));
+        transactions.push(pay(b, 1, a, 10));
+        // It fails because `a` doesn't have that much.
+        transactions.push(pay(a, 10, c, 100_000));
+        assert!(transactions.len() >= MIN_SPECULATED_TRANSACTIONS);
+
+        let funded: Vec<_> = iter::once(a).chain(independents).collect();
+        let speculative = execute(&funded, &transactions, true);
+        let serial = execute(&funded, &transactions, false);
+        assert_eq!(serial, speculative);
+        assert_eq!(transactions.len(), speculative.1.len());
+        assert!(speculative.2.is_some(), "The last transaction fails");
+        assert!(speculative.1[..transactions.len() - 1].iter().all(|invoice| invoice.error.is_none()));
+    }
 }
@@ -19,7 +19,7 @@ use super::body_db::{BodyDB, BodyProvider};
 use super::extras::{BlockDetails, TransactionAddress};
 use super::headerchain::{HeaderChain, HeaderProvider};
 use super::invoice_db::{InvoiceDB, InvoiceProvider};
-use super::route::{tree_route, ImportRoute};
+use super::route::{tree_route, ImportRoute, TreeRoute};
 use crate::blockchain_info::BlockChainInfo;
 use crate::consensus::CodeChainEngine;
 use crate::db;
@@ -141,6 +141,34 @@ impl BlockChain {
         *self.pending_best_proposal_block_hash.write() = Some(*hash);
     }
 
+    /// Switches the canonical chain to the branch ending at `hash`, which must be an imported block.
+    /// Returns the route from the previous best block.
+    pub fn update_best_branch(&self, batch: &mut DBTransaction, hash: &BlockHash) -> Option<TreeRoute> {
+        ctrace!(BLOCKCHAIN, "Updating the best branch to {}", hash);
+
+        assert!(self.pending_best_block_hash.read().is_none());
+        assert!(self.pending_best_proposal_block_hash.read().is_none());
+
+        let route = tree_route(self, self.best_block_hash(), *hash)?;
+        let bodies = |hashes: &[BlockHash]| -> Option<Vec<_>> {
+            hashes.iter().map(|hash| Some((*hash, self.block_body(hash)?))).collect()
+        };
+        let retracted = bodies(&route.retracted)?;
+        let enacted = bodies(&route.enacted)?;
+        let enacted_headers =
+            route.enacted.iter().map(|hash| self.block_header_data(hash)).collect::<Option<Vec<_>>>()?;
+
+        self.headerchain.update_best_branch(batch, hash, &enacted_headers);
+        self.body_db.update_best_branch(batch, &retracted, &enacted);
+
+        batch.put(db::COL_EXTRA, BEST_BLOCK_KEY, hash);
+        *self.pending_best_block_hash.write() = Some(*hash);
+        batch.put(db::COL_EXTRA, BEST_PROPOSAL_BLOCK_KEY, hash);
+        *self.pending_best_proposal_block_hash.write() = Some(*hash);
+
+        Some(route)
+    }
+
     /// Inserts the block into backing cache database.
     /// Expects the block to be valid and already verified.
     /// If the block is already known, does nothing.
@@ -0,0 +1,220 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::db;
+use kvdb::{DBTransaction, KeyValueDB};
+use parking_lot::{Mutex, RwLock};
+use primitives::Bytes;
+use std::io::{self, Read, Write};
+use zstd::dict::{DecoderDictionary, EncoderDictionary};
+use zstd::stream::{Decoder, Encoder};
+
+/// The body is compressed with zstd without the dictionary.
+const FORMAT_PLAIN: u8 = 0;
+/// The body is compressed with zstd using the dictionary.
+const FORMAT_DICTIONARY: u8 = 1;
+
+const LEVEL: i32 = 3;
+/// The smaller bodies, like the ones without transactions, are stored as they are, because the zstd frame
+/// would be larger than the saving.
+const MIN_COMPRESSED_SIZE: usize = 64;
+
+const DICTIONARY_KEY: &[u8] = b"body-compression-dictionary";
+const DICTIONARY_SIZE: usize = 64 * 1024;
+/// The number of the bodies the dictionary is trained on.
+const DICTIONARY_SAMPLES: usize = 1024;
+
+/// Compresses the block bodies with zstd before they are written to the database.
+///
+/// The dictionary is trained on the first bodies written and put into the batch. It is used for the bodies
+/// written after the batch is committed, so no body using it is written before it. The bodies written before
+/// this compression was introduced don't start with a format byte but with an RLP list prefix, and they are
+/// read as they are.
+pub struct BodyCompressor {
+    dictionary: RwLock<Option<Dictionary>>,
+    /// The dictionary trained and put into a batch that is not committed yet.
+    pending_dictionary: Mutex<Option<Bytes>>,
+    samples: Mutex<Vec<Bytes>>,
+}
+
+struct Dictionary {
+    encoder: EncoderDictionary<'static>,
+    decoder: DecoderDictionary<'static>,
+}
+
+impl Dictionary {
+    fn new(dictionary: &[u8]) -> Self {
+        Self {
+            encoder: EncoderDictionary::new(dictionary, LEVEL),
+            decoder: DecoderDictionary::new(dictionary),
+        }
+    }
+}
+
+impl BodyCompressor {
+    pub fn new(db: &dyn KeyValueDB) -> Self {
+        let dictionary =
+            db.get(db::COL_EXTRA, DICTIONARY_KEY).expect("Low level database error. Some issue with disk?");
+        Self {
+            dictionary: RwLock::new(dictionary.map(|dictionary| Dictionary::new(&dictionary))),
+            pending_dictionary: Default::default(),
+            samples: Default::default(),
+        }
+    }
+
+    /// Compresses the body. The dictionary is put into the `batch` when it is trained.
+    pub fn compress(&self, batch: &mut DBTransaction, body: &[u8]) -> Bytes {
+        if body.len() < MIN_COMPRESSED_SIZE {
+            return body.to_vec()
+        }
+        if self.dictionary.read().is_none() {
+            self.sample(batch, body);
+        }
+
+        let dictionary = self.dictionary.read();
+        let (format, compressed) = match &*dictionary {
+            Some(dictionary) => (FORMAT_DICTIONARY, encode(body, Some(&dictionary.encoder))),
+            None => (FORMAT_PLAIN, encode(body, None)),
+        };
+        let mut stored = Vec::with_capacity(compressed.len() + 1);
+        stored.push(format);
+        stored.extend_from_slice(&compressed);
+        stored
+    }
+
+    /// Returns an error if the body is corrupted or compressed with a dictionary that isn't loaded.
+    pub fn decompress(&self, stored: &[u8]) -> io::Result<Bytes> {
+        match stored.first() {
+            Some(&FORMAT_PLAIN) => decode(&stored[1..], None),
+            Some(&FORMAT_DICTIONARY) => match &*self.dictionary.read() {
+                Some(dictionary) => decode(&stored[1..], Some(&dictionary.decoder)),
+                None => Err(io::Error::new(io::ErrorKind::NotFound, "The dictionary of the body is not stored")),
+            },
+            _ => Ok(stored.to_vec()),
+        }
+    }
+
+    /// Starts using the dictionary trained since the last commit. It must be called after the batch is written.
+    pub fn commit(&self) {
+        if let Some(dictionary) = self.pending_dictionary.lock().take() {
+            *self.dictionary.write() = Some(Dictionary::new(&dictionary));
+        }
+    }
+
+    fn sample(&self, batch: &mut DBTransaction, body: &[u8]) {
+        let mut pending_dictionary = self.pending_dictionary.lock();
+        if pending_dictionary.is_some() {
+            return
+        }
+        let mut samples = self.samples.lock();
+        samples.push(body.to_vec());
+        if samples.len() < DICTIONARY_SAMPLES {
+            return
+        }
+
+        let samples = std::mem::replace(&mut *samples, Vec::new());
+        match zstd::dict::from_samples(&samples, DICTIONARY_SIZE) {
+            Ok(dictionary) => {
+                cinfo!(BLOCKCHAIN, "The dictionary compressing the block bodies is trained");
+                batch.put(db::COL_EXTRA, DICTIONARY_KEY, &dictionary);
+                *pending_dictionary = Some(dictionary);
+            }
+            // The samples are collected again.
+            Err(err) => cwarn!(BLOCKCHAIN, "Cannot train the dictionary compressing the block bodies: {}", err),
+        }
+    }
+}
+
+fn encode(body: &[u8], dictionary: Option<&EncoderDictionary<'_>>) -> Bytes {
+    let result: io::Result<Bytes> = (|| {
+        let mut encoder = match dictionary {
+            Some(dictionary) => Encoder::with_prepared_dictionary(Vec::new(), dictionary)?,
+            None => Encoder::new(Vec::new(), LEVEL)?,
+        };
+        encoder.write_all(body)?;
+        encoder.finish()
+    })();
+    result.expect("Compressing to memory doesn't fail")
+}
+
+fn decode(compressed: &[u8], dictionary: Option<&DecoderDictionary<'_>>) -> io::Result<Bytes> {
+    let mut decoded = Vec::new();
+    match dictionary {
+        Some(dictionary) => Decoder::with_prepared_dictionary(compressed, dictionary)?.read_to_end(&mut decoded)?,
+        None => Decoder::new(compressed)?.read_to_end(&mut decoded)?,
+    };
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(index: usize) -> Bytes {
+        let mut body = vec![0xf9, 0x01, 0x00];
+        for tx in 0..4 {
+            body.extend_from_slice(b"pay transaction to the address ");
+            body.extend_from_slice(format!("{:08}-{:04}", index, tx).as_bytes());
+        }
+        body
+    }
+
+    #[test]
+    fn bodies_are_restored_as_they_were() {
+        let db = kvdb_memorydb::create(db::NUM_COLUMNS.unwrap_or(0));
+        let compressor = BodyCompressor::new(&db);
+        let mut batch = DBTransaction::new();
+
+        let small = vec![0xc0];
+        assert_eq!(small, compressor.compress(&mut batch, &small));
+        assert_eq!(small, compressor.decompress(&small).unwrap());
+
+        let large = body(0);
+        let compressed = compressor.compress(&mut batch, &large);
+        assert_eq!(FORMAT_PLAIN, compressed[0]);
+        assert!(compressed.len() < large.len());
+        assert_eq!(large, compressor.decompress(&compressed).unwrap());
+    }
+
+    #[test]
+    fn dictionary_is_trained_and_stored() {
+        let db = kvdb_memorydb::create(db::NUM_COLUMNS.unwrap_or(0));
+        let compressor = BodyCompressor::new(&db);
+        let mut batch = DBTransaction::new();
+        for index in 0..DICTIONARY_SAMPLES {
+            compressor.compress(&mut batch, &body(index));
+        }
+        // The dictionary isn't used until the batch storing it is committed.
+        assert_eq!(FORMAT_PLAIN, compressor.compress(&mut batch, &body(DICTIONARY_SAMPLES))[0]);
+        db.write(batch).unwrap();
+        compressor.commit();
+
+        let mut batch = DBTransaction::new();
+        let compressed = compressor.compress(&mut batch, &body(DICTIONARY_SAMPLES));
+        assert_eq!(FORMAT_DICTIONARY, compressed[0]);
+
+        let reopened = BodyCompressor::new(&db);
+        assert_eq!(body(DICTIONARY_SAMPLES), reopened.decompress(&compressed).unwrap());
+    }
+
+    #[test]
+    fn body_without_its_dictionary_is_an_error() {
+        let db = kvdb_memorydb::create(db::NUM_COLUMNS.unwrap_or(0));
+        let compressor = BodyCompressor::new(&db);
+        assert!(compressor.decompress(&[FORMAT_DICTIONARY, 0x28, 0xb5, 0x2f, 0xfd]).is_err());
+        assert!(compressor.decompress(&[FORMAT_PLAIN, 0x00, 0x01]).is_err());
+    }
+}
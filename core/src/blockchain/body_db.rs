@@ -15,6 +15,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use super::block_info::BestBlockChanged;
+use super::body_compression::BodyCompressor;
 use super::extras::{TransactionAddress, TransactionAddresses};
 use crate::db::{self, CacheUpdatePolicy, Readable, Writable};
 use crate::views::BlockView;
@@ -37,10 +38,12 @@ pub struct BodyDB {
     body_cache: Mutex<LruCache<BlockHash, Bytes>>,
     address_by_hash_cache: RwLock<HashMap<TxHash, TransactionAddress>>,
     pending_addresses_by_hash: RwLock<HashMap<TxHash, TransactionAddress>>,
+    pending_retracted_hashes: RwLock<HashSet<TxHash>>,
 
     addresses_by_tracker_cache: Mutex<HashMap<Tracker, TransactionAddresses>>,
     pending_addresses_by_tracker: Mutex<HashMap<Tracker, TransactionAddresses>>,
 
+    compressor: BodyCompressor,
     db: Arc<dyn KeyValueDB>,
 }
 
@@ -53,10 +56,12 @@ impl BodyDB {
             body_cache: Mutex::new(LruCache::new(BODY_CACHE_SIZE)),
             address_by_hash_cache: RwLock::new(HashMap::new()),
             pending_addresses_by_hash: RwLock::new(HashMap::new()),
+            pending_retracted_hashes: RwLock::new(HashSet::new()),
 
             addresses_by_tracker_cache: Default::default(),
             pending_addresses_by_tracker: Default::default(),
 
+            compressor: BodyCompressor::new(db.as_ref()),
             db,
         };
 
@@ -82,6 +87,7 @@ impl BodyDB {
         }
 
         let compressed_body = compress(&Self::block_to_body(block), blocks_swapper());
+        let compressed_body = self.compressor.compress(batch, &compressed_body);
 
         // store block in db
         batch.put(db::COL_BODIES, &hash, &compressed_body);
@@ -104,14 +110,80 @@ impl BodyDB {
         );
     }
 
+    /// Moves the transaction addresses from the retracted blocks to the enacted blocks.
+    /// Both are given in the order of the route.
+    pub fn update_best_branch(
+        &self,
+        batch: &mut DBTransaction,
+        retracted: &[(BlockHash, encoded::Body)],
+        enacted: &[(BlockHash, encoded::Body)],
+    ) {
+        let mut new_addresses_by_hash = HashMap::new();
+        let mut added_addresses: HashMap<Tracker, TransactionAddresses> = Default::default();
+        let mut removed_addresses: HashMap<Tracker, TransactionAddresses> = Default::default();
+        for (hash, body) in enacted {
+            new_addresses_by_hash.extend(tx_hash_and_address_entries(*hash, body.transaction_hashes()));
+            for (tracker, address) in tracker_and_addresses_entries(*hash, body.transactions()) {
+                *added_addresses.entry(tracker).or_insert_with(Default::default) += address;
+            }
+        }
+        let mut retracted_hashes = HashSet::new();
+        for (hash, body) in retracted {
+            retracted_hashes.extend(
+                body.transaction_hashes().into_iter().filter(|tx_hash| !new_addresses_by_hash.contains_key(tx_hash)),
+            );
+            for (tracker, address) in tracker_and_addresses_entries(*hash, body.transactions()) {
+                *removed_addresses.entry(tracker).or_insert_with(Default::default) += address;
+            }
+        }
+
+        let trackers: HashSet<Tracker> = added_addresses.keys().chain(removed_addresses.keys()).cloned().collect();
+        let mut new_addresses_by_tracker = HashMap::new();
+        for tracker in trackers {
+            let mut addresses: TransactionAddresses = self.db.read(db::COL_EXTRA, &tracker).unwrap_or_default();
+            if let Some(removed) = removed_addresses.remove(&tracker) {
+                addresses -= removed;
+            }
+            if let Some(added) = added_addresses.remove(&tracker) {
+                addresses += added;
+            }
+            new_addresses_by_tracker.insert(tracker, addresses);
+        }
+
+        let mut pending_addresses_by_hash = self.pending_addresses_by_hash.write();
+        let mut pending_retracted_hashes = self.pending_retracted_hashes.write();
+        let mut pending_addresses_by_tracker = self.pending_addresses_by_tracker.lock();
+        for tx_hash in &retracted_hashes {
+            Writable::delete::<TransactionAddress, _>(batch, db::COL_EXTRA, tx_hash);
+            pending_addresses_by_hash.remove(tx_hash);
+        }
+        pending_retracted_hashes.extend(retracted_hashes);
+        batch.extend_with_cache(
+            db::COL_EXTRA,
+            &mut *pending_addresses_by_hash,
+            new_addresses_by_hash,
+            CacheUpdatePolicy::Overwrite,
+        );
+        batch.extend_with_cache(
+            db::COL_EXTRA,
+            &mut *pending_addresses_by_tracker,
+            new_addresses_by_tracker,
+            CacheUpdatePolicy::Overwrite,
+        );
+    }
+
     /// Apply pending insertion updates
     pub fn commit(&self) {
         let mut address_by_hash_cache = self.address_by_hash_cache.write();
         let mut pending_addresses_by_hash = self.pending_addresses_by_hash.write();
+        let mut pending_retracted_hashes = self.pending_retracted_hashes.write();
 
         let mut addresses_by_tracker_cache = self.addresses_by_tracker_cache.lock();
         let mut pending_addresses_by_tracker = self.pending_addresses_by_tracker.lock();
 
+        for tx_hash in pending_retracted_hashes.drain() {
+            address_by_hash_cache.remove(&tx_hash);
+        }
         let new_txs_by_hash = mem::replace(&mut *pending_addresses_by_hash, HashMap::new());
 
         address_by_hash_cache.extend(new_txs_by_hash.into_iter());
@@ -119,6 +191,8 @@ impl BodyDB {
         let new_txs_by_tracker = mem::replace(&mut *pending_addresses_by_tracker, HashMap::new());
 
         addresses_by_tracker_cache.extend(new_txs_by_tracker.into_iter());
+
+        self.compressor.commit();
     }
 
     /// This function returns modified transaction addresses.
@@ -236,6 +310,13 @@ impl BodyProvider for BodyDB {
         let compressed_body =
             self.db.get(db::COL_BODIES, hash).expect("Low level database error. Some issue with disk?")?;
 
+        let compressed_body = match self.compressor.decompress(&compressed_body) {
+            Ok(compressed_body) => compressed_body,
+            Err(err) => {
+                cerror!(BLOCKCHAIN, "The body of the block {} cannot be read: {}", hash, err);
+                return None
+            }
+        };
         let raw_body = decompress(&compressed_body, blocks_swapper());
         let mut lock = self.body_cache.lock();
         lock.insert(*hash, raw_body.clone());
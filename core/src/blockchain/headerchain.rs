@@ -157,6 +157,25 @@ impl HeaderChain {
         *self.pending_best_proposal_block_hash.write() = Some(*hash);
     }
 
+    /// Switches the canonical chain to the branch ending at `hash`.
+    /// `enacted` is the list of the headers which become canonical, ordered by their numbers.
+    pub fn update_best_branch(&self, batch: &mut DBTransaction, hash: &BlockHash, enacted: &[encoded::Header]) {
+        ctrace!(HEADERCHAIN, "Updating the best branch to {}", hash);
+        assert!(self.is_known_header(hash));
+        assert!(self.pending_best_header_hash.read().is_none());
+        assert!(self.pending_best_proposal_block_hash.read().is_none());
+
+        // The hashes of the numbers above the new best one are hidden by `block_hash`.
+        let new_hashes = enacted.iter().map(|header| (header.number(), header.hash())).collect();
+        let mut pending_hashes = self.pending_hashes.write();
+        batch.extend_with_cache(db::COL_EXTRA, &mut *pending_hashes, new_hashes, CacheUpdatePolicy::Overwrite);
+
+        batch.put(db::COL_EXTRA, BEST_HEADER_KEY, hash);
+        *self.pending_best_header_hash.write() = Some(*hash);
+        batch.put(db::COL_EXTRA, BEST_PROPOSAL_HEADER_KEY, hash);
+        *self.pending_best_proposal_block_hash.write() = Some(*hash);
+    }
+
     /// Inserts the header into backing cache database.
     /// Expects the header to be valid and already verified.
     /// If the header is already known, does nothing.
@@ -17,6 +17,7 @@
 mod block_info;
 #[cfg_attr(feature = "cargo-clippy", allow(clippy::module_inception))]
 mod blockchain;
+mod body_compression;
 mod body_db;
 mod extras;
 mod headerchain;
@@ -28,4 +29,4 @@ pub use self::body_db::BodyProvider;
 pub use self::extras::{BlockDetails, TransactionAddress, TransactionAddresses};
 pub use self::headerchain::HeaderProvider;
 pub use self::invoice_db::InvoiceProvider;
-pub use self::route::ImportRoute;
+pub use self::route::{ImportRoute, TreeRoute};
@@ -14,41 +14,62 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use super::disk_space::DiskSpaceLevel;
 use super::importer::Importer;
+use super::prewarm;
 use super::{
-    AccountData, BlockChainClient, BlockChainInfo, BlockChainTrait, BlockProducer, ChainNotify, ClientConfig,
-    DatabaseClient, EngineClient, EngineInfo, ExecuteClient, ImportBlock, ImportResult, MiningBlockChainClient, Shard,
-    StateInfo, StateOrBlock,
+    AccountChange, AccountData, BlockChainClient, BlockChainInfo, BlockChainTrait, BlockExecutionSummary,
+    BlockProducer, BlocksImported, ChainNotify, ClientConfig, DatabaseClient, DivergentAccount, EngineClient,
+    EngineInfo, EventBus, ExecuteClient, Finalized, ForkClient, FraudProofClient, ImportBlock, ImportResult,
+    LightClientSupport, MaintenanceClient, MiningBlockChainClient, Reorganized, ReplayClient, ReplayedBlock,
+    SchemeExportClient, Shard, StateInfo, StateModification, StateOrBlock, StateSurgeryClient, TransactionExecution,
+    TransactionSummary, TransactionsReceived,
 };
-use crate::block::{Block, ClosedBlock, IsBlock, OpenBlock, SealedBlock};
+use crate::block::{enact, execute_each, Block, ClosedBlock, IsBlock, OpenBlock, SealedBlock};
 use crate::blockchain::{BlockChain, BlockProvider, BodyProvider, HeaderProvider, InvoiceProvider, TransactionAddress};
 use crate::client::{ConsensusClient, SnapshotClient, TermInfo};
-use crate::consensus::{CodeChainEngine, EngineError};
+use crate::codechain_machine::CodeChainMachine;
+use crate::consensus::{ClockDrift, CodeChainEngine, Commit, CommitValidator, Duties, EngineError, EngineType};
 use crate::encoded;
 use crate::error::{BlockImportError, Error, ImportError, SchemeError};
-use crate::miner::{Miner, MinerService};
+use crate::miner::{MemPoolContent, Miner, MinerService, TxSource};
 use crate::scheme::Scheme;
 use crate::service::ClientIoMessage;
 use crate::transaction::{LocalizedTransaction, PendingSignedTransactions, SignedTransaction, UnverifiedTransaction};
 use crate::types::{BlockId, BlockStatus, TransactionId, VerificationQueueInfo as BlockQueueInfo};
+use crate::verification::FraudProof;
 use crate::MemPoolMinFees;
+use ccrypto::BLAKE_NULL_RLP;
 use cdb::{new_journaldb, Algorithm, AsHashDB, DatabaseError};
 use cio::IoChannel;
-use ckey::{Address, NetworkId, PlatformAddress, Public};
+use cjson::bytes::Bytes as JsonBytes;
+use cjson::hash::H256 as JsonH256;
+use cjson::uint::Uint;
+use ckey::{public_to_address, Address, NetworkId, PlatformAddress, Public, Signature};
+use cmemory::{share, MemoryConsumer};
 use cnetwork::NodeId;
-use cstate::{ActionHandler, FindActionHandler, StateDB, StateResult, TopLevelState, TopStateView};
+use cstate::{
+    check_state, AccountProof, ActionDataProof, ActionHandler, FindActionHandler, ShardProof, StateCheck, StateDB,
+    StateResult, TopLevelState, TopState, TopStateView, Witness,
+};
 use ctimer::{TimeoutHandler, TimerApi, TimerScheduleError, TimerToken};
-use ctypes::header::Header;
-use ctypes::transaction::{AssetTransferInput, PartialHashing, ShardTransaction};
+use ctypes::header::{Header, Seal};
+use ctypes::transaction::{Action, AssetTransferInput, PartialHashing, ShardTransaction, Transaction};
 use ctypes::{BlockHash, BlockNumber, CommonParams, ShardId, Tracker, TxHash};
 use cvm::{decode, execute, ChainTimeInfo, ScriptResult, VMConfig};
 use kvdb::{DBTransaction, KeyValueDB};
+use lru_cache::LruCache;
+use merkle_trie::snapshot::{ChunkCompressor, Snapshot};
 use parking_lot::{Mutex, RwLock, RwLockReadGuard};
 use primitives::{Bytes, H256, U256};
 use rlp::Rlp;
+use serde_json::json;
+use std::cmp;
+use std::collections::{BTreeSet, HashSet};
 use std::ops::Range;
-use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering};
 use std::sync::{Arc, Weak};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 const MAX_MEM_POOL_SIZE: usize = 4096;
 
@@ -67,6 +88,9 @@ pub struct Client {
     /// List of actors to be notified on certain chain events
     notify: RwLock<Vec<Weak<dyn ChainNotify>>>,
 
+    /// The events of the chain are published to it for the consumers that don't have to handle them in place.
+    events: EventBus,
+
     /// Count of pending transactions in the queue
     queue_transactions: AtomicUsize,
 
@@ -76,6 +100,25 @@ pub struct Client {
 
     /// Timer for reseal_min_period on miner client
     reseal_timer: TimerApi,
+
+    execution_summaries: Mutex<LruCache<BlockHash, Arc<BlockExecutionSummary>>>,
+
+    /// Set by the `DiskSpaceMonitor`.
+    disk_space: RwLock<DiskSpaceLevel>,
+
+    /// The node doesn't author blocks nor accept transactions while it's true.
+    maintenance: AtomicBool,
+
+    pending_state: Mutex<Option<PendingState>>,
+}
+
+const EXECUTION_SUMMARY_CACHE_SIZE: usize = 128;
+
+/// The state built on the best block from the ready transactions. It's reused until either of them changes.
+struct PendingState {
+    parent: BlockHash,
+    mem_pool_generation: u64,
+    state: TopLevelState,
 }
 
 impl Client {
@@ -87,8 +130,9 @@ impl Client {
         message_channel: IoChannel<ClientIoMessage>,
         reseal_timer: TimerApi,
     ) -> Result<Arc<Client>, Error> {
+        scheme.check_genesis_hash(&*db)?;
         let journal_db = new_journaldb(Arc::clone(&db), Algorithm::Archive, crate::db::COL_STATE);
-        let mut state_db = StateDB::new(journal_db);
+        let mut state_db = StateDB::new_with_node_cache(journal_db, config.trie_node_cache_size);
         if !scheme.check_genesis_root(state_db.as_hashdb()) {
             return Err(SchemeError::InvalidState.into())
         }
@@ -116,10 +160,15 @@ impl Client {
             db,
             state_db: RwLock::new(state_db),
             notify: RwLock::new(Vec::new()),
+            events: EventBus::default(),
             queue_transactions: AtomicUsize::new(0),
             genesis_accounts,
             importer,
             reseal_timer,
+            execution_summaries: Mutex::new(LruCache::new(EXECUTION_SUMMARY_CACHE_SIZE)),
+            disk_space: RwLock::new(DiskSpaceLevel::Sufficient),
+            maintenance: AtomicBool::new(false),
+            pending_state: Mutex::new(None),
         });
 
         // ensure buffered changes are flushed.
@@ -137,10 +186,18 @@ impl Client {
         self.notify.write().push(target);
     }
 
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
     pub fn transactions_received(&self, hashes: &[TxHash], peer_id: NodeId) {
         self.notify(|notify| {
             notify.transactions_received(hashes.to_vec(), peer_id);
         });
+        self.events.publish(TransactionsReceived {
+            hashes: hashes.to_vec(),
+            peer: peer_id,
+        });
     }
 
     pub fn new_blocks(
@@ -151,6 +208,12 @@ impl Client {
         sealed: &[BlockHash],
     ) {
         self.notify(|notify| notify.new_blocks(imported.to_vec(), invalid.to_vec(), enacted.to_vec(), sealed.to_vec()));
+        self.events.publish(BlocksImported {
+            imported: imported.to_vec(),
+            invalid: invalid.to_vec(),
+            enacted: enacted.to_vec(),
+            sealed: sealed.to_vec(),
+        });
     }
 
     pub fn new_headers(
@@ -184,19 +247,81 @@ impl Client {
 
     /// This is triggered by a message coming from a header queue when the header is ready for insertion
     pub fn import_verified_headers(&self) -> usize {
+        if self.is_read_only() {
+            return 0
+        }
         self.importer.import_verified_headers(self)
     }
 
     /// This is triggered by a message coming from a block queue when the block is ready for insertion
     pub fn import_verified_blocks(&self) -> usize {
+        if self.is_read_only() {
+            return 0
+        }
         self.importer.import_verified_blocks(self)
     }
 
     /// This is triggered by a message coming from a engine when a new block should be created
     pub fn update_sealing(&self, parent_block: BlockId, allow_empty_block: bool) {
+        if self.is_read_only() {
+            cdebug!(CLIENT, "Skip sealing a block: the disk space is critical");
+            return
+        }
+        if self.is_in_maintenance() {
+            cdebug!(CLIENT, "Skip sealing a block: the node is in maintenance");
+            return
+        }
         self.importer.miner.update_sealing(self, parent_block, allow_empty_block);
     }
 
+    pub fn disk_space_level(&self) -> DiskSpaceLevel {
+        *self.disk_space.read()
+    }
+
+    /// Restricts the mem pool and the import by the level of the free space.
+    /// The blocks verified while the node was read-only are imported once it's lifted.
+    pub fn set_disk_space_level(&self, level: DiskSpaceLevel, free: u64) {
+        let previous = {
+            let mut disk_space = self.disk_space.write();
+            let previous = *disk_space;
+            *disk_space = level;
+            previous
+        };
+        if previous == level {
+            return
+        }
+        match level {
+            DiskSpaceLevel::Sufficient => {
+                cinfo!(CLIENT, "{} bytes are free on the disk: the restrictions are lifted", free)
+            }
+            DiskSpaceLevel::Low => {
+                cwarn!(CLIENT, "Only {} bytes are free on the disk: new transactions are refused", free)
+            }
+            DiskSpaceLevel::Critical => {
+                cerror!(CLIENT, "Only {} bytes are free on the disk: the node stops importing blocks", free)
+            }
+        }
+        if previous == DiskSpaceLevel::Critical {
+            let io_channel = self.io_channel.lock();
+            for message in [ClientIoMessage::HeaderVerified, ClientIoMessage::BlockVerified].iter() {
+                if let Err(err) = io_channel.send(message.clone()) {
+                    cwarn!(CLIENT, "Cannot resume the import: {}", err);
+                }
+            }
+        }
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.disk_space_level() == DiskSpaceLevel::Critical
+    }
+
+    fn check_disk_space_for_blocks(&self) -> Result<(), BlockImportError> {
+        if self.is_read_only() {
+            return Err(BlockImportError::Import(ImportError::LowDiskSpace))
+        }
+        Ok(())
+    }
+
     fn block_hash(chain: &BlockChain, id: &BlockId) -> Option<BlockHash> {
         match id {
             BlockId::Hash(hash) => Some(*hash),
@@ -231,7 +356,7 @@ impl Client {
             transactions.iter().filter_map(|bytes| Rlp::new(bytes).as_val().ok()).collect();
         let hashes: Vec<_> = transactions.iter().map(UnverifiedTransaction::hash).collect();
         self.transactions_received(&hashes, peer_id);
-        let results = self.importer.miner.import_external_transactions(self, transactions);
+        let results = self.importer.miner.import_external_transactions(self, transactions, TxSource::Peer(peer_id));
         results.len()
     }
 
@@ -239,7 +364,7 @@ impl Client {
     /// See EngineClient::update_best_as_committed() for details.
     pub fn update_best_as_committed(&self, block_hash: BlockHash) {
         ctrace!(CLIENT, "Update the best block to the hash({}), as requested", block_hash);
-        let route = {
+        let (route, cache_restored) = {
             let _import_lock = self.importer.import_lock.lock();
 
             let chain = self.block_chain();
@@ -249,16 +374,22 @@ impl Client {
             self.db().write(batch).expect("DB flush failed.");
             chain.commit();
 
-            // Clear the state DB cache
+            // The cache of the new best state is restored if it's recent, or the cache is cleared.
             let mut state_db = self.state_db().write();
-            state_db.clear_cache();
+            let cache_restored = state_db.revert_to(&chain.best_block_header().state_root());
 
-            route
+            (route, cache_restored)
         };
 
         if route.is_none() {
             return
         }
+        if !cache_restored {
+            self.prewarm_state();
+        }
+        self.events.publish(Finalized {
+            block_hash,
+        });
 
         let enacted = self.importer.extract_enacted(vec![route]);
         self.importer.miner.chain_new_blocks(self, &[], &[], &enacted);
@@ -292,6 +423,45 @@ impl Client {
         &self.state_db
     }
 
+    /// Saves the addresses of the accounts in the state cache to warm it up by them after a restart.
+    pub fn save_hot_accounts(&self) {
+        let addresses = self.state_db.read().hot_accounts();
+        if addresses.is_empty() {
+            return
+        }
+        prewarm::save_hot_accounts(&*self.db, &addresses);
+    }
+
+    /// Reads the saved hot accounts from the best state into the state cache.
+    pub fn prewarm_state(&self) {
+        let addresses = prewarm::load_hot_accounts(&*self.db);
+        if addresses.is_empty() {
+            return
+        }
+        let _import_lock = self.importer.import_lock.lock();
+        let root = self.block_chain().best_block_header().state_root();
+        let state = match TopLevelState::from_existing(self.state_db.read().clone(&root), root) {
+            Ok(state) => state,
+            Err(err) => {
+                cwarn!(CLIENT, "Cannot open the state {} to prewarm the cache: {:?}", root, err);
+                return
+            }
+        };
+        let mut loaded = 0;
+        for address in &addresses {
+            match state.account(address) {
+                Ok(Some(_)) => loaded += 1,
+                Ok(None) => {}
+                Err(err) => {
+                    cwarn!(CLIENT, "Cannot read the account {} to prewarm the cache: {:?}", address, err);
+                    return
+                }
+            }
+        }
+        self.state_db.write().override_state(&state);
+        cinfo!(CLIENT, "Prewarmed the state cache with {} of {} hot accounts", loaded, addresses.len());
+    }
+
     pub fn block_chain(&self) -> RwLockReadGuard<'_, BlockChain> {
         self.chain.read()
     }
@@ -319,6 +489,23 @@ impl TimeoutHandler for Client {
     }
 }
 
+impl MemoryConsumer for Client {
+    /// The trie nodes cached and the blocks and the headers waiting to be verified.
+    fn memory_usage(&self) -> usize {
+        self.memory_usages().iter().sum()
+    }
+
+    fn restrict_memory(&self, allowance: Option<usize>) {
+        let allowances = match allowance {
+            Some(allowance) => share(&self.memory_usages(), allowance).into_iter().map(Some).collect(),
+            None => vec![None; 3],
+        };
+        self.state_db.read().restrict_node_cache(allowances[0]);
+        self.importer.block_queue.restrict_memory(allowances[1]);
+        self.importer.header_queue.restrict_memory(allowances[2]);
+    }
+}
+
 impl DatabaseClient for Client {
     fn database(&self) -> Arc<dyn KeyValueDB> {
         Arc::clone(&self.db())
@@ -338,6 +525,57 @@ impl ExecuteClient for Client {
         )
     }
 
+    fn dry_run_transaction(
+        &self,
+        tx: &Transaction,
+        signer_public: &Public,
+        id: BlockId,
+    ) -> Option<TransactionExecution> {
+        let header = self.block_header(&id)?.decode();
+        let original = self.state_at(BlockId::Hash(header.hash()))?;
+        let common_params = self.common_params(BlockId::Hash(header.hash()))?;
+
+        let unverified = UnverifiedTransaction::new(tx.clone(), Signature::default());
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let mut state = original.clone();
+        let result = self.engine.machine().verify_transaction_with_params(&unverified, &common_params).and_then(|()| {
+            Ok(state.apply(
+                tx,
+                &unverified.hash(),
+                signer_public,
+                self,
+                header.number(),
+                header.timestamp(),
+                cmp::max(now, header.timestamp() + 1),
+            )?)
+        });
+        let fee = if result.is_ok() {
+            tx.fee
+        } else {
+            0
+        };
+
+        let mut changed_accounts = Vec::new();
+        for (address, _) in state.top_cache().cached_accounts() {
+            let before = (original.balance(&address).ok()?, original.seq(&address).ok()?);
+            let after = (state.balance(&address).ok()?, state.seq(&address).ok()?);
+            if before != after {
+                changed_accounts.push(AccountChange {
+                    address,
+                    before,
+                    after,
+                });
+            }
+        }
+        changed_accounts.sort_by_key(|change| change.address);
+
+        Some(TransactionExecution {
+            result,
+            fee,
+            changed_accounts,
+        })
+    }
+
     fn execute_vm(
         &self,
         tx: &dyn PartialHashing,
@@ -390,6 +628,31 @@ impl StateInfo for Client {
             TopLevelState::from_existing(self.state_db.read().clone(&root), root).ok()
         })
     }
+
+    fn pending_state(&self) -> Option<TopLevelState> {
+        let parent = self.block_header(&BlockId::Latest)?;
+        // The generation is read before the transactions, so the cached state is never older than its key.
+        let mem_pool_generation = self.importer.miner.mem_pool_generation();
+        if let Some(pending) = &*self.pending_state.lock() {
+            if pending.parent == parent.hash() && pending.mem_pool_generation == mem_pool_generation {
+                return Some(pending.state.clone())
+            }
+        }
+
+        let ready = self.ready_transactions(0..u64::max_value()).transactions;
+        let mut block = self.prepare_open_block(BlockId::Hash(parent.hash()), Address::default(), vec![]);
+        self.engine.on_open_block(block.inner_mut()).ok()?;
+        for tx in ready {
+            let _ = block.push_transaction(tx, None, self, parent.number(), parent.timestamp());
+        }
+        let state = block.state().clone();
+        *self.pending_state.lock() = Some(PendingState {
+            parent: parent.hash(),
+            mem_pool_generation,
+            state: state.clone(),
+        });
+        Some(state)
+    }
 }
 
 impl EngineInfo for Client {
@@ -444,6 +707,14 @@ impl EngineInfo for Client {
             addresses.into_iter().map(|address| PlatformAddress::new_v1(network_id, address)).collect()
         }))
     }
+
+    fn clock_drift(&self) -> Option<ClockDrift> {
+        self.engine().clock_drift()
+    }
+
+    fn duties(&self, count: usize) -> Option<Duties> {
+        self.engine().duties(count)
+    }
 }
 
 impl EngineClient for Client {
@@ -530,6 +801,7 @@ impl ImportBlock for Client {
         use crate::verification::queue::kind::blocks::Unverified;
         use crate::verification::queue::kind::BlockLike;
 
+        self.check_disk_space_for_blocks()?;
         let unverified = Unverified::new(bytes);
         {
             if self.block_chain().is_known(&unverified.hash()) {
@@ -540,6 +812,7 @@ impl ImportBlock for Client {
     }
 
     fn import_header(&self, bytes: Bytes) -> Result<BlockHash, BlockImportError> {
+        self.check_disk_space_for_blocks()?;
         let unverified = encoded::Header::new(bytes).decode();
         {
             if self.block_chain().is_known_header(&unverified.hash()) {
@@ -550,6 +823,7 @@ impl ImportBlock for Client {
     }
 
     fn import_trusted_header(&self, header: &Header) -> Result<BlockHash, BlockImportError> {
+        self.check_disk_space_for_blocks()?;
         if self.block_chain().is_known_header(&header.hash()) {
             return Err(BlockImportError::Import(ImportError::AlreadyInChain))
         }
@@ -559,6 +833,7 @@ impl ImportBlock for Client {
     }
 
     fn import_trusted_block(&self, block: &Block) -> Result<BlockHash, BlockImportError> {
+        self.check_disk_space_for_blocks()?;
         if self.block_chain().is_known(&block.header.hash()) {
             return Err(BlockImportError::Import(ImportError::AlreadyInChain))
         }
@@ -584,7 +859,7 @@ impl ImportBlock for Client {
             self.importer.import_headers(vec![header], self, &import_lock);
 
             let route = self.importer.commit_block(block, header, &block_data, self);
-            cinfo!(CLIENT, "Imported sealed block #{} ({})", number, h);
+            cdebug!(CLIENT, "Imported sealed block #{} ({})", number, h);
             route
         };
         let enacted = self.importer.extract_enacted(vec![route]);
@@ -594,6 +869,14 @@ impl ImportBlock for Client {
         Ok(h)
     }
 
+    fn memory_usages(&self) -> [usize; 3] {
+        [
+            self.state_db.read().node_cache_size(),
+            self.importer.block_queue.queue_info().mem_used,
+            self.importer.header_queue.queue_info().mem_used,
+        ]
+    }
+
     fn set_min_timer(&self) {
         self.reseal_timer.cancel(RESEAL_MIN_TIMER_TOKEN).expect("Reseal min timer clear succeeds");
         match self
@@ -616,6 +899,13 @@ impl BlockChainClient for Client {
 
     /// Import own transaction
     fn queue_own_transaction(&self, transaction: SignedTransaction) -> Result<(), Error> {
+        if self.is_in_maintenance() {
+            return Err(Error::Maintenance)
+        }
+        let disk_space = self.disk_space_level();
+        if disk_space != DiskSpaceLevel::Sufficient {
+            return Err(Error::LowDiskSpace(disk_space))
+        }
         self.importer.miner.import_own_transaction(self, transaction)?;
         Ok(())
     }
@@ -623,7 +913,11 @@ impl BlockChainClient for Client {
     fn queue_transactions(&self, transactions: Vec<Bytes>, peer_id: NodeId) {
         let queue_size = self.queue_transactions.load(AtomicOrdering::Relaxed);
         ctrace!(EXTERNAL_TX, "Queue size: {}", queue_size);
-        if queue_size > MAX_MEM_POOL_SIZE {
+        if self.is_in_maintenance() {
+            ctrace!(EXTERNAL_TX, "Ignoring {} transactions: the node is in maintenance", transactions.len());
+        } else if self.disk_space_level() != DiskSpaceLevel::Sufficient {
+            ctrace!(EXTERNAL_TX, "Ignoring {} transactions: the disk space is low", transactions.len());
+        } else if queue_size > MAX_MEM_POOL_SIZE {
             cwarn!(EXTERNAL_TX, "Ignoring {} transactions: queue is full", transactions.len());
         } else {
             let len = transactions.len();
@@ -642,6 +936,10 @@ impl BlockChainClient for Client {
         self.importer.miner.delete_all_pending_transactions();
     }
 
+    fn cancel_pending_transaction(&self, signer: &Address, seq: u64) -> Option<TxHash> {
+        self.importer.miner.cancel_transaction(self, signer, seq)
+    }
+
     fn ready_transactions(&self, range: Range<u64>) -> PendingSignedTransactions {
         self.importer.miner.ready_transactions(range)
     }
@@ -650,6 +948,14 @@ impl BlockChainClient for Client {
         self.importer.miner.count_pending_transactions(range)
     }
 
+    fn scheduled_transactions(&self) -> Vec<SignedTransaction> {
+        self.importer.miner.scheduled_transactions()
+    }
+
+    fn pooled_transactions_of(&self, signer: &Address) -> Vec<SignedTransaction> {
+        self.importer.miner.transactions_of(signer)
+    }
+
     fn is_pending_queue_empty(&self) -> bool {
         self.importer.miner.status().transactions_in_pending_queue == 0
     }
@@ -747,6 +1053,12 @@ impl AccountData for Client {
         state.balance(address).ok()
     }
 
+    fn locked_balance(&self, address: &Address, id: BlockId) -> Option<u64> {
+        let block_number = self.block_number(&id)?;
+        let state = self.state_at(id)?;
+        state.locked_balance(address, block_number).ok()
+    }
+
     fn regular_key(&self, address: &Address, state: StateOrBlock) -> Option<Public> {
         let state = self.state_info(state)?;
         state.regular_key(address).ok()?
@@ -783,6 +1095,11 @@ impl Shard for Client {
         let state = self.state_info(state)?;
         state.shard_users(shard_id).ok()?
     }
+
+    fn shard_proof(&self, shard_id: ShardId, key: &H256, id: BlockId) -> Option<ShardProof> {
+        let state = self.state_at(id)?;
+        state.shard_proof(shard_id, key).ok()?
+    }
 }
 
 impl BlockProducer for Client {
@@ -831,6 +1148,18 @@ impl MiningBlockChainClient for Client {
     fn mem_pool_min_fees(&self) -> MemPoolMinFees {
         self.importer.miner.get_options().mem_pool_min_fees
     }
+
+    fn base_fee(&self, action: &Action) -> u64 {
+        self.common_params(BlockId::Latest).map_or(0, |params| CodeChainMachine::min_cost(&params, action))
+    }
+
+    fn mem_pool_content(&self) -> MemPoolContent {
+        self.importer.miner.mem_pool_content()
+    }
+
+    fn invalid_transactions_of_peers(&self) -> Vec<(NodeId, usize)> {
+        self.importer.miner.invalid_transactions_of_peers()
+    }
 }
 
 impl ChainTimeInfo for Client {
@@ -856,3 +1185,679 @@ impl SnapshotClient for Client {
         }
     }
 }
+
+impl SchemeExportClient for Client {
+    fn export_genesis(&self, id: BlockId) -> Option<serde_json::Value> {
+        let header = self.block_header(&id)?;
+        let state_root = header.state_root();
+        let state = self.state_at(id)?;
+        let number_of_shards = *state.metadata().ok()??.number_of_shards();
+        let mut roots = vec![state_root];
+        for shard_id in 0..number_of_shards {
+            // A terminated shard has no root.
+            roots.extend(state.shard_root(shard_id).ok()?);
+        }
+
+        // The top level trie and the shard level tries are exported as they are, so the state root is kept.
+        let db = self.state_db.read().clone(&state_root);
+        let mut chunks = serde_json::Map::new();
+        for root in roots.into_iter().filter(|root| *root != BLAKE_NULL_RLP) {
+            for chunk in Snapshot::from_hashdb(db.as_hashdb(), root) {
+                let mut compressed = Vec::new();
+                if let Err(err) = ChunkCompressor::new(&mut compressed).compress_chunk(&chunk) {
+                    cwarn!(CLIENT, "Cannot compress the state chunk {}: {}", chunk.root, err);
+                    return None
+                }
+                chunks.insert(format!("0x{:x}", chunk.root), json!(JsonBytes::new(compressed)));
+            }
+        }
+
+        Some(json!({
+            "genesis": {
+                "timestamp": Uint::from(header.timestamp()),
+                "stateRoot": JsonH256::from(state_root),
+            },
+            "stateChunks": chunks,
+        }))
+    }
+}
+
+impl FraudProofClient for Client {
+    fn fraud_proofs(&self) -> Vec<FraudProof> {
+        self.importer.fraud_proofs.lock().iter().rev().cloned().collect()
+    }
+
+    fn import_fraud_proof(&self, proof: FraudProof) -> Result<bool, Error> {
+        let block_hash = proof.block_hash();
+        if self.importer.has_fraud_proof(&block_hash) {
+            return Ok(false)
+        }
+        if let FraudProof::BadStateTransition {
+            parent,
+            ..
+        } = &proof
+        {
+            // The witness is checked against the state root of the parent, so the parent must be known.
+            if self.block_header(&BlockId::Hash(parent.hash())).is_none() {
+                return Ok(false)
+            }
+        }
+        if !proof.verify(&*self.engine, self)? {
+            return Ok(false)
+        }
+        self.importer.block_queue.mark_as_bad(&[block_hash]);
+        Ok(self.importer.add_fraud_proof(proof))
+    }
+}
+
+impl ReplayClient for Client {
+    fn replay_block(&self, id: BlockId) -> Result<Option<ReplayedBlock>, Error> {
+        let block = match self.block(&id) {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+        let header = block.decode_header();
+        let parent = match self.block_header(&BlockId::Hash(*header.parent_hash())) {
+            Some(parent) => parent.decode(),
+            None => return Ok(None),
+        };
+        let parent_state = match self.state_at(BlockId::Hash(parent.hash())) {
+            Some(state) => state,
+            None => return Ok(None),
+        };
+
+        let transactions = SignedTransaction::try_new_batch(block.transactions())?;
+        let db = self.state_db().read().clone(parent.state_root());
+        let replayed = enact(&header, &transactions, &*self.engine, self, db, &parent)?;
+
+        let mut addresses = BTreeSet::new();
+        addresses.insert(*header.author());
+        for tx in &transactions {
+            let signer = public_to_address(&tx.signer_public());
+            addresses.insert(parent_state.regular_key_owner(&signer)?.unwrap_or(signer));
+            if let Some(sponsor_public) = tx.sponsor_public() {
+                let sponsor = public_to_address(&sponsor_public);
+                addresses.insert(parent_state.regular_key_owner(&sponsor)?.unwrap_or(sponsor));
+            }
+            for action in tx.action.flatten() {
+                if let Action::Pay {
+                    receiver,
+                    ..
+                } = action
+                {
+                    addresses.insert(*receiver);
+                }
+            }
+        }
+
+        // The stored state may be pruned.
+        let mut divergent_accounts = Vec::new();
+        if let Some(expected_state) = self.state_at(BlockId::Hash(header.hash())) {
+            let replayed_state = replayed.state();
+            for address in addresses {
+                let expected = (expected_state.balance(&address)?, expected_state.seq(&address)?);
+                let replayed = (replayed_state.balance(&address)?, replayed_state.seq(&address)?);
+                if expected != replayed {
+                    divergent_accounts.push(DivergentAccount {
+                        address,
+                        expected,
+                        replayed,
+                    });
+                }
+            }
+        }
+
+        Ok(Some(ReplayedBlock {
+            number: header.number(),
+            hash: header.hash(),
+            expected_state_root: *header.state_root(),
+            state_root: *replayed.header().state_root(),
+            divergent_accounts,
+        }))
+    }
+
+    fn block_execution_summary(&self, id: BlockId) -> Result<Option<Arc<BlockExecutionSummary>>, Error> {
+        let hash = match self.block_hash(&id) {
+            Some(hash) => hash,
+            None => return Ok(None),
+        };
+        if let Some(summary) = self.execution_summaries.lock().get_mut(&hash) {
+            return Ok(Some(Arc::clone(summary)))
+        }
+
+        let block = match self.block(&BlockId::Hash(hash)) {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+        let header = block.decode_header();
+        let parent = match self.block_header(&BlockId::Hash(*header.parent_hash())) {
+            Some(parent) => parent.decode(),
+            None => return Ok(None),
+        };
+        if self.state_at(BlockId::Hash(parent.hash())).is_none() {
+            return Ok(None)
+        }
+
+        let transactions = SignedTransaction::try_new_batch(block.transactions())?;
+        let db = self.state_db().read().clone(parent.state_root());
+        let results = execute_each(&header, &transactions, &*self.engine, self, db, &parent)?;
+        let summary = Arc::new(BlockExecutionSummary {
+            number: header.number(),
+            hash,
+            transactions: transactions
+                .iter()
+                .zip(results)
+                .map(|(tx, (result, touched_accounts))| TransactionSummary {
+                    hash: tx.hash(),
+                    action: tx.action.clone(),
+                    fee: if result.is_ok() {
+                        tx.fee
+                    } else {
+                        0
+                    },
+                    error: result.err().map(|err| err.to_string()),
+                    touched_accounts,
+                })
+                .collect(),
+        });
+        self.execution_summaries.lock().insert(hash, Arc::clone(&summary));
+        Ok(Some(summary))
+    }
+
+    fn block_witness(&self, id: BlockId) -> Result<Option<Vec<Bytes>>, Error> {
+        let block = match self.block(&id) {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+        let header = block.decode_header();
+        let parent = match self.block_header(&BlockId::Hash(*header.parent_hash())) {
+            Some(parent) => parent.decode(),
+            None => return Ok(None),
+        };
+        if self.state_at(BlockId::Hash(parent.hash())).is_none() {
+            return Ok(None)
+        }
+
+        let transactions = SignedTransaction::try_new_batch(block.transactions())?;
+        // The cached items are not read from the trie, so they would be missing in the witness.
+        let mut db = self.state_db().read().clone_without_cache();
+        let witness = Witness::default();
+        db.record_witness(witness.clone());
+        enact(&header, &transactions, &*self.engine, self, db, &parent)?;
+        Ok(Some(witness.nodes()))
+    }
+
+    fn execute_with_witness(&self, id: BlockId, witness: &[Bytes]) -> Result<Option<H256>, Error> {
+        let block = match self.block(&id) {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+        let header = block.decode_header();
+        let parent = match self.block_header(&BlockId::Hash(*header.parent_hash())) {
+            Some(parent) => parent.decode(),
+            None => return Ok(None),
+        };
+
+        let transactions = SignedTransaction::try_new_batch(block.transactions())?;
+        let db = StateDB::from_witness(witness);
+        let executed = enact(&header, &transactions, &*self.engine, self, db, &parent)?;
+        Ok(Some(*executed.header().state_root()))
+    }
+}
+
+impl Client {
+    fn ensure_fork_supported(&self) -> Result<(), Error> {
+        if self.engine.engine_type() != EngineType::Solo {
+            return Err(Error::Other("Forks are supported only by the solo engine".to_string()))
+        }
+        Ok(())
+    }
+
+    /// Creates a block with the transactions on `parent` and imports it.
+    fn create_block(&self, parent: BlockHash, transactions: &[SignedTransaction]) -> Result<BlockHash, Error> {
+        self.ensure_fork_supported()?;
+        let parent_header = self
+            .block_header(&BlockId::Hash(parent))
+            .ok_or_else(|| Error::Other(format!("Unknown block {}", parent)))?
+            .decode();
+        if self.state_at(BlockId::Hash(parent)).is_none() {
+            return Err(Error::Other(format!("The state of {} is not available", parent)))
+        }
+
+        // The random extra data distinguishes the block from its siblings made at the same time.
+        let mut open_block =
+            self.prepare_open_block(BlockId::Hash(parent), *parent_header.author(), H256::random().to_vec());
+        self.engine.on_open_block(open_block.inner_mut())?;
+        open_block.push_transactions(transactions, self, parent_header.number(), parent_header.timestamp())?;
+        let seal = self.engine.generate_seal(None, &parent_header);
+        let seal_fields = seal.seal_fields().ok_or_else(|| Error::Other("Cannot seal a fork".to_string()))?;
+        open_block.seal(&*self.engine, seal_fields)?;
+        let term_common_params = self.term_common_params(BlockId::Hash(parent));
+        let sealed = open_block.close(term_common_params.as_ref())?.lock().already_sealed();
+        Ok(self.import_sealed_block(&sealed)?)
+    }
+}
+
+impl MaintenanceClient for Client {
+    fn is_in_maintenance(&self) -> bool {
+        self.maintenance.load(AtomicOrdering::SeqCst)
+    }
+
+    fn set_maintenance_mode(&self, on: bool) {
+        if self.maintenance.swap(on, AtomicOrdering::SeqCst) == on {
+            return
+        }
+        if on {
+            cwarn!(CLIENT, "The node enters maintenance: it stops authoring blocks and accepting transactions");
+        } else {
+            cinfo!(CLIENT, "The node leaves maintenance");
+        }
+    }
+}
+
+impl ForkClient for Client {
+    fn create_fork(&self, parent: BlockHash) -> Result<BlockHash, Error> {
+        self.create_block(parent, &[])
+    }
+
+    fn reorg_to(&self, hash: BlockHash) -> Result<(), Error> {
+        self.ensure_fork_supported()?;
+        if self.state_at(BlockId::Hash(hash)).is_none() {
+            return Err(Error::Other(format!("The state of {} is not available", hash)))
+        }
+
+        let route = {
+            let _import_lock = self.importer.import_lock.lock();
+            let chain = self.block_chain();
+            let mut batch = DBTransaction::new();
+            let route = chain
+                .update_best_branch(&mut batch, &hash)
+                .ok_or_else(|| Error::Other(format!("Cannot find the route to {}", hash)))?;
+            self.db().write_buffered(batch);
+            chain.commit();
+            route
+        };
+        cinfo!(CLIENT, "Reorganized to {} retracting {} blocks", hash, route.retracted.len());
+
+        let retracted_transactions = {
+            let chain = self.block_chain();
+            let enacted_transactions: HashSet<TxHash> = route
+                .enacted
+                .iter()
+                .flat_map(|hash| chain.block_body(hash))
+                .flat_map(|body| body.transaction_hashes())
+                .collect();
+            route
+                .retracted
+                .iter()
+                .rev()
+                .flat_map(|hash| chain.block_body(hash))
+                .flat_map(|body| body.transactions())
+                .filter(|tx| !enacted_transactions.contains(&tx.hash()))
+                .collect()
+        };
+        self.importer.miner.import_external_transactions(self, retracted_transactions, TxSource::Retracted);
+
+        self.importer.miner.chain_new_blocks(self, &[], &[], &route.enacted);
+        self.new_blocks(&[], &[], &route.enacted, &[]);
+        self.events.publish(Reorganized {
+            retracted: route.retracted,
+            enacted: route.enacted,
+        });
+        self.db().flush().expect("DB flush failed.");
+        Ok(())
+    }
+}
+
+impl StateSurgeryClient for Client {
+    fn rewrite_state(&self, id: BlockId, modifications: &[StateModification]) -> Result<BlockHash, Error> {
+        let block = self.block(&id).ok_or_else(|| Error::Other(format!("Unknown block {:?}", id)))?;
+        let mut header = block.decode_header();
+        if header.number() == 0 {
+            return Err(Error::Other("The state of the genesis block cannot be rewritten".to_string()))
+        }
+        let mut state = self
+            .state_at(BlockId::Hash(header.hash()))
+            .ok_or_else(|| Error::Other(format!("The state of {} is not available", header.hash())))?;
+
+        for modification in modifications {
+            match modification {
+                StateModification::Balance {
+                    address,
+                    balance,
+                } => state.set_balance(address, *balance)?,
+                StateModification::Seq {
+                    address,
+                    seq,
+                } => state.set_seq(address, *seq)?,
+                StateModification::ActionData {
+                    key,
+                    value,
+                } if value.is_empty() => state.remove_action_data(key),
+                StateModification::ActionData {
+                    key,
+                    value,
+                } => state.update_action_data(key, value.clone())?,
+            }
+        }
+        header.set_state_root(state.commit()?);
+        let hash = header.hash();
+        let block_data = Block {
+            header: header.clone(),
+            transactions: block.transactions(),
+        }
+        .rlp_bytes(&Seal::With);
+
+        let route = {
+            let _import_lock = self.importer.import_lock.lock();
+            let chain = self.block_chain();
+
+            let mut batch = DBTransaction::new();
+            state.journal_under(&mut batch, header.number())?;
+            chain.insert_block(&mut batch, &block_data, Vec::new(), &*self.engine);
+            self.db().write_buffered(batch);
+            chain.commit();
+
+            let mut batch = DBTransaction::new();
+            let route = chain
+                .update_best_branch(&mut batch, &hash)
+                .ok_or_else(|| Error::Other(format!("Cannot find the route to {}", hash)))?;
+            self.db().write_buffered(batch);
+            chain.commit();
+            self.state_db().write().override_state(&state);
+            route
+        };
+        cinfo!(
+            CLIENT,
+            "Rewrote the state of #{} to {} as {}, retracting {} blocks",
+            header.number(),
+            header.state_root(),
+            hash,
+            route.retracted.len()
+        );
+        self.db().flush().expect("DB flush failed.");
+        self.events.publish(Reorganized {
+            retracted: route.retracted,
+            enacted: route.enacted,
+        });
+        Ok(hash)
+    }
+
+    fn check_state(&self, id: BlockId) -> Option<StateCheck> {
+        let header = self.block_header(&id)?;
+        let state_db = self.state_db().read();
+        Some(check_state(state_db.as_hashdb(), header.state_root()))
+    }
+}
+
+impl LightClientSupport for Client {
+    fn commit(&self, id: BlockId) -> Option<Commit> {
+        let header = self.block_header(&id)?.decode();
+        let child = self.block_header(&BlockId::Number(header.number() + 1))?.decode();
+        if *child.parent_hash() != header.hash() {
+            return None
+        }
+        let (view, signatures) = self.engine.parent_commit(&child)?;
+        Some(Commit {
+            header,
+            view,
+            signatures,
+        })
+    }
+
+    fn commit_validators(&self, id: BlockId) -> Option<Vec<CommitValidator>> {
+        let hash = self.block_hash(&id)?;
+        self.engine.commit_validators(&hash)
+    }
+
+    fn action_data_proof(&self, key: &H256, id: BlockId) -> Option<ActionDataProof> {
+        let state = self.state_at(id)?;
+        state.action_data_proof(key).ok()
+    }
+
+    fn account_proof(&self, address: &Address, id: BlockId) -> Option<AccountProof> {
+        let state = self.state_at(id)?;
+        state.account_proof(address).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::NUM_COLUMNS;
+    use crate::miner::MinerOptions;
+    use crate::service::ClientService;
+    use ckey::{KeyPair, Private};
+    use ctimer::TimerLoop;
+
+    fn start_client(scheme: &Scheme, timer_loop: &TimerLoop) -> ClientService {
+        start_client_with_options(scheme, Default::default(), timer_loop)
+    }
+
+    fn start_client_with_options(scheme: &Scheme, options: MinerOptions, timer_loop: &TimerLoop) -> ClientService {
+        let db: Arc<dyn KeyValueDB> = Arc::new(kvdb_memorydb::create(NUM_COLUMNS.unwrap()));
+        let miner = Miner::new(options, scheme, None, Arc::clone(&db));
+        let reseal_timer = timer_loop.new_timer_with_name("Client reseal timer");
+        ClientService::start(&ClientConfig::default(), scheme, db, miner, reseal_timer).unwrap()
+    }
+
+    #[test]
+    fn exported_genesis_reproduces_the_state() {
+        let scheme = Scheme::new_test_solo();
+        let timer_loop = TimerLoop::new(1);
+        let service = start_client(&scheme, &timer_loop);
+        let client = service.client();
+        let hash = client.create_fork(client.chain_info().best_block_hash).unwrap();
+        let header = client.block_header(&BlockId::Hash(hash)).unwrap();
+        assert_ne!(scheme.state_root(), header.state_root());
+
+        let mut exported = client.export_genesis(BlockId::Hash(hash)).unwrap();
+        exported.as_object_mut().unwrap().insert("extends".to_string(), json!("solo"));
+        let imported = Scheme::load(exported.to_string().as_bytes()).unwrap();
+        assert_eq!(header.state_root(), imported.state_root());
+
+        let db = imported.ensure_genesis_state(StateDB::new_with_memorydb()).unwrap();
+        let state = TopLevelState::from_existing(db, imported.state_root()).unwrap();
+        let expected = client.state_at(BlockId::Hash(hash)).unwrap();
+        assert_eq!(expected.metadata().unwrap(), state.metadata().unwrap());
+        assert_eq!(expected.shard_root(0).unwrap(), state.shard_root(0).unwrap());
+        for address in scheme.genesis_accounts() {
+            assert_eq!(expected.balance(&address).unwrap(), state.balance(&address).unwrap());
+            assert_eq!(expected.seq(&address).unwrap(), state.seq(&address).unwrap());
+        }
+    }
+
+    #[test]
+    fn replayed_block_matches_the_stored_state() {
+        let scheme = Scheme::new_test_solo();
+        let timer_loop = TimerLoop::new(1);
+        let service = start_client(&scheme, &timer_loop);
+        let client = service.client();
+        let genesis = client.chain_info().best_block_hash;
+        let hash = client.create_fork(genesis).unwrap();
+
+        let replayed = client.replay_block(BlockId::Hash(hash)).unwrap().unwrap();
+        assert_eq!(1, replayed.number);
+        assert_eq!(hash, replayed.hash);
+        assert_eq!(client.block_header(&BlockId::Hash(hash)).unwrap().state_root(), replayed.expected_state_root);
+        assert!(!replayed.is_diverged());
+        assert!(replayed.divergent_accounts.is_empty());
+
+        assert!(client.replay_block(BlockId::Hash(genesis)).unwrap().is_none());
+        assert!(client.replay_block(BlockId::Number(2)).unwrap().is_none());
+    }
+
+    #[test]
+    fn reorg_moves_the_best_block_and_the_pooled_transactions() {
+        let scheme = Scheme::new_test_solo();
+        let timer_loop = TimerLoop::new(1);
+        // The miner must not make blocks by itself.
+        let options = MinerOptions {
+            reseal_on_external_transaction: false,
+            reseal_on_own_transaction: false,
+            no_reseal_timer: true,
+            ..Default::default()
+        };
+        let service = start_client_with_options(&scheme, options, &timer_loop);
+        let client = service.client();
+        let genesis = client.chain_info().best_block_hash;
+
+        let secret: Private = "ede1d4ccb4ec9a8bbbae9a13db3f4a7b56ea04189be86ac3a6a439d9a0a1addd".into();
+        let keypair = KeyPair::from_private(secret).unwrap();
+        let sender = public_to_address(keypair.public());
+        let common_params = client.common_params(BlockId::Latest).unwrap();
+        let tx = Transaction {
+            seq: 0,
+            fee: common_params.min_pay_transaction_cost(),
+            network_id: common_params.network_id(),
+            action: Action::Pay {
+                receiver: Address::random(),
+                quantity: 1,
+            },
+        };
+        let tx = SignedTransaction::new_with_sign(tx, keypair.private());
+
+        let with_tx = client.create_block(genesis, &[tx.clone()]).unwrap();
+        let fork = client.create_fork(genesis).unwrap();
+        let longer_fork = client.create_fork(fork).unwrap();
+
+        client.reorg_to(with_tx).unwrap();
+        assert_eq!(with_tx, client.chain_info().best_block_hash);
+        assert_eq!(1, client.chain_info().best_block_number);
+        assert_eq!(Some(with_tx), client.block_hash(&BlockId::Number(1)));
+        assert_eq!(None, client.block_hash(&BlockId::Number(2)));
+        assert_eq!(with_tx, client.block_header(&BlockId::Latest).unwrap().hash());
+        assert_eq!(vec![tx.hash()], client.block_body(&BlockId::Number(1)).unwrap().transaction_hashes());
+        assert_eq!(Some(with_tx), client.transaction(&tx.hash().into()).map(|tx| tx.block_hash));
+        assert_eq!(1, client.latest_seq(&sender));
+        assert!(client.pooled_transactions_of(&sender).is_empty());
+
+        client.reorg_to(longer_fork).unwrap();
+        assert_eq!(longer_fork, client.chain_info().best_block_hash);
+        assert_eq!(2, client.chain_info().best_block_number);
+        assert_eq!(Some(fork), client.block_hash(&BlockId::Number(1)));
+        assert_eq!(longer_fork, client.block_header(&BlockId::Latest).unwrap().hash());
+        assert!(client.block_body(&BlockId::Number(1)).unwrap().transaction_hashes().is_empty());
+        assert!(client.transaction(&tx.hash().into()).is_none());
+        assert_eq!(0, client.latest_seq(&sender));
+        // The retracted transaction goes back to the mem pool.
+        assert_eq!(vec![tx.clone()], client.pooled_transactions_of(&sender));
+
+        client.reorg_to(with_tx).unwrap();
+        assert_eq!(with_tx, client.chain_info().best_block_hash);
+        assert_eq!(None, client.block_hash(&BlockId::Number(2)));
+        assert!(client.pooled_transactions_of(&sender).is_empty());
+    }
+
+    #[test]
+    fn rewritten_state_becomes_the_best_state() {
+        let scheme = Scheme::new_test_solo();
+        let timer_loop = TimerLoop::new(1);
+        let service = start_client(&scheme, &timer_loop);
+        let client = service.client();
+        let genesis = client.chain_info().best_block_hash;
+        let block = client.create_fork(genesis).unwrap();
+        client.create_fork(block).unwrap();
+        assert_eq!(2, client.chain_info().best_block_number);
+
+        let address = Address::random();
+        let key = H256::random();
+        let modifications = [
+            StateModification::Balance {
+                address,
+                balance: 100,
+            },
+            StateModification::Seq {
+                address,
+                seq: 3,
+            },
+            StateModification::ActionData {
+                key,
+                value: vec![0xc0],
+            },
+        ];
+        let rewritten = client.rewrite_state(BlockId::Number(1), &modifications).unwrap();
+        assert_ne!(block, rewritten);
+        assert_eq!(rewritten, client.chain_info().best_block_hash);
+        assert_eq!(1, client.chain_info().best_block_number);
+        assert_eq!(Some(rewritten), client.block_hash(&BlockId::Number(1)));
+        assert_eq!(None, client.block_hash(&BlockId::Number(2)));
+        assert_eq!(Some(genesis), client.block_header(&BlockId::Latest).map(|header| header.parent_hash()));
+        assert_eq!(100, client.latest_balance(&address));
+        assert_eq!(3, client.latest_seq(&address));
+        let state = client.state_at(BlockId::Latest).unwrap();
+        assert_eq!(Some(vec![0xc0]), state.action_data(&key).unwrap().map(|data| data.to_vec()));
+
+        let removal = [StateModification::ActionData {
+            key,
+            value: Vec::new(),
+        }];
+        client.rewrite_state(BlockId::Latest, &removal).unwrap();
+        assert_eq!(None, client.state_at(BlockId::Latest).unwrap().action_data(&key).unwrap());
+        assert_eq!(100, client.latest_balance(&address));
+
+        assert!(client.rewrite_state(BlockId::Hash(genesis), &modifications).is_err());
+    }
+
+    #[test]
+    fn pending_state_follows_the_best_block_and_the_ready_transactions() {
+        let scheme = Scheme::new_test_solo();
+        let timer_loop = TimerLoop::new(1);
+        // The miner must not make blocks by itself.
+        let options = MinerOptions {
+            reseal_on_external_transaction: false,
+            reseal_on_own_transaction: false,
+            no_reseal_timer: true,
+            ..Default::default()
+        };
+        let service = start_client_with_options(&scheme, options, &timer_loop);
+        let client = service.client();
+        let genesis = client.chain_info().best_block_hash;
+
+        let secret: Private = "ede1d4ccb4ec9a8bbbae9a13db3f4a7b56ea04189be86ac3a6a439d9a0a1addd".into();
+        let keypair = KeyPair::from_private(secret).unwrap();
+        let sender = public_to_address(keypair.public());
+        let receiver = Address::random();
+        let common_params = client.common_params(BlockId::Latest).unwrap();
+        let tx = Transaction {
+            seq: 0,
+            fee: common_params.min_pay_transaction_cost(),
+            network_id: common_params.network_id(),
+            action: Action::Pay {
+                receiver,
+                quantity: 1,
+            },
+        };
+        let tx = SignedTransaction::new_with_sign(tx, keypair.private());
+
+        assert_eq!(0, client.pending_state().unwrap().balance(&receiver).unwrap());
+        client.queue_own_transaction(tx.clone()).unwrap();
+        let state = client.pending_state().unwrap();
+        assert_eq!(1, state.balance(&receiver).unwrap());
+        assert_eq!(1, state.seq(&sender).unwrap());
+        assert_eq!(0, client.latest_balance(&receiver));
+        let generation = client.importer.miner.mem_pool_generation();
+        {
+            let pending_state = client.pending_state.lock();
+            let pending = pending_state.as_ref().unwrap();
+            assert_eq!((genesis, generation), (pending.parent, pending.mem_pool_generation));
+        }
+        // Reading the pending state leaves the pool as it is.
+        client.pending_state().unwrap();
+        assert_eq!(generation, client.importer.miner.mem_pool_generation());
+
+        let best = client.create_fork(genesis).unwrap();
+        assert_eq!(best, client.chain_info().best_block_hash);
+        assert_eq!(1, client.pending_state().unwrap().balance(&receiver).unwrap());
+        assert_eq!(Some(best), client.pending_state.lock().as_ref().map(|pending| pending.parent));
+
+        let with_tx = client.create_block(best, &[tx]).unwrap();
+        client.reorg_to(with_tx).unwrap();
+        assert_eq!(1, client.pending_state().unwrap().balance(&receiver).unwrap());
+        assert_eq!(0, client.ready_transactions(0..u64::max_value()).transactions.len());
+        let generation = client.importer.miner.mem_pool_generation();
+        let pending_state = client.pending_state.lock();
+        let pending = pending_state.as_ref().unwrap();
+        assert_eq!((with_tx, generation), (pending.parent, pending.mem_pool_generation));
+    }
+}
@@ -71,6 +71,8 @@ pub struct ClientConfig {
     pub db_compaction: DatabaseCompactionProfile,
     /// State db cache-size.
     pub state_cache_size: usize,
+    /// Size of the trie nodes cached across blocks.
+    pub trie_node_cache_size: usize,
     /// Type of block verifier used by client.
     pub verifier_type: VerifierType,
 }
@@ -79,11 +81,13 @@ impl Default for ClientConfig {
     fn default() -> Self {
         let mb = 1024 * 1024;
         const DEFAULT_STATE_CACHE_SIZE: u32 = 25;
+        const DEFAULT_TRIE_NODE_CACHE_SIZE: u32 = 64;
         Self {
             queue: Default::default(),
             db_cache_size: Default::default(),
             db_compaction: Default::default(),
             state_cache_size: DEFAULT_STATE_CACHE_SIZE as usize * mb,
+            trie_node_cache_size: DEFAULT_TRIE_NODE_CACHE_SIZE as usize * mb,
             verifier_type: Default::default(),
         }
     }
@@ -0,0 +1,180 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::Client;
+use ctimer::{TimeoutHandler, TimerToken};
+use parking_lot::Mutex;
+use std::ffi::CString;
+use std::fmt;
+use std::io;
+use std::mem::MaybeUninit;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::Weak;
+
+/// A level is lifted once the free space exceeds its threshold by this percent.
+const RELIEF_PERCENT: u64 = 10;
+
+/// How much the node restricts itself to keep the database from running the disk out of space.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiskSpaceLevel {
+    Sufficient,
+    /// The mem pool refuses new transactions.
+    Low,
+    /// The node stops importing blocks and serves only the data it has.
+    Critical,
+}
+
+impl fmt::Display for DiskSpaceLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiskSpaceLevel::Sufficient => write!(f, "sufficient"),
+            DiskSpaceLevel::Low => write!(f, "low"),
+            DiskSpaceLevel::Critical => write!(f, "critical"),
+        }
+    }
+}
+
+/// The free bytes below which each level is entered. Zero disables the level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DiskSpaceThresholds {
+    pub low: u64,
+    pub critical: u64,
+}
+
+impl DiskSpaceThresholds {
+    /// The level for `free` bytes. The current level stays until the free space exceeds its threshold enough,
+    /// so the node doesn't flip between the levels while the space hovers around a threshold.
+    pub fn level(&self, free: u64, current: DiskSpaceLevel) -> DiskSpaceLevel {
+        let below = |threshold: u64, level: DiskSpaceLevel| {
+            let threshold = if current >= level {
+                threshold + threshold / 100 * RELIEF_PERCENT
+            } else {
+                threshold
+            };
+            free < threshold
+        };
+        if below(self.critical, DiskSpaceLevel::Critical) {
+            DiskSpaceLevel::Critical
+        } else if below(self.low, DiskSpaceLevel::Low) {
+            DiskSpaceLevel::Low
+        } else {
+            DiskSpaceLevel::Sufficient
+        }
+    }
+}
+
+/// The bytes available to the node on the volume of `path`.
+pub fn free_space(path: &Path) -> io::Result<u64> {
+    let path =
+        CString::new(path.as_os_str().as_bytes()).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+    // `stat` is initialized by `statvfs` if it succeeds.
+    let stat = unsafe {
+        if libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return Err(io::Error::last_os_error())
+        }
+        stat.assume_init()
+    };
+    // The types of the fields differ between the platforms.
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+/// Checks the free space of the volume having the database periodically, and restricts the client by the level.
+pub struct DiskSpaceMonitor {
+    path: PathBuf,
+    thresholds: DiskSpaceThresholds,
+    client: Weak<Client>,
+    failed: Mutex<bool>,
+}
+
+impl DiskSpaceMonitor {
+    pub fn new(path: PathBuf, thresholds: DiskSpaceThresholds, client: Weak<Client>) -> Self {
+        Self {
+            path,
+            thresholds,
+            client,
+            failed: Mutex::new(false),
+        }
+    }
+
+    pub fn check(&self) {
+        let client = match self.client.upgrade() {
+            Some(client) => client,
+            None => return,
+        };
+        let free = match free_space(&self.path) {
+            Ok(free) => free,
+            Err(err) => {
+                // The error is reported once, and the level is kept until the space can be read again.
+                let mut failed = self.failed.lock();
+                if !*failed {
+                    cwarn!(CLIENT, "Cannot read the free space of {}: {}", self.path.display(), err);
+                    *failed = true;
+                }
+                return
+            }
+        };
+        *self.failed.lock() = false;
+        let level = self.thresholds.level(free, client.disk_space_level());
+        client.set_disk_space_level(level, free);
+    }
+}
+
+impl TimeoutHandler for DiskSpaceMonitor {
+    fn on_timeout(&self, _token: TimerToken) {
+        self.check();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLDS: DiskSpaceThresholds = DiskSpaceThresholds {
+        low: 1000,
+        critical: 100,
+    };
+
+    #[test]
+    fn levels_are_entered_below_the_thresholds() {
+        assert_eq!(DiskSpaceLevel::Sufficient, THRESHOLDS.level(1000, DiskSpaceLevel::Sufficient));
+        assert_eq!(DiskSpaceLevel::Low, THRESHOLDS.level(999, DiskSpaceLevel::Sufficient));
+        assert_eq!(DiskSpaceLevel::Critical, THRESHOLDS.level(99, DiskSpaceLevel::Sufficient));
+    }
+
+    #[test]
+    fn levels_are_lifted_with_relief() {
+        assert_eq!(DiskSpaceLevel::Critical, THRESHOLDS.level(105, DiskSpaceLevel::Critical));
+        assert_eq!(DiskSpaceLevel::Low, THRESHOLDS.level(110, DiskSpaceLevel::Critical));
+        assert_eq!(DiskSpaceLevel::Low, THRESHOLDS.level(1050, DiskSpaceLevel::Low));
+        assert_eq!(DiskSpaceLevel::Sufficient, THRESHOLDS.level(1100, DiskSpaceLevel::Low));
+    }
+
+    #[test]
+    fn zero_disables_the_level() {
+        let thresholds = DiskSpaceThresholds {
+            low: 0,
+            critical: 0,
+        };
+        assert_eq!(DiskSpaceLevel::Sufficient, thresholds.level(0, DiskSpaceLevel::Critical));
+    }
+
+    #[test]
+    fn free_space_of_the_current_directory() {
+        assert!(free_space(Path::new(".")).unwrap() > 0);
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The bus that the modules of the node publish their events to.
+//!
+//! Each type of event has its own channels. A consumer subscribes to the type it wants with a buffer, and reads the
+//! events from the receiver at its own pace. Publishing never blocks: a subscriber whose buffer is full loses the
+//! event, and the subscribers that dropped their receivers are removed.
+
+use crate::invoice::Invoice;
+use cnetwork::NodeId;
+use crossbeam_channel as crossbeam;
+use ctypes::{BlockHash, BlockNumber, TxHash};
+use parking_lot::Mutex;
+use std::any::{type_name, Any, TypeId};
+use std::collections::HashMap;
+
+/// The blocks imported to the chain. The enacted ones are moved to the best chain.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlocksImported {
+    pub imported: Vec<BlockHash>,
+    pub invalid: Vec<BlockHash>,
+    pub enacted: Vec<BlockHash>,
+    pub sealed: Vec<BlockHash>,
+}
+
+/// The results of the transactions in a block committed to the database.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransactionsExecuted {
+    pub block_hash: BlockHash,
+    pub block_number: BlockNumber,
+    pub invoices: Vec<Invoice>,
+}
+
+/// The best chain moved to another branch.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reorganized {
+    /// From the highest.
+    pub retracted: Vec<BlockHash>,
+    /// From the lowest.
+    pub enacted: Vec<BlockHash>,
+}
+
+/// The block committed by the consensus as the best block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Finalized {
+    pub block_hash: BlockHash,
+}
+
+/// The transactions propagated by a peer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransactionsReceived {
+    pub hashes: Vec<TxHash>,
+    pub peer: NodeId,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PeerEvent {
+    Connected(NodeId),
+    Disconnected(NodeId),
+}
+
+type Subscribers<E> = Vec<crossbeam::Sender<E>>;
+
+#[derive(Default)]
+pub struct EventBus {
+    /// The subscribers of each type of event, keyed by the type.
+    topics: Mutex<HashMap<TypeId, Box<dyn Any + Send>>>,
+}
+
+impl EventBus {
+    /// Returns the receiver of the events of type `E` published from now on.
+    /// At most `buffer` events wait in the receiver.
+    pub fn subscribe<E: Clone + Send + 'static>(&self, buffer: usize) -> crossbeam::Receiver<E> {
+        assert_ne!(0, buffer, "A subscriber must buffer at least an event");
+        let (sender, receiver) = crossbeam::bounded(buffer);
+        let mut topics = self.topics.lock();
+        let topic = topics.entry(TypeId::of::<E>()).or_insert_with(|| Box::new(Subscribers::<E>::new()));
+        topic.downcast_mut::<Subscribers<E>>().expect("The topics are keyed by the types of their events").push(sender);
+        receiver
+    }
+
+    /// Sends the event to the subscribers of its type.
+    pub fn publish<E: Clone + Send + 'static>(&self, event: E) {
+        let mut topics = self.topics.lock();
+        let subscribers = match topics.get_mut(&TypeId::of::<E>()) {
+            Some(topic) => {
+                topic.downcast_mut::<Subscribers<E>>().expect("The topics are keyed by the types of their events")
+            }
+            None => return,
+        };
+        subscribers.retain(|subscriber| match subscriber.try_send(event.clone()) {
+            Ok(()) => true,
+            Err(crossbeam::TrySendError::Full(_)) => {
+                cdebug!(CLIENT, "A subscriber of {} lost an event", type_name::<E>());
+                true
+            }
+            Err(crossbeam::TrySendError::Disconnected(_)) => false,
+        });
+    }
+
+    /// The number of the subscribers of the events of type `E`.
+    pub fn subscribers<E: Clone + Send + 'static>(&self) -> usize {
+        self.topics
+            .lock()
+            .get(&TypeId::of::<E>())
+            .and_then(|topic| topic.downcast_ref::<Subscribers<E>>())
+            .map_or(0, Vec::len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_delivered_by_their_types() {
+        let bus = EventBus::default();
+        let finalized = bus.subscribe::<Finalized>(4);
+        let peers = bus.subscribe::<PeerEvent>(4);
+
+        let node = NodeId::new([127, 0, 0, 1].into(), 3485);
+        bus.publish(PeerEvent::Connected(node));
+        bus.publish(Finalized {
+            block_hash: BlockHash::default(),
+        });
+
+        assert_eq!(Ok(PeerEvent::Connected(node)), peers.try_recv());
+        assert!(peers.try_recv().is_err());
+        assert_eq!(
+            Ok(Finalized {
+                block_hash: BlockHash::default(),
+            }),
+            finalized.try_recv()
+        );
+    }
+
+    #[test]
+    fn slow_subscriber_loses_events() {
+        let bus = EventBus::default();
+        let slow = bus.subscribe::<PeerEvent>(1);
+        let fast = bus.subscribe::<PeerEvent>(2);
+
+        let node = NodeId::new([127, 0, 0, 1].into(), 3485);
+        bus.publish(PeerEvent::Connected(node));
+        bus.publish(PeerEvent::Disconnected(node));
+
+        assert_eq!(vec![PeerEvent::Connected(node)], slow.try_iter().collect::<Vec<_>>());
+        assert_eq!(
+            vec![PeerEvent::Connected(node), PeerEvent::Disconnected(node)],
+            fast.try_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn dropped_subscriber_is_removed() {
+        let bus = EventBus::default();
+        let subscriber = bus.subscribe::<Finalized>(1);
+        assert_eq!(1, bus.subscribers::<Finalized>());
+
+        drop(subscriber);
+        bus.publish(Finalized {
+            block_hash: BlockHash::default(),
+        });
+        assert_eq!(0, bus.subscribers::<Finalized>());
+    }
+}
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use super::{BlockChainTrait, Client, ClientConfig};
+use super::{BlockChainTrait, Client, ClientConfig, TransactionsExecuted};
 use crate::block::{enact, Block, IsBlock, LockedBlock};
 use crate::blockchain::{BodyProvider, HeaderProvider, ImportRoute};
 use crate::client::EngineInfo;
@@ -24,19 +24,23 @@ use crate::miner::{Miner, MinerService};
 use crate::service::ClientIoMessage;
 use crate::types::BlockId;
 use crate::verification::queue::{BlockQueue, HeaderQueue};
-use crate::verification::{self, PreverifiedBlock, Verifier};
+use crate::verification::{self, FraudProof, PreverifiedBlock, Verifier};
 use crate::views::{BlockView, HeaderView};
 use cio::IoChannel;
+use cstate::Witness;
 use ctypes::header::{Header, Seal};
 use ctypes::BlockHash;
 use kvdb::DBTransaction;
 use parking_lot::{Mutex, MutexGuard};
 use rlp::Encodable;
 use std::borrow::Borrow;
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 use std::iter::FromIterator;
 use std::sync::Arc;
 
+/// The number of the fraud proofs kept for the peers.
+const MAX_FRAUD_PROOFS: usize = 32;
+
 pub struct Importer {
     /// Lock used during block import
     pub import_lock: Mutex<()>, // FIXME Maybe wrap the whole `Importer` instead?
@@ -55,6 +59,9 @@ pub struct Importer {
 
     /// CodeChain engine to be used during import
     pub engine: Arc<dyn CodeChainEngine>,
+
+    /// Proofs of the recently rejected blocks, from the oldest one
+    pub fraud_proofs: Mutex<VecDeque<FraudProof>>,
 }
 
 impl Importer {
@@ -81,9 +88,29 @@ impl Importer {
             header_queue,
             miner,
             engine,
+            fraud_proofs: Default::default(),
         })
     }
 
+    pub fn has_fraud_proof(&self, block_hash: &BlockHash) -> bool {
+        self.fraud_proofs.lock().iter().any(|proof| proof.block_hash() == *block_hash)
+    }
+
+    /// Keeps the proof unless the block is already proven to be invalid.
+    pub fn add_fraud_proof(&self, proof: FraudProof) -> bool {
+        let mut fraud_proofs = self.fraud_proofs.lock();
+        let block_hash = proof.block_hash();
+        if fraud_proofs.iter().any(|known| known.block_hash() == block_hash) {
+            return false
+        }
+        cinfo!(CLIENT, "Block {} is proven to be invalid", block_hash);
+        fraud_proofs.push_back(proof);
+        if fraud_proofs.len() > MAX_FRAUD_PROOFS {
+            fraud_proofs.pop_front();
+        }
+        true
+    }
+
     /// This is triggered by a message coming from a block queue when the block is ready for insertion
     pub fn import_verified_blocks(&self, client: &Client) -> usize {
         let (imported_blocks, import_results, invalid_blocks, imported, is_empty) = {
@@ -167,6 +194,11 @@ impl Importer {
 
         // Commit results
         let invoices = block.invoices().to_owned();
+        let executed = TransactionsExecuted {
+            block_hash: hash,
+            block_number: number,
+            invoices: invoices.clone(),
+        };
 
         assert_eq!(hash, BlockView::new(block_data).header_view().hash());
 
@@ -184,6 +216,7 @@ impl Importer {
             let state = block.state();
             state_db.override_state(&state);
         }
+        client.events().publish(executed);
 
         route
     }
@@ -250,6 +283,7 @@ impl Importer {
                 header.hash(),
                 e
             );
+            self.add_fraud_proof(FraudProof::BadSeal(header.clone()));
         })?;
 
         // Enact Verified Block
@@ -269,11 +303,34 @@ impl Importer {
                 header.hash(),
                 e
             );
+            if let Some(proof) = self.state_transition_fraud_proof(block, &parent, client) {
+                self.add_fraud_proof(proof);
+            }
         })?;
 
         Ok(locked_block)
     }
 
+    /// Executes the block again recording the trie nodes it reads, which prove the bad state transition.
+    fn state_transition_fraud_proof(
+        &self,
+        block: &PreverifiedBlock,
+        parent: &Header,
+        client: &Client,
+    ) -> Option<FraudProof> {
+        // The cached items are not read from the trie, so they would be missing in the witness.
+        let mut db = client.state_db().read().clone_without_cache();
+        let witness = Witness::default();
+        db.record_witness(witness.clone());
+        enact(&block.header, &block.transactions, &*self.engine, client, db, parent).ok()?;
+        Some(FraudProof::BadStateTransition {
+            parent: parent.clone(),
+            header: block.header.clone(),
+            transactions: block.transactions.iter().cloned().map(Into::into).collect(),
+            witness: witness.nodes(),
+        })
+    }
+
     /// This is triggered by a message coming from a header queue when the header is ready for insertion
     pub fn import_verified_headers(&self, client: &Client) -> usize {
         const MAX_HEADERS_TO_IMPORT: usize = 1_000;
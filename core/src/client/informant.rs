@@ -0,0 +1,156 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{BlockChainClient, BlockChainTrait, Client, TransactionsExecuted};
+use cmemory::MemoryConsumer;
+use cnetwork::NetworkControl;
+use crossbeam_channel as crossbeam;
+use ctimer::{TimeoutHandler, TimerToken};
+use ctypes::BlockNumber;
+use parking_lot::Mutex;
+use std::fmt;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+/// The number of the executed blocks waiting for the next report. The blocks over it are not counted.
+const EXECUTED_BUFFER: usize = 65_536;
+
+/// Logs a one-line summary of the import progress periodically, instead of a line for each block.
+pub struct Informant {
+    client: Weak<Client>,
+    network: Option<Arc<dyn NetworkControl>>,
+    executed: crossbeam::Receiver<TransactionsExecuted>,
+    last_report: Mutex<Instant>,
+}
+
+impl Informant {
+    pub fn new(client: &Arc<Client>, network: Option<Arc<dyn NetworkControl>>) -> Self {
+        Self {
+            client: Arc::downgrade(client),
+            network,
+            executed: client.events().subscribe(EXECUTED_BUFFER),
+            last_report: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn report(&self) {
+        let client = match self.client.upgrade() {
+            Some(client) => client,
+            None => return,
+        };
+        let elapsed = {
+            let mut last_report = self.last_report.lock();
+            let now = Instant::now();
+            let elapsed = now - *last_report;
+            *last_report = now;
+            elapsed
+        };
+        let (blocks, transactions) = self
+            .executed
+            .try_iter()
+            .fold((0, 0), |(blocks, transactions), executed| (blocks + 1, transactions + executed.invoices.len()));
+        let summary = Summary {
+            best_block_number: client.chain_info().best_block_number,
+            best_header_number: client.best_header().number(),
+            blocks,
+            transactions,
+            elapsed,
+            queued_blocks: client.queue_info().total_queue_size(),
+            memory_usage: client.memory_usage(),
+            peers: self.network.as_ref().and_then(|network| network.get_peer_count().ok()),
+        };
+        cinfo!(CLIENT, "{}", summary);
+    }
+}
+
+impl TimeoutHandler for Informant {
+    fn on_timeout(&self, _token: TimerToken) {
+        self.report();
+    }
+}
+
+struct Summary {
+    best_block_number: BlockNumber,
+    /// The headers are downloaded ahead of the bodies while syncing.
+    best_header_number: BlockNumber,
+    blocks: usize,
+    transactions: usize,
+    elapsed: Duration,
+    queued_blocks: usize,
+    memory_usage: usize,
+    peers: Option<usize>,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.elapsed.as_secs_f64().max(0.001);
+        if self.best_header_number > self.best_block_number {
+            write!(f, "Syncing #{}/{}", self.best_block_number, self.best_header_number)?;
+        } else {
+            write!(f, "Imported #{}", self.best_block_number)?;
+        }
+        write!(
+            f,
+            ", {:.2} blk/s, {:.2} tx/s, {} blocks queued, {} MiB cached",
+            self.blocks as f64 / seconds,
+            self.transactions as f64 / seconds,
+            self.queued_blocks,
+            self.memory_usage / 1024 / 1024
+        )?;
+        if let Some(peers) = self.peers {
+            write!(f, ", {} peers", peers)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_shows_the_sync_progress() {
+        let summary = Summary {
+            best_block_number: 100,
+            best_header_number: 250,
+            blocks: 50,
+            transactions: 125,
+            elapsed: Duration::from_secs(10),
+            queued_blocks: 7,
+            memory_usage: 3 * 1024 * 1024,
+            peers: Some(4),
+        };
+        assert_eq!(
+            "Syncing #100/250, 5.00 blk/s, 12.50 tx/s, 7 blocks queued, 3 MiB cached, 4 peers",
+            summary.to_string()
+        );
+    }
+
+    #[test]
+    fn summary_of_an_idle_node() {
+        let summary = Summary {
+            best_block_number: 100,
+            best_header_number: 100,
+            blocks: 0,
+            transactions: 0,
+            elapsed: Duration::from_secs(30),
+            queued_blocks: 0,
+            memory_usage: 0,
+            peers: None,
+        };
+        assert_eq!("Imported #100, 0.00 blk/s, 0.00 tx/s, 0 blocks queued, 0 MiB cached", summary.to_string());
+    }
+}
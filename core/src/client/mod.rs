@@ -18,7 +18,12 @@ mod chain_notify;
 #[cfg_attr(feature = "cargo-clippy", allow(clippy::module_inception))]
 mod client;
 mod config;
+mod disk_space;
+mod event_bus;
 mod importer;
+mod informant;
+mod prewarm;
+mod root_verifier;
 pub mod snapshot_notify;
 mod test_client;
 
@@ -26,22 +31,32 @@ pub use self::chain_notify::ChainNotify;
 
 pub use self::client::Client;
 pub use self::config::ClientConfig;
+pub use self::disk_space::{free_space, DiskSpaceLevel, DiskSpaceMonitor, DiskSpaceThresholds};
+pub use self::event_bus::{
+    BlocksImported, EventBus, Finalized, PeerEvent, Reorganized, TransactionsExecuted, TransactionsReceived,
+};
+pub use self::informant::Informant;
+pub use self::prewarm::HotAccountsSaver;
+pub use self::root_verifier::{replay_random_block, StateRootVerifier};
 pub use self::test_client::TestBlockChainClient;
 
 use crate::block::{Block, ClosedBlock, OpenBlock, SealedBlock};
 use crate::blockchain_info::BlockChainInfo;
-use crate::consensus::EngineError;
+use crate::consensus::{ClockDrift, Commit, CommitValidator, Duties, EngineError};
 use crate::encoded;
 use crate::error::{BlockImportError, Error as GenericError};
-use crate::miner::MemPoolMinFees;
+use crate::miner::{MemPoolContent, MemPoolMinFees};
 use crate::transaction::{LocalizedTransaction, PendingSignedTransactions, SignedTransaction};
 use crate::types::{BlockId, BlockStatus, TransactionId, VerificationQueueInfo as BlockQueueInfo};
+use crate::verification::FraudProof;
 use cdb::DatabaseError;
 use ckey::{Address, NetworkId, PlatformAddress, Public};
 use cnetwork::NodeId;
-use cstate::{FindActionHandler, StateResult, TopLevelState, TopStateView};
+use cstate::{
+    AccountProof, ActionDataProof, FindActionHandler, ShardProof, StateCheck, StateResult, TopLevelState, TopStateView,
+};
 use ctypes::header::Header;
-use ctypes::transaction::{AssetTransferInput, PartialHashing, ShardTransaction};
+use ctypes::transaction::{Action, AssetTransferInput, PartialHashing, ShardTransaction, Transaction};
 use ctypes::{BlockHash, BlockNumber, CommonParams, ShardId, Tracker, TxHash};
 use cvm::ChainTimeInfo;
 use kvdb::KeyValueDB;
@@ -93,6 +108,8 @@ pub trait EngineInfo: Send + Sync {
     fn mining_reward(&self, block_number: u64) -> Option<u64>;
     fn recommended_confirmation(&self) -> u32;
     fn possible_authors(&self, block_number: Option<u64>) -> Result<Option<Vec<PlatformAddress>>, EngineError>;
+    fn clock_drift(&self) -> Option<ClockDrift>;
+    fn duties(&self, count: usize) -> Option<Duties>;
 }
 
 /// Client facilities used by internally sealing Engines.
@@ -144,6 +161,11 @@ pub trait AccountData {
         )
     }
 
+    /// Get the balance of the address which cannot be spent at the given block.
+    ///
+    /// Returns None if the block's root hash has been pruned from the DB.
+    fn locked_balance(&self, address: &Address, id: BlockId) -> Option<u64>;
+
     fn regular_key(&self, address: &Address, state: StateOrBlock) -> Option<Public>;
     fn latest_regular_key(&self, address: &Address) -> Option<Public> {
         self.regular_key(address, BlockId::Latest.into())
@@ -184,6 +206,9 @@ pub trait Shard {
 
     fn shard_owners(&self, shard_id: ShardId, state: StateOrBlock) -> Option<Vec<Address>>;
     fn shard_users(&self, shard_id: ShardId, state: StateOrBlock) -> Option<Vec<Address>>;
+
+    /// Returns the proof of the value at `key` in the shard against the state root of the block.
+    fn shard_proof(&self, shard_id: ShardId, key: &H256, id: BlockId) -> Option<ShardProof>;
 }
 
 /// Provides methods to import block into blockchain
@@ -226,12 +251,21 @@ pub trait BlockChainClient: Sync + Send + AccountData + BlockChainTrait + Import
     /// Delete all pending transactions.
     fn delete_all_pending_transactions(&self);
 
+    /// Cancel the pending local transaction of the signer with the given seq.
+    fn cancel_pending_transaction(&self, signer: &Address, seq: u64) -> Option<TxHash>;
+
     /// List all transactions that are allowed into the next block.
     fn ready_transactions(&self, range: Range<u64>) -> PendingSignedTransactions;
 
     /// Get the count of all pending transactions currently in the mem_pool.
     fn count_pending_transactions(&self, range: Range<u64>) -> usize;
 
+    /// List the transactions waiting for their activation block.
+    fn scheduled_transactions(&self) -> Vec<SignedTransaction>;
+
+    /// List the pooled transactions signed by the given account.
+    fn pooled_transactions_of(&self, signer: &Address) -> Vec<SignedTransaction>;
+
     /// Check there are transactions which are allowed into the next block.
     fn is_pending_queue_empty(&self) -> bool;
 
@@ -293,6 +327,15 @@ pub trait MiningBlockChainClient: BlockChainClient + BlockProducer + FindActionH
     fn register_immune_users(&self, immune_user_vec: Vec<Address>);
 
     fn mem_pool_min_fees(&self) -> MemPoolMinFees;
+
+    /// The base fee of the action at the best block. The rest of the fee is the tip.
+    fn base_fee(&self, action: &Action) -> u64;
+
+    /// Returns all the transactions in the mem pool with their sources.
+    fn mem_pool_content(&self) -> MemPoolContent;
+
+    /// Returns the number of the transactions that proved invalid for each peer that propagated them.
+    fn invalid_transactions_of_peers(&self) -> Vec<(NodeId, usize)>;
 }
 
 /// Provides methods to access database.
@@ -303,6 +346,15 @@ pub trait DatabaseClient {
 pub trait ExecuteClient: ChainTimeInfo {
     fn execute_transaction(&self, transaction: &ShardTransaction, sender: &Address) -> StateResult<()>;
 
+    /// Executes the transaction on the state of the given block without persisting the result.
+    /// Returns `None` if the state of the block is not available.
+    fn dry_run_transaction(
+        &self,
+        tx: &Transaction,
+        signer_public: &Public,
+        id: BlockId,
+    ) -> Option<TransactionExecution>;
+
     fn execute_vm(
         &self,
         tx: &dyn PartialHashing,
@@ -312,6 +364,25 @@ pub trait ExecuteClient: ChainTimeInfo {
     ) -> Result<Vec<String>, DatabaseError>;
 }
 
+/// The result of executing a transaction without persisting it.
+#[derive(Debug)]
+pub struct TransactionExecution {
+    pub result: Result<(), GenericError>,
+    /// The fee paid by the transaction. A failed transaction pays nothing.
+    pub fee: u64,
+    /// The accounts whose balance or seq is changed by the transaction.
+    pub changed_accounts: Vec<AccountChange>,
+}
+
+#[derive(Debug)]
+pub struct AccountChange {
+    pub address: Address,
+    /// (balance, seq) before the transaction
+    pub before: (u64, u64),
+    /// (balance, seq) after the transaction
+    pub after: (u64, u64),
+}
+
 pub trait StateInfo {
     /// Attempt to get a copy of a specific block's final state.
     ///
@@ -319,8 +390,160 @@ pub trait StateInfo {
     /// Otherwise, this can fail (but may not) if the DB prunes state or the block
     /// is unknown.
     fn state_at(&self, id: BlockId) -> Option<TopLevelState>;
+
+    /// Get the state after the ready transactions in the mem pool are executed on the best block.
+    /// The transactions that fail are skipped as the miner does.
+    fn pending_state(&self) -> Option<TopLevelState>;
 }
 
 pub trait SnapshotClient {
     fn notify_snapshot(&self, id: BlockId);
 }
+
+pub trait SchemeExportClient {
+    /// Returns the scheme sections whose genesis state equals the state at the given block.
+    ///
+    /// The result overrides `genesis` and adds the state chunks to the current scheme,
+    /// so it should be used with `extends` that names the current scheme.
+    /// All the state tries are walked, so it takes long on a large state.
+    fn export_genesis(&self, id: BlockId) -> Option<serde_json::Value>;
+}
+
+/// The result of re-executing a stored block.
+#[derive(Debug)]
+pub struct ReplayedBlock {
+    pub number: BlockNumber,
+    pub hash: BlockHash,
+    /// The state root in the stored header.
+    pub expected_state_root: H256,
+    /// The state root computed by the replay.
+    pub state_root: H256,
+    /// The accounts touched by the block whose balance or seq differs from the stored state.
+    pub divergent_accounts: Vec<DivergentAccount>,
+}
+
+impl ReplayedBlock {
+    pub fn is_diverged(&self) -> bool {
+        self.expected_state_root != self.state_root
+    }
+}
+
+#[derive(Debug)]
+pub struct DivergentAccount {
+    pub address: Address,
+    /// (balance, seq) in the stored state
+    pub expected: (u64, u64),
+    /// (balance, seq) in the replayed state
+    pub replayed: (u64, u64),
+}
+
+/// Lets the operator pause the node for a maintenance window.
+/// The node keeps syncing and serving the queries, but it doesn't author blocks nor accept transactions.
+pub trait MaintenanceClient: Send + Sync {
+    fn is_in_maintenance(&self) -> bool;
+
+    fn set_maintenance_mode(&self, on: bool);
+}
+
+/// Builds branches and switches the canonical chain between them.
+/// Only the solo engine supports it.
+pub trait ForkClient {
+    /// Imports an empty block on top of the given block and returns its hash.
+    /// The block doesn't become the best block unless its parent is the best block.
+    fn create_fork(&self, parent: BlockHash) -> Result<BlockHash, GenericError>;
+
+    /// Makes the given block the best block.
+    /// The transactions only in the retracted blocks are re-imported to the mem pool.
+    fn reorg_to(&self, hash: BlockHash) -> Result<(), GenericError>;
+}
+
+/// Proves the invalid blocks to the nodes which don't execute them.
+pub trait FraudProofClient {
+    /// Returns the proofs of the recently rejected blocks, from the latest one.
+    fn fraud_proofs(&self) -> Vec<FraudProof>;
+
+    /// Keeps the proof and rejects the block if the proof is valid and new.
+    /// Returns true if the proof should be relayed to the other peers.
+    fn import_fraud_proof(&self, proof: FraudProof) -> Result<bool, GenericError>;
+}
+
+pub trait ReplayClient {
+    /// Re-executes the block on the stored state of its parent.
+    /// Returns `None` if the block or the state of its parent doesn't exist.
+    fn replay_block(&self, id: BlockId) -> Result<Option<ReplayedBlock>, GenericError>;
+
+    /// Summarizes the execution of each transaction in the block. The summary is cached.
+    /// Returns `None` if the block or the state of its parent doesn't exist.
+    fn block_execution_summary(&self, id: BlockId) -> Result<Option<Arc<BlockExecutionSummary>>, GenericError>;
+
+    /// Re-executes the block on the stored state of its parent and returns the trie nodes it read.
+    /// Returns `None` if the block or the state of its parent doesn't exist.
+    fn block_witness(&self, id: BlockId) -> Result<Option<Vec<Bytes>>, GenericError>;
+
+    /// Re-executes the block with only the trie nodes of the witness and returns the resulting state root.
+    /// It fails if the witness lacks a node that the execution reads.
+    /// Returns `None` if the block doesn't exist.
+    fn execute_with_witness(&self, id: BlockId, witness: &[Bytes]) -> Result<Option<H256>, GenericError>;
+}
+
+#[derive(Debug)]
+pub struct BlockExecutionSummary {
+    pub number: BlockNumber,
+    pub hash: BlockHash,
+    pub transactions: Vec<TransactionSummary>,
+}
+
+#[derive(Debug)]
+pub struct TransactionSummary {
+    pub hash: TxHash,
+    pub action: Action,
+    /// The reason of the failure
+    pub error: Option<String>,
+    /// The fee paid by the transaction. A failed transaction pays nothing.
+    pub fee: u64,
+    /// The accounts written by the transaction, including the fee payer.
+    pub touched_accounts: Vec<Address>,
+}
+
+#[derive(Debug)]
+pub enum StateModification {
+    Balance {
+        address: Address,
+        balance: u64,
+    },
+    Seq {
+        address: Address,
+        seq: u64,
+    },
+    /// Sets the action data under the key. An empty value removes it.
+    ActionData {
+        key: H256,
+        value: Bytes,
+    },
+}
+
+/// Inspects and edits the stored state of a stopped node.
+pub trait StateSurgeryClient {
+    /// Applies the modifications to the state of the given block and stores the block again with the new state root.
+    /// The rewritten block becomes the best block, which drops the original block and its descendants
+    /// from the canonical chain. Returns the hash of the rewritten block.
+    fn rewrite_state(&self, id: BlockId, modifications: &[StateModification]) -> Result<BlockHash, GenericError>;
+
+    /// Walks the whole state of the given block, looking for missing or corrupted trie nodes and inconsistent items.
+    fn check_state(&self, id: BlockId) -> Option<StateCheck>;
+}
+
+/// Provides what a light client of this chain on a counterparty chain needs to follow it.
+pub trait LightClientSupport {
+    /// Returns the commit finalizing the block, which is known once its child is imported.
+    fn commit(&self, id: BlockId) -> Option<Commit>;
+
+    /// Returns the validators signing the commit of the block.
+    fn commit_validators(&self, id: BlockId) -> Option<Vec<CommitValidator>>;
+
+    /// Returns the proof of the action data at `key` against the state root of the block.
+    fn action_data_proof(&self, key: &H256, id: BlockId) -> Option<ActionDataProof>;
+
+    /// Returns the proof of the account against the state root of the block.
+    fn account_proof(&self, address: &Address, id: BlockId) -> Option<AccountProof>;
+}
@@ -0,0 +1,92 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Keeps the addresses of the accounts in the state cache in the database, so the cache is warmed up again from them
+//! after a restart instead of making the first blocks read every account from the disk.
+
+use super::Client;
+use crate::db;
+use ckey::Address;
+use ctimer::{TimeoutHandler, TimerToken};
+use kvdb::{DBTransaction, KeyValueDB};
+use rlp::Rlp;
+use std::sync::Weak;
+
+const HOT_ACCOUNTS_KEY: &[u8] = b"hot-accounts";
+
+pub fn save_hot_accounts(db: &dyn KeyValueDB, addresses: &[Address]) {
+    let mut batch = DBTransaction::new();
+    batch.put(db::COL_EXTRA, HOT_ACCOUNTS_KEY, &rlp::encode_list(addresses));
+    db.write(batch).expect("Low level database error. Some issue with disk?");
+}
+
+/// The addresses saved by `save_hot_accounts`. It's empty if nothing is saved or the saved value is broken.
+pub fn load_hot_accounts(db: &dyn KeyValueDB) -> Vec<Address> {
+    let value = db.get(db::COL_EXTRA, HOT_ACCOUNTS_KEY).expect("Low level database error. Some issue with disk?");
+    let value = match value {
+        Some(value) => value,
+        None => return Vec::new(),
+    };
+    Rlp::new(&value).as_list().unwrap_or_else(|err| {
+        cwarn!(CLIENT, "Cannot decode the hot accounts: {:?}", err);
+        Vec::new()
+    })
+}
+
+/// Saves the hot accounts of the client periodically, so a crash doesn't lose them.
+pub struct HotAccountsSaver {
+    client: Weak<Client>,
+}
+
+impl HotAccountsSaver {
+    pub fn new(client: Weak<Client>) -> Self {
+        Self {
+            client,
+        }
+    }
+}
+
+impl TimeoutHandler for HotAccountsSaver {
+    fn on_timeout(&self, _token: TimerToken) {
+        if let Some(client) = self.client.upgrade() {
+            client.save_hot_accounts();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hot_accounts_are_saved_and_loaded() {
+        let db = kvdb_memorydb::create(db::NUM_COLUMNS.unwrap_or(0));
+        assert_eq!(Vec::<Address>::new(), load_hot_accounts(&db));
+
+        let addresses = vec![Address::random(), Address::random()];
+        save_hot_accounts(&db, &addresses);
+        assert_eq!(addresses, load_hot_accounts(&db));
+    }
+
+    #[test]
+    fn broken_hot_accounts_are_ignored() {
+        let db = kvdb_memorydb::create(db::NUM_COLUMNS.unwrap_or(0));
+        let mut batch = DBTransaction::new();
+        batch.put(db::COL_EXTRA, HOT_ACCOUNTS_KEY, &[0x01, 0x02]);
+        db.write(batch).unwrap();
+        assert_eq!(Vec::<Address>::new(), load_hot_accounts(&db));
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{BlockChainTrait, Client, ReplayClient, ReplayedBlock};
+use crate::error::Error;
+use crate::types::BlockId;
+use ctimer::{TimeoutHandler, TimerToken};
+use ctypes::BlockNumber;
+use rand::{thread_rng, Rng};
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Weak;
+
+/// How many blocks are drawn before giving up on finding one whose parent state is kept.
+const MAX_DRAWS: usize = 16;
+
+/// Replays a block drawn uniformly from `range`. The blocks whose parent state is pruned are redrawn.
+/// Returns `None` if no drawn block could be replayed.
+pub fn replay_random_block<C: ReplayClient + ?Sized>(
+    client: &C,
+    range: RangeInclusive<BlockNumber>,
+) -> Result<Option<ReplayedBlock>, Error> {
+    // The genesis block has no parent to replay it on.
+    let (from, to) = ((*range.start()).max(1), *range.end());
+    if from > to {
+        return Ok(None)
+    }
+    let mut rng = thread_rng();
+    for _ in 0..MAX_DRAWS {
+        let number = rng.gen_range(from, to + 1);
+        if let Some(replayed) = client.replay_block(BlockId::Number(number))? {
+            return Ok(Some(replayed))
+        }
+    }
+    Ok(None)
+}
+
+/// Re-executes a random historical block periodically, and reports it if the recomputed state root differs from the
+/// stored header. A mismatch means that the database is corrupted or that the state machine isn't deterministic.
+pub struct StateRootVerifier {
+    client: Weak<Client>,
+    verified: AtomicUsize,
+    mismatches: AtomicUsize,
+}
+
+impl StateRootVerifier {
+    pub fn new(client: Weak<Client>) -> Self {
+        Self {
+            client,
+            verified: AtomicUsize::new(0),
+            mismatches: AtomicUsize::new(0),
+        }
+    }
+
+    /// The number of blocks verified and the number of them whose state root mismatched.
+    pub fn counts(&self) -> (usize, usize) {
+        (self.verified.load(Ordering::SeqCst), self.mismatches.load(Ordering::SeqCst))
+    }
+
+    pub fn verify(&self) {
+        let client = match self.client.upgrade() {
+            Some(client) => client,
+            None => return,
+        };
+        let best_block_number = client.chain_info().best_block_number;
+        let replayed = match replay_random_block(&*client, 1..=best_block_number) {
+            Ok(Some(replayed)) => replayed,
+            Ok(None) => {
+                cdebug!(CLIENT, "No historical block with its parent state was found to verify");
+                return
+            }
+            Err(err) => {
+                cwarn!(CLIENT, "Cannot replay a historical block: {}", err);
+                return
+            }
+        };
+        self.verified.fetch_add(1, Ordering::SeqCst);
+        if !replayed.is_diverged() {
+            ctrace!(CLIENT, "The state root of block #{} {} is verified", replayed.number, replayed.hash);
+            return
+        }
+
+        self.mismatches.fetch_add(1, Ordering::SeqCst);
+        cerror!(
+            CLIENT,
+            "The state root of block #{} {} mismatches: the header has {} but the replay computed {}",
+            replayed.number,
+            replayed.hash,
+            replayed.expected_state_root,
+            replayed.state_root
+        );
+        for account in &replayed.divergent_accounts {
+            cerror!(
+                CLIENT,
+                "  {}: stored (balance: {}, seq: {}) but replayed (balance: {}, seq: {})",
+                account.address,
+                account.expected.0,
+                account.expected.1,
+                account.replayed.0,
+                account.replayed.1
+            );
+        }
+    }
+}
+
+impl TimeoutHandler for StateRootVerifier {
+    fn on_timeout(&self, _token: TimerToken) {
+        self.verify();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::BlockExecutionSummary;
+    use ctypes::BlockHash;
+    use primitives::{Bytes, H256};
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    /// Replays only the given blocks, as if the states of the others were pruned.
+    struct Replayable(HashSet<BlockNumber>);
+
+    impl ReplayClient for Replayable {
+        fn replay_block(&self, id: BlockId) -> Result<Option<ReplayedBlock>, Error> {
+            let number = match id {
+                BlockId::Number(number) => number,
+                _ => unreachable!(),
+            };
+            assert_ne!(0, number, "The genesis block is never replayed");
+            Ok(self.0.get(&number).map(|&number| ReplayedBlock {
+                number,
+                hash: BlockHash::default(),
+                expected_state_root: H256::default(),
+                state_root: H256::default(),
+                divergent_accounts: Vec::new(),
+            }))
+        }
+
+        fn block_execution_summary(&self, _id: BlockId) -> Result<Option<Arc<BlockExecutionSummary>>, Error> {
+            unimplemented!()
+        }
+
+        fn block_witness(&self, _id: BlockId) -> Result<Option<Vec<Bytes>>, Error> {
+            unimplemented!()
+        }
+
+        fn execute_with_witness(&self, _id: BlockId, _witness: &[Bytes]) -> Result<Option<H256>, Error> {
+            unimplemented!()
+        }
+    }
+
+    #[test]
+    fn draws_blocks_in_the_range() {
+        let client = Replayable((1..=10).collect());
+        for _ in 0..100 {
+            let replayed = replay_random_block(&client, 3..=5).unwrap().unwrap();
+            assert!((3..=5).contains(&replayed.number));
+        }
+    }
+
+    #[test]
+    fn genesis_block_is_skipped() {
+        let client = Replayable(vec![1].into_iter().collect());
+        assert_eq!(1, replay_random_block(&client, 0..=1).unwrap().unwrap().number);
+        assert!(replay_random_block(&client, 0..=0).unwrap().is_none());
+    }
+
+    #[test]
+    fn gives_up_if_no_state_is_kept() {
+        let client = Replayable(HashSet::new());
+        assert!(replay_random_block(&client, 1..=1000).unwrap().is_none());
+    }
+}
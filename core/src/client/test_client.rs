@@ -36,12 +36,13 @@ use crate::client::{
     AccountData, BlockChainClient, BlockChainTrait, BlockProducer, BlockStatus, ConsensusClient, EngineInfo,
     ImportBlock, ImportResult, MiningBlockChainClient, StateInfo, StateOrBlock, TermInfo,
 };
+use crate::codechain_machine::CodeChainMachine;
 use crate::consensus::stake::{NextValidators, Validator};
-use crate::consensus::EngineError;
+use crate::consensus::{ClockDrift, Duties, EngineError};
 use crate::db::{COL_STATE, NUM_COLUMNS};
 use crate::encoded;
 use crate::error::{BlockImportError, Error as GenericError};
-use crate::miner::{MemPoolMinFees, Miner, MinerService, TransactionImportResult};
+use crate::miner::{MemPoolContent, MemPoolMinFees, Miner, MinerService, TransactionImportResult, TxSource};
 use crate::scheme::Scheme;
 use crate::transaction::{LocalizedTransaction, PendingSignedTransactions, SignedTransaction};
 use crate::types::{BlockId, TransactionId, VerificationQueueInfo as QueueInfo};
@@ -297,7 +298,7 @@ impl TestBlockChainClient {
         let sender_address = public_to_address(&signed.signer_public());
         self.set_balance(sender_address, 10_000_000_000_000_000_000);
         let hash = signed.hash();
-        let res = self.miner.import_external_transactions(self, vec![signed.into()]);
+        let res = self.miner.import_external_transactions(self, vec![signed.into()], TxSource::Unknown);
         let res = res.into_iter().next().unwrap().expect("Successful import");
         assert_eq!(res, TransactionImportResult::Current);
         hash
@@ -377,6 +378,18 @@ impl MiningBlockChainClient for TestBlockChainClient {
     fn mem_pool_min_fees(&self) -> MemPoolMinFees {
         self.miner.get_options().mem_pool_min_fees
     }
+
+    fn base_fee(&self, action: &Action) -> u64 {
+        CodeChainMachine::min_cost(self.scheme.engine.machine().genesis_common_params(), action)
+    }
+
+    fn mem_pool_content(&self) -> MemPoolContent {
+        self.miner.mem_pool_content()
+    }
+
+    fn invalid_transactions_of_peers(&self) -> Vec<(NodeId, usize)> {
+        self.miner.invalid_transactions_of_peers()
+    }
 }
 
 impl AccountData for TestBlockChainClient {
@@ -396,6 +409,13 @@ impl AccountData for TestBlockChainClient {
         }
     }
 
+    fn locked_balance(&self, _address: &Address, id: BlockId) -> Option<u64> {
+        match id {
+            BlockId::Latest => Some(0),
+            _ => None,
+        }
+    }
+
     fn regular_key(&self, _address: &Address, _state: StateOrBlock) -> Option<Public> {
         None
     }
@@ -536,16 +556,20 @@ impl BlockChainClient for TestBlockChainClient {
         Ok(())
     }
 
-    fn queue_transactions(&self, transactions: Vec<Bytes>, _peer_id: NodeId) {
+    fn queue_transactions(&self, transactions: Vec<Bytes>, peer_id: NodeId) {
         // import right here
         let transactions = transactions.into_iter().filter_map(|bytes| Rlp::new(&bytes).as_val().ok()).collect();
-        self.miner.import_external_transactions(self, transactions);
+        self.miner.import_external_transactions(self, transactions, TxSource::Peer(peer_id));
     }
 
     fn delete_all_pending_transactions(&self) {
         self.miner.delete_all_pending_transactions();
     }
 
+    fn cancel_pending_transaction(&self, signer: &Address, seq: u64) -> Option<TxHash> {
+        self.miner.cancel_transaction(self, signer, seq)
+    }
+
     fn ready_transactions(&self, range: Range<u64>) -> PendingSignedTransactions {
         self.miner.ready_transactions(range)
     }
@@ -554,6 +578,14 @@ impl BlockChainClient for TestBlockChainClient {
         self.miner.count_pending_transactions(range)
     }
 
+    fn scheduled_transactions(&self) -> Vec<SignedTransaction> {
+        self.miner.scheduled_transactions()
+    }
+
+    fn pooled_transactions_of(&self, signer: &Address) -> Vec<SignedTransaction> {
+        self.miner.transactions_of(signer)
+    }
+
     fn is_pending_queue_empty(&self) -> bool {
         self.miner.status().transactions_in_pending_queue == 0
     }
@@ -664,6 +696,14 @@ impl EngineInfo for TestBlockChainClient {
     fn possible_authors(&self, _block_number: Option<u64>) -> Result<Option<Vec<PlatformAddress>>, EngineError> {
         unimplemented!()
     }
+
+    fn clock_drift(&self) -> Option<ClockDrift> {
+        None
+    }
+
+    fn duties(&self, _count: usize) -> Option<Duties> {
+        None
+    }
 }
 
 impl ConsensusClient for TestBlockChainClient {}
@@ -690,4 +730,8 @@ impl StateInfo for TestBlockChainClient {
 
         Some(top_state)
     }
+
+    fn pending_state(&self) -> Option<TopLevelState> {
+        self.state_at(BlockId::Latest)
+    }
 }
@@ -17,22 +17,29 @@
 
 use crate::block::{ExecutedBlock, IsBlock};
 use crate::client::BlockChainTrait;
+use crate::consensus::stake;
 use crate::error::Error;
-use crate::transaction::{SignedTransaction, UnverifiedTransaction};
-use ckey::Address;
+use crate::transaction::{recover_publics, SignedTransaction, UnverifiedTransaction};
+use ckey::{Address, Public};
 use cstate::{StateError, TopState, TopStateView};
 use ctypes::errors::SyntaxError;
 use ctypes::transaction::Action;
-use ctypes::{CommonParams, Header};
+use ctypes::{
+    BlockNumber, CommonParams, ForkSchedule, Header, BATCH_FORK, BURN_SEQ_FORK, SCHEDULED_ACTION_FORK,
+    TERMINATE_SHARD_FORK,
+};
+use rlp::{Decodable, Rlp};
 
 pub struct CodeChainMachine {
     params: CommonParams,
+    fork_schedule: ForkSchedule,
 }
 
 impl CodeChainMachine {
-    pub fn new(params: CommonParams) -> Self {
+    pub fn new(params: CommonParams, fork_schedule: ForkSchedule) -> Self {
         CodeChainMachine {
             params,
+            fork_schedule,
         }
     }
 
@@ -41,6 +48,16 @@ impl CodeChainMachine {
         &self.params
     }
 
+    /// Get the activation schedule of the protocol upgrades.
+    pub fn fork_schedule(&self) -> &ForkSchedule {
+        &self.fork_schedule
+    }
+
+    /// Returns true if the given protocol upgrade is active at the given block.
+    pub fn is_fork_active(&self, name: &str, block_number: BlockNumber) -> bool {
+        self.fork_schedule.is_active(name, block_number)
+    }
+
     /// Does basic verification of the transaction.
     pub fn verify_transaction_with_params(
         &self,
@@ -66,15 +83,76 @@ impl CodeChainMachine {
         Ok(SignedTransaction::try_new(p)?)
     }
 
+    /// Verify the seal of a transaction whose signer public was recovered in advance.
+    pub fn verify_recovered_transaction_seal(
+        p: UnverifiedTransaction,
+        signer_public: Public,
+        _header: &Header,
+    ) -> Result<SignedTransaction, Error> {
+        p.check_low_s()?;
+        Ok(SignedTransaction::from_recovered(p, signer_public)?)
+    }
+
+    /// Verify the seals of the transactions in a block. The signatures are recovered in parallel.
+    pub fn verify_transactions_seal(
+        txs: Vec<UnverifiedTransaction>,
+        header: &Header,
+    ) -> Result<Vec<SignedTransaction>, Error> {
+        let signer_publics = recover_publics(&txs);
+        txs.into_iter()
+            .zip(signer_publics)
+            .map(|(tx, signer_public)| Self::verify_recovered_transaction_seal(tx, signer_public?, header))
+            .collect()
+    }
+
     /// Does verification of the transaction against the parent state.
     pub fn verify_transaction<C: BlockChainTrait>(
         &self,
-        _tx: &SignedTransaction,
-        _header: &Header,
+        tx: &SignedTransaction,
+        header: &Header,
         _client: &C,
         _verify_timelock: bool,
     ) -> Result<(), Error> {
-        // FIXME: Filter transactions.
+        self.verify_action_activated(&tx.action, header.number())
+    }
+
+    /// Rejects the actions whose fork is not active at the given block.
+    fn verify_action_activated(&self, action: &Action, block_number: BlockNumber) -> Result<(), Error> {
+        let fork = match action {
+            Action::Batch {
+                actions,
+            } => {
+                for action in actions {
+                    self.verify_action_activated(action, block_number)?;
+                }
+                BATCH_FORK
+            }
+            Action::Scheduled {
+                action,
+                ..
+            } => {
+                self.verify_action_activated(action, block_number)?;
+                SCHEDULED_ACTION_FORK
+            }
+            Action::BurnSeq => BURN_SEQ_FORK,
+            Action::TerminateShard {
+                ..
+            } => TERMINATE_SHARD_FORK,
+            Action::Custom {
+                handler_id,
+                bytes,
+            } if *handler_id == stake::CUSTOM_ACTION_HANDLER_ID => {
+                // The malformed actions are rejected by the verification of the handler.
+                match stake::Action::decode(&Rlp::new(bytes)).ok().and_then(|action| action.fork()) {
+                    Some(fork) => fork,
+                    None => return Ok(()),
+                }
+            }
+            _ => return Ok(()),
+        };
+        if !self.is_fork_active(fork, block_number) {
+            return Err(SyntaxError::InactiveAction(fork.to_string()).into())
+        }
         Ok(())
     }
 
@@ -88,7 +166,8 @@ impl CodeChainMachine {
         match action {
             Action::Pay {
                 ..
-            } => params.min_pay_transaction_cost(),
+            }
+            | Action::BurnSeq => params.min_pay_transaction_cost(),
             Action::SetRegularKey {
                 ..
             } => params.min_set_regular_key_transaction_cost(),
@@ -97,6 +176,9 @@ impl CodeChainMachine {
             } => params.min_create_shard_transaction_cost(),
             Action::SetShardOwners {
                 ..
+            }
+            | Action::TerminateShard {
+                ..
             } => params.min_set_shard_owners_transaction_cost(),
             Action::SetShardUsers {
                 ..
@@ -110,6 +192,13 @@ impl CodeChainMachine {
                 // FIXME
                 0
             }
+            Action::Batch {
+                actions,
+            } => actions.iter().map(|action| Self::min_cost(params, action)).sum(),
+            Action::Scheduled {
+                action,
+                ..
+            } => Self::min_cost(params, action),
         }
     }
 
@@ -127,3 +216,99 @@ impl CodeChainMachine {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::TestBlockChainClient;
+    use crate::scheme::Scheme;
+    use ckey::{Generator, NetworkId, Random};
+    use ctypes::transaction::Transaction;
+    use ctypes::COMMISSION_FORK;
+    use rlp::Encodable;
+
+    fn verify(scheme: &Scheme, action: Action, block_number: BlockNumber) -> Result<(), Error> {
+        let tx = Transaction {
+            seq: 0,
+            fee: 10,
+            network_id: NetworkId::default(),
+            action,
+        };
+        let signed = SignedTransaction::new_with_sign(tx, Random.generate().unwrap().private());
+        let mut header = Header::default();
+        header.set_number(block_number);
+        scheme.engine.machine().verify_transaction(&signed, &header, &TestBlockChainClient::new(), true)
+    }
+
+    fn batch() -> Action {
+        Action::Batch {
+            actions: vec![Action::BurnSeq],
+        }
+    }
+
+    #[test]
+    fn action_is_accepted_from_the_activation_of_its_fork() {
+        let s = r#"{
+            "extends": "null",
+            "forks": {
+                "batch": 5
+            }
+        }"#;
+        let scheme = Scheme::load(s.as_bytes()).unwrap();
+        match verify(&scheme, batch(), 4) {
+            Err(Error::Syntax(SyntaxError::InactiveAction(fork))) => assert_eq!(BATCH_FORK, fork),
+            result => panic!("Unexpected result {:?}", result),
+        }
+        verify(&scheme, batch(), 5).unwrap();
+        verify(&scheme, Action::BurnSeq, 4).unwrap();
+    }
+
+    #[test]
+    fn action_inside_a_batch_is_rejected_before_its_fork() {
+        let s = r#"{
+            "extends": "null",
+            "forks": {
+                "burnSeq": null
+            }
+        }"#;
+        let scheme = Scheme::load(s.as_bytes()).unwrap();
+        match verify(&scheme, batch(), 100) {
+            Err(Error::Syntax(SyntaxError::InactiveAction(fork))) => assert_eq!(BURN_SEQ_FORK, fork),
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn stake_action_is_accepted_from_the_activation_of_its_fork() {
+        let s = r#"{
+            "extends": "null",
+            "forks": {
+                "commission": 10
+            }
+        }"#;
+        let scheme = Scheme::load(s.as_bytes()).unwrap();
+        let set_commission = Action::Custom {
+            handler_id: stake::CUSTOM_ACTION_HANDLER_ID,
+            bytes: stake::Action::SetCommission {
+                rate: 10,
+                destination: Address::default(),
+            }
+            .rlp_bytes(),
+        };
+        match verify(&scheme, set_commission.clone(), 9) {
+            Err(Error::Syntax(SyntaxError::InactiveAction(fork))) => assert_eq!(COMMISSION_FORK, fork),
+            result => panic!("Unexpected result {:?}", result),
+        }
+        verify(&scheme, set_commission, 10).unwrap();
+
+        let transfer = Action::Custom {
+            handler_id: stake::CUSTOM_ACTION_HANDLER_ID,
+            bytes: stake::Action::TransferCCS {
+                address: Address::default(),
+                quantity: 1,
+            }
+            .rlp_bytes(),
+        };
+        verify(&scheme, transfer, 0).unwrap();
+    }
+}
@@ -0,0 +1,154 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::light_client::{validator_set_hash, CommitValidator};
+use ccrypto::blake256;
+use ckey::{verify_schnorr, Public, SchnorrSignature};
+use ctypes::{BlockHash, BlockNumber, Header};
+use primitives::H256;
+
+/// A finalized block and its state, which the validators sign periodically.
+/// The external systems trusting the validator set can anchor on it without running a node.
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct Checkpoint {
+    pub number: BlockNumber,
+    pub block_hash: BlockHash,
+    pub state_root: H256,
+    /// The hash of the validators finalizing the block. See `validator_set_hash`.
+    pub validator_set_hash: H256,
+}
+
+impl Checkpoint {
+    pub fn new(header: &Header, validators: &[CommitValidator]) -> Self {
+        Self {
+            number: header.number(),
+            block_hash: header.hash(),
+            state_root: *header.state_root(),
+            validator_set_hash: validator_set_hash(validators),
+        }
+    }
+
+    /// The message signed by the validators.
+    pub fn hash(&self) -> H256 {
+        blake256(rlp::encode(self))
+    }
+}
+
+/// A signature of a validator on a checkpoint, which is gossiped to the other nodes.
+#[derive(Clone, Debug, PartialEq, RlpEncodable, RlpDecodable)]
+pub struct CheckpointSignature {
+    pub checkpoint: Checkpoint,
+    pub signer: Public,
+    pub signature: SchnorrSignature,
+}
+
+/// A checkpoint with the signatures collected from its validators.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignedCheckpoint {
+    pub checkpoint: Checkpoint,
+    pub validators: Vec<CommitValidator>,
+    /// Signatures on the checkpoint, indexed by the position of the signer in the validator set.
+    pub signatures: Vec<(usize, SchnorrSignature)>,
+}
+
+impl SignedCheckpoint {
+    pub fn new(checkpoint: Checkpoint, validators: Vec<CommitValidator>) -> Self {
+        debug_assert_eq!(checkpoint.validator_set_hash, validator_set_hash(&validators));
+        Self {
+            checkpoint,
+            validators,
+            signatures: Vec::new(),
+        }
+    }
+
+    /// Adds the signature if the signer is a validator which hasn't signed yet and the signature is valid.
+    pub fn add_signature(&mut self, signer: &Public, signature: SchnorrSignature) -> bool {
+        let index = match self.validators.iter().position(|validator| validator.pubkey == *signer) {
+            Some(index) => index,
+            None => return false,
+        };
+        if self.signatures.iter().any(|(signed, _)| *signed == index) {
+            return false
+        }
+        if !verify_schnorr(signer, &signature, &self.checkpoint.hash()).unwrap_or(false) {
+            return false
+        }
+        self.signatures.push((index, signature));
+        self.signatures.sort_unstable_by_key(|(index, _)| *index);
+        true
+    }
+
+    /// The voting power of the validators which signed the checkpoint.
+    pub fn voting_power(&self) -> u64 {
+        self.signatures.iter().map(|(index, _)| self.validators[*index].voting_power).sum()
+    }
+
+    pub fn total_voting_power(&self) -> u64 {
+        self.validators.iter().map(|validator| validator.voting_power).sum()
+    }
+
+    /// Whether more than 2/3 of the voting power signed the checkpoint.
+    pub fn is_final(&self) -> bool {
+        self.voting_power() * 3 > self.total_voting_power() * 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckey::{sign_schnorr, Generator, KeyPair, Random};
+
+    fn signed_checkpoint(n: usize) -> (Vec<KeyPair>, SignedCheckpoint) {
+        let key_pairs: Vec<KeyPair> = (0..n).map(|_| Random.generate().unwrap()).collect();
+        let validators: Vec<_> = key_pairs
+            .iter()
+            .map(|key_pair| CommitValidator {
+                pubkey: *key_pair.public(),
+                voting_power: 1,
+            })
+            .collect();
+        let checkpoint = Checkpoint::new(&Header::default(), &validators);
+        (key_pairs, SignedCheckpoint::new(checkpoint, validators))
+    }
+
+    #[test]
+    fn checkpoint_is_final_with_the_signatures_of_two_thirds() {
+        let (key_pairs, mut signed) = signed_checkpoint(4);
+        let message = signed.checkpoint.hash();
+        for key_pair in &key_pairs[1..3] {
+            assert!(signed.add_signature(key_pair.public(), sign_schnorr(key_pair.private(), &message).unwrap()));
+        }
+        assert!(!signed.is_final());
+
+        assert!(signed.add_signature(key_pairs[0].public(), sign_schnorr(key_pairs[0].private(), &message).unwrap()));
+        assert!(signed.is_final());
+        assert_eq!(vec![0, 1, 2], signed.signatures.iter().map(|(index, _)| *index).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn invalid_or_duplicated_signatures_are_rejected() {
+        let (key_pairs, mut signed) = signed_checkpoint(4);
+        let message = signed.checkpoint.hash();
+        let stranger = Random.generate().unwrap();
+        assert!(!signed.add_signature(stranger.public(), sign_schnorr(stranger.private(), &message).unwrap()));
+        assert!(!signed.add_signature(key_pairs[0].public(), sign_schnorr(key_pairs[1].private(), &message).unwrap()));
+
+        let signature = sign_schnorr(key_pairs[0].private(), &message).unwrap();
+        assert!(signed.add_signature(key_pairs[0].public(), signature));
+        assert!(!signed.add_signature(key_pairs[0].public(), signature));
+        assert_eq!(1, signed.voting_power());
+    }
+}
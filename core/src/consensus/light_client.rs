@@ -0,0 +1,296 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::tendermint::{Step, View, VoteOn, VoteStep};
+use ccrypto::blake256;
+use ckey::{verify_schnorr, Public, SchnorrSignature};
+use ctypes::{BlockHash, BlockNumber, Header};
+use primitives::H256;
+use std::collections::HashSet;
+use std::fmt;
+
+/// A validator which signs the precommits finalizing a block.
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct CommitValidator {
+    pub pubkey: Public,
+    pub voting_power: u64,
+}
+
+/// The hash committing to the validator set, which is the blake256 of the RLP encoded list.
+pub fn validator_set_hash(validators: &[CommitValidator]) -> H256 {
+    blake256(rlp::encode_list(validators))
+}
+
+/// The precommits finalizing a header, taken from the seal of its child.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Commit {
+    pub header: Header,
+    /// The view at which the header is finalized.
+    pub view: View,
+    /// Signatures on the precommit, indexed by the position of the signer in the validator set.
+    pub signatures: Vec<(usize, SchnorrSignature)>,
+}
+
+impl Commit {
+    /// The message signed by the validators.
+    pub fn vote_on(&self) -> VoteOn {
+        VoteOn {
+            step: VoteStep::new(self.header.number(), self.view, Step::Precommit),
+            block_hash: Some(self.header.hash()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LightClientError {
+    UnknownValidator(usize),
+    DuplicatedSigner(usize),
+    InvalidSignature(usize),
+    NotEnoughVotingPower {
+        voted: u64,
+        total: u64,
+    },
+    NotSuccessor {
+        trusted: BlockHash,
+        parent: BlockHash,
+    },
+    ValidatorSetMismatch {
+        expected: H256,
+        found: H256,
+    },
+}
+
+impl fmt::Display for LightClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LightClientError::UnknownValidator(index) => write!(f, "There is no validator at {}", index),
+            LightClientError::DuplicatedSigner(index) => write!(f, "The validator at {} signed twice", index),
+            LightClientError::InvalidSignature(index) => {
+                write!(f, "The signature of the validator at {} is invalid", index)
+            }
+            LightClientError::NotEnoughVotingPower {
+                voted,
+                total,
+            } => write!(f, "Only {} of {} voting power signed the commit", voted, total),
+            LightClientError::NotSuccessor {
+                trusted,
+                parent,
+            } => write!(f, "The parent {} is not the trusted header {}", parent, trusted),
+            LightClientError::ValidatorSetMismatch {
+                expected,
+                found,
+            } => write!(f, "The validator set hash is {} but {} is expected", found, expected),
+        }
+    }
+}
+
+/// Checks that more than 2/3 of the voting power of the validators signed the commit.
+pub fn verify_commit(commit: &Commit, validators: &[CommitValidator]) -> Result<(), LightClientError> {
+    let message = commit.vote_on().hash();
+    let mut signers = HashSet::new();
+    let mut voted = 0u64;
+    for (index, signature) in &commit.signatures {
+        let validator = validators.get(*index).ok_or(LightClientError::UnknownValidator(*index))?;
+        if !signers.insert(*index) {
+            return Err(LightClientError::DuplicatedSigner(*index))
+        }
+        if !verify_schnorr(&validator.pubkey, signature, &message).unwrap_or(false) {
+            return Err(LightClientError::InvalidSignature(*index))
+        }
+        voted += validator.voting_power;
+    }
+
+    let total: u64 = validators.iter().map(|validator| validator.voting_power).sum();
+    if voted * 3 > total * 2 {
+        Ok(())
+    } else {
+        Err(LightClientError::NotEnoughVotingPower {
+            voted,
+            total,
+        })
+    }
+}
+
+/// Follows the headers of a chain from a trusted header, as a light client on the counterparty chain does.
+///
+/// The validator set of the next header is given by the relayer and checked against the hash which is trusted
+/// by the previous update, so the validator sets must be proven out of band when they change.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LightClient {
+    number: BlockNumber,
+    hash: BlockHash,
+    state_root: H256,
+    validator_set_hash: H256,
+}
+
+impl LightClient {
+    /// Trusts the header and the hash of the validator set which signs its child.
+    pub fn new(header: &Header, validator_set_hash: H256) -> Self {
+        Self {
+            number: header.number(),
+            hash: header.hash(),
+            state_root: *header.state_root(),
+            validator_set_hash,
+        }
+    }
+
+    pub fn number(&self) -> BlockNumber {
+        self.number
+    }
+
+    pub fn hash(&self) -> BlockHash {
+        self.hash
+    }
+
+    /// The state root against which the commitment proofs are verified.
+    pub fn state_root(&self) -> H256 {
+        self.state_root
+    }
+
+    pub fn validator_set_hash(&self) -> H256 {
+        self.validator_set_hash
+    }
+
+    /// Moves to the child of the trusted header if the commit is signed by the trusted validator set.
+    pub fn update(
+        &mut self,
+        commit: &Commit,
+        validators: &[CommitValidator],
+        next_validator_set_hash: H256,
+    ) -> Result<(), LightClientError> {
+        if *commit.header.parent_hash() != self.hash {
+            return Err(LightClientError::NotSuccessor {
+                trusted: self.hash,
+                parent: *commit.header.parent_hash(),
+            })
+        }
+        let found = validator_set_hash(validators);
+        if found != self.validator_set_hash {
+            return Err(LightClientError::ValidatorSetMismatch {
+                expected: self.validator_set_hash,
+                found,
+            })
+        }
+        verify_commit(commit, validators)?;
+
+        *self = Self::new(&commit.header, next_validator_set_hash);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckey::{sign_schnorr, Generator, KeyPair, Random};
+
+    fn validators(n: usize) -> (Vec<KeyPair>, Vec<CommitValidator>) {
+        let key_pairs: Vec<KeyPair> = (0..n).map(|_| Random.generate().unwrap()).collect();
+        let validators = key_pairs
+            .iter()
+            .map(|key_pair| CommitValidator {
+                pubkey: *key_pair.public(),
+                voting_power: 1,
+            })
+            .collect();
+        (key_pairs, validators)
+    }
+
+    fn commit(header: Header, signers: &[(usize, &KeyPair)]) -> Commit {
+        let mut commit = Commit {
+            header,
+            view: 0,
+            signatures: Vec::new(),
+        };
+        let message = commit.vote_on().hash();
+        commit.signatures = signers
+            .iter()
+            .map(|(index, key_pair)| (*index, sign_schnorr(key_pair.private(), &message).unwrap()))
+            .collect();
+        commit
+    }
+
+    #[test]
+    fn commit_signed_by_two_thirds_is_verified() {
+        let (key_pairs, validators) = validators(4);
+        let commit = commit(Header::default(), &[(0, &key_pairs[0]), (1, &key_pairs[1]), (3, &key_pairs[3])]);
+        assert_eq!(Ok(()), verify_commit(&commit, &validators));
+    }
+
+    #[test]
+    fn commit_without_enough_voting_power_is_rejected() {
+        let (key_pairs, validators) = validators(4);
+        let commit = commit(Header::default(), &[(0, &key_pairs[0]), (1, &key_pairs[1])]);
+        assert_eq!(
+            Err(LightClientError::NotEnoughVotingPower {
+                voted: 2,
+                total: 4,
+            }),
+            verify_commit(&commit, &validators)
+        );
+    }
+
+    #[test]
+    fn signature_of_another_validator_is_rejected() {
+        let (key_pairs, validators) = validators(4);
+        let commit = commit(Header::default(), &[(0, &key_pairs[0]), (1, &key_pairs[2]), (3, &key_pairs[3])]);
+        assert_eq!(Err(LightClientError::InvalidSignature(1)), verify_commit(&commit, &validators));
+    }
+
+    #[test]
+    fn light_client_follows_the_child() {
+        let (key_pairs, validators) = validators(4);
+        let trusted = Header::default();
+        let mut client = LightClient::new(&trusted, validator_set_hash(&validators));
+
+        let mut child = Header::default();
+        child.set_parent_hash(trusted.hash());
+        child.set_number(1);
+        let next_hash = H256::random();
+        let commit = commit(child.clone(), &[(0, &key_pairs[0]), (1, &key_pairs[1]), (2, &key_pairs[2])]);
+        assert_eq!(Ok(()), client.update(&commit, &validators, next_hash));
+        assert_eq!(child.hash(), client.hash());
+        assert_eq!(1, client.number());
+        assert_eq!(next_hash, client.validator_set_hash());
+
+        assert_eq!(
+            Err(LightClientError::NotSuccessor {
+                trusted: child.hash(),
+                parent: trusted.hash(),
+            }),
+            client.update(&commit, &validators, next_hash)
+        );
+    }
+
+    #[test]
+    fn light_client_rejects_another_validator_set() {
+        let (key_pairs, validators) = validators(4);
+        let trusted = Header::default();
+        let trusted_hash = H256::random();
+        let mut client = LightClient::new(&trusted, trusted_hash);
+
+        let mut child = Header::default();
+        child.set_parent_hash(trusted.hash());
+        child.set_number(1);
+        let commit = commit(child, &[(0, &key_pairs[0]), (1, &key_pairs[1]), (2, &key_pairs[2])]);
+        assert_eq!(
+            Err(LightClientError::ValidatorSetMismatch {
+                expected: trusted_hash,
+                found: validator_set_hash(&validators),
+            }),
+            client.update(&commit, &validators, H256::random())
+        );
+    }
+}
@@ -15,18 +15,29 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 mod bit_set;
+mod checkpoint;
+mod light_client;
 mod null_engine;
+mod shadow;
 pub(crate) mod signer;
 mod solo;
 pub mod stake;
 mod tendermint;
 mod validator_set;
 
+pub use self::checkpoint::{Checkpoint, CheckpointSignature, SignedCheckpoint};
+pub use self::light_client::{
+    validator_set_hash, verify_commit, Commit, CommitValidator, LightClient, LightClientError,
+};
 pub use self::null_engine::NullEngine;
+pub use self::shadow::ShadowEngine;
 pub use self::solo::Solo;
 pub use self::tendermint::{
-    ConsensusMessage, Height, Step, Tendermint, TendermintParams, TimeGapParams, View, VoteOn, VoteStep,
+    ClockDrift, ConsensusMessage, Duties, Height, Slot, Step, Tendermint, TendermintParams, TimeGapParams, View, VoteOn,
+    VoteStep,
 };
+#[cfg(feature = "fuzzing")]
+pub use self::tendermint::TendermintMessage;
 pub use self::validator_set::validator_list::RoundRobinValidator;
 pub use self::validator_set::{DynamicValidator, ValidatorSet};
 
@@ -205,8 +216,20 @@ pub trait ConsensusEngine: Sync + Send {
     /// Register an account which signs consensus messages.
     fn set_signer(&self, _ap: Arc<AccountProvider>, _address: Address) {}
 
+    /// Lets the signer sign for `owner` as its session key.
+    fn set_signer_owner(&self, _owner: Address) {}
+
     fn register_network_extension_to_service(&self, _: &NetworkService) {}
 
+    /// Starts the engine without a network, as the shadow of the active engine. The messages it sends are dropped.
+    fn start_as_shadow(&self) {}
+
+    /// Registers an engine to be fed the consensus messages that this engine receives from the peers.
+    fn register_shadow(&self, _shadow: Weak<dyn CodeChainEngine>) {}
+
+    /// Handles a consensus message received by the active engine, without answering the peer.
+    fn handle_shadowed_message(&self, _message: &[u8]) {}
+
     fn register_time_gap_config_to_worker(&self, _time_gap_params: TimeGapParams) {}
 
     fn block_reward(&self, block_number: u64) -> u64;
@@ -215,6 +238,11 @@ pub trait ConsensusEngine: Sync + Send {
         transactions.map(|tx| tx.fee).sum()
     }
 
+    /// The maximum size of a block body.
+    fn max_body_size(&self, common_params: &CommonParams) -> usize {
+        common_params.max_body_size()
+    }
+
     fn recommended_confirmation(&self) -> u32;
 
     fn register_chain_notify(&self, _: &Client) {}
@@ -250,6 +278,26 @@ pub trait ConsensusEngine: Sync + Send {
     }
 
     fn possible_authors(&self, block_number: Option<u64>) -> Result<Option<Vec<Address>>, EngineError>;
+
+    /// Returns the validators whose precommits finalize the block, in the order of the signature indices.
+    fn commit_validators(&self, _block_hash: &BlockHash) -> Option<Vec<CommitValidator>> {
+        None
+    }
+
+    /// Returns the view and the precommit signatures finalizing the parent, which are in the seal of the header.
+    fn parent_commit(&self, _header: &Header) -> Option<(View, Vec<(usize, SchnorrSignature)>)> {
+        None
+    }
+
+    /// Returns the drift of the local clock from the other validators if the engine estimates it.
+    fn clock_drift(&self) -> Option<ClockDrift> {
+        None
+    }
+
+    /// Returns the proposal slots of the signer in the next `count` blocks if the engine designates the proposers.
+    fn duties(&self, _count: usize) -> Option<Duties> {
+        None
+    }
 }
 
 /// Voting errors.
@@ -330,15 +378,17 @@ pub trait CodeChainEngine: ConsensusEngine {
         tx: &UnverifiedTransaction,
         common_params: &CommonParams,
     ) -> Result<(), Error> {
-        if let Action::Custom {
-            handler_id,
-            bytes,
-        } = &tx.action
-        {
-            let handler = self
-                .find_action_handler_for(*handler_id)
-                .ok_or_else(|| SyntaxError::InvalidCustomAction(format!("{} is an invalid handler id", handler_id)))?;
-            handler.verify(bytes, common_params)?;
+        for action in tx.action.flatten() {
+            if let Action::Custom {
+                handler_id,
+                bytes,
+            } = action
+            {
+                let handler = self.find_action_handler_for(*handler_id).ok_or_else(|| {
+                    SyntaxError::InvalidCustomAction(format!("{} is an invalid handler id", handler_id))
+                })?;
+                handler.verify(bytes, common_params)?;
+            }
         }
         self.machine().verify_transaction_with_params(tx, common_params)
     }
@@ -21,9 +21,10 @@ use super::ConsensusEngine;
 use crate::block::ExecutedBlock;
 use crate::codechain_machine::CodeChainMachine;
 use crate::consensus::{EngineError, EngineType};
-use crate::error::Error;
+use crate::error::{BlockError, Error};
 use ckey::Address;
-use ctypes::CommonParams;
+use ctypes::util::unexpected::{Mismatch, OutOfBounds};
+use ctypes::{BlockNumber, CommonParams, Header};
 
 /// An engine which does not provide any consensus mechanism and does not seal blocks.
 pub struct NullEngine {
@@ -39,6 +40,16 @@ impl NullEngine {
             machine,
         }
     }
+
+    /// Returns the author who should produce the block if the author rotation is given.
+    fn expected_author(&self, block_number: BlockNumber) -> Option<Address> {
+        let rotation = &self.params.author_rotation;
+        if rotation.is_empty() {
+            return None
+        }
+        let index = block_number.saturating_sub(1) % rotation.len() as u64;
+        Some(rotation[index as usize])
+    }
 }
 
 impl ConsensusEngine for NullEngine {
@@ -58,6 +69,42 @@ impl ConsensusEngine for NullEngine {
         EngineType::Solo
     }
 
+    fn verify_header_basic(&self, header: &Header) -> Result<(), Error> {
+        if let Some(expected) = self.expected_author(header.number()) {
+            if *header.author() != expected {
+                return Err(EngineError::NotProposer(Mismatch {
+                    expected,
+                    found: *header.author(),
+                })
+                .into())
+            }
+        }
+        Ok(())
+    }
+
+    fn verify_block_family(&self, header: &Header, parent: &Header) -> Result<(), Error> {
+        let min_timestamp = parent.timestamp() + self.params.block_interval;
+        if header.timestamp() < min_timestamp {
+            return Err(BlockError::InvalidTimestamp(OutOfBounds {
+                min: Some(min_timestamp),
+                max: None,
+                found: header.timestamp(),
+            })
+            .into())
+        }
+        Ok(())
+    }
+
+    fn populate_from_parent(&self, header: &mut Header, parent: &Header) {
+        if let Some(author) = self.expected_author(header.number()) {
+            header.set_author(author);
+        }
+        let min_timestamp = parent.timestamp() + self.params.block_interval;
+        if header.timestamp() < min_timestamp {
+            header.set_timestamp(min_timestamp);
+        }
+    }
+
     fn on_close_block(
         &self,
         block: &mut ExecutedBlock,
@@ -77,11 +124,87 @@ impl ConsensusEngine for NullEngine {
         self.params.block_reward
     }
 
+    fn max_body_size(&self, common_params: &CommonParams) -> usize {
+        self.params.max_body_size.unwrap_or_else(|| common_params.max_body_size())
+    }
+
     fn recommended_confirmation(&self) -> u32 {
         1
     }
 
-    fn possible_authors(&self, _block_number: Option<u64>) -> Result<Option<Vec<Address>>, EngineError> {
-        Ok(None)
+    fn possible_authors(&self, block_number: Option<u64>) -> Result<Option<Vec<Address>>, EngineError> {
+        if self.params.author_rotation.is_empty() {
+            return Ok(None)
+        }
+        match block_number {
+            Some(block_number) => Ok(self.expected_author(block_number).map(|author| vec![author])),
+            None => Ok(Some(self.params.author_rotation.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ctypes::ForkSchedule;
+
+    fn engine(params: NullEngineParams) -> NullEngine {
+        NullEngine::new(params, CodeChainMachine::new(CommonParams::default_for_test(), ForkSchedule::default()))
+    }
+
+    fn header(number: BlockNumber, timestamp: u64, author: Address) -> Header {
+        let mut header = Header::new();
+        header.set_number(number);
+        header.set_timestamp(timestamp);
+        header.set_author(author);
+        header
+    }
+
+    #[test]
+    fn authors_take_turns() {
+        let (author1, author2) = (Address::random(), Address::random());
+        let engine = engine(NullEngineParams {
+            author_rotation: vec![author1, author2],
+            ..Default::default()
+        });
+        let parent = header(0, 0, Address::default());
+
+        for (number, expected) in [(1, author1), (2, author2), (3, author1)].iter() {
+            let mut header = header(*number, 0, Address::random());
+            engine.populate_from_parent(&mut header, &parent);
+            assert_eq!(expected, header.author());
+            assert!(engine.verify_header_basic(&header).is_ok());
+        }
+        assert!(engine.verify_header_basic(&header(1, 0, author2)).is_err());
+        assert_eq!(Ok(Some(vec![author2])), engine.possible_authors(Some(2)).map_err(|e| e.to_string()));
+    }
+
+    #[test]
+    fn blocks_are_produced_with_interval() {
+        let engine = engine(NullEngineParams {
+            block_interval: 5,
+            ..Default::default()
+        });
+        let parent = header(1, 100, Address::default());
+
+        let mut header = header(2, 101, Address::default());
+        engine.populate_from_parent(&mut header, &parent);
+        assert_eq!(105, header.timestamp());
+        assert!(engine.verify_block_family(&header, &parent).is_ok());
+
+        header.set_timestamp(104);
+        assert!(engine.verify_block_family(&header, &parent).is_err());
+    }
+
+    #[test]
+    fn max_body_size_overrides_common_params() {
+        let common_params = CommonParams::default_for_test();
+        assert_eq!(common_params.max_body_size(), engine(Default::default()).max_body_size(&common_params));
+
+        let engine = engine(NullEngineParams {
+            max_body_size: Some(1000),
+            ..Default::default()
+        });
+        assert_eq!(1000, engine.max_body_size(&common_params));
     }
 }
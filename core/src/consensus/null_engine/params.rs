@@ -15,18 +15,33 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use cjson;
+use ckey::{Address, PlatformAddress};
 
 /// Params for a null engine.
 #[derive(Clone, Default)]
 pub struct NullEngineParams {
     /// base reward for a block.
     pub block_reward: u64,
+    /// The minimum interval between blocks in seconds.
+    pub block_interval: u64,
+    /// The authors who produce blocks in turn. Any author is allowed if it's empty.
+    pub author_rotation: Vec<Address>,
+    /// Overrides the maximum body size of the common params.
+    pub max_body_size: Option<usize>,
 }
 
 impl From<cjson::scheme::NullEngineParams> for NullEngineParams {
     fn from(p: cjson::scheme::NullEngineParams) -> Self {
         NullEngineParams {
             block_reward: p.block_reward.map_or_else(Default::default, Into::into),
+            block_interval: p.block_interval.map_or_else(Default::default, Into::into),
+            author_rotation: p
+                .author_rotation
+                .unwrap_or_default()
+                .into_iter()
+                .map(PlatformAddress::into_address)
+                .collect(),
+            max_body_size: p.max_body_size.map(Into::into),
         }
     }
 }
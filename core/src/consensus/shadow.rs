@@ -0,0 +1,751 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Runs a candidate engine next to the active one in validate-only mode.
+//!
+//! The candidate is asked every decision the active engine is asked, and it is fed the blocks and the consensus
+//! messages the active engine gets. Only the decisions of the active engine take effect. The candidate can't seal,
+//! can't send messages and can't change the chain; what it would have done is compared with what the active engine
+//! did, and every divergence is logged.
+
+use super::{
+    ClockDrift, CodeChainEngine, CommitValidator, ConsensusEngine, Duties, EngineError, EngineType, Seal, View,
+};
+use crate::account_provider::AccountProvider;
+use crate::block::{Block, ExecutedBlock, IsBlock, SealedBlock};
+use crate::blockchain_info::BlockChainInfo;
+use crate::client::snapshot_notify::{self, NotifySender as SnapshotNotifySender};
+use crate::client::{
+    AccountData, BlockChainClient, BlockChainTrait, ChainNotify, Client, ConsensusClient, EngineClient, EngineInfo,
+    ImportBlock, ImportResult, StateInfo, StateOrBlock, TermInfo,
+};
+use crate::codechain_machine::CodeChainMachine;
+use crate::consensus::TimeGapParams;
+use crate::encoded;
+use crate::error::{BlockImportError, Error};
+use crate::transaction::{LocalizedTransaction, PendingSignedTransactions, SignedTransaction, UnverifiedTransaction};
+use crate::types::{BlockId, BlockStatus, TransactionId, VerificationQueueInfo as BlockQueueInfo};
+use crate::views::HeaderView;
+use ckey::{Address, NetworkId, PlatformAddress, Public, SchnorrSignature};
+use cnetwork::{NetworkService, NodeId};
+use cstate::{ActionHandler, StateWithCache, TopLevelState};
+use ctypes::{BlockHash, BlockNumber, CommonParams, Header, Tracker, TxHash};
+use cvm::ChainTimeInfo;
+use kvdb::KeyValueDB;
+use parking_lot::{Mutex, RwLock};
+use primitives::{Bytes, H256, U256};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::mem;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::{Arc, Weak};
+
+pub struct ShadowEngine {
+    active: Arc<dyn CodeChainEngine>,
+    candidate: Arc<dyn CodeChainEngine>,
+    /// The client the candidate sees. It's kept here because the candidate holds it weakly.
+    client: RwLock<Option<Arc<ShadowClient>>>,
+    divergences: Arc<Divergences>,
+}
+
+impl ShadowEngine {
+    pub fn new(active: Arc<dyn CodeChainEngine>, candidate: Arc<dyn CodeChainEngine>) -> Self {
+        Self {
+            active,
+            candidate,
+            client: Default::default(),
+            divergences: Default::default(),
+        }
+    }
+
+    /// The number of decisions on which the candidate disagreed with the active engine.
+    pub fn divergences(&self) -> usize {
+        self.divergences.0.load(AtomicOrdering::SeqCst)
+    }
+
+    fn compare_verification(
+        &self,
+        decision: &str,
+        header: &Header,
+        verify: impl Fn(&dyn CodeChainEngine) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let active = verify(&*self.active);
+        let candidate = verify(&*self.candidate);
+        self.divergences.compare(
+            decision,
+            format_args!("block #{} {}", header.number(), header.hash()),
+            outcome(&active),
+            outcome(&candidate),
+        );
+        active
+    }
+
+    /// Applies the transformation to the block with the active engine and to a copy of it with the candidate.
+    fn compare_transformation(
+        &self,
+        decision: &str,
+        block: &mut ExecutedBlock,
+        transform: impl Fn(&dyn CodeChainEngine, &mut ExecutedBlock) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let mut shadowed = block.clone();
+        let candidate = transform(&*self.candidate, &mut shadowed);
+        let active = transform(&*self.active, block);
+
+        let (number, parent) = (block.header().number(), *block.header().parent_hash());
+        self.divergences.compare(
+            decision,
+            format_args!("block #{} on {}", number, parent),
+            outcome(&active),
+            outcome(&candidate),
+        );
+        if active.is_ok() && candidate.is_ok() {
+            self.divergences.compare(
+                decision,
+                format_args!("the state root of block #{} on {}", number, parent),
+                state_root(block),
+                state_root(&shadowed),
+            );
+        }
+        active
+    }
+}
+
+impl ConsensusEngine for ShadowEngine {
+    fn name(&self) -> &str {
+        self.active.name()
+    }
+
+    fn machine(&self) -> &CodeChainMachine {
+        self.active.machine()
+    }
+
+    fn seal_fields(&self, header: &Header) -> usize {
+        self.active.seal_fields(header)
+    }
+
+    fn seals_internally(&self) -> bool {
+        self.active.seals_internally()
+    }
+
+    fn engine_type(&self) -> EngineType {
+        self.active.engine_type()
+    }
+
+    fn generate_seal(&self, block: Option<&ExecutedBlock>, parent: &Header) -> Seal {
+        self.active.generate_seal(block, parent)
+    }
+
+    fn proposal_generated(&self, sealed_block: &SealedBlock) {
+        self.active.proposal_generated(sealed_block)
+    }
+
+    fn verify_header_basic(&self, header: &Header) -> Result<(), Error> {
+        self.compare_verification("verify_header_basic", header, |engine| engine.verify_header_basic(header))
+    }
+
+    fn verify_block_seal(&self, header: &Header) -> Result<(), Error> {
+        self.compare_verification("verify_block_seal", header, |engine| engine.verify_block_seal(header))
+    }
+
+    fn verify_block_family(&self, header: &Header, parent: &Header) -> Result<(), Error> {
+        self.compare_verification("verify_block_family", header, |engine| engine.verify_block_family(header, parent))
+    }
+
+    fn verify_block_external(&self, header: &Header) -> Result<(), Error> {
+        self.compare_verification("verify_block_external", header, |engine| engine.verify_block_external(header))
+    }
+
+    fn populate_from_parent(&self, header: &mut Header, parent: &Header) {
+        let mut shadowed = header.clone();
+        self.candidate.populate_from_parent(&mut shadowed, parent);
+        self.active.populate_from_parent(header, parent);
+        self.divergences.compare(
+            "populate_from_parent",
+            format_args!("block #{} on {}", header.number(), parent.hash()),
+            &*header,
+            &shadowed,
+        );
+    }
+
+    fn on_timeout(&self, token: usize) {
+        self.active.on_timeout(token)
+    }
+
+    fn stop(&self) {
+        self.candidate.stop();
+        self.active.stop()
+    }
+
+    fn on_open_block(&self, block: &mut ExecutedBlock) -> Result<(), Error> {
+        self.compare_transformation("on_open_block", block, |engine, block| engine.on_open_block(block))
+    }
+
+    fn on_close_block(
+        &self,
+        block: &mut ExecutedBlock,
+        term_common_params: Option<&CommonParams>,
+    ) -> Result<(), Error> {
+        self.compare_transformation("on_close_block", block, |engine, block| {
+            engine.on_close_block(block, term_common_params)
+        })
+    }
+
+    fn register_client(&self, client: Weak<dyn ConsensusClient>) {
+        let shadow_client = Arc::new(ShadowClient {
+            client: Weak::clone(&client),
+            divergences: Arc::clone(&self.divergences),
+            commits: Default::default(),
+        });
+        self.candidate.register_client(Arc::downgrade(&shadow_client) as Weak<dyn ConsensusClient>);
+        *self.client.write() = Some(shadow_client);
+        self.active.register_client(client)
+    }
+
+    fn is_proposal(&self, verified_header: &Header) -> bool {
+        let active = self.active.is_proposal(verified_header);
+        let candidate = self.candidate.is_proposal(verified_header);
+        self.divergences.compare(
+            "is_proposal",
+            format_args!("block #{} {}", verified_header.number(), verified_header.hash()),
+            active,
+            candidate,
+        );
+        active
+    }
+
+    fn set_signer(&self, ap: Arc<AccountProvider>, address: Address) {
+        self.active.set_signer(ap, address)
+    }
+
+    fn set_signer_owner(&self, owner: Address) {
+        self.active.set_signer_owner(owner)
+    }
+
+    fn register_network_extension_to_service(&self, service: &NetworkService) {
+        self.active.register_shadow(Arc::downgrade(&self.candidate));
+        self.active.register_network_extension_to_service(service);
+        self.candidate.start_as_shadow()
+    }
+
+    fn register_time_gap_config_to_worker(&self, time_gap_params: TimeGapParams) {
+        self.candidate.register_time_gap_config_to_worker(time_gap_params);
+        self.active.register_time_gap_config_to_worker(time_gap_params)
+    }
+
+    fn block_reward(&self, block_number: u64) -> u64 {
+        let active = self.active.block_reward(block_number);
+        let candidate = self.candidate.block_reward(block_number);
+        self.divergences.compare("block_reward", format_args!("block #{}", block_number), active, candidate);
+        active
+    }
+
+    fn block_fee(&self, transactions: Box<dyn Iterator<Item = UnverifiedTransaction>>) -> u64 {
+        let transactions: Vec<_> = transactions.collect();
+        let count = transactions.len();
+        let candidate = self.candidate.block_fee(Box::new(transactions.clone().into_iter()));
+        let active = self.active.block_fee(Box::new(transactions.into_iter()));
+        self.divergences.compare("block_fee", format_args!("{} transactions", count), active, candidate);
+        active
+    }
+
+    fn max_body_size(&self, common_params: &CommonParams) -> usize {
+        self.active.max_body_size(common_params)
+    }
+
+    fn recommended_confirmation(&self) -> u32 {
+        self.active.recommended_confirmation()
+    }
+
+    fn register_chain_notify(&self, client: &Client) {
+        self.candidate.register_chain_notify(client);
+        self.active.register_chain_notify(client);
+        if let Some(shadow_client) = self.client.read().as_ref() {
+            client.add_notify(Arc::downgrade(shadow_client) as Weak<dyn ChainNotify>);
+        }
+    }
+
+    fn complete_register(&self) {
+        self.candidate.complete_register();
+        self.active.complete_register()
+    }
+
+    fn register_snapshot_notify_sender(&self, sender: SnapshotNotifySender) {
+        // The receiver is dropped, so the snapshots the candidate requests are ignored.
+        let (shadow_sender, _receiver) = snapshot_notify::create();
+        self.candidate.register_snapshot_notify_sender(shadow_sender);
+        self.active.register_snapshot_notify_sender(sender)
+    }
+
+    fn send_snapshot_notify(&self, block_hash: BlockHash) {
+        self.active.send_snapshot_notify(block_hash)
+    }
+
+    fn get_best_block_from_best_proposal_header(&self, header: &HeaderView<'_>) -> BlockHash {
+        let active = self.active.get_best_block_from_best_proposal_header(header);
+        let candidate = self.candidate.get_best_block_from_best_proposal_header(header);
+        self.divergences.compare(
+            "get_best_block_from_best_proposal_header",
+            format_args!("block #{} {}", header.number(), header.hash()),
+            active,
+            candidate,
+        );
+        active
+    }
+
+    fn can_change_canon_chain(
+        &self,
+        new_block_hash: BlockHash,
+        parent_hash_of_new_header: BlockHash,
+        grandparent_hash_of_new_header: BlockHash,
+        previous_best_hash: BlockHash,
+    ) -> bool {
+        let active = self.active.can_change_canon_chain(
+            new_block_hash,
+            parent_hash_of_new_header,
+            grandparent_hash_of_new_header,
+            previous_best_hash,
+        );
+        let candidate = self.candidate.can_change_canon_chain(
+            new_block_hash,
+            parent_hash_of_new_header,
+            grandparent_hash_of_new_header,
+            previous_best_hash,
+        );
+        self.divergences.compare(
+            "can_change_canon_chain",
+            format_args!("block {} over the best block {}", new_block_hash, previous_best_hash),
+            active,
+            candidate,
+        );
+        active
+    }
+
+    fn action_handlers(&self) -> &[Arc<dyn ActionHandler>] {
+        self.active.action_handlers()
+    }
+
+    fn possible_authors(&self, block_number: Option<u64>) -> Result<Option<Vec<Address>>, EngineError> {
+        let active = self.active.possible_authors(block_number);
+        let candidate = self.candidate.possible_authors(block_number);
+        self.divergences.compare(
+            "possible_authors",
+            format_args!("block {:?}", block_number),
+            active.as_ref().map_err(ToString::to_string),
+            candidate.as_ref().map_err(ToString::to_string),
+        );
+        active
+    }
+
+    fn commit_validators(&self, block_hash: &BlockHash) -> Option<Vec<CommitValidator>> {
+        let active = self.active.commit_validators(block_hash);
+        let candidate = self.candidate.commit_validators(block_hash);
+        self.divergences.compare("commit_validators", format_args!("block {}", block_hash), &active, &candidate);
+        active
+    }
+
+    fn parent_commit(&self, header: &Header) -> Option<(View, Vec<(usize, SchnorrSignature)>)> {
+        self.active.parent_commit(header)
+    }
+
+    fn clock_drift(&self) -> Option<ClockDrift> {
+        self.active.clock_drift()
+    }
+
+    fn duties(&self, count: usize) -> Option<Duties> {
+        self.active.duties(count)
+    }
+}
+
+/// Counts and logs the decisions on which the candidate disagrees with the active engine.
+#[derive(Default)]
+struct Divergences(AtomicUsize);
+
+impl Divergences {
+    fn compare<T: fmt::Debug + PartialEq>(&self, decision: &str, subject: fmt::Arguments<'_>, active: T, candidate: T) {
+        if active == candidate {
+            return
+        }
+        let count = self.0.fetch_add(1, AtomicOrdering::SeqCst) + 1;
+        cwarn!(
+            ENGINE,
+            "Shadow engine divergence #{} in {} of {}: the active engine decided {:?} but the candidate decided {:?}",
+            count,
+            decision,
+            subject,
+            active,
+            candidate
+        );
+    }
+}
+
+fn outcome(result: &Result<(), Error>) -> Result<(), String> {
+    result.as_ref().map(|_| ()).map_err(ToString::to_string)
+}
+
+fn state_root(block: &ExecutedBlock) -> Result<H256, String> {
+    let mut state = block.state().clone();
+    state.commit().map_err(|e| e.to_string())
+}
+
+/// The client seen by the candidate. It reads from the real client, but doesn't let the candidate change anything.
+/// The blocks the candidate commits are compared with the blocks the active engine commits.
+struct ShadowClient {
+    client: Weak<dyn ConsensusClient>,
+    divergences: Arc<Divergences>,
+    /// The blocks committed by the candidate above the best block.
+    commits: Mutex<BTreeMap<BlockNumber, BlockHash>>,
+}
+
+impl ShadowClient {
+    fn client(&self) -> Arc<dyn ConsensusClient> {
+        self.client.upgrade().expect("Client lives longer than consensus")
+    }
+
+    fn check_commits(&self) {
+        let client = self.client();
+        let best_block_number = client.chain_info().best_block_number;
+        let decided = {
+            let mut commits = self.commits.lock();
+            let pending = commits.split_off(&(best_block_number + 1));
+            mem::replace(&mut *commits, pending)
+        };
+        for (number, candidate) in decided {
+            let active = client.block_hash(&BlockId::Number(number));
+            self.divergences.compare("the committed block", format_args!("height {}", number), active, Some(candidate));
+        }
+    }
+}
+
+impl ChainNotify for ShadowClient {
+    fn new_blocks(
+        &self,
+        _imported: Vec<BlockHash>,
+        _invalid: Vec<BlockHash>,
+        _enacted: Vec<BlockHash>,
+        _sealed: Vec<BlockHash>,
+    ) {
+        self.check_commits();
+    }
+}
+
+impl EngineClient for ShadowClient {
+    fn update_sealing(&self, _parent_block: BlockId, _allow_empty_block: bool) {}
+
+    fn update_best_as_committed(&self, block_hash: BlockHash) {
+        match self.client().block_number(&BlockId::Hash(block_hash)) {
+            Some(number) => {
+                self.commits.lock().insert(number, block_hash);
+                self.check_commits();
+            }
+            None => cwarn!(ENGINE, "The candidate engine committed an unknown block {}", block_hash),
+        }
+    }
+
+    fn get_kvdb(&self) -> Arc<dyn KeyValueDB> {
+        self.client().get_kvdb()
+    }
+}
+
+impl ImportBlock for ShadowClient {
+    fn import_block(&self, _bytes: Bytes) -> Result<BlockHash, BlockImportError> {
+        Err(BlockImportError::Other("The shadow engine cannot import blocks".to_string()))
+    }
+
+    fn import_header(&self, _bytes: Bytes) -> Result<BlockHash, BlockImportError> {
+        Err(BlockImportError::Other("The shadow engine cannot import headers".to_string()))
+    }
+
+    fn import_trusted_header(&self, _header: &Header) -> Result<BlockHash, BlockImportError> {
+        Err(BlockImportError::Other("The shadow engine cannot import headers".to_string()))
+    }
+
+    fn import_trusted_block(&self, _block: &Block) -> Result<BlockHash, BlockImportError> {
+        Err(BlockImportError::Other("The shadow engine cannot import blocks".to_string()))
+    }
+
+    fn force_update_best_block(&self, _hash: &BlockHash) {}
+
+    fn import_sealed_block(&self, block: &SealedBlock) -> ImportResult {
+        // The candidate has no signer, so it never seals a block.
+        Ok(block.header().hash())
+    }
+
+    fn set_min_timer(&self) {}
+}
+
+impl BlockChainTrait for ShadowClient {
+    fn chain_info(&self) -> BlockChainInfo {
+        self.client().chain_info()
+    }
+
+    fn genesis_accounts(&self) -> Vec<PlatformAddress> {
+        self.client().genesis_accounts()
+    }
+
+    fn block_header(&self, id: &BlockId) -> Option<encoded::Header> {
+        self.client().block_header(id)
+    }
+
+    fn best_block_header(&self) -> encoded::Header {
+        self.client().best_block_header()
+    }
+
+    fn best_header(&self) -> encoded::Header {
+        self.client().best_header()
+    }
+
+    fn best_proposal_header(&self) -> encoded::Header {
+        self.client().best_proposal_header()
+    }
+
+    fn block(&self, id: &BlockId) -> Option<encoded::Block> {
+        self.client().block(id)
+    }
+
+    fn transaction_block(&self, id: &TransactionId) -> Option<BlockHash> {
+        self.client().transaction_block(id)
+    }
+
+    fn transaction_header(&self, tracker: &Tracker) -> Option<encoded::Header> {
+        self.client().transaction_header(tracker)
+    }
+}
+
+impl AccountData for ShadowClient {
+    fn seq(&self, address: &Address, id: BlockId) -> Option<u64> {
+        self.client().seq(address, id)
+    }
+
+    fn balance(&self, address: &Address, state: StateOrBlock) -> Option<u64> {
+        self.client().balance(address, state)
+    }
+
+    fn locked_balance(&self, address: &Address, id: BlockId) -> Option<u64> {
+        self.client().locked_balance(address, id)
+    }
+
+    fn regular_key(&self, address: &Address, state: StateOrBlock) -> Option<Public> {
+        self.client().regular_key(address, state)
+    }
+
+    fn regular_key_owner(&self, address: &Address, state: StateOrBlock) -> Option<Address> {
+        self.client().regular_key_owner(address, state)
+    }
+}
+
+impl ChainTimeInfo for ShadowClient {
+    fn transaction_block_age(&self, tracker: &Tracker, parent_block_number: BlockNumber) -> Option<u64> {
+        self.client().transaction_block_age(tracker, parent_block_number)
+    }
+
+    fn transaction_time_age(&self, tracker: &Tracker, parent_timestamp: u64) -> Option<u64> {
+        self.client().transaction_time_age(tracker, parent_timestamp)
+    }
+}
+
+impl BlockChainClient for ShadowClient {
+    fn queue_info(&self) -> BlockQueueInfo {
+        self.client().queue_info()
+    }
+
+    fn queue_own_transaction(&self, _transaction: SignedTransaction) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn queue_transactions(&self, _transactions: Vec<Bytes>, _peer_id: NodeId) {}
+
+    fn delete_all_pending_transactions(&self) {}
+
+    fn cancel_pending_transaction(&self, _signer: &Address, _seq: u64) -> Option<TxHash> {
+        None
+    }
+
+    fn ready_transactions(&self, range: Range<u64>) -> PendingSignedTransactions {
+        self.client().ready_transactions(range)
+    }
+
+    fn count_pending_transactions(&self, range: Range<u64>) -> usize {
+        self.client().count_pending_transactions(range)
+    }
+
+    fn scheduled_transactions(&self) -> Vec<SignedTransaction> {
+        self.client().scheduled_transactions()
+    }
+
+    fn pooled_transactions_of(&self, signer: &Address) -> Vec<SignedTransaction> {
+        self.client().pooled_transactions_of(signer)
+    }
+
+    fn is_pending_queue_empty(&self) -> bool {
+        self.client().is_pending_queue_empty()
+    }
+
+    fn block_number(&self, id: &BlockId) -> Option<BlockNumber> {
+        self.client().block_number(id)
+    }
+
+    fn block_body(&self, id: &BlockId) -> Option<encoded::Body> {
+        self.client().block_body(id)
+    }
+
+    fn block_status(&self, id: &BlockId) -> BlockStatus {
+        self.client().block_status(id)
+    }
+
+    fn block_total_score(&self, id: &BlockId) -> Option<U256> {
+        self.client().block_total_score(id)
+    }
+
+    fn block_hash(&self, id: &BlockId) -> Option<BlockHash> {
+        self.client().block_hash(id)
+    }
+
+    fn transaction(&self, id: &TransactionId) -> Option<LocalizedTransaction> {
+        self.client().transaction(id)
+    }
+
+    fn error_hint(&self, hash: &TxHash) -> Option<String> {
+        self.client().error_hint(hash)
+    }
+
+    fn transaction_by_tracker(&self, tracker: &Tracker) -> Option<LocalizedTransaction> {
+        self.client().transaction_by_tracker(tracker)
+    }
+
+    fn error_hints_by_tracker(&self, tracker: &Tracker) -> Vec<(TxHash, Option<String>)> {
+        self.client().error_hints_by_tracker(tracker)
+    }
+}
+
+impl EngineInfo for ShadowClient {
+    fn network_id(&self) -> NetworkId {
+        self.client().network_id()
+    }
+
+    fn common_params(&self, block_id: BlockId) -> Option<CommonParams> {
+        self.client().common_params(block_id)
+    }
+
+    fn metadata_seq(&self, block_id: BlockId) -> Option<u64> {
+        self.client().metadata_seq(block_id)
+    }
+
+    fn block_reward(&self, block_number: u64) -> u64 {
+        self.client().block_reward(block_number)
+    }
+
+    fn mining_reward(&self, block_number: u64) -> Option<u64> {
+        self.client().mining_reward(block_number)
+    }
+
+    fn recommended_confirmation(&self) -> u32 {
+        self.client().recommended_confirmation()
+    }
+
+    fn possible_authors(&self, block_number: Option<u64>) -> Result<Option<Vec<PlatformAddress>>, EngineError> {
+        self.client().possible_authors(block_number)
+    }
+
+    fn clock_drift(&self) -> Option<ClockDrift> {
+        self.client().clock_drift()
+    }
+
+    fn duties(&self, count: usize) -> Option<Duties> {
+        self.client().duties(count)
+    }
+}
+
+impl TermInfo for ShadowClient {
+    fn last_term_finished_block_num(&self, id: BlockId) -> Option<BlockNumber> {
+        self.client().last_term_finished_block_num(id)
+    }
+
+    fn current_term_id(&self, id: BlockId) -> Option<u64> {
+        self.client().current_term_id(id)
+    }
+
+    fn term_common_params(&self, id: BlockId) -> Option<CommonParams> {
+        self.client().term_common_params(id)
+    }
+}
+
+impl StateInfo for ShadowClient {
+    fn state_at(&self, id: BlockId) -> Option<TopLevelState> {
+        self.client().state_at(id)
+    }
+
+    fn pending_state(&self) -> Option<TopLevelState> {
+        self.client().pending_state()
+    }
+}
+
+impl ConsensusClient for ShadowClient {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::OpenBlock;
+    use crate::client::TestBlockChainClient;
+    use crate::scheme::Scheme;
+    use crate::tests::helpers::get_temp_state_db;
+    use serde_json::json;
+
+    fn solo_with_block_reward(block_reward: u64) -> Scheme {
+        let mut scheme = Scheme::load_json("solo").unwrap();
+        scheme["engine"]["solo"]["params"]["blockReward"] = json!(format!("0x{:x}", block_reward));
+        Scheme::load(scheme.to_string().as_bytes()).unwrap()
+    }
+
+    /// Closes an empty block on the genesis block of the scheme, and returns its state root.
+    fn close_block(engine: Arc<dyn CodeChainEngine>, scheme: Scheme) -> H256 {
+        let client = Arc::new(TestBlockChainClient::new_with_scheme(scheme));
+        engine.register_client(Arc::downgrade(&(Arc::clone(&client) as Arc<dyn ConsensusClient>)));
+        let genesis_header = client.scheme.genesis_header();
+        let db = client.scheme.ensure_genesis_state(get_temp_state_db()).unwrap();
+        let block = OpenBlock::try_new(&*engine, db, &genesis_header, Address::default(), vec![]).unwrap();
+        let block = block.close_and_lock(Some(&CommonParams::default_for_test())).unwrap();
+        *block.header().state_root()
+    }
+
+    #[test]
+    fn same_engine_never_diverges() {
+        let scheme = Scheme::new_test_solo();
+        let expected = close_block(Arc::clone(&scheme.engine), Scheme::new_test_solo());
+
+        let shadow = Arc::new(ShadowEngine::new(Scheme::new_test_solo().engine, Scheme::new_test_solo().engine));
+        assert_eq!(expected, close_block(Arc::clone(&shadow) as Arc<dyn CodeChainEngine>, Scheme::new_test_solo()));
+        assert!(shadow.verify_header_basic(&scheme.genesis_header()).is_ok());
+        assert_eq!(scheme.engine.block_reward(1), shadow.block_reward(1));
+        assert_eq!(0, shadow.divergences());
+    }
+
+    #[test]
+    fn divergent_candidate_takes_no_effect() {
+        let scheme = Scheme::new_test_solo();
+        let expected = close_block(Arc::clone(&scheme.engine), Scheme::new_test_solo());
+
+        let candidate = solo_with_block_reward(100);
+        assert_ne!(scheme.engine.block_reward(1), candidate.engine.block_reward(1));
+        let shadow = Arc::new(ShadowEngine::new(Scheme::new_test_solo().engine, candidate.engine));
+        assert_eq!(scheme.engine.block_reward(1), shadow.block_reward(1));
+        assert_eq!(1, shadow.divergences());
+
+        // Only the state root of the closed block differs.
+        assert_eq!(expected, close_block(Arc::clone(&shadow) as Arc<dyn CodeChainEngine>, Scheme::new_test_solo()));
+        assert_eq!(2, shadow.divergences());
+    }
+}
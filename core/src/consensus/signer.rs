@@ -24,6 +24,8 @@ use std::sync::Arc;
 pub struct EngineSigner {
     account_provider: Arc<AccountProvider>,
     signer: Option<(Address, Public)>,
+    /// The validator whose session key the signer is.
+    owner: Option<Address>,
     decrypted_account: Option<DecryptedAccount>,
 }
 
@@ -32,6 +34,7 @@ impl Default for EngineSigner {
         EngineSigner {
             account_provider: AccountProvider::transient_provider(),
             signer: Default::default(),
+            owner: Default::default(),
             decrypted_account: Default::default(),
         }
     }
@@ -50,6 +53,12 @@ impl EngineSigner {
         cinfo!(ENGINE, "Setting Engine signer to {} (retaining)", address);
     }
 
+    /// Signs for `owner` as its session key.
+    pub fn set_owner(&mut self, owner: Address) {
+        self.owner = Some(owner);
+        cinfo!(ENGINE, "Setting Engine signer as the session key of {}", owner);
+    }
+
     /// Sign a consensus message hash.
     pub fn sign(&self, hash: H256) -> Result<SchnorrSignature, AccountProviderError> {
         let address = self.signer.map(|(address, _public)| address).unwrap_or_else(Default::default);
@@ -86,6 +95,11 @@ impl EngineSigner {
         self.signer.as_ref().map(|(address, _)| address)
     }
 
+    /// Address of the validator that the signer signs for.
+    pub fn validator_address(&self) -> Option<&Address> {
+        self.owner.as_ref().or_else(|| self.address())
+    }
+
     /// Check if the given address is the signing address.
     pub fn is_address(&self, a: &Address) -> bool {
         self.signer.map_or(false, |(address, _public)| *a == address)
@@ -26,7 +26,7 @@ use crate::codechain_machine::CodeChainMachine;
 use crate::consensus::{EngineError, EngineType};
 use crate::error::Error;
 use ckey::Address;
-use cstate::{ActionHandler, HitHandler, TopStateView};
+use cstate::{ActionHandler, HitHandler, NameRegistry, TopStateView};
 use ctypes::{BlockHash, CommonParams, Header};
 use parking_lot::RwLock;
 use std::sync::{Arc, Weak};
@@ -47,7 +47,9 @@ impl Solo {
         if params.enable_hit_handler {
             action_handlers.push(Arc::new(HitHandler::new()));
         }
-        action_handlers.push(Arc::new(stake::Stake::new(params.genesis_stakes.clone())));
+        let fork_schedule = machine.fork_schedule().clone();
+        action_handlers.push(Arc::new(stake::Stake::new(params.genesis_stakes.clone(), fork_schedule)));
+        action_handlers.push(Arc::new(NameRegistry::new()));
 
         Solo {
             client: Default::default(),
@@ -19,11 +19,12 @@ use ckey::{public_to_address, Address, Public};
 use cstate::{ActionData, ActionDataKeyBuilder, StateResult, TopLevelState, TopState, TopStateView};
 use ctypes::errors::RuntimeError;
 use primitives::{Bytes, H256};
-use rlp::{decode_list, encode_list, Decodable, Encodable, Rlp, RlpStream};
+use rlp::{decode_list, encode_list, Decodable, DecoderError, Encodable, Rlp, RlpStream};
 use std::cmp::Ordering;
 use std::collections::btree_map::{BTreeMap, Entry};
 use std::collections::btree_set::{self, BTreeSet};
 use std::collections::{btree_map, HashMap, HashSet};
+use std::convert::TryFrom;
 use std::mem;
 use std::ops::Deref;
 use std::vec;
@@ -44,12 +45,36 @@ lazy_static! {
         ActionDataKeyBuilder::new(CUSTOM_ACTION_HANDLER_ID, 1).append(&"Validators").into_key();
     pub static ref CURRENT_VALIDATORS_KEY: H256 =
         ActionDataKeyBuilder::new(CUSTOM_ACTION_HANDLER_ID, 1).append(&"CurrentValidators").into_key();
+    pub static ref MISSES_KEY: H256 =
+        ActionDataKeyBuilder::new(CUSTOM_ACTION_HANDLER_ID, 1).append(&"Misses").into_key();
+    pub static ref PROPOSALS_KEY: H256 =
+        ActionDataKeyBuilder::new(CUSTOM_ACTION_HANDLER_ID, 1).append(&"Proposals").into_key();
 }
 
 pub fn get_delegation_key(address: &Address) -> H256 {
     ActionDataKeyBuilder::new(CUSTOM_ACTION_HANDLER_ID, 2).append(&"Delegation").append(address).into_key()
 }
 
+pub fn get_commission_key(address: &Address) -> H256 {
+    ActionDataKeyBuilder::new(CUSTOM_ACTION_HANDLER_ID, 2).append(&"Commission").append(address).into_key()
+}
+
+pub fn get_session_key_key(address: &Address) -> H256 {
+    ActionDataKeyBuilder::new(CUSTOM_ACTION_HANDLER_ID, 2).append(&"SessionKey").append(address).into_key()
+}
+
+pub fn get_session_owner_key(address: &Address) -> H256 {
+    ActionDataKeyBuilder::new(CUSTOM_ACTION_HANDLER_ID, 2).append(&"SessionOwner").append(address).into_key()
+}
+
+pub fn get_proposal_key(id: u64) -> H256 {
+    ActionDataKeyBuilder::new(CUSTOM_ACTION_HANDLER_ID, 2).append(&"Proposal").append(&id).into_key()
+}
+
+pub fn get_history_key(address: &Address, term: u64) -> H256 {
+    ActionDataKeyBuilder::new(CUSTOM_ACTION_HANDLER_ID, 3).append(&"History").append(address).append(&term).into_key()
+}
+
 pub fn get_intermediate_rewards_key() -> H256 {
     ActionDataKeyBuilder::new(CUSTOM_ACTION_HANDLER_ID, 1).append(&"IntermediateRewards").into_key()
 }
@@ -271,6 +296,10 @@ impl Validator {
     pub fn delegation(&self) -> StakeQuantity {
         self.delegation
     }
+
+    pub fn deposit(&self) -> Deposit {
+        self.deposit
+    }
 }
 
 #[derive(Debug)]
@@ -365,6 +394,22 @@ impl NextValidators {
         self.0.sort_unstable();
     }
 
+    /// The proposers of the next `count` blocks at view 0, supposing each of them proposes its block.
+    pub fn upcoming_proposers(&self, count: usize) -> Vec<Address> {
+        if self.0.is_empty() {
+            return Vec::new()
+        }
+        let mut validators = NextValidators(self.0.clone());
+        (0..count)
+            .map(|_| {
+                // The validators are in the ascending order, and the last one proposes at view 0.
+                let proposer = public_to_address(&validators.0.last().expect("Checked above").pubkey);
+                validators.update_weight(&proposer);
+                proposer
+            })
+            .collect()
+    }
+
     pub fn remove(&mut self, target: &Address) {
         self.0.retain(
             |Validator {
@@ -524,6 +569,14 @@ impl IntermediateRewards {
         *self.current.entry(address).or_insert(0) += quantity;
     }
 
+    pub fn current(&self) -> &BTreeMap<Address, u64> {
+        &self.current
+    }
+
+    pub fn calculated(&self) -> &BTreeMap<Address, u64> {
+        &self.calculated
+    }
+
     pub fn update_calculated(&mut self, rewards: BTreeMap<Address, u64>) {
         self.calculated = rewards;
     }
@@ -593,6 +646,10 @@ impl Candidates {
         self.0.iter().find(|c| public_to_address(&c.pubkey) == *account)
     }
 
+    pub fn total_deposit(&self) -> Deposit {
+        self.0.iter().map(|c| c.deposit).sum()
+    }
+
     #[cfg(test)]
     pub fn len(&self) -> usize {
         self.0.len()
@@ -710,6 +767,10 @@ impl Jail {
         Ok(())
     }
 
+    pub fn prisoners(&self) -> btree_map::Values<'_, Address, Prisoner> {
+        self.0.values()
+    }
+
     pub fn get_prisoner(&self, address: &Address) -> Option<&Prisoner> {
         self.0.get(address)
     }
@@ -784,6 +845,482 @@ impl Banned {
     pub fn is_banned(&self, address: &Address) -> bool {
         self.0.contains(address)
     }
+
+    pub fn iter(&self) -> btree_set::Iter<'_, Address> {
+        self.0.iter()
+    }
+}
+
+/// The share of the rewards that the validator takes before the rest goes to its delegators.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, RlpEncodable, RlpDecodable)]
+pub struct Commission {
+    /// Percentage of the rewards.
+    pub rate: u64,
+    /// The address receiving the commission.
+    pub destination: Address,
+}
+
+impl Commission {
+    pub fn load_from_state(state: &TopLevelState, validator: &Address) -> StateResult<Option<Self>> {
+        let key = get_commission_key(validator);
+        Ok(state.action_data(&key)?.map(|data| Rlp::new(&data).as_val().unwrap()))
+    }
+
+    pub fn save_to_state(&self, state: &mut TopLevelState, validator: &Address) -> StateResult<()> {
+        let key = get_commission_key(validator);
+        state.update_action_data(&key, rlp::encode(self))?;
+        Ok(())
+    }
+
+    pub fn split(&self, reward: u64) -> u64 {
+        u64::try_from(u128::from(reward) * u128::from(self.rate) / 100).unwrap()
+    }
+}
+
+/// The key signing the consensus messages for the validator, so that the key of the validator can be kept offline.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, RlpEncodable, RlpDecodable)]
+pub struct SessionKey {
+    pub pubkey: Public,
+    /// The last block whose votes are signed with the key.
+    pub expires_at: u64,
+}
+
+impl SessionKey {
+    pub fn load_from_state(state: &TopLevelState, validator: &Address) -> StateResult<Option<Self>> {
+        let key = get_session_key_key(validator);
+        Ok(state.action_data(&key)?.map(|data| Rlp::new(&data).as_val().unwrap()))
+    }
+
+    /// Replaces the session key of the validator.
+    pub fn save_to_state(&self, state: &mut TopLevelState, validator: &Address) -> StateResult<()> {
+        if let Some(previous) = Self::load_from_state(state, validator)? {
+            state.remove_action_data(&get_session_owner_key(&public_to_address(&previous.pubkey)));
+        }
+        state.update_action_data(&get_session_key_key(validator), rlp::encode(self))?;
+        state.update_action_data(&get_session_owner_key(&public_to_address(&self.pubkey)), rlp::encode(validator))?;
+        Ok(())
+    }
+
+    /// The validator that the session key of `address` signs for.
+    pub fn owner_of(state: &TopLevelState, address: &Address) -> StateResult<Option<Address>> {
+        let key = get_session_owner_key(address);
+        Ok(state.action_data(&key)?.map(|data| Rlp::new(&data).as_val().unwrap()))
+    }
+
+    pub fn is_active(&self, block_number: u64) -> bool {
+        block_number <= self.expires_at
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, RlpEncodable, RlpDecodable)]
+pub struct MissRecord {
+    pub proposed: u64,
+    pub missed_proposals: u64,
+    pub missed_votes: u64,
+}
+
+impl MissRecord {
+    /// The percentage of the missed ones among the proposals and the votes expected in `blocks` blocks.
+    pub fn rate(&self, blocks: u64) -> u64 {
+        let duties = blocks + self.proposed + self.missed_proposals;
+        if duties == 0 {
+            return 0
+        }
+        (self.missed_proposals + self.missed_votes) * 100 / duties
+    }
+}
+
+/// The proposals and the votes that the validators missed in the current term.
+#[derive(Debug, Default, PartialEq)]
+pub struct Misses {
+    blocks: u64,
+    records: BTreeMap<Address, MissRecord>,
+}
+
+impl Misses {
+    pub fn load_from_state(state: &TopLevelState) -> StateResult<Self> {
+        let key = *MISSES_KEY;
+        let misses = match state.action_data(&key)? {
+            Some(data) => {
+                let rlp = Rlp::new(&data);
+                assert_eq!(Ok(2), rlp.item_count());
+                Self {
+                    blocks: rlp.val_at(0).unwrap(),
+                    records: decode_map_impl(rlp.at(1).unwrap()),
+                }
+            }
+            None => Default::default(),
+        };
+        Ok(misses)
+    }
+
+    pub fn save_to_state(&self, state: &mut TopLevelState) -> StateResult<()> {
+        let key = *MISSES_KEY;
+        if self.blocks == 0 && self.records.is_empty() {
+            state.remove_action_data(&key);
+        } else {
+            let mut rlp = RlpStream::new();
+            rlp.begin_list(2).append(&self.blocks);
+            encode_map_impl(&mut rlp, &self.records);
+            state.update_action_data(&key, rlp.drain())?;
+        }
+        Ok(())
+    }
+
+    pub fn record_block(&mut self, author: Address, missed_proposers: &[Address], missed_voters: &[Address]) {
+        self.blocks += 1;
+        self.records.entry(author).or_default().proposed += 1;
+        for address in missed_proposers {
+            self.records.entry(*address).or_default().missed_proposals += 1;
+        }
+        for address in missed_voters {
+            self.records.entry(*address).or_default().missed_votes += 1;
+        }
+    }
+
+    pub fn blocks(&self) -> u64 {
+        self.blocks
+    }
+
+    pub fn get(&self, address: &Address) -> MissRecord {
+        self.records.get(address).cloned().unwrap_or_default()
+    }
+
+    pub fn iter(&self) -> btree_map::Iter<'_, Address, MissRecord> {
+        self.records.iter()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u8)]
+pub enum VoteOption {
+    Yes = 1,
+    No = 2,
+    Abstain = 3,
+}
+
+impl Encodable for VoteOption {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.append_single_value(&(*self as u8));
+    }
+}
+
+impl Decodable for VoteOption {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        match rlp.as_val()? {
+            1u8 => Ok(VoteOption::Yes),
+            2 => Ok(VoteOption::No),
+            3 => Ok(VoteOption::Abstain),
+            _ => Err(DecoderError::Custom("Unexpected VoteOption Value")),
+        }
+    }
+}
+
+/// The deposits of the voters at the deadline of the proposal.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, RlpEncodable, RlpDecodable)]
+pub struct Tally {
+    pub yes: Deposit,
+    pub no: Deposit,
+    pub abstain: Deposit,
+    /// The deposits of all the candidates including the ones who didn't vote.
+    pub total: Deposit,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Proposal {
+    pub proposer: Address,
+    pub text: String,
+    pub deadline: u64,
+    votes: BTreeMap<Address, VoteOption>,
+    tally: Option<Tally>,
+}
+
+impl Proposal {
+    pub fn new(proposer: Address, text: String, deadline: u64) -> Self {
+        Self {
+            proposer,
+            text,
+            deadline,
+            votes: Default::default(),
+            tally: None,
+        }
+    }
+
+    pub fn load_from_state(state: &TopLevelState, id: u64) -> StateResult<Option<Self>> {
+        let key = get_proposal_key(id);
+        Ok(state.action_data(&key)?.map(|data| {
+            let rlp = Rlp::new(&data);
+            assert_eq!(Ok(5), rlp.item_count());
+            Self {
+                proposer: rlp.val_at(0).unwrap(),
+                text: rlp.val_at(1).unwrap(),
+                deadline: rlp.val_at(2).unwrap(),
+                votes: decode_map_impl(rlp.at(3).unwrap()),
+                tally: rlp.list_at::<Tally>(4).unwrap().into_iter().next(),
+            }
+        }))
+    }
+
+    pub fn save_to_state(&self, state: &mut TopLevelState, id: u64) -> StateResult<()> {
+        let key = get_proposal_key(id);
+        let mut rlp = RlpStream::new();
+        rlp.begin_list(5).append(&self.proposer).append(&self.text).append(&self.deadline);
+        encode_map_impl(&mut rlp, &self.votes);
+        rlp.append_list(&self.tally.iter().cloned().collect::<Vec<_>>());
+        state.update_action_data(&key, rlp.drain())?;
+        Ok(())
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.tally.is_none()
+    }
+
+    /// Records the vote. A later vote of the same voter replaces the former one.
+    pub fn vote(&mut self, voter: Address, option: VoteOption) {
+        debug_assert!(self.is_open());
+        self.votes.insert(voter, option);
+    }
+
+    pub fn votes(&self) -> btree_map::Iter<'_, Address, VoteOption> {
+        self.votes.iter()
+    }
+
+    pub fn tally(&self) -> Option<&Tally> {
+        self.tally.as_ref()
+    }
+
+    /// Weights the votes by the current deposits of the voters and closes the proposal.
+    /// The voters who are not candidates anymore have no weight.
+    pub fn close(&mut self, candidates: &Candidates) {
+        let mut tally = Tally {
+            total: candidates.total_deposit(),
+            ..Default::default()
+        };
+        for (voter, option) in &self.votes {
+            let deposit = candidates.get_candidate(voter).map_or(0, |candidate| candidate.deposit);
+            match option {
+                VoteOption::Yes => tally.yes += deposit,
+                VoteOption::No => tally.no += deposit,
+                VoteOption::Abstain => tally.abstain += deposit,
+            }
+        }
+        self.tally = Some(tally);
+    }
+}
+
+#[derive(Clone, Copy)]
+#[repr(u8)]
+enum StakeEventTag {
+    Delegated = 1,
+    Revoked = 2,
+    Redelegated = 3,
+    Reverted = 4,
+    Rewarded = 5,
+}
+
+/// The change of the stakes or the balance of an account, recorded for the history.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StakeEvent {
+    Delegated {
+        delegatee: Address,
+        quantity: StakeQuantity,
+    },
+    Revoked {
+        delegatee: Address,
+        quantity: StakeQuantity,
+    },
+    Redelegated {
+        prev_delegatee: Address,
+        next_delegatee: Address,
+        quantity: StakeQuantity,
+    },
+    /// The delegation returned because the delegatee expired, got released from the jail or got banned.
+    Reverted {
+        delegatee: Address,
+        quantity: StakeQuantity,
+    },
+    /// The reward paid at the end of the term from the block rewards of the validator.
+    Rewarded {
+        validator: Address,
+        quantity: u64,
+    },
+}
+
+impl Encodable for StakeEvent {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        match self {
+            StakeEvent::Delegated {
+                delegatee,
+                quantity,
+            } => {
+                s.begin_list(3).append(&(StakeEventTag::Delegated as u8)).append(delegatee).append(quantity);
+            }
+            StakeEvent::Revoked {
+                delegatee,
+                quantity,
+            } => {
+                s.begin_list(3).append(&(StakeEventTag::Revoked as u8)).append(delegatee).append(quantity);
+            }
+            StakeEvent::Redelegated {
+                prev_delegatee,
+                next_delegatee,
+                quantity,
+            } => {
+                s.begin_list(4)
+                    .append(&(StakeEventTag::Redelegated as u8))
+                    .append(prev_delegatee)
+                    .append(next_delegatee)
+                    .append(quantity);
+            }
+            StakeEvent::Reverted {
+                delegatee,
+                quantity,
+            } => {
+                s.begin_list(3).append(&(StakeEventTag::Reverted as u8)).append(delegatee).append(quantity);
+            }
+            StakeEvent::Rewarded {
+                validator,
+                quantity,
+            } => {
+                s.begin_list(3).append(&(StakeEventTag::Rewarded as u8)).append(validator).append(quantity);
+            }
+        }
+    }
+}
+
+impl Decodable for StakeEvent {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        let tag: u8 = rlp.val_at(0)?;
+        let expected = if tag == StakeEventTag::Redelegated as u8 {
+            4
+        } else {
+            3
+        };
+        let item_count = rlp.item_count()?;
+        if item_count != expected {
+            return Err(DecoderError::RlpInvalidLength {
+                expected,
+                got: item_count,
+            })
+        }
+        match tag {
+            1 => Ok(StakeEvent::Delegated {
+                delegatee: rlp.val_at(1)?,
+                quantity: rlp.val_at(2)?,
+            }),
+            2 => Ok(StakeEvent::Revoked {
+                delegatee: rlp.val_at(1)?,
+                quantity: rlp.val_at(2)?,
+            }),
+            3 => Ok(StakeEvent::Redelegated {
+                prev_delegatee: rlp.val_at(1)?,
+                next_delegatee: rlp.val_at(2)?,
+                quantity: rlp.val_at(3)?,
+            }),
+            4 => Ok(StakeEvent::Reverted {
+                delegatee: rlp.val_at(1)?,
+                quantity: rlp.val_at(2)?,
+            }),
+            5 => Ok(StakeEvent::Rewarded {
+                validator: rlp.val_at(1)?,
+                quantity: rlp.val_at(2)?,
+            }),
+            _ => Err(DecoderError::Custom("Unexpected StakeEventTag Value")),
+        }
+    }
+}
+
+/// The stake events of an account in a term, in the order they happened.
+#[derive(Debug, Default, PartialEq)]
+pub struct History(Vec<StakeEvent>);
+
+impl History {
+    pub fn load_from_state(state: &TopLevelState, address: &Address, term: u64) -> StateResult<Self> {
+        let key = get_history_key(address, term);
+        let events = state.action_data(&key)?.map(|data| decode_list(&data)).unwrap_or_default();
+        Ok(History(events))
+    }
+
+    pub fn save_to_state(&self, state: &mut TopLevelState, address: &Address, term: u64) -> StateResult<()> {
+        let key = get_history_key(address, term);
+        if !self.0.is_empty() {
+            state.update_action_data(&key, encode_list(&self.0).to_vec())?;
+        } else {
+            state.remove_action_data(&key);
+        }
+        Ok(())
+    }
+
+    pub fn push(&mut self, event: StakeEvent) {
+        self.0.push(event);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl IntoIterator for History {
+    type Item = StakeEvent;
+    type IntoIter = vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+/// The number of the proposals ever created and the ids of the ones not tallied yet.
+/// The ids are given in the order of creation from 0.
+#[derive(Debug, Default, PartialEq)]
+pub struct Proposals {
+    count: u64,
+    open: BTreeSet<u64>,
+}
+
+impl Proposals {
+    pub fn load_from_state(state: &TopLevelState) -> StateResult<Self> {
+        let key = *PROPOSALS_KEY;
+        let proposals = match state.action_data(&key)? {
+            Some(data) => {
+                let rlp = Rlp::new(&data);
+                assert_eq!(Ok(2), rlp.item_count());
+                Self {
+                    count: rlp.val_at(0).unwrap(),
+                    open: rlp.list_at::<u64>(1).unwrap().into_iter().collect(),
+                }
+            }
+            None => Default::default(),
+        };
+        Ok(proposals)
+    }
+
+    pub fn save_to_state(&self, state: &mut TopLevelState) -> StateResult<()> {
+        let key = *PROPOSALS_KEY;
+        let mut rlp = RlpStream::new();
+        rlp.begin_list(2).append(&self.count).append_list(&self.open.iter().cloned().collect::<Vec<_>>());
+        state.update_action_data(&key, rlp.drain())?;
+        Ok(())
+    }
+
+    /// Returns the id of the new proposal.
+    pub fn add(&mut self) -> u64 {
+        let id = self.count;
+        self.count += 1;
+        self.open.insert(id);
+        id
+    }
+
+    pub fn close(&mut self, id: u64) {
+        assert!(self.open.remove(&id), "The proposal {} is not open", id);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn open(&self) -> btree_set::Iter<'_, u64> {
+        self.open.iter()
+    }
 }
 
 fn decode_set<V>(data: Option<&ActionData>) -> BTreeSet<V>
@@ -907,6 +1444,28 @@ mod tests {
         XorShiftRng::from_seed(seed)
     }
 
+    #[test]
+    fn history_save_and_load() {
+        let mut state = helpers::get_temp_state();
+        let address = Address::random();
+        let validator = Address::random();
+
+        let mut history = History::load_from_state(&state, &address, 3).unwrap();
+        assert!(history.is_empty());
+        history.push(StakeEvent::Rewarded {
+            validator,
+            quantity: 10,
+        });
+        history.save_to_state(&mut state, &address, 3).unwrap();
+
+        let history = History::load_from_state(&state, &address, 3).unwrap();
+        assert_eq!(history.into_iter().collect::<Vec<_>>(), vec![StakeEvent::Rewarded {
+            validator,
+            quantity: 10,
+        }]);
+        assert!(History::load_from_state(&state, &address, 4).unwrap().is_empty());
+    }
+
     #[test]
     fn default_balance_is_zero() {
         let state = helpers::get_temp_state();
@@ -1858,4 +2417,40 @@ mod tests {
             pubkeys.iter().map(|pubkey| candidates.get_index(&public_to_address(pubkey)).unwrap()).collect();
         assert_eq!(indexes, vec![5, 6, 7, 8, 9, 0, 1, 2, 3, 4]);
     }
+
+    #[test]
+    fn upcoming_proposers_follow_the_weights() {
+        let (major, minor) = (Public::random(), Public::random());
+        let validators =
+            NextValidators(vec![Validator::new_for_test(1, 0, minor), Validator::new_for_test(3, 0, major)]);
+        let (major, minor) = (public_to_address(&major), public_to_address(&minor));
+        assert_eq!(vec![major, major, major, minor, major, major, major, minor], validators.upcoming_proposers(8));
+        assert_eq!(Vec::<Address>::new(), NextValidators(vec![]).upcoming_proposers(8));
+    }
+
+    #[test]
+    fn record_misses() {
+        let author = Address::random();
+        let absent = Address::random();
+        let mut state = helpers::get_temp_state();
+        let mut misses = Misses::load_from_state(&state).unwrap();
+        misses.record_block(author, &[absent], &[absent]);
+        misses.record_block(author, &[], &[absent]);
+        misses.save_to_state(&mut state).unwrap();
+
+        let recovered = Misses::load_from_state(&state).unwrap();
+        assert_eq!(misses, recovered);
+        assert_eq!(2, recovered.blocks());
+        assert_eq!(
+            MissRecord {
+                proposed: 2,
+                missed_proposals: 0,
+                missed_votes: 0,
+            },
+            recovered.get(&author)
+        );
+        // 3 missed among 2 votes and 1 proposal
+        assert_eq!(100, recovered.get(&absent).rate(recovered.blocks()));
+        assert_eq!(0, recovered.get(&author).rate(recovered.blocks()));
+    }
 }
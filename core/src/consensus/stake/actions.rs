@@ -14,16 +14,22 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use super::action_data::VoteOption;
 use crate::client::ConsensusClient;
 use crate::consensus::{ConsensusMessage, ValidatorSet};
 use ccrypto::Blake;
-use ckey::{recover, Address, Signature};
+use ckey::{recover, Address, Public, Signature};
 use ctypes::errors::SyntaxError;
-use ctypes::CommonParams;
+use ctypes::{
+    BlockNumber, CommonParams, COMMISSION_FORK, LIVENESS_FORK, PROPOSAL_FORK, SCHEDULE_PARAMS_FORK, SESSION_KEY_FORK,
+};
 use primitives::{Bytes, H256};
 use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
 use std::sync::Arc;
 
+pub const MAX_COMMISSION_RATE: u64 = 100;
+pub const MAX_PROPOSAL_TEXT_SIZE: usize = 1024;
+
 #[derive(Clone, Copy)]
 #[repr(u8)]
 enum ActionTag {
@@ -33,6 +39,12 @@ enum ActionTag {
     SelfNominate = 4,
     ReportDoubleVote = 5,
     Redelegate = 6,
+    Unjail = 7,
+    SetCommission = 8,
+    CreateProposal = 9,
+    VoteProposal = 10,
+    SetSessionKey = 11,
+    ScheduleParams = 0xFE,
     ChangeParams = 0xFF,
 }
 
@@ -52,6 +64,12 @@ impl Decodable for ActionTag {
             4 => Ok(ActionTag::SelfNominate),
             5 => Ok(ActionTag::ReportDoubleVote),
             6 => Ok(ActionTag::Redelegate),
+            7 => Ok(ActionTag::Unjail),
+            8 => Ok(ActionTag::SetCommission),
+            9 => Ok(ActionTag::CreateProposal),
+            10 => Ok(ActionTag::VoteProposal),
+            11 => Ok(ActionTag::SetSessionKey),
+            0xFE => Ok(ActionTag::ScheduleParams),
             0xFF => Ok(ActionTag::ChangeParams),
             _ => Err(DecoderError::Custom("Unexpected ActionTag Value")),
         }
@@ -81,11 +99,42 @@ pub enum Action {
         deposit: u64,
         metadata: Bytes,
     },
+    /// Returns the jailed account to the candidates with its deposit after the custody period.
+    Unjail,
+    /// Takes `rate` percent of the rewards to `destination` and gives the rest to the delegators.
+    SetCommission {
+        rate: u64,
+        destination: Address,
+    },
+    /// Creates a text proposal, which is tallied at the end of the deadline block.
+    CreateProposal {
+        text: String,
+        deadline: BlockNumber,
+    },
+    /// Votes on the open proposal. The vote is weighted by the deposit of the voter at the deadline.
+    VoteProposal {
+        id: u64,
+        option: VoteOption,
+    },
+    /// Lets `pubkey` sign the consensus messages for the sender until the `expires_at` block.
+    /// It replaces the previous session key of the sender.
+    SetSessionKey {
+        pubkey: Public,
+        expires_at: BlockNumber,
+    },
     ChangeParams {
         metadata_seq: u64,
         params: Box<CommonParams>,
         signatures: Vec<Signature>,
     },
+    /// Changes the params at the end of the activation block.
+    /// It must be signed by the validators who have more than 2/3 of the delegations.
+    ScheduleParams {
+        metadata_seq: u64,
+        activation: BlockNumber,
+        params: Box<CommonParams>,
+        signatures: Vec<Signature>,
+    },
     // TODO: ConsensusMessage is tied to the Tendermint
     ReportDoubleVote {
         message1: Box<ConsensusMessage>,
@@ -94,6 +143,29 @@ pub enum Action {
 }
 
 impl Action {
+    /// Returns the fork which enables the action, if any.
+    pub fn fork(&self) -> Option<&'static str> {
+        match self {
+            Action::Unjail => Some(LIVENESS_FORK),
+            Action::SetCommission {
+                ..
+            } => Some(COMMISSION_FORK),
+            Action::CreateProposal {
+                ..
+            }
+            | Action::VoteProposal {
+                ..
+            } => Some(PROPOSAL_FORK),
+            Action::SetSessionKey {
+                ..
+            } => Some(SESSION_KEY_FORK),
+            Action::ScheduleParams {
+                ..
+            } => Some(SCHEDULE_PARAMS_FORK),
+            _ => None,
+        }
+    }
+
     pub fn verify(
         &self,
         current_params: &CommonParams,
@@ -113,6 +185,38 @@ impl Action {
             Action::Redelegate {
                 ..
             } => {}
+            Action::Unjail => {}
+            Action::SetCommission {
+                rate,
+                ..
+            } => {
+                if *rate > MAX_COMMISSION_RATE {
+                    return Err(SyntaxError::InvalidCustomAction(format!(
+                        "Too high commission rate: the limit is {}",
+                        MAX_COMMISSION_RATE
+                    )))
+                }
+            }
+            Action::CreateProposal {
+                text,
+                ..
+            } => {
+                if text.is_empty() {
+                    return Err(SyntaxError::InvalidCustomAction("Empty proposal text".to_string()))
+                }
+                if text.len() > MAX_PROPOSAL_TEXT_SIZE {
+                    return Err(SyntaxError::InvalidCustomAction(format!(
+                        "Too long proposal text: the size limit is {}",
+                        MAX_PROPOSAL_TEXT_SIZE
+                    )))
+                }
+            }
+            Action::VoteProposal {
+                ..
+            } => {}
+            Action::SetSessionKey {
+                ..
+            } => {}
             Action::SelfNominate {
                 metadata,
                 ..
@@ -143,6 +247,26 @@ impl Action {
                     })?;
                 }
             }
+            Action::ScheduleParams {
+                metadata_seq,
+                activation,
+                params,
+                signatures,
+            } => {
+                params.verify_change(current_params).map_err(SyntaxError::InvalidCustomAction)?;
+                let action = Action::ScheduleParams {
+                    metadata_seq: *metadata_seq,
+                    activation: *activation,
+                    params: params.clone(),
+                    signatures: vec![],
+                };
+                let encoded_action = H256::blake(rlp::encode(&action));
+                for signature in signatures {
+                    recover(&signature, &encoded_action).map_err(|err| {
+                        SyntaxError::InvalidCustomAction(format!("Cannot decode the signature: {}", err))
+                    })?;
+                }
+            }
             Action::ReportDoubleVote {
                 message1,
                 message2,
@@ -238,6 +362,33 @@ impl Encodable for Action {
             } => {
                 s.begin_list(3).append(&ActionTag::SelfNominate).append(deposit).append(metadata);
             }
+            Action::Unjail => {
+                s.begin_list(1).append(&ActionTag::Unjail);
+            }
+            Action::SetCommission {
+                rate,
+                destination,
+            } => {
+                s.begin_list(3).append(&ActionTag::SetCommission).append(rate).append(destination);
+            }
+            Action::CreateProposal {
+                text,
+                deadline,
+            } => {
+                s.begin_list(3).append(&ActionTag::CreateProposal).append(text).append(deadline);
+            }
+            Action::VoteProposal {
+                id,
+                option,
+            } => {
+                s.begin_list(3).append(&ActionTag::VoteProposal).append(id).append(option);
+            }
+            Action::SetSessionKey {
+                pubkey,
+                expires_at,
+            } => {
+                s.begin_list(3).append(&ActionTag::SetSessionKey).append(pubkey).append(expires_at);
+            }
             Action::ChangeParams {
                 metadata_seq,
                 params,
@@ -251,6 +402,21 @@ impl Encodable for Action {
                     s.append(signature);
                 }
             }
+            Action::ScheduleParams {
+                metadata_seq,
+                activation,
+                params,
+                signatures,
+            } => {
+                s.begin_list(4 + signatures.len())
+                    .append(&ActionTag::ScheduleParams)
+                    .append(metadata_seq)
+                    .append(activation)
+                    .append(&**params);
+                for signature in signatures {
+                    s.append(signature);
+                }
+            }
             Action::ReportDoubleVote {
                 message1,
                 message2,
@@ -321,6 +487,68 @@ impl Decodable for Action {
                     quantity: rlp.val_at(3)?,
                 })
             }
+            ActionTag::Unjail => {
+                let item_count = rlp.item_count()?;
+                if item_count != 1 {
+                    return Err(DecoderError::RlpInvalidLength {
+                        expected: 1,
+                        got: item_count,
+                    })
+                }
+                Ok(Action::Unjail)
+            }
+            ActionTag::SetCommission => {
+                let item_count = rlp.item_count()?;
+                if item_count != 3 {
+                    return Err(DecoderError::RlpInvalidLength {
+                        expected: 3,
+                        got: item_count,
+                    })
+                }
+                Ok(Action::SetCommission {
+                    rate: rlp.val_at(1)?,
+                    destination: rlp.val_at(2)?,
+                })
+            }
+            ActionTag::CreateProposal => {
+                let item_count = rlp.item_count()?;
+                if item_count != 3 {
+                    return Err(DecoderError::RlpInvalidLength {
+                        expected: 3,
+                        got: item_count,
+                    })
+                }
+                Ok(Action::CreateProposal {
+                    text: rlp.val_at(1)?,
+                    deadline: rlp.val_at(2)?,
+                })
+            }
+            ActionTag::VoteProposal => {
+                let item_count = rlp.item_count()?;
+                if item_count != 3 {
+                    return Err(DecoderError::RlpInvalidLength {
+                        expected: 3,
+                        got: item_count,
+                    })
+                }
+                Ok(Action::VoteProposal {
+                    id: rlp.val_at(1)?,
+                    option: rlp.val_at(2)?,
+                })
+            }
+            ActionTag::SetSessionKey => {
+                let item_count = rlp.item_count()?;
+                if item_count != 3 {
+                    return Err(DecoderError::RlpInvalidLength {
+                        expected: 3,
+                        got: item_count,
+                    })
+                }
+                Ok(Action::SetSessionKey {
+                    pubkey: rlp.val_at(1)?,
+                    expires_at: rlp.val_at(2)?,
+                })
+            }
             ActionTag::SelfNominate => {
                 let item_count = rlp.item_count()?;
                 if item_count != 3 {
@@ -351,6 +579,25 @@ impl Decodable for Action {
                     signatures,
                 })
             }
+            ActionTag::ScheduleParams => {
+                let item_count = rlp.item_count()?;
+                if item_count < 5 {
+                    return Err(DecoderError::RlpIncorrectListLen {
+                        expected: 5,
+                        got: item_count,
+                    })
+                }
+                let metadata_seq = rlp.val_at(1)?;
+                let activation = rlp.val_at(2)?;
+                let params = Box::new(rlp.val_at(3)?);
+                let signatures = (4..item_count).map(|i| rlp.val_at(i)).collect::<Result<_, _>>()?;
+                Ok(Action::ScheduleParams {
+                    metadata_seq,
+                    activation,
+                    params,
+                    signatures,
+                })
+            }
             ActionTag::ReportDoubleVote => {
                 let item_count = rlp.item_count()?;
                 if item_count != 3 {
@@ -395,6 +642,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn rlp_of_unjail() {
+        rlp_encode_and_decode_test!(Action::Unjail);
+    }
+
+    #[test]
+    fn rlp_of_set_commission() {
+        rlp_encode_and_decode_test!(Action::SetCommission {
+            rate: 10,
+            destination: Address::random(),
+        });
+    }
+
+    #[test]
+    fn verify_fails_if_commission_rate_is_too_high() {
+        let action = Action::SetCommission {
+            rate: MAX_COMMISSION_RATE + 1,
+            destination: Address::random(),
+        };
+        assert!(action.verify(&CommonParams::default_for_test(), None, None).is_err());
+    }
+
+    #[test]
+    fn rlp_of_create_proposal() {
+        rlp_encode_and_decode_test!(Action::CreateProposal {
+            text: "Upgrade to 2.0 at block 1000".to_string(),
+            deadline: 500,
+        });
+    }
+
+    #[test]
+    fn rlp_of_vote_proposal() {
+        rlp_encode_and_decode_test!(Action::VoteProposal {
+            id: 3,
+            option: VoteOption::Abstain,
+        });
+    }
+
+    #[test]
+    fn rlp_of_set_session_key() {
+        rlp_encode_and_decode_test!(Action::SetSessionKey {
+            pubkey: Public::random(),
+            expires_at: 1000,
+        });
+    }
+
+    #[test]
+    fn verify_fails_if_proposal_text_is_too_long() {
+        let action = Action::CreateProposal {
+            text: "a".repeat(MAX_PROPOSAL_TEXT_SIZE + 1),
+            deadline: 500,
+        };
+        assert!(action.verify(&CommonParams::default_for_test(), None, None).is_err());
+    }
+
     #[test]
     fn rlp_of_change_params() {
         rlp_encode_and_decode_test!(Action::ChangeParams {
@@ -404,6 +706,33 @@ mod tests {
         });
     }
 
+    #[test]
+    fn decode_fail_if_schedule_params_have_no_signatures() {
+        let action = Action::ScheduleParams {
+            metadata_seq: 3,
+            activation: 100,
+            params: CommonParams::default_for_test().into(),
+            signatures: vec![],
+        };
+        assert_eq!(
+            Err(DecoderError::RlpIncorrectListLen {
+                expected: 5,
+                got: 4,
+            }),
+            Rlp::new(&rlp::encode(&action)).as_val::<Action>()
+        );
+    }
+
+    #[test]
+    fn rlp_of_schedule_params() {
+        rlp_encode_and_decode_test!(Action::ScheduleParams {
+            metadata_seq: 3,
+            activation: 100,
+            params: CommonParams::default_for_test().into(),
+            signatures: vec![Signature::random(), Signature::random()],
+        });
+    }
+
     struct ConsensusMessageInfo {
         pub height: u64,
         pub view: u64,
@@ -24,20 +24,23 @@ use ckey::{public_to_address, recover, Address, Public, Signature};
 use cstate::{ActionHandler, StateResult, TopLevelState, TopState, TopStateView};
 use ctypes::errors::{RuntimeError, SyntaxError};
 use ctypes::util::unexpected::Mismatch;
-use ctypes::{CommonParams, Header};
+use ctypes::{BlockNumber, CommonParams, ForkSchedule, Header, SCHEDULE_PARAMS_FORK};
 use parking_lot::RwLock;
 use primitives::{Bytes, H256};
 use rlp::{Decodable, Rlp};
 use std::collections::btree_map::BTreeMap;
+use std::collections::BTreeSet;
 use std::collections::hash_map::RandomState;
 use std::collections::HashMap;
 use std::sync::{Arc, Weak};
 
 pub use self::action_data::{
-    Banned, Candidates, CurrentValidators, Jail, NextValidators, PreviousValidators, Validator,
+    Banned, Candidate, Candidates, Commission, CurrentValidators, Delegation, History, IntermediateRewards, Jail,
+    MissRecord, Misses, NextValidators, PreviousValidators, Prisoner, Proposal, Proposals, SessionKey, StakeAccount,
+    StakeEvent, Tally, Validator, VoteOption,
 };
-use self::action_data::{Delegation, IntermediateRewards, ReleaseResult, StakeAccount, Stakeholders};
-pub use self::actions::Action;
+use self::action_data::{ReleaseResult, Stakeholders};
+pub use self::actions::{Action, MAX_COMMISSION_RATE, MAX_PROPOSAL_TEXT_SIZE};
 pub use self::distribute::fee_distribute;
 use super::ValidatorSet;
 
@@ -45,14 +48,16 @@ pub const CUSTOM_ACTION_HANDLER_ID: u64 = 2;
 
 pub struct Stake {
     genesis_stakes: HashMap<Address, u64>,
+    fork_schedule: ForkSchedule,
     client: RwLock<Option<Weak<dyn ConsensusClient>>>,
     validators: RwLock<Option<Weak<dyn ValidatorSet>>>,
 }
 
 impl Stake {
-    pub fn new(genesis_stakes: HashMap<Address, u64>) -> Stake {
+    pub fn new(genesis_stakes: HashMap<Address, u64>, fork_schedule: ForkSchedule) -> Stake {
         Stake {
             genesis_stakes,
+            fork_schedule,
             client: Default::default(),
             validators: Default::default(),
         }
@@ -92,6 +97,7 @@ impl ActionHandler for Stake {
         state: &mut TopLevelState,
         fee_payer: &Address,
         sender_public: &Public,
+        current_block_number: BlockNumber,
     ) -> StateResult<()> {
         let action = Action::decode(&Rlp::new(bytes)).expect("Verification passed");
         match action {
@@ -129,11 +135,45 @@ impl ActionHandler for Stake {
                 };
                 self_nominate(state, fee_payer, sender_public, deposit, current_term, nomination_ends_at, metadata)
             }
+            Action::Unjail => {
+                let (current_term, nomination_ends_at) = {
+                    let metadata = state.metadata()?.expect("Metadata must exist");
+                    let current_term = metadata.current_term_id();
+                    let expiration = metadata.params().expect("Only candidates can be jailed").nomination_expiration();
+                    (current_term, current_term + expiration)
+                };
+                unjail(state, fee_payer, sender_public, current_term, nomination_ends_at)
+            }
+            Action::SetCommission {
+                rate,
+                destination,
+            } => set_commission(state, fee_payer, sender_public, rate, destination),
+            Action::CreateProposal {
+                text,
+                deadline,
+            } => create_proposal(state, fee_payer, sender_public, text, deadline),
+            Action::VoteProposal {
+                id,
+                option,
+            } => vote_proposal(state, fee_payer, sender_public, id, option),
+            Action::SetSessionKey {
+                pubkey,
+                expires_at,
+            } => set_session_key(state, fee_payer, sender_public, pubkey, expires_at),
             Action::ChangeParams {
                 metadata_seq,
                 params,
                 signatures,
-            } => change_params(state, metadata_seq, *params, &signatures),
+            } => {
+                let distinct_signers = self.fork_schedule.is_active(SCHEDULE_PARAMS_FORK, current_block_number);
+                change_params(state, metadata_seq, *params, &signatures, distinct_signers)
+            }
+            Action::ScheduleParams {
+                metadata_seq,
+                activation,
+                params,
+                signatures,
+            } => schedule_params(state, metadata_seq, activation, *params, &signatures, current_block_number),
             Action::ReportDoubleVote {
                 message1,
                 ..
@@ -143,9 +183,9 @@ impl ActionHandler for Stake {
                 let client = self.client.read().as_ref().and_then(Weak::upgrade).expect("Client must be initialized");
                 let parent_hash =
                     client.block_header(&(message1.height() - 1).into()).expect("Parent header verified").hash();
-                let malicious_user_public = validator_set.get(&parent_hash, message1.signer_index());
+                let malicious_user = validator_set.get_address(&parent_hash, message1.signer_index());
 
-                ban(state, sender_public, public_to_address(&malicious_user_public))
+                ban(state, sender_public, malicious_user)
             }
         }
     }
@@ -158,8 +198,9 @@ impl ActionHandler for Stake {
         action.verify(current_params, client, validators)
     }
 
-    fn on_close_block(&self, _state: &mut TopLevelState, _header: &Header) -> StateResult<()> {
-        Ok(())
+    fn on_close_block(&self, state: &mut TopLevelState, header: &Header) -> StateResult<()> {
+        state.activate_scheduled_params(header.number())?;
+        close_proposals(state, header.number())
     }
 }
 
@@ -203,6 +244,10 @@ fn delegate_ccs(state: &mut TopLevelState, fee_payer: &Address, delegatee: &Addr
 
     delegation.save_to_state(state)?;
     delegator.save_to_state(state)?;
+    record_history(state, fee_payer, StakeEvent::Delegated {
+        delegatee: *delegatee,
+        quantity,
+    })?;
 
     ctrace!(ENGINE, "Delegated CCS. delegator: {}, delegatee: {}, quantity: {}", fee_payer, delegatee, quantity);
     Ok(())
@@ -218,6 +263,10 @@ fn revoke(state: &mut TopLevelState, fee_payer: &Address, delegatee: &Address, q
 
     delegation.save_to_state(state)?;
     delegator.save_to_state(state)?;
+    record_history(state, fee_payer, StakeEvent::Revoked {
+        delegatee: *delegatee,
+        quantity,
+    })?;
 
     ctrace!(ENGINE, "Revoked CCS. delegator: {}, delegatee: {}, quantity: {}", fee_payer, delegatee, quantity);
     Ok(())
@@ -248,6 +297,11 @@ fn redelegate(
 
     delegation.save_to_state(state)?;
     delegator.save_to_state(state)?;
+    record_history(state, fee_payer, StakeEvent::Redelegated {
+        prev_delegatee: *prev_delegatee,
+        next_delegatee: *next_delegatee,
+        quantity,
+    })?;
 
     ctrace!(
         ENGINE,
@@ -308,6 +362,183 @@ fn self_nominate(
     Ok(())
 }
 
+fn unjail(
+    state: &mut TopLevelState,
+    fee_payer: &Address,
+    sender_public: &Public,
+    current_term: u64,
+    nomination_ends_at: u64,
+) -> StateResult<()> {
+    if public_to_address(sender_public) != *fee_payer {
+        return Err(RuntimeError::FailedToHandleCustomAction("Cannot unjail with regular key".to_string()).into())
+    }
+
+    let mut jail = Jail::load_from_state(&state)?;
+    let prisoner = match jail.try_release(fee_payer, current_term) {
+        ReleaseResult::InCustody => {
+            return Err(RuntimeError::FailedToHandleCustomAction("Account is still in custody".to_string()).into())
+        }
+        ReleaseResult::NotExists => {
+            return Err(RuntimeError::FailedToHandleCustomAction("Account is not jailed".to_string()).into())
+        }
+        ReleaseResult::Released(prisoner) => prisoner,
+    };
+
+    let mut candidates = Candidates::load_from_state(&state)?;
+    candidates.add_deposit(sender_public, prisoner.deposit, nomination_ends_at, Bytes::new());
+
+    jail.save_to_state(state)?;
+    candidates.save_to_state(state)?;
+
+    ctrace!(
+        ENGINE,
+        "Unjailed. candidate: {}, deposit: {}, current_term: {}, ends_at: {}",
+        fee_payer,
+        prisoner.deposit,
+        current_term,
+        nomination_ends_at
+    );
+    Ok(())
+}
+
+fn set_commission(
+    state: &mut TopLevelState,
+    fee_payer: &Address,
+    sender_public: &Public,
+    rate: u64,
+    destination: Address,
+) -> StateResult<()> {
+    if public_to_address(sender_public) != *fee_payer {
+        return Err(
+            RuntimeError::FailedToHandleCustomAction("Cannot set the commission with regular key".to_string()).into()
+        )
+    }
+    let commission = Commission {
+        rate,
+        destination,
+    };
+    commission.save_to_state(state, fee_payer)?;
+
+    ctrace!(ENGINE, "Commission set. validator: {}, rate: {}, destination: {}", fee_payer, rate, destination);
+    Ok(())
+}
+
+fn create_proposal(
+    state: &mut TopLevelState,
+    fee_payer: &Address,
+    sender_public: &Public,
+    text: String,
+    deadline: BlockNumber,
+) -> StateResult<()> {
+    if public_to_address(sender_public) != *fee_payer {
+        return Err(RuntimeError::FailedToHandleCustomAction("Cannot propose with regular key".to_string()).into())
+    }
+    let candidates = Candidates::load_from_state(state)?;
+    if candidates.get_candidate(fee_payer).is_none() {
+        return Err(RuntimeError::FailedToHandleCustomAction("Only candidates can propose".to_string()).into())
+    }
+
+    let mut proposals = Proposals::load_from_state(state)?;
+    let id = proposals.add();
+    Proposal::new(*fee_payer, text, deadline).save_to_state(state, id)?;
+    proposals.save_to_state(state)?;
+
+    ctrace!(ENGINE, "Proposal created. id: {}, proposer: {}, deadline: {}", id, fee_payer, deadline);
+    Ok(())
+}
+
+fn vote_proposal(
+    state: &mut TopLevelState,
+    fee_payer: &Address,
+    sender_public: &Public,
+    id: u64,
+    option: VoteOption,
+) -> StateResult<()> {
+    if public_to_address(sender_public) != *fee_payer {
+        return Err(RuntimeError::FailedToHandleCustomAction("Cannot vote with regular key".to_string()).into())
+    }
+    let candidates = Candidates::load_from_state(state)?;
+    if candidates.get_candidate(fee_payer).is_none() {
+        return Err(RuntimeError::FailedToHandleCustomAction("Only candidates can vote".to_string()).into())
+    }
+
+    let mut proposal = match Proposal::load_from_state(state, id)? {
+        Some(proposal) if proposal.is_open() => proposal,
+        Some(_) => {
+            return Err(RuntimeError::FailedToHandleCustomAction("The proposal is already tallied".to_string()).into())
+        }
+        None => return Err(RuntimeError::FailedToHandleCustomAction("No such proposal".to_string()).into()),
+    };
+    proposal.vote(*fee_payer, option);
+    proposal.save_to_state(state, id)?;
+
+    ctrace!(ENGINE, "Voted. id: {}, voter: {}, option: {:?}", id, fee_payer, option);
+    Ok(())
+}
+
+fn set_session_key(
+    state: &mut TopLevelState,
+    fee_payer: &Address,
+    sender_public: &Public,
+    pubkey: Public,
+    expires_at: BlockNumber,
+) -> StateResult<()> {
+    if public_to_address(sender_public) != *fee_payer {
+        return Err(
+            RuntimeError::FailedToHandleCustomAction("Cannot set the session key with regular key".to_string()).into()
+        )
+    }
+    let session = public_to_address(&pubkey);
+    if session == *fee_payer {
+        return Err(RuntimeError::FailedToHandleCustomAction("The session key must be another key".to_string()).into())
+    }
+    match SessionKey::owner_of(state, &session)? {
+        Some(owner) if owner != *fee_payer => {
+            return Err(RuntimeError::FailedToHandleCustomAction(format!(
+                "{} is the session key of {}",
+                session, owner
+            ))
+            .into())
+        }
+        _ => {}
+    }
+    if Candidates::load_from_state(state)?.get_candidate(&session).is_some() {
+        return Err(RuntimeError::FailedToHandleCustomAction(format!("{} is a candidate", session)).into())
+    }
+    SessionKey {
+        pubkey,
+        expires_at,
+    }
+    .save_to_state(state, fee_payer)?;
+
+    ctrace!(ENGINE, "Session key set. validator: {}, session: {}, expires at: {}", fee_payer, session, expires_at);
+    Ok(())
+}
+
+/// Tallies the open proposals whose deadline is reached.
+fn close_proposals(state: &mut TopLevelState, block_number: BlockNumber) -> StateResult<()> {
+    let mut proposals = Proposals::load_from_state(state)?;
+    let mut due = Vec::new();
+    for id in proposals.open() {
+        let proposal = Proposal::load_from_state(state, *id)?.expect("Open proposals exist");
+        if proposal.deadline <= block_number {
+            due.push((*id, proposal));
+        }
+    }
+    if due.is_empty() {
+        return Ok(())
+    }
+
+    let candidates = Candidates::load_from_state(state)?;
+    for (id, mut proposal) in due {
+        proposal.close(&candidates);
+        proposal.save_to_state(state, id)?;
+        proposals.close(id);
+        ctrace!(ENGINE, "Proposal tallied. id: {}, tally: {:?}", id, proposal.tally());
+    }
+    proposals.save_to_state(state)
+}
+
 pub fn get_stakes(state: &TopLevelState) -> StateResult<HashMap<Address, u64>> {
     let stakeholders = Stakeholders::load_from_state(state)?;
     let mut result = HashMap::new();
@@ -349,6 +580,53 @@ pub fn drain_calculated_rewards(state: &mut TopLevelState) -> StateResult<BTreeM
     Ok(drained)
 }
 
+/// Splits the rewards of the validators into the commissions and the shares of their delegators.
+/// The validators that have not set the commission take the whole rewards.
+/// Returns the payouts as `(recipient, validator, quantity)`, where the validator is the one whose rewards are split.
+pub fn distribute_rewards(
+    state: &TopLevelState,
+    rewards: BTreeMap<Address, u64>,
+) -> StateResult<Vec<(Address, Address, u64)>> {
+    let mut delegators = HashMap::<Address, HashMap<Address, u64>>::new();
+    let stakeholders = Stakeholders::load_from_state(state)?;
+    for stakeholder in stakeholders.iter() {
+        let delegation = Delegation::load_from_state(state, stakeholder)?;
+        for (delegatee, quantity) in delegation.iter() {
+            delegators.entry(*delegatee).or_default().insert(*stakeholder, *quantity);
+        }
+    }
+
+    let mut payouts = Vec::with_capacity(rewards.len());
+    for (validator, reward) in rewards {
+        let commission = match Commission::load_from_state(state, &validator)? {
+            Some(commission) => commission,
+            None => {
+                payouts.push((validator, validator, reward));
+                continue
+            }
+        };
+        let commission_reward = commission.split(reward);
+        let mut remaining = reward - commission_reward;
+        if let Some(delegations) = delegators.get(&validator) {
+            let mut distributor = fee_distribute(remaining, delegations);
+            for (delegator, share) in &mut distributor {
+                payouts.push((*delegator, validator, share));
+            }
+            remaining = distributor.remaining_fee();
+        }
+        payouts.push((commission.destination, validator, commission_reward + remaining));
+    }
+    Ok(payouts)
+}
+
+/// Appends the event to the history of the account in the current term.
+pub fn record_history(state: &mut TopLevelState, address: &Address, event: StakeEvent) -> StateResult<()> {
+    let term = state.metadata()?.map_or(0, |metadata| metadata.current_term_id());
+    let mut history = History::load_from_state(state, address, term)?;
+    history.push(event);
+    history.save_to_state(state, address, term)
+}
+
 pub fn update_validator_weights(state: &mut TopLevelState, block_author: &Address) -> StateResult<()> {
     let mut validators = NextValidators::load_from_state(state)?;
     validators.update_weight(block_author);
@@ -360,6 +638,7 @@ fn change_params(
     metadata_seq: u64,
     params: CommonParams,
     signatures: &[Signature],
+    distinct_signers: bool,
 ) -> StateResult<()> {
     // Update state first because the signature validation is more expensive.
     state.update_params(metadata_seq, params)?;
@@ -371,11 +650,8 @@ fn change_params(
     };
     let encoded_action = H256::blake(rlp::encode(&action));
     let stakes = get_stakes(state)?;
-    let signed_stakes = signatures.iter().try_fold(0, |sum, signature| {
-        let public = recover(signature, &encoded_action).unwrap_or_else(|err| {
-            unreachable!("The transaction with an invalid signature cannot pass the verification: {}", err);
-        });
-        let address = public_to_address(&public);
+    let signers = recover_signers(signatures, &encoded_action, distinct_signers);
+    let signed_stakes = signers.into_iter().try_fold(0, |sum, address| {
         stakes.get(&address).map(|stake| sum + stake).ok_or_else(|| RuntimeError::SignatureOfInvalidAccount(address))
     })?;
     let total_stakes: u64 = stakes.values().sum();
@@ -391,6 +667,121 @@ fn change_params(
     Ok(())
 }
 
+fn schedule_params(
+    state: &mut TopLevelState,
+    metadata_seq: u64,
+    activation: BlockNumber,
+    params: CommonParams,
+    signatures: &[Signature],
+    current_block_number: BlockNumber,
+) -> StateResult<()> {
+    // Update state first because the signature validation is more expensive.
+    state.schedule_params(metadata_seq, activation, params, current_block_number)?;
+
+    let action = Action::ScheduleParams {
+        metadata_seq,
+        activation,
+        params: params.into(),
+        signatures: vec![],
+    };
+    let encoded_action = H256::blake(rlp::encode(&action));
+    let validators: HashMap<Address, u64> = CurrentValidators::load_from_state(state)?
+        .iter()
+        .map(|validator| (public_to_address(validator.pubkey()), validator.delegation()))
+        .collect();
+    let signed_delegations = recover_signers(signatures, &encoded_action, true).into_iter().try_fold(0, |sum, address| {
+        validators
+            .get(&address)
+            .map(|delegation| sum + delegation)
+            .ok_or_else(|| RuntimeError::SignatureOfInvalidAccount(address))
+    })?;
+    let total_delegations: u64 = validators.values().sum();
+    if total_delegations * 2 >= signed_delegations * 3 {
+        return Err(RuntimeError::InsufficientStakes(Mismatch {
+            expected: total_delegations,
+            found: signed_delegations,
+        })
+        .into())
+    }
+
+    ctrace!(ENGINE, "ScheduleParams. activation: {}, params: {:?}", activation, params);
+    Ok(())
+}
+
+/// Returns the signer of each signature. A repeated signer is returned once if `distinct` is set.
+fn recover_signers(signatures: &[Signature], message: &H256, distinct: bool) -> Vec<Address> {
+    let signers = signatures.iter().map(|signature| {
+        let public = recover(signature, message).unwrap_or_else(|err| {
+            unreachable!("The transaction with an invalid signature cannot pass the verification: {}", err);
+        });
+        public_to_address(&public)
+    });
+    if distinct {
+        signers.collect::<BTreeSet<_>>().into_iter().collect()
+    } else {
+        signers.collect()
+    }
+}
+
+pub fn record_misses(
+    state: &mut TopLevelState,
+    author: Address,
+    missed_proposers: &[Address],
+    missed_voters: &[Address],
+) -> StateResult<()> {
+    let mut misses = Misses::load_from_state(state)?;
+    misses.record_block(author, missed_proposers, missed_voters);
+    misses.save_to_state(state)
+}
+
+/// Jails the validators whose miss rate exceeds `max_miss_rate` percent for the rest of the term.
+/// They can unjail themselves from the next term.
+pub fn jail_unresponsive(state: &mut TopLevelState, max_miss_rate: u64, min_blocks: u64) -> StateResult<Vec<Address>> {
+    let misses = Misses::load_from_state(state)?;
+    if misses.blocks() < min_blocks {
+        return Ok(Vec::new())
+    }
+
+    let metadata = state.metadata()?.expect("The metadata must exist");
+    let current_term = metadata.current_term_id();
+    let (release_period, min_num_of_validators) = {
+        let params = metadata.params().expect("Validators exist only after the ChangeParams is called");
+        (params.release_period(), params.min_num_of_validators())
+    };
+
+    let mut validators = NextValidators::load_from_state(state)?;
+    let mut candidates = Candidates::load_from_state(state)?;
+    let mut jail = Jail::load_from_state(state)?;
+    let mut jailed = Vec::new();
+    for (address, record) in misses.iter() {
+        if record.rate(misses.blocks()) <= max_miss_rate {
+            continue
+        }
+        if !validators.iter().any(|validator| public_to_address(validator.pubkey()) == *address) {
+            continue
+        }
+        if validators.len() <= min_num_of_validators {
+            cwarn!(ENGINE, "Cannot jail {} because there are only {} validators", address, validators.len());
+            break
+        }
+        let candidate = match candidates.remove(address) {
+            Some(candidate) => candidate,
+            None => continue,
+        };
+        ctrace!(ENGINE, "jail_unresponsive. validator: {}, misses: {:?}", address, record);
+        validators.remove(address);
+        jail.add(candidate, current_term, current_term + release_period);
+        jailed.push(*address);
+    }
+
+    if !jailed.is_empty() {
+        validators.save_to_state(state)?;
+        candidates.save_to_state(state)?;
+        jail.save_to_state(state)?;
+    }
+    Ok(jailed)
+}
+
 pub fn on_term_close(
     state: &mut TopLevelState,
     last_term_finished_block_num: u64,
@@ -425,6 +816,8 @@ pub fn on_term_close(
     let validators = NextValidators::elect(state)?;
     validators.save_to_state(state)?;
 
+    Misses::default().save_to_state(state)?;
+
     state.increase_term_id(last_term_finished_block_num)?;
     Ok(())
 }
@@ -529,6 +922,10 @@ fn revert_delegations(state: &mut TopLevelState, reverted_delegatees: &[Address]
             if quantity > 0 {
                 delegation.subtract_quantity(*delegatee, quantity)?;
                 delegator.add_balance(quantity)?;
+                record_history(state, stakeholder, StakeEvent::Reverted {
+                    delegatee: *delegatee,
+                    quantity,
+                })?;
                 ctrace!(
                     ENGINE,
                     "revert_delegation delegator: {}, delegatee: {}, quantity: {}",
@@ -550,9 +947,12 @@ mod tests {
     use super::*;
 
     use crate::consensus::stake::action_data::{get_delegation_key, Candidate, Prisoner};
+    use cjson::scheme::Forks;
+    use ckey::{sign, Generator, KeyPair, Random};
     use cstate::tests::helpers;
     use cstate::TopStateView;
     use rlp::Encodable;
+    use std::convert::TryFrom;
 
     fn metadata_for_election() -> TopLevelState {
         let mut state = helpers::get_temp_state_with_metadata();
@@ -572,7 +972,7 @@ mod tests {
         let stake = {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(address1, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
 
@@ -597,7 +997,7 @@ mod tests {
         let stake = {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(address1, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
 
@@ -625,7 +1025,7 @@ mod tests {
         let stake = {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(address1, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
 
@@ -657,7 +1057,7 @@ mod tests {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegatee, 100);
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
         self_nominate(&mut state, &delegatee, &delegatee_pubkey, 0, 0, 10, b"".to_vec()).unwrap();
@@ -666,7 +1066,7 @@ mod tests {
             address: delegatee,
             quantity: 40,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert_eq!(result, Ok(()));
 
         let delegator_account = StakeAccount::load_from_state(&state, &delegator).unwrap();
@@ -688,6 +1088,163 @@ mod tests {
         assert!(stakeholders.contains(&delegatee));
     }
 
+    #[test]
+    fn proposal_is_tallied_by_deposits_at_deadline() {
+        let voters: Vec<_> = (0..4).map(|_| Public::random()).collect();
+        let outsider = Public::random();
+
+        let mut state = helpers::get_temp_state();
+        let stake = Stake::new(HashMap::new(), ForkSchedule::default());
+        stake.init(&mut state).unwrap();
+        for (voter, deposit) in voters.iter().zip(&[100, 200, 300, 400]) {
+            let address = public_to_address(voter);
+            state.add_balance(&address, *deposit).unwrap();
+            self_nominate(&mut state, &address, voter, *deposit, 0, 10, b"".to_vec()).unwrap();
+        }
+
+        let execute = |state: &mut TopLevelState, signer: &Public, action: Action| {
+            stake.execute(&action.rlp_bytes(), state, &public_to_address(signer), signer, 1)
+        };
+        let propose = || Action::CreateProposal {
+            text: "Upgrade at block 1000".to_string(),
+            deadline: 5,
+        };
+        assert!(execute(&mut state, &outsider, propose()).is_err(), "Only candidates can propose");
+        assert_eq!(Ok(()), execute(&mut state, &voters[0], propose()));
+
+        let vote = |option| Action::VoteProposal {
+            id: 0,
+            option,
+        };
+        assert_eq!(Ok(()), execute(&mut state, &voters[0], vote(VoteOption::No)));
+        assert_eq!(Ok(()), execute(&mut state, &voters[0], vote(VoteOption::Yes)));
+        assert_eq!(Ok(()), execute(&mut state, &voters[1], vote(VoteOption::No)));
+        assert_eq!(Ok(()), execute(&mut state, &voters[2], vote(VoteOption::Abstain)));
+        assert!(execute(&mut state, &outsider, vote(VoteOption::Yes)).is_err(), "Only candidates can vote");
+
+        close_proposals(&mut state, 4).unwrap();
+        assert!(Proposal::load_from_state(&state, 0).unwrap().unwrap().is_open());
+
+        close_proposals(&mut state, 5).unwrap();
+        let proposal = Proposal::load_from_state(&state, 0).unwrap().unwrap();
+        assert_eq!(
+            proposal.tally(),
+            Some(&Tally {
+                yes: 100,
+                no: 200,
+                abstain: 300,
+                total: 1000,
+            })
+        );
+        assert_eq!(Proposals::load_from_state(&state).unwrap().open().count(), 0);
+        assert!(execute(&mut state, &voters[3], vote(VoteOption::Yes)).is_err(), "The proposal is closed");
+    }
+
+    #[test]
+    fn distribute_rewards_with_commission() {
+        let validator_pubkey = Public::random();
+        let validator = public_to_address(&validator_pubkey);
+        let destination = Address::random();
+        let delegators: Vec<_> = (0..2).map(|_| Public::random()).collect();
+        let no_commission = Address::random();
+
+        let mut state = helpers::get_temp_state();
+        let stake = {
+            let mut genesis_stakes = HashMap::new();
+            for delegator in &delegators {
+                genesis_stakes.insert(public_to_address(delegator), 100);
+            }
+            Stake::new(genesis_stakes, ForkSchedule::default())
+        };
+        stake.init(&mut state).unwrap();
+        self_nominate(&mut state, &validator, &validator_pubkey, 0, 0, 10, b"".to_vec()).unwrap();
+
+        let action = Action::SetCommission {
+            rate: 10,
+            destination,
+        };
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &validator, &validator_pubkey, 1);
+        assert_eq!(result, Ok(()));
+
+        for (delegator, quantity) in delegators.iter().zip(&[30, 10]) {
+            let action = Action::DelegateCCS {
+                address: validator,
+                quantity: *quantity,
+            };
+            let result = stake.execute(&action.rlp_bytes(), &mut state, &public_to_address(delegator), delegator, 1);
+            assert_eq!(result, Ok(()));
+        }
+
+        let mut rewards = BTreeMap::new();
+        rewards.insert(validator, 1001);
+        rewards.insert(no_commission, 500);
+        let payouts: HashMap<_, _> = distribute_rewards(&state, rewards)
+            .unwrap()
+            .into_iter()
+            .map(|(recipient, validator, quantity)| ((recipient, validator), quantity))
+            .collect();
+        assert_eq!(payouts, {
+            let mut expected = HashMap::new();
+            // The delegators share the 901 left. The remainder of the division goes to the destination.
+            expected.insert((public_to_address(&delegators[0]), validator), 675);
+            expected.insert((public_to_address(&delegators[1]), validator), 225);
+            expected.insert((destination, validator), 101);
+            expected.insert((no_commission, no_commission), 500);
+            expected
+        });
+    }
+
+    #[test]
+    fn session_key_replaces_the_previous_one() {
+        let validator_pubkey = Public::random();
+        let validator = public_to_address(&validator_pubkey);
+        let (first, second) = (Public::random(), Public::random());
+
+        let mut state = helpers::get_temp_state();
+        let stake = Stake::new(HashMap::new(), ForkSchedule::default());
+        stake.init(&mut state).unwrap();
+
+        for session in &[first, second] {
+            let action = Action::SetSessionKey {
+                pubkey: *session,
+                expires_at: 100,
+            };
+            let result = stake.execute(&action.rlp_bytes(), &mut state, &validator, &validator_pubkey, 1);
+            assert_eq!(result, Ok(()));
+        }
+        assert_eq!(
+            Some(SessionKey {
+                pubkey: second,
+                expires_at: 100,
+            }),
+            SessionKey::load_from_state(&state, &validator).unwrap()
+        );
+        assert_eq!(None, SessionKey::owner_of(&state, &public_to_address(&first)).unwrap());
+        assert_eq!(Some(validator), SessionKey::owner_of(&state, &public_to_address(&second)).unwrap());
+    }
+
+    #[test]
+    fn cannot_take_the_session_key_of_another_validator() {
+        let session = Public::random();
+        let validators: Vec<_> = (0..2).map(|_| Public::random()).collect();
+
+        let mut state = helpers::get_temp_state();
+        let stake = Stake::new(HashMap::new(), ForkSchedule::default());
+        stake.init(&mut state).unwrap();
+
+        let action = Action::SetSessionKey {
+            pubkey: session,
+            expires_at: 100,
+        };
+        let result =
+            stake.execute(&action.rlp_bytes(), &mut state, &public_to_address(&validators[0]), &validators[0], 1);
+        assert_eq!(result, Ok(()));
+        let result =
+            stake.execute(&action.rlp_bytes(), &mut state, &public_to_address(&validators[1]), &validators[1], 1);
+        assert!(result.is_err());
+        assert_eq!(None, SessionKey::load_from_state(&state, &public_to_address(&validators[1])).unwrap());
+    }
+
     #[test]
     fn delegate_all() {
         let delegatee_pubkey = Public::random();
@@ -700,7 +1257,7 @@ mod tests {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegatee, 100);
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
         self_nominate(&mut state, &delegatee, &delegatee_pubkey, 0, 0, 10, b"".to_vec()).unwrap();
@@ -709,7 +1266,7 @@ mod tests {
             address: delegatee,
             quantity: 100,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert_eq!(result, Ok(()));
 
         let delegator_account = StakeAccount::load_from_state(&state, &delegator).unwrap();
@@ -744,7 +1301,7 @@ mod tests {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegatee, 100);
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
 
@@ -752,7 +1309,7 @@ mod tests {
             address: delegatee,
             quantity: 40,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert!(result.is_err());
     }
 
@@ -768,7 +1325,7 @@ mod tests {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegatee, 100);
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
         self_nominate(&mut state, &delegatee, &delegatee_pubkey, 0, 0, 10, b"".to_vec()).unwrap();
@@ -777,7 +1334,7 @@ mod tests {
             address: delegatee,
             quantity: 200,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert!(result.is_err());
     }
 
@@ -793,7 +1350,7 @@ mod tests {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegatee, 100);
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
         self_nominate(&mut state, &delegatee, &delegatee_pubkey, 0, 0, 10, b"".to_vec()).unwrap();
@@ -802,13 +1359,13 @@ mod tests {
             address: delegatee,
             quantity: 50,
         };
-        stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey).unwrap();
+        stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1).unwrap();
 
         let action = Action::TransferCCS {
             address: delegatee,
             quantity: 50,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert!(result.is_ok());
     }
 
@@ -824,7 +1381,7 @@ mod tests {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegatee, 100);
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
         self_nominate(&mut state, &delegatee, &delegatee_pubkey, 0, 0, 10, b"".to_vec()).unwrap();
@@ -833,13 +1390,13 @@ mod tests {
             address: delegatee,
             quantity: 50,
         };
-        stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey).unwrap();
+        stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1).unwrap();
 
         let action = Action::TransferCCS {
             address: delegatee,
             quantity: 100,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert!(result.is_err());
     }
 
@@ -855,7 +1412,7 @@ mod tests {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegatee, 100);
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
         self_nominate(&mut state, &delegatee, &delegatee_pubkey, 0, 0, 10, b"".to_vec()).unwrap();
@@ -864,14 +1421,14 @@ mod tests {
             address: delegatee,
             quantity: 50,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert!(result.is_ok());
 
         let action = Action::Revoke {
             address: delegatee,
             quantity: 20,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert_eq!(Ok(()), result);
 
         let delegator_account = StakeAccount::load_from_state(&state, &delegator).unwrap();
@@ -893,7 +1450,7 @@ mod tests {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegatee, 100);
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
         self_nominate(&mut state, &delegatee, &delegatee_pubkey, 0, 0, 10, b"".to_vec()).unwrap();
@@ -902,14 +1459,14 @@ mod tests {
             address: delegatee,
             quantity: 50,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert!(result.is_ok());
 
         let action = Action::Revoke {
             address: delegatee,
             quantity: 70,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert!(result.is_err());
 
         let delegator_account = StakeAccount::load_from_state(&state, &delegator).unwrap();
@@ -931,7 +1488,7 @@ mod tests {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegatee, 100);
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
         self_nominate(&mut state, &delegatee, &delegatee_pubkey, 0, 0, 10, b"".to_vec()).unwrap();
@@ -940,14 +1497,14 @@ mod tests {
             address: delegatee,
             quantity: 50,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert!(result.is_ok());
 
         let action = Action::Revoke {
             address: delegatee,
             quantity: 50,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert_eq!(Ok(()), result);
 
         let delegator_account = StakeAccount::load_from_state(&state, &delegator).unwrap();
@@ -968,7 +1525,7 @@ mod tests {
         let stake = {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
         self_nominate(&mut state, &prev_delegatee, &prev_delegatee_pubkey, 0, 0, 10, b"".to_vec()).unwrap();
@@ -978,7 +1535,7 @@ mod tests {
             address: prev_delegatee,
             quantity: 50,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert!(result.is_ok());
 
         let action = Action::Redelegate {
@@ -986,7 +1543,7 @@ mod tests {
             next_delegatee,
             quantity: 20,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert_eq!(Ok(()), result);
 
         let delegator_account = StakeAccount::load_from_state(&state, &delegator).unwrap();
@@ -997,6 +1554,69 @@ mod tests {
         assert_eq!(delegation.get_quantity(&next_delegatee), 20);
     }
 
+    #[test]
+    fn history_records_the_delegation_changes() {
+        let delegatee_pubkey = Public::random();
+        let delegatee = public_to_address(&delegatee_pubkey);
+        let next_delegatee_pubkey = Public::random();
+        let next_delegatee = public_to_address(&next_delegatee_pubkey);
+        let delegator_pubkey = Public::random();
+        let delegator = public_to_address(&delegator_pubkey);
+
+        let mut state = helpers::get_temp_state();
+        let stake = {
+            let mut genesis_stakes = HashMap::new();
+            genesis_stakes.insert(delegator, 100);
+            Stake::new(genesis_stakes, ForkSchedule::default())
+        };
+        stake.init(&mut state).unwrap();
+        self_nominate(&mut state, &delegatee, &delegatee_pubkey, 0, 0, 10, b"".to_vec()).unwrap();
+        self_nominate(&mut state, &next_delegatee, &next_delegatee_pubkey, 0, 0, 10, b"".to_vec()).unwrap();
+
+        let actions = vec![
+            Action::DelegateCCS {
+                address: delegatee,
+                quantity: 50,
+            },
+            Action::Redelegate {
+                prev_delegatee: delegatee,
+                next_delegatee,
+                quantity: 20,
+            },
+            Action::Revoke {
+                address: delegatee,
+                quantity: 10,
+            },
+        ];
+        for action in actions {
+            let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
+            assert_eq!(Ok(()), result);
+        }
+        revert_delegations(&mut state, &[next_delegatee]).unwrap();
+
+        let history: Vec<_> = History::load_from_state(&state, &delegator, 0).unwrap().into_iter().collect();
+        assert_eq!(history, vec![
+            StakeEvent::Delegated {
+                delegatee,
+                quantity: 50,
+            },
+            StakeEvent::Redelegated {
+                prev_delegatee: delegatee,
+                next_delegatee,
+                quantity: 20,
+            },
+            StakeEvent::Revoked {
+                delegatee,
+                quantity: 10,
+            },
+            StakeEvent::Reverted {
+                delegatee: next_delegatee,
+                quantity: 20,
+            },
+        ]);
+        assert!(History::load_from_state(&state, &delegator, 1).unwrap().is_empty());
+    }
+
     #[test]
     fn cannot_redelegate_more_than_delegated_tokens() {
         let prev_delegatee_pubkey = Public::random();
@@ -1010,7 +1630,7 @@ mod tests {
         let stake = {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
         self_nominate(&mut state, &prev_delegatee, &prev_delegatee_pubkey, 0, 0, 10, b"".to_vec()).unwrap();
@@ -1020,7 +1640,7 @@ mod tests {
             address: prev_delegatee,
             quantity: 50,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert!(result.is_ok());
 
         let action = Action::Redelegate {
@@ -1028,7 +1648,7 @@ mod tests {
             next_delegatee,
             quantity: 70,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert!(result.is_err());
 
         let delegator_account = StakeAccount::load_from_state(&state, &delegator).unwrap();
@@ -1052,7 +1672,7 @@ mod tests {
         let stake = {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
         self_nominate(&mut state, &prev_delegatee, &prev_delegatee_pubkey, 0, 0, 10, b"".to_vec()).unwrap();
@@ -1062,7 +1682,7 @@ mod tests {
             address: prev_delegatee,
             quantity: 50,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert!(result.is_ok());
 
         let action = Action::Redelegate {
@@ -1070,7 +1690,7 @@ mod tests {
             next_delegatee,
             quantity: 50,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert_eq!(Ok(()), result);
 
         let delegator_account = StakeAccount::load_from_state(&state, &delegator).unwrap();
@@ -1094,7 +1714,7 @@ mod tests {
         let stake = {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
 
@@ -1104,7 +1724,7 @@ mod tests {
             address: prev_delegatee,
             quantity: 40,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert!(result.is_ok());
 
         let action = Action::Redelegate {
@@ -1112,7 +1732,7 @@ mod tests {
             next_delegatee,
             quantity: 50,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert!(result.is_err());
     }
 
@@ -1132,7 +1752,7 @@ mod tests {
         let stake = {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
         self_nominate(&mut state, &prev_delegatee, &prev_delegatee_pubkey, 0, 0, 10, b"".to_vec()).unwrap();
@@ -1142,12 +1762,12 @@ mod tests {
             address: criminal,
             quantity: 40,
         };
-        stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey).unwrap();
+        stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1).unwrap();
         let action = Action::DelegateCCS {
             address: prev_delegatee,
             quantity: 40,
         };
-        stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey).unwrap();
+        stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1).unwrap();
 
         let candidates = Candidates::load_from_state(&state).unwrap();
         assert_eq!(candidates.len(), 2);
@@ -1165,7 +1785,7 @@ mod tests {
             next_delegatee: criminal,
             quantity: 40,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert!(result.is_err());
     }
 
@@ -1184,7 +1804,7 @@ mod tests {
         let stake = {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
         self_nominate(&mut state, &prev_delegatee, &prev_delegatee_pubkey, 0, 0, 10, b"".to_vec()).unwrap();
@@ -1196,7 +1816,7 @@ mod tests {
             address: prev_delegatee,
             quantity: 40,
         };
-        stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey).unwrap();
+        stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1).unwrap();
 
         let candidates = Candidates::load_from_state(&state).unwrap();
         assert_eq!(candidates.len(), 2);
@@ -1214,7 +1834,7 @@ mod tests {
             next_delegatee: jail_address,
             quantity: 40,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert!(result.is_err());
     }
 
@@ -1226,7 +1846,7 @@ mod tests {
         let mut state = helpers::get_temp_state();
         state.add_balance(&address, 1000).unwrap();
 
-        let stake = Stake::new(HashMap::new());
+        let stake = Stake::new(HashMap::new(), ForkSchedule::default());
         stake.init(&mut state).unwrap();
 
         // TODO: change with stake.execute()
@@ -1286,7 +1906,7 @@ mod tests {
         let mut state = helpers::get_temp_state();
         state.add_balance(&address, 1000).unwrap();
 
-        let stake = Stake::new(HashMap::new());
+        let stake = Stake::new(HashMap::new(), ForkSchedule::default());
         stake.init(&mut state).unwrap();
 
         // TODO: change with stake.execute()
@@ -1311,7 +1931,7 @@ mod tests {
         increase_term_id_until(&mut state, 29);
         state.add_balance(&address, 1000).unwrap();
 
-        let stake = Stake::new(HashMap::new());
+        let stake = Stake::new(HashMap::new(), ForkSchedule::default());
         stake.init(&mut state).unwrap();
 
         // TODO: change with stake.execute()
@@ -1355,7 +1975,7 @@ mod tests {
         let stake = {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
 
@@ -1366,7 +1986,7 @@ mod tests {
             address,
             quantity: 40,
         };
-        stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey).unwrap();
+        stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1).unwrap();
 
         let result = on_term_close(&mut state, pseudo_term_to_block_num_calculator(29), &[]);
         assert_eq!(result, Ok(()));
@@ -1393,7 +2013,7 @@ mod tests {
         let mut state = helpers::get_temp_state();
         state.add_balance(&address, 1000).unwrap();
 
-        let stake = Stake::new(HashMap::new());
+        let stake = Stake::new(HashMap::new(), ForkSchedule::default());
         stake.init(&mut state).unwrap();
 
         // TODO: change with stake.execute()
@@ -1431,7 +2051,7 @@ mod tests {
         let mut state = metadata_for_election();
         state.add_balance(&address, 1000).unwrap();
 
-        let stake = Stake::new(HashMap::new());
+        let stake = Stake::new(HashMap::new(), ForkSchedule::default());
         stake.init(&mut state).unwrap();
 
         // TODO: change with stake.execute()
@@ -1470,7 +2090,7 @@ mod tests {
         let mut state = metadata_for_election();
         state.add_balance(&address, 1000).unwrap();
 
-        let stake = Stake::new(HashMap::new());
+        let stake = Stake::new(HashMap::new(), ForkSchedule::default());
         stake.init(&mut state).unwrap();
 
         // TODO: change with stake.execute()
@@ -1516,6 +2136,100 @@ mod tests {
         assert_eq!(state.balance(&address).unwrap(), 1000 - deposit - additional_deposit, "Deposit is accumulated");
     }
 
+    #[test]
+    fn can_unjail_only_after_custody() {
+        let address_pubkey = Public::random();
+        let address = public_to_address(&address_pubkey);
+
+        let mut state = metadata_for_election();
+        state.add_balance(&address, 1000).unwrap();
+
+        let stake = Stake::new(HashMap::new(), ForkSchedule::default());
+        stake.init(&mut state).unwrap();
+
+        let deposit = 200;
+        let nominate_expire = 5;
+        let custody_until = 10;
+        let released_at = 20;
+        self_nominate(&mut state, &address, &address_pubkey, deposit, 0, nominate_expire, b"metadata".to_vec())
+            .unwrap();
+        jail(&mut state, &[address], custody_until, released_at).unwrap();
+
+        for current_term in 0..=custody_until {
+            let result = unjail(&mut state, &address, &address_pubkey, current_term, current_term + nominate_expire);
+            assert!(
+                result.is_err(),
+                "Shouldn't unjail while current_term({}) <= custody_until({})",
+                current_term,
+                custody_until
+            );
+            on_term_close(&mut state, pseudo_term_to_block_num_calculator(current_term), &[]).unwrap();
+        }
+
+        let current_term = custody_until + 1;
+        let result = unjail(&mut state, &address, &address_pubkey, current_term, current_term + nominate_expire);
+        assert_eq!(Ok(()), result);
+
+        let candidates = Candidates::load_from_state(&state).unwrap();
+        assert_eq!(
+            candidates.get_candidate(&address),
+            Some(&Candidate {
+                deposit,
+                nomination_ends_at: current_term + nominate_expire,
+                pubkey: address_pubkey,
+                metadata: b"".to_vec(),
+            }),
+            "The prisoner is become a candidate with the same deposit",
+        );
+        let jail = Jail::load_from_state(&state).unwrap();
+        assert_eq!(jail.get_prisoner(&address), None, "The prisoner is removed");
+        assert_eq!(state.balance(&address).unwrap(), 1000 - deposit);
+
+        let result = unjail(&mut state, &address, &address_pubkey, current_term, current_term + nominate_expire);
+        assert!(result.is_err(), "Cannot unjail a candidate");
+    }
+
+    #[test]
+    fn jail_unresponsive_validator() {
+        let mut state = metadata_for_election();
+        let stake = Stake::new(HashMap::new(), ForkSchedule::default());
+        stake.init(&mut state).unwrap();
+
+        let pubkeys: Vec<_> = (0..5).map(|_| Public::random()).collect();
+        let deposit = 1000;
+        for pubkey in &pubkeys {
+            let address = public_to_address(pubkey);
+            state.add_balance(&address, deposit).unwrap();
+            self_nominate(&mut state, &address, pubkey, deposit, 0, 10, b"".to_vec()).unwrap();
+        }
+        let validators = pubkeys.iter().map(|pubkey| Validator::new_for_test(0, deposit, *pubkey)).collect();
+        NextValidators::from_vector_to_test(validators).save_to_state(&mut state).unwrap();
+
+        let author = public_to_address(&pubkeys[0]);
+        let absent = public_to_address(&pubkeys[1]);
+        for _ in 0..9 {
+            record_misses(&mut state, author, &[], &[absent]).unwrap();
+        }
+        assert_eq!(Ok(vec![]), jail_unresponsive(&mut state, 50, 10), "Too few blocks to judge");
+
+        record_misses(&mut state, author, &[], &[absent]).unwrap();
+        assert_eq!(Ok(vec![absent]), jail_unresponsive(&mut state, 50, 10));
+
+        let validators = NextValidators::load_from_state(&state).unwrap();
+        assert_eq!(4, validators.len());
+        assert!(validators.iter().all(|validator| *validator.pubkey() != pubkeys[1]));
+        let candidates = Candidates::load_from_state(&state).unwrap();
+        assert_eq!(None, candidates.get_candidate(&absent));
+        let jail = Jail::load_from_state(&state).unwrap();
+        assert_eq!(Some(0), jail.get_prisoner(&absent).map(|prisoner| prisoner.custody_until), "Jailed for the term");
+
+        record_misses(&mut state, author, &[], &[public_to_address(&pubkeys[2])]).unwrap();
+        assert_eq!(Ok(vec![]), jail_unresponsive(&mut state, 0, 10), "Keeps the minimum number of validators");
+
+        on_term_close(&mut state, pseudo_term_to_block_num_calculator(0), &[]).unwrap();
+        assert_eq!(Misses::default(), Misses::load_from_state(&state).unwrap(), "Misses are cleared every term");
+    }
+
     #[test]
     fn jail_released_after() {
         let address_pubkey = Public::random();
@@ -1524,7 +2238,7 @@ mod tests {
         let mut state = metadata_for_election();
         state.add_balance(&address, 1000).unwrap();
 
-        let stake = Stake::new(HashMap::new());
+        let stake = Stake::new(HashMap::new(), ForkSchedule::default());
         stake.init(&mut state).unwrap();
 
         // TODO: change with stake.execute()
@@ -1569,7 +2283,7 @@ mod tests {
         let stake = {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
 
@@ -1586,7 +2300,7 @@ mod tests {
                 address,
                 quantity: 1,
             };
-            let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+            let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
             assert_ne!(Ok(()), result);
 
             on_term_close(&mut state, pseudo_term_to_block_num_calculator(current_term), &[]).unwrap();
@@ -1596,7 +2310,7 @@ mod tests {
             address,
             quantity: 1,
         };
-        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey);
+        let result = stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1);
         assert!(result.is_err());
     }
 
@@ -1613,7 +2327,7 @@ mod tests {
         let stake = {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
 
@@ -1628,7 +2342,7 @@ mod tests {
             address,
             quantity: 40,
         };
-        stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey).unwrap();
+        stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1).unwrap();
 
         jail(&mut state, &[address], custody_until, released_at).unwrap();
 
@@ -1656,7 +2370,7 @@ mod tests {
         let stake = {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
 
@@ -1670,7 +2384,7 @@ mod tests {
             address,
             quantity: 40,
         };
-        stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey).unwrap();
+        stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1).unwrap();
 
         jail(&mut state, &[address], custody_until, released_at).unwrap();
 
@@ -1711,7 +2425,7 @@ mod tests {
         let stake = {
             let mut genesis_stakes = HashMap::new();
             genesis_stakes.insert(delegator, 100);
-            Stake::new(genesis_stakes)
+            Stake::new(genesis_stakes, ForkSchedule::default())
         };
         stake.init(&mut state).unwrap();
 
@@ -1721,7 +2435,7 @@ mod tests {
             address: criminal,
             quantity: 40,
         };
-        stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey).unwrap();
+        stake.execute(&action.rlp_bytes(), &mut state, &delegator, &delegator_pubkey, 1).unwrap();
 
         assert_eq!(Ok(()), ban(&mut state, &informant_pubkey, criminal));
 
@@ -1747,7 +2461,7 @@ mod tests {
         let criminal = public_to_address(&criminal_pubkey);
 
         let mut state = helpers::get_temp_state();
-        let stake = Stake::new(HashMap::new());
+        let stake = Stake::new(HashMap::new(), ForkSchedule::default());
         stake.init(&mut state).unwrap();
         assert_eq!(Ok(()), state.add_balance(&criminal, 100));
 
@@ -1766,4 +2480,204 @@ mod tests {
     fn pseudo_term_to_block_num_calculator(term_id: u64) -> u64 {
         term_id * 10 + 1
     }
+
+    fn state_with_validators(validators: &[(&KeyPair, u64)]) -> TopLevelState {
+        let mut state = metadata_for_election();
+        let mut current_validators = CurrentValidators::load_from_state(&state).unwrap();
+        current_validators.update(
+            validators
+                .iter()
+                .map(|(keypair, delegation)| Validator::new_for_test(*delegation, 0, *keypair.public()))
+                .collect(),
+        );
+        current_validators.save_to_state(&mut state).unwrap();
+        state
+    }
+
+    fn sign_schedule_params(
+        keypairs: &[&KeyPair],
+        metadata_seq: u64,
+        activation: BlockNumber,
+        params: CommonParams,
+    ) -> Vec<Signature> {
+        let action = Action::ScheduleParams {
+            metadata_seq,
+            activation,
+            params: params.into(),
+            signatures: vec![],
+        };
+        let encoded_action = H256::blake(rlp::encode(&action));
+        keypairs.iter().map(|keypair| sign(keypair.private(), &encoded_action).unwrap()).collect()
+    }
+
+    #[test]
+    fn schedule_params_with_supermajority() {
+        let validator1 = Random.generate().unwrap();
+        let validator2 = Random.generate().unwrap();
+        let mut state = state_with_validators(&[(&validator1, 30), (&validator2, 70)]);
+
+        let mut params = CommonParams::default_for_test();
+        params.set_dynamic_validator_params_for_test(30, 10, 3, 20, 30, 4, 1000, 10000, 200);
+        let signatures = sign_schedule_params(&[&validator2], 1, 100, params);
+        assert_eq!(Ok(()), schedule_params(&mut state, 1, 100, params, &signatures, 1));
+
+        let metadata = state.metadata().unwrap().unwrap();
+        assert_eq!(Some((100, &params)), metadata.scheduled_params());
+        assert_ne!(Some(&params), metadata.params());
+
+        assert_eq!(Ok(()), state.activate_scheduled_params(99));
+        assert_ne!(Some(&params), state.metadata().unwrap().unwrap().params());
+
+        assert_eq!(Ok(()), state.activate_scheduled_params(100));
+        let metadata = state.metadata().unwrap().unwrap();
+        assert_eq!(Some(&params), metadata.params());
+        assert_eq!(None, metadata.scheduled_params());
+    }
+
+    #[test]
+    fn cannot_schedule_params_without_supermajority() {
+        let validator1 = Random.generate().unwrap();
+        let validator2 = Random.generate().unwrap();
+        let mut state = state_with_validators(&[(&validator1, 40), (&validator2, 60)]);
+
+        let params = CommonParams::default_for_test();
+        let signatures = sign_schedule_params(&[&validator2], 1, 100, params);
+        assert_eq!(
+            Err(RuntimeError::InsufficientStakes(Mismatch {
+                expected: 100,
+                found: 60,
+            })
+            .into()),
+            schedule_params(&mut state, 1, 100, params, &signatures, 1)
+        );
+    }
+
+    #[test]
+    fn cannot_schedule_params_with_signature_of_non_validator() {
+        let validator = Random.generate().unwrap();
+        let non_validator = Random.generate().unwrap();
+        let mut state = state_with_validators(&[(&validator, 100)]);
+
+        let params = CommonParams::default_for_test();
+        let signatures = sign_schedule_params(&[&validator, &non_validator], 1, 100, params);
+        assert_eq!(
+            Err(RuntimeError::SignatureOfInvalidAccount(non_validator.address()).into()),
+            schedule_params(&mut state, 1, 100, params, &signatures, 1)
+        );
+    }
+
+    #[test]
+    fn repeated_signature_is_counted_once_when_scheduling_params() {
+        let validator1 = Random.generate().unwrap();
+        let validator2 = Random.generate().unwrap();
+        let mut state = state_with_validators(&[(&validator1, 40), (&validator2, 60)]);
+
+        let params = CommonParams::default_for_test();
+        let signatures = sign_schedule_params(&[&validator2, &validator2], 1, 100, params);
+        assert_eq!(
+            Err(RuntimeError::InsufficientStakes(Mismatch {
+                expected: 100,
+                found: 60,
+            })
+            .into()),
+            schedule_params(&mut state, 1, 100, params, &signatures, 1)
+        );
+    }
+
+    #[test]
+    fn repeated_signature_is_counted_once_when_changing_params_after_the_fork() {
+        let stakeholder1 = Random.generate().unwrap();
+        let stakeholder2 = Random.generate().unwrap();
+        let mut state = metadata_for_election();
+        let stake = {
+            let mut genesis_stakes = HashMap::new();
+            genesis_stakes.insert(stakeholder1.address(), 40);
+            genesis_stakes.insert(stakeholder2.address(), 60);
+            Stake::new(genesis_stakes, ForkSchedule::default())
+        };
+        stake.init(&mut state).unwrap();
+
+        let params = CommonParams::default_for_test();
+        let action = Action::ChangeParams {
+            metadata_seq: 1,
+            params: params.into(),
+            signatures: vec![],
+        };
+        let encoded_action = H256::blake(rlp::encode(&action));
+        let signature = sign(stakeholder2.private(), &encoded_action).unwrap();
+        assert_eq!(
+            Err(RuntimeError::InsufficientStakes(Mismatch {
+                expected: 100,
+                found: 60,
+            })
+            .into()),
+            change_params(&mut state, 1, params, &[signature, signature], true)
+        );
+    }
+
+    #[test]
+    fn repeated_signature_is_counted_for_each_signature_before_the_fork() {
+        let stakeholder1 = Random.generate().unwrap();
+        let stakeholder2 = Random.generate().unwrap();
+        let stake = {
+            let mut genesis_stakes = HashMap::new();
+            genesis_stakes.insert(stakeholder1.address(), 40);
+            genesis_stakes.insert(stakeholder2.address(), 60);
+            let mut forks = Forks::new();
+            forks.insert(SCHEDULE_PARAMS_FORK.to_string(), 10.into());
+            Stake::new(genesis_stakes, ForkSchedule::try_from(forks).unwrap())
+        };
+
+        let params = CommonParams::default_for_test();
+        let action = Action::ChangeParams {
+            metadata_seq: 1,
+            params: params.into(),
+            signatures: vec![],
+        };
+        let signature = sign(stakeholder2.private(), &H256::blake(rlp::encode(&action))).unwrap();
+        let action = Action::ChangeParams {
+            metadata_seq: 1,
+            params: params.into(),
+            signatures: vec![signature, signature],
+        };
+        let signer = stakeholder2.public();
+
+        let mut state = metadata_for_election();
+        stake.init(&mut state).unwrap();
+        assert_eq!(Ok(()), stake.execute(&action.rlp_bytes(), &mut state, &stakeholder2.address(), signer, 9));
+
+        let mut state = metadata_for_election();
+        stake.init(&mut state).unwrap();
+        assert!(stake.execute(&action.rlp_bytes(), &mut state, &stakeholder2.address(), signer, 10).is_err());
+    }
+
+    #[test]
+    fn cannot_schedule_params_at_or_before_the_current_block() {
+        let validator = Random.generate().unwrap();
+        let mut state = state_with_validators(&[(&validator, 100)]);
+
+        let params = CommonParams::default_for_test();
+        let signatures = sign_schedule_params(&[&validator], 1, 100, params);
+        assert!(schedule_params(&mut state, 1, 100, params, &signatures, 100).is_err());
+        assert!(schedule_params(&mut state, 1, 100, params, &signatures, 101).is_err());
+        assert_eq!(None, state.metadata().unwrap().unwrap().scheduled_params());
+
+        assert_eq!(Ok(()), schedule_params(&mut state, 1, 100, params, &signatures, 99));
+    }
+
+    #[test]
+    fn cannot_overwrite_scheduled_params() {
+        let validator = Random.generate().unwrap();
+        let mut state = state_with_validators(&[(&validator, 100)]);
+
+        let first = CommonParams::default_for_test();
+        let signatures = sign_schedule_params(&[&validator], 1, 100, first);
+        assert_eq!(Ok(()), schedule_params(&mut state, 1, 100, first, &signatures, 1));
+
+        let mut second = CommonParams::default_for_test();
+        second.set_dynamic_validator_params_for_test(30, 10, 3, 20, 30, 4, 1000, 10000, 200);
+        let signatures = sign_schedule_params(&[&validator], 2, 200, second);
+        assert!(schedule_params(&mut state, 2, 200, second, &signatures, 2).is_err());
+        assert_eq!(Some((100, &first)), state.metadata().unwrap().unwrap().scheduled_params());
+    }
 }
@@ -0,0 +1,103 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// The number of the recent proposals whose timestamps are compared with the local time.
+const MAX_SAMPLES: usize = 16;
+/// The drift is not estimated from fewer proposals, so one proposer with a bad clock is not enough.
+const MIN_SAMPLES: usize = 3;
+
+/// How far the local clock is from the clocks of the other validators.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClockDrift {
+    /// The median of the timestamps of the recent proposals minus the local time when they are received, in seconds.
+    /// It is positive if the local clock is behind the others.
+    pub drift: i64,
+    pub samples: usize,
+    pub max_drift: Duration,
+}
+
+impl ClockDrift {
+    pub fn is_exceeded(&self) -> bool {
+        self.drift.abs() as u64 * 1000 > self.max_drift.as_millis() as u64
+    }
+}
+
+/// Estimates the drift of the local clock from the timestamps of the proposals received from the others.
+#[derive(Default)]
+pub struct ClockDriftMonitor {
+    samples: Mutex<VecDeque<i64>>,
+    max_drift: Mutex<Duration>,
+}
+
+impl ClockDriftMonitor {
+    pub fn set_max_drift(&self, max_drift: Duration) {
+        *self.max_drift.lock() = max_drift;
+    }
+
+    /// Records a proposal with `timestamp` received at `now`, both in seconds, and returns the new estimate.
+    pub fn record(&self, timestamp: u64, now: u64) -> Option<ClockDrift> {
+        {
+            let mut samples = self.samples.lock();
+            if samples.len() == MAX_SAMPLES {
+                samples.pop_front();
+            }
+            samples.push_back(timestamp as i64 - now as i64);
+        }
+        self.estimate()
+    }
+
+    pub fn estimate(&self) -> Option<ClockDrift> {
+        let mut samples: Vec<i64> = self.samples.lock().iter().cloned().collect();
+        if samples.len() < MIN_SAMPLES {
+            return None
+        }
+        samples.sort_unstable();
+        Some(ClockDrift {
+            drift: samples[samples.len() / 2],
+            samples: samples.len(),
+            max_drift: *self.max_drift.lock(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drift_is_the_median_of_the_recent_proposals() {
+        let monitor = ClockDriftMonitor::default();
+        monitor.set_max_drift(Duration::from_secs(3));
+        assert_eq!(None, monitor.record(100, 100));
+        assert_eq!(None, monitor.record(210, 200));
+        let drift = monitor.record(305, 300).unwrap();
+        assert_eq!(5, drift.drift);
+        assert_eq!(3, drift.samples);
+        assert!(drift.is_exceeded());
+
+        for now in 0..MAX_SAMPLES as u64 {
+            monitor.record(now, now + 1);
+        }
+        let drift = monitor.estimate().unwrap();
+        assert_eq!(-1, drift.drift);
+        assert_eq!(MAX_SAMPLES, drift.samples);
+        assert!(!drift.is_exceeded());
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::types::View;
+use ckey::Address;
+use ctypes::BlockNumber;
+use parking_lot::{Mutex, RwLock};
+
+/// A proposal slot: the block and the view at which a validator is designated to propose.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Slot {
+    pub block_number: BlockNumber,
+    pub view: View,
+}
+
+/// The proposal slots of the local validator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Duties {
+    pub proposer: Address,
+    /// The blocks that the validator proposes at view 0, predicted from the best block.
+    pub upcoming: Vec<BlockNumber>,
+    /// The number of the slots missed since the node started.
+    pub missed: u64,
+    pub last_missed: Option<Slot>,
+}
+
+/// Keeps the proposal slots that the local validator missed.
+#[derive(Default)]
+pub struct DutyMonitor {
+    proposer: RwLock<Option<Address>>,
+    missed: Mutex<(u64, Option<Slot>)>,
+}
+
+impl DutyMonitor {
+    pub fn set_proposer(&self, proposer: Address) {
+        *self.proposer.write() = Some(proposer);
+    }
+
+    pub fn proposer(&self) -> Option<Address> {
+        *self.proposer.read()
+    }
+
+    pub fn record_missed(&self, slot: Slot) {
+        let mut missed = self.missed.lock();
+        missed.0 += 1;
+        missed.1 = Some(slot);
+    }
+
+    /// The duties of the local validator. `proposers` are the proposers of the blocks from `next_block`.
+    pub fn duties(&self, proposers: &[Address], next_block: BlockNumber) -> Option<Duties> {
+        let proposer = self.proposer()?;
+        let upcoming = proposers
+            .iter()
+            .zip(next_block..)
+            .filter(|(address, _)| **address == proposer)
+            .map(|(_, block_number)| block_number)
+            .collect();
+        let (missed, last_missed) = *self.missed.lock();
+        Some(Duties {
+            proposer,
+            upcoming,
+            missed,
+            last_missed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duties_are_the_slots_of_the_local_validator() {
+        let monitor = DutyMonitor::default();
+        let (local, other) = (Address::random(), Address::random());
+        assert_eq!(None, monitor.duties(&[local, other], 10));
+
+        monitor.set_proposer(local);
+        monitor.record_missed(Slot {
+            block_number: 7,
+            view: 0,
+        });
+        monitor.record_missed(Slot {
+            block_number: 9,
+            view: 1,
+        });
+        assert_eq!(
+            Some(Duties {
+                proposer: local,
+                upcoming: vec![10, 13],
+                missed: 2,
+                last_missed: Some(Slot {
+                    block_number: 9,
+                    view: 1,
+                }),
+            }),
+            monitor.duties(&[local, other, other, local], 10)
+        );
+    }
+}
@@ -15,36 +15,38 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use super::super::stake;
-use super::super::{ConsensusEngine, EngineError, Seal};
+use super::super::{CodeChainEngine, CommitValidator, ConsensusEngine, EngineError, Seal};
+use super::message::TendermintMessage;
 use super::network::TendermintExtension;
 pub use super::params::{TendermintParams, TimeoutParams};
 use super::types::TendermintSealView;
 use super::worker;
-use super::{ChainNotify, Tendermint, SEAL_FIELDS};
+use super::{ChainNotify, ClockDrift, Duties, Tendermint, View, SEAL_FIELDS};
 use crate::account_provider::AccountProvider;
 use crate::block::*;
 use crate::client::snapshot_notify::NotifySender as SnapshotNotifySender;
 use crate::client::{Client, ConsensusClient};
 use crate::codechain_machine::CodeChainMachine;
-use crate::consensus::tendermint::params::TimeGapParams;
+use crate::consensus::tendermint::params::{MissLimit, TimeGapParams};
 use crate::consensus::{EngineType, ValidatorSet};
 use crate::encoded;
 use crate::error::Error;
 use crate::views::HeaderView;
 use crate::BlockId;
-use ckey::{public_to_address, Address};
+use ckey::{public_to_address, Address, SchnorrSignature};
 use cnetwork::NetworkService;
 use crossbeam_channel as crossbeam;
-use cstate::{ActionHandler, TopState, TopStateView};
-use ctypes::{BlockHash, CommonParams, Header};
+use cstate::{ActionHandler, TopLevelState, TopState, TopStateView};
+use ctypes::{BlockHash, CommonParams, Header, COMMISSION_FORK, LIVENESS_FORK};
 use num_rational::Ratio;
-use rlp::Encodable;
+use rlp::{Encodable, Rlp};
 use std::collections::btree_map::BTreeMap;
 use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::iter::Iterator;
 use std::sync::atomic::Ordering as AtomicOrdering;
 use std::sync::{Arc, Weak};
+use std::thread;
 
 #[derive(Default)]
 struct WorkInfo {
@@ -230,6 +232,11 @@ impl ConsensusEngine for Tendermint {
             _ => {
                 stake::update_validator_weights(block.state_mut(), &author)?;
                 stake::add_intermediate_rewards(block.state_mut(), author, block_author_reward)?;
+                // The first block of a term carries the votes of the previous term's validators.
+                if block_number > metadata.last_term_finished_block_num() + 1 {
+                    let header = block.header().clone();
+                    punish_unresponsive(block.state_mut(), &self.machine, self.miss_limit, &*self.validators, &header)?;
+                }
             }
         }
 
@@ -240,8 +247,18 @@ impl ConsensusEngine for Tendermint {
         let inactive_validators = match term {
             0 => Vec::new(),
             _ => {
-                for (address, reward) in stake::drain_calculated_rewards(block.state_mut())? {
+                let rewards = stake::drain_calculated_rewards(block.state_mut())?;
+                let payouts = if self.machine.is_fork_active(COMMISSION_FORK, block_number) {
+                    stake::distribute_rewards(block.state(), rewards)?
+                } else {
+                    rewards.into_iter().map(|(validator, reward)| (validator, validator, reward)).collect()
+                };
+                for (address, validator, reward) in payouts {
                     self.machine.add_balance(block, &address, reward)?;
+                    stake::record_history(block.state_mut(), &address, stake::StakeEvent::Rewarded {
+                        validator,
+                        quantity: reward,
+                    })?;
                 }
 
                 let start_of_the_current_term = metadata.last_term_finished_block_num() + 1;
@@ -278,6 +295,7 @@ impl ConsensusEngine for Tendermint {
 
     fn set_signer(&self, ap: Arc<AccountProvider>, address: Address) {
         self.has_signer.store(true, AtomicOrdering::SeqCst);
+        self.duties.set_proposer(address);
         self.inner
             .send(worker::Event::SetSigner {
                 ap,
@@ -286,15 +304,86 @@ impl ConsensusEngine for Tendermint {
             .unwrap();
     }
 
+    fn set_signer_owner(&self, owner: Address) {
+        self.duties.set_proposer(owner);
+        self.inner.send(worker::Event::SetSignerOwner(owner)).unwrap();
+    }
+
     fn register_network_extension_to_service(&self, service: &NetworkService) {
         let timeouts = self.timeouts;
 
         let inner = self.inner.clone();
-        let extension = service.register_extension(move |api| TendermintExtension::new(inner, timeouts, api));
+        let shadow = self.shadow.read().clone();
+        let extension = service.register_extension(move |api| TendermintExtension::new(inner, timeouts, shadow, api));
+        let client = Arc::downgrade(&self.client().unwrap());
+        self.extension_initializer.send((extension, client)).unwrap();
+    }
+
+    fn start_as_shadow(&self) {
+        let (extension, events) = crossbeam::unbounded();
+        thread::Builder::new()
+            .name("tendermint.shadow".to_string())
+            .spawn(move || events.iter().for_each(drop))
+            .expect("The thread dropping the messages of the shadow engine is spawned");
         let client = Arc::downgrade(&self.client().unwrap());
         self.extension_initializer.send((extension, client)).unwrap();
     }
 
+    fn register_shadow(&self, shadow: Weak<dyn CodeChainEngine>) {
+        *self.shadow.write() = Some(shadow);
+    }
+
+    fn handle_shadowed_message(&self, message: &[u8]) {
+        match Rlp::new(message).as_val() {
+            Ok(TendermintMessage::ConsensusMessage(messages)) => {
+                let (result, receiver) = crossbeam::bounded(messages.len());
+                self.inner
+                    .send(worker::Event::HandleMessages {
+                        messages,
+                        result,
+                    })
+                    .unwrap();
+                for result in receiver.iter() {
+                    if let Err(e) = result {
+                        cdebug!(ENGINE, "The shadow engine failed to handle a message: {}", e);
+                    }
+                }
+            }
+            Ok(TendermintMessage::ProposalBlock {
+                signature,
+                view,
+                message,
+            }) => {
+                let (result, receiver) = crossbeam::bounded(1);
+                self.inner
+                    .send(worker::Event::ProposalBlock {
+                        signature,
+                        view,
+                        message,
+                        result,
+                    })
+                    .unwrap();
+                receiver.recv().unwrap();
+            }
+            Ok(TendermintMessage::Commit {
+                block,
+                votes,
+            }) => {
+                let (result, receiver) = crossbeam::bounded(1);
+                self.inner
+                    .send(worker::Event::GetCommit {
+                        block,
+                        votes,
+                        result,
+                    })
+                    .unwrap();
+                receiver.recv().unwrap();
+            }
+            // The active engine answers the requests and the states of the peers.
+            _ => {}
+        }
+    }
+
     fn register_time_gap_config_to_worker(&self, time_gap_params: TimeGapParams) {
         self.external_params_initializer.send(time_gap_params).unwrap();
     }
@@ -339,6 +428,16 @@ impl ConsensusEngine for Tendermint {
         &self.action_handlers
     }
 
+    fn clock_drift(&self) -> Option<ClockDrift> {
+        self.clock_drift.estimate()
+    }
+
+    fn duties(&self, count: usize) -> Option<Duties> {
+        let best = self.client()?.block_header(&BlockId::Latest)?;
+        let proposers = self.validators.upcoming_proposers(&best.hash(), count);
+        self.duties.duties(&proposers, best.number() + 1)
+    }
+
     fn possible_authors(&self, block_number: Option<u64>) -> Result<Option<Vec<Address>>, EngineError> {
         let client = self.client().ok_or(EngineError::CannotOpenBlock)?;
         let block_hash = match block_number {
@@ -354,6 +453,51 @@ impl ConsensusEngine for Tendermint {
         };
         Ok(Some(self.validators.next_addresses(&block_hash)))
     }
+
+    fn commit_validators(&self, block_hash: &BlockHash) -> Option<Vec<CommitValidator>> {
+        let client = self.client()?;
+        let state = client.state_at((*block_hash).into())?;
+        let current = stake::CurrentValidators::load_from_state(&state).ok()?;
+        if !current.is_empty() {
+            return Some(commit_validators(&current))
+        }
+
+        // Same as the fallback in verify_block_external: the validators are taken from the parent.
+        let parent_hash = client.block_header(&(*block_hash).into())?.parent_hash();
+        let parent_state = client.state_at(parent_hash.into())?;
+        let next = stake::NextValidators::load_from_state(&parent_state).ok()?;
+        if !next.is_empty() {
+            return Some(commit_validators(&next))
+        }
+        Some(
+            (0..self.validators.count(&parent_hash))
+                .map(|index| CommitValidator {
+                    pubkey: self.validators.get(&parent_hash, index),
+                    voting_power: 1,
+                })
+                .collect(),
+        )
+    }
+
+    fn parent_commit(&self, header: &Header) -> Option<(View, Vec<(usize, SchnorrSignature)>)> {
+        if header.seal().len() != SEAL_FIELDS {
+            return None
+        }
+        let seal_view = TendermintSealView::new(header.seal());
+        Some((seal_view.parent_block_finalized_view().ok()?, seal_view.signatures().ok()?))
+    }
+}
+
+/// The validators are stored in the reverse order of the signature indices.
+fn commit_validators(validators: &[stake::Validator]) -> Vec<CommitValidator> {
+    validators
+        .iter()
+        .rev()
+        .map(|validator| CommitValidator {
+            pubkey: *validator.pubkey(),
+            voting_power: validator.delegation(),
+        })
+        .collect()
 }
 
 pub(super) fn is_term_changed(header: &Header, parent: &Header, term_seconds: u64) -> bool {
@@ -384,6 +528,50 @@ fn inactive_validators(
     validators.into_iter().collect()
 }
 
+/// Returns the proposers of the views that failed before the block is proposed,
+/// and the validators that didn't vote for the parent block.
+/// Records the misses of the block and jails the validators that missed too many proposals and votes.
+/// Nothing is recorded without the miss limit or before the liveness fork.
+fn punish_unresponsive(
+    state: &mut TopLevelState,
+    machine: &CodeChainMachine,
+    miss_limit: Option<MissLimit>,
+    validators: &dyn ValidatorSet,
+    header: &Header,
+) -> Result<(), Error> {
+    let limit = match miss_limit {
+        Some(limit) if machine.is_fork_active(LIVENESS_FORK, header.number()) => limit,
+        _ => return Ok(()),
+    };
+    let (missed_proposers, missed_voters) = missed_validators(validators, header)?;
+    stake::record_misses(state, *header.author(), &missed_proposers, &missed_voters)?;
+    for address in stake::jail_unresponsive(state, limit.max_rate, limit.min_blocks)? {
+        cinfo!(ENGINE, "{} is jailed for missing too many proposals and votes", address);
+    }
+    Ok(())
+}
+
+fn missed_validators(validators: &dyn ValidatorSet, header: &Header) -> Result<(Vec<Address>, Vec<Address>), Error> {
+    let parent_hash = header.parent_hash();
+    let seal = TendermintSealView::new(header.seal());
+
+    let missed_proposers = (0..seal.author_view()?)
+        .filter_map(|view| validators.next_block_proposer(parent_hash, view))
+        .filter(|proposer| proposer != header.author())
+        .collect();
+
+    let votes = seal.bitset()?;
+    let missed_voters = validators
+        .current_addresses(parent_hash)
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| !votes.is_set(*index))
+        .map(|(_, address)| address)
+        .collect();
+
+    Ok((missed_proposers, missed_voters))
+}
+
 // Aggregate the validators' work info of a term
 fn aggregate_work_info(
     chain: &dyn ConsensusClient,
@@ -526,6 +714,12 @@ mod tests {
     use std::iter::FromIterator;
 
     use super::*;
+    use crate::consensus::{BitSet, RoundRobinValidator};
+    use cjson::scheme::Forks;
+    use ckey::{Generator, Random};
+    use cstate::tests::helpers;
+    use cstate::StateWithCache;
+    use ctypes::ForkSchedule;
 
     #[test]
     fn test_final_rewards() {
@@ -705,4 +899,48 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn misses_are_recorded_from_the_liveness_fork() {
+        let proposer = Random.generate().unwrap();
+        let absentee = Random.generate().unwrap();
+        let validators = RoundRobinValidator::new(vec![*proposer.public(), *absentee.public()]);
+        let machine = {
+            let mut forks = Forks::new();
+            forks.insert(LIVENESS_FORK.to_string(), 10.into());
+            CodeChainMachine::new(CommonParams::default_for_test(), ForkSchedule::try_from(forks).unwrap())
+        };
+        let miss_limit = MissLimit {
+            max_rate: 50,
+            min_blocks: 100,
+        };
+        let header = |number| {
+            let mut header = Header::default();
+            header.set_number(number);
+            header.set_author(proposer.address());
+            header.set_seal(
+                Seal::Tendermint {
+                    prev_view: 0,
+                    cur_view: 0,
+                    precommits: vec![],
+                    precommit_bitset: BitSet::new_with_indices(&[0]),
+                }
+                .seal_fields()
+                .unwrap(),
+            );
+            header
+        };
+
+        let mut state = helpers::get_temp_state_with_metadata();
+        let root = state.commit().unwrap();
+        punish_unresponsive(&mut state, &machine, Some(miss_limit), &validators, &header(9)).unwrap();
+        assert_eq!(root, state.commit().unwrap(), "The state root is unchanged before the fork");
+        punish_unresponsive(&mut state, &machine, None, &validators, &header(10)).unwrap();
+        assert_eq!(root, state.commit().unwrap(), "Nothing is recorded without the miss limit");
+
+        punish_unresponsive(&mut state, &machine, Some(miss_limit), &validators, &header(10)).unwrap();
+        assert_ne!(root, state.commit().unwrap());
+        let misses = stake::Misses::load_from_state(&state).unwrap();
+        assert_eq!(1, misses.blocks());
+    }
 }
@@ -516,4 +516,17 @@ mod tests {
         let decoded = rlp::decode::<ConsensusMessage>(&encoded).unwrap();
         assert_eq!(consensus_message, decoded);
     }
+
+    #[test]
+    fn truncated_tendermint_message_is_not_decoded() {
+        let message = TendermintMessage::ProposalBlock {
+            signature: SchnorrSignature::random(),
+            view: 1,
+            message: vec![1u8, 2u8],
+        };
+        let encoded = rlp::encode(&message);
+        for length in 0..encoded.len() {
+            assert!(rlp::decode::<TendermintMessage>(&encoded[..length]).is_err(), "{} bytes", length);
+        }
+    }
 }
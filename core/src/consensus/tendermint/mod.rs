@@ -16,6 +16,8 @@
 
 mod backup;
 mod chain_notify;
+mod clock_drift;
+mod duty;
 mod engine;
 mod message;
 mod network;
@@ -26,16 +28,23 @@ mod vote_regression_checker;
 mod worker;
 
 use self::chain_notify::TendermintChainNotify;
+pub use self::clock_drift::ClockDrift;
+use self::clock_drift::ClockDriftMonitor;
+pub use self::duty::{Duties, Slot};
+use self::duty::DutyMonitor;
 pub use self::message::{ConsensusMessage, VoteOn, VoteStep};
-pub use self::params::{TendermintParams, TimeGapParams, TimeoutParams};
+#[cfg(feature = "fuzzing")]
+pub use self::message::TendermintMessage;
+pub use self::params::{MissLimit, TendermintParams, TimeGapParams, TimeoutParams};
 pub use self::types::{Height, Step, View};
 pub use super::{stake, ValidatorSet};
 use crate::client::ConsensusClient;
 use crate::codechain_machine::CodeChainMachine;
+use crate::consensus::CodeChainEngine;
 use crate::snapshot_notify::NotifySender as SnapshotNotifySender;
 use crate::ChainNotify;
 use crossbeam_channel as crossbeam;
-use cstate::ActionHandler;
+use cstate::{ActionHandler, NameRegistry};
 use ctimer::TimerToken;
 use parking_lot::RwLock;
 use std::sync::atomic::AtomicBool;
@@ -65,6 +74,8 @@ pub struct Tendermint {
     validators: Arc<dyn ValidatorSet>,
     /// Reward per block, in base units.
     block_reward: u64,
+    /// Jails the validators that miss too many proposals and votes.
+    miss_limit: Option<MissLimit>,
     /// codechain machine descriptor
     machine: Arc<CodeChainMachine>,
     /// Action handlers for this consensus method
@@ -74,6 +85,10 @@ pub struct Tendermint {
     /// Chain notify
     chain_notify: Arc<TendermintChainNotify>,
     has_signer: AtomicBool,
+    clock_drift: Arc<ClockDriftMonitor>,
+    duties: Arc<DutyMonitor>,
+    /// The engine fed the consensus messages received from the peers in shadow mode.
+    shadow: RwLock<Option<Weak<dyn CodeChainEngine>>>,
 }
 
 impl Drop for Tendermint {
@@ -89,9 +104,11 @@ impl Tendermint {
     /// Create a new instance of Tendermint engine
     pub fn new(our_params: TendermintParams, machine: CodeChainMachine) -> Arc<Self> {
         let validators = Arc::clone(&our_params.validators);
-        let stake = Arc::new(stake::Stake::new(our_params.genesis_stakes));
+        let stake = Arc::new(stake::Stake::new(our_params.genesis_stakes, machine.fork_schedule().clone()));
         let timeouts = our_params.timeouts;
         let machine = Arc::new(machine);
+        let clock_drift = Arc::new(ClockDriftMonitor::default());
+        let duties = Arc::new(DutyMonitor::default());
 
         let (
             join,
@@ -100,8 +117,8 @@ impl Tendermint {
             snapshot_notify_sender_initializer,
             inner,
             quit_tendermint,
-        ) = worker::spawn(our_params.validators);
-        let action_handlers: Vec<Arc<dyn ActionHandler>> = vec![stake.clone()];
+        ) = worker::spawn(our_params.validators, Arc::clone(&clock_drift), Arc::clone(&duties));
+        let action_handlers: Vec<Arc<dyn ActionHandler>> = vec![stake.clone(), Arc::new(NameRegistry::new())];
         let chain_notify = Arc::new(TendermintChainNotify::new(inner.clone()));
 
         Arc::new(Tendermint {
@@ -115,11 +132,15 @@ impl Tendermint {
             inner,
             validators,
             block_reward: our_params.block_reward,
+            miss_limit: our_params.miss_limit,
             machine,
             action_handlers,
             stake,
             chain_notify,
             has_signer: false.into(),
+            clock_drift,
+            duties,
+            shadow: Default::default(),
         })
     }
 
@@ -172,6 +193,7 @@ mod tests {
         let time_gap_params = TimeGapParams {
             allowed_past_gap: Duration::from_millis(30000),
             allowed_future_gap: Duration::from_millis(5000),
+            max_clock_drift: Duration::from_millis(3000),
         };
         engine.register_time_gap_config_to_worker(time_gap_params);
         assert!(!engine.name().is_empty());
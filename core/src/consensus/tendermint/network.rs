@@ -23,7 +23,7 @@ use super::{
     ENGINE_TIMEOUT_BROADCAST_STEP_STATE, ENGINE_TIMEOUT_BROADCAT_STEP_STATE_INTERVAL, ENGINE_TIMEOUT_EMPTY_PROPOSAL,
     ENGINE_TIMEOUT_TOKEN_NONCE_BASE,
 };
-use crate::consensus::EngineError;
+use crate::consensus::{CodeChainEngine, EngineError};
 use ckey::SchnorrSignature;
 use cnetwork::{Api, NetworkExtension, NodeId};
 use crossbeam_channel as crossbeam;
@@ -36,7 +36,7 @@ use rlp::{Encodable, Rlp};
 use std::cmp;
 use std::collections::HashMap;
 use std::iter::Iterator;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 use std::time::Duration;
 
 pub struct TendermintExtension {
@@ -44,13 +44,19 @@ pub struct TendermintExtension {
     peers: HashMap<NodeId, PeerState>,
     api: Box<dyn Api>,
     timeouts: TimeoutParams,
+    shadow: Option<Weak<dyn CodeChainEngine>>,
 }
 
 const MIN_PEERS_PROPAGATION: usize = 4;
 const MAX_PEERS_PROPAGATION: usize = 128;
 
 impl TendermintExtension {
-    pub fn new(inner: crossbeam::Sender<worker::Event>, timeouts: TimeoutParams, api: Box<dyn Api>) -> Self {
+    pub fn new(
+        inner: crossbeam::Sender<worker::Event>,
+        timeouts: TimeoutParams,
+        shadow: Option<Weak<dyn CodeChainEngine>>,
+        api: Box<dyn Api>,
+    ) -> Self {
         let initial = timeouts.initial();
         ctrace!(ENGINE, "Setting the initial timeout to {:?}.", initial);
         api.set_timer_once(ENGINE_TIMEOUT_TOKEN_NONCE_BASE, initial).expect("Timer set succeeds");
@@ -64,6 +70,7 @@ impl TendermintExtension {
             peers: Default::default(),
             api,
             timeouts,
+            shadow,
         }
     }
 
@@ -397,6 +404,10 @@ impl NetworkExtension<Event> for TendermintExtension {
             }
             _ => cinfo!(ENGINE, "Invalid message from peer {}", token),
         }
+
+        if let Some(shadow) = self.shadow.as_ref().and_then(Weak::upgrade) {
+            shadow.handle_shadowed_message(data);
+        }
     }
 
     fn on_timeout(&mut self, token: TimerToken) {
@@ -33,6 +33,8 @@ pub struct TendermintParams {
     pub block_reward: u64,
     /// Tokens distributed at genesis.
     pub genesis_stakes: HashMap<Address, u64>,
+    /// Jails the validators that miss too many proposals and votes.
+    pub miss_limit: Option<MissLimit>,
 }
 
 impl From<cjson::scheme::TendermintParams> for TendermintParams {
@@ -56,18 +58,35 @@ impl From<cjson::scheme::TendermintParams> for TendermintParams {
                 .into_iter()
                 .map(|(pa, amount)| (PlatformAddress::into_address(pa), amount))
                 .collect(),
+            miss_limit: p.max_miss_rate.map(|max_rate| MissLimit {
+                max_rate: max_rate.into(),
+                min_blocks: p.min_miss_rate_blocks.map_or(DEFAULT_MIN_MISS_RATE_BLOCKS, Into::into),
+            }),
         }
     }
 }
 
+const DEFAULT_MIN_MISS_RATE_BLOCKS: u64 = 10;
+
+#[derive(Debug, Copy, Clone)]
+pub struct MissLimit {
+    /// Percentage of the missed proposals and votes in a term.
+    pub max_rate: u64,
+    /// The number of blocks in a term before the miss rate is checked.
+    pub min_blocks: u64,
+}
+
 fn to_duration(ms: cjson::uint::Uint) -> Duration {
     let ms: usize = ms.into();
     Duration::from_millis(ms as u64)
 }
 
+#[derive(Clone, Copy)]
 pub struct TimeGapParams {
     pub allowed_past_gap: Duration,
     pub allowed_future_gap: Duration,
+    /// The drift of the local clock from the other validators above which the node warns.
+    pub max_clock_drift: Duration,
 }
 
 /// Base timeout of each step in ms.
@@ -16,6 +16,8 @@
 
 use super::super::BitSet;
 use super::backup::{backup, restore, BackupView};
+use super::clock_drift::ClockDriftMonitor;
+use super::duty::{DutyMonitor, Slot};
 use super::message::*;
 use super::network;
 use super::params::TimeGapParams;
@@ -64,8 +66,12 @@ type SpawnResult = (
     crossbeam::Sender<()>,
 );
 
-pub fn spawn(validators: Arc<DynamicValidator>) -> SpawnResult {
-    Worker::spawn(validators)
+pub fn spawn(
+    validators: Arc<DynamicValidator>,
+    clock_drift: Arc<ClockDriftMonitor>,
+    duties: Arc<DutyMonitor>,
+) -> SpawnResult {
+    Worker::spawn(validators, clock_drift, duties)
 }
 
 struct Worker {
@@ -99,6 +105,10 @@ struct Worker {
     timeout_token_nonce: usize,
     vote_regression_checker: VoteRegressionChecker,
     snapshot_notify_sender: SnapshotNotifySender,
+    clock_drift: Arc<ClockDriftMonitor>,
+    /// Whether the last estimate of the clock drift exceeded the limit.
+    clock_drift_exceeded: bool,
+    duties: Arc<DutyMonitor>,
 }
 
 pub enum Event {
@@ -138,6 +148,7 @@ pub enum Event {
         ap: Arc<AccountProvider>,
         address: Address,
     },
+    SetSignerOwner(Address),
     Restore(crossbeam::Sender<()>),
     ProposalBlock {
         signature: SchnorrSignature,
@@ -183,7 +194,10 @@ impl Worker {
         client: Weak<dyn ConsensusClient>,
         time_gap_params: TimeGapParams,
         snapshot_notify_sender: SnapshotNotifySender,
+        clock_drift: Arc<ClockDriftMonitor>,
+        duties: Arc<DutyMonitor>,
     ) -> Self {
+        clock_drift.set_max_drift(time_gap_params.max_clock_drift);
         Worker {
             client,
             height: 1,
@@ -202,10 +216,17 @@ impl Worker {
             timeout_token_nonce: ENGINE_TIMEOUT_TOKEN_NONCE_BASE,
             vote_regression_checker: VoteRegressionChecker::new(),
             snapshot_notify_sender,
+            clock_drift,
+            clock_drift_exceeded: false,
+            duties,
         }
     }
 
-    fn spawn(validators: Arc<DynamicValidator>) -> SpawnResult {
+    fn spawn(
+        validators: Arc<DynamicValidator>,
+        clock_drift: Arc<ClockDriftMonitor>,
+        duties: Arc<DutyMonitor>,
+    ) -> SpawnResult {
         let (sender, receiver) = crossbeam::unbounded();
         let (quit, quit_receiver) = crossbeam::bounded(1);
         let (external_params_initializer, external_params_receiver) = crossbeam::bounded(1);
@@ -276,7 +297,15 @@ impl Worker {
                 }
                 };
                 validators.register_client(Weak::clone(&client));
-                let mut inner = Self::new(validators, extension, client, time_gap_params, snapshot_notify_sender);
+                let mut inner = Self::new(
+                    validators,
+                    extension,
+                    client,
+                    time_gap_params,
+                    snapshot_notify_sender,
+                    clock_drift,
+                    duties,
+                );
                 loop {
                     crossbeam::select! {
                     recv(receiver) -> msg => {
@@ -334,6 +363,9 @@ impl Worker {
                             }) => {
                                 inner.set_signer(ap, address);
                             }
+                            Ok(Event::SetSignerOwner(owner)) => {
+                                inner.signer.set_owner(owner);
+                            }
                             Ok(Event::Restore(result)) => {
                                 inner.restore();
                                 result.send(()).unwrap();
@@ -552,7 +584,7 @@ impl Worker {
 
     /// Check if current signer is the current proposer.
     fn is_signer_proposer(&self, bh: &BlockHash) -> bool {
-        self.view_proposer(bh, self.view).map_or(false, |proposer| self.signer.is_address(&proposer))
+        self.view_proposer(bh, self.view).map_or(false, |proposer| self.signer.validator_address() == Some(&proposer))
     }
 
     fn is_step(&self, message: &ConsensusMessage) -> bool {
@@ -750,6 +782,8 @@ impl Worker {
                         Ok(block) => self.repropose_block(block),
                         Err(error_msg) => cwarn!(ENGINE, "{}", error_msg),
                     }
+                } else if let Some(error_msg) = self.clock_drift_invalidates_proposal() {
+                    cerror!(ENGINE, "I am a proposer, but I don't create a block: {}", error_msg);
                 } else {
                     cinfo!(ENGINE, "I am a proposer, I'll create a block");
                     self.update_sealing(parent_block_hash);
@@ -853,6 +887,40 @@ impl Worker {
         }
     }
 
+    /// Returns the reason if the others would reject a block generated now because of the local clock.
+    fn clock_drift_invalidates_proposal(&self) -> Option<String> {
+        let drift = self.clock_drift.estimate()?.drift;
+        let gap = Duration::from_secs(drift.abs() as u64);
+        if drift > 0 && gap > self.time_gap_params.allowed_past_gap {
+            Some(format!("the local clock is {} seconds behind the other validators", drift))
+        } else if drift < 0 && gap > self.time_gap_params.allowed_future_gap {
+            Some(format!("the local clock is {} seconds ahead of the other validators", -drift))
+        } else {
+            None
+        }
+    }
+
+    /// Compares the timestamp of a proposal just received with the local time.
+    fn record_proposal_time(&mut self, timestamp: u64) {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let drift = match self.clock_drift.record(timestamp, now) {
+            Some(drift) => drift,
+            None => return,
+        };
+        let exceeded = drift.is_exceeded();
+        if exceeded && !self.clock_drift_exceeded {
+            cerror!(
+                ENGINE,
+                "The local clock drifts {} seconds from the other validators over the {} ms allowed. Check the time synchronization of this node.",
+                drift.drift,
+                drift.max_drift.as_millis()
+            );
+        } else if !exceeded && self.clock_drift_exceeded {
+            cinfo!(ENGINE, "The local clock drifts {} seconds from the other validators", drift.drift);
+        }
+        self.clock_drift_exceeded = exceeded;
+    }
+
     fn locked_proposal_block(
         &self,
         locked_view: View,
@@ -1649,6 +1717,7 @@ impl Worker {
         };
 
         self.send_snapshot_notify(c.as_ref(), enacted.as_slice());
+        self.check_missed_slots(c.as_ref(), enacted.as_slice());
 
         if self.step.is_commit() && (imported.len() + enacted.len() == 1) {
             let (_, committed_block_hash) = self.step.committed().expect("Commit state always has block_hash");
@@ -1701,6 +1770,38 @@ impl Worker {
         }
     }
 
+    /// Warns of the slots of the signer that passed before the blocks were proposed.
+    fn check_missed_slots(&self, c: &dyn ConsensusClient, enacted: &[BlockHash]) {
+        let signer = match self.signer.validator_address() {
+            Some(signer) => *signer,
+            None => return,
+        };
+        for block_hash in enacted {
+            let header = c.block_header(&(*block_hash).into()).expect("Enacted block must exist").decode();
+            let author_view = match TendermintSealView::new(header.seal()).author_view() {
+                Ok(author_view) => author_view,
+                Err(_) => continue,
+            };
+            for view in 0..author_view {
+                if self.view_proposer(header.parent_hash(), view) != Some(signer) {
+                    continue
+                }
+                cwarn!(
+                    ENGINE,
+                    "Missed the proposal slot of #{} at view {}. The block is proposed by {} at view {}",
+                    header.number(),
+                    view,
+                    header.author(),
+                    author_view
+                );
+                self.duties.record_missed(Slot {
+                    block_number: header.number(),
+                    view,
+                });
+            }
+        }
+    }
+
     // Notify once for the latest block even if multiple blocks have been enacted.
     fn send_snapshot_notify(&mut self, c: &dyn ConsensusClient, enacted: &[BlockHash]) {
         let mut last_snapshot_point = None;
@@ -1849,6 +1950,10 @@ impl Worker {
             }
 
             if number == self.height as u64 && proposed_view == self.view {
+                // A re-proposed block was generated in an earlier view, so its timestamp is old.
+                if TendermintSealView::new(header_view.seal()).author_view().ok() == Some(proposed_view) {
+                    self.record_proposal_time(header_view.timestamp());
+                }
                 // The proposer re-proposed its locked proposal.
                 // If we already imported the proposal, we should set `proposal` here.
                 if c.block(&BlockId::Hash(header_view.hash())).is_some() {
@@ -17,7 +17,7 @@
 use super::{RoundRobinValidator, ValidatorSet};
 use crate::client::ConsensusClient;
 use crate::consensus::bit_set::BitSet;
-use crate::consensus::stake::{CurrentValidators, NextValidators, PreviousValidators, Validator};
+use crate::consensus::stake::{CurrentValidators, NextValidators, PreviousValidators, SessionKey, Validator};
 use crate::consensus::EngineError;
 use ckey::{public_to_address, Address, Public};
 use ctypes::util::unexpected::OutOfBounds;
@@ -39,7 +39,7 @@ impl DynamicValidator {
         }
     }
 
-    fn next_validators(&self, hash: BlockHash) -> Option<Vec<Validator>> {
+    fn load_next_validators(&self, hash: BlockHash) -> Option<NextValidators> {
         let client: Arc<dyn ConsensusClient> =
             self.client.read().as_ref().and_then(Weak::upgrade).expect("Client is not initialized");
         let block_id = hash.into();
@@ -56,12 +56,16 @@ impl DynamicValidator {
         if validators.is_empty() {
             None
         } else {
-            let mut validators: Vec<_> = validators.into();
-            validators.reverse();
             Some(validators)
         }
     }
 
+    fn next_validators(&self, hash: BlockHash) -> Option<Vec<Validator>> {
+        let mut validators: Vec<_> = self.load_next_validators(hash)?.into();
+        validators.reverse();
+        Some(validators)
+    }
+
     fn current_validators(&self, hash: BlockHash) -> Option<Vec<Validator>> {
         let client: Arc<dyn ConsensusClient> =
             self.client.read().as_ref().and_then(Weak::upgrade).expect("Client is not initialized");
@@ -108,6 +112,32 @@ impl DynamicValidator {
         }
     }
 
+    /// The keys signing the votes on the child of `parent`.
+    /// A validator signs with its session key until the key expires, and with its own key otherwise.
+    fn signing_keys(&self, parent: BlockHash, validators: Vec<Validator>) -> Vec<Public> {
+        let client: Arc<dyn ConsensusClient> =
+            self.client.read().as_ref().and_then(Weak::upgrade).expect("Client is not initialized");
+        let (state, height) = match (client.state_at(parent.into()), client.block_number(&parent.into())) {
+            (Some(state), Some(number)) => (state, number + 1),
+            _ => return validators.iter().map(|validator| *validator.pubkey()).collect(),
+        };
+        validators
+            .iter()
+            .map(|validator| {
+                let owner = public_to_address(validator.pubkey());
+                match SessionKey::load_from_state(&state, &owner).unwrap() {
+                    Some(session) if session.is_active(height) => session.pubkey,
+                    _ => *validator.pubkey(),
+                }
+            })
+            .collect()
+    }
+
+    fn signing_pubkeys(&self, parent: BlockHash) -> Option<Vec<Public>> {
+        let validators = self.next_validators(parent)?;
+        Some(self.signing_keys(parent, validators))
+    }
+
     fn validators_pubkey(&self, hash: BlockHash) -> Option<Vec<Public>> {
         self.next_validators(hash).map(|validators| validators.into_iter().map(|val| *val.pubkey()).collect())
     }
@@ -131,7 +161,12 @@ impl DynamicValidator {
     }
 
     pub fn get_current(&self, hash: &BlockHash, index: usize) -> Option<Public> {
-        let validators = self.current_validators_pubkey(*hash)?;
+        let validators = self.current_validators(*hash)?;
+        let client: Arc<dyn ConsensusClient> =
+            self.client.read().as_ref().and_then(Weak::upgrade).expect("Client is not initialized");
+        // The current validators voted on the block of `hash`, whose parent has their session keys.
+        let parent = client.block_header(&(*hash).into())?.parent_hash();
+        let validators = self.signing_keys(parent, validators);
         let n_validators = validators.len();
         Some(*validators.get(index % n_validators).unwrap())
     }
@@ -171,7 +206,7 @@ impl DynamicValidator {
 
 impl ValidatorSet for DynamicValidator {
     fn contains(&self, parent: &BlockHash, public: &Public) -> bool {
-        if let Some(validators) = self.validators_pubkey(*parent) {
+        if let Some(validators) = self.signing_pubkeys(*parent) {
             validators.into_iter().any(|pubkey| pubkey == *public)
         } else {
             self.initial_list.contains(parent, public)
@@ -187,7 +222,7 @@ impl ValidatorSet for DynamicValidator {
     }
 
     fn get(&self, parent: &BlockHash, index: usize) -> Public {
-        if let Some(validators) = self.validators_pubkey(*parent) {
+        if let Some(validators) = self.signing_pubkeys(*parent) {
             let n_validators = validators.len();
             *validators.get(index % n_validators).unwrap()
         } else {
@@ -195,8 +230,17 @@ impl ValidatorSet for DynamicValidator {
         }
     }
 
-    fn get_index(&self, parent: &BlockHash, public: &Public) -> Option<usize> {
+    fn get_address(&self, parent: &BlockHash, index: usize) -> Address {
         if let Some(validators) = self.validators_pubkey(*parent) {
+            let n_validators = validators.len();
+            public_to_address(validators.get(index % n_validators).unwrap())
+        } else {
+            self.initial_list.get_address(parent, index)
+        }
+    }
+
+    fn get_index(&self, parent: &BlockHash, public: &Public) -> Option<usize> {
+        if let Some(validators) = self.signing_pubkeys(*parent) {
             validators.into_iter().enumerate().find(|(_index, pubkey)| pubkey == public).map(|(index, _)| index)
         } else {
             self.initial_list.get_index(parent, public)
@@ -225,6 +269,16 @@ impl ValidatorSet for DynamicValidator {
         }
     }
 
+    /// The weights of the validators are updated by the proposers, but the validators elected when the term closes
+    /// are not foreseen.
+    fn upcoming_proposers(&self, parent: &BlockHash, count: usize) -> Vec<Address> {
+        if let Some(validators) = self.load_next_validators(*parent) {
+            validators.upcoming_proposers(count)
+        } else {
+            self.initial_list.upcoming_proposers(parent, count)
+        }
+    }
+
     fn count(&self, parent: &BlockHash) -> usize {
         if let Some(validators) = self.next_validators(*parent) {
             validators.len()
@@ -18,7 +18,7 @@ use self::validator_list::RoundRobinValidator;
 use super::BitSet;
 use crate::client::ConsensusClient;
 use crate::consensus::EngineError;
-use ckey::{Address, Public};
+use ckey::{public_to_address, Address, Public};
 use ctypes::BlockHash;
 use std::sync::Weak;
 
@@ -37,8 +37,14 @@ pub trait ValidatorSet: Send + Sync {
     fn contains_address(&self, parent: &BlockHash, address: &Address) -> bool;
 
     /// Draws a validator from index modulo number of validators.
+    /// It's the key that signs the consensus messages, which is the session key of the validator if it has one.
     fn get(&self, parent: &BlockHash, index: usize) -> Public;
 
+    /// Draws the address of a validator from index modulo number of validators.
+    fn get_address(&self, parent: &BlockHash, index: usize) -> Address {
+        public_to_address(&self.get(parent, index))
+    }
+
     /// Draws a validator from nonce modulo number of validators.
     fn get_index(&self, parent: &BlockHash, public: &Public) -> Option<usize>;
 
@@ -47,6 +53,9 @@ pub trait ValidatorSet: Send + Sync {
 
     fn next_block_proposer(&self, parent: &BlockHash, view: u64) -> Option<Address>;
 
+    /// The proposers of the `count` blocks after `parent` at view 0, supposing each of them proposes its block.
+    fn upcoming_proposers(&self, parent: &BlockHash, count: usize) -> Vec<Address>;
+
     /// Returns the current number of validators.
     fn count(&self, parent: &BlockHash) -> usize;
 
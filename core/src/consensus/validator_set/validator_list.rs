@@ -80,6 +80,11 @@ impl ValidatorSet for RoundRobinValidator {
         })
     }
 
+    fn upcoming_proposers(&self, parent: &BlockHash, count: usize) -> Vec<Address> {
+        // The proposer of the block at `view` is the one of the next block at view 0, and so on.
+        (0..count as u64).filter_map(|view| self.next_block_proposer(parent, view)).collect()
+    }
+
     fn count(&self, _bh: &BlockHash) -> usize {
         self.validators.len()
     }
@@ -15,6 +15,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use crate::account_provider::Error as AccountProviderError;
+use crate::client::DiskSpaceLevel;
 use crate::consensus::EngineError;
 use cdb::DatabaseError;
 use cio::IoError;
@@ -38,6 +39,8 @@ pub enum ImportError {
     AlreadyQueued,
     /// Already marked as bad from a previous import (could mean parent is bad).
     KnownBad,
+    /// The node is read-only because the disk space is critical.
+    LowDiskSpace,
 }
 
 impl fmt::Display for ImportError {
@@ -46,6 +49,7 @@ impl fmt::Display for ImportError {
             ImportError::AlreadyInChain => "block already in chain",
             ImportError::AlreadyQueued => "block already in the block queue",
             ImportError::KnownBad => "block known to be bad",
+            ImportError::LowDiskSpace => "the disk space is critical",
         };
 
         f.write_fmt(format_args!("Block import error ({})", msg))
@@ -113,10 +117,14 @@ pub enum BlockError {
     BodySizeIsTooBig,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum SchemeError {
     InvalidCommonParams,
     InvalidState,
+    /// The database holds another chain.
+    GenesisMismatch(Mismatch<BlockHash>),
+    /// The forks section names an upgrade unknown to this node.
+    InvalidForks(String),
 }
 
 impl fmt::Display for SchemeError {
@@ -125,6 +133,8 @@ impl fmt::Display for SchemeError {
         let msg: String = match self {
             InvalidCommonParams => "Common params are not matched with gensis block".into(),
             InvalidState => "Genesis state is not same with spec".into(),
+            GenesisMismatch(mis) => format!("The database holds another chain. Genesis block: {}", mis),
+            InvalidForks(reason) => format!("Invalid forks: {}", reason),
         };
         f.write_fmt(format_args!("Scheme file error ({})", msg))
     }
@@ -180,6 +190,12 @@ pub enum Error {
     /// Error concerning a database.
     Database(DatabaseError),
     Rlp(DecoderError),
+    /// The node refuses the transactions because the disk space is low.
+    LowDiskSpace(DiskSpaceLevel),
+    /// The node refuses the transactions because it's in maintenance.
+    Maintenance,
+    /// The mem pool refuses the transaction by its admission policy.
+    Rejected(String),
     Other(String),
 }
 
@@ -199,6 +215,9 @@ impl fmt::Display for Error {
             Error::Syntax(err) => err.fmt(f),
             Error::Database(err) => err.fmt(f),
             Error::Rlp(err) => err.fmt(f),
+            Error::LowDiskSpace(level) => write!(f, "The disk space is {}: new transactions are refused", level),
+            Error::Maintenance => write!(f, "The node is in maintenance: new transactions are refused"),
+            Error::Rejected(reason) => write!(f, "The transaction is rejected: {}", reason),
             Error::Other(s) => write!(f, "{}", s),
         }
     }
@@ -273,6 +292,7 @@ impl From<StateError> for Error {
         match err {
             StateError::Trie(err) => Error::Trie(err),
             StateError::Runtime(err) => Error::Runtime(err),
+            StateError::Rlp(err) => Error::Rlp(err),
         }
     }
 }
@@ -24,6 +24,7 @@ extern crate codechain_key as ckey;
 extern crate codechain_keystore as ckeystore;
 #[macro_use]
 extern crate codechain_logger as clogger;
+extern crate codechain_memory as cmemory;
 extern crate codechain_network as cnetwork;
 extern crate codechain_state as cstate;
 extern crate codechain_timer as ctimer;
@@ -34,6 +35,8 @@ extern crate rand_xorshift;
 extern crate rlp;
 #[macro_use]
 extern crate rlp_derive;
+#[cfg(test)]
+extern crate tempfile;
 #[macro_use]
 extern crate log;
 
@@ -66,16 +69,29 @@ pub use crate::block::Block;
 pub use crate::client::snapshot_notify;
 pub use crate::client::ConsensusClient;
 pub use crate::client::{
-    AccountData, BlockChainClient, BlockChainTrait, ChainNotify, Client, ClientConfig, DatabaseClient, EngineClient,
-    EngineInfo, ExecuteClient, ImportBlock, MiningBlockChainClient, Shard, SnapshotClient, StateInfo, TermInfo,
-    TestBlockChainClient,
+    replay_random_block, AccountChange, AccountData, BlockChainClient, BlockChainTrait, BlockExecutionSummary,
+    BlocksImported, ChainNotify, Client, ClientConfig, DatabaseClient, DiskSpaceLevel, DiskSpaceMonitor,
+    DiskSpaceThresholds, DivergentAccount, EngineClient, EngineInfo, EventBus, ExecuteClient, Finalized, ForkClient,
+    FraudProofClient, HotAccountsSaver, ImportBlock, Informant, LightClientSupport, MaintenanceClient,
+    MiningBlockChainClient, PeerEvent, Reorganized, ReplayClient, ReplayedBlock, SchemeExportClient, Shard,
+    SnapshotClient, StateInfo, StateModification, StateOrBlock, StateRootVerifier, StateSurgeryClient, TermInfo,
+    TestBlockChainClient, TransactionExecution, TransactionSummary, TransactionsExecuted, TransactionsReceived,
 };
 pub use crate::consensus::signer::EngineSigner;
 pub use crate::consensus::stake;
-pub use crate::consensus::{EngineType, TimeGapParams};
+pub use crate::consensus::{
+    validator_set_hash, verify_commit, Checkpoint, CheckpointSignature, ClockDrift, Commit, CommitValidator, Duties,
+    EngineType, LightClient, LightClientError, SignedCheckpoint, Slot, TimeGapParams,
+};
+#[cfg(feature = "fuzzing")]
+pub use crate::consensus::TendermintMessage;
 pub use crate::db::{COL_STATE, NUM_COLUMNS};
 pub use crate::error::{BlockImportError, Error, ImportError};
-pub use crate::miner::{MemPoolMinFees, Miner, MinerOptions, MinerService};
+pub use crate::invoice::Invoice;
+pub use crate::miner::{
+    ActionFilter, ActionPolicy, AdmissionStage, Candidate, MemPoolContent, MemPoolError, MemPoolInput, MemPoolMinFees,
+    Miner, MinerOptions, MinerService, TxSource,
+};
 pub use crate::peer_db::PeerDb;
 pub use crate::rlp::Encodable;
 pub use crate::scheme::Scheme;
@@ -84,3 +100,4 @@ pub use crate::transaction::{
     LocalizedTransaction, PendingSignedTransactions, SignedTransaction, UnverifiedTransaction,
 };
 pub use crate::types::{BlockId, BlockStatus, TransactionId};
+pub use crate::verification::FraudProof;
@@ -0,0 +1,313 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The stages that a transaction passes in order to be admitted to the mem pool.
+//!
+//! The signatures are verified before, when the signers are recovered. Then the built-in stages check the size, the
+//! fee, the balance and the seq, and the added stages check their policies in the order they are added.
+
+use super::mem_pool::Error;
+use crate::transaction::SignedTransaction;
+use ckey::public_to_address;
+use ctypes::errors::{HistoryError, RuntimeError, SyntaxError};
+use ctypes::transaction::{Action, ACTION_TYPE_NAMES};
+use ctypes::CommonParams;
+
+/// A transaction to be admitted, and what the mem pool knows about it.
+pub struct Candidate<'a> {
+    pub tx: &'a SignedTransaction,
+    /// The local transactions ignore the fee and the replacement policies.
+    pub is_local: bool,
+    /// The seq of the signer in the best block.
+    pub seq: u64,
+    pub fee_payer_balance: u64,
+    /// The fee that the best block requires for the action.
+    pub base_fee: u64,
+    /// The tip that the mem pool requires for the action on top of the base fee.
+    pub minimum_tip: u64,
+    /// One more than the lowest tip in the pool when it's full, and 0 otherwise.
+    pub full_pool_minimum_tip: u64,
+    /// The fee of the pooled transaction that has the same signer and seq.
+    pub replaced_fee: Option<u64>,
+    pub is_pooled: bool,
+    /// The parameters of the best block. They are unknown until the pool sees a block.
+    pub common_params: Option<&'a CommonParams>,
+}
+
+impl<'a> Candidate<'a> {
+    pub fn tip(&self) -> u64 {
+        self.tx.fee.saturating_sub(self.base_fee)
+    }
+}
+
+pub trait AdmissionStage: Send + Sync {
+    fn name(&self) -> &str;
+    fn check(&self, candidate: &Candidate<'_>) -> Result<(), Error>;
+}
+
+pub struct AdmissionPipeline {
+    stages: Vec<Box<dyn AdmissionStage>>,
+}
+
+impl AdmissionPipeline {
+    /// The pipeline of the built-in stages.
+    pub fn new(fee_bump_shift: usize) -> Self {
+        Self {
+            stages: vec![
+                Box::new(SizeStage),
+                Box::new(FeeStage),
+                Box::new(BalanceStage),
+                Box::new(SeqStage {
+                    fee_bump_shift,
+                }),
+            ],
+        }
+    }
+
+    pub fn push(&mut self, stage: Box<dyn AdmissionStage>) {
+        self.stages.push(stage);
+    }
+
+    pub fn stage_names(&self) -> Vec<&str> {
+        self.stages.iter().map(|stage| stage.name()).collect()
+    }
+
+    /// Returns the error of the first stage that rejects the candidate.
+    pub fn check(&self, candidate: &Candidate<'_>) -> Result<(), Error> {
+        for stage in &self.stages {
+            if let Err(err) = stage.check(candidate) {
+                ctrace!(MEM_POOL, "{:?} is rejected by the {} stage: {:?}", candidate.tx.hash(), stage.name(), err);
+                return Err(err)
+            }
+        }
+        Ok(())
+    }
+}
+
+struct SizeStage;
+
+impl AdmissionStage for SizeStage {
+    fn name(&self) -> &str {
+        "size"
+    }
+
+    fn check(&self, candidate: &Candidate<'_>) -> Result<(), Error> {
+        let max_body_size = match candidate.common_params {
+            Some(common_params) => common_params.max_body_size(),
+            None => return Ok(()),
+        };
+        if rlp::encode(candidate.tx).len() >= max_body_size {
+            return Err(SyntaxError::TransactionIsTooBig.into())
+        }
+        Ok(())
+    }
+}
+
+struct FeeStage;
+
+impl AdmissionStage for FeeStage {
+    fn name(&self) -> &str {
+        "fee"
+    }
+
+    fn check(&self, candidate: &Candidate<'_>) -> Result<(), Error> {
+        if candidate.is_local {
+            return Ok(())
+        }
+        for minimum_tip in &[candidate.minimum_tip, candidate.full_pool_minimum_tip] {
+            if candidate.tip() < *minimum_tip {
+                return Err(SyntaxError::InsufficientFee {
+                    minimal: candidate.base_fee + minimum_tip,
+                    got: candidate.tx.fee,
+                }
+                .into())
+            }
+        }
+        Ok(())
+    }
+}
+
+struct BalanceStage;
+
+impl AdmissionStage for BalanceStage {
+    fn name(&self) -> &str {
+        "balance"
+    }
+
+    fn check(&self, candidate: &Candidate<'_>) -> Result<(), Error> {
+        if candidate.fee_payer_balance < candidate.tx.fee {
+            return Err(RuntimeError::InsufficientBalance {
+                address: public_to_address(&candidate.tx.fee_payer_public()),
+                cost: candidate.tx.fee,
+                balance: candidate.fee_payer_balance,
+            }
+            .into())
+        }
+        Ok(())
+    }
+}
+
+struct SeqStage {
+    /// The fee of a replacing transaction should be higher than `old_fee + old_fee >> fee_bump_shift`.
+    fee_bump_shift: usize,
+}
+
+impl AdmissionStage for SeqStage {
+    fn name(&self) -> &str {
+        "seq"
+    }
+
+    fn check(&self, candidate: &Candidate<'_>) -> Result<(), Error> {
+        if candidate.is_pooled {
+            return Err(HistoryError::TransactionAlreadyImported.into())
+        }
+        if candidate.tx.seq < candidate.seq {
+            return Err(HistoryError::Old.into())
+        }
+        if let (false, Some(old_fee)) = (candidate.is_local, candidate.replaced_fee) {
+            if candidate.tx.fee < old_fee + (old_fee >> self.fee_bump_shift) {
+                return Err(HistoryError::TooCheapToReplace.into())
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Rejects the actions that `is_allowed` doesn't allow.
+pub struct ActionPolicy<F> {
+    name: String,
+    is_allowed: F,
+}
+
+impl<F> ActionPolicy<F>
+where
+    F: Fn(&Action) -> bool + Send + Sync,
+{
+    pub fn new(name: &str, is_allowed: F) -> Self {
+        Self {
+            name: name.to_string(),
+            is_allowed,
+        }
+    }
+}
+
+impl<F> AdmissionStage for ActionPolicy<F>
+where
+    F: Fn(&Action) -> bool + Send + Sync,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn check(&self, candidate: &Candidate<'_>) -> Result<(), Error> {
+        if !(self.is_allowed)(&candidate.tx.action) {
+            return Err(Error::Rejected(format!("The action is not allowed by {}", self.name)))
+        }
+        Ok(())
+    }
+}
+
+/// Filters the actions by their type names, such as "pay" and "createShard".
+/// The actions in a batch or a scheduled action are filtered too.
+#[derive(Clone, Debug, Default)]
+pub struct ActionFilter {
+    /// Only these types are allowed if it's set.
+    accepted: Option<Vec<String>>,
+    rejected: Vec<String>,
+}
+
+impl ActionFilter {
+    pub fn new(accepted: Option<Vec<String>>, rejected: Vec<String>) -> Result<Self, String> {
+        for name in accepted.iter().flatten().chain(&rejected) {
+            if !ACTION_TYPE_NAMES.contains(&name.as_str()) {
+                return Err(format!("{} is not an action type. The types are {}", name, ACTION_TYPE_NAMES.join(", ")))
+            }
+        }
+        Ok(Self {
+            accepted,
+            rejected,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accepted.is_none() && self.rejected.is_empty()
+    }
+
+    pub fn allows(&self, action: &Action) -> bool {
+        self.allows_type(action.type_name())
+            && action.flatten().iter().all(|action| self.allows_type(action.type_name()))
+    }
+
+    fn allows_type(&self, type_name: &str) -> bool {
+        if let Some(accepted) = &self.accepted {
+            if !accepted.iter().any(|name| name == type_name) {
+                return false
+            }
+        }
+        !self.rejected.iter().any(|name| name == type_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckey::Address;
+
+    fn pay() -> Action {
+        Action::Pay {
+            receiver: Address::random(),
+            quantity: 100,
+        }
+    }
+
+    #[test]
+    fn rejected_types_are_not_allowed() {
+        let filter = ActionFilter::new(None, vec!["createShard".to_string()]).unwrap();
+        assert!(filter.allows(&pay()));
+        assert!(!filter.allows(&Action::CreateShard {
+            users: vec![],
+        }));
+        assert!(!filter.allows(&Action::Batch {
+            actions: vec![pay(), Action::CreateShard {
+                users: vec![],
+            }],
+        }));
+    }
+
+    #[test]
+    fn only_accepted_types_are_allowed() {
+        let filter = ActionFilter::new(Some(vec!["pay".to_string(), "scheduled".to_string()]), vec![]).unwrap();
+        assert!(filter.allows(&pay()));
+        assert!(filter.allows(&Action::Scheduled {
+            activation: 10,
+            action: Box::new(pay()),
+        }));
+        assert!(!filter.allows(&Action::Scheduled {
+            activation: 10,
+            action: Box::new(Action::BurnSeq),
+        }));
+        assert!(!filter.allows(&Action::Batch {
+            actions: vec![pay()],
+        }));
+    }
+
+    #[test]
+    fn unknown_types_are_refused() {
+        assert!(ActionFilter::new(None, vec!["mintAsset".to_string()]).is_err());
+        assert!(ActionFilter::new(Some(vec!["Pay".to_string()]), vec![]).is_err());
+        assert!(ActionFilter::default().is_empty());
+    }
+}
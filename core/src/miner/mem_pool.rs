@@ -14,19 +14,22 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use super::admission::{AdmissionPipeline, AdmissionStage, Candidate};
 use super::backup;
 use super::mem_pool_types::{
-    AccountDetails, CurrentQueue, FutureQueue, MemPoolInput, MemPoolItem, MemPoolMinFees, MemPoolStatus,
-    PoolingInstant, QueueTag, TransactionOrder, TransactionOrderWithTag, TxOrigin,
+    AccountDetails, CurrentQueue, FutureQueue, MemPoolContent, MemPoolInput, MemPoolItem, MemPoolMinFees,
+    MemPoolStatus, PoolingInstant, QueueTag, TransactionOrder, TransactionOrderWithTag, TxOrigin, TxSource,
 };
 use super::TransactionImportResult;
 use crate::client::{AccountData, BlockChainTrait};
+use crate::codechain_machine::CodeChainMachine;
 use crate::miner::fetch_account_creator;
 use crate::transaction::{PendingSignedTransactions, SignedTransaction};
 use crate::Error as CoreError;
-use ckey::{public_to_address, Public};
+use ckey::{public_to_address, Address, Public};
 use ctypes::errors::{HistoryError, RuntimeError, SyntaxError};
-use ctypes::{BlockNumber, TxHash};
+use ctypes::transaction::Action;
+use ctypes::{BlockNumber, CommonParams, ShardId, TxHash};
 use kvdb::{DBTransaction, KeyValueDB};
 use rlp;
 use std::collections::{BTreeSet, HashMap, HashSet};
@@ -41,6 +44,8 @@ pub enum Error {
     History(HistoryError),
     Runtime(RuntimeError),
     Syntax(SyntaxError),
+    /// Rejected by an admission stage added to the pool.
+    Rejected(String),
 }
 
 impl Error {
@@ -49,6 +54,7 @@ impl Error {
             Error::History(err) => CoreError::History(err),
             Error::Runtime(err) => CoreError::Runtime(err),
             Error::Syntax(err) => CoreError::Syntax(err),
+            Error::Rejected(reason) => CoreError::Rejected(reason),
         }
     }
 }
@@ -72,13 +78,12 @@ impl From<SyntaxError> for Error {
 }
 
 pub struct MemPool {
-    /// Fee threshold for transactions that can be imported to this pool
+    /// Tip threshold for transactions that can be imported to this pool
     minimum_fees: MemPoolMinFees,
-    /// A value which is used to check whether a new transaciton can replace a transaction in the memory pool with the same signer and seq.
-    /// If the fee of the new transaction is `new_fee` and the fee of the transaction in the memory pool is `old_fee`,
-    /// then `new_fee > old_fee + old_fee >> mem_pool_fee_bump_shift` should be satisfied to replace.
-    /// Local transactions ignore this option.
-    fee_bump_shift: usize,
+    /// The parameters of the best block, which define the base fees of the actions
+    common_params: Option<CommonParams>,
+    /// The stages that a new transaction should pass
+    admission: AdmissionPipeline,
     /// Maximal time transaction may occupy the pool.
     /// When we reach `max_time_in_pool / 2^3` we re-validate
     /// account balance.
@@ -93,8 +98,17 @@ pub struct MemPool {
     queue_count_limit: usize,
     /// The memory limit of each queue
     queue_memory_limit: usize,
+    /// The memory limit of each queue lowered to keep the memory budget
+    memory_restriction: Option<usize>,
+    /// The maximum number of transactions for a single shard in the pool and in a block
+    /// Local transactions are not rejected for this option, but they are also held for the next blocks.
+    shard_quota: Option<usize>,
     /// All transactions managed by pool indexed by hash
     by_hash: HashMap<TxHash, MemPoolItem>,
+    /// The number of transactions in `by_hash` for each shard
+    shard_counts: ShardCounts,
+    /// Transactions waiting for their activation block indexed by hash
+    scheduled: HashMap<TxHash, MemPoolInput>,
     /// Current seq of each public key (fee payer)
     first_seqs: HashMap<Public, u64>,
     /// Next seq of transaction in current (to quickly check next expected transaction)
@@ -122,14 +136,19 @@ impl MemPool {
     ) -> Self {
         MemPool {
             minimum_fees,
-            fee_bump_shift,
+            common_params: None,
+            admission: AdmissionPipeline::new(fee_bump_shift),
             max_block_number_period_in_pool: DEFAULT_POOLING_PERIOD,
             current: CurrentQueue::new(),
             future: FutureQueue::new(),
             by_signer_public: Table::new(),
             queue_count_limit: limit,
             queue_memory_limit: memory_limit,
+            memory_restriction: None,
+            shard_quota: None,
             by_hash: HashMap::new(),
+            shard_counts: ShardCounts::default(),
+            scheduled: HashMap::new(),
             first_seqs: HashMap::new(),
             next_seqs: HashMap::new(),
             is_local_account: HashSet::new(),
@@ -145,6 +164,59 @@ impl MemPool {
         self.queue_count_limit = limit;
     }
 
+    /// The memory used by the transactions in the current and the future queue.
+    pub fn mem_usage(&self) -> usize {
+        self.current.mem_usage + self.future.mem_usage
+    }
+
+    /// Drops the transactions until the pool uses at most `memory_limit` bytes, and keeps it so until it
+    /// is called again. `None` restores the configured limit. The local transactions are never dropped.
+    pub fn restrict_memory(&mut self, memory_limit: Option<usize>) {
+        self.memory_restriction = memory_limit.map(|limit| limit / 2);
+        let mut batch = backup::backup_batch_with_capacity(0);
+        self.enforce_limit(&mut batch);
+        self.db.write(batch).expect("Low level database error. Some issue with disk?");
+    }
+
+    fn queue_memory_limit(&self) -> usize {
+        self.memory_restriction.map_or(self.queue_memory_limit, |restriction| restriction.min(self.queue_memory_limit))
+    }
+
+    /// Set the maximum number of transactions for a single shard.
+    pub fn set_shard_quota(&mut self, quota: Option<usize>) {
+        self.shard_quota = quota;
+        // The quota changes the transactions taken from the current queue.
+        self.current.generation += 1;
+    }
+
+    /// Add a stage that the new transactions should pass after the built-in stages.
+    pub fn add_admission_stage(&mut self, stage: Box<dyn AdmissionStage>) {
+        self.admission.push(stage);
+    }
+
+    /// Rejects the transaction if the pool already has `shard_quota` transactions for its shard,
+    /// so that a flood of transactions for one shard can't crowd out the transactions for others.
+    fn check_shard_quota(&self, tx: &SignedTransaction, origin: TxOrigin) -> Result<(), Error> {
+        let (quota, shard_id) = match (self.shard_quota, target_shard(tx)) {
+            (Some(quota), Some(shard_id)) => (quota, shard_id),
+            _ => return Ok(()),
+        };
+        let replaces_same_shard = self
+            .by_signer_public
+            .get(&tx.signer_public(), &tx.seq)
+            .and_then(|old| self.by_hash.get(&old.order.hash))
+            .map_or(false, |old| target_shard(&old.tx) == Some(shard_id));
+        if replaces_same_shard {
+            return Ok(())
+        }
+
+        if origin != TxOrigin::Local && self.shard_counts.get(shard_id) >= quota {
+            ctrace!(MEM_POOL, "Dropping transaction because shard {} reached its quota: {:?}", shard_id, tx.hash());
+            return Err(HistoryError::LimitReached.into())
+        }
+        Ok(())
+    }
+
     /// Enforce the limit to the current/future queue
     fn enforce_limit(&mut self, batch: &mut DBTransaction) {
         // Get transaction orders to drop from each queue (current/future)
@@ -165,25 +237,27 @@ impl MemPool {
                 .collect()
         }
 
+        let queue_memory_limit = self.queue_memory_limit();
         let to_drop_current =
-            if self.current.mem_usage > self.queue_memory_limit || self.current.count > self.queue_count_limit {
-                get_orders_to_drop(&self.current.queue, self.queue_count_limit, self.queue_memory_limit)
+            if self.current.mem_usage > queue_memory_limit || self.current.count > self.queue_count_limit {
+                get_orders_to_drop(&self.current.queue, self.queue_count_limit, queue_memory_limit)
             } else {
                 vec![]
             };
 
-        let to_drop_future =
-            if self.future.mem_usage > self.queue_memory_limit || self.future.count > self.queue_count_limit {
-                get_orders_to_drop(&self.future.queue, self.queue_count_limit, self.queue_memory_limit)
-            } else {
-                vec![]
-            };
+        let to_drop_future = if self.future.mem_usage > queue_memory_limit || self.future.count > self.queue_count_limit
+        {
+            get_orders_to_drop(&self.future.queue, self.queue_count_limit, queue_memory_limit)
+        } else {
+            vec![]
+        };
 
         for (order, is_current) in
             to_drop_current.iter().map(|order| (order, true)).chain(to_drop_future.iter().map(|order| (order, false)))
         {
             let hash = order.hash;
             let item = self.by_hash.remove(&hash).expect("`by_hash` and `current/future` should be synced");
+            self.shard_counts.remove(&item.tx);
             backup::remove_item(batch, &hash);
             let signer_public = item.signer_public();
             let seq = item.seq();
@@ -206,16 +280,57 @@ impl MemPool {
         self.queue_count_limit
     }
 
-    /// Get one more than the lowest fee in the pool iff the pool is
+    /// Get one more than the lowest tip in the pool iff the pool is
     /// full, otherwise 0.
-    pub fn effective_minimum_fee(&self) -> u64 {
+    pub fn effective_minimum_tip(&self) -> u64 {
         if self.current.len() >= self.queue_count_limit {
-            self.current.minimum_fee()
+            self.current.minimum_tip()
         } else {
             0
         }
     }
 
+    /// The base fee of the action defined by the scheme. The rest of the fee is the tip.
+    pub fn base_fee(&self, action: &Action) -> u64 {
+        self.common_params.as_ref().map_or(0, |params| CodeChainMachine::min_cost(params, action))
+    }
+
+    /// Updates the base fees, and reorders the transactions by their new tips.
+    pub fn set_common_params(&mut self, common_params: CommonParams) {
+        if self.common_params == Some(common_params) {
+            return
+        }
+        self.common_params = Some(common_params);
+
+        let keys = self.by_signer_public.keys().cloned().collect::<Vec<_>>();
+        for public in keys {
+            let row = self.by_signer_public.row_mut(&public).expect("The key is taken from the table");
+            for order_with_tag in row.values_mut() {
+                let old_order = order_with_tag.order;
+                let item =
+                    self.by_hash.get(&old_order.hash).expect("`by_hash` and `by_signer_public` should be synced");
+                let new_order = old_order.update_base_fee(CodeChainMachine::min_cost(&common_params, &item.tx.action));
+                match order_with_tag.tag {
+                    QueueTag::Current => {
+                        self.current.remove(&old_order);
+                        self.current.insert(new_order);
+                    }
+                    QueueTag::Future => {
+                        self.future.remove(&old_order);
+                        self.future.insert(new_order);
+                    }
+                    QueueTag::New => continue,
+                }
+                order_with_tag.order = new_order;
+            }
+        }
+    }
+
+    /// Returns a number that changes whenever the transactions returned by `top_transactions` may change.
+    pub fn generation(&self) -> u64 {
+        self.current.generation
+    }
+
     /// Returns current status for this pool
     pub fn status(&self) -> MemPoolStatus {
         MemPoolStatus {
@@ -245,6 +360,7 @@ impl MemPool {
 
         for input in inputs {
             let tx = input.transaction;
+            let source = input.source;
             let signer_public = tx.signer_public();
             let seq = tx.seq;
             let hash = tx.hash();
@@ -260,25 +376,41 @@ impl MemPool {
             };
 
             let client_account = fetch_account(&signer_public);
-            if let Err(e) = self.verify_transaction(&tx, origin, &client_account) {
+            let fee_payer_balance = match tx.sponsor_public() {
+                Some(sponsor_public) => fetch_account(&sponsor_public).balance,
+                None => client_account.balance,
+            };
+            if let Err(e) = self.verify_transaction(&tx, origin, &client_account, fee_payer_balance) {
                 insert_results.push(Err(e));
                 continue
             }
+            if let Err(e) = self.check_shard_quota(&tx, origin) {
+                insert_results.push(Err(e));
+                continue
+            }
+            if !is_activated(&tx, inserted_block_number) {
+                insert_results.push(self.schedule(MemPoolInput::new(tx, origin, source)).map(|()| None));
+                continue
+            }
 
             let id = self.next_transaction_id;
             self.next_transaction_id += 1;
-            let item = MemPoolItem::new(tx, origin, inserted_block_number, inserted_timestamp, id);
-            let order = TransactionOrder::for_transaction(&item, client_account.seq);
+            let item = MemPoolItem::new(tx, origin, source, inserted_block_number, inserted_timestamp, id);
+            let base_fee = self.base_fee(&item.tx.action);
+            let order = TransactionOrder::for_transaction(&item, client_account.seq, base_fee);
             let order_with_tag = TransactionOrderWithTag::new(order, QueueTag::New);
 
             backup::backup_item(&mut batch, *hash, &item);
+            self.shard_counts.insert(&item.tx);
             self.by_hash.insert(hash, item);
 
             if let Some(old_order_with_tag) = self.by_signer_public.insert(signer_public, seq, order_with_tag) {
                 let old_order = old_order_with_tag.order;
                 let tag = old_order_with_tag.tag;
 
-                self.by_hash.remove(&old_order.hash);
+                if let Some(old) = self.by_hash.remove(&old_order.hash) {
+                    self.shard_counts.remove(&old.tx);
+                }
                 backup::remove_item(&mut batch, &old_order.hash);
 
                 match tag {
@@ -293,7 +425,7 @@ impl MemPool {
             }
 
             to_insert.entry(signer_public).or_default().push(seq);
-            insert_results.push(Ok((signer_public, seq)));
+            insert_results.push(Ok(Some((signer_public, seq))));
         }
 
         let keys = self.by_signer_public.keys().map(Clone::clone).collect::<Vec<_>>();
@@ -349,14 +481,14 @@ impl MemPool {
         self.last_timestamp = inserted_timestamp;
 
         assert_eq!(self.current.len() + self.future.len(), self.by_hash.len());
-        assert_eq!(self.current.fee_counter.values().sum::<usize>(), self.current.len());
+        assert_eq!(self.current.tip_counter.values().sum::<usize>(), self.current.len());
         assert_eq!(self.by_signer_public.len(), self.by_hash.len());
 
         self.db.write(batch).expect("Low level database error. Some issue with disk?");
         insert_results
             .into_iter()
             .map(|v| match v {
-                Ok((signer_public, seq)) => match self.by_signer_public.get(&signer_public, &seq) {
+                Ok(Some((signer_public, seq))) => match self.by_signer_public.get(&signer_public, &seq) {
                     Some(order_with_tag) => match order_with_tag.tag {
                         QueueTag::Current => Ok(TransactionImportResult::Current),
                         QueueTag::Future => Ok(TransactionImportResult::Future),
@@ -364,15 +496,69 @@ impl MemPool {
                     },
                     None => Err(HistoryError::LimitReached.into()),
                 },
+                Ok(None) => Ok(TransactionImportResult::Scheduled),
                 Err(e) => Err(e),
             })
             .collect()
     }
 
-    /// Clear both current and future.
+    /// Clear current, future and the scheduled transactions.
     pub fn remove_all(&mut self) {
         self.current.clear();
         self.future.clear();
+        self.scheduled.clear();
+    }
+
+    /// Removes the local transaction of the signer with the given seq, including the scheduled ones.
+    /// Returns the hash of the removed transaction.
+    pub fn cancel<F>(
+        &mut self,
+        signer: &Address,
+        seq: u64,
+        fetch_seq: &F,
+        current_block_number: PoolingInstant,
+        current_timestamp: u64,
+    ) -> Option<TxHash>
+    where
+        F: Fn(&Public) -> u64, {
+        let is_target = |tx: &SignedTransaction, origin: TxOrigin| {
+            origin.is_local() && tx.seq == seq && public_to_address(&tx.signer_public()) == *signer
+        };
+        let scheduled = self.scheduled.iter().find(|(_, input)| is_target(&input.transaction, input.origin));
+        if let Some(hash) = scheduled.map(|(hash, _)| *hash) {
+            self.scheduled.remove(&hash);
+            return Some(hash)
+        }
+
+        let hash = self.by_hash.iter().find(|(_, item)| is_target(&item.tx, item.origin)).map(|(hash, _)| *hash)?;
+        self.remove(&[hash], fetch_seq, current_block_number, current_timestamp);
+        Some(hash)
+    }
+
+    /// Holds the transaction until the block before its activation block is imported.
+    fn schedule(&mut self, input: MemPoolInput) -> Result<(), Error> {
+        let hash = input.transaction.hash();
+        if self.scheduled.contains_key(&hash) {
+            ctrace!(MEM_POOL, "Dropping already scheduled transaction: {:?}", hash);
+            return Err(HistoryError::TransactionAlreadyImported.into())
+        }
+        if self.scheduled.len() >= self.queue_count_limit {
+            return Err(HistoryError::LimitReached.into())
+        }
+        self.scheduled.insert(hash, input);
+        Ok(())
+    }
+
+    /// Takes out the scheduled transactions which can be included in the block after `current_block_number`.
+    /// They should be added to the pool again.
+    pub fn take_activated(&mut self, current_block_number: PoolingInstant) -> Vec<MemPoolInput> {
+        let activated: Vec<TxHash> = self
+            .scheduled
+            .iter()
+            .filter(|(_, input)| is_activated(&input.transaction, current_block_number))
+            .map(|(hash, _)| *hash)
+            .collect();
+        activated.iter().filter_map(|hash| self.scheduled.remove(hash)).collect()
     }
 
     /// Checks the current seq for all transactions' senders in the pool and removes the old transactions.
@@ -438,9 +624,10 @@ impl MemPool {
                 max_insertion_id = item.insertion_id;
             }
 
-            let order = TransactionOrder::for_transaction(&item, client_account.seq);
+            let order = TransactionOrder::for_transaction(&item, client_account.seq, self.base_fee(&item.tx.action));
             let order_with_tag = TransactionOrderWithTag::new(order, QueueTag::New);
 
+            self.shard_counts.insert(&item.tx);
             self.by_hash.insert((*hash).into(), item.clone());
 
             self.by_signer_public.insert(signer_public, seq, order_with_tag);
@@ -511,6 +698,7 @@ impl MemPool {
                 }
 
                 self.by_hash.remove(hash);
+                self.shard_counts.remove(&item.tx);
                 backup::remove_item(&mut batch, hash);
                 self.by_signer_public.remove(&signer_public, &seq);
                 if current_seq <= seq {
@@ -572,7 +760,7 @@ impl MemPool {
         self.last_timestamp = current_timestamp;
 
         assert_eq!(self.current.len() + self.future.len(), self.by_hash.len());
-        assert_eq!(self.current.fee_counter.values().sum::<usize>(), self.current.len());
+        assert_eq!(self.current.tip_counter.values().sum::<usize>(), self.current.len());
         assert_eq!(self.by_signer_public.len(), self.by_hash.len());
 
         self.db.write(batch).expect("Low level database error. Some issue with disk?");
@@ -676,7 +864,9 @@ impl MemPool {
             row.remove(&seq);
 
             if seq < current_seq {
-                self.by_hash.remove(&old_order.hash);
+                if let Some(old) = self.by_hash.remove(&old_order.hash) {
+                    self.shard_counts.remove(&old.tx);
+                }
                 backup::remove_item(batch, &old_order.hash);
             } else {
                 let new_order = old_order.update_height(seq, current_seq);
@@ -699,100 +889,28 @@ impl MemPool {
     }
 
     /// Verify signed transaction with its content.
-    /// This function can return errors: InsufficientFee, InsufficientBalance,
-    /// TransactionAlreadyImported, Old, TooCheapToReplace
+    /// This function can return errors: TransactionIsTooBig, InsufficientFee, InsufficientBalance,
+    /// TransactionAlreadyImported, Old, TooCheapToReplace, and the errors of the added stages
     fn verify_transaction(
         &self,
         tx: &SignedTransaction,
         origin: TxOrigin,
         client_account: &AccountDetails,
+        fee_payer_balance: u64,
     ) -> Result<(), Error> {
-        let action_min_fee = self.minimum_fees.min_cost(&tx.action);
-        if origin != TxOrigin::Local && tx.fee < action_min_fee {
-            ctrace!(
-                MEM_POOL,
-                "Dropping transaction below mempool defined minimum fee: {:?} (gp: {} < {})",
-                tx.hash(),
-                tx.fee,
-                action_min_fee
-            );
-
-            return Err(SyntaxError::InsufficientFee {
-                minimal: action_min_fee,
-                got: tx.fee,
-            }
-            .into())
-        }
-
-        let full_pools_lowest = self.effective_minimum_fee();
-        if origin != TxOrigin::Local && tx.fee < full_pools_lowest {
-            ctrace!(
-                MEM_POOL,
-                "Dropping transaction below lowest fee in a full pool: {:?} (gp: {} < {})",
-                tx.hash(),
-                tx.fee,
-                full_pools_lowest
-            );
-
-            return Err(SyntaxError::InsufficientFee {
-                minimal: full_pools_lowest,
-                got: tx.fee,
-            }
-            .into())
-        }
-
-        if client_account.balance < tx.fee {
-            ctrace!(
-                MEM_POOL,
-                "Dropping transaction without sufficient balance: {:?} ({} < {})",
-                tx.hash(),
-                client_account.balance,
-                tx.fee
-            );
-
-            return Err(RuntimeError::InsufficientBalance {
-                address: public_to_address(&tx.signer_public()),
-                cost: tx.fee,
-                balance: client_account.balance,
-            }
-            .into())
-        }
-
-        if self.by_hash.get(&tx.hash()).is_some() {
-            ctrace!(MEM_POOL, "Dropping already imported transaction: {:?}", tx.hash());
-            return Err(HistoryError::TransactionAlreadyImported.into())
-        }
-
-        if tx.seq < client_account.seq {
-            ctrace!(MEM_POOL, "Dropping old transaction: {:?} (seq: {} < {})", tx.hash(), tx.seq, client_account.seq);
-            return Err(HistoryError::Old.into())
-        }
-
-        if origin != TxOrigin::Local {
-            if let Some(TransactionOrderWithTag {
-                order,
-                ..
-            }) = self.by_signer_public.get(&tx.signer_public(), &tx.seq)
-            {
-                let old_fee = order.fee;
-                let new_fee = tx.fee;
-                let min_required_fee = old_fee + (old_fee >> self.fee_bump_shift);
-
-                if new_fee < min_required_fee {
-                    ctrace!(
-                        MEM_POOL,
-                        "Dropping transaction because fee is not enough to replace: {:?} (gp: {} < {}) (old_fee: {})",
-                        tx.hash(),
-                        new_fee,
-                        min_required_fee,
-                        old_fee,
-                    );
-                    return Err(HistoryError::TooCheapToReplace.into())
-                }
-            }
-        }
-
-        Ok(())
+        let replaced_fee = self.by_signer_public.get(&tx.signer_public(), &tx.seq).map(|order| order.order.fee);
+        self.admission.check(&Candidate {
+            tx,
+            is_local: origin == TxOrigin::Local,
+            seq: client_account.seq,
+            fee_payer_balance,
+            base_fee: self.base_fee(&tx.action),
+            minimum_tip: self.minimum_fees.min_tip(&tx.action),
+            full_pool_minimum_tip: self.effective_minimum_tip(),
+            replaced_fee,
+            is_pooled: self.by_hash.contains_key(&tx.hash()),
+            common_params: self.common_params.as_ref(),
+        })
     }
 
     /// Removes all elements (in any state) from the pool
@@ -802,6 +920,8 @@ impl MemPool {
         self.future.clear();
         self.by_signer_public.clear();
         self.by_hash.clear();
+        self.shard_counts.clear();
+        self.scheduled.clear();
         self.first_seqs.clear();
         self.next_seqs.clear();
     }
@@ -816,6 +936,8 @@ impl MemPool {
         range: Range<u64>,
     ) -> PendingSignedTransactions {
         let mut current_size: usize = 0;
+        let mut block_shard_counts: HashMap<ShardId, usize> = HashMap::new();
+        let mut held_signers = HashSet::new();
         let pending_items: Vec<_> = self
             .current
             .queue
@@ -834,6 +956,26 @@ impl MemPool {
                 true
             })
             .filter(|t| range.contains(&t.inserted_timestamp))
+            .filter(|t| {
+                // A block takes at most `shard_quota` transactions for a shard, so that a flood for one shard
+                // can't delay the others. The later transactions of the signer wait for the held one.
+                let signer_public = t.signer_public();
+                if held_signers.contains(&signer_public) {
+                    return false
+                }
+                let quota_reached = match (self.shard_quota, target_shard(&t.tx)) {
+                    (Some(quota), Some(shard_id)) => {
+                        let count = block_shard_counts.entry(shard_id).or_insert(0);
+                        *count += 1;
+                        *count > quota
+                    }
+                    _ => false,
+                };
+                if quota_reached {
+                    held_signers.insert(signer_public);
+                }
+                !quota_reached
+            })
             .take_while(|t| {
                 let encoded_byte_array = rlp::encode(&t.tx);
                 let size_in_byte = encoded_byte_array.len();
@@ -878,6 +1020,84 @@ impl MemPool {
             .map(|t| t.tx.clone())
             .collect()
     }
+
+    /// Return all transactions waiting for their activation block.
+    pub fn scheduled_transactions(&self) -> Vec<SignedTransaction> {
+        self.scheduled.values().map(|input| input.transaction.clone()).collect()
+    }
+
+    /// Return all transactions in the pool with their sources.
+    pub fn content(&self) -> MemPoolContent {
+        let to_input = |order: &TransactionOrder| {
+            let item = self
+                .by_hash
+                .get(&order.hash)
+                .expect("All transactions in `current` and `future` are always included in `by_hash`");
+            MemPoolInput::new(item.tx.clone(), item.origin, item.source)
+        };
+        MemPoolContent {
+            pending: self.current.queue.iter().map(to_input).collect(),
+            future: self.future.queue.iter().map(to_input).collect(),
+            scheduled: self.scheduled.values().cloned().collect(),
+        }
+    }
+
+    /// Return where the transaction in the pool came from.
+    pub fn source_of(&self, hash: &TxHash) -> Option<TxSource> {
+        self.by_hash.get(hash).map(|item| item.source).or_else(|| self.scheduled.get(hash).map(|input| input.source))
+    }
+
+    /// Return all transactions of the signer in the pool, including the scheduled ones.
+    pub fn transactions_of(&self, signer: &Address) -> Vec<SignedTransaction> {
+        let pooled = self.by_hash.values().map(|item| &item.tx);
+        let scheduled = self.scheduled.values().map(|input| &input.transaction);
+        pooled.chain(scheduled).filter(|tx| public_to_address(&tx.signer_public()) == *signer).cloned().collect()
+    }
+}
+
+/// Whether the transaction can be included in the block after `current_block_number`.
+fn is_activated(tx: &SignedTransaction, current_block_number: PoolingInstant) -> bool {
+    tx.action.activation().map_or(true, |activation| activation <= current_block_number + 1)
+}
+
+fn target_shard(tx: &SignedTransaction) -> Option<ShardId> {
+    tx.action.flatten().iter().find_map(|action| match action {
+        Action::ShardStore {
+            shard_id,
+            ..
+        } => Some(*shard_id),
+        _ => None,
+    })
+}
+
+/// The number of transactions for each shard, kept up to date on every insertion and removal.
+#[derive(Default)]
+struct ShardCounts(HashMap<ShardId, usize>);
+
+impl ShardCounts {
+    fn get(&self, shard_id: ShardId) -> usize {
+        self.0.get(&shard_id).cloned().unwrap_or(0)
+    }
+
+    fn insert(&mut self, tx: &SignedTransaction) {
+        if let Some(shard_id) = target_shard(tx) {
+            *self.0.entry(shard_id).or_insert(0) += 1;
+        }
+    }
+
+    fn remove(&mut self, tx: &SignedTransaction) {
+        if let Some(shard_id) = target_shard(tx) {
+            let count = self.0.get_mut(&shard_id).expect("Only the counted transactions are removed");
+            *count -= 1;
+            if *count == 0 {
+                self.0.remove(&shard_id);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.0.clear();
+    }
 }
 
 #[cfg(test)]
@@ -885,11 +1105,14 @@ pub mod test {
     use std::cmp::Ordering;
 
     use crate::client::{AccountData, TestBlockChainClient};
+    use crate::miner::ActionPolicy;
+    use cjson::scheme::Params;
     use ckey::{Generator, KeyPair, Random};
+    use cnetwork::NodeId;
     use ctypes::transaction::{Action, Transaction};
 
     use super::*;
-    use rlp::rlp_encode_and_decode_test;
+    use rlp::{rlp_encode_and_decode_test, RlpStream};
 
     #[test]
     fn origin_ordering() {
@@ -913,7 +1136,7 @@ pub mod test {
             },
         };
         let signed = SignedTransaction::new_with_sign(tx, keypair.private());
-        let item = MemPoolItem::new(signed, TxOrigin::Local, 0, 0, 0);
+        let item = MemPoolItem::new(signed, TxOrigin::Local, TxSource::Rpc, 0, 0, 0);
 
         assert_eq!(fee + quantity, item.cost());
     }
@@ -923,6 +1146,14 @@ pub mod test {
         rlp_encode_and_decode_test!(TxOrigin::External);
     }
 
+    #[test]
+    fn txsource_encode_and_decode() {
+        rlp_encode_and_decode_test!(TxSource::Rpc);
+        rlp_encode_and_decode_test!(TxSource::Peer(NodeId::new([127, 0, 0, 1].into(), 3485)));
+        rlp_encode_and_decode_test!(TxSource::Retracted);
+        rlp_encode_and_decode_test!(TxSource::Unknown);
+    }
+
     #[test]
     fn signed_transaction_encode_and_decode() {
         let receiver = 0u64.into();
@@ -954,11 +1185,31 @@ pub mod test {
             },
         };
         let signed = SignedTransaction::new_with_sign(tx, keypair.private());
-        let item = MemPoolItem::new(signed, TxOrigin::Local, 0, 0, 0);
+        let item = MemPoolItem::new(signed, TxOrigin::Local, TxSource::Rpc, 0, 0, 0);
 
         rlp_encode_and_decode_test!(item);
     }
 
+    #[test]
+    fn mempool_item_without_source_is_decoded() {
+        let keypair = Random.generate().unwrap();
+        let tx = Transaction {
+            seq: 0,
+            fee: 10,
+            network_id: "tc".into(),
+            action: Action::Pay {
+                receiver: Default::default(),
+                quantity: 0,
+            },
+        };
+        let signed = SignedTransaction::new_with_sign(tx, keypair.private());
+        let mut stream = RlpStream::new_list(5);
+        stream.append(&signed).append(&TxOrigin::External).append(&1u64).append(&100u64).append(&2u64);
+
+        let item: MemPoolItem = rlp::decode(&stream.out()).unwrap();
+        assert_eq!(MemPoolItem::new(signed, TxOrigin::External, TxSource::Unknown, 1, 100, 2), item);
+    }
+
     #[test]
     fn db_backup_and_recover() {
         //setup test_client
@@ -1048,7 +1299,7 @@ pub mod test {
 
     fn create_mempool_input_with_pay(seq: u64, keypair: KeyPair) -> MemPoolInput {
         let signed = create_signed_pay(seq, keypair);
-        MemPoolInput::new(signed, TxOrigin::Local)
+        MemPoolInput::new(signed, TxOrigin::Local, TxSource::Rpc)
     }
 
     fn abbreviated_mempool_add(
@@ -1061,7 +1312,8 @@ pub mod test {
 
         let inserted_block_number = 1;
         let inserted_timestamp = 100;
-        let inputs: Vec<MemPoolInput> = txs.into_iter().map(|tx| MemPoolInput::new(tx, origin)).collect();
+        let inputs: Vec<MemPoolInput> =
+            txs.into_iter().map(|tx| MemPoolInput::new(tx, origin, TxSource::Unknown)).collect();
         mem_pool.add(inputs, inserted_block_number, inserted_timestamp, &fetch_account)
     }
 
@@ -1176,6 +1428,357 @@ pub mod test {
         assert_eq!(Vec::<SignedTransaction>::default(), mem_pool.future_transactions());
     }
 
+    fn common_params_with_min_pay_cost(min_pay_cost: u64) -> CommonParams {
+        CommonParams::from(Params {
+            min_pay_cost: min_pay_cost.into(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn transactions_with_higher_tips_come_first() {
+        let test_client = TestBlockChainClient::new();
+        let db = Arc::new(kvdb_memorydb::create(crate::db::NUM_COLUMNS.unwrap_or(0)));
+        let mut mem_pool = MemPool::with_limits(8192, usize::max_value(), 3, db, Default::default());
+        mem_pool.set_common_params(common_params_with_min_pay_cost(100));
+
+        let keypairs: Vec<_> = (0..3).map(|_| Random.generate().unwrap()).collect();
+        for keypair in &keypairs {
+            test_client.set_balance(public_to_address(keypair.public()), 1_000_000_000_000);
+        }
+        let txs = vec![
+            create_signed_pay_with_fee(0, 200, keypairs[0]),
+            create_signed_pay_with_fee(0, 1000, keypairs[1]),
+            create_signed_pay_with_fee(0, 500, keypairs[2]),
+        ];
+        abbreviated_mempool_add(&test_client, &mut mem_pool, txs, TxOrigin::External);
+
+        assert_eq!(
+            vec![
+                create_signed_pay_with_fee(0, 1000, keypairs[1]),
+                create_signed_pay_with_fee(0, 500, keypairs[2]),
+                create_signed_pay_with_fee(0, 200, keypairs[0]),
+            ],
+            mem_pool.top_transactions(std::usize::MAX, None, 0..std::u64::MAX).transactions
+        );
+        assert_eq!(100, mem_pool.current.tip_counter.keys().next().cloned().unwrap());
+    }
+
+    #[test]
+    fn the_mem_pool_minimum_is_required_on_top_of_the_base_fee() {
+        let test_client = TestBlockChainClient::new();
+        let fees = MemPoolMinFees::create_from_options(
+            Some(50),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        let db = Arc::new(kvdb_memorydb::create(crate::db::NUM_COLUMNS.unwrap_or(0)));
+        let mut mem_pool = MemPool::with_limits(8192, usize::max_value(), 3, db, fees);
+        mem_pool.set_common_params(common_params_with_min_pay_cost(100));
+
+        let keypair = Random.generate().unwrap();
+        test_client.set_balance(public_to_address(keypair.public()), 1_000_000_000_000);
+
+        let txs = vec![create_signed_pay_with_fee(0, 140, keypair), create_signed_pay_with_fee(0, 150, keypair)];
+        let result = abbreviated_mempool_add(&test_client, &mut mem_pool, txs, TxOrigin::External);
+        assert_eq!(
+            vec![
+                Err(Error::Syntax(SyntaxError::InsufficientFee {
+                    minimal: 150,
+                    got: 140,
+                })),
+                Ok(TransactionImportResult::Current),
+            ],
+            result
+        );
+    }
+
+    fn create_signed_shard_store(seq: u64, shard_id: ShardId, keypair: KeyPair) -> SignedTransaction {
+        let tx = Transaction {
+            seq,
+            fee: 100,
+            network_id: "tc".into(),
+            action: Action::ShardStore {
+                network_id: "tc".into(),
+                shard_id,
+                content: format!("{}", seq),
+            },
+        };
+        SignedTransaction::new_with_sign(tx, keypair.private())
+    }
+
+    #[test]
+    fn external_transactions_over_the_shard_quota_are_rejected() {
+        let test_client = TestBlockChainClient::new();
+        let db = Arc::new(kvdb_memorydb::create(crate::db::NUM_COLUMNS.unwrap_or(0)));
+        let mut mem_pool = MemPool::with_limits(8192, usize::max_value(), 3, db, Default::default());
+        mem_pool.set_shard_quota(Some(2));
+
+        let spammer = Random.generate().unwrap();
+        let user = Random.generate().unwrap();
+        test_client.set_balance(public_to_address(spammer.public()), 1_000_000_000_000);
+        test_client.set_balance(public_to_address(user.public()), 1_000_000_000_000);
+
+        let txs = vec![
+            create_signed_shard_store(0, 0, spammer),
+            create_signed_shard_store(1, 0, spammer),
+            create_signed_shard_store(2, 0, spammer),
+            create_signed_shard_store(0, 1, user),
+        ];
+        let result = abbreviated_mempool_add(&test_client, &mut mem_pool, txs, TxOrigin::External);
+        assert_eq!(
+            vec![
+                Ok(TransactionImportResult::Current),
+                Ok(TransactionImportResult::Current),
+                Err(Error::History(HistoryError::LimitReached)),
+                Ok(TransactionImportResult::Current),
+            ],
+            result
+        );
+
+        let local = vec![create_signed_shard_store(2, 0, spammer)];
+        let result = abbreviated_mempool_add(&test_client, &mut mem_pool, local, TxOrigin::Local);
+        assert_eq!(vec![Ok(TransactionImportResult::Current)], result);
+    }
+
+    #[test]
+    fn removed_transactions_free_the_shard_quota() {
+        let test_client = TestBlockChainClient::new();
+        let db = Arc::new(kvdb_memorydb::create(crate::db::NUM_COLUMNS.unwrap_or(0)));
+        let mut mem_pool = MemPool::with_limits(8192, usize::max_value(), 3, db, Default::default());
+        mem_pool.set_shard_quota(Some(1));
+
+        let spammer = Random.generate().unwrap();
+        let user = Random.generate().unwrap();
+        test_client.set_balance(public_to_address(spammer.public()), 1_000_000_000_000);
+        test_client.set_balance(public_to_address(user.public()), 1_000_000_000_000);
+
+        let spam = create_signed_shard_store(0, 0, spammer);
+        let result = abbreviated_mempool_add(&test_client, &mut mem_pool, vec![spam.clone()], TxOrigin::External);
+        assert_eq!(vec![Ok(TransactionImportResult::Current)], result);
+        let txs = vec![create_signed_shard_store(0, 0, user)];
+        let result = abbreviated_mempool_add(&test_client, &mut mem_pool, txs.clone(), TxOrigin::External);
+        assert_eq!(vec![Err(Error::History(HistoryError::LimitReached))], result);
+
+        mem_pool.remove(&[spam.hash()], &|_: &Public| 1, 2, 200);
+        let result = abbreviated_mempool_add(&test_client, &mut mem_pool, txs, TxOrigin::External);
+        assert_eq!(vec![Ok(TransactionImportResult::Current)], result);
+    }
+
+    #[test]
+    fn a_block_takes_at_most_the_shard_quota_of_transactions_for_a_shard() {
+        let test_client = TestBlockChainClient::new();
+        let db = Arc::new(kvdb_memorydb::create(crate::db::NUM_COLUMNS.unwrap_or(0)));
+        let mut mem_pool = MemPool::with_limits(8192, usize::max_value(), 3, db, Default::default());
+        mem_pool.set_shard_quota(Some(2));
+
+        let spammer = Random.generate().unwrap();
+        let user = Random.generate().unwrap();
+        test_client.set_balance(public_to_address(spammer.public()), 1_000_000_000_000);
+        test_client.set_balance(public_to_address(user.public()), 1_000_000_000_000);
+
+        let spam: Vec<_> = (0..4).map(|seq| create_signed_shard_store(seq, 0, spammer)).collect();
+        let result = abbreviated_mempool_add(&test_client, &mut mem_pool, spam.clone(), TxOrigin::Local);
+        assert!(result.iter().all(Result::is_ok));
+        let other = create_signed_shard_store(0, 1, user);
+        let result = abbreviated_mempool_add(&test_client, &mut mem_pool, vec![other.clone()], TxOrigin::External);
+        assert_eq!(vec![Ok(TransactionImportResult::Current)], result);
+
+        let mut top = mem_pool.top_transactions(std::usize::MAX, None, 0..std::u64::MAX).transactions;
+        top.sort_by_key(|tx| (tx.signer_public() == *user.public(), tx.seq));
+        assert_eq!(vec![spam[0].clone(), spam[1].clone(), other], top);
+    }
+
+    #[test]
+    fn added_admission_stages_run_after_the_built_in_stages() {
+        let test_client = TestBlockChainClient::new();
+        let db = Arc::new(kvdb_memorydb::create(crate::db::NUM_COLUMNS.unwrap_or(0)));
+        let mut mem_pool = MemPool::with_limits(8192, usize::max_value(), 3, db, Default::default());
+        mem_pool.add_admission_stage(Box::new(ActionPolicy::new("no-shard-store", |action| match action {
+            Action::ShardStore {
+                ..
+            } => false,
+            _ => true,
+        })));
+        assert_eq!(vec!["size", "fee", "balance", "seq", "no-shard-store"], mem_pool.admission.stage_names());
+
+        let keypair = Random.generate().unwrap();
+        test_client.set_balance(public_to_address(keypair.public()), 1_000_000_000_000);
+
+        let txs = vec![create_signed_pay(0, keypair), create_signed_shard_store(1, 0, keypair)];
+        let result = abbreviated_mempool_add(&test_client, &mut mem_pool, txs, TxOrigin::Local);
+        assert_eq!(
+            vec![
+                Ok(TransactionImportResult::Current),
+                Err(Error::Rejected("The action is not allowed by no-shard-store".to_string())),
+            ],
+            result
+        );
+
+        let poor = Random.generate().unwrap();
+        let txs = vec![create_signed_shard_store(0, 0, poor)];
+        let result = abbreviated_mempool_add(&test_client, &mut mem_pool, txs, TxOrigin::External);
+        assert_eq!(
+            vec![Err(Error::Runtime(RuntimeError::InsufficientBalance {
+                address: public_to_address(poor.public()),
+                cost: 100,
+                balance: 0,
+            }))],
+            result
+        );
+    }
+
+    #[test]
+    fn external_transactions_are_dropped_to_keep_the_memory_restriction() {
+        let test_client = TestBlockChainClient::new();
+        let db = Arc::new(kvdb_memorydb::create(crate::db::NUM_COLUMNS.unwrap_or(0)));
+        let mut mem_pool = MemPool::with_limits(8192, usize::max_value(), 3, db, Default::default());
+
+        let keypair = Random.generate().unwrap();
+        test_client.set_balance(public_to_address(keypair.public()), 1_000_000_000_000);
+        let txs = (0..4).map(|seq| create_signed_pay(seq, keypair)).collect();
+        abbreviated_mempool_add(&test_client, &mut mem_pool, txs, TxOrigin::External);
+        let usage = mem_pool.mem_usage();
+
+        // Each of the current and the future queue gets the half.
+        mem_pool.restrict_memory(Some(usage / 2));
+        assert_ne!(0, mem_pool.mem_usage());
+        assert!(mem_pool.mem_usage() <= usage / 4);
+
+        mem_pool.restrict_memory(None);
+        let txs = (0..4).map(|seq| create_signed_pay(seq, keypair)).collect();
+        abbreviated_mempool_add(&test_client, &mut mem_pool, txs, TxOrigin::External);
+        assert_eq!(usage, mem_pool.mem_usage());
+    }
+
+    #[test]
+    fn scheduled_transactions_are_held_until_the_activation_block() {
+        let test_client = TestBlockChainClient::new();
+
+        let db = Arc::new(kvdb_memorydb::create(crate::db::NUM_COLUMNS.unwrap_or(0)));
+        let mut mem_pool = MemPool::with_limits(8192, usize::max_value(), 3, db, Default::default());
+
+        let fetch_account = fetch_account_creator(&test_client);
+        let keypair = Random.generate().unwrap();
+        let address = public_to_address(keypair.public());
+        test_client.set_balance(address, 1_000_000_000_000);
+
+        let tx = Transaction {
+            seq: 0,
+            fee: 100,
+            network_id: "tc".into(),
+            action: Action::Scheduled {
+                activation: 10,
+                action: Box::new(Action::Pay {
+                    receiver: 1u64.into(),
+                    quantity: 100_000,
+                }),
+            },
+        };
+        let scheduled = SignedTransaction::new_with_sign(tx, keypair.private());
+        let result = mem_pool.add(
+            vec![MemPoolInput::new(scheduled.clone(), TxOrigin::Local, TxSource::Rpc)],
+            1,
+            100,
+            &fetch_account,
+        );
+        assert_eq!(vec![Ok(TransactionImportResult::Scheduled)], result);
+        assert_eq!(vec![scheduled.clone()], mem_pool.scheduled_transactions());
+        assert!(mem_pool.top_transactions(std::usize::MAX, None, 0..std::u64::MAX).transactions.is_empty());
+
+        assert!(mem_pool.take_activated(8).is_empty());
+        let activated = mem_pool.take_activated(9);
+        assert_eq!(1, activated.len());
+        assert!(mem_pool.scheduled_transactions().is_empty());
+
+        let result = mem_pool.add(activated, 9, 200, &fetch_account);
+        assert_eq!(vec![Ok(TransactionImportResult::Current)], result);
+        assert_eq!(vec![scheduled], mem_pool.top_transactions(std::usize::MAX, None, 0..std::u64::MAX).transactions);
+    }
+
+    #[test]
+    fn only_local_transactions_are_cancelled() {
+        let test_client = TestBlockChainClient::new();
+
+        let db = Arc::new(kvdb_memorydb::create(crate::db::NUM_COLUMNS.unwrap_or(0)));
+        let mut mem_pool = MemPool::with_limits(8192, usize::max_value(), 3, db, Default::default());
+
+        let fetch_account = fetch_account_creator(&test_client);
+        let fetch_seq = |p: &Public| fetch_account(p).seq;
+        let local = Random.generate().unwrap();
+        let external = Random.generate().unwrap();
+        test_client.set_balance(public_to_address(local.public()), 1_000_000_000_000);
+        test_client.set_balance(public_to_address(external.public()), 1_000_000_000_000);
+
+        let local_txs = vec![create_signed_pay(0, local), create_signed_pay(1, local), create_signed_pay(2, local)];
+        abbreviated_mempool_add(&test_client, &mut mem_pool, local_txs.clone(), TxOrigin::Local);
+        abbreviated_mempool_add(&test_client, &mut mem_pool, vec![create_signed_pay(0, external)], TxOrigin::External);
+
+        let external_address = public_to_address(external.public());
+        assert_eq!(None, mem_pool.cancel(&external_address, 0, &fetch_seq, 1, 100));
+
+        let local_address = public_to_address(local.public());
+        assert_eq!(Some(local_txs[1].hash()), mem_pool.cancel(&local_address, 1, &fetch_seq, 1, 100));
+        assert_eq!(None, mem_pool.cancel(&local_address, 1, &fetch_seq, 1, 100));
+        assert_eq!(vec![local_txs[2].clone()], mem_pool.future_transactions());
+    }
+
+    #[test]
+    fn transactions_of_returns_only_the_transactions_of_the_signer() {
+        let test_client = TestBlockChainClient::new();
+
+        let db = Arc::new(kvdb_memorydb::create(crate::db::NUM_COLUMNS.unwrap_or(0)));
+        let mut mem_pool = MemPool::with_limits(8192, usize::max_value(), 3, db, Default::default());
+
+        let signer = Random.generate().unwrap();
+        let other = Random.generate().unwrap();
+        test_client.set_balance(public_to_address(signer.public()), 1_000_000_000_000);
+        test_client.set_balance(public_to_address(other.public()), 1_000_000_000_000);
+
+        let signer_txs = vec![create_signed_pay(0, signer), create_signed_pay(2, signer)];
+        abbreviated_mempool_add(&test_client, &mut mem_pool, signer_txs.clone(), TxOrigin::Local);
+        abbreviated_mempool_add(&test_client, &mut mem_pool, vec![create_signed_pay(0, other)], TxOrigin::Local);
+
+        let mut pooled = mem_pool.transactions_of(&public_to_address(signer.public()));
+        pooled.sort_by_key(|tx| tx.seq);
+        assert_eq!(signer_txs, pooled);
+    }
+
+    #[test]
+    fn content_keeps_the_sources_of_the_transactions() {
+        let test_client = TestBlockChainClient::new();
+
+        let db = Arc::new(kvdb_memorydb::create(crate::db::NUM_COLUMNS.unwrap_or(0)));
+        let mut mem_pool = MemPool::with_limits(8192, usize::max_value(), 3, db, Default::default());
+
+        let fetch_account = fetch_account_creator(&test_client);
+        let keypair = Random.generate().unwrap();
+        test_client.set_balance(public_to_address(keypair.public()), 1_000_000_000_000);
+
+        let peer = TxSource::Peer(NodeId::new([192, 168, 0, 7].into(), 3485));
+        let inputs = vec![
+            MemPoolInput::new(create_signed_pay(0, keypair), TxOrigin::External, peer),
+            MemPoolInput::new(create_signed_pay(2, keypair), TxOrigin::External, TxSource::Rpc),
+        ];
+        mem_pool.add(inputs, 1, 100, &fetch_account);
+
+        let content = mem_pool.content();
+        assert_eq!(vec![peer], content.pending.iter().map(|input| input.source).collect::<Vec<_>>());
+        assert_eq!(vec![TxSource::Rpc], content.future.iter().map(|input| input.source).collect::<Vec<_>>());
+        assert!(content.scheduled.is_empty());
+        assert_eq!(Some(peer), mem_pool.source_of(&create_signed_pay(0, keypair).hash()));
+        assert_eq!(None, mem_pool.source_of(&create_signed_pay(1, keypair).hash()));
+    }
+
     #[test]
     fn transactions_are_moved_to_future_queue_if_the_preceding_one_removed() {
         //setup test_client
@@ -18,7 +18,7 @@ extern crate test;
 
 use self::test::{black_box, Bencher};
 use super::mem_pool::MemPool;
-use super::mem_pool_types::{AccountDetails, MemPoolInput, PoolingInstant, TxOrigin};
+use super::mem_pool_types::{AccountDetails, MemPoolInput, PoolingInstant, TxOrigin, TxSource};
 use crate::transaction::SignedTransaction;
 use ckey::{Generator, KeyPair, Public, Random};
 use ctypes::transaction::{Action, Transaction};
@@ -40,7 +40,7 @@ fn create_input(keypair: &KeyPair, seq: u64, block: Option<PoolingInstant>, time
     };
     let signed = SignedTransaction::new_with_sign(tx, keypair.private());
 
-    MemPoolInput::new(signed, TxOrigin::Local)
+    MemPoolInput::new(signed, TxOrigin::Local, TxSource::Rpc)
 }
 
 #[bench]
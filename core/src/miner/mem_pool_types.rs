@@ -16,6 +16,7 @@
 
 use crate::transaction::SignedTransaction;
 use ckey::Public;
+use cnetwork::NodeId;
 use ctypes::transaction::Action;
 use ctypes::{BlockNumber, TxHash};
 use rlp;
@@ -87,6 +88,69 @@ impl TxOrigin {
     }
 }
 
+/// Where a pooled transaction came from.
+/// Unlike `TxOrigin`, it doesn't affect how the transaction is treated in the pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxSource {
+    /// Sent through RPC
+    Rpc,
+    /// Propagated by the peer
+    Peer(NodeId),
+    /// Included in a block that was retracted
+    Retracted,
+    /// Not recorded, e.g. recovered from the backup of an older version
+    Unknown,
+}
+
+type TxSourceType = u8;
+const SOURCE_UNKNOWN: TxSourceType = 0x00;
+const SOURCE_RPC: TxSourceType = 0x01;
+const SOURCE_PEER: TxSourceType = 0x02;
+const SOURCE_RETRACTED: TxSourceType = 0x03;
+
+impl Encodable for TxSource {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        match self {
+            TxSource::Rpc => s.begin_list(1).append(&SOURCE_RPC),
+            TxSource::Peer(node_id) => s.begin_list(2).append(&SOURCE_PEER).append(node_id),
+            TxSource::Retracted => s.begin_list(1).append(&SOURCE_RETRACTED),
+            TxSource::Unknown => s.begin_list(1).append(&SOURCE_UNKNOWN),
+        };
+    }
+}
+
+impl Decodable for TxSource {
+    fn decode(d: &Rlp<'_>) -> Result<Self, DecoderError> {
+        let item_count = d.item_count()?;
+        let expected_count = match d.val_at(0)? {
+            SOURCE_PEER => 2,
+            _ => 1,
+        };
+        if item_count != expected_count {
+            return Err(DecoderError::RlpIncorrectListLen {
+                got: item_count,
+                expected: expected_count,
+            })
+        }
+        match d.val_at(0)? {
+            SOURCE_UNKNOWN => Ok(TxSource::Unknown),
+            SOURCE_RPC => Ok(TxSource::Rpc),
+            SOURCE_PEER => Ok(TxSource::Peer(d.val_at(1)?)),
+            SOURCE_RETRACTED => Ok(TxSource::Retracted),
+            _ => Err(DecoderError::Custom("Unexpected TxSource type")),
+        }
+    }
+}
+
+impl TxSource {
+    pub fn peer(self) -> Option<NodeId> {
+        match self {
+            TxSource::Peer(node_id) => Some(node_id),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 /// Light structure used to identify transaction and its order
 pub struct TransactionOrder {
@@ -96,8 +160,10 @@ pub struct TransactionOrder {
     pub seq_height: u64,
     /// Fee of the transaction.
     pub fee: u64,
-    /// Fee per bytes(rlp serialized) of the transaction
-    pub fee_per_byte: u64,
+    /// The part of the fee over the base fee of the actions, which the block author takes.
+    pub tip: u64,
+    /// Tip per bytes(rlp serialized) of the transaction
+    pub tip_per_byte: u64,
     /// Memory usage of this transaction.
     /// Currently using the RLP byte length of the transaction as the mem usage.
     pub mem_usage: usize,
@@ -110,19 +176,21 @@ pub struct TransactionOrder {
 }
 
 impl TransactionOrder {
-    pub fn for_transaction(item: &MemPoolItem, seq_seq: u64) -> Self {
+    pub fn for_transaction(item: &MemPoolItem, seq_seq: u64, base_fee: u64) -> Self {
         let rlp_bytes_len = rlp::encode(&item.tx).len();
         let fee = item.tx.fee;
         ctrace!(MEM_POOL, "New tx with size {}", rlp_bytes_len);
         Self {
             seq_height: item.seq() - seq_seq,
             fee,
+            tip: 0,
+            tip_per_byte: 0,
             mem_usage: rlp_bytes_len,
-            fee_per_byte: fee / rlp_bytes_len as u64,
             hash: item.hash(),
             insertion_id: item.insertion_id,
             origin: item.origin,
         }
+        .update_base_fee(base_fee)
     }
 
     pub fn update_height(mut self, seq: u64, base_seq: u64) -> Self {
@@ -130,6 +198,12 @@ impl TransactionOrder {
         self
     }
 
+    pub fn update_base_fee(mut self, base_fee: u64) -> Self {
+        self.tip = self.fee.saturating_sub(base_fee);
+        self.tip_per_byte = self.tip / self.mem_usage as u64;
+        self
+    }
+
     pub fn change_origin(mut self, origin: TxOrigin) -> Self {
         self.origin = origin;
         self
@@ -160,13 +234,14 @@ impl Ord for TransactionOrder {
             return self.seq_height.cmp(&b.seq_height)
         }
 
-        if self.fee_per_byte != b.fee_per_byte {
-            return self.fee_per_byte.cmp(&b.fee_per_byte)
+        // The higher tip per byte goes first
+        if self.tip_per_byte != b.tip_per_byte {
+            return b.tip_per_byte.cmp(&self.tip_per_byte)
         }
 
-        // Then compare fee
-        if self.fee != b.fee {
-            return b.fee.cmp(&self.fee)
+        // Then compare tip
+        if self.tip != b.tip {
+            return b.tip.cmp(&self.tip)
         }
 
         // Lastly compare insertion_id
@@ -175,12 +250,14 @@ impl Ord for TransactionOrder {
 }
 
 /// Transaction item in the mem pool.
-#[derive(Clone, Eq, PartialEq, Debug, RlpEncodable, RlpDecodable)]
+#[derive(Clone, Eq, PartialEq, Debug)]
 pub struct MemPoolItem {
     /// Transaction.
     pub tx: SignedTransaction,
     /// Transaction origin.
     pub origin: TxOrigin,
+    /// Where the transaction came from.
+    pub source: TxSource,
     /// Insertion time
     pub inserted_block_number: PoolingInstant,
     /// Insertion timstamp
@@ -193,6 +270,7 @@ impl MemPoolItem {
     pub fn new(
         tx: SignedTransaction,
         origin: TxOrigin,
+        source: TxSource,
         inserted_block_number: PoolingInstant,
         inserted_timestamp: u64,
         insertion_id: u64,
@@ -200,6 +278,7 @@ impl MemPoolItem {
         MemPoolItem {
             tx,
             origin,
+            source,
             inserted_block_number,
             inserted_timestamp,
             insertion_id,
@@ -218,13 +297,19 @@ impl MemPoolItem {
         self.tx.signer_public()
     }
 
+    /// The balance the signer spends. The fee is not included if a sponsor pays it.
     pub fn cost(&self) -> u64 {
+        let fee = if self.tx.sponsor_public().is_some() {
+            0
+        } else {
+            self.tx.fee
+        };
         match &self.tx.action {
             Action::Pay {
                 quantity,
                 ..
-            } => self.tx.fee + *quantity,
-            _ => self.tx.fee,
+            } => fee + *quantity,
+            _ => fee,
         }
     }
 
@@ -234,6 +319,42 @@ impl MemPoolItem {
     }
 }
 
+impl Encodable for MemPoolItem {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(6)
+            .append(&self.tx)
+            .append(&self.origin)
+            .append(&self.inserted_block_number)
+            .append(&self.inserted_timestamp)
+            .append(&self.insertion_id)
+            .append(&self.source);
+    }
+}
+
+impl Decodable for MemPoolItem {
+    fn decode(rlp: &Rlp<'_>) -> Result<Self, DecoderError> {
+        // The backups of the older versions don't have the source.
+        let source = match rlp.item_count()? {
+            5 => TxSource::Unknown,
+            6 => rlp.val_at(5)?,
+            item_count => {
+                return Err(DecoderError::RlpIncorrectListLen {
+                    got: item_count,
+                    expected: 6,
+                })
+            }
+        };
+        Ok(Self {
+            tx: rlp.val_at(0)?,
+            origin: rlp.val_at(1)?,
+            source,
+            inserted_block_number: rlp.val_at(2)?,
+            inserted_timestamp: rlp.val_at(3)?,
+            insertion_id: rlp.val_at(4)?,
+        })
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum QueueTag {
     Current,
@@ -260,29 +381,33 @@ impl TransactionOrderWithTag {
 pub struct CurrentQueue {
     /// Priority queue for transactions
     pub queue: BTreeSet<TransactionOrder>,
-    /// Counter on fees of transactions in the current queue
-    pub fee_counter: BTreeMap<u64, usize>,
+    /// Counter on tips of transactions in the current queue
+    pub tip_counter: BTreeMap<u64, usize>,
     /// Memory usage of the external transactions in the queue
     pub mem_usage: usize,
     /// Count of the external transactions in the queue
     pub count: usize,
+    /// Incremented whenever the queue changes
+    pub generation: u64,
 }
 
 impl CurrentQueue {
     pub fn new() -> Self {
         Self {
             queue: BTreeSet::new(),
-            fee_counter: BTreeMap::new(),
+            tip_counter: BTreeMap::new(),
             mem_usage: 0,
             count: 0,
+            generation: 0,
         }
     }
 
     pub fn clear(&mut self) {
         self.queue.clear();
-        self.fee_counter.clear();
+        self.tip_counter.clear();
         self.mem_usage = 0;
         self.count = 0;
+        self.generation += 1;
     }
 
     pub fn len(&self) -> usize {
@@ -295,27 +420,29 @@ impl CurrentQueue {
             self.mem_usage += order.mem_usage;
             self.count += 1;
         }
-        *self.fee_counter.entry(order.fee).or_default() += 1;
+        *self.tip_counter.entry(order.tip).or_default() += 1;
+        self.generation += 1;
     }
 
     pub fn remove(&mut self, order: &TransactionOrder) {
         assert!(self.queue.remove(order));
+        self.generation += 1;
         if !order.origin.is_local() {
             self.mem_usage -= order.mem_usage;
             self.count -= 1;
         }
         {
-            let counter = self.fee_counter.get_mut(&order.fee).unwrap();
+            let counter = self.tip_counter.get_mut(&order.tip).unwrap();
             *counter -= 1;
             if *counter != 0 {
                 return
             }
         }
-        self.fee_counter.remove(&order.fee);
+        self.tip_counter.remove(&order.tip);
     }
 
-    pub fn minimum_fee(&self) -> u64 {
-        self.fee_counter.keys().next().map_or(0, |k| k + 1)
+    pub fn minimum_tip(&self) -> u64 {
+        self.tip_counter.keys().next().map_or(0, |k| k + 1)
     }
 }
 
@@ -369,17 +496,30 @@ impl FutureQueue {
 pub struct MemPoolInput {
     pub transaction: SignedTransaction,
     pub origin: TxOrigin,
+    pub source: TxSource,
 }
 
 impl MemPoolInput {
-    pub fn new(transaction: SignedTransaction, origin: TxOrigin) -> Self {
+    pub fn new(transaction: SignedTransaction, origin: TxOrigin, source: TxSource) -> Self {
         Self {
             transaction,
             origin,
+            source,
         }
     }
 }
 
+/// The transactions in the pool with where they came from.
+#[derive(Debug, Default)]
+pub struct MemPoolContent {
+    /// The transactions in the current queue, which are allowed into the next block
+    pub pending: Vec<MemPoolInput>,
+    /// The transactions waiting for the transactions with lower seqs
+    pub future: Vec<MemPoolInput>,
+    /// The transactions waiting for their activation block
+    pub scheduled: Vec<MemPoolInput>,
+}
+
 #[derive(Debug)]
 /// Current status of the pool
 pub struct MemPoolStatus {
@@ -399,7 +539,7 @@ pub struct AccountDetails {
 }
 
 #[derive(Default, Clone, Copy, Debug, PartialEq)]
-/// Minimum fee thresholds defined not by network but by Mempool
+/// Minimum tips over the base fees of the network, defined by Mempool
 pub struct MemPoolMinFees {
     pub min_pay_transaction_cost: u64,
     pub min_set_regular_key_transaction_cost: u64,
@@ -446,11 +586,12 @@ impl MemPoolMinFees {
             min_asset_unwrap_ccc_cost: min_asset_unwrap_ccc_cost_option.unwrap_or_default(),
         }
     }
-    pub fn min_cost(&self, action: &Action) -> u64 {
+    pub fn min_tip(&self, action: &Action) -> u64 {
         match action {
             Action::Pay {
                 ..
-            } => self.min_pay_transaction_cost,
+            }
+            | Action::BurnSeq => self.min_pay_transaction_cost,
             Action::SetRegularKey {
                 ..
             } => self.min_set_regular_key_transaction_cost,
@@ -459,6 +600,9 @@ impl MemPoolMinFees {
             } => self.min_create_shard_transaction_cost,
             Action::SetShardOwners {
                 ..
+            }
+            | Action::TerminateShard {
+                ..
             } => self.min_set_shard_owners_transaction_cost,
             Action::SetShardUsers {
                 ..
@@ -472,6 +616,13 @@ impl MemPoolMinFees {
                 // FIXME
                 0
             }
+            Action::Batch {
+                actions,
+            } => actions.iter().map(|action| self.min_tip(action)).sum(),
+            Action::Scheduled {
+                action,
+                ..
+            } => self.min_tip(action),
         }
     }
 }
@@ -14,9 +14,10 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use super::admission::{ActionFilter, ActionPolicy, AdmissionStage};
 use super::mem_pool::{Error as MemPoolError, MemPool};
 pub use super::mem_pool_types::MemPoolMinFees;
-use super::mem_pool_types::{MemPoolInput, TxOrigin};
+use super::mem_pool_types::{MemPoolContent, MemPoolInput, TxOrigin, TxSource};
 use super::{fetch_account_creator, MinerService, MinerStatus, TransactionImportResult};
 use crate::account_provider::{AccountProvider, Error as AccountProviderError};
 use crate::block::{ClosedBlock, IsBlock};
@@ -27,19 +28,22 @@ use crate::codechain_machine::CodeChainMachine;
 use crate::consensus::{CodeChainEngine, EngineType};
 use crate::error::Error;
 use crate::scheme::Scheme;
-use crate::transaction::{PendingSignedTransactions, SignedTransaction, UnverifiedTransaction};
+use crate::transaction::{recover_publics, PendingSignedTransactions, SignedTransaction, UnverifiedTransaction};
 use crate::types::{BlockId, TransactionId};
 use ckey::{public_to_address, Address, Password, PlatformAddress, Public};
+use cmemory::MemoryConsumer;
+use cnetwork::NodeId;
 use cstate::{FindActionHandler, TopLevelState};
 use ctypes::errors::HistoryError;
 use ctypes::transaction::{Action, IncompleteTransaction};
-use ctypes::{BlockHash, TxHash};
+use ctypes::{BlockHash, CommonParams, TxHash};
 use cvm::ChainTimeInfo;
 use kvdb::KeyValueDB;
 use parking_lot::{Mutex, RwLock};
 use primitives::Bytes;
+use rayon::prelude::*;
 use std::borrow::Borrow;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::iter::once;
 use std::iter::FromIterator;
 use std::ops::Range;
@@ -47,6 +51,10 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+const DEFAULT_RANGE: Range<u64> = 0..::std::u64::MAX;
+/// The transactions from a peer are ignored once this number of its transactions proved invalid.
+const MAX_INVALID_TRANSACTIONS_OF_PEER: usize = 256;
+
 /// Configures the behaviour of the miner.
 #[derive(Debug, PartialEq)]
 pub struct MinerOptions {
@@ -67,9 +75,19 @@ pub struct MinerOptions {
     /// then `new_fee > old_fee + old_fee >> mem_pool_fee_bump_shift` should be satisfied to replace.
     /// Local transactions ignore this option.
     pub mem_pool_fee_bump_shift: usize,
+    /// Maximum number of transactions for a single shard in the mem pool and in a block.
+    /// The mem pool doesn't reject local transactions for this option.
+    pub mem_pool_shard_quota: Option<usize>,
+    /// The mem pool rejects the CreateShard transactions unless it's set.
     pub allow_create_shard: bool,
+    /// The types of the actions that the mem pool accepts and relays. The blocks are not filtered.
+    pub mem_pool_action_filter: ActionFilter,
     /// Minimum fees configured by the machine.
     pub mem_pool_min_fees: MemPoolMinFees,
+    /// The local limit of the body size of the authored blocks. It doesn't raise the limit of the engine.
+    pub max_block_body_size: Option<usize>,
+    /// The maximum number of transactions in the authored blocks.
+    pub max_block_transactions: Option<usize>,
 }
 
 impl Default for MinerOptions {
@@ -82,8 +100,12 @@ impl Default for MinerOptions {
             mem_pool_size: 8192,
             mem_pool_memory_limit: Some(2 * 1024 * 1024),
             mem_pool_fee_bump_shift: 3,
+            mem_pool_shard_quota: None,
             allow_create_shard: false,
+            mem_pool_action_filter: Default::default(),
             mem_pool_min_fees: Default::default(),
+            max_block_body_size: None,
+            max_block_transactions: None,
         }
     }
 }
@@ -96,6 +118,25 @@ pub struct AuthoringParams {
 
 type TransactionListener = Box<dyn Fn(&[TxHash]) + Send + Sync>;
 
+/// The transactions selected and verified for the block on top of `parent`.
+///
+/// They are prepared while `parent` is still being agreed on, so that proposing the next block
+/// doesn't have to wait for the selection.
+struct PreparedTransactions {
+    parent: BlockHash,
+    transactions: Vec<SignedTransaction>,
+}
+
+impl PreparedTransactions {
+    fn take_for(prepared: &Mutex<Option<Self>>, parent: &BlockHash) -> Option<Vec<SignedTransaction>> {
+        let mut prepared = prepared.lock();
+        if prepared.as_ref()?.parent != *parent {
+            return None
+        }
+        prepared.take().map(|prepared| prepared.transactions)
+    }
+}
+
 pub struct Miner {
     mem_pool: Arc<RwLock<MemPool>>,
     transaction_listener: RwLock<Vec<TransactionListener>>,
@@ -109,6 +150,9 @@ pub struct Miner {
     accounts: Option<Arc<AccountProvider>>,
     malicious_users: RwLock<HashSet<Address>>,
     immune_users: RwLock<HashSet<Address>>,
+    /// The number of the transactions propagated by each peer that proved invalid later.
+    invalid_transactions_of_peers: RwLock<HashMap<NodeId, usize>>,
+    prepared_transactions: Arc<Mutex<Option<PreparedTransactions>>>,
 }
 
 impl Miner {
@@ -132,13 +176,28 @@ impl Miner {
         db: Arc<dyn KeyValueDB>,
     ) -> Self {
         let mem_limit = options.mem_pool_memory_limit.unwrap_or_else(usize::max_value);
-        let mem_pool = Arc::new(RwLock::new(MemPool::with_limits(
+        let mut mem_pool = MemPool::with_limits(
             options.mem_pool_size,
             mem_limit,
             options.mem_pool_fee_bump_shift,
             db,
             options.mem_pool_min_fees,
-        )));
+        );
+        mem_pool.set_shard_quota(options.mem_pool_shard_quota);
+        if !options.allow_create_shard {
+            mem_pool.add_admission_stage(Box::new(ActionPolicy::new("allow-create-shard", |action| match action {
+                Action::CreateShard {
+                    ..
+                } => false,
+                _ => true,
+            })));
+        }
+        if !options.mem_pool_action_filter.is_empty() {
+            let filter = options.mem_pool_action_filter.clone();
+            let policy = ActionPolicy::new("action-filter", move |action| filter.allows(action));
+            mem_pool.add_admission_stage(Box::new(policy));
+        }
+        let mem_pool = Arc::new(RwLock::new(mem_pool));
 
         Self {
             mem_pool,
@@ -151,11 +210,27 @@ impl Miner {
             accounts,
             malicious_users: RwLock::new(HashSet::new()),
             immune_users: RwLock::new(HashSet::new()),
+            invalid_transactions_of_peers: Default::default(),
+            prepared_transactions: Default::default(),
         }
     }
 
     pub fn recover_from_db(&self, client: &Client) {
-        self.mem_pool.write().recover_from_db(client);
+        let mut mem_pool = self.mem_pool.write();
+        if let Some(common_params) = client.common_params(BlockId::Latest) {
+            mem_pool.set_common_params(common_params);
+        }
+        mem_pool.recover_from_db(client);
+    }
+
+    /// Add a stage that the new transactions should pass to enter the mem pool.
+    pub fn add_admission_stage(&self, stage: Box<dyn AdmissionStage>) {
+        self.mem_pool.write().add_admission_stage(stage);
+    }
+
+    /// Returns a number that changes whenever the ready transactions may change.
+    pub fn mem_pool_generation(&self) -> u64 {
+        self.mem_pool.read().generation()
     }
 
     /// Set a callback to be notified about imported transactions' hashes.
@@ -172,6 +247,7 @@ impl Miner {
         client: &C,
         transactions: Vec<UnverifiedTransaction>,
         default_origin: TxOrigin,
+        source: TxSource,
         mem_pool: &mut MemPool,
     ) -> Vec<Result<TransactionImportResult, Error>> {
         let best_header = client.best_block_header().decode();
@@ -182,12 +258,13 @@ impl Miner {
         let mut to_insert = Vec::new();
         let mut tx_hashes = Vec::new();
 
+        let signer_publics = recover_publics(&transactions);
         let intermediate_results: Vec<Result<(), Error>> = transactions
             .into_iter()
-            .map(|tx| {
+            .zip(signer_publics)
+            .map(|(tx, signer_public)| {
                 let hash = tx.hash();
-                // FIXME: Refactoring is needed. recover_public is calling in verify_transaction_unordered.
-                let signer_public = tx.recover_public()?;
+                let signer_public = signer_public?;
                 let signer_address = public_to_address(&signer_public);
                 if default_origin.is_local() {
                     self.immune_users.write().insert(signer_address);
@@ -211,9 +288,6 @@ impl Miner {
                     cdebug!(MINER, "Rejected transaction {:?}: already in the blockchain", hash);
                     return Err(HistoryError::TransactionAlreadyImported.into())
                 }
-                if !self.is_allowed_transaction(&tx.action) {
-                    cdebug!(MINER, "Rejected transaction {:?}: {:?} is not allowed transaction", hash, tx.action);
-                }
                 let immune_users = self.immune_users.read();
                 let tx = tx
                     .verify_basic()
@@ -222,7 +296,7 @@ impl Miner {
                         let common_params = client.common_params(best_header.hash().into()).unwrap();
                         self.engine.verify_transaction_with_params(&tx, &common_params)
                     })
-                    .and_then(|_| CodeChainMachine::verify_transaction_seal(tx, &fake_header))
+                    .and_then(|_| CodeChainMachine::verify_recovered_transaction_seal(tx, signer_public, &fake_header))
                     .map_err(|e| {
                         match e {
                             Error::Syntax(_) if !origin.is_local() && !immune_users.contains(&signer_address) => {
@@ -247,7 +321,7 @@ impl Miner {
 
                 let tx_hash = tx.hash();
 
-                to_insert.push(MemPoolInput::new(tx, origin));
+                to_insert.push(MemPoolInput::new(tx, origin, source));
                 tx_hashes.push(tx_hash);
                 Ok(())
             })
@@ -255,6 +329,9 @@ impl Miner {
 
         let fetch_account = fetch_account_creator(client);
 
+        if let Some(common_params) = client.common_params(best_header.hash().into()) {
+            mem_pool.set_common_params(common_params);
+        }
         let insertion_results = mem_pool.add(to_insert, current_block_number, current_timestamp, &fetch_account);
 
         debug_assert_eq!(insertion_results.len(), intermediate_results.iter().filter(|r| r.is_ok()).count());
@@ -265,9 +342,9 @@ impl Miner {
                 Err(e) => Err(e),
                 Ok(()) => {
                     let idx = insertion_results_index;
+                    insertion_results_index += 1;
                     let result = insertion_results[idx].clone().map_err(MemPoolError::into_core_error)?;
                     inserted.push(tx_hashes[idx]);
-                    insertion_results_index += 1;
                     Ok(result)
                 }
             })
@@ -280,11 +357,50 @@ impl Miner {
         results
     }
 
+    /// Counts the transactions that proved invalid against the peers that propagated them.
+    fn blame_peers(&self, mem_pool: &MemPool, invalid_transactions: &[TxHash]) {
+        let mut invalid_transactions_of_peers = self.invalid_transactions_of_peers.write();
+        for peer in invalid_transactions.iter().filter_map(|hash| mem_pool.source_of(hash)?.peer()) {
+            let count = invalid_transactions_of_peers.entry(peer).or_default();
+            *count += 1;
+            if *count == MAX_INVALID_TRANSACTIONS_OF_PEER {
+                cwarn!(MINER, "{} transactions from {} proved invalid. Its transactions are ignored", count, peer);
+            }
+        }
+    }
+
+    fn is_ignored_peer(&self, peer: &NodeId) -> bool {
+        self.invalid_transactions_of_peers
+            .read()
+            .get(peer)
+            .map_or(false, |count| *count >= MAX_INVALID_TRANSACTIONS_OF_PEER)
+    }
+
     pub fn delete_all_pending_transactions(&self) {
         let mut mem_pool = self.mem_pool.write();
         mem_pool.remove_all();
     }
 
+    /// Removes the pending local transaction of the signer with the given seq.
+    pub fn cancel_transaction<C: AccountData + BlockChainTrait>(
+        &self,
+        client: &C,
+        signer: &Address,
+        seq: u64,
+    ) -> Option<TxHash> {
+        let fetch_account = fetch_account_creator(client);
+        let fetch_seq = |public: &Public| fetch_account(public).seq;
+        let current_block_number = client.chain_info().best_block_number;
+        let current_timestamp = client.chain_info().best_block_timestamp;
+        self.mem_pool.write().cancel(signer, seq, &fetch_seq, current_block_number, current_timestamp)
+    }
+
+    /// The body size limit of the authored blocks.
+    fn max_block_body_size(&self, common_params: &CommonParams) -> usize {
+        let max_body_size = self.engine.max_body_size(common_params);
+        self.options.max_block_body_size.map_or(max_body_size, |limit| limit.min(max_body_size))
+    }
+
     /// Prepares new block for sealing including top transactions from queue and seal it.
     fn prepare_and_seal_block<
         C: AccountData + BlockChainTrait + BlockProducer + ChainTimeInfo + EngineInfo + FindActionHandler + TermInfo,
@@ -303,14 +419,21 @@ impl Miner {
                 let parent_hash = *header.parent_hash();
                 (block_number, parent_hash)
             };
-            let max_body_size = chain.common_params(parent_hash.into()).unwrap().max_body_size();
-            const DEFAULT_RANGE: Range<u64> = 0..::std::u64::MAX;
-
-            // NOTE: This lock should be acquired after `prepare_open_block` to prevent deadlock
-            let mem_pool = self.mem_pool.read();
-            let transactions = mem_pool
-                .top_transactions(max_body_size, Some(open_block.header().timestamp()), DEFAULT_RANGE)
-                .transactions;
+            let transactions = match PreparedTransactions::take_for(&self.prepared_transactions, &parent_hash) {
+                Some(transactions) => {
+                    cdebug!(MINER, "Use {} transactions prepared on top of {}", transactions.len(), parent_hash);
+                    transactions
+                }
+                None => {
+                    let max_body_size = self.max_block_body_size(&chain.common_params(parent_hash.into()).unwrap());
+
+                    // NOTE: This lock should be acquired after `prepare_open_block` to prevent deadlock
+                    let mem_pool = self.mem_pool.read();
+                    mem_pool
+                        .top_transactions(max_body_size, Some(open_block.header().timestamp()), DEFAULT_RANGE)
+                        .transactions
+                }
+            };
 
             (transactions, open_block, block_number)
         };
@@ -328,6 +451,7 @@ impl Miner {
             return Ok(None)
         }
         self.engine.on_open_block(open_block.inner_mut())?;
+        open_block.prefetch_shard_texts(&transactions)?;
 
         let mut invalid_transactions = Vec::new();
 
@@ -336,6 +460,9 @@ impl Miner {
         let mut invalid_tx_users = HashSet::new();
 
         for tx in transactions {
+            if self.options.max_block_transactions.map_or(false, |limit| tx_count >= limit) {
+                break
+            }
             let signer_public = tx.signer_public();
             let signer_address = public_to_address(&signer_public);
             if self.malicious_users.read().contains(&signer_address) {
@@ -395,6 +522,7 @@ impl Miner {
 
         {
             let mut mem_pool = self.mem_pool.write();
+            self.blame_peers(&mem_pool, &invalid_transactions);
             mem_pool.remove(
                 &invalid_transactions,
                 &fetch_seq,
@@ -405,6 +533,51 @@ impl Miner {
         Ok(Some(block))
     }
 
+    /// Selects the transactions for the block on top of `parent` while `parent` is still being agreed on.
+    ///
+    /// The transactions in `parent` are left out, since they are removed from the mem pool only after
+    /// `parent` becomes the best block. The selected transactions are verified in the background.
+    fn prepare_transactions<C: BlockChainTrait + EngineInfo>(&self, chain: &C, parent: BlockHash) {
+        let (parent_block, common_params) =
+            match (chain.block(&BlockId::Hash(parent)), chain.common_params(parent.into())) {
+                (Some(parent_block), Some(common_params)) => (parent_block, common_params),
+                _ => return,
+            };
+        let included: HashSet<TxHash> = parent_block.transaction_hashes().into_iter().collect();
+        // The transactions in `parent` are counted in the size limit, so the limit is raised by their size.
+        let max_body_size =
+            self.max_block_body_size(&common_params) + parent_block.rlp().at(1).map_or(0, |body| body.as_raw().len());
+        let transactions = {
+            let mem_pool = self.mem_pool.read();
+            mem_pool.top_transactions(max_body_size, Some(parent_block.header_view().timestamp()), DEFAULT_RANGE)
+        }
+        .transactions;
+        let malicious_users = self.malicious_users.read().clone();
+        let transactions: Vec<_> = transactions
+            .into_iter()
+            .filter(|tx| !included.contains(&tx.hash()))
+            .filter(|tx| !malicious_users.contains(&public_to_address(&tx.signer_public())))
+            .filter(|tx| self.is_allowed_transaction(&tx.action))
+            .collect();
+
+        let engine = Arc::clone(&self.engine);
+        let prepared_transactions = Arc::clone(&self.prepared_transactions);
+        rayon::spawn(move || {
+            let machine = engine.machine();
+            let transactions: Vec<_> = transactions
+                .into_par_iter()
+                .filter(|tx| {
+                    tx.check_low_s().is_ok() && machine.verify_transaction_with_params(tx, &common_params).is_ok()
+                })
+                .collect();
+            ctrace!(MINER, "Prepared {} transactions on top of {}", transactions.len(), parent);
+            *prepared_transactions.lock() = Some(PreparedTransactions {
+                parent,
+                transactions,
+            });
+        });
+    }
+
     /// Attempts to perform internal sealing (one that does not require work) and handles the result depending on the type of Seal.
     fn import_block_internally<C>(&self, chain: &C, block: ClosedBlock) -> bool
     where
@@ -424,17 +597,15 @@ impl Miner {
     fn transaction_reseal_allowed(&self) -> bool {
         self.sealing_enabled.load(Ordering::Relaxed) && (Instant::now() > *self.next_allowed_reseal.lock())
     }
+}
 
-    fn is_allowed_transaction(&self, action: &Action) -> bool {
-        if let Action::CreateShard {
-            ..
-        } = action
-        {
-            if !self.options.allow_create_shard {
-                return false
-            }
-        }
-        true
+impl MemoryConsumer for Miner {
+    fn memory_usage(&self) -> usize {
+        self.mem_pool.read().mem_usage()
+    }
+
+    fn restrict_memory(&self, allowance: Option<usize>) {
+        self.mem_pool.write().restrict_memory(allowance);
     }
 }
 
@@ -472,6 +643,11 @@ impl MinerService for Miner {
         }
     }
 
+    fn set_validator_owner(&self, owner: Address) {
+        self.params.write().author = owner;
+        self.engine.set_signer_owner(owner);
+    }
+
     fn get_author_address(&self) -> Address {
         self.params.read().author
     }
@@ -488,7 +664,7 @@ impl MinerService for Miner {
         self.mem_pool.write().set_limit(limit)
     }
 
-    fn chain_new_blocks<C>(&self, chain: &C, _imported: &[BlockHash], _invalid: &[BlockHash], _enacted: &[BlockHash])
+    fn chain_new_blocks<C>(&self, chain: &C, imported: &[BlockHash], _invalid: &[BlockHash], enacted: &[BlockHash])
     where
         C: AccountData + BlockChainTrait + BlockProducer + EngineInfo + ImportBlock, {
         ctrace!(MINER, "chain_new_blocks");
@@ -498,7 +674,35 @@ impl MinerService for Miner {
             let current_block_number = chain.chain_info().best_block_number;
             let current_timestamp = chain.chain_info().best_block_timestamp;
             let mut mem_pool = self.mem_pool.write();
+            if let Some(common_params) = chain.common_params(BlockId::Latest) {
+                mem_pool.set_common_params(common_params);
+            }
             mem_pool.remove_old(&fetch_account, current_block_number, current_timestamp);
+
+            // The transactions in the enacted blocks are not pending anymore, including the ones from a reorg.
+            let enacted_transactions: Vec<_> = enacted
+                .iter()
+                .filter_map(|hash| chain.block(&BlockId::Hash(*hash)))
+                .flat_map(|block| block.transaction_hashes())
+                .collect();
+            let fetch_seq = |public: &Public| fetch_account(public).seq;
+            mem_pool.remove(&enacted_transactions, &fetch_seq, current_block_number, current_timestamp);
+
+            let activated = mem_pool.take_activated(current_block_number);
+            if !activated.is_empty() {
+                ctrace!(MINER, "{} scheduled transactions are activated", activated.len());
+                let results = mem_pool.add(activated, current_block_number, current_timestamp, &fetch_account);
+                for err in results.into_iter().filter_map(Result::err) {
+                    cdebug!(MINER, "Failed to activate a scheduled transaction: {:?}", err);
+                }
+            }
+        }
+
+        // The next block will be built on top of the best proposal once it is committed.
+        let best_proposal = chain.best_proposal_header().hash();
+        let is_committed = best_proposal == chain.chain_info().best_block_hash;
+        if self.engine.seals_internally() && !is_committed && imported.contains(&best_proposal) {
+            self.prepare_transactions(chain, best_proposal);
         }
 
         if !self.options.no_reseal_timer {
@@ -561,11 +765,16 @@ impl MinerService for Miner {
         &self,
         client: &C,
         transactions: Vec<UnverifiedTransaction>,
+        source: TxSource,
     ) -> Vec<Result<TransactionImportResult, Error>> {
         ctrace!(EXTERNAL_TX, "Importing external transactions");
+        if let Some(peer) = source.peer().filter(|peer| self.is_ignored_peer(peer)) {
+            cdebug!(EXTERNAL_TX, "Ignore {} transactions from {}", transactions.len(), peer);
+            return Vec::new()
+        }
         let results = {
             let mut mem_pool = self.mem_pool.write();
-            self.add_transactions_to_pool(client, transactions, TxOrigin::External, &mut mem_pool)
+            self.add_transactions_to_pool(client, transactions, TxOrigin::External, source, &mut mem_pool)
         };
 
         if !results.is_empty()
@@ -594,7 +803,7 @@ impl MinerService for Miner {
             let mut mem_pool = self.mem_pool.write();
             // We need to re-validate transactions
             let import = self
-                .add_transactions_to_pool(chain, vec![tx.into()], TxOrigin::Local, &mut mem_pool)
+                .add_transactions_to_pool(chain, vec![tx.into()], TxOrigin::Local, TxSource::Rpc, &mut mem_pool)
                 .pop()
                 .expect("one result returned per added transaction; one added => one result; qed");
 
@@ -651,7 +860,6 @@ impl MinerService for Miner {
                         seq
                     })
                     .unwrap_or_else(|| {
-                        const DEFAULT_RANGE: Range<u64> = 0..::std::u64::MAX;
                         get_next_seq(self.ready_transactions(DEFAULT_RANGE).transactions, &addresses)
                             .map(|seq| {
                                 cdebug!(RPC, "There are ready transactions for {}", platform_address);
@@ -674,7 +882,7 @@ impl MinerService for Miner {
 
     fn ready_transactions(&self, range: Range<u64>) -> PendingSignedTransactions {
         // FIXME: Update the body size when the common params are updated
-        let max_body_size = self.engine.machine().genesis_common_params().max_body_size();
+        let max_body_size = self.engine.max_body_size(self.engine.machine().genesis_common_params());
         self.mem_pool.read().top_transactions(max_body_size, None, range)
     }
 
@@ -687,6 +895,22 @@ impl MinerService for Miner {
         self.mem_pool.read().future_transactions()
     }
 
+    fn scheduled_transactions(&self) -> Vec<SignedTransaction> {
+        self.mem_pool.read().scheduled_transactions()
+    }
+
+    fn transactions_of(&self, signer: &Address) -> Vec<SignedTransaction> {
+        self.mem_pool.read().transactions_of(signer)
+    }
+
+    fn mem_pool_content(&self) -> MemPoolContent {
+        self.mem_pool.read().content()
+    }
+
+    fn invalid_transactions_of_peers(&self) -> Vec<(NodeId, usize)> {
+        self.invalid_transactions_of_peers.read().iter().map(|(peer, count)| (*peer, *count)).collect()
+    }
+
     fn start_sealing<C: MiningBlockChainClient + EngineInfo + TermInfo>(&self, client: &C) {
         cdebug!(MINER, "Start sealing");
         self.sealing_enabled.store(true, Ordering::Relaxed);
@@ -799,7 +1023,39 @@ pub mod test {
         );
 
         let transactions = vec![transaction1.clone(), transaction2, transaction1];
-        miner.add_transactions_to_pool(client.as_ref(), transactions, TxOrigin::Local, &mut mem_pool);
+        miner.add_transactions_to_pool(client.as_ref(), transactions, TxOrigin::Local, TxSource::Rpc, &mut mem_pool);
+    }
+
+    #[test]
+    fn prepared_transactions_are_used_only_on_top_of_their_parent() {
+        let parent = BlockHash::from(H256::random());
+        let prepared = Mutex::new(Some(PreparedTransactions {
+            parent,
+            transactions: vec![],
+        }));
+
+        assert_eq!(None, PreparedTransactions::take_for(&prepared, &H256::random().into()));
+        assert_eq!(Some(vec![]), PreparedTransactions::take_for(&prepared, &parent));
+        assert_eq!(None, PreparedTransactions::take_for(&prepared, &parent));
+    }
+
+    #[test]
+    fn local_body_size_limit_cannot_raise_the_limit_of_the_engine() {
+        let scheme = Scheme::new_test();
+        let common_params = scheme.genesis_params();
+        let max_body_size = scheme.engine.max_body_size(&common_params);
+        let miner_with_limit = |max_block_body_size| {
+            let db = Arc::new(kvdb_memorydb::create(NUM_COLUMNS.unwrap()));
+            let options = MinerOptions {
+                max_block_body_size,
+                ..Default::default()
+            };
+            Miner::new_raw(options, &scheme, None, db)
+        };
+
+        assert_eq!(max_body_size, miner_with_limit(None).max_block_body_size(&common_params));
+        assert_eq!(1000, miner_with_limit(Some(1000)).max_block_body_size(&common_params));
+        assert_eq!(max_body_size, miner_with_limit(Some(max_body_size + 1)).max_block_body_size(&common_params));
     }
 
     fn generate_test_client(db: Arc<dyn KeyValueDB>, miner: Arc<Miner>, scheme: &Scheme) -> Result<Arc<Client>, Error> {
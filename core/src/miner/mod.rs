@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+mod admission;
 mod backup;
 mod mem_pool;
 mod mem_pool_types;
@@ -21,6 +22,7 @@ mod mem_pool_types;
 mod miner;
 
 use ckey::{public_to_address, Address, Password, PlatformAddress, Public};
+use cnetwork::NodeId;
 use cstate::{FindActionHandler, TopStateView};
 use ctypes::transaction::IncompleteTransaction;
 use ctypes::{BlockHash, TxHash};
@@ -29,7 +31,9 @@ use primitives::Bytes;
 use std::ops::Range;
 
 use self::mem_pool_types::AccountDetails;
-pub use self::mem_pool_types::MemPoolMinFees;
+pub use self::admission::{ActionFilter, ActionPolicy, AdmissionStage, Candidate};
+pub use self::mem_pool::Error as MemPoolError;
+pub use self::mem_pool_types::{MemPoolContent, MemPoolInput, MemPoolMinFees, TxSource};
 pub use self::miner::{AuthoringParams, Miner, MinerOptions};
 use crate::account_provider::{AccountProvider, Error as AccountProviderError};
 use crate::client::{
@@ -54,6 +58,9 @@ pub trait MinerService: Send + Sync {
     /// Set the author that we will seal blocks as.
     fn set_author(&self, author: Address) -> Result<(), AccountProviderError>;
 
+    /// Seal blocks as `owner`, whose session key is the engine signer.
+    fn set_validator_owner(&self, owner: Address);
+
     ///Get the address that sealed the block.
     fn get_author_address(&self) -> Address;
 
@@ -87,10 +94,12 @@ pub trait MinerService: Send + Sync {
             + TermInfo;
 
     /// Imports transactions to mem pool.
+    /// The transactions are ignored if too many transactions from the same peer proved invalid.
     fn import_external_transactions<C: MiningBlockChainClient + EngineInfo + TermInfo>(
         &self,
         client: &C,
         transactions: Vec<UnverifiedTransaction>,
+        source: TxSource,
     ) -> Vec<Result<TransactionImportResult, Error>>;
 
     /// Imports own (node owner) transaction to mem pool.
@@ -120,6 +129,18 @@ pub trait MinerService: Send + Sync {
     /// Get a list of all future transactions.
     fn future_transactions(&self) -> Vec<SignedTransaction>;
 
+    /// Get a list of the transactions waiting for their activation block.
+    fn scheduled_transactions(&self) -> Vec<SignedTransaction>;
+
+    /// Get a list of the pooled transactions signed by the given account.
+    fn transactions_of(&self, signer: &Address) -> Vec<SignedTransaction>;
+
+    /// Get all the transactions in the mem pool with their sources.
+    fn mem_pool_content(&self) -> MemPoolContent;
+
+    /// Get the number of the transactions that proved invalid for each peer that propagated them.
+    fn invalid_transactions_of_peers(&self) -> Vec<(NodeId, usize)>;
+
     /// Start sealing.
     fn start_sealing<C: MiningBlockChainClient + EngineInfo + TermInfo>(&self, client: &C);
 
@@ -158,6 +179,8 @@ pub enum TransactionImportResult {
     Current,
     /// Transaction was imported to future queue.
     Future,
+    /// Transaction is held until its activation block.
+    Scheduled,
 }
 
 #[cfg(all(feature = "nightly", test))]
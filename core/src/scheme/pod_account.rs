@@ -16,7 +16,7 @@
 
 use cjson;
 use ckey::Public;
-use cstate::Account;
+use cstate::{Account, Vesting};
 use rlp::{Encodable, RlpStream};
 use std::fmt;
 
@@ -30,6 +30,8 @@ pub struct PodAccount {
     pub seq: u64,
     /// Regular key of the account.
     pub regular_key: Option<Public>,
+    /// Lock-up schedule of the balance.
+    pub vesting: Option<Vesting>,
 }
 
 impl<'a> From<&'a PodAccount> for Account {
@@ -51,6 +53,14 @@ impl From<cjson::scheme::Account> for PodAccount {
             balance: a.balance.map_or(0, Into::into),
             seq: a.seq.map_or(0, Into::into),
             regular_key: None,
+            vesting: a.lock.map(|lock| {
+                Vesting::new(
+                    lock.amount.into(),
+                    lock.start.map_or(0, Into::into),
+                    lock.cliff.map_or(0, Into::into),
+                    lock.period.into(),
+                )
+            }),
         }
     }
 }
@@ -19,21 +19,33 @@ use super::seal::Generic as GenericSeal;
 use super::Genesis;
 use crate::blockchain::HeaderProvider;
 use crate::codechain_machine::CodeChainMachine;
-use crate::consensus::{CodeChainEngine, NullEngine, Solo, Tendermint};
+use crate::consensus::{CodeChainEngine, NullEngine, ShadowEngine, Solo, Tendermint};
+use crate::db::Readable;
 use crate::error::{Error, SchemeError};
 use ccrypto::{blake256, BLAKE_NULL_RLP};
 use cdb::{AsHashDB, HashDB};
 use cjson;
 use ckey::Address;
-use cstate::{Metadata, MetadataAddress, Shard, ShardAddress, StateDB, StateResult, StateWithCache, TopLevelState};
+use cstate::{
+    ActionData, Metadata, MetadataAddress, Shard, ShardAddress, StateDB, StateResult, StateWithCache, TopLevelState,
+    TopStateView, Vesting,
+};
 use ctypes::errors::SyntaxError;
-use ctypes::{BlockHash, CommonParams, Header, ShardId};
+use ctypes::util::unexpected::Mismatch;
+use ctypes::{BlockHash, BlockNumber, CommonParams, ForkSchedule, Header, ShardId};
+use kvdb::KeyValueDB;
+use merkle_trie::snapshot::{ChunkDecompressor, Restore as SnapshotRestore};
 use merkle_trie::{TrieFactory, TrieMut};
 use parking_lot::RwLock;
 use primitives::{Bytes, H256, U256};
 use rlp::{Encodable, Rlp, RlpStream};
+use serde_json;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::{fmt, fs};
 
 /// Parameters for a block chain; includes both those intrinsic to the design of the
 /// chain and those to be interpreted by the active chain engine.
@@ -69,6 +81,8 @@ pub struct Scheme {
     /// Genesis state as plain old data.
     genesis_accounts: PodAccounts,
     genesis_shards: PodShards,
+    /// Compressed chunks of the genesis state tries. If given, they replace the plain old data.
+    genesis_state_chunks: HashMap<H256, Bytes>,
 }
 
 // helper for formatting errors.
@@ -76,6 +90,63 @@ fn fmt_err<F: ::std::fmt::Display>(f: F) -> String {
     format!("Scheme json is invalid: {}", f)
 }
 
+/// Returns the bundled scheme of the given name.
+fn bundled_scheme(name: &str) -> Option<&'static [u8]> {
+    match name {
+        "null" => Some(include_bytes!("../../res/null.json") as &[u8]),
+        "solo" => Some(include_bytes!("../../res/solo.json") as &[u8]),
+        "tendermint" => Some(include_bytes!("../../res/tendermint.json") as &[u8]),
+        "mainnet" => Some(include_bytes!("../../res/mainnet.json") as &[u8]),
+        "corgi" => Some(include_bytes!("../../res/corgi.json") as &[u8]),
+        "beagle" => Some(include_bytes!("../../res/beagle.json") as &[u8]),
+        _ => None,
+    }
+}
+
+/// Returns the resolver of the base schemes named by `extends`: a bundled scheme name or a path to a scheme file.
+///
+/// A relative path is resolved against the directory of the scheme that extends it, which is `dir` for the first one.
+/// The working directory is used if it's not known. A bundled scheme name is refused if a file of the same name
+/// exists there, so the file has to be written as an explicit path like `./solo`.
+fn base_scheme_resolver(dir: Option<&Path>) -> impl FnMut(&str) -> Result<serde_json::Value, String> {
+    let mut dir = dir.map(Path::to_path_buf);
+    // The `extends` chain is linear, so the next base always belongs to the previously resolved one.
+    move |base| {
+        let path = match &dir {
+            Some(dir) => dir.join(base),
+            None => PathBuf::from(base),
+        };
+        if let Some(bytes) = bundled_scheme(base) {
+            if path.is_file() {
+                return Err(format!(
+                    "The base scheme {} is ambiguous with the file at {}. Use ./{} to extend the file",
+                    base,
+                    path.display(),
+                    base
+                ))
+            }
+            dir = None;
+            return serde_json::from_slice(bytes).map_err(|e| format!("Bundled scheme {} is invalid: {}", base, e))
+        }
+        let scheme = parse_scheme_file(&path)?;
+        dir = path.parent().map(Path::to_path_buf);
+        Ok(scheme)
+    }
+}
+
+/// Parses the scheme file at `path`. It's read as TOML if its extension is `.toml`, and as JSON otherwise.
+fn parse_scheme_file(path: &Path) -> Result<serde_json::Value, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Could not load the scheme file at {}: {}", path.display(), e))?;
+    let invalid = |e: &dyn fmt::Display| format!("The scheme file at {} is invalid: {}", path.display(), e);
+    if path.extension().map_or(false, |extension| extension == "toml") {
+        let scheme: toml::Value = toml::from_str(&content).map_err(|e| invalid(&e))?;
+        serde_json::to_value(scheme).map_err(|e| invalid(&e))
+    } else {
+        serde_json::from_str(&content).map_err(|e| invalid(&e))
+    }
+}
+
 macro_rules! load_bundled {
     ($e:expr) => {
         Scheme::load(include_bytes!(concat!("../../res/", $e, ".json")) as &[u8]).expect(concat!(
@@ -88,14 +159,22 @@ macro_rules! load_bundled {
 
 impl Scheme {
     // create an instance of an CodeChain state machine, minus consensus logic.
-    fn machine(_engine_scheme: &cjson::scheme::Engine, params: CommonParams) -> CodeChainMachine {
-        CodeChainMachine::new(params)
+    fn machine(
+        _engine_scheme: &cjson::scheme::Engine,
+        params: CommonParams,
+        fork_schedule: ForkSchedule,
+    ) -> CodeChainMachine {
+        CodeChainMachine::new(params, fork_schedule)
     }
 
     /// Convert engine scheme into a arc'd Engine of the right underlying type.
     /// TODO avoid this hard-coded nastiness - use dynamic-linked plugin framework instead.
-    fn engine(engine_scheme: cjson::scheme::Engine, params: CommonParams) -> Arc<dyn CodeChainEngine> {
-        let machine = Self::machine(&engine_scheme, params);
+    fn engine(
+        engine_scheme: cjson::scheme::Engine,
+        params: CommonParams,
+        fork_schedule: ForkSchedule,
+    ) -> Arc<dyn CodeChainEngine> {
+        let machine = Self::machine(&engine_scheme, params, fork_schedule);
 
         match engine_scheme {
             cjson::scheme::Engine::Null(null) => Arc::new(NullEngine::new(null.params.into(), machine)),
@@ -105,6 +184,9 @@ impl Scheme {
     }
 
     fn initialize_state(&self, db: StateDB) -> Result<StateDB, Error> {
+        if !self.genesis_state_chunks.is_empty() {
+            return self.restore_state(db)
+        }
         let root = BLAKE_NULL_RLP;
         let (db, root) = self.initialize_accounts(db, root)?;
         let (db, root) = self.initialize_shards(db, root)?;
@@ -114,6 +196,38 @@ impl Scheme {
         Ok(db)
     }
 
+    /// Restores the genesis state, exported by `SchemeExportClient::export_genesis`, from the chunks.
+    fn restore_state(&self, mut db: StateDB) -> Result<StateDB, Error> {
+        let root = self.state_root();
+        self.restore_trie(&mut db, root)?;
+        let shard_roots = {
+            let state = TopLevelState::from_existing(db.clone(&root), root)?;
+            let number_of_shards = *state.metadata()?.ok_or(SchemeError::InvalidState)?.number_of_shards();
+            (0..number_of_shards).map(|shard_id| state.shard_root(shard_id)).collect::<Result<Vec<_>, _>>()?
+        };
+        for shard_root in shard_roots.into_iter().flatten() {
+            self.restore_trie(&mut db, shard_root)?;
+        }
+        Ok(db)
+    }
+
+    fn restore_trie(&self, db: &mut StateDB, root: H256) -> Result<(), Error> {
+        if root == BLAKE_NULL_RLP {
+            return Ok(())
+        }
+        let mut restore = SnapshotRestore::new(root);
+        while let Some(chunk_root) = restore.next_to_feed() {
+            let chunk = self
+                .genesis_state_chunks
+                .get(&chunk_root)
+                .and_then(|compressed| ChunkDecompressor::from_slice(compressed).decompress().ok())
+                .and_then(|chunk| chunk.recover(chunk_root).ok())
+                .ok_or(SchemeError::InvalidState)?;
+            restore.feed(db.as_hashdb_mut(), chunk);
+        }
+        Ok(())
+    }
+
     fn initialize_accounts<DB: AsHashDB>(&self, mut db: DB, mut root: H256) -> StateResult<(DB, H256)> {
         // basic accounts in scheme.
         {
@@ -123,6 +237,12 @@ impl Scheme {
                 let r = t.insert(&**address, &account.rlp_bytes());
                 debug_assert_eq!(Ok(None), r);
                 r?;
+                if let Some(vesting) = &account.vesting {
+                    let data = ActionData::from(vesting.rlp_bytes());
+                    let r = t.insert(&*Vesting::key(address), &data.rlp_bytes());
+                    debug_assert_eq!(Ok(None), r);
+                    r?;
+                }
             }
         }
 
@@ -176,6 +296,20 @@ impl Scheme {
         Ok(top_level.commit_and_into_db()?)
     }
 
+    /// Returns an error if the database holds the blocks of another chain.
+    pub fn check_genesis_hash(&self, db: &dyn KeyValueDB) -> Result<(), Error> {
+        let expected = self.genesis_header().hash();
+        let genesis_number: BlockNumber = 0;
+        match db.read(crate::db::COL_EXTRA, &genesis_number) {
+            Some(found) if found != expected => Err(SchemeError::GenesisMismatch(Mismatch {
+                expected,
+                found,
+            })
+            .into()),
+            _ => Ok(()),
+        }
+    }
+
     pub fn check_genesis_root(&self, db: &dyn HashDB) -> bool {
         if db.is_empty() {
             return true
@@ -214,12 +348,37 @@ impl Scheme {
         *self.state_root_memo.read()
     }
 
-    /// Loads scheme from json file. Provide factories for executing contracts and ensuring
+    /// Loads scheme from json file. The file may extend a bundled scheme or another scheme file
+    /// with `"extends"`. Provide factories for executing contracts and ensuring
     /// storage goes to the right place.
     pub fn load<R>(reader: R) -> Result<Self, String>
     where
         R: Read, {
-        cjson::scheme::Scheme::load(reader).map_err(fmt_err).and_then(|x| load_from(x).map_err(fmt_err))
+        Self::load_in(reader, None)
+    }
+
+    /// Loads scheme from json in `dir`, against which a relative path in `"extends"` is resolved.
+    pub fn load_in<R>(reader: R, dir: Option<&Path>) -> Result<Self, String>
+    where
+        R: Read, {
+        cjson::scheme::Scheme::load_extended(reader, base_scheme_resolver(dir))
+            .map_err(fmt_err)
+            .and_then(|x| load_from(x).map_err(fmt_err))
+    }
+
+    /// Loads scheme from the file at `path`, which is written in TOML if its extension is `.toml`
+    /// and in JSON otherwise.
+    pub fn load_file(path: &Path) -> Result<Self, String> {
+        let scheme = parse_scheme_file(path)?;
+        let scheme = cjson::scheme::resolve_extends(scheme, base_scheme_resolver(path.parent())).map_err(fmt_err)?;
+        serde_json::from_value(scheme).map_err(fmt_err).and_then(|x| load_from(x).map_err(fmt_err))
+    }
+
+    /// Loads the JSON of a bundled scheme or a scheme file with its `"extends"` resolved.
+    pub fn load_json(name: &str) -> Result<serde_json::Value, String> {
+        let mut resolve = base_scheme_resolver(None);
+        let scheme = resolve(name)?;
+        cjson::scheme::resolve_extends(scheme, resolve).map_err(fmt_err)
     }
 
     /// Create a new test Scheme.
@@ -276,6 +435,20 @@ impl Scheme {
         header
     }
 
+    /// Runs the engine of `candidate` in shadow mode next to the engine of this scheme.
+    /// The candidate must describe the same chain.
+    pub fn shadow_engine(&mut self, candidate: Scheme) -> Result<(), String> {
+        let (active_genesis, candidate_genesis) = (self.genesis_header().hash(), candidate.genesis_header().hash());
+        if active_genesis != candidate_genesis {
+            return Err(format!(
+                "The shadow chain has a different genesis block: expected {} but found {}",
+                active_genesis, candidate_genesis
+            ))
+        }
+        self.engine = Arc::new(ShadowEngine::new(Arc::clone(&self.engine), candidate.engine));
+        Ok(())
+    }
+
     /// Compose the genesis block for this chain.
     pub fn genesis_block(&self) -> Bytes {
         let empty_list = RlpStream::new_list(0).out();
@@ -297,7 +470,13 @@ fn load_from(s: cjson::scheme::Scheme) -> Result<Scheme, Error> {
     let GenericSeal(seal_rlp) = g.seal.into();
     let params = CommonParams::from(s.params);
     params.verify().map_err(|reason| Error::Syntax(SyntaxError::InvalidCustomAction(reason)))?;
-    let engine = Scheme::engine(s.engine, params);
+    let fork_schedule = s
+        .forks
+        .map(ForkSchedule::try_from)
+        .transpose()
+        .map_err(|reason| Error::Scheme(SchemeError::InvalidForks(reason)))?
+        .unwrap_or_default();
+    let engine = Scheme::engine(s.engine, params, fork_schedule);
 
     let mut s = Scheme {
         name: s.name.clone(),
@@ -314,11 +493,19 @@ fn load_from(s: cjson::scheme::Scheme) -> Result<Scheme, Error> {
         state_root_memo: RwLock::new(Default::default()), // will be overwritten right after.
         genesis_accounts: s.accounts.into(),
         genesis_shards: s.shards.into(),
+        genesis_state_chunks: s
+            .state_chunks
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(root, chunk)| (root.into(), chunk.into_vec()))
+            .collect(),
     };
 
     // use memoized state root if provided.
     match g.state_root {
         Some(root) => *s.state_root_memo.get_mut() = root,
+        // The chunks cannot be restored without their root.
+        None if !s.genesis_state_chunks.is_empty() => return Err(SchemeError::InvalidState.into()),
         None => {
             let db = StateDB::new_with_memorydb();
             let _ = s.initialize_state(db)?;
@@ -333,6 +520,9 @@ mod tests {
     use ccrypto::Blake;
 
     use super::*;
+    use crate::blockchain::BlockChain;
+    use ctypes::BATCH_FORK;
+    use tempfile::tempdir;
 
     #[test]
     fn extra_data_of_genesis_header_is_hash_of_common_params() {
@@ -344,4 +534,131 @@ mod tests {
         let result = genesis_header.extra_data();
         assert_eq!(&hash_of_common_params, result);
     }
+
+    #[test]
+    fn scheme_can_extend_bundled_scheme() {
+        let s = r#"{
+            "extends": "solo",
+            "name": "Private",
+            "params": {
+                "networkID": "pv"
+            }
+        }"#;
+        let scheme = Scheme::load(s.as_bytes()).unwrap();
+        let solo = Scheme::new_test_solo();
+        assert_eq!("Private", scheme.name);
+        assert_eq!("pv", &scheme.genesis_params().network_id().to_string());
+        assert_eq!(
+            solo.genesis_params().min_pay_transaction_cost(),
+            scheme.genesis_params().min_pay_transaction_cost()
+        );
+        assert_eq!(solo.genesis_accounts(), scheme.genesis_accounts());
+    }
+
+    #[test]
+    fn unknown_fork_is_rejected() {
+        let s = r#"{
+            "extends": "null",
+            "forks": {
+                "fastTermTransition": 10
+            }
+        }"#;
+        match Scheme::load(s.as_bytes()) {
+            Err(err) => assert!(err.contains("Unknown fork fastTermTransition"), err),
+            Ok(_) => panic!("The unknown fork must be rejected"),
+        }
+    }
+
+    #[test]
+    fn extends_path_is_relative_to_the_extending_file() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("nested")).unwrap();
+        fs::write(dir.path().join("grand.json"), r#"{"extends": "solo", "name": "Grand"}"#).unwrap();
+        fs::write(dir.path().join("base.json"), r#"{"extends": "grand.json", "params": {"networkID": "pv"}}"#)
+            .unwrap();
+        let child = dir.path().join("nested").join("child.json");
+        fs::write(&child, r#"{"extends": "../base.json", "nodes": ["a"]}"#).unwrap();
+        // The working directory of the tests is the crate, which has none of the files.
+        assert!(!Path::new("base.json").exists() && !Path::new("grand.json").exists());
+
+        let scheme = Scheme::load_file(&child).unwrap();
+        assert_eq!("Grand", scheme.name);
+        assert_eq!("pv", &scheme.genesis_params().network_id().to_string());
+        assert_eq!(vec!["a".to_string()], scheme.nodes);
+
+        let json = Scheme::load_json(child.to_str().unwrap()).unwrap();
+        assert_eq!("Grand", json["name"]);
+    }
+
+    #[test]
+    fn bundled_scheme_name_shadowed_by_a_file_is_refused() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("solo"), r#"{"extends": "null", "name": "NotSolo"}"#).unwrap();
+        let child = dir.path().join("child.json");
+
+        fs::write(&child, r#"{"extends": "solo"}"#).unwrap();
+        match Scheme::load_file(&child) {
+            Err(err) => assert!(err.contains("ambiguous"), err),
+            Ok(scheme) => panic!("{} is extended", scheme.name),
+        }
+
+        fs::write(&child, r#"{"extends": "./solo"}"#).unwrap();
+        assert_eq!("NotSolo", Scheme::load_file(&child).unwrap().name);
+    }
+
+    #[test]
+    fn toml_scheme_extending_bundled_scheme() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("solo.toml");
+        fs::write(&path, "extends = \"solo\"\nname = \"TomlSolo\"\nnodes = [\"127.0.0.1:3485\"]\n").unwrap();
+
+        let scheme = Scheme::load_file(&path).unwrap();
+        assert_eq!("TomlSolo", scheme.name);
+        assert_eq!(vec!["127.0.0.1:3485".to_string()], scheme.nodes);
+        assert_eq!(Scheme::new_test_solo().genesis_params(), scheme.genesis_params());
+
+        fs::write(&path, "name = ").unwrap();
+        assert!(Scheme::load_file(&path).is_err());
+    }
+
+    #[test]
+    fn base_scheme_is_parsed_by_its_extension() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("base.toml"), "extends = \"solo\"\nname = \"TomlBase\"\n").unwrap();
+        let child = dir.path().join("child.json");
+        fs::write(&child, r#"{"extends": "base.toml", "params": {"networkID": "pv"}}"#).unwrap();
+
+        let scheme = Scheme::load_file(&child).unwrap();
+        assert_eq!("TomlBase", scheme.name);
+        assert_eq!("pv", &scheme.genesis_params().network_id().to_string());
+    }
+
+    #[test]
+    fn toml_scheme_unsets_the_keys_of_its_base() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("no_forks.toml");
+        fs::write(&path, "extends = \"solo\"\nunset = [\"forks.batch\"]\n").unwrap();
+
+        let json = Scheme::load_json(path.to_str().unwrap()).unwrap();
+        assert!(json["forks"].get("batch").is_none());
+        assert!(json["forks"].get("burnSeq").is_some());
+        let scheme = Scheme::load_file(&path).unwrap();
+        assert!(!scheme.engine.machine().is_fork_active(BATCH_FORK, 0));
+    }
+
+    #[test]
+    fn database_of_another_chain_is_rejected() {
+        let solo = Scheme::new_test_solo();
+        let db = Arc::new(kvdb_memorydb::create(crate::db::NUM_COLUMNS.unwrap()));
+        assert!(solo.check_genesis_hash(&*db).is_ok());
+
+        BlockChain::new(&solo.genesis_block(), db.clone());
+        assert!(solo.check_genesis_hash(&*db).is_ok());
+        match Scheme::new_test_tendermint().check_genesis_hash(&*db) {
+            Err(Error::Scheme(SchemeError::GenesisMismatch(mismatch))) => {
+                assert_eq!(solo.genesis_header().hash(), mismatch.found)
+            }
+            result => panic!("The database of Solo is opened as Tendermint: {:?}", result),
+        }
+    }
 }
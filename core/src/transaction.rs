@@ -20,9 +20,14 @@ use ckey::{self, public_to_address, recover, sign, Private, Public, Signature};
 use ctypes::errors::SyntaxError;
 use ctypes::transaction::Transaction;
 use ctypes::{BlockHash, BlockNumber, CommonParams, TxHash};
+use primitives::H256;
+use rayon::prelude::*;
 use rlp::{self, DecoderError, Encodable, Rlp, RlpStream};
 use std::ops::Deref;
 
+/// The minimum number of signatures recovered by a single rayon task.
+const RECOVERY_BATCH_SIZE: usize = 16;
+
 /// Signed transaction information without verified signature.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct UnverifiedTransaction {
@@ -30,6 +35,8 @@ pub struct UnverifiedTransaction {
     unsigned: Transaction,
     /// Signature.
     sig: Signature,
+    /// Signature of the sponsor paying the fee on behalf of the signer.
+    sponsor_sig: Option<Signature>,
     /// Hash of the transaction
     hash: TxHash,
 }
@@ -51,12 +58,16 @@ impl From<UnverifiedTransaction> for Transaction {
 impl rlp::Decodable for UnverifiedTransaction {
     fn decode(d: &Rlp<'_>) -> Result<Self, DecoderError> {
         let item_count = d.item_count()?;
-        if item_count != 5 {
-            return Err(DecoderError::RlpIncorrectListLen {
-                expected: 5,
-                got: item_count,
-            })
-        }
+        let sponsor_sig = match item_count {
+            5 => None,
+            6 => Some(d.val_at(5)?),
+            _ => {
+                return Err(DecoderError::RlpIncorrectListLen {
+                    expected: 5,
+                    got: item_count,
+                })
+            }
+        };
         let hash = blake256(d.as_raw()).into();
         Ok(UnverifiedTransaction {
             unsigned: Transaction {
@@ -66,6 +77,7 @@ impl rlp::Decodable for UnverifiedTransaction {
                 action: d.val_at(3)?,
             },
             sig: d.val_at(4)?,
+            sponsor_sig,
             hash,
         })
     }
@@ -82,11 +94,21 @@ impl UnverifiedTransaction {
         UnverifiedTransaction {
             unsigned,
             sig,
+            sponsor_sig: None,
             hash: Default::default(),
         }
         .compute_hash()
     }
 
+    /// Attaches the signature of the sponsor who pays the fee instead of the signer.
+    pub fn with_sponsor(self, sponsor_sig: Signature) -> Self {
+        UnverifiedTransaction {
+            sponsor_sig: Some(sponsor_sig),
+            ..self
+        }
+        .compute_hash()
+    }
+
     /// Used to compute hash of created transactions
     fn compute_hash(mut self) -> UnverifiedTransaction {
         let hash = blake256(&*self.rlp_bytes()).into();
@@ -96,7 +118,20 @@ impl UnverifiedTransaction {
 
     /// Append object with a signature into RLP stream
     fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) {
-        s.begin_list(5);
+        match &self.sponsor_sig {
+            Some(sponsor_sig) => {
+                s.begin_list(6);
+                self.rlp_append_signed_fields(s);
+                s.append(sponsor_sig);
+            }
+            None => {
+                s.begin_list(5);
+                self.rlp_append_signed_fields(s);
+            }
+        }
+    }
+
+    fn rlp_append_signed_fields(&self, s: &mut RlpStream) {
         s.append(&self.seq);
         s.append(&self.fee);
         s.append(&self.network_id);
@@ -104,6 +139,14 @@ impl UnverifiedTransaction {
         s.append(&self.sig);
     }
 
+    /// The message the sponsor signs: the hash of the transaction without the sponsor signature.
+    /// It commits to the signature of the signer, so a sponsor cannot be reused for other transactions.
+    pub fn sponsor_message(&self) -> H256 {
+        let mut s = RlpStream::new_list(5);
+        self.rlp_append_signed_fields(&mut s);
+        blake256(s.as_raw())
+    }
+
     /// Get the hash of this header (blake256 of the RLP).
     pub fn hash(&self) -> TxHash {
         self.hash
@@ -114,14 +157,28 @@ impl UnverifiedTransaction {
         self.sig
     }
 
+    /// Returns the signature of the sponsor if the fee is paid by a sponsor.
+    pub fn sponsor_signature(&self) -> Option<Signature> {
+        self.sponsor_sig
+    }
+
     /// Recovers the public key of the signature.
     pub fn recover_public(&self) -> Result<Public, ckey::Error> {
         Ok(recover(&self.signature(), &self.unsigned.hash())?)
     }
 
-    /// Checks whether the signature has a low 's' value.
+    /// Recovers the public key of the sponsor, if any.
+    pub fn recover_sponsor_public(&self) -> Result<Option<Public>, ckey::Error> {
+        match &self.sponsor_sig {
+            Some(sponsor_sig) => Ok(Some(recover(sponsor_sig, &self.sponsor_message())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Checks whether the signatures have a low 's' value.
     pub fn check_low_s(&self) -> Result<(), ckey::Error> {
-        if !self.signature().is_low_s() {
+        let sponsor_is_low_s = self.sponsor_sig.map_or(true, |sponsor_sig| sponsor_sig.is_low_s());
+        if !self.signature().is_low_s() || !sponsor_is_low_s {
             Err(ckey::Error::InvalidSignature)
         } else {
             Ok(())
@@ -151,6 +208,7 @@ impl UnverifiedTransaction {
 pub struct SignedTransaction {
     tx: UnverifiedTransaction,
     signer_public: Public,
+    sponsor_public: Option<Public>,
 }
 
 pub struct PendingSignedTransactions {
@@ -167,13 +225,17 @@ impl rlp::Encodable for SignedTransaction {
 impl rlp::Decodable for SignedTransaction {
     fn decode(d: &Rlp<'_>) -> Result<Self, DecoderError> {
         let unverified_transaction: UnverifiedTransaction = UnverifiedTransaction::decode(d)?;
-        match unverified_transaction.recover_public() {
-            Ok(key) => Ok(SignedTransaction {
-                tx: unverified_transaction,
-                signer_public: key,
-            }),
-            Err(_) => Err(DecoderError::Custom("signer public key recover failed")),
-        }
+        let signer_public = unverified_transaction
+            .recover_public()
+            .map_err(|_| DecoderError::Custom("signer public key recover failed"))?;
+        let sponsor_public = unverified_transaction
+            .recover_sponsor_public()
+            .map_err(|_| DecoderError::Custom("sponsor public key recover failed"))?;
+        Ok(SignedTransaction {
+            tx: unverified_transaction,
+            signer_public,
+            sponsor_public,
+        })
     }
 }
 
@@ -194,11 +256,27 @@ impl SignedTransaction {
     /// Try to verify transaction and recover public.
     pub fn try_new(tx: UnverifiedTransaction) -> Result<Self, Error> {
         let signer_public = tx.recover_public()?;
+        Self::from_recovered(tx, signer_public)
+    }
+
+    /// Try to verify transactions, recovering their publics in parallel.
+    pub fn try_new_batch(txs: Vec<UnverifiedTransaction>) -> Result<Vec<Self>, Error> {
+        let signer_publics = recover_publics(&txs);
+        txs.into_iter()
+            .zip(signer_publics)
+            .map(|(tx, signer_public)| Self::from_recovered(tx, signer_public?))
+            .collect()
+    }
+
+    /// Verify transaction with the public already recovered from its signature.
+    pub fn from_recovered(tx: UnverifiedTransaction, signer_public: Public) -> Result<Self, Error> {
         let signer = public_to_address(&signer_public);
         tx.action.verify_with_signer_address(&signer)?;
+        let sponsor_public = tx.recover_sponsor_public()?;
         Ok(SignedTransaction {
             tx,
             signer_public,
+            sponsor_public,
         })
     }
 
@@ -213,6 +291,16 @@ impl SignedTransaction {
         self.signer_public
     }
 
+    /// Returns a public key of the sponsor who pays the fee, if any.
+    pub fn sponsor_public(&self) -> Option<Public> {
+        self.sponsor_public
+    }
+
+    /// Returns the public key of the account paying the fee.
+    pub fn fee_payer_public(&self) -> Public {
+        self.sponsor_public.unwrap_or(self.signer_public)
+    }
+
     /// Deconstructs this transaction back into `UnverifiedTransaction`
     pub fn deconstruct(self) -> (UnverifiedTransaction, Public) {
         (self.tx, self.signer_public)
@@ -262,11 +350,16 @@ impl From<LocalizedTransaction> for Transaction {
     }
 }
 
+/// Recovers the publics of the transactions in the rayon thread pool.
+/// The results are in the same order as the transactions.
+pub fn recover_publics(txs: &[UnverifiedTransaction]) -> Vec<Result<Public, ckey::Error>> {
+    txs.par_iter().with_min_len(RECOVERY_BATCH_SIZE).map(UnverifiedTransaction::recover_public).collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use ckey::{Address, Public, Signature};
+    use ckey::{Address, Generator, KeyPair, Public, Random, Signature};
     use ctypes::transaction::Action;
-    use primitives::H256;
     use rlp::rlp_encode_and_decode_test;
 
     use super::*;
@@ -283,6 +376,7 @@ mod tests {
                 network_id: "tc".into(),
             },
             sig: Signature::default(),
+            sponsor_sig: None,
             hash: H256::default().into(),
         }
         .compute_hash());
@@ -301,6 +395,7 @@ mod tests {
                 },
             },
             sig: Signature::default(),
+            sponsor_sig: None,
             hash: H256::default().into(),
         }
         .compute_hash());
@@ -318,6 +413,7 @@ mod tests {
                 },
             },
             sig: Signature::default(),
+            sponsor_sig: None,
             hash: H256::default().into(),
         }
         .compute_hash());
@@ -335,8 +431,120 @@ mod tests {
                 },
             },
             sig: Signature::default(),
+            sponsor_sig: None,
             hash: H256::default().into(),
         }
         .compute_hash());
     }
+
+    #[test]
+    fn encode_and_decode_sponsored_transaction() {
+        rlp_encode_and_decode_test!(UnverifiedTransaction {
+            unsigned: Transaction {
+                seq: 30,
+                fee: 40,
+                network_id: "tc".into(),
+                action: Action::Pay {
+                    receiver: Address::random(),
+                    quantity: 300,
+                },
+            },
+            sig: Signature::default(),
+            sponsor_sig: Some(Signature::default()),
+            hash: H256::default().into(),
+        }
+        .compute_hash());
+    }
+
+    #[test]
+    fn sponsor_is_recovered_from_the_sponsor_signature() {
+        let signer = Random.generate().unwrap();
+        let sponsor = Random.generate().unwrap();
+        let tx = Transaction {
+            seq: 0,
+            fee: 10,
+            network_id: "tc".into(),
+            action: Action::Pay {
+                receiver: Address::random(),
+                quantity: 300,
+            },
+        };
+        let unsponsored: UnverifiedTransaction = SignedTransaction::new_with_sign(tx, signer.private()).into();
+        let sponsor_sig = sign(sponsor.private(), &unsponsored.sponsor_message()).unwrap();
+        let sponsored = unsponsored.clone().with_sponsor(sponsor_sig);
+        assert_ne!(unsponsored.hash(), sponsored.hash());
+        assert_eq!(unsponsored.hash(), TxHash::from(sponsored.sponsor_message()));
+
+        let signed = SignedTransaction::try_new(sponsored).unwrap();
+        assert_eq!(*signer.public(), signed.signer_public());
+        assert_eq!(Some(*sponsor.public()), signed.sponsor_public());
+        assert_eq!(*sponsor.public(), signed.fee_payer_public());
+    }
+
+    #[test]
+    fn try_new_batch_keeps_the_order_of_transactions() {
+        let key_pairs: Vec<KeyPair> = (0..40).map(|_| Random.generate().unwrap()).collect();
+        let txs: Vec<UnverifiedTransaction> = key_pairs
+            .iter()
+            .enumerate()
+            .map(|(seq, key_pair)| {
+                let tx = Transaction {
+                    seq: seq as u64,
+                    fee: 10,
+                    network_id: "tc".into(),
+                    action: Action::Pay {
+                        receiver: Address::random(),
+                        quantity: 300,
+                    },
+                };
+                SignedTransaction::new_with_sign(tx, key_pair.private()).into()
+            })
+            .collect();
+
+        let signed = SignedTransaction::try_new_batch(txs).unwrap();
+        let signers: Vec<Public> = signed.iter().map(SignedTransaction::signer_public).collect();
+        let expected: Vec<Public> = key_pairs.iter().map(|key_pair| *key_pair.public()).collect();
+        assert_eq!(expected, signers);
+    }
+
+    #[test]
+    fn try_new_batch_fails_with_an_invalid_signature() {
+        let key_pair = Random.generate().unwrap();
+        let tx = Transaction {
+            seq: 0,
+            fee: 10,
+            network_id: "tc".into(),
+            action: Action::CreateShard {
+                users: vec![],
+            },
+        };
+        let valid: UnverifiedTransaction = SignedTransaction::new_with_sign(tx.clone(), key_pair.private()).into();
+        let invalid = UnverifiedTransaction::new(tx, Signature::default());
+
+        assert!(SignedTransaction::try_new_batch(vec![valid, invalid]).is_err());
+    }
+
+    #[test]
+    fn truncated_transaction_is_not_decoded() {
+        let tx = UnverifiedTransaction {
+            unsigned: Transaction {
+                seq: 30,
+                fee: 40,
+                network_id: "tc".into(),
+                action: Action::Pay {
+                    receiver: Address::random(),
+                    quantity: 300,
+                },
+            },
+            sig: Signature::default(),
+            sponsor_sig: Some(Signature::default()),
+            hash: H256::default().into(),
+        }
+        .compute_hash();
+        let encoded = rlp::encode(&tx);
+        assert_eq!(tx, rlp::decode(&encoded).unwrap());
+        for length in 0..encoded.len() {
+            assert!(rlp::decode::<UnverifiedTransaction>(&encoded[..length]).is_err(), "{} bytes", length);
+        }
+    }
 }
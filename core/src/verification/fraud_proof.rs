@@ -0,0 +1,165 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::block::{enact, IsBlock};
+use crate::client::{EngineInfo, TermInfo};
+use crate::consensus::CodeChainEngine;
+use crate::error::Error;
+use crate::transaction::{SignedTransaction, UnverifiedTransaction};
+use ccrypto::BLAKE_NULL_RLP;
+use cstate::{FindActionHandler, StateDB};
+use ctypes::{BlockHash, Header};
+use cvm::ChainTimeInfo;
+use merkle_trie::skewed_merkle_root;
+use primitives::Bytes;
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+
+const BAD_SEAL: u8 = 1;
+const BAD_STATE_TRANSITION: u8 = 2;
+
+/// Proves that a block is invalid to the nodes which don't execute it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FraudProof {
+    /// The seal of the header is not signed by its validators.
+    BadSeal(Header),
+    /// The transactions of the block don't result in the state root of its header.
+    /// The witness has the trie nodes of the parent state that the execution reads.
+    BadStateTransition {
+        parent: Header,
+        header: Header,
+        transactions: Vec<UnverifiedTransaction>,
+        witness: Vec<Bytes>,
+    },
+}
+
+impl FraudProof {
+    /// The hash of the invalid block.
+    pub fn block_hash(&self) -> BlockHash {
+        match self {
+            FraudProof::BadSeal(header)
+            | FraudProof::BadStateTransition {
+                header,
+                ..
+            } => header.hash(),
+        }
+    }
+
+    /// Returns true if the proof shows that the block is invalid.
+    /// The parent of a bad state transition should be checked against the trusted headers by the caller.
+    pub fn verify<C: ChainTimeInfo + EngineInfo + FindActionHandler + TermInfo + Sync>(
+        &self,
+        engine: &dyn CodeChainEngine,
+        client: &C,
+    ) -> Result<bool, Error> {
+        match self {
+            FraudProof::BadSeal(header) => Ok(engine.verify_block_external(header).is_err()),
+            FraudProof::BadStateTransition {
+                parent,
+                header,
+                transactions,
+                witness,
+            } => {
+                if *header.parent_hash() != parent.hash() {
+                    return Ok(false)
+                }
+                // The transactions are committed by the header, so the prover can't replace them.
+                let transactions_root =
+                    skewed_merkle_root(BLAKE_NULL_RLP, transactions.iter().map(Encodable::rlp_bytes));
+                if *header.transactions_root() != transactions_root {
+                    return Ok(false)
+                }
+                let transactions = SignedTransaction::try_new_batch(transactions.clone())?;
+                let db = StateDB::from_witness(witness);
+                let executed = enact(header, &transactions, engine, client, db, parent)?;
+                Ok(executed.header().state_root() != header.state_root())
+            }
+        }
+    }
+}
+
+impl Encodable for FraudProof {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        match self {
+            FraudProof::BadSeal(header) => {
+                s.begin_list(2);
+                s.append(&BAD_SEAL);
+                s.append(header);
+            }
+            FraudProof::BadStateTransition {
+                parent,
+                header,
+                transactions,
+                witness,
+            } => {
+                s.begin_list(5);
+                s.append(&BAD_STATE_TRANSITION);
+                s.append(parent);
+                s.append(header);
+                s.append_list(transactions);
+                s.append_list::<Bytes, Bytes>(witness);
+            }
+        }
+    }
+}
+
+impl Decodable for FraudProof {
+    fn decode(rlp: &Rlp<'_>) -> Result<Self, DecoderError> {
+        let item_count = rlp.item_count()?;
+        let tag: u8 = rlp.val_at(0)?;
+        let expected = match tag {
+            BAD_SEAL => 2,
+            BAD_STATE_TRANSITION => 5,
+            _ => return Err(DecoderError::Custom("Unexpected fraud proof type")),
+        };
+        if item_count != expected {
+            return Err(DecoderError::RlpIncorrectListLen {
+                got: item_count,
+                expected,
+            })
+        }
+        if tag == BAD_SEAL {
+            return Ok(FraudProof::BadSeal(rlp.val_at(1)?))
+        }
+        Ok(FraudProof::BadStateTransition {
+            parent: rlp.val_at(1)?,
+            header: rlp.val_at(2)?,
+            transactions: rlp.list_at(3)?,
+            witness: rlp.list_at(4)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rlp::rlp_encode_and_decode_test;
+
+    #[test]
+    fn fraud_proof_rlp() {
+        let mut header = Header::default();
+        header.set_number(10);
+        rlp_encode_and_decode_test!(FraudProof::BadSeal(header.clone()));
+
+        let parent = Header::default();
+        header.set_parent_hash(parent.hash());
+        rlp_encode_and_decode_test!(FraudProof::BadStateTransition {
+            parent,
+            header,
+            transactions: Vec::new(),
+            witness: vec![vec![0xc0], vec![1, 2, 3]],
+        });
+    }
+}
@@ -15,6 +15,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 mod canon_verifier;
+mod fraud_proof;
 mod noop_verifier;
 pub mod queue;
 #[cfg_attr(feature = "cargo-clippy", allow(clippy::module_inception))]
@@ -22,6 +23,7 @@ mod verification;
 mod verifier;
 
 pub use self::canon_verifier::CanonVerifier;
+pub use self::fraud_proof::FraudProof;
 pub use self::noop_verifier::NoopVerifier;
 pub use self::queue::{BlockQueue, Config as QueueConfig};
 pub use self::verification::*;
@@ -74,6 +74,8 @@ pub struct VerificationQueue<K: Kind> {
     verifier_handles: Vec<JoinHandle<()>>,
     max_queue_size: usize,
     max_mem_use: usize,
+    /// `max_mem_use` lowered to keep the memory budget.
+    mem_limit: AtomicUsize,
 }
 
 struct QueueSignal {
@@ -187,6 +189,7 @@ impl<K: Kind> VerificationQueue<K> {
             verifier_handles,
             max_queue_size: cmp::max(config.max_queue_size, MIN_QUEUE_LIMIT),
             max_mem_use: cmp::max(config.max_mem_use, MIN_MEM_LIMIT),
+            mem_limit: AtomicUsize::new(cmp::max(config.max_mem_use, MIN_MEM_LIMIT)),
         }
     }
 
@@ -478,11 +481,18 @@ impl<K: Kind> VerificationQueue<K> {
             verifying_queue_size: verifying_len,
             verified_queue_size: verified_len,
             max_queue_size: self.max_queue_size,
-            max_mem_use: self.max_mem_use,
+            max_mem_use: self.mem_limit.load(AtomicOrdering::Relaxed),
             mem_used: unverified_bytes + verifying_bytes + verified_bytes,
         }
     }
 
+    /// Lowers the memory limit, or restores it if `limit` is `None`.
+    /// The queue is reported full while it uses more than the limit, so the blocks are not downloaded.
+    pub fn restrict_memory(&self, limit: Option<usize>) {
+        let limit = limit.map_or(self.max_mem_use, |limit| cmp::max(cmp::min(limit, self.max_mem_use), MIN_MEM_LIMIT));
+        self.mem_limit.store(limit, AtomicOrdering::Relaxed);
+    }
+
     /// Get the total score of all the blocks in the queue.
     pub fn total_score(&self) -> U256 {
         *self.total_score.read()
@@ -79,7 +79,7 @@ pub fn verify_block_with_params(
     verify_header_with_params(&header, common_params)?;
 
     let body_rlp = Rlp::new(bytes).at(1).expect("verify_block_basic already checked it");
-    if body_rlp.as_raw().len() > common_params.max_body_size() {
+    if body_rlp.as_raw().len() > engine.max_body_size(common_params) {
         return Err(BlockError::BodySizeIsTooBig.into())
     }
 
@@ -166,14 +166,7 @@ pub fn verify_block_seal(
         engine.verify_block_seal(&header)?;
     }
     // Verify transactions.
-    let mut transactions = Vec::new();
-    {
-        let v = BlockView::new(&bytes);
-        for t in v.transactions() {
-            let signed = CodeChainMachine::verify_transaction_seal(t, &header)?;
-            transactions.push(signed);
-        }
-    }
+    let transactions = CodeChainMachine::verify_transactions_seal(BlockView::new(&bytes).transactions(), &header)?;
     Ok(PreverifiedBlock {
         header,
         transactions,
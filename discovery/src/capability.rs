@@ -0,0 +1,185 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use cnetwork::SocketAddr;
+use ctypes::ShardId;
+use parking_lot::RwLock;
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const ARCHIVE: u8 = 0x01;
+const SNAPSHOT: u8 = 0x02;
+const LIGHT_SERVER: u8 = 0x04;
+
+/// What a node serves to its peers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Capabilities {
+    /// The states of all the blocks are kept.
+    pub archive: bool,
+    /// The snapshots are served.
+    pub snapshot: bool,
+    /// The requests of the light clients are answered.
+    pub light_server: bool,
+    /// The shards whose states are kept. All shards are kept if it's `None`.
+    pub shards: Option<Vec<ShardId>>,
+}
+
+impl Capabilities {
+    /// Returns true if the node serves everything `wanted` asks.
+    /// The shards of `wanted` are not asked if they are `None`.
+    pub fn satisfies(&self, wanted: &Capabilities) -> bool {
+        if (wanted.archive && !self.archive)
+            || (wanted.snapshot && !self.snapshot)
+            || (wanted.light_server && !self.light_server)
+        {
+            return false
+        }
+        match (&self.shards, &wanted.shards) {
+            (Some(shards), Some(wanted_shards)) => wanted_shards.iter().all(|shard| shards.contains(shard)),
+            _ => true,
+        }
+    }
+
+    /// Returns true if every node satisfies it.
+    pub fn is_empty(&self) -> bool {
+        *self == Capabilities::default()
+    }
+
+    fn flags(&self) -> u8 {
+        let mut flags = 0;
+        if self.archive {
+            flags |= ARCHIVE;
+        }
+        if self.snapshot {
+            flags |= SNAPSHOT;
+        }
+        if self.light_server {
+            flags |= LIGHT_SERVER;
+        }
+        flags
+    }
+}
+
+impl Encodable for Capabilities {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        match &self.shards {
+            None => s.begin_list(1).append(&self.flags()),
+            Some(shards) => s.begin_list(2).append(&self.flags()).append_list(shards),
+        };
+    }
+}
+
+impl Decodable for Capabilities {
+    fn decode(rlp: &Rlp<'_>) -> Result<Self, DecoderError> {
+        let shards = match rlp.item_count()? {
+            1 => None,
+            2 => Some(rlp.list_at(1)?),
+            got => {
+                return Err(DecoderError::RlpIncorrectListLen {
+                    got,
+                    expected: 2,
+                })
+            }
+        };
+        let flags: u8 = rlp.val_at(0)?;
+        Ok(Self {
+            archive: flags & ARCHIVE != 0,
+            snapshot: flags & SNAPSHOT != 0,
+            light_server: flags & LIGHT_SERVER != 0,
+            shards,
+        })
+    }
+}
+
+/// The capabilities that the peers advertised, by their addresses.
+#[derive(Default)]
+pub struct Directory {
+    capabilities: RwLock<HashMap<SocketAddr, Capabilities>>,
+}
+
+impl Directory {
+    pub fn new() -> Arc<Self> {
+        Default::default()
+    }
+
+    pub fn insert(&self, address: SocketAddr, capabilities: Capabilities) {
+        self.capabilities.write().insert(address, capabilities);
+    }
+
+    pub fn get(&self, address: &SocketAddr) -> Option<Capabilities> {
+        self.capabilities.read().get(address).cloned()
+    }
+
+    /// The addresses of the nodes that serve everything `wanted` asks.
+    pub fn find(&self, wanted: &Capabilities) -> Vec<SocketAddr> {
+        self.capabilities
+            .read()
+            .iter()
+            .filter(|(_, capabilities)| capabilities.satisfies(wanted))
+            .map(|(address, _)| *address)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rlp::rlp_encode_and_decode_test;
+
+    #[test]
+    fn encode_and_decode_capabilities() {
+        rlp_encode_and_decode_test!(Capabilities::default());
+        rlp_encode_and_decode_test!(Capabilities {
+            archive: true,
+            snapshot: false,
+            light_server: true,
+            shards: Some(vec![0, 3]),
+        });
+    }
+
+    #[test]
+    fn satisfies_the_wanted_capabilities() {
+        let snapshot_server = Capabilities {
+            snapshot: true,
+            shards: Some(vec![0, 1]),
+            ..Default::default()
+        };
+        assert!(snapshot_server.satisfies(&Capabilities::default()));
+        assert!(snapshot_server.satisfies(&Capabilities {
+            snapshot: true,
+            shards: Some(vec![1]),
+            ..Default::default()
+        }));
+        assert!(!snapshot_server.satisfies(&Capabilities {
+            light_server: true,
+            ..Default::default()
+        }));
+        assert!(!snapshot_server.satisfies(&Capabilities {
+            shards: Some(vec![1, 2]),
+            ..Default::default()
+        }));
+
+        let full_node = Capabilities {
+            archive: true,
+            ..Default::default()
+        };
+        assert!(full_node.satisfies(&Capabilities {
+            shards: Some(vec![1, 2]),
+            ..Default::default()
+        }));
+    }
+}
@@ -14,19 +14,26 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use super::capability::Capabilities;
+use super::K;
+use super::T_REFRESH;
+
 pub struct Config {
     pub bucket_size: u8,
     pub t_refresh: u32,
+    /// Advertised to the peers.
+    pub capabilities: Capabilities,
+    /// Only the addresses of the nodes that serve these are asked to the peers.
+    pub wanted: Capabilities,
 }
 
-use super::K;
-use super::T_REFRESH;
-
 impl Config {
     pub fn new(bucket_size: Option<u8>, t_refresh: Option<u32>) -> Self {
         Self {
             bucket_size: bucket_size.unwrap_or(K),
             t_refresh: t_refresh.unwrap_or(T_REFRESH),
+            capabilities: Default::default(),
+            wanted: Default::default(),
         }
     }
 }
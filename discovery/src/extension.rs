@@ -14,29 +14,39 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use super::message::Message;
+use super::capability::{Capabilities, Directory};
+use super::message::{CapabilityMessage, Contact, Message};
 use super::node_id::{address_to_hash, KademliaId};
 use super::Config;
-use cnetwork::{Api, IntoSocketAddr, NetworkExtension, NodeId, RoutingTable};
+use cnetwork::{Api, IntoSocketAddr, NetworkExtension, NodeId, RoutingTable, SocketAddr};
 use ctimer::TimerToken;
 use never_type::Never;
+use primitives::Bytes;
 use rand::prelude::SliceRandom;
 use rand::thread_rng;
 use rlp::{Decodable, Encodable, Rlp};
-use std::collections::HashSet;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
 pub struct Extension {
     config: Config,
     routing_table: Arc<RoutingTable>,
+    directory: Arc<Directory>,
     api: Box<dyn Api>,
-    nodes: HashSet<NodeId>, // FIXME: Find the optimized data structure for it
+    /// The versions of the connected nodes
+    nodes: HashMap<NodeId, u64>, // FIXME: Find the optimized data structure for it
     use_kademlia: bool,
 }
 
 impl Extension {
-    pub fn new(routing_table: Arc<RoutingTable>, config: Config, api: Box<dyn Api>, use_kademlia: bool) -> Self {
+    pub fn new(
+        routing_table: Arc<RoutingTable>,
+        directory: Arc<Directory>,
+        config: Config,
+        api: Box<dyn Api>,
+        use_kademlia: bool,
+    ) -> Self {
         if use_kademlia {
             cinfo!(DISCOVERY, "Discovery starts with kademlia option");
         } else {
@@ -47,11 +57,110 @@ impl Extension {
         Self {
             config,
             routing_table,
+            directory,
             api,
             nodes: Default::default(),
             use_kademlia,
         }
     }
+
+    fn request(&self, version: u64) -> Arc<Bytes> {
+        let request = match version {
+            0 => Message::Request(self.config.bucket_size).rlp_bytes(),
+            _ => CapabilityMessage::Request {
+                limit: self.config.bucket_size,
+                wanted: self.config.wanted.clone(),
+            }
+            .rlp_bytes(),
+        };
+        Arc::new(request)
+    }
+
+    /// The addresses to answer the request of `node`, which serve `wanted`.
+    fn addresses_for(&self, node: &NodeId, len: u8, wanted: &Capabilities) -> Vec<SocketAddr> {
+        let mut addresses = self.routing_table.reachable_addresses(&node.into_addr());
+        if !wanted.is_empty() {
+            addresses.retain(|address| {
+                self.directory.get(address).map_or(false, |capabilities| capabilities.satisfies(wanted))
+            });
+        }
+        let len = ::std::cmp::min(self.config.bucket_size, len) as usize;
+        if self.use_kademlia {
+            let datum = address_to_hash(&node.into_addr());
+            let mut addresses =
+                addresses.into_iter().map(|address| KademliaId::new(address, &datum)).collect::<Vec<_>>();
+
+            addresses.sort_unstable();
+
+            addresses.into_iter().map(From::from).take(len).collect()
+        } else {
+            addresses.shuffle(&mut thread_rng());
+            addresses.into_iter().take(len).collect()
+        }
+    }
+
+    fn on_message_v0(&mut self, node: &NodeId, message: &[u8]) {
+        let message = match Message::decode(&Rlp::new(message)) {
+            Ok(message) => message,
+            Err(err) => {
+                cwarn!(DISCOVERY, "Invalid message from {} : {:?}", node, err);
+                return
+            }
+        };
+        match message {
+            Message::Request(len) => {
+                let addresses = self.addresses_for(node, len, &Capabilities::default());
+                let response = Arc::new(Message::Response(addresses).rlp_bytes());
+                self.api.send(&node, response);
+            }
+            Message::Response(addresses) => {
+                self.routing_table.touch_addresses(addresses);
+            }
+        }
+    }
+
+    fn on_message_v1(&mut self, node: &NodeId, message: &[u8]) {
+        let message = match CapabilityMessage::decode(&Rlp::new(message)) {
+            Ok(message) => message,
+            Err(err) => {
+                cwarn!(DISCOVERY, "Invalid message from {} : {:?}", node, err);
+                return
+            }
+        };
+        match message {
+            CapabilityMessage::Advertise(capabilities) => {
+                self.directory.insert(node.into_addr(), capabilities);
+            }
+            CapabilityMessage::Request {
+                limit,
+                wanted,
+            } => {
+                let contacts = self
+                    .addresses_for(node, limit, &wanted)
+                    .into_iter()
+                    .map(|address| Contact {
+                        address,
+                        capabilities: self.directory.get(&address),
+                    })
+                    .collect();
+                let response = Arc::new(CapabilityMessage::Response(contacts).rlp_bytes());
+                self.api.send(&node, response);
+            }
+            CapabilityMessage::Response(contacts) => {
+                let mut addresses = Vec::with_capacity(contacts.len());
+                for contact in contacts {
+                    // The nodes that are connected advertise their capabilities by themselves.
+                    if let Some(capabilities) = contact.capabilities {
+                        if self.directory.get(&contact.address).is_none() {
+                            self.directory.insert(contact.address, capabilities);
+                        }
+                    }
+                    addresses.push(contact.address);
+                }
+                self.routing_table.touch_addresses(addresses);
+            }
+        }
+    }
 }
 
 const REFRESH_TOKEN: TimerToken = 0;
@@ -66,13 +175,17 @@ impl NetworkExtension<Never> for Extension {
     }
 
     fn versions() -> &'static [u64] {
-        const VERSIONS: &[u64] = &[0];
+        const VERSIONS: &[u64] = &[0, 1];
         &VERSIONS
     }
 
-    fn on_node_added(&mut self, node: &NodeId, _version: u64) {
-        self.nodes.insert(*node);
-        self.api.send(&node, Arc::new(Message::Request(self.config.bucket_size).rlp_bytes()));
+    fn on_node_added(&mut self, node: &NodeId, version: u64) {
+        self.nodes.insert(*node, version);
+        if version >= 1 {
+            let advertise = CapabilityMessage::Advertise(self.config.capabilities.clone()).rlp_bytes();
+            self.api.send(&node, Arc::new(advertise));
+        }
+        self.api.send(&node, self.request(version));
     }
 
     fn on_node_removed(&mut self, node: &NodeId) {
@@ -80,51 +193,20 @@ impl NetworkExtension<Never> for Extension {
     }
 
     fn on_message(&mut self, node: &NodeId, message: &[u8]) {
-        let message = match Message::decode(&Rlp::new(message)) {
-            Ok(message) => message,
-            Err(err) => {
-                cwarn!(DISCOVERY, "Invalid message from {} : {:?}", node, err);
-                return
-            }
-        };
-        match message {
-            Message::Request(len) => {
-                let addresses = if self.use_kademlia {
-                    let datum = address_to_hash(&node.into_addr());
-                    let mut addresses = self
-                        .routing_table
-                        .reachable_addresses(&node.into_addr())
-                        .into_iter()
-                        .map(|address| KademliaId::new(address, &datum))
-                        .collect::<Vec<_>>();
-
-                    addresses.sort_unstable();
-
-                    addresses
-                        .into_iter()
-                        .map(From::from)
-                        .take(::std::cmp::min(self.config.bucket_size, len) as usize)
-                        .collect()
-                } else {
-                    let mut addresses = self.routing_table.reachable_addresses(&node.into_addr());
-                    addresses.shuffle(&mut thread_rng());
-                    addresses.into_iter().take(::std::cmp::min(self.config.bucket_size, len) as usize).collect()
-                };
-                let response = Arc::new(Message::Response(addresses).rlp_bytes());
-                self.api.send(&node, response);
-            }
-            Message::Response(addresses) => {
-                self.routing_table.touch_addresses(addresses);
-            }
+        match self.nodes.get(node) {
+            Some(0) => self.on_message_v0(node, message),
+            Some(_) => self.on_message_v1(node, message),
+            None => cwarn!(DISCOVERY, "Message from the unknown node {}", node),
         }
     }
 
     fn on_timeout(&mut self, timer: TimerToken) {
         match timer {
             REFRESH_TOKEN => {
-                let request = Arc::new(Message::Request(self.config.bucket_size).rlp_bytes());
-                for node in &self.nodes {
-                    self.api.send(node, Arc::clone(&request));
+                let requests = [self.request(0), self.request(1)];
+                for (node, version) in &self.nodes {
+                    let request = &requests[::std::cmp::min(*version, 1) as usize];
+                    self.api.send(node, Arc::clone(request));
                 }
             }
             _ => unreachable!(),
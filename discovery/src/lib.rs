@@ -23,14 +23,19 @@ extern crate codechain_crypto as ccrypto;
 extern crate codechain_logger as clogger;
 extern crate codechain_network as cnetwork;
 extern crate codechain_timer as ctimer;
+extern crate codechain_types as ctypes;
 
+mod capability;
 mod config;
 mod extension;
 mod message;
 mod node_id;
+mod pex;
 
 const K: u8 = 16;
 const T_REFRESH: u32 = 60_000;
 
+pub use crate::capability::{Capabilities, Directory};
 pub use crate::config::Config;
 pub use crate::extension::Extension as Discovery;
+pub use crate::pex::{Config as PexConfig, Extension as Pex};
@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use crate::capability::Capabilities;
 use cnetwork::SocketAddr;
 use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
 
@@ -46,6 +47,101 @@ impl Decodable for Message {
     }
 }
 
+const MESSAGE_ID_ADVERTISE: u8 = 0x01;
+const MESSAGE_ID_REQUEST: u8 = 0x02;
+const MESSAGE_ID_RESPONSE: u8 = 0x03;
+
+/// The messages of the version 1, which carry the capabilities of the nodes.
+#[derive(Debug, PartialEq)]
+pub enum CapabilityMessage {
+    Advertise(Capabilities),
+    Request {
+        limit: u8,
+        wanted: Capabilities,
+    },
+    Response(Vec<Contact>),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Contact {
+    pub address: SocketAddr,
+    /// `None` if the node didn't advertise its capabilities.
+    pub capabilities: Option<Capabilities>,
+}
+
+impl Encodable for CapabilityMessage {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        match self {
+            CapabilityMessage::Advertise(capabilities) => {
+                s.begin_list(2).append(&MESSAGE_ID_ADVERTISE).append(capabilities);
+            }
+            CapabilityMessage::Request {
+                limit,
+                wanted,
+            } => {
+                s.begin_list(3).append(&MESSAGE_ID_REQUEST).append(limit).append(wanted);
+            }
+            CapabilityMessage::Response(contacts) => {
+                s.begin_list(2).append(&MESSAGE_ID_RESPONSE).append_list(contacts);
+            }
+        }
+    }
+}
+
+impl Decodable for CapabilityMessage {
+    fn decode(rlp: &Rlp<'_>) -> Result<Self, DecoderError> {
+        let id: u8 = rlp.val_at(0)?;
+        let expected = match id {
+            MESSAGE_ID_REQUEST => 3,
+            _ => 2,
+        };
+        let got = rlp.item_count()?;
+        if got != expected {
+            return Err(DecoderError::RlpIncorrectListLen {
+                got,
+                expected,
+            })
+        }
+        match id {
+            MESSAGE_ID_ADVERTISE => Ok(CapabilityMessage::Advertise(rlp.val_at(1)?)),
+            MESSAGE_ID_REQUEST => Ok(CapabilityMessage::Request {
+                limit: rlp.val_at(1)?,
+                wanted: rlp.val_at(2)?,
+            }),
+            MESSAGE_ID_RESPONSE => Ok(CapabilityMessage::Response(rlp.list_at(1)?)),
+            _ => Err(DecoderError::Custom("Unknown message id detected")),
+        }
+    }
+}
+
+impl Encodable for Contact {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        match &self.capabilities {
+            None => s.begin_list(1).append(&self.address),
+            Some(capabilities) => s.begin_list(2).append(&self.address).append(capabilities),
+        };
+    }
+}
+
+impl Decodable for Contact {
+    fn decode(rlp: &Rlp<'_>) -> Result<Self, DecoderError> {
+        let capabilities = match rlp.item_count()? {
+            1 => None,
+            2 => Some(rlp.val_at(1)?),
+            got => {
+                return Err(DecoderError::RlpIncorrectListLen {
+                    got,
+                    expected: 2,
+                })
+            }
+        };
+        Ok(Self {
+            address: rlp.val_at(0)?,
+            capabilities,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use rlp::rlp_encode_and_decode_test;
@@ -89,4 +185,28 @@ mod tests {
             SocketAddr::v4(127, 0, 0, 1, 3481),
         ]));
     }
+
+    #[test]
+    fn encode_and_decode_capability_messages() {
+        let capabilities = Capabilities {
+            snapshot: true,
+            shards: Some(vec![1]),
+            ..Default::default()
+        };
+        rlp_encode_and_decode_test!(CapabilityMessage::Advertise(capabilities.clone()));
+        rlp_encode_and_decode_test!(CapabilityMessage::Request {
+            limit: 10,
+            wanted: Capabilities::default(),
+        });
+        rlp_encode_and_decode_test!(CapabilityMessage::Response(vec![
+            Contact {
+                address: SocketAddr::v4(127, 0, 0, 1, 3480),
+                capabilities: Some(capabilities),
+            },
+            Contact {
+                address: SocketAddr::v4(127, 0, 0, 1, 3481),
+                capabilities: None,
+            },
+        ]));
+    }
 }
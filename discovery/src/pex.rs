@@ -0,0 +1,206 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Peer exchange: the connected peers periodically push the addresses of their established peers to each other.
+//!
+//! Only the addresses this node has established sessions with are sent, so the addresses received from a peer are
+//! never relayed before they are proved healthy. The messages of a peer are dropped if they come faster than the half
+//! of the interval, and only `max_addresses` addresses are taken from a message.
+
+use cnetwork::{Api, IntoSocketAddr, NetworkExtension, NodeId, RoutingTable, SocketAddr};
+use ctimer::TimerToken;
+use never_type::Never;
+use rand::prelude::SliceRandom;
+use rand::thread_rng;
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub struct Config {
+    /// In milliseconds.
+    pub interval: u32,
+    /// The number of the addresses in a message.
+    pub max_addresses: u8,
+}
+
+#[derive(Debug, PartialEq)]
+struct Message(Vec<SocketAddr>);
+
+impl Encodable for Message {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.append_list(&self.0);
+    }
+}
+
+impl Decodable for Message {
+    fn decode(rlp: &Rlp<'_>) -> Result<Self, DecoderError> {
+        Ok(Message(rlp.as_list()?))
+    }
+}
+
+/// Allows a message for each node once in `min_gap`.
+struct RateLimit {
+    min_gap: Duration,
+    last_received: HashMap<NodeId, Instant>,
+}
+
+impl RateLimit {
+    fn new(min_gap: Duration) -> Self {
+        Self {
+            min_gap,
+            last_received: HashMap::new(),
+        }
+    }
+
+    fn allow(&mut self, node: &NodeId, now: Instant) -> bool {
+        if let Some(last_received) = self.last_received.get(node) {
+            if now < *last_received + self.min_gap {
+                return false
+            }
+        }
+        self.last_received.insert(*node, now);
+        true
+    }
+
+    fn remove(&mut self, node: &NodeId) {
+        self.last_received.remove(node);
+    }
+}
+
+pub struct Extension {
+    config: Config,
+    routing_table: Arc<RoutingTable>,
+    api: Box<dyn Api>,
+    nodes: HashSet<NodeId>,
+    rate_limit: RateLimit,
+}
+
+impl Extension {
+    pub fn new(routing_table: Arc<RoutingTable>, config: Config, api: Box<dyn Api>) -> Self {
+        let interval = Duration::from_millis(u64::from(config.interval));
+        api.set_timer(EXCHANGE_TOKEN, interval).expect("Exchange must be registered");
+        Self {
+            config,
+            routing_table,
+            api,
+            nodes: Default::default(),
+            rate_limit: RateLimit::new(interval / 2),
+        }
+    }
+
+    /// Picks the established addresses that `node` can reach at random.
+    fn addresses_for(&self, node: &NodeId, established: &[SocketAddr]) -> Vec<SocketAddr> {
+        let from = node.into_addr();
+        let mut addresses: Vec<_> = established.iter().filter(|address| from.is_reachable(address)).cloned().collect();
+        addresses.shuffle(&mut thread_rng());
+        addresses.truncate(self.config.max_addresses as usize);
+        addresses
+    }
+}
+
+const EXCHANGE_TOKEN: TimerToken = 0;
+
+impl NetworkExtension<Never> for Extension {
+    fn name() -> &'static str {
+        "pex"
+    }
+
+    fn need_encryption() -> bool {
+        false
+    }
+
+    fn versions() -> &'static [u64] {
+        const VERSIONS: &[u64] = &[0];
+        &VERSIONS
+    }
+
+    fn on_node_added(&mut self, node: &NodeId, _version: u64) {
+        self.nodes.insert(*node);
+    }
+
+    fn on_node_removed(&mut self, node: &NodeId) {
+        self.nodes.remove(node);
+        self.rate_limit.remove(node);
+    }
+
+    fn on_message(&mut self, node: &NodeId, message: &[u8]) {
+        let Message(mut addresses) = match Message::decode(&Rlp::new(message)) {
+            Ok(message) => message,
+            Err(err) => {
+                cwarn!(DISCOVERY, "Invalid peer exchange message from {} : {:?}", node, err);
+                return
+            }
+        };
+        if !self.rate_limit.allow(node, Instant::now()) {
+            cdebug!(DISCOVERY, "Peer exchange message from {} is dropped by the rate limit", node);
+            return
+        }
+        if addresses.len() > self.config.max_addresses as usize {
+            cdebug!(DISCOVERY, "{} sent {} addresses, more than {}", node, addresses.len(), self.config.max_addresses);
+            addresses.truncate(self.config.max_addresses as usize);
+        }
+        let routing_table = &self.routing_table;
+        routing_table.touch_addresses(addresses.into_iter().filter(|address| !routing_table.is_banned(address)));
+    }
+
+    fn on_timeout(&mut self, timer: TimerToken) {
+        match timer {
+            EXCHANGE_TOKEN => {
+                let established = self.routing_table.established_addresses();
+                for node in &self.nodes {
+                    let addresses = self.addresses_for(node, &established);
+                    if addresses.is_empty() {
+                        continue
+                    }
+                    self.api.send(node, Arc::new(Message(addresses).rlp_bytes()));
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rlp::rlp_encode_and_decode_test;
+
+    #[test]
+    fn encode_and_decode_message() {
+        rlp_encode_and_decode_test!(Message(vec![]));
+        rlp_encode_and_decode_test!(Message(vec![
+            SocketAddr::v4(127, 0, 0, 1, 3485),
+            SocketAddr::v4(192, 168, 0, 1, 3486),
+        ]));
+    }
+
+    #[test]
+    fn messages_faster_than_the_min_gap_are_dropped() {
+        let mut rate_limit = RateLimit::new(Duration::from_secs(10));
+        let node = NodeId::new([127, 0, 0, 1].into(), 3485);
+        let other = NodeId::new([127, 0, 0, 1].into(), 3486);
+        let now = Instant::now();
+
+        assert!(rate_limit.allow(&node, now));
+        assert!(!rate_limit.allow(&node, now + Duration::from_secs(9)));
+        assert!(rate_limit.allow(&other, now + Duration::from_secs(9)));
+        assert!(rate_limit.allow(&node, now + Duration::from_secs(10)));
+
+        rate_limit.remove(&node);
+        assert!(rate_limit.allow(&node, now + Duration::from_secs(11)));
+    }
+}
@@ -15,7 +15,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use crate::config::load_config;
-use ccore::stake::Action::SelfNominate;
+use ccore::stake::Action as StakeAction;
 use ccore::stake::{Banned, Candidates, Jail, CUSTOM_ACTION_HANDLER_ID};
 use ccore::{
     AccountProvider, AccountProviderError, BlockId, ConsensusClient, Encodable, SignedTransaction,
@@ -26,7 +26,7 @@ use ckey::{Address, Public, Signature};
 use ckeystore::DecryptedAccount;
 use clap::ArgMatches;
 use codechain_types::transaction::{Action, Transaction};
-use primitives::{Bytes, H256};
+use primitives::H256;
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
@@ -123,13 +123,13 @@ impl AutoSelfNomination {
             return
         }
         let jailed = Jail::load_from_state(&state).unwrap();
-        if jailed.get_prisoner(&address).is_some() {
-            let prisoner = jailed.get_prisoner(&address).unwrap();
-
-            if prisoner.custody_until <= (current_term) {
+        if let Some(prisoner) = jailed.get_prisoner(&address) {
+            if prisoner.custody_until >= current_term {
                 cwarn!(ENGINE, "Account is still in custody");
                 return
             }
+            AutoSelfNomination::send_stake_transaction(&client, &signer, StakeAction::Unjail, "unjail");
+            return
         }
         let candidate = Candidates::load_from_state(&state).unwrap();
         if candidate.get_candidate(&address).is_some() {
@@ -150,15 +150,14 @@ impl AutoSelfNomination {
             }
         }
 
-        AutoSelfNomination::self_nomination_transaction(&client, &signer, dep, metabytes);
+        let selfnominate = StakeAction::SelfNominate {
+            deposit: dep,
+            metadata: metabytes,
+        };
+        AutoSelfNomination::send_stake_transaction(&client, &signer, selfnominate, "self nominate");
     }
 
-    fn self_nomination_transaction(
-        client: &Arc<dyn ConsensusClient>,
-        signer: &SelfSigner,
-        deposit: u64,
-        metadata: Bytes,
-    ) {
+    fn send_stake_transaction(client: &Arc<dyn ConsensusClient>, signer: &SelfSigner, action: StakeAction, name: &str) {
         let network_id = client.network_id();
         let seq = match signer.address() {
             Some(address) => client.latest_seq(address),
@@ -167,17 +166,13 @@ impl AutoSelfNomination {
                 return
             }
         };
-        let selfnominate = SelfNominate {
-            deposit,
-            metadata,
-        };
         let tx = Transaction {
             seq,
             fee: 0,
             network_id,
             action: Action::Custom {
                 handler_id: CUSTOM_ACTION_HANDLER_ID,
-                bytes: selfnominate.rlp_bytes(),
+                bytes: action.rlp_bytes(),
             },
         };
 
@@ -193,10 +188,10 @@ impl AutoSelfNomination {
 
         match client.queue_own_transaction(signed) {
             Ok(_) => {
-                cinfo!(ENGINE, "Send self nominate transaction");
+                cinfo!(ENGINE, "Send {} transaction", name);
             }
             Err(e) => {
-                cerror!(ENGINE, "Failed to queue self nominate transaction: {}", e);
+                cerror!(ENGINE, "Failed to queue {} transaction: {}", name, e);
             }
         }
     }
@@ -18,8 +18,9 @@ use ccore::Scheme;
 use never_type::Never;
 use serde::de::{Error, Visitor};
 use serde::{Deserialize, Deserializer};
+use std::path::Path;
+use std::fmt;
 use std::str::FromStr;
-use std::{fmt, fs};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum ChainType {
@@ -104,11 +105,7 @@ impl ChainType {
             ChainType::Tendermint => Ok(Scheme::new_test_tendermint()),
             ChainType::Corgi => Ok(Scheme::new_corgi()),
             ChainType::Beagle => Ok(Scheme::new_beagle()),
-            ChainType::Custom(filename) => {
-                let file = fs::File::open(filename)
-                    .map_err(|e| format!("Could not load specification file at {}: {}", filename, e))?;
-                Scheme::load(file)
-            }
+            ChainType::Custom(filename) => Scheme::load_file(Path::new(filename)),
         }
     }
 }
@@ -15,20 +15,38 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 mod chain_type;
+mod preset;
 
-use ccore::{MemPoolMinFees, MinerOptions, TimeGapParams};
+use ccore::{ActionFilter, DiskSpaceThresholds, MemPoolMinFees, MinerOptions, TimeGapParams};
 use cidr::IpCidr;
 use ckey::PlatformAddress;
 use clap;
+use clogger::{LogFileConfig, LogRotation};
 use cnetwork::{FilterEntry, NetworkConfig, SocketAddr};
+use crpc::{RpcLimits, SlowSubscriberPolicy, SubscriptionLimits, TlsConfig};
+use csync::snapshot::{Publisher, S3Target};
 use primitives::H256;
+use std::env;
 use std::fs;
+use std::path::PathBuf;
 use std::str::{self, FromStr};
 use std::time::Duration;
 use toml;
 
 pub use self::chain_type::ChainType;
-use crate::rpc::{RpcHttpConfig, RpcIpcConfig, RpcWsConfig};
+pub use self::preset::Preset;
+use crate::rpc::{RpcAdminConfig, RpcGrpcConfig, RpcHttpConfig, RpcIpcConfig, RpcRestConfig, RpcWsConfig};
+
+const DEFAULT_RPC_BLOCKING_THREADS: usize = 4;
+const DEFAULT_RPC_RESPONSE_CACHE_SIZE: usize = 1024;
+/// In megabytes.
+const DEFAULT_DISK_LOW_THRESHOLD: u64 = 1024;
+/// In megabytes.
+const DEFAULT_DISK_CRITICAL_THRESHOLD: u64 = 256;
+/// In seconds.
+const DEFAULT_INFORMANT_INTERVAL: u64 = 30;
+/// The default ports are shifted by this times the instance id, so each instance has its own range of ports.
+const PORTS_PER_INSTANCE: usize = 10;
 
 #[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -40,9 +58,17 @@ pub struct Config {
     pub network: Network,
     pub rpc: Rpc,
     pub ws: Ws,
+    #[serde(default)]
+    pub grpc: Grpc,
+    #[serde(default)]
+    pub rest: Rest,
+    #[serde(default)]
+    pub admin_rpc: AdminRpc,
     pub snapshot: Snapshot,
     #[serde(default)]
     pub email_alarm: EmailAlarm,
+    #[serde(default)]
+    pub logging: Logging,
 }
 
 impl Config {
@@ -53,8 +79,44 @@ impl Config {
         self.network.merge(&other.network);
         self.rpc.merge(&other.rpc);
         self.ws.merge(&other.ws);
+        self.grpc.merge(&other.grpc);
+        self.rest.merge(&other.rest);
+        self.admin_rpc.merge(&other.admin_rpc);
         self.snapshot.merge(&other.snapshot);
         self.email_alarm.merge(&other.email_alarm);
+        self.logging.merge(&other.logging);
+    }
+
+    /// Derives the default ports and paths of the instance, so several nodes can run on one machine.
+    fn isolate_instance(&mut self, instance_id: Option<usize>, ports_shift: u16) -> Result<(), String> {
+        for port in &mut [
+            &mut self.network.port,
+            &mut self.rpc.port,
+            &mut self.ws.port,
+            &mut self.grpc.port,
+            &mut self.rest.port,
+            &mut self.admin_rpc.port,
+        ] {
+            if let Some(port) = port {
+                *port = port.checked_add(ports_shift).ok_or_else(|| format!("Cannot shift the port {}", port))?;
+            }
+        }
+
+        if let Some(instance_id) = instance_id {
+            if let Some(base_path) = &mut self.operating.base_path {
+                *base_path = format!("{}/instance-{}", base_path, instance_id);
+            }
+            if let Some(path) = &mut self.snapshot.path {
+                *path = format!("{}-{}", path, instance_id);
+            }
+            if let Some(path) = &mut self.ipc.path {
+                *path = match path.rfind('.') {
+                    Some(index) => format!("{}-{}{}", &path[..index], instance_id, &path[index..]),
+                    None => format!("{}-{}", path, instance_id),
+                };
+            }
+        }
+        Ok(())
     }
 
     pub fn miner_options(&self) -> Result<MinerOptions, String> {
@@ -95,12 +157,19 @@ impl Config {
                 mem_size => Some(mem_size * 1024 * 1024),
             },
             mem_pool_fee_bump_shift: self.mining.mem_pool_fee_bump_shift.unwrap(),
+            mem_pool_shard_quota: self.mining.mem_pool_shard_quota,
             allow_create_shard: self.mining.allow_create_shard.unwrap_or(false),
+            mem_pool_action_filter: ActionFilter::new(
+                self.mining.accepted_actions.clone(),
+                self.mining.rejected_actions.clone().unwrap_or_default(),
+            )?,
             reseal_on_own_transaction,
             reseal_on_external_transaction,
             reseal_min_period: Duration::from_millis(self.mining.reseal_min_period.unwrap()),
             no_reseal_timer: self.mining.no_reseal_timer.unwrap(),
             mem_pool_min_fees,
+            max_block_body_size: self.mining.max_block_body_size,
+            max_block_transactions: self.mining.max_block_transactions,
         })
     }
 
@@ -113,9 +182,135 @@ impl Config {
             port: self.rpc.port.unwrap(),
             cors: self.rpc.cors.clone(),
             hosts: self.rpc.hosts.clone(),
+            tls: tls_config(&self.rpc.tls_cert, &self.rpc.tls_key, &self.rpc.tls_client_ca),
+            limits: rpc_limits(
+                self.rpc.max_body_size,
+                self.rpc.max_in_flight_requests,
+                self.rpc.read_timeout,
+                self.rpc.write_timeout,
+            ),
+        }
+    }
+
+    /// The memory limit of the caches and the queues in bytes, if it is given.
+    pub fn memory_budget(&self) -> Option<usize> {
+        self.operating.memory_budget.map(|megabytes| megabytes * 1024 * 1024)
+    }
+
+    /// How often a random historical block is re-executed to verify its state root. Zero disables it.
+    pub fn verify_roots_interval(&self) -> Option<Duration> {
+        match self.operating.verify_roots_interval {
+            None | Some(0) => None,
+            Some(seconds) => Some(Duration::from_secs(seconds)),
         }
     }
 
+    /// Where the snapshots are published. The S3 credentials fall back to the standard AWS environment variables.
+    pub fn snapshot_publisher(&self) -> Result<Option<Publisher>, String> {
+        let snapshot = &self.snapshot;
+        match (&snapshot.ipfs_api, &snapshot.s3_bucket) {
+            (None, None) => Ok(None),
+            (Some(_), Some(_)) => Err("The snapshots can be published to either IPFS or S3, not both".to_string()),
+            (Some(ipfs_api), None) => Ok(Some(Publisher::Ipfs(ipfs_api.clone()))),
+            (None, Some(bucket)) => {
+                let region = snapshot.s3_region.clone().ok_or_else(|| "s3_region is not specified".to_string())?;
+                let endpoint =
+                    snapshot.s3_endpoint.clone().unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region));
+                let access_key = snapshot
+                    .s3_access_key
+                    .clone()
+                    .or_else(|| env::var("AWS_ACCESS_KEY_ID").ok())
+                    .ok_or_else(|| "s3_access_key is not specified".to_string())?;
+                let secret_key = snapshot
+                    .s3_secret_key
+                    .clone()
+                    .or_else(|| env::var("AWS_SECRET_ACCESS_KEY").ok())
+                    .ok_or_else(|| "s3_secret_key is not specified".to_string())?;
+                Ok(Some(Publisher::S3(S3Target {
+                    endpoint,
+                    region,
+                    bucket: bucket.clone(),
+                    access_key,
+                    secret_key,
+                })))
+            }
+        }
+    }
+
+    /// The file the logs are written to and how it's rotated, if it is given.
+    pub fn log_file_config(&self) -> Result<Option<LogFileConfig>, String> {
+        let logging = &self.logging;
+        let path = match &logging.file {
+            Some(path) => PathBuf::from(path),
+            None => return Ok(None),
+        };
+        let rotation = match &logging.rotation {
+            Some(rotation) => rotation.parse()?,
+            None => LogRotation::Never,
+        };
+        Ok(Some(LogFileConfig {
+            path,
+            max_size: logging.max_size.map(|megabytes| megabytes * 1024 * 1024),
+            rotation,
+            compress: logging.compress.unwrap_or(false),
+            max_files: logging.max_files,
+            max_age: logging.max_age.map(|days| Duration::from_secs(days * 24 * 60 * 60)),
+        }))
+    }
+
+    /// How often the summary of the import progress is logged. Zero disables it.
+    pub fn informant_interval(&self) -> Option<Duration> {
+        match self.operating.informant_interval.unwrap_or(DEFAULT_INFORMANT_INTERVAL) {
+            0 => None,
+            seconds => Some(Duration::from_secs(seconds)),
+        }
+    }
+
+    /// The ports of the enabled servers.
+    pub fn listen_ports(&self) -> Vec<ListenPort> {
+        let admin_rpc = &self.admin_rpc;
+        let servers = [
+            ("network.port", "--port", self.network.disable, &self.network.interface, self.network.port),
+            ("rpc.port", "--jsonrpc-port", self.rpc.disable, &self.rpc.interface, self.rpc.port),
+            ("ws.port", "--ws-port", self.ws.disable, &self.ws.interface, self.ws.port),
+            ("grpc.port", "--grpc-port", self.grpc.disable, &self.grpc.interface, self.grpc.port),
+            ("rest.port", "--rest-port", self.rest.disable, &self.rest.interface, self.rest.port),
+            ("admin_rpc.port", "--admin-rpc-port", admin_rpc.disable, &admin_rpc.interface, admin_rpc.port),
+        ];
+        servers
+            .iter()
+            .filter(|(_, _, disable, ..)| !disable.unwrap_or(false))
+            .filter_map(|(key, option, _, interface, port)| {
+                Some(ListenPort {
+                    key: *key,
+                    option: *option,
+                    interface: (*interface).clone()?,
+                    port: (*port)?,
+                })
+            })
+            .collect()
+    }
+
+    /// The free bytes of the disk below which the node refuses new transactions or stops importing blocks.
+    pub fn disk_space_thresholds(&self) -> DiskSpaceThresholds {
+        let low = self.operating.disk_low_threshold.unwrap_or(DEFAULT_DISK_LOW_THRESHOLD);
+        let critical = self.operating.disk_critical_threshold.unwrap_or(DEFAULT_DISK_CRITICAL_THRESHOLD);
+        DiskSpaceThresholds {
+            low: low * 1024 * 1024,
+            critical: critical * 1024 * 1024,
+        }
+    }
+
+    /// The number of the threads reading the blocks and the states for the RPC methods.
+    pub fn rpc_blocking_threads(&self) -> usize {
+        self.rpc.blocking_threads.unwrap_or(DEFAULT_RPC_BLOCKING_THREADS)
+    }
+
+    /// The number of the responses for the finalized blocks kept to answer the same calls.
+    pub fn rpc_response_cache_size(&self) -> usize {
+        self.rpc.response_cache_size.unwrap_or(DEFAULT_RPC_RESPONSE_CACHE_SIZE)
+    }
+
     pub fn rpc_ipc_config(&self) -> RpcIpcConfig {
         debug_assert!(!self.ipc.disable.unwrap());
 
@@ -132,6 +327,51 @@ impl Config {
             interface: self.ws.interface.clone().unwrap(),
             port: self.ws.port.unwrap(),
             max_connections: self.ws.max_connections.unwrap(),
+            tls: tls_config(&self.ws.tls_cert, &self.ws.tls_key, &self.ws.tls_client_ca),
+            limits: rpc_limits(
+                self.ws.max_frame_size,
+                self.ws.max_in_flight_requests,
+                self.ws.read_timeout,
+                self.ws.write_timeout,
+            ),
+            subscription_limits: subscription_limits(
+                self.ws.max_subscriptions_per_connection,
+                self.ws.subscription_buffer,
+                &self.ws.slow_subscriber,
+            ),
+        }
+    }
+
+    pub fn rpc_grpc_config(&self) -> RpcGrpcConfig {
+        debug_assert!(!self.grpc.disable.unwrap());
+
+        RpcGrpcConfig {
+            interface: self.grpc.interface.clone().unwrap(),
+            port: self.grpc.port.unwrap(),
+            subscription_limits: subscription_limits(
+                self.grpc.max_subscriptions_per_connection,
+                self.grpc.subscription_buffer,
+                &self.grpc.slow_subscriber,
+            ),
+        }
+    }
+
+    pub fn rpc_rest_config(&self) -> RpcRestConfig {
+        debug_assert!(!self.rest.disable.unwrap());
+
+        RpcRestConfig {
+            interface: self.rest.interface.clone().unwrap(),
+            port: self.rest.port.unwrap(),
+        }
+    }
+
+    pub fn rpc_admin_config(&self) -> RpcAdminConfig {
+        debug_assert!(!self.admin_rpc.disable.unwrap());
+
+        RpcAdminConfig {
+            interface: self.admin_rpc.interface.clone().unwrap(),
+            port: self.admin_rpc.port.unwrap(),
+            jwt_secret_path: self.admin_rpc.jwt_secret.clone().unwrap(),
         }
     }
 
@@ -168,6 +408,29 @@ impl Config {
             }
         }
 
+        fn make_trusted_peers(list_path: Option<&String>) -> Result<Vec<SocketAddr>, String> {
+            if let Some(path) = list_path {
+                let content = fs::read_to_string(path)
+                    .map_err(|e| format!("Cannot open the trusted peers file {:?}: {:?}", path, e))?;
+                content
+                    .lines()
+                    .map(|s| {
+                        const COMMENT_CHAR: char = '#';
+                        match s.find(COMMENT_CHAR) {
+                            Some(index) => s[..index].trim(),
+                            None => s.trim(),
+                        }
+                    })
+                    .filter(|s| !s.is_empty())
+                    .map(|addr| {
+                        SocketAddr::from_str(addr).map_err(|e| format!("Cannot parse the node id {}: {:?}", addr, e))
+                    })
+                    .collect()
+            } else {
+                Ok(Vec::new())
+            }
+        }
+
         let bootstrap_addresses = self
             .network
             .bootstrap_addresses
@@ -179,6 +442,11 @@ impl Config {
 
         let whitelist = make_ipaddr_list(self.network.whitelist_path.as_ref(), "white")?;
         let blacklist = make_ipaddr_list(self.network.blacklist_path.as_ref(), "black")?;
+        let trusted_peers = if self.network.permissioned.unwrap_or(false) {
+            Some(make_trusted_peers(self.network.trusted_peers_path.as_ref())?)
+        } else {
+            None
+        };
 
         Ok(NetworkConfig {
             address: self.network.interface.clone().unwrap(),
@@ -188,10 +456,23 @@ impl Config {
             max_peers: self.network.max_peers.unwrap(),
             whitelist,
             blacklist,
+            trusted_peers,
+            capture_path: self.network.capture_path.clone(),
         })
     }
 }
 
+/// A port that the node listens on, with the names to change it by.
+#[derive(Clone, Debug)]
+pub struct ListenPort {
+    /// The key in the config file.
+    pub key: &'static str,
+    /// The command line option.
+    pub option: &'static str,
+    pub interface: String,
+    pub port: u16,
+}
+
 #[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Ipc {
@@ -199,16 +480,30 @@ pub struct Ipc {
     pub path: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Default, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Operating {
     pub quiet: Option<bool>,
     pub instance_id: Option<usize>,
+    /// Added to all the ports. It's the instance id times 10 if it's not given.
+    pub ports_shift: Option<u16>,
     pub base_path: Option<String>,
     pub db_path: Option<String>,
     pub keys_path: Option<String>,
     pub password_path: Option<String>,
     pub chain: Option<ChainType>,
+    /// The chain whose engine runs in shadow mode next to the engine of `chain`.
+    pub shadow_chain: Option<ChainType>,
+    /// In megabytes.
+    pub memory_budget: Option<usize>,
+    /// In megabytes.
+    pub disk_low_threshold: Option<u64>,
+    /// In megabytes.
+    pub disk_critical_threshold: Option<u64>,
+    /// In seconds. A random historical block is re-executed every interval to verify its state root.
+    pub verify_roots_interval: Option<u64>,
+    /// In seconds. The summary of the import progress is logged every interval.
+    pub informant_interval: Option<u64>,
 }
 
 #[derive(Deserialize)]
@@ -216,6 +511,8 @@ pub struct Operating {
 pub struct Mining {
     pub author: Option<PlatformAddress>,
     pub engine_signer: Option<PlatformAddress>,
+    /// The validator whose session key the engine signer is. Its key can be kept offline.
+    pub validator_owner: Option<PlatformAddress>,
     pub mem_pool_size: Option<usize>,
     pub mem_pool_mem_limit: Option<usize>,
     pub self_nomination_metadata: Option<String>,
@@ -223,12 +520,25 @@ pub struct Mining {
     pub self_nomination_enable: bool,
     pub self_nomination_interval: Option<u64>,
     pub mem_pool_fee_bump_shift: Option<usize>,
+    pub mem_pool_shard_quota: Option<usize>,
     pub allow_create_shard: Option<bool>,
+    /// The action types that the mem pool accepts and relays, such as "pay". All types are accepted if it's not set.
+    pub accepted_actions: Option<Vec<String>>,
+    /// The types of the actions that the mem pool refuses. The blocks including them are still imported.
+    pub rejected_actions: Option<Vec<String>>,
     pub reseal_on_txs: Option<String>,
     pub reseal_min_period: Option<u64>,
     pub no_reseal_timer: Option<bool>,
+    /// The text placed in the extra data of the authored blocks.
+    pub extra_data: Option<String>,
+    /// In bytes.
+    pub max_block_body_size: Option<usize>,
+    pub max_block_transactions: Option<usize>,
     pub allowed_past_gap: Option<u64>,
     pub allowed_future_gap: Option<u64>,
+    /// In milliseconds.
+    pub max_clock_drift: Option<u64>,
+    /// The minimum tips over the base fees for the external transactions.
     pub min_pay_transaction_cost: Option<u64>,
     pub min_set_regular_key_transaction_cost: Option<u64>,
     pub min_create_shard_transaction_cost: Option<u64>,
@@ -256,12 +566,30 @@ pub struct Network {
     pub snapshot_hash: Option<H256>,
     pub snapshot_number: Option<u64>,
     pub transaction_relay: Option<bool>,
+    /// Announces the new blocks with the short ids of their transactions to the peers that support it.
+    pub compact_block_relay: Option<bool>,
+    /// Makes a checkpoint signed by the validators at every given number of blocks. Checkpoints are not made if it's 0.
+    pub checkpoint_interval: Option<u64>,
+    /// Answers the requests of light clients with the headers and the proofs.
+    pub light_server: Option<bool>,
     pub discovery: Option<bool>,
     pub discovery_type: Option<String>,
     pub discovery_refresh: Option<u32>,
     pub discovery_bucket_size: Option<u8>,
+    /// Exchanges the addresses of the established peers with the connected peers periodically.
+    pub pex: Option<bool>,
+    /// In milliseconds.
+    pub pex_interval: Option<u32>,
+    /// The number of the addresses exchanged at once.
+    pub pex_max_addresses: Option<u8>,
     pub blacklist_path: Option<String>,
     pub whitelist_path: Option<String>,
+    /// Only the trusted peers can establish sessions if it's true.
+    pub permissioned: Option<bool>,
+    /// The file listing the node ids, `<ip>:<port>`, of the trusted peers.
+    pub trusted_peers_path: Option<String>,
+    /// The file to which the inbound messages of the extensions are recorded.
+    pub capture_path: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -274,6 +602,17 @@ pub struct Rpc {
     pub port: Option<u16>,
     #[serde(default = "default_enable_devel_api")]
     pub enable_devel_api: bool,
+    pub tls_cert: Option<String>,
+    pub tls_key: Option<String>,
+    pub tls_client_ca: Option<String>,
+    pub max_body_size: Option<usize>,
+    pub max_in_flight_requests: Option<usize>,
+    /// In seconds.
+    pub read_timeout: Option<u64>,
+    /// In seconds.
+    pub write_timeout: Option<u64>,
+    pub blocking_threads: Option<usize>,
+    pub response_cache_size: Option<usize>,
 }
 
 #[derive(Deserialize)]
@@ -283,12 +622,118 @@ pub struct Ws {
     pub interface: Option<String>,
     pub port: Option<u16>,
     pub max_connections: Option<usize>,
+    pub tls_cert: Option<String>,
+    pub tls_key: Option<String>,
+    pub tls_client_ca: Option<String>,
+    pub max_frame_size: Option<usize>,
+    pub max_in_flight_requests: Option<usize>,
+    /// In seconds.
+    pub read_timeout: Option<u64>,
+    /// In seconds.
+    pub write_timeout: Option<u64>,
+    pub max_subscriptions_per_connection: Option<usize>,
+    pub subscription_buffer: Option<usize>,
+    /// `drop` or `disconnect`.
+    pub slow_subscriber: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Grpc {
+    pub disable: Option<bool>,
+    pub interface: Option<String>,
+    pub port: Option<u16>,
+    pub max_subscriptions_per_connection: Option<usize>,
+    pub subscription_buffer: Option<usize>,
+    /// `drop` or `disconnect`.
+    pub slow_subscriber: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rest {
+    pub disable: Option<bool>,
+    pub interface: Option<String>,
+    pub port: Option<u16>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdminRpc {
+    pub disable: Option<bool>,
+    pub interface: Option<String>,
+    pub port: Option<u16>,
+    /// The file having the hex encoded secret of the JWTs. It's created if it doesn't exist.
+    pub jwt_secret: Option<String>,
 }
 
 fn default_enable_devel_api() -> bool {
     cfg!(debug_assertions)
 }
 
+fn tls_config(cert: &Option<String>, key: &Option<String>, client_ca: &Option<String>) -> Option<TlsConfig> {
+    match (cert, key) {
+        (Some(cert), Some(key)) => Some(TlsConfig {
+            cert_path: cert.into(),
+            key_path: key.into(),
+            client_ca_path: client_ca.as_ref().map(Into::into),
+        }),
+        _ => None,
+    }
+}
+
+/// The limits not given are the defaults.
+fn rpc_limits(
+    max_payload_size: Option<usize>,
+    max_in_flight_requests: Option<usize>,
+    read_timeout: Option<u64>,
+    write_timeout: Option<u64>,
+) -> RpcLimits {
+    let default = RpcLimits::default();
+    RpcLimits {
+        max_payload_size: max_payload_size.unwrap_or(default.max_payload_size),
+        max_in_flight_requests: max_in_flight_requests.unwrap_or(default.max_in_flight_requests),
+        read_timeout: read_timeout.map_or(default.read_timeout, Duration::from_secs),
+        write_timeout: write_timeout.map_or(default.write_timeout, Duration::from_secs),
+    }
+}
+
+fn subscription_limits(
+    max_subscriptions_per_connection: Option<usize>,
+    buffer: Option<usize>,
+    slow_subscriber: &Option<String>,
+) -> SubscriptionLimits {
+    let default = SubscriptionLimits::default();
+    SubscriptionLimits {
+        max_subscriptions_per_connection: max_subscriptions_per_connection
+            .unwrap_or(default.max_subscriptions_per_connection),
+        buffer: buffer.unwrap_or(default.buffer),
+        slow_subscriber: slow_subscriber
+            .as_ref()
+            .map_or(default.slow_subscriber, |policy| policy.parse().expect("Validated in overwrite_with")),
+    }
+}
+
+fn validate_subscriptions(buffer: Option<usize>, slow_subscriber: &Option<String>) -> Result<(), String> {
+    if buffer == Some(0) {
+        return Err("The subscription buffer must hold at least one block".to_string())
+    }
+    if let Some(slow_subscriber) = slow_subscriber {
+        slow_subscriber.parse::<SlowSubscriberPolicy>()?;
+    }
+    Ok(())
+}
+
+fn validate_tls(cert: &Option<String>, key: &Option<String>, client_ca: &Option<String>) -> Result<(), String> {
+    if cert.is_some() != key.is_some() {
+        return Err("Both the TLS certificate and the TLS key are required to use TLS".to_string())
+    }
+    if client_ca.is_some() && cert.is_none() {
+        return Err("The TLS client CA requires the TLS certificate and the TLS key".to_string())
+    }
+    Ok(())
+}
+
 #[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Snapshot {
@@ -296,6 +741,13 @@ pub struct Snapshot {
     pub path: Option<String>,
     // Snapshot's age in blocks
     pub expiration: Option<u64>,
+    /// The URL of the HTTP API of the IPFS node the snapshots are published to.
+    pub ipfs_api: Option<String>,
+    pub s3_endpoint: Option<String>,
+    pub s3_region: Option<String>,
+    pub s3_bucket: Option<String>,
+    pub s3_access_key: Option<String>,
+    pub s3_secret_key: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -306,6 +758,22 @@ pub struct EmailAlarm {
     pub sendgrid_key: Option<String>,
 }
 
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Logging {
+    /// The logs are written to this file as well as to stderr.
+    pub file: Option<String>,
+    /// In megabytes.
+    pub max_size: Option<u64>,
+    /// never, hourly or daily
+    pub rotation: Option<String>,
+    pub compress: Option<bool>,
+    /// The number of the rotated files kept.
+    pub max_files: Option<usize>,
+    /// In days.
+    pub max_age: Option<u64>,
+}
+
 impl Ipc {
     pub fn merge(&mut self, other: &Ipc) {
         if other.disable.is_some() {
@@ -335,6 +803,9 @@ impl Operating {
         if other.instance_id.is_some() {
             self.instance_id = other.instance_id;
         }
+        if other.ports_shift.is_some() {
+            self.ports_shift = other.ports_shift;
+        }
         if other.base_path.is_some() {
             self.base_path = other.base_path.clone();
         }
@@ -350,6 +821,36 @@ impl Operating {
         if other.chain.is_some() {
             self.chain = other.chain.clone();
         }
+        if other.shadow_chain.is_some() {
+            self.shadow_chain = other.shadow_chain.clone();
+        }
+        if other.memory_budget.is_some() {
+            self.memory_budget = other.memory_budget;
+        }
+        if other.disk_low_threshold.is_some() {
+            self.disk_low_threshold = other.disk_low_threshold;
+        }
+        if other.disk_critical_threshold.is_some() {
+            self.disk_critical_threshold = other.disk_critical_threshold;
+        }
+        if other.verify_roots_interval.is_some() {
+            self.verify_roots_interval = other.verify_roots_interval;
+        }
+        if other.informant_interval.is_some() {
+            self.informant_interval = other.informant_interval;
+        }
+    }
+
+    fn ports_shift(&self) -> Result<u16, String> {
+        match (self.ports_shift, self.instance_id) {
+            (Some(ports_shift), _) => Ok(ports_shift),
+            (None, Some(instance_id)) => instance_id
+                .checked_mul(PORTS_PER_INSTANCE)
+                .filter(|shift| *shift <= std::u16::MAX as usize)
+                .map(|shift| shift as u16)
+                .ok_or_else(|| format!("The instance id {} is too large to shift the ports", instance_id)),
+            (None, None) => Ok(0),
+        }
     }
 
     pub fn overwrite_with(&mut self, matches: &clap::ArgMatches<'_>) -> Result<(), String> {
@@ -359,6 +860,9 @@ impl Operating {
         if let Some(instance_id) = matches.value_of("instance-id") {
             self.instance_id = Some(instance_id.parse().map_err(|e| format!("{}", e))?);
         }
+        if let Some(ports_shift) = matches.value_of("ports-shift") {
+            self.ports_shift = Some(ports_shift.parse().map_err(|_| "Invalid ports shift")?);
+        }
         if let Some(base_path) = matches.value_of("base-path") {
             self.base_path = Some(base_path.to_string());
         }
@@ -374,6 +878,24 @@ impl Operating {
         if let Some(chain) = matches.value_of("chain") {
             self.chain = Some(chain.parse().unwrap());
         }
+        if let Some(chain) = matches.value_of("shadow-chain") {
+            self.shadow_chain = Some(chain.parse().unwrap());
+        }
+        if let Some(memory_budget) = matches.value_of("memory-budget") {
+            self.memory_budget = Some(memory_budget.parse().map_err(|_| "Invalid memory budget")?);
+        }
+        if let Some(threshold) = matches.value_of("disk-low-threshold") {
+            self.disk_low_threshold = Some(threshold.parse().map_err(|_| "Invalid disk low threshold")?);
+        }
+        if let Some(threshold) = matches.value_of("disk-critical-threshold") {
+            self.disk_critical_threshold = Some(threshold.parse().map_err(|_| "Invalid disk critical threshold")?);
+        }
+        if let Some(interval) = matches.value_of("verify-roots-interval") {
+            self.verify_roots_interval = Some(interval.parse().map_err(|_| "Invalid verify roots interval")?);
+        }
+        if let Some(interval) = matches.value_of("informant-interval") {
+            self.informant_interval = Some(interval.parse().map_err(|_| "Invalid informant interval")?);
+        }
         Ok(())
     }
 }
@@ -387,6 +909,9 @@ impl Mining {
         if other.engine_signer.is_some() {
             self.engine_signer = other.engine_signer;
         }
+        if other.validator_owner.is_some() {
+            self.validator_owner = other.validator_owner;
+        }
         if other.self_nomination_metadata.is_some() {
             self.self_nomination_metadata = other.self_nomination_metadata.clone();
         }
@@ -402,9 +927,18 @@ impl Mining {
         if other.mem_pool_mem_limit.is_some() {
             self.mem_pool_mem_limit = other.mem_pool_mem_limit;
         }
+        if other.mem_pool_shard_quota.is_some() {
+            self.mem_pool_shard_quota = other.mem_pool_shard_quota;
+        }
         if other.allow_create_shard.is_some() {
             self.allow_create_shard = other.allow_create_shard;
         }
+        if other.accepted_actions.is_some() {
+            self.accepted_actions = other.accepted_actions.clone();
+        }
+        if other.rejected_actions.is_some() {
+            self.rejected_actions = other.rejected_actions.clone();
+        }
         if other.reseal_on_txs.is_some() {
             self.reseal_on_txs = other.reseal_on_txs.clone();
         }
@@ -414,6 +948,18 @@ impl Mining {
         if other.no_reseal_timer.is_some() {
             self.no_reseal_timer = other.no_reseal_timer;
         }
+        if other.extra_data.is_some() {
+            self.extra_data = other.extra_data.clone();
+        }
+        if other.max_block_body_size.is_some() {
+            self.max_block_body_size = other.max_block_body_size;
+        }
+        if other.max_block_transactions.is_some() {
+            self.max_block_transactions = other.max_block_transactions;
+        }
+        if other.max_clock_drift.is_some() {
+            self.max_clock_drift = other.max_clock_drift;
+        }
         if other.min_pay_transaction_cost.is_some() {
             self.min_pay_transaction_cost = other.min_pay_transaction_cost;
         }
@@ -459,6 +1005,9 @@ impl Mining {
         if let Some(engine_signer) = matches.value_of("engine-signer") {
             self.engine_signer = Some(engine_signer.parse().map_err(|_| "Invalid address format")?);
         }
+        if let Some(validator_owner) = matches.value_of("validator-owner") {
+            self.validator_owner = Some(validator_owner.parse().map_err(|_| "Invalid address format")?);
+        }
         if let Some(self_nomination_metadata) = matches.value_of("self-nomination-metadata") {
             self.self_nomination_metadata =
                 Some(self_nomination_metadata.parse().map_err(|_| "Invalid self nomination metadata format")?);
@@ -478,6 +1027,7 @@ impl Mining {
         if matches.is_present("no-miner") {
             self.author = None;
             self.engine_signer = None;
+            self.validator_owner = None;
             println!("This option was deprecated. PBFT type engine with no author implicitly means no-miner.");
         }
         if let Some(mem_pool_fee_bump_shift) = matches.value_of("mem-pool-fee-bump-shift") {
@@ -490,9 +1040,18 @@ impl Mining {
         if let Some(mem_pool_size) = matches.value_of("mem-pool-size") {
             self.mem_pool_size = Some(mem_pool_size.parse().map_err(|_| "Invalid size")?);
         }
+        if let Some(mem_pool_shard_quota) = matches.value_of("mem-pool-shard-quota") {
+            self.mem_pool_shard_quota = Some(mem_pool_shard_quota.parse().map_err(|_| "Invalid shard quota")?);
+        }
         if matches.is_present("allow-create-shard") {
             self.allow_create_shard = Some(true)
         }
+        if let Some(accepted_actions) = matches.values_of_lossy("accepted-actions") {
+            self.accepted_actions = Some(accepted_actions);
+        }
+        if let Some(rejected_actions) = matches.values_of_lossy("rejected-actions") {
+            self.rejected_actions = Some(rejected_actions);
+        }
         if let Some(reseal_on_txs) = matches.value_of("reseal-on-txs") {
             self.reseal_on_txs = Some(reseal_on_txs.to_string());
         }
@@ -502,22 +1061,38 @@ impl Mining {
         if matches.is_present("no-reseal-timer") {
             self.no_reseal_timer = Some(true);
         }
+        if let Some(extra_data) = matches.value_of("extra-data") {
+            self.extra_data = Some(extra_data.to_string());
+        }
+        if let Some(max_block_body_size) = matches.value_of("max-block-body-size") {
+            self.max_block_body_size =
+                Some(max_block_body_size.parse().map_err(|_| "Invalid max block body size")?);
+        }
+        if let Some(max_block_transactions) = matches.value_of("max-block-transactions") {
+            self.max_block_transactions =
+                Some(max_block_transactions.parse().map_err(|_| "Invalid max block transactions")?);
+        }
         if let Some(allowed_past_gap) = matches.value_of("allowed-past-gap") {
             self.allowed_past_gap = Some(allowed_past_gap.parse().map_err(|_| "Invalid time gap")?);
         }
         if let Some(allowed_future_gap) = matches.value_of("allowed-future-gap") {
             self.allowed_future_gap = Some(allowed_future_gap.parse().map_err(|_| "Invalid time gap")?);
         }
+        if let Some(max_clock_drift) = matches.value_of("max-clock-drift") {
+            self.max_clock_drift = Some(max_clock_drift.parse().map_err(|_| "Invalid max clock drift")?);
+        }
         Ok(())
     }
 
     pub fn create_time_gaps(&self) -> TimeGapParams {
         let allowed_past_gap = Duration::from_millis(self.allowed_past_gap.unwrap_or(30000));
         let allowed_future_gap = Duration::from_millis(self.allowed_future_gap.unwrap_or(5000));
+        let max_clock_drift = Duration::from_millis(self.max_clock_drift.unwrap_or(3000));
 
         TimeGapParams {
             allowed_past_gap,
             allowed_future_gap,
+            max_clock_drift,
         }
     }
 }
@@ -554,6 +1129,15 @@ impl Network {
         if other.transaction_relay.is_some() {
             self.transaction_relay = other.transaction_relay;
         }
+        if other.compact_block_relay.is_some() {
+            self.compact_block_relay = other.compact_block_relay;
+        }
+        if other.checkpoint_interval.is_some() {
+            self.checkpoint_interval = other.checkpoint_interval;
+        }
+        if other.light_server.is_some() {
+            self.light_server = other.light_server;
+        }
         if other.discovery.is_some() {
             self.discovery = other.discovery;
         }
@@ -566,12 +1150,30 @@ impl Network {
         if other.discovery_bucket_size.is_some() {
             self.discovery_bucket_size = other.discovery_bucket_size;
         }
+        if other.pex.is_some() {
+            self.pex = other.pex;
+        }
+        if other.pex_interval.is_some() {
+            self.pex_interval = other.pex_interval;
+        }
+        if other.pex_max_addresses.is_some() {
+            self.pex_max_addresses = other.pex_max_addresses;
+        }
         if other.blacklist_path.is_some() {
             self.blacklist_path = other.blacklist_path.clone();
         }
         if other.whitelist_path.is_some() {
             self.whitelist_path = other.whitelist_path.clone();
         }
+        if other.permissioned.is_some() {
+            self.permissioned = other.permissioned;
+        }
+        if other.trusted_peers_path.is_some() {
+            self.trusted_peers_path = other.trusted_peers_path.clone();
+        }
+        if other.capture_path.is_some() {
+            self.capture_path = other.capture_path.clone();
+        }
     }
 
     pub fn overwrite_with(&mut self, matches: &clap::ArgMatches<'_>) -> Result<(), String> {
@@ -612,6 +1214,16 @@ impl Network {
         if matches.is_present("no-tx-relay") {
             self.transaction_relay = Some(false);
         }
+        if matches.is_present("no-compact-block-relay") {
+            self.compact_block_relay = Some(false);
+        }
+        if let Some(checkpoint_interval) = matches.value_of("checkpoint-interval") {
+            self.checkpoint_interval =
+                Some(checkpoint_interval.parse().map_err(|_| "Invalid checkpoint interval".to_string())?);
+        }
+        if matches.is_present("light-server") {
+            self.light_server = Some(true);
+        }
 
         if matches.is_present("no-discovery") {
             self.discovery = Some(false);
@@ -625,6 +1237,15 @@ impl Network {
         if let Some(bucket_size) = matches.value_of("discovery-bucket-size") {
             self.discovery_bucket_size = Some(bucket_size.parse().map_err(|_| "Invalid discovery-bucket-size")?);
         }
+        if matches.is_present("no-pex") {
+            self.pex = Some(false);
+        }
+        if let Some(interval) = matches.value_of("pex-interval") {
+            self.pex_interval = Some(interval.parse().map_err(|_| "Invalid pex-interval")?);
+        }
+        if let Some(max_addresses) = matches.value_of("pex-max-addresses") {
+            self.pex_max_addresses = Some(max_addresses.parse().map_err(|_| "Invalid pex-max-addresses")?);
+        }
 
         if let Some(file_path) = matches.value_of("whitelist-path") {
             self.whitelist_path = Some(file_path.to_string());
@@ -632,6 +1253,15 @@ impl Network {
         if let Some(file_path) = matches.value_of("blacklist-path") {
             self.blacklist_path = Some(file_path.to_string());
         }
+        if matches.is_present("permissioned") {
+            self.permissioned = Some(true);
+        }
+        if let Some(file_path) = matches.value_of("trusted-peers-path") {
+            self.trusted_peers_path = Some(file_path.to_string());
+        }
+        if let Some(file_path) = matches.value_of("network-capture-path") {
+            self.capture_path = Some(file_path.to_string());
+        }
 
         Ok(())
     }
@@ -654,6 +1284,33 @@ impl Rpc {
         if other.port.is_some() {
             self.port = other.port;
         }
+        if other.tls_cert.is_some() {
+            self.tls_cert = other.tls_cert.clone();
+        }
+        if other.tls_key.is_some() {
+            self.tls_key = other.tls_key.clone();
+        }
+        if other.tls_client_ca.is_some() {
+            self.tls_client_ca = other.tls_client_ca.clone();
+        }
+        if other.max_body_size.is_some() {
+            self.max_body_size = other.max_body_size;
+        }
+        if other.max_in_flight_requests.is_some() {
+            self.max_in_flight_requests = other.max_in_flight_requests;
+        }
+        if other.read_timeout.is_some() {
+            self.read_timeout = other.read_timeout;
+        }
+        if other.write_timeout.is_some() {
+            self.write_timeout = other.write_timeout;
+        }
+        if other.blocking_threads.is_some() {
+            self.blocking_threads = other.blocking_threads;
+        }
+        if other.response_cache_size.is_some() {
+            self.response_cache_size = other.response_cache_size;
+        }
     }
 
     pub fn overwrite_with(&mut self, matches: &clap::ArgMatches<'_>) -> Result<(), String> {
@@ -675,7 +1332,35 @@ impl Rpc {
         if matches.is_present("enable-devel-api") {
             self.enable_devel_api = true;
         }
-        Ok(())
+        if let Some(tls_cert) = matches.value_of("jsonrpc-tls-cert") {
+            self.tls_cert = Some(tls_cert.to_string());
+        }
+        if let Some(tls_key) = matches.value_of("jsonrpc-tls-key") {
+            self.tls_key = Some(tls_key.to_string());
+        }
+        if let Some(tls_client_ca) = matches.value_of("jsonrpc-tls-client-ca") {
+            self.tls_client_ca = Some(tls_client_ca.to_string());
+        }
+        if let Some(max_body_size) = matches.value_of("jsonrpc-max-body-size") {
+            self.max_body_size = Some(max_body_size.parse().map_err(|_| "Invalid max body size")?);
+        }
+        if let Some(max_in_flight_requests) = matches.value_of("jsonrpc-max-in-flight") {
+            self.max_in_flight_requests =
+                Some(max_in_flight_requests.parse().map_err(|_| "Invalid max in-flight requests")?);
+        }
+        if let Some(read_timeout) = matches.value_of("jsonrpc-read-timeout") {
+            self.read_timeout = Some(read_timeout.parse().map_err(|_| "Invalid read timeout")?);
+        }
+        if let Some(blocking_threads) = matches.value_of("jsonrpc-blocking-threads") {
+            self.blocking_threads = Some(blocking_threads.parse().map_err(|_| "Invalid blocking threads")?);
+        }
+        if let Some(response_cache_size) = matches.value_of("jsonrpc-cache-size") {
+            self.response_cache_size = Some(response_cache_size.parse().map_err(|_| "Invalid response cache size")?);
+        }
+        if let Some(write_timeout) = matches.value_of("jsonrpc-write-timeout") {
+            self.write_timeout = Some(write_timeout.parse().map_err(|_| "Invalid write timeout")?);
+        }
+        validate_tls(&self.tls_cert, &self.tls_key, &self.tls_client_ca)
     }
 }
 
@@ -693,6 +1378,36 @@ impl Ws {
         if other.max_connections.is_some() {
             self.max_connections = other.max_connections;
         }
+        if other.tls_cert.is_some() {
+            self.tls_cert = other.tls_cert.clone();
+        }
+        if other.tls_key.is_some() {
+            self.tls_key = other.tls_key.clone();
+        }
+        if other.tls_client_ca.is_some() {
+            self.tls_client_ca = other.tls_client_ca.clone();
+        }
+        if other.max_frame_size.is_some() {
+            self.max_frame_size = other.max_frame_size;
+        }
+        if other.max_in_flight_requests.is_some() {
+            self.max_in_flight_requests = other.max_in_flight_requests;
+        }
+        if other.read_timeout.is_some() {
+            self.read_timeout = other.read_timeout;
+        }
+        if other.write_timeout.is_some() {
+            self.write_timeout = other.write_timeout;
+        }
+        if other.max_subscriptions_per_connection.is_some() {
+            self.max_subscriptions_per_connection = other.max_subscriptions_per_connection;
+        }
+        if other.subscription_buffer.is_some() {
+            self.subscription_buffer = other.subscription_buffer;
+        }
+        if other.slow_subscriber.is_some() {
+            self.slow_subscriber = other.slow_subscriber.clone();
+        }
     }
 
     pub fn overwrite_with(&mut self, matches: &clap::ArgMatches<'_>) -> Result<(), String> {
@@ -709,6 +1424,151 @@ impl Ws {
         if let Some(max_connections) = matches.value_of("ws-max-connections") {
             self.max_connections = Some(max_connections.parse().map_err(|_| "Invalid max connections")?);
         }
+        if let Some(tls_cert) = matches.value_of("ws-tls-cert") {
+            self.tls_cert = Some(tls_cert.to_string());
+        }
+        if let Some(tls_key) = matches.value_of("ws-tls-key") {
+            self.tls_key = Some(tls_key.to_string());
+        }
+        if let Some(tls_client_ca) = matches.value_of("ws-tls-client-ca") {
+            self.tls_client_ca = Some(tls_client_ca.to_string());
+        }
+        if let Some(max_frame_size) = matches.value_of("ws-max-frame-size") {
+            self.max_frame_size = Some(max_frame_size.parse().map_err(|_| "Invalid max frame size")?);
+        }
+        if let Some(max_in_flight_requests) = matches.value_of("ws-max-in-flight") {
+            self.max_in_flight_requests =
+                Some(max_in_flight_requests.parse().map_err(|_| "Invalid max in-flight requests")?);
+        }
+        if let Some(read_timeout) = matches.value_of("ws-read-timeout") {
+            self.read_timeout = Some(read_timeout.parse().map_err(|_| "Invalid read timeout")?);
+        }
+        if let Some(write_timeout) = matches.value_of("ws-write-timeout") {
+            self.write_timeout = Some(write_timeout.parse().map_err(|_| "Invalid write timeout")?);
+        }
+        if let Some(max_subscriptions) = matches.value_of("ws-max-subscriptions") {
+            self.max_subscriptions_per_connection =
+                Some(max_subscriptions.parse().map_err(|_| "Invalid max subscriptions")?);
+        }
+        if let Some(subscription_buffer) = matches.value_of("ws-subscription-buffer") {
+            self.subscription_buffer = Some(subscription_buffer.parse().map_err(|_| "Invalid subscription buffer")?);
+        }
+        if let Some(slow_subscriber) = matches.value_of("ws-slow-subscriber") {
+            self.slow_subscriber = Some(slow_subscriber.to_string());
+        }
+        validate_subscriptions(self.subscription_buffer, &self.slow_subscriber)?;
+        validate_tls(&self.tls_cert, &self.tls_key, &self.tls_client_ca)
+    }
+}
+
+impl Grpc {
+    pub fn merge(&mut self, other: &Grpc) {
+        if other.disable.is_some() {
+            self.disable = other.disable;
+        }
+        if other.interface.is_some() {
+            self.interface = other.interface.clone();
+        }
+        if other.port.is_some() {
+            self.port = other.port;
+        }
+        if other.max_subscriptions_per_connection.is_some() {
+            self.max_subscriptions_per_connection = other.max_subscriptions_per_connection;
+        }
+        if other.subscription_buffer.is_some() {
+            self.subscription_buffer = other.subscription_buffer;
+        }
+        if other.slow_subscriber.is_some() {
+            self.slow_subscriber = other.slow_subscriber.clone();
+        }
+    }
+
+    pub fn overwrite_with(&mut self, matches: &clap::ArgMatches<'_>) -> Result<(), String> {
+        if matches.is_present("no-grpc") {
+            self.disable = Some(true);
+        }
+
+        if let Some(interface) = matches.value_of("grpc-interface") {
+            self.interface = Some(interface.to_string());
+        }
+        if let Some(port) = matches.value_of("grpc-port") {
+            self.port = Some(port.parse().map_err(|_| "Invalid port")?);
+        }
+        if let Some(max_subscriptions) = matches.value_of("grpc-max-subscriptions") {
+            self.max_subscriptions_per_connection =
+                Some(max_subscriptions.parse().map_err(|_| "Invalid max subscriptions")?);
+        }
+        if let Some(subscription_buffer) = matches.value_of("grpc-subscription-buffer") {
+            self.subscription_buffer = Some(subscription_buffer.parse().map_err(|_| "Invalid subscription buffer")?);
+        }
+        if let Some(slow_subscriber) = matches.value_of("grpc-slow-subscriber") {
+            self.slow_subscriber = Some(slow_subscriber.to_string());
+        }
+        validate_subscriptions(self.subscription_buffer, &self.slow_subscriber)
+    }
+}
+
+impl Rest {
+    pub fn merge(&mut self, other: &Rest) {
+        if other.disable.is_some() {
+            self.disable = other.disable;
+        }
+        if other.interface.is_some() {
+            self.interface = other.interface.clone();
+        }
+        if other.port.is_some() {
+            self.port = other.port;
+        }
+    }
+
+    pub fn overwrite_with(&mut self, matches: &clap::ArgMatches<'_>) -> Result<(), String> {
+        if matches.is_present("no-rest") {
+            self.disable = Some(true);
+        }
+
+        if let Some(interface) = matches.value_of("rest-interface") {
+            self.interface = Some(interface.to_string());
+        }
+        if let Some(port) = matches.value_of("rest-port") {
+            self.port = Some(port.parse().map_err(|_| "Invalid port")?);
+        }
+        Ok(())
+    }
+}
+
+impl AdminRpc {
+    pub fn merge(&mut self, other: &AdminRpc) {
+        if other.disable.is_some() {
+            self.disable = other.disable;
+        }
+        if other.interface.is_some() {
+            self.interface = other.interface.clone();
+        }
+        if other.port.is_some() {
+            self.port = other.port;
+        }
+        if other.jwt_secret.is_some() {
+            self.jwt_secret = other.jwt_secret.clone();
+        }
+    }
+
+    pub fn overwrite_with(&mut self, matches: &clap::ArgMatches<'_>) -> Result<(), String> {
+        if matches.is_present("no-admin-rpc") {
+            self.disable = Some(true);
+        }
+        if matches.is_present("admin-rpc") {
+            self.disable = Some(false);
+        }
+
+        if let Some(interface) = matches.value_of("admin-rpc-interface") {
+            self.interface = Some(interface.to_string());
+        }
+        if let Some(port) = matches.value_of("admin-rpc-port") {
+            self.port = Some(port.parse().map_err(|_| "Invalid port")?);
+        }
+        if let Some(jwt_secret) = matches.value_of("admin-rpc-jwt-secret") {
+            self.jwt_secret = Some(jwt_secret.to_string());
+        }
         Ok(())
     }
 }
@@ -724,6 +1584,24 @@ impl Snapshot {
         if other.expiration.is_some() {
             self.expiration = other.expiration;
         }
+        if other.ipfs_api.is_some() {
+            self.ipfs_api = other.ipfs_api.clone();
+        }
+        if other.s3_endpoint.is_some() {
+            self.s3_endpoint = other.s3_endpoint.clone();
+        }
+        if other.s3_region.is_some() {
+            self.s3_region = other.s3_region.clone();
+        }
+        if other.s3_bucket.is_some() {
+            self.s3_bucket = other.s3_bucket.clone();
+        }
+        if other.s3_access_key.is_some() {
+            self.s3_access_key = other.s3_access_key.clone();
+        }
+        if other.s3_secret_key.is_some() {
+            self.s3_secret_key = other.s3_secret_key.clone();
+        }
     }
 
     pub fn overwrite_with(&mut self, matches: &clap::ArgMatches<'_>) -> Result<(), String> {
@@ -734,6 +1612,18 @@ impl Snapshot {
         if let Some(snapshot_path) = matches.value_of("snapshot-path") {
             self.path = Some(snapshot_path.to_string());
         }
+        if let Some(ipfs_api) = matches.value_of("snapshot-ipfs-api") {
+            self.ipfs_api = Some(ipfs_api.to_string());
+        }
+        if let Some(endpoint) = matches.value_of("snapshot-s3-endpoint") {
+            self.s3_endpoint = Some(endpoint.to_string());
+        }
+        if let Some(region) = matches.value_of("snapshot-s3-region") {
+            self.s3_region = Some(region.to_string());
+        }
+        if let Some(bucket) = matches.value_of("snapshot-s3-bucket") {
+            self.s3_bucket = Some(bucket.to_string());
+        }
         Ok(())
     }
 }
@@ -766,6 +1656,40 @@ impl EmailAlarm {
     }
 }
 
+impl Logging {
+    pub fn merge(&mut self, other: &Logging) {
+        if other.file.is_some() {
+            self.file = other.file.clone();
+        }
+        if other.max_size.is_some() {
+            self.max_size = other.max_size;
+        }
+        if other.rotation.is_some() {
+            self.rotation = other.rotation.clone();
+        }
+        if other.compress.is_some() {
+            self.compress = other.compress;
+        }
+        if other.max_files.is_some() {
+            self.max_files = other.max_files;
+        }
+        if other.max_age.is_some() {
+            self.max_age = other.max_age;
+        }
+    }
+
+    pub fn overwrite_with(&mut self, matches: &clap::ArgMatches<'_>) -> Result<(), String> {
+        if let Some(file) = matches.value_of("log-file") {
+            self.file = Some(file.to_string());
+        }
+        if let Some(rotation) = matches.value_of("log-rotation") {
+            rotation.parse::<LogRotation>()?;
+            self.rotation = Some(rotation.to_string());
+        }
+        Ok(())
+    }
+}
+
 impl Default for EmailAlarm {
     fn default() -> Self {
         Self {
@@ -793,21 +1717,92 @@ pub fn load_config(matches: &clap::ArgMatches<'_>) -> Result<Config, String> {
         let toml_string = read_preset_config().to_string();
         toml::from_str(toml_string.as_ref()).expect("The preset config file must be valid")
     };
+    if let Some(preset) = matches.value_of("preset") {
+        let preset: Preset = preset.parse()?;
+        let preset_config: Config = toml::from_str(preset.toml()).expect("The preset config file must be valid");
+        config.merge(&preset_config);
+    }
 
-    if let Some(config_path) = matches.value_of("config") {
-        let toml_string = fs::read_to_string(config_path).map_err(|e| format!("Fail to read file: {:?}", e))?;
-        let extra_config: Config =
-            toml::from_str(toml_string.as_ref()).map_err(|e| format!("Error while parsing TOML: {:?}", e))?;
-        config.merge(&extra_config);
+    let extra_config = match matches.value_of("config") {
+        Some(config_path) => {
+            let toml_string = fs::read_to_string(config_path).map_err(|e| format!("Fail to read file: {:?}", e))?;
+            let extra_config: Config =
+                toml::from_str(toml_string.as_ref()).map_err(|e| format!("Error while parsing TOML: {:?}", e))?;
+            Some(extra_config)
+        }
+        None => None,
     };
 
+    // Only the preset values are derived from the instance id, so the values given explicitly are kept.
+    let mut instance = Operating::default();
+    if let Some(extra_config) = &extra_config {
+        instance.merge(&extra_config.operating);
+    }
+    instance.overwrite_with(&matches)?;
+    let ports_shift = instance.ports_shift()?;
+    config.isolate_instance(instance.instance_id, ports_shift)?;
+
+    if let Some(extra_config) = &extra_config {
+        config.merge(extra_config);
+    }
+
     config.ipc.overwrite_with(&matches)?;
     config.operating.overwrite_with(&matches)?;
     config.mining.overwrite_with(&matches)?;
     config.network.overwrite_with(&matches)?;
     config.rpc.overwrite_with(&matches)?;
     config.ws.overwrite_with(&matches)?;
+    config.grpc.overwrite_with(&matches)?;
+    config.rest.overwrite_with(&matches)?;
+    config.admin_rpc.overwrite_with(&matches)?;
     config.snapshot.overwrite_with(&matches)?;
     config.email_alarm.overwrite_with(&matches)?;
+    config.logging.overwrite_with(&matches)?;
     Ok(config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dev_config() -> Config {
+        toml::from_str(include_str!("presets/config.dev.toml")).unwrap()
+    }
+
+    fn instance(instance_id: Option<usize>, ports_shift: Option<u16>) -> Operating {
+        Operating {
+            instance_id,
+            ports_shift,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn instance_gets_its_own_ports_and_paths() {
+        let mut config = dev_config();
+        let ports_shift = instance(Some(2), None).ports_shift().unwrap();
+        config.isolate_instance(Some(2), ports_shift).unwrap();
+        assert_eq!(Some(3505), config.network.port);
+        assert_eq!(Some(8100), config.rpc.port);
+        assert_eq!(Some(8101), config.ws.port);
+        assert_eq!(Some(8102), config.grpc.port);
+        assert_eq!(Some(8103), config.rest.port);
+        assert_eq!(Some(8104), config.admin_rpc.port);
+        assert_eq!(Some("./instance-2"), config.operating.base_path.as_ref().map(String::as_str));
+        assert_eq!(Some("snapshot-2"), config.snapshot.path.as_ref().map(String::as_str));
+        assert_eq!(Some("/tmp/jsonrpc-2.ipc"), config.ipc.path.as_ref().map(String::as_str));
+    }
+
+    #[test]
+    fn ports_shift_overrides_the_instance_id() {
+        assert_eq!(Ok(5), instance(Some(2), Some(5)).ports_shift());
+        assert_eq!(Ok(0), instance(None, None).ports_shift());
+        assert!(instance(Some(7000), None).ports_shift().is_err());
+
+        let mut config = dev_config();
+        config.isolate_instance(None, 5).unwrap();
+        assert_eq!(Some(3490), config.network.port);
+        assert_eq!(Some("."), config.operating.base_path.as_ref().map(String::as_str));
+        assert!(dev_config().isolate_instance(None, 60000).is_err());
+    }
+}
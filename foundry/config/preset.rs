@@ -0,0 +1,85 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The defaults for the role of the node. They are applied over the base preset and under the config file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Preset {
+    Validator,
+    RpcProvider,
+    Archive,
+    Light,
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "validator" => Ok(Preset::Validator),
+            "rpc-provider" => Ok(Preset::RpcProvider),
+            "archive" => Ok(Preset::Archive),
+            "light" => Ok(Preset::Light),
+            other => {
+                Err(format!("{} is not a preset. Possible values are validator, rpc-provider, archive, light", other))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Preset::Validator => "validator",
+            Preset::RpcProvider => "rpc-provider",
+            Preset::Archive => "archive",
+            Preset::Light => "light",
+        })
+    }
+}
+
+impl Preset {
+    pub fn toml(self) -> &'static str {
+        match self {
+            Preset::Validator => include_str!("presets/validator.toml"),
+            Preset::RpcProvider => include_str!("presets/rpc-provider.toml"),
+            Preset::Archive => include_str!("presets/archive.toml"),
+            Preset::Light => include_str!("presets/light.toml"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Config;
+    use super::*;
+
+    #[test]
+    fn presets_are_valid() {
+        let base_presets = [include_str!("presets/config.prod.toml"), include_str!("presets/config.dev.toml")];
+        for toml_string in &base_presets {
+            assert!(toml::from_str::<Config>(toml_string).is_ok());
+        }
+        for preset in &[Preset::Validator, Preset::RpcProvider, Preset::Archive, Preset::Light] {
+            if let Err(e) = toml::from_str::<Config>(preset.toml()) {
+                panic!("The {} preset is invalid: {}", preset, e);
+            }
+            assert_eq!(Ok(*preset), preset.to_string().parse());
+        }
+    }
+}
@@ -16,3 +16,5 @@
 
 pub const DEFAULT_KEYS_PATH: &str = "keys";
 pub const DEFAULT_DB_PATH: &str = "db";
+/// The directory under the base path that holds a directory for each chain.
+pub const CHAINS_PATH: &str = "chains";
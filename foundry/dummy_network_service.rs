@@ -16,7 +16,7 @@
 
 use cidr::IpCidr;
 use ckey::Public;
-use cnetwork::{FilterEntry, NetworkControl, NetworkControlError, SocketAddr};
+use cnetwork::{ChaosConfig, FilterEntry, NetworkControl, NetworkControlError, SocketAddr};
 use std::collections::HashMap;
 use std::net::IpAddr;
 
@@ -106,7 +106,27 @@ impl NetworkControl for DummyNetworkService {
         Err(NetworkControlError::Disabled)
     }
 
+    fn add_trusted_peer(&self, _addr: SocketAddr) -> Result<(), NetworkControlError> {
+        Err(NetworkControlError::Disabled)
+    }
+
+    fn remove_trusted_peer(&self, _addr: &SocketAddr) -> Result<(), NetworkControlError> {
+        Err(NetworkControlError::Disabled)
+    }
+
+    fn get_trusted_peers(&self) -> Result<(Vec<SocketAddr>, bool), NetworkControlError> {
+        Err(NetworkControlError::Disabled)
+    }
+
     fn recent_network_usage(&self) -> Result<HashMap<String, usize>, NetworkControlError> {
         Err(NetworkControlError::Disabled)
     }
+
+    fn set_chaos(&self, _config: ChaosConfig) -> Result<(), NetworkControlError> {
+        Err(NetworkControlError::Disabled)
+    }
+
+    fn chaos(&self) -> Result<ChaosConfig, NetworkControlError> {
+        Err(NetworkControlError::Disabled)
+    }
 }
@@ -26,6 +26,7 @@ extern crate codechain_key as ckey;
 extern crate codechain_keystore as ckeystore;
 #[macro_use]
 extern crate codechain_logger as clogger;
+extern crate codechain_memory as cmemory;
 extern crate codechain_network as cnetwork;
 extern crate codechain_rpc as crpc;
 extern crate codechain_sync as csync;
@@ -38,6 +39,7 @@ mod config;
 mod constants;
 mod dummy_network_service;
 mod json;
+mod ports;
 mod rpc;
 mod rpc_apis;
 mod run_node;
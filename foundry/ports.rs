@@ -0,0 +1,101 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Probes the listen ports before the servers start, so that all the conflicts are reported at once instead of the
+//! first one failing deep inside a transport.
+
+use crate::config::ListenPort;
+use std::collections::HashMap;
+use std::net::TcpListener;
+
+/// The number of the ports after a taken one that are probed to suggest a free one.
+const SUGGESTION_RANGE: u16 = 100;
+
+pub fn check_ports(ports: &[ListenPort]) -> Result<(), String> {
+    let mut owners: HashMap<u16, &ListenPort> = HashMap::new();
+    let mut conflicts = Vec::new();
+    for port in ports {
+        if let Some(owner) = owners.get(&port.port) {
+            conflicts.push((port, format!("it's also {}", owner.key)));
+            continue
+        }
+        owners.insert(port.port, port);
+        if let Err(e) = TcpListener::bind((port.interface.as_str(), port.port)) {
+            conflicts.push((port, e.to_string()));
+        }
+    }
+    if conflicts.is_empty() {
+        return Ok(())
+    }
+
+    let mut taken: Vec<u16> = ports.iter().map(|port| port.port).collect();
+    let mut lines = vec!["Cannot listen on the configured ports:".to_string()];
+    for (port, reason) in conflicts {
+        let mut line = format!("  {} = {} on {} ({})", port.key, port.port, port.interface, reason);
+        if let Some(free) = find_free_port(&port.interface, port.port, &taken) {
+            line += &format!(". Try {} {}", port.option, free);
+            taken.push(free);
+        }
+        lines.push(line);
+    }
+    lines.push("Or move all the ports at once with --ports-shift.".to_string());
+    Err(lines.join("\n"))
+}
+
+fn find_free_port(interface: &str, from: u16, taken: &[u16]) -> Option<u16> {
+    (1..=SUGGESTION_RANGE)
+        .filter_map(|offset| from.checked_add(offset))
+        .filter(|port| !taken.contains(port))
+        .find(|port| TcpListener::bind((interface, *port)).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listen_port(key: &'static str, option: &'static str, port: u16) -> ListenPort {
+        ListenPort {
+            key,
+            option,
+            interface: "127.0.0.1".to_string(),
+            port,
+        }
+    }
+
+    #[test]
+    fn all_the_conflicts_are_reported() {
+        let busy = TcpListener::bind("127.0.0.1:0").unwrap();
+        let busy_port = busy.local_addr().unwrap().port();
+        let free_port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+        let ports = [
+            listen_port("network.port", "--port", busy_port),
+            listen_port("rpc.port", "--jsonrpc-port", free_port),
+            listen_port("ws.port", "--ws-port", free_port),
+        ];
+        let message = check_ports(&ports).unwrap_err();
+        assert!(message.contains(&format!("network.port = {} on 127.0.0.1", busy_port)), "{}", message);
+        assert!(message.contains(&format!("ws.port = {} on 127.0.0.1 (it's also rpc.port)", free_port)), "{}", message);
+        assert!(!message.contains("rpc.port ="), "{}", message);
+        assert!(message.contains(". Try --ws-port "), "{}", message);
+    }
+
+    #[test]
+    fn free_ports_pass() {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        assert_eq!(Ok(()), check_ports(&[listen_port("rpc.port", "--jsonrpc-port", port)]));
+    }
+}
@@ -16,12 +16,54 @@
 
 use crate::config::Config;
 use crate::rpc_apis;
+use ccore::Client;
+use crpc::v1::{ChainClient, MempoolClient};
 use crpc::{
-    jsonrpc_core, start_http, start_ipc, start_ws, HttpServer, IpcServer, MetaIoHandler, Middleware, WsError, WsServer,
+    jsonrpc_core, start_authenticated_http, start_grpc, start_http, start_ipc, start_relay, start_rest, start_tls,
+    start_ws, start_ws_notify, AdminFilter, BlockingPool, Connection, GrpcServer, HttpServer, InFlightLimit, IpcServer,
+    JwtSecret, MetaIoHandler, Middleware, RelayServer, RequestIdMiddleware, RestServer, RpcLimits, SubscriptionLimits,
+    TlsConfig, WsError, WsNotify, WsServer, WsSubscriptions,
 };
 use futures::future::Either;
 use serde_json;
 use std::io;
+use std::iter;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+/// The namespaces served only to the clients authenticated with a certificate when the TLS client CA is given.
+const ADMIN_NAMESPACES: &[&str] = &["account_", "devel_", "net_"];
+
+/// The methods switching the namespaces, which are served only through the IPC and the admin channel.
+const API_SWITCH_METHODS: &[&str] = &["devel_enableApi", "devel_disableApi"];
+
+/// The methods served only through the admin channel when it's enabled.
+const ADMIN_CHANNEL_METHODS: &[&str] = &[
+    "devel_",
+    "net_localKeyFor",
+    "net_registerRemoteKeyFor",
+    "net_connect",
+    "net_disconnect",
+    "net_addToWhitelist",
+    "net_removeFromWhitelist",
+    "net_addToBlacklist",
+    "net_removeFromBlacklist",
+    "net_enableWhitelist",
+    "net_disableWhitelist",
+    "net_enableBlacklist",
+    "net_disableBlacklist",
+    "net_addTrustedPeer",
+    "net_removeTrustedPeer",
+    "account_create",
+    "account_importRaw",
+    "account_unlock",
+    "account_changePassword",
+    "account_sign",
+    "account_signTypedData",
+    "account_sendTransaction",
+    "mempool_cancelTransaction",
+];
 
 #[derive(Debug, PartialEq)]
 pub struct RpcHttpConfig {
@@ -29,31 +71,78 @@ pub struct RpcHttpConfig {
     pub port: u16,
     pub cors: Option<Vec<String>>,
     pub hosts: Option<Vec<String>>,
+    pub tls: Option<TlsConfig>,
+    pub limits: RpcLimits,
+}
+
+/// The HTTP server, and the relay server in front of it.
+pub struct RpcHttpServer {
+    server: HttpServer,
+    admin_server: Option<HttpServer>,
+    relay_server: RelayServer,
+}
+
+impl RpcHttpServer {
+    pub fn close(self) {
+        self.relay_server.close();
+        for server in iter::once(self.server).chain(self.admin_server) {
+            server.close_handle().close();
+            server.wait();
+        }
+    }
 }
 
 pub fn rpc_http_start(
-    server: MetaIoHandler<(), impl Middleware<()>>,
+    server: MetaIoHandler<Connection, impl Middleware<Connection>>,
+    admin_server: Option<MetaIoHandler<Connection, impl Middleware<Connection>>>,
     config: RpcHttpConfig,
-) -> Result<HttpServer, String> {
+) -> Result<RpcHttpServer, String> {
     let url = format!("{}:{}", config.interface, config.port);
     let addr = url.parse().map_err(|_| format!("Invalid JSONRPC listen host/port given: {}", url))?;
-    let start_result = start_http(&addr, config.cors.clone(), config.hosts.clone(), server);
-    match start_result {
-        Err(ref err) if err.kind() == io::ErrorKind::AddrInUse => {
-            Err(format!("RPC address {} is already in use, make sure that another instance of a CodeChain node is not running or change the address using the --jsonrpc-port option.", url))
-        },
-        Err(e) => Err(format!("RPC error: {:?}", e)),
-        Ok(server) => {
-            cinfo!(RPC, "RPC Listening on {}", url);
-            if let Some(hosts) = config.hosts {
-                cinfo!(RPC, "Allowed hosts are {:?}", hosts);
-            }
-            if let Some(cors) = config.cors {
-                cinfo!(RPC, "CORS domains are {:?}", cors);
-            }
-            Ok(server)
-        },
+    let max_body_size = config.limits.max_payload_size;
+    // The plain servers are reachable only through the relay server.
+    let server = start_http(&loopback(), config.cors.clone(), config.hosts.clone(), max_body_size, server)
+        .map_err(|err| http_start_error(&url, err))?;
+    let (admin_server, relay_server) = match &config.tls {
+        None => {
+            (None, start_relay(&addr, *server.address(), &config.limits).map_err(|err| http_start_error(&url, err))?)
+        }
+        Some(tls) => {
+            let admin_server = admin_server
+                .map(|admin_server| {
+                    start_http(&loopback(), config.cors.clone(), config.hosts.clone(), max_body_size, admin_server)
+                })
+                .transpose()
+                .map_err(|err| http_start_error(&url, err))?;
+            let relay_server = start_tls(
+                &addr,
+                tls,
+                *server.address(),
+                admin_server.as_ref().map(|server| *server.address()),
+                &config.limits,
+            )
+            .map_err(|err| http_start_error(&url, err))?;
+            (admin_server, relay_server)
+        }
+    };
+    cinfo!(RPC, "RPC Listening on {}", url);
+    if config.tls.is_some() {
+        cinfo!(RPC, "RPC is served over HTTPS");
     }
+    if admin_server.is_some() {
+        cinfo!(RPC, "The {:?} namespaces require a client certificate", ADMIN_NAMESPACES);
+    }
+    if let Some(hosts) = config.hosts {
+        cinfo!(RPC, "Allowed hosts are {:?}", hosts);
+    }
+    if let Some(cors) = config.cors {
+        cinfo!(RPC, "CORS domains are {:?}", cors);
+    }
+    Ok(RpcHttpServer {
+        server,
+        admin_server,
+        relay_server,
+    })
 }
 
 #[derive(Debug, PartialEq)]
@@ -62,7 +151,7 @@ pub struct RpcIpcConfig {
 }
 
 pub fn rpc_ipc_start(
-    server: MetaIoHandler<(), impl Middleware<()>>,
+    server: MetaIoHandler<Connection, impl Middleware<Connection>>,
     config: RpcIpcConfig,
 ) -> Result<IpcServer, String> {
     let start_result = start_ipc(&config.socket_addr, server);
@@ -83,26 +172,251 @@ pub struct RpcWsConfig {
     pub interface: String,
     pub port: u16,
     pub max_connections: usize,
+    pub tls: Option<TlsConfig>,
+    pub limits: RpcLimits,
+    pub subscription_limits: SubscriptionLimits,
 }
 
-pub fn rpc_ws_start(server: MetaIoHandler<(), impl Middleware<()>>, config: RpcWsConfig) -> Result<WsServer, String> {
+/// The WebSockets server, the relay server in front of it, and the thread notifying the blocks to the subscriptions.
+pub struct RpcWsServer {
+    server: WsServer,
+    admin_server: Option<WsServer>,
+    relay_server: RelayServer,
+    notify: WsNotify,
+}
+
+impl RpcWsServer {
+    pub fn close(self) -> Result<(), String> {
+        self.relay_server.close();
+        for server in iter::once(self.server).chain(self.admin_server) {
+            server.close_handle().close();
+            server.wait().map_err(|err| format!("Error while closing jsonrpc ws server: {}", err))?;
+        }
+        self.notify.close();
+        Ok(())
+    }
+}
+
+/// The number of the block imports waiting to be notified to the WebSockets subscriptions.
+const WS_EVENT_BUFFER: usize = 1024;
+
+pub fn rpc_ws_start(
+    client: Arc<Client>,
+    server: MetaIoHandler<Connection, impl Middleware<Connection>>,
+    admin_server: Option<MetaIoHandler<Connection, impl Middleware<Connection>>>,
+    config: RpcWsConfig,
+) -> Result<RpcWsServer, String> {
+    let url = format!("{}:{}", config.interface, config.port);
+    let addr: SocketAddr = url.parse().map_err(|_| format!("Invalid WebSockets listen host/port given: {}", url))?;
+    let max_frame_size = config.limits.max_payload_size;
+    // The sessions of both servers share the subscriptions.
+    let subscriptions = Arc::new(WsSubscriptions::new(config.subscription_limits));
+    // The plain servers are reachable only through the relay server.
+    let server = start_ws(&loopback(), server, config.max_connections, max_frame_size, &subscriptions)
+        .map_err(|err| ws_start_error(&addr, err))?;
+    let (admin_server, relay_server) = match &config.tls {
+        None => (
+            None,
+            start_relay(&addr, *server.addr(), &config.limits)
+                .map_err(|err| ws_start_error(&addr, WsError::Io(err)))?,
+        ),
+        Some(tls) => {
+            let admin_server = admin_server
+                .map(|admin_server| {
+                    start_ws(&loopback(), admin_server, config.max_connections, max_frame_size, &subscriptions)
+                })
+                .transpose()
+                .map_err(|err| ws_start_error(&addr, err))?;
+            let relay_server = start_tls(
+                &addr,
+                tls,
+                *server.addr(),
+                admin_server.as_ref().map(|server| *server.addr()),
+                &config.limits,
+            )
+            .map_err(|err| ws_start_error(&addr, WsError::Io(err)))?;
+            (admin_server, relay_server)
+        }
+    };
+    let events = client.events().subscribe(WS_EVENT_BUFFER);
+    let notify =
+        start_ws_notify(client, events, subscriptions).map_err(|err| ws_start_error(&addr, WsError::Io(err)))?;
+    cinfo!(RPC, "WebSockets Listening on {}", addr);
+    if config.tls.is_some() {
+        cinfo!(RPC, "WebSockets is served over WSS");
+    }
+    if admin_server.is_some() {
+        cinfo!(RPC, "The {:?} namespaces require a client certificate", ADMIN_NAMESPACES);
+    }
+    Ok(RpcWsServer {
+        server,
+        admin_server,
+        relay_server,
+        notify,
+    })
+}
+
+fn http_start_error(url: &str, err: io::Error) -> String {
+    if err.kind() == io::ErrorKind::AddrInUse {
+        format!("RPC address {} is already in use, make sure that another instance of a CodeChain node is not running or change the address using the --jsonrpc-port option.", url)
+    } else {
+        format!("RPC error: {:?}", err)
+    }
+}
+
+fn ws_start_error(addr: &SocketAddr, err: WsError) -> String {
+    match err {
+        WsError::Io(ref err) if err.kind() == io::ErrorKind::AddrInUse => {
+            format!("WebSockets address {} is already in use, make sure that another instance of a Codechain node is not running or change the address using the --ws-port options.", addr)
+        }
+        err => format!("WebSockets error: {:?}", err),
+    }
+}
+
+/// An address on the loopback interface whose port is chosen by the OS.
+fn loopback() -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], 0))
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RpcGrpcConfig {
+    pub interface: String,
+    pub port: u16,
+    pub subscription_limits: SubscriptionLimits,
+}
+
+/// The number of the block imports waiting to be streamed to the gRPC subscribers.
+const GRPC_EVENT_BUFFER: usize = 1024;
+
+pub fn rpc_grpc_start(client: Arc<Client>, config: RpcGrpcConfig) -> Result<GrpcServer, String> {
     let url = format!("{}:{}", config.interface, config.port);
-    let addr = url.parse().map_err(|_| format!("Invalid WebSockets listen host/port given: {}", url))?;
-    let start_result = start_ws(&addr, server, config.max_connections);
+    let addr = url.parse().map_err(|_| format!("Invalid gRPC listen host/port given: {}", url))?;
+    let events = client.events().subscribe(GRPC_EVENT_BUFFER);
+    let start_result = start_grpc(&addr, client, events, config.subscription_limits);
     match start_result {
-        Err(WsError::Io(ref err)) if err.kind() == io::ErrorKind::AddrInUse => {
-            Err(format!("WebSockets address {} is already in use, make sure that another instance of a Codechain node is not running or change the address using the --ws-port options.", addr))
+        Err(ref err) if err.kind() == io::ErrorKind::AddrInUse => {
+            Err(format!("gRPC address {} is already in use, make sure that another instance of a Codechain node is not running or change the address using the --grpc-port options.", addr))
+        },
+        Err(e) => Err(format!("gRPC error: {:?}", e)),
+        Ok(server) => {
+            cinfo!(RPC, "gRPC Listening on {}", addr);
+            Ok(server)
+        },
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RpcRestConfig {
+    pub interface: String,
+    pub port: u16,
+}
+
+pub fn rpc_rest_start(
+    client: Arc<Client>,
+    blocking_pool: BlockingPool,
+    config: RpcRestConfig,
+) -> Result<RestServer, String> {
+    let url = format!("{}:{}", config.interface, config.port);
+    let addr = url.parse().map_err(|_| format!("Invalid REST listen host/port given: {}", url))?;
+    let start_result = start_rest(
+        &addr,
+        ChainClient::new(Arc::clone(&client), blocking_pool),
+        MempoolClient::new(Arc::clone(&client)),
+        client,
+    );
+    match start_result {
+        Err(ref err) if err.kind() == io::ErrorKind::AddrInUse => {
+            Err(format!("REST address {} is already in use, make sure that another instance of a Codechain node is not running or change the address using the --rest-port options.", addr))
+        },
+        Err(e) => Err(format!("REST error: {:?}", e)),
+        Ok(server) => {
+            cinfo!(RPC, "REST Listening on {}", addr);
+            Ok(server)
+        },
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RpcAdminConfig {
+    pub interface: String,
+    pub port: u16,
+    pub jwt_secret_path: String,
+}
+
+pub fn rpc_admin_start(
+    server: MetaIoHandler<Connection, impl Middleware<Connection>>,
+    config: RpcAdminConfig,
+) -> Result<HttpServer, String> {
+    let url = format!("{}:{}", config.interface, config.port);
+    let addr = url.parse().map_err(|_| format!("Invalid admin JSONRPC listen host/port given: {}", url))?;
+    let secret = JwtSecret::load_or_create(Path::new(&config.jwt_secret_path))
+        .map_err(|err| format!("Cannot load the JWT secret {}: {}", config.jwt_secret_path, err))?;
+    let start_result = start_authenticated_http(&addr, secret, server);
+    match start_result {
+        Err(ref err) if err.kind() == io::ErrorKind::AddrInUse => {
+            Err(format!("Admin RPC address {} is already in use, make sure that another instance of a Codechain node is not running or change the address using the --admin-rpc-port options.", addr))
         },
-        Err(e) => Err(format!("WebSockets error: {:?}", e)),
+        Err(e) => Err(format!("Admin RPC error: {:?}", e)),
         Ok(server) => {
-            cinfo!(RPC, "WebSockets Listening on {}", addr);
+            cinfo!(RPC, "Admin RPC Listening on {}", addr);
+            cinfo!(RPC, "The JWT secret is in {}", config.jwt_secret_path);
             Ok(server)
         },
     }
 }
 
-pub fn setup_rpc_server(config: &Config, deps: &rpc_apis::ApiDependencies) -> MetaIoHandler<(), impl Middleware<()>> {
-    let mut handler = MetaIoHandler::with_middleware(LogMiddleware::new());
+/// Sets up the handler serving all the methods, for the IPC and the admin channel.
+pub fn setup_rpc_server(
+    config: &Config,
+    deps: &rpc_apis::ApiDependencies,
+) -> MetaIoHandler<Connection, impl Middleware<Connection>> {
+    // Their clients are trusted, so the calls in flight are not limited.
+    setup_filtered_rpc_server(config, deps, AdminFilter::none(), usize::max_value())
+}
+
+/// Sets up the handlers of a server which may be behind the TLS server.
+/// If the TLS server authenticates the clients, the second handler serves the authenticated clients
+/// and the first one rejects the admin namespaces.
+pub fn setup_tls_rpc_servers(
+    config: &Config,
+    deps: &rpc_apis::ApiDependencies,
+    tls: Option<&TlsConfig>,
+    limits: &RpcLimits,
+) -> (
+    MetaIoHandler<Connection, impl Middleware<Connection>>,
+    Option<MetaIoHandler<Connection, impl Middleware<Connection>>>,
+) {
+    let max_in_flight_requests = limits.max_in_flight_requests;
+    if tls.map_or(false, |tls| tls.client_ca_path.is_some()) {
+        let filter = public_filter(config).require_client_certificate(ADMIN_NAMESPACES);
+        let server = setup_filtered_rpc_server(config, deps, filter, max_in_flight_requests);
+        let admin_server = setup_filtered_rpc_server(config, deps, public_filter(config), max_in_flight_requests);
+        (server, Some(admin_server))
+    } else {
+        (setup_filtered_rpc_server(config, deps, public_filter(config), max_in_flight_requests), None)
+    }
+}
+
+/// The methods of the admin channel are not served by the other servers while it's enabled.
+fn public_filter(config: &Config) -> AdminFilter {
+    if config.admin_rpc.disable.unwrap() {
+        AdminFilter::none().require_admin_channel(API_SWITCH_METHODS)
+    } else {
+        AdminFilter::none().require_admin_channel(ADMIN_CHANNEL_METHODS)
+    }
+}
+
+fn setup_filtered_rpc_server(
+    config: &Config,
+    deps: &rpc_apis::ApiDependencies,
+    filter: AdminFilter,
+    max_in_flight_requests: usize,
+) -> MetaIoHandler<Connection, impl Middleware<Connection>> {
+    // The calls are logged after the request id is attached to the logs.
+    let logging = (RequestIdMiddleware, LogMiddleware::new());
+    // A disabled namespace is rejected before its cached responses are served.
+    let middleware = (filter, (deps.api_switch.clone(), deps.response_cache.clone()));
+    let mut handler = MetaIoHandler::with_middleware((InFlightLimit::new(max_in_flight_requests), logging, middleware));
     deps.extend_api(config, &mut handler);
     rpc_apis::setup_rpc(handler)
 }
@@ -113,19 +427,12 @@ impl<M: jsonrpc_core::Metadata> jsonrpc_core::Middleware<M> for LogMiddleware {
     type Future = jsonrpc_core::FutureResponse;
     type CallFuture = jsonrpc_core::FutureOutput;
 
-    fn on_request<F, X>(&self, request: jsonrpc_core::Request, meta: M, next: F) -> Either<Self::Future, X>
+    fn on_call<F, X>(&self, call: jsonrpc_core::Call, meta: M, next: F) -> Either<Self::CallFuture, X>
     where
-        F: FnOnce(jsonrpc_core::Request, M) -> X + Send,
-        X: futures::Future<Item = Option<jsonrpc_core::Response>, Error = ()> + Send + 'static, {
-        match &request {
-            jsonrpc_core::Request::Single(call) => Self::print_call(call),
-            jsonrpc_core::Request::Batch(calls) => {
-                for call in calls {
-                    Self::print_call(call);
-                }
-            }
-        }
-        Either::B(next(request, meta))
+        F: FnOnce(jsonrpc_core::Call, M) -> X + Send,
+        X: futures::Future<Item = Option<jsonrpc_core::Output>, Error = ()> + Send + 'static, {
+        Self::print_call(&call);
+        Either::B(next(call, meta))
     }
 }
 
@@ -151,3 +458,35 @@ impl LogMiddleware {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc_core::{Params, Value};
+
+    /// The error code of the response to the method on the public handler, if any.
+    fn rejection_code(method: &str) -> Option<i64> {
+        let filter = AdminFilter::none().require_admin_channel(ADMIN_CHANNEL_METHODS);
+        let mut handler = MetaIoHandler::with_middleware(filter);
+        handler.add_method(method, |_params: Params| Ok(Value::Null));
+        let request = serde_json::json!({"jsonrpc": "2.0", "method": method, "params": [], "id": 1});
+        let response = handler.handle_request_sync(&request.to_string(), ()).unwrap();
+        let response: Value = serde_json::from_str(&response).unwrap();
+        response["error"]["code"].as_i64()
+    }
+
+    #[test]
+    fn methods_using_unlocked_keys_require_the_admin_channel() {
+        assert_eq!(Some(-32051), rejection_code("account_sign"));
+        assert_eq!(Some(-32051), rejection_code("account_signTypedData"));
+        assert_eq!(Some(-32051), rejection_code("account_sendTransaction"));
+        assert_eq!(None, rejection_code("account_getList"));
+        assert_eq!(None, rejection_code("mempool_sendSignedTransaction"));
+    }
+
+    #[test]
+    fn cancel_requires_the_admin_channel() {
+        // The transactions sent through the public servers are local, so anyone could cancel them.
+        assert_eq!(Some(-32051), rejection_code("mempool_cancelTransaction"));
+    }
+}
@@ -15,11 +15,12 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use crate::config::Config;
-use ccore::{AccountProvider, Client, Miner};
+use ccore::{AccountProvider, Client, EngineInfo, Miner};
 use clogger::SLOGGER;
 use cnetwork::{EventSender, NetworkControl};
-use crpc::{MetaIoHandler, Middleware, Params, Value};
-use csync::BlockSyncEvent;
+use crpc::v1::types::{NodeFeatures, NodeInfo};
+use crpc::{ApiSwitch, BlockingPool, MetaIoHandler, Metadata, Middleware, Params, ResponseCache, Value};
+use csync::{BlockSyncEvent, CheckpointEvent};
 use std::sync::Arc;
 
 pub struct ApiDependencies {
@@ -28,30 +29,116 @@ pub struct ApiDependencies {
     pub network_control: Arc<dyn NetworkControl>,
     pub account_provider: Arc<AccountProvider>,
     pub block_sync: Option<EventSender<BlockSyncEvent>>,
+    pub checkpoint: Option<EventSender<CheckpointEvent>>,
+    pub blocking_pool: BlockingPool,
+    pub response_cache: ResponseCache,
+    pub api_switch: ApiSwitch,
+}
+
+/// The namespaces served regardless of the config.
+const NAMESPACES: &[&str] =
+    &["chain", "mempool", "snapshot", "engine", "gov", "net", "rpc", "ibc", "account", "shard", "stake", "client"];
+
+/// The switch of the namespaces served by the node. `devel` is disabled unless the config enables it.
+pub fn api_switch(config: &Config, checkpoint: bool) -> ApiSwitch {
+    let switch = ApiSwitch::default();
+    for namespace in NAMESPACES {
+        switch.register(namespace, true);
+    }
+    switch.register("devel", config.rpc.enable_devel_api);
+    if checkpoint {
+        switch.register("checkpoint", true);
+    }
+    switch
 }
 
 impl ApiDependencies {
-    pub fn extend_api(&self, config: &Config, handler: &mut MetaIoHandler<(), impl Middleware<()>>) {
+    pub fn extend_api<M: Metadata>(&self, config: &Config, handler: &mut MetaIoHandler<M, impl Middleware<M>>) {
         use crpc::v1::*;
-        handler.extend_with(ChainClient::new(Arc::clone(&self.client)).to_delegate());
+        handler.extend_with(ChainClient::new(Arc::clone(&self.client), self.blocking_pool.clone()).to_delegate());
         handler.extend_with(MempoolClient::new(Arc::clone(&self.client)).to_delegate());
         handler.extend_with(SnapshotClient::new(Arc::clone(&self.client), config.snapshot.path.clone()).to_delegate());
-        if config.rpc.enable_devel_api {
-            handler.extend_with(
-                DevelClient::new(Arc::clone(&self.client), Arc::clone(&self.miner), self.block_sync.clone())
-                    .to_delegate(),
-            );
-        }
-        handler.extend_with(EngineClient::new(Arc::clone(&self.client), Arc::clone(&self.miner)).to_delegate());
+        // It's served even if it's disabled, so that the operator can enable it later.
+        handler.extend_with(
+            DevelClient::new(
+                Arc::clone(&self.client),
+                Arc::clone(&self.miner),
+                self.block_sync.clone(),
+                Some(Arc::clone(&self.network_control)),
+                self.api_switch.clone(),
+                self.blocking_pool.clone(),
+            )
+            .to_delegate(),
+        );
+        handler.extend_with(
+            EngineClient::new(
+                Arc::clone(&self.account_provider),
+                Arc::clone(&self.client),
+                Arc::clone(&self.miner),
+                self.blocking_pool.clone(),
+            )
+            .to_delegate(),
+        );
+        handler.extend_with(
+            GovernanceClient::new(
+                Arc::clone(&self.account_provider),
+                Arc::clone(&self.client),
+                Arc::clone(&self.miner),
+                self.blocking_pool.clone(),
+            )
+            .to_delegate(),
+        );
         handler.extend_with(NetClient::new(Arc::clone(&self.network_control)).to_delegate());
+        handler.extend_with(DiscoveryClient::new().to_delegate());
+        handler.extend_with(IbcClient::new(Arc::clone(&self.client)).to_delegate());
+        if let Some(checkpoint) = &self.checkpoint {
+            handler.extend_with(CheckpointClient::new(checkpoint.clone()).to_delegate());
+        }
         handler.extend_with(
             AccountClient::new(Arc::clone(&self.account_provider), Arc::clone(&self.client), Arc::clone(&self.miner))
                 .to_delegate(),
         );
+        handler.extend_with(
+            ShardClient::new(
+                Arc::clone(&self.account_provider),
+                Arc::clone(&self.client),
+                Arc::clone(&self.miner),
+                self.blocking_pool.clone(),
+            )
+            .to_delegate(),
+        );
+        handler.extend_with(
+            StakeClient::new(
+                Arc::clone(&self.account_provider),
+                Arc::clone(&self.client),
+                Arc::clone(&self.miner),
+                self.blocking_pool.clone(),
+            )
+            .to_delegate(),
+        );
+        handler.extend_with(NodeClient::new(self.node_info(config), self.api_switch.clone()).to_delegate());
+    }
+
+    fn node_info(&self, config: &Config) -> NodeInfo {
+        // The disabled namespaces are left out when the info is queried.
+        let namespaces = self.api_switch.namespaces();
+        NodeInfo {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            commit_hash: env!("VERGEN_SHA").to_string(),
+            chain_type: config.operating.chain.as_ref().map(ToString::to_string).unwrap_or_default(),
+            network_id: self.client.network_id(),
+            features: NodeFeatures {
+                // The state DB never prunes the old states.
+                archive: true,
+                indexes: vec!["transactionHash".to_string(), "transactionTracker".to_string()],
+                snapshot: !config.snapshot.disable.unwrap(),
+            },
+            namespaces,
+        }
     }
 }
 
-pub fn setup_rpc<M: Middleware<()>>(mut handler: MetaIoHandler<(), M>) -> MetaIoHandler<(), M> {
+pub fn setup_rpc<M: Metadata, S: Middleware<M>>(mut handler: MetaIoHandler<M, S>) -> MetaIoHandler<M, S> {
     handler.add_method("ping", |_params: Params| Ok(Value::String("pong".to_string())));
     handler.add_method("version", |_params: Params| Ok(Value::String(env!("CARGO_PKG_VERSION").to_string())));
     handler.add_method("commitHash", |_params: Params| Ok(Value::String(env!("VERGEN_SHA").to_string())));
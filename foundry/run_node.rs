@@ -16,25 +16,37 @@
 
 use crate::auto_self_nominate::AutoSelfNomination;
 use crate::config::{self, load_config};
-use crate::constants::{DEFAULT_DB_PATH, DEFAULT_KEYS_PATH};
+use crate::constants::{CHAINS_PATH, DEFAULT_DB_PATH, DEFAULT_KEYS_PATH};
 use crate::dummy_network_service::DummyNetworkService;
 use crate::json::PasswordFile;
-use crate::rpc::{rpc_http_start, rpc_ipc_start, rpc_ws_start, setup_rpc_server};
-use crate::rpc_apis::ApiDependencies;
+use crate::ports::check_ports;
+use crate::rpc::{
+    rpc_admin_start, rpc_grpc_start, rpc_http_start, rpc_ipc_start, rpc_rest_start, rpc_ws_start, setup_rpc_server,
+    setup_tls_rpc_servers,
+};
+use crate::rpc_apis::{self, ApiDependencies};
 use ccore::{snapshot_notify, ConsensusClient, EngineClient};
 use ccore::{
-    AccountProvider, AccountProviderError, ChainNotify, ClientConfig, ClientService, EngineInfo, EngineType, Miner,
-    MinerService, PeerDb, Scheme, NUM_COLUMNS,
+    AccountProvider, AccountProviderError, ChainNotify, ClientConfig, ClientService, DiskSpaceMonitor, EngineInfo,
+    EngineSigner, EngineType, HotAccountsSaver, Informant, Miner, MinerService, PeerDb, Scheme, StateRootVerifier,
+    NUM_COLUMNS,
 };
-use cdiscovery::{Config, Discovery};
+use cdiscovery::{Capabilities, Config, Directory, Discovery, Pex, PexConfig};
 use ckey::{Address, NetworkId, PlatformAddress};
 use ckeystore::accounts_dir::RootDiskDirectory;
 use ckeystore::KeyStore;
 use clap::ArgMatches;
-use clogger::{self, EmailAlarm, LoggerConfig};
-use cnetwork::{Filters, ManagingPeerdb, NetworkConfig, NetworkControl, NetworkService, RoutingTable, SocketAddr};
+use clogger::{self, EmailAlarm, LogFile, LoggerConfig};
+use cmemory::{MemoryBudget, MemoryConsumer};
+use cnetwork::{
+    Capture, Filters, ManagingPeerdb, NetworkConfig, NetworkControl, NetworkService, NodeId, RoutingTable, SocketAddr,
+};
+use crpc::{BlockingPool, ResponseCache, ResponseCacheNotify};
 use csync::snapshot::Service as SnapshotService;
-use csync::{BlockSyncExtension, BlockSyncSender, TransactionSyncExtension};
+use csync::{
+    BlockSyncExtension, BlockSyncSender, CheckpointExtension, FraudProofExtension, LightServerExtension,
+    TransactionSyncExtension,
+};
 use ctimer::TimerLoop;
 use ctrlc::CtrlC;
 use fdlimit::raise_fd_limit;
@@ -42,9 +54,17 @@ use kvdb::KeyValueDB;
 use kvdb_rocksdb::{Database, DatabaseConfig};
 use parking_lot::{Condvar, Mutex};
 use std::fs;
-use std::path::Path;
+use std::path::{self, Path, PathBuf};
 use std::sync::{Arc, Weak};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How often the memory used by the caches and the queues is checked against the budget.
+const MEMORY_BUDGET_INTERVAL: Duration = Duration::from_secs(5);
+/// How often the free space of the disk having the database is checked.
+const DISK_SPACE_INTERVAL: Duration = Duration::from_secs(10);
+/// How often the accounts in the state cache are saved to warm the cache up after a restart.
+const HOT_ACCOUNTS_INTERVAL: Duration = Duration::from_secs(60);
 
 fn network_start(
     network_id: NetworkId,
@@ -55,7 +75,18 @@ fn network_start(
 ) -> Result<Arc<NetworkService>, String> {
     let addr = cfg.address.parse().map_err(|_| format!("Invalid NETWORK listen host given: {}", cfg.address))?;
     let sockaddress = SocketAddr::new(addr, cfg.port);
-    let filters = Filters::new(cfg.whitelist.clone(), cfg.blacklist.clone());
+    let trusted_peers = cfg.trusted_peers.as_ref().map(|peers| peers.iter().map(NodeId::from).collect());
+    let filters = Filters::new(cfg.whitelist.clone(), cfg.blacklist.clone(), trusted_peers);
+    if cfg.trusted_peers.is_some() {
+        cinfo!(NETWORK, "The network is permissioned");
+    }
+    let capture = match &cfg.capture_path {
+        Some(path) => {
+            cwarn!(NETWORK, "Capturing the network messages to {}", path);
+            Some(Capture::create(path).map_err(|e| format!("Cannot create the capture file {}: {}", path, e))?)
+        }
+        None => None,
+    };
     let service = NetworkService::start(
         network_id,
         timer_loop,
@@ -66,6 +97,7 @@ fn network_start(
         filters,
         routing_table,
         peer_db,
+        capture,
     )
     .map_err(|e| format!("Network service error: {:?}", e))?;
 
@@ -81,10 +113,22 @@ fn discovery_start(
     service: &NetworkService,
     cfg: &config::Network,
     routing_table: Arc<RoutingTable>,
+    capabilities: Capabilities,
 ) -> Result<(), String> {
+    // The warp sync asks only the peers serving the snapshots, instead of probing every peer.
+    let wanted = if cfg.snapshot_hash.is_some() {
+        Capabilities {
+            snapshot: true,
+            ..Default::default()
+        }
+    } else {
+        Default::default()
+    };
     let config = Config {
         bucket_size: cfg.discovery_bucket_size.unwrap(),
         t_refresh: cfg.discovery_refresh.unwrap(),
+        capabilities,
+        wanted,
     };
     let use_kademlia = match cfg.discovery_type.as_ref().map(String::as_str) {
         Some("unstructured") => false,
@@ -92,11 +136,20 @@ fn discovery_start(
         Some(discovery_type) => return Err(format!("Unknown discovery {}", discovery_type)),
         None => return Ok(()),
     };
-    service.register_extension(move |api| Discovery::new(routing_table, config, api, use_kademlia));
+    let directory = Directory::new();
+    service.register_extension(move |api| Discovery::new(routing_table, directory, config, api, use_kademlia));
     Ok(())
 }
 
-fn client_start(
+fn pex_start(service: &NetworkService, cfg: &config::Network, routing_table: Arc<RoutingTable>) {
+    let config = PexConfig {
+        interval: cfg.pex_interval.unwrap(),
+        max_addresses: cfg.pex_max_addresses.unwrap(),
+    };
+    service.register_extension(move |api| Pex::new(routing_table, config, api));
+}
+
+pub fn client_start(
     client_config: &ClientConfig,
     timer_loop: &TimerLoop,
     db: Arc<dyn KeyValueDB>,
@@ -122,16 +175,21 @@ fn new_miner(
 
     match miner.engine_type() {
         EngineType::PBFT => match &config.mining.engine_signer {
-            Some(ref engine_signer) => match miner.set_author((*engine_signer).into_address()) {
-                Err(AccountProviderError::NotUnlocked) => {
-                    return Err(
-                        "The account is not unlocked. Specify the password path using --password-path option."
-                            .to_string(),
-                    )
+            Some(ref engine_signer) => {
+                match miner.set_author((*engine_signer).into_address()) {
+                    Err(AccountProviderError::NotUnlocked) => {
+                        return Err(
+                            "The account is not unlocked. Specify the password path using --password-path option."
+                                .to_string(),
+                        )
+                    }
+                    Err(e) => return Err(format!("{}", e)),
+                    _ => (),
+                }
+                if let Some(validator_owner) = config.mining.validator_owner {
+                    miner.set_validator_owner(validator_owner.into_address());
                 }
-                Err(e) => return Err(format!("{}", e)),
-                _ => (),
-            },
+            }
             None if config.mining.author.is_some() => {
                 return Err("PBFT type engine needs not an author but an engine signer for mining. Specify the engine signer using --engine-signer option."
                     .to_string())
@@ -143,6 +201,18 @@ fn new_miner(
             .expect("set_author never fails when Solo is used"),
     }
 
+    if let Some(extra_data) = &config.mining.extra_data {
+        let max_extra_data_size = scheme.genesis_params().max_extra_data_size();
+        if extra_data.len() > max_extra_data_size {
+            return Err(format!(
+                "The extra data is {} bytes, but the chain allows only {} bytes",
+                extra_data.len(),
+                max_extra_data_size
+            ))
+        }
+        miner.set_extra_data(extra_data.as_bytes().to_vec());
+    }
+
     Ok(miner)
 }
 
@@ -160,7 +230,7 @@ fn wait_for_exit() {
     exit.1.wait(&mut l);
 }
 
-fn prepare_account_provider(keys_path: &str) -> Result<Arc<AccountProvider>, String> {
+fn prepare_account_provider(keys_path: &Path) -> Result<Arc<AccountProvider>, String> {
     let keystore_dir = RootDiskDirectory::create(keys_path).map_err(|_| "Cannot read key path directory")?;
     let keystore = KeyStore::open(Box::new(keystore_dir)).map_err(|_| "Cannot open key store")?;
     Ok(AccountProvider::new(keystore))
@@ -191,10 +261,56 @@ fn unlock_accounts(ap: &AccountProvider, pf: &PasswordFile) -> Result<(), String
     Ok(())
 }
 
-pub fn open_db(cfg: &config::Operating, client_config: &ClientConfig) -> Result<Arc<dyn KeyValueDB>, String> {
-    let base_path = cfg.base_path.as_ref().unwrap().clone();
-    let db_path = cfg.db_path.as_ref().map(String::clone).unwrap_or_else(|| base_path + "/" + DEFAULT_DB_PATH);
-    let client_path = Path::new(&db_path);
+/// The directory holding the database and the keys of the chain, so that the chains under a base path don't share them.
+fn chain_path(cfg: &config::Operating, scheme: &Scheme) -> Result<PathBuf, String> {
+    let data_dir = scheme.data_dir.as_str();
+    if data_dir.is_empty() || data_dir == "." || data_dir == ".." || data_dir.chars().any(path::is_separator) {
+        return Err(format!("The data directory of {} must be a directory name, not {:?}", scheme.name, data_dir))
+    }
+    Ok(Path::new(cfg.base_path.as_ref().unwrap()).join(CHAINS_PATH).join(data_dir))
+}
+
+/// The directory `name` of the chain. The one right under the base path, where the older versions put it, is used
+/// until it's moved to the chain's directory.
+fn default_path(cfg: &config::Operating, scheme: &Scheme, name: &str) -> Result<PathBuf, String> {
+    let path = chain_path(cfg, scheme)?.join(name);
+    let legacy_path = Path::new(cfg.base_path.as_ref().unwrap()).join(name);
+    if !path.exists() && legacy_path.exists() {
+        cwarn!(
+            CLIENT,
+            "{} is not in the directory of {}. Move it to {} to keep the other chains apart.",
+            legacy_path.display(),
+            scheme.name,
+            path.display()
+        );
+        return Ok(legacy_path)
+    }
+    Ok(path)
+}
+
+fn db_path(cfg: &config::Operating, scheme: &Scheme) -> Result<PathBuf, String> {
+    match &cfg.db_path {
+        Some(db_path) => Ok(db_path.into()),
+        None => default_path(cfg, scheme, DEFAULT_DB_PATH),
+    }
+}
+
+fn keys_path(cfg: &config::Operating, scheme: &Scheme) -> Result<PathBuf, String> {
+    match &cfg.keys_path {
+        Some(keys_path) => Ok(keys_path.into()),
+        None => default_path(cfg, scheme, DEFAULT_KEYS_PATH),
+    }
+}
+
+/// Opens the database of the chain. The client refuses the database if it holds another chain.
+pub fn open_db(
+    cfg: &config::Operating,
+    scheme: &Scheme,
+    client_config: &ClientConfig,
+) -> Result<Arc<dyn KeyValueDB>, String> {
+    let db_path = db_path(cfg, scheme)?;
+    let client_path = db_path.as_path();
+    fs::create_dir_all(client_path).map_err(|e| format!("Cannot create {}: {}", client_path.display(), e))?;
     let mut db_config = DatabaseConfig::with_columns(NUM_COLUMNS);
 
     db_config.memory_budget = client_config.db_cache_size;
@@ -217,10 +333,14 @@ pub fn run_node(matches: &ArgMatches<'_>) -> Result<(), String> {
     let config = load_config(matches)?;
 
     let time_gap_params = config.mining.create_time_gaps();
-    let scheme = match &config.operating.chain {
+    let mut scheme = match &config.operating.chain {
         Some(chain) => chain.scheme()?,
         None => return Err("chain is not specified".to_string()),
     };
+    if let Some(shadow_chain) = &config.operating.shadow_chain {
+        scheme.shadow_engine(shadow_chain.scheme()?)?;
+        cinfo!(CLIENT, "Running the consensus engine of {} in shadow mode", shadow_chain);
+    }
     scheme.engine.register_time_gap_config_to_worker(time_gap_params);
 
     let instance_id = config.operating.instance_id.unwrap_or(
@@ -240,28 +360,77 @@ pub fn run_node(matches: &ArgMatches<'_>) -> Result<(), String> {
     } else {
         None
     };
-    clogger::init(&LoggerConfig::new(instance_id), email_alarm.clone())
-        .expect("Logger must be successfully initialized");
+    let mut logger_config = LoggerConfig::new(instance_id);
+    if let Some(file_config) = config.log_file_config()? {
+        let path = file_config.path.clone();
+        let file = LogFile::open(file_config)
+            .map_err(|e| format!("Cannot open the log file {}: {}", path.display(), e))?;
+        logger_config.file = Some(Arc::new(file));
+    }
+    clogger::init(&logger_config, email_alarm.clone()).expect("Logger must be successfully initialized");
     if let Some(email_alarm) = email_alarm {
         panic_hook::set_with_email_alarm(email_alarm);
     }
+    check_ports(&config.listen_ports())?;
 
     let pf = load_password_file(&config.operating.password_path)?;
-    let base_path = config.operating.base_path.as_ref().unwrap().clone();
-    let keys_path =
-        config.operating.keys_path.as_ref().map(String::clone).unwrap_or_else(|| base_path + "/" + DEFAULT_KEYS_PATH);
-    let ap = prepare_account_provider(&keys_path)?;
+    let ap = prepare_account_provider(&keys_path(&config.operating, &scheme)?)?;
     unlock_accounts(&*ap, &pf)?;
 
     let client_config: ClientConfig = Default::default();
-    let db = open_db(&config.operating, &client_config)?;
+    let db = open_db(&config.operating, &scheme, &client_config)?;
 
     let miner = new_miner(&config, &scheme, ap.clone(), Arc::clone(&db))?;
     let client = client_start(&client_config, &timer_loop, db, &scheme, miner.clone())?;
     miner.recover_from_db(client.client().as_ref());
 
+    let memory_budget = config.memory_budget().map(|limit| {
+        let budget = Arc::new(MemoryBudget::new(limit));
+        budget.register("client", Arc::downgrade(&client.client()) as Weak<dyn MemoryConsumer>);
+        budget.register("mem pool", Arc::downgrade(&miner) as Weak<dyn MemoryConsumer>);
+        let timer = timer_loop.new_timer_with_name("Memory budget timer");
+        timer.set_handler(Arc::downgrade(&budget));
+        timer.schedule_repeat(MEMORY_BUDGET_INTERVAL, 0).expect("Memory budget timer is scheduled only once");
+        budget
+    });
+
+    let _disk_space_monitor = {
+        let monitor = Arc::new(DiskSpaceMonitor::new(
+            db_path(&config.operating, &scheme)?,
+            config.disk_space_thresholds(),
+            Arc::downgrade(&client.client()),
+        ));
+        monitor.check();
+        let timer = timer_loop.new_timer_with_name("Disk space timer");
+        timer.set_handler(Arc::downgrade(&monitor));
+        timer.schedule_repeat(DISK_SPACE_INTERVAL, 0).expect("Disk space timer is scheduled only once");
+        monitor
+    };
+
+    let _state_root_verifier = config.verify_roots_interval().map(|interval| {
+        let verifier = Arc::new(StateRootVerifier::new(Arc::downgrade(&client.client())));
+        let timer = timer_loop.new_timer_with_name("State root verifier timer");
+        timer.set_handler(Arc::downgrade(&verifier));
+        timer.schedule_repeat(interval, 0).expect("State root verifier timer is scheduled only once");
+        verifier
+    });
+
+    let _hot_accounts_saver = {
+        let prewarmed = client.client();
+        thread::Builder::new()
+            .name("State prewarmer".to_string())
+            .spawn(move || prewarmed.prewarm_state())
+            .map_err(|e| format!("Cannot start the state prewarmer: {}", e))?;
+        let saver = Arc::new(HotAccountsSaver::new(Arc::downgrade(&client.client())));
+        let timer = timer_loop.new_timer_with_name("Hot accounts timer");
+        timer.set_handler(Arc::downgrade(&saver));
+        timer.schedule_repeat(HOT_ACCOUNTS_INTERVAL, 0).expect("Hot accounts timer is scheduled only once");
+        saver
+    };
+
     let mut _maybe_sync = None;
     let mut maybe_sync_sender = None;
+    let mut maybe_checkpoint_sender = None;
 
     scheme.engine.register_chain_notify(client.client().as_ref());
 
@@ -274,9 +443,24 @@ pub fn run_node(matches: &ArgMatches<'_>) -> Result<(), String> {
             let routing_table = RoutingTable::new();
             let peer_db = PeerDb::new(c.get_kvdb());
             let service = network_start(network_id, timer_loop, &network_config, Arc::clone(&routing_table), peer_db)?;
+            if let Some(budget) = &memory_budget {
+                budget.register("network buffers", Arc::downgrade(&service) as Weak<dyn MemoryConsumer>);
+            }
+
+            if config.network.pex.unwrap() {
+                pex_start(&service, &config.network, Arc::clone(&routing_table));
+            }
 
             if config.network.discovery.unwrap() {
-                discovery_start(&service, &config.network, routing_table)?;
+                let capabilities = Capabilities {
+                    // The state DB never prunes the old states.
+                    archive: true,
+                    snapshot: !config.snapshot.disable.unwrap(),
+                    light_server: config.network.light_server.unwrap(),
+                    // The node keeps the states of all shards.
+                    shards: None,
+                };
+                discovery_start(&service, &config.network, routing_table, capabilities)?;
             } else {
                 cwarn!(DISCOVERY, "Node runs without discovery extension");
             }
@@ -289,8 +473,9 @@ pub fn run_node(matches: &ArgMatches<'_>) -> Result<(), String> {
                         _ => None,
                     };
                     let snapshot_dir = config.snapshot.path.clone();
+                    let compact_block_relay = config.network.compact_block_relay.unwrap();
                     service.register_extension(move |api| {
-                        BlockSyncExtension::new(client, api, snapshot_target, snapshot_dir)
+                        BlockSyncExtension::new(client, api, snapshot_target, snapshot_dir, compact_block_relay)
                     })
                 };
                 let sync = Arc::new(BlockSyncSender::from(sync_sender.clone()));
@@ -302,6 +487,29 @@ pub fn run_node(matches: &ArgMatches<'_>) -> Result<(), String> {
                 let client = client.client();
                 service.register_extension(move |api| TransactionSyncExtension::new(client, api));
             }
+            let checkpoint_interval = config.network.checkpoint_interval.unwrap();
+            if checkpoint_interval != 0 {
+                let client = client.client();
+                let signer = match (miner.engine_type(), &config.mining.engine_signer) {
+                    (EngineType::PBFT, Some(engine_signer)) => {
+                        let mut signer = EngineSigner::default();
+                        signer.set_to_keep_decrypted_account(Arc::clone(&ap), (*engine_signer).into_address());
+                        Some(signer)
+                    }
+                    _ => None,
+                };
+                maybe_checkpoint_sender = Some(service.register_extension(move |api| {
+                    CheckpointExtension::new(client, signer, checkpoint_interval, api)
+                }));
+            }
+            if config.network.light_server.unwrap() {
+                let client = client.client();
+                service.register_extension(move |api| LightServerExtension::new(client, api));
+            }
+            if config.network.sync.unwrap() {
+                let client = client.client();
+                service.register_extension(move |api| FraudProofExtension::new(client, api));
+            }
 
             scheme.engine.register_network_extension_to_service(&service);
 
@@ -310,6 +518,14 @@ pub fn run_node(matches: &ArgMatches<'_>) -> Result<(), String> {
             Arc::new(DummyNetworkService::new())
         }
     };
+    let _informant = config.informant_interval().map(|interval| {
+        let informant = Arc::new(Informant::new(&client.client(), Some(Arc::clone(&network_service))));
+        let timer = timer_loop.new_timer_with_name("Informant timer");
+        timer.set_handler(Arc::downgrade(&informant));
+        timer.schedule_repeat(interval, 0).expect("Informant timer is scheduled only once");
+        informant
+    });
+
     if config.mining.self_nomination_enable {
         let c = client.client();
         let address = miner.get_author_address();
@@ -317,19 +533,31 @@ pub fn run_node(matches: &ArgMatches<'_>) -> Result<(), String> {
         self_nominate_start(c, matches, accountp, address);
     }
 
-    let (rpc_server, ipc_server, ws_server) = {
+    let blocking_pool =
+        BlockingPool::new(config.rpc_blocking_threads()).map_err(|e| format!("Cannot start the RPC threads: {}", e))?;
+    let response_cache = ResponseCache::new(config.rpc_response_cache_size());
+    let response_cache_notify = Arc::new(ResponseCacheNotify::new(response_cache.clone(), client.client()));
+    client.client().add_notify(Arc::downgrade(&response_cache_notify) as Weak<dyn ChainNotify>);
+    let api_switch = rpc_apis::api_switch(&config, maybe_checkpoint_sender.is_some());
+    let (rpc_server, ipc_server, ws_server, admin_server) = {
         let rpc_apis_deps = ApiDependencies {
             client: client.client(),
             miner: Arc::clone(&miner),
             network_control: Arc::clone(&network_service),
             account_provider: ap,
             block_sync: maybe_sync_sender,
+            checkpoint: maybe_checkpoint_sender,
+            blocking_pool: blocking_pool.clone(),
+            response_cache,
+            api_switch,
         };
 
         let rpc_server = {
             if !config.rpc.disable.unwrap() {
-                let server = setup_rpc_server(&config, &rpc_apis_deps);
-                Some(rpc_http_start(server, config.rpc_http_config())?)
+                let http_config = config.rpc_http_config();
+                let (server, admin_server) =
+                    setup_tls_rpc_servers(&config, &rpc_apis_deps, http_config.tls.as_ref(), &http_config.limits);
+                Some(rpc_http_start(server, admin_server, http_config)?)
             } else {
                 None
             }
@@ -346,14 +574,37 @@ pub fn run_node(matches: &ArgMatches<'_>) -> Result<(), String> {
 
         let ws_server = {
             if !config.ws.disable.unwrap() {
+                let ws_config = config.rpc_ws_config();
+                let (server, admin_server) =
+                    setup_tls_rpc_servers(&config, &rpc_apis_deps, ws_config.tls.as_ref(), &ws_config.limits);
+                Some(rpc_ws_start(client.client(), server, admin_server, ws_config)?)
+            } else {
+                None
+            }
+        };
+
+        let admin_server = {
+            if !config.admin_rpc.disable.unwrap() {
                 let server = setup_rpc_server(&config, &rpc_apis_deps);
-                Some(rpc_ws_start(server, config.rpc_ws_config())?)
+                Some(rpc_admin_start(server, config.rpc_admin_config())?)
             } else {
                 None
             }
         };
 
-        (rpc_server, ipc_server, ws_server)
+        (rpc_server, ipc_server, ws_server, admin_server)
+    };
+
+    let grpc_server = if !config.grpc.disable.unwrap() {
+        Some(rpc_grpc_start(client.client(), config.rpc_grpc_config())?)
+    } else {
+        None
+    };
+
+    let rest_server = if !config.rest.disable.unwrap() {
+        Some(rpc_rest_start(client.client(), blocking_pool, config.rpc_rest_config())?)
+    } else {
+        None
     };
 
     let _snapshot_service = {
@@ -361,8 +612,14 @@ pub fn run_node(matches: &ArgMatches<'_>) -> Result<(), String> {
         let (tx, rx) = snapshot_notify::create();
         client.engine().register_snapshot_notify_sender(tx);
         if !config.snapshot.disable.unwrap() {
-            let service =
-                Arc::new(SnapshotService::new(client, rx, config.snapshot.path.unwrap(), config.snapshot.expiration));
+            let publisher = config.snapshot_publisher()?;
+            let service = Arc::new(SnapshotService::new(
+                client,
+                rx,
+                config.snapshot.path.unwrap(),
+                config.snapshot.expiration,
+                publisher,
+            ));
             Some(service)
         } else {
             None
@@ -376,18 +633,27 @@ pub fn run_node(matches: &ArgMatches<'_>) -> Result<(), String> {
     cinfo!(TEST_SCRIPT, "Initialization complete");
 
     wait_for_exit();
+    client.client().save_hot_accounts();
 
     if let Some(server) = rpc_server {
-        server.close_handle().close();
-        server.wait();
+        server.close();
     }
     if let Some(server) = ipc_server {
         server.close_handle().close();
         server.wait();
     }
     if let Some(server) = ws_server {
+        server.close()?;
+    }
+    if let Some(server) = admin_server {
         server.close_handle().close();
-        server.wait().map_err(|err| format!("Error while closing jsonrpc ws server: {}", err))?;
+        server.wait();
+    }
+    if let Some(server) = grpc_server {
+        server.close();
+    }
+    if let Some(server) = rest_server {
+        server.close();
     }
 
     Ok(())
@@ -0,0 +1,245 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::run_node::client_start;
+use ccore::{
+    AccountData, BlockChainClient, BlockChainTrait, BlockId, ClientConfig, ClientService, ImportBlock, Miner,
+    MinerOptions, MinerService, Scheme, SignedTransaction, TxSource, UnverifiedTransaction, NUM_COLUMNS,
+};
+use ckey::{
+    public_to_address, recover, sign, sign_schnorr, verify_schnorr, Address, Generator, KeyPair, Private, Random,
+};
+use clap::ArgMatches;
+use clogger::{self, LoggerConfig};
+use codechain_state::tests::helpers::get_temp_state;
+use codechain_state::{StateWithCache, TopState};
+use codechain_types::transaction::{Action, Transaction};
+use ctimer::TimerLoop;
+use kvdb::KeyValueDB;
+use primitives::H256;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const WORKLOADS: [&str; 4] = ["import", "trie", "signature", "mem-pool"];
+/// The account funded in the genesis of the bundled solo scheme.
+const FAUCET_SECRET: &str = "ede1d4ccb4ec9a8bbbae9a13db3f4a7b56ea04189be86ac3a6a439d9a0a1addd";
+const FEE: u64 = 10;
+const TIMEOUT: Duration = Duration::from_secs(600);
+
+pub fn run_bench_command(matches: &ArgMatches<'_>) -> Result<(), String> {
+    clogger::init(&LoggerConfig::new(0), None).expect("Logger must be successfully initialized");
+
+    let count = matches.value_of("count").map(parse_count).transpose()?;
+    let workloads = match matches.value_of("workload") {
+        Some(workload) => vec![workload],
+        None => WORKLOADS.to_vec(),
+    };
+
+    for workload in workloads {
+        let (items, elapsed) = match workload {
+            "import" => bench_import(count.unwrap_or(2_000))?,
+            "trie" => bench_trie(count.unwrap_or(20_000))?,
+            "signature" => bench_signature(count.unwrap_or(2_000))?,
+            "mem-pool" => bench_mem_pool(count.unwrap_or(5_000))?,
+            _ => return Err(format!("Invalid workload: {}", workload)),
+        };
+        let seconds = elapsed.as_secs_f64();
+        println!("{:<10} {:>8} items {:>10.3} s {:>12.1} items/s", workload, items, seconds, items as f64 / seconds);
+    }
+    Ok(())
+}
+
+/// Imports the blocks of a chain authored beforehand into a fresh client.
+///
+/// The chain is made of `count` payments from the faucet of the solo scheme, so the numbers are comparable between
+/// runs and machines. Only the import is measured.
+fn bench_import(count: usize) -> Result<(usize, Duration), String> {
+    let faucet = faucet()?;
+    let timer_loop = TimerLoop::new(1);
+
+    let options = MinerOptions {
+        reseal_min_period: Duration::from_secs(0),
+        mem_pool_size: count,
+        mem_pool_memory_limit: None,
+        ..Default::default()
+    };
+    let (author, _) = start_client(&timer_loop, options)?;
+    let client = author.client();
+    for (seq, receiver) in receivers(count).into_iter().enumerate() {
+        client
+            .queue_own_transaction(payment(&faucet, seq as u64, receiver))
+            .map_err(|e| format!("Cannot queue the transaction #{}: {}", seq, e))?;
+    }
+    let sender = public_to_address(faucet.public());
+    wait_until(|| client.latest_seq(&sender) == count as u64)?;
+
+    let best_block_number = client.chain_info().best_block_number;
+    let blocks = (1..=best_block_number)
+        .map(|number| client.block(&BlockId::Number(number)).expect("Authored blocks exist").into_inner())
+        .collect::<Vec<_>>();
+
+    let (importer, _) = start_client(&timer_loop, Default::default())?;
+    let client = importer.client();
+    let start = Instant::now();
+    for (number, block) in blocks.into_iter().enumerate() {
+        client.import_block(block).map_err(|e| format!("Cannot import the block #{}: {:?}", number + 1, e))?;
+    }
+    wait_until(|| client.chain_info().best_block_number == best_block_number)?;
+    Ok((count, start.elapsed()))
+}
+
+/// Commits balance changes of new accounts to the trie, a hundred accounts per commit.
+fn bench_trie(count: usize) -> Result<(usize, Duration), String> {
+    let addresses = receivers(count);
+    let mut state = get_temp_state();
+
+    let start = Instant::now();
+    for chunk in addresses.chunks(100) {
+        for address in chunk {
+            state.add_balance(address, 1).map_err(|e| format!("Cannot add the balance: {}", e))?;
+        }
+        state.commit().map_err(|e| format!("Cannot commit the state: {}", e))?;
+    }
+    Ok((count, start.elapsed()))
+}
+
+/// Recovers ECDSA signatures, as done for transactions, and verifies Schnorr signatures, as done for votes.
+fn bench_signature(count: usize) -> Result<(usize, Duration), String> {
+    let key_pair = Random.generate().map_err(|e| format!("Cannot generate a key: {}", e))?;
+    let messages = (0..count).map(|i| H256::from(i as u64)).collect::<Vec<_>>();
+    let ecdsa = messages
+        .iter()
+        .map(|message| sign(key_pair.private(), message))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Cannot sign: {}", e))?;
+    let schnorr = messages
+        .iter()
+        .map(|message| sign_schnorr(key_pair.private(), message))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Cannot sign: {}", e))?;
+
+    let start = Instant::now();
+    for ((message, ecdsa), schnorr) in messages.iter().zip(&ecdsa).zip(&schnorr) {
+        let recovered = recover(ecdsa, message).map_err(|e| format!("Cannot recover: {}", e))?;
+        let verified =
+            verify_schnorr(key_pair.public(), schnorr, message).map_err(|e| format!("Cannot verify: {}", e))?;
+        if recovered != *key_pair.public() || !verified {
+            return Err("A signature is not verified".to_string())
+        }
+    }
+    Ok((count * 2, start.elapsed()))
+}
+
+/// Inserts external transactions into the mem pool without authoring blocks.
+fn bench_mem_pool(count: usize) -> Result<(usize, Duration), String> {
+    let faucet = faucet()?;
+    let timer_loop = TimerLoop::new(1);
+    let options = MinerOptions {
+        reseal_on_external_transaction: false,
+        no_reseal_timer: true,
+        mem_pool_size: count,
+        mem_pool_memory_limit: None,
+        ..Default::default()
+    };
+    let (service, miner) = start_client(&timer_loop, options)?;
+    let client = service.client();
+    let transactions = receivers(count)
+        .into_iter()
+        .enumerate()
+        .map(|(seq, receiver)| UnverifiedTransaction::from(payment(&faucet, seq as u64, receiver)))
+        .collect::<Vec<_>>();
+
+    let start = Instant::now();
+    let results = miner.import_external_transactions(&*client, transactions, TxSource::Unknown);
+    for (seq, result) in results.into_iter().enumerate() {
+        result.map_err(|e| format!("Cannot import the transaction #{}: {}", seq, e))?;
+    }
+    Ok((count, start.elapsed()))
+}
+
+/// Starts a solo client on an in-memory database.
+fn start_client(timer_loop: &TimerLoop, options: MinerOptions) -> Result<(ClientService, Arc<Miner>), String> {
+    let scheme = Scheme::new_test_solo();
+    let db: Arc<dyn KeyValueDB> = Arc::new(kvdb_memorydb::create(NUM_COLUMNS.unwrap_or(0)));
+    let miner = Miner::new(options, &scheme, None, Arc::clone(&db));
+    let service = client_start(&ClientConfig::default(), timer_loop, db, &scheme, Arc::clone(&miner))?;
+    Ok((service, miner))
+}
+
+fn faucet() -> Result<KeyPair, String> {
+    let private = Private::from_str(FAUCET_SECRET).map_err(|e| e.to_string())?;
+    KeyPair::from_private(private).map_err(|e| e.to_string())
+}
+
+/// Deterministic addresses, so that every run writes the same trie.
+fn receivers(count: usize) -> Vec<Address> {
+    (1..=count).map(|i| Address::from(i as u64)).collect()
+}
+
+fn payment(sender: &KeyPair, seq: u64, receiver: Address) -> SignedTransaction {
+    let tx = Transaction {
+        seq,
+        fee: FEE,
+        network_id: "tc".into(),
+        action: Action::Pay {
+            receiver,
+            quantity: 1,
+        },
+    };
+    SignedTransaction::new_with_sign(tx, sender.private())
+}
+
+fn wait_until(mut condition: impl FnMut() -> bool) -> Result<(), String> {
+    let start = Instant::now();
+    while !condition() {
+        if start.elapsed() > TIMEOUT {
+            return Err("Timed out".to_string())
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    Ok(())
+}
+
+fn parse_count(count: &str) -> Result<usize, String> {
+    match count.parse() {
+        Ok(0) => Err("The count must be positive".to_string()),
+        Ok(count) => Ok(count),
+        Err(e) => Err(format!("Invalid count {}: {}", count, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workloads_process_every_item() {
+        assert_eq!(10, bench_import(10).unwrap().0);
+        assert_eq!(250, bench_trie(250).unwrap().0);
+        assert_eq!(20, bench_signature(10).unwrap().0);
+        assert_eq!(10, bench_mem_pool(10).unwrap().0);
+    }
+
+    #[test]
+    fn count_must_be_a_positive_number() {
+        assert_eq!(Ok(20), parse_count("20"));
+        assert!(parse_count("0").is_err());
+        assert!(parse_count("-1").is_err());
+        assert!(parse_count("many").is_err());
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::config::load_config;
+use crate::run_node::{client_start, open_db};
+use ccore::{BlockChainTrait, BlockId, ClientConfig, ClientService, Miner, StateModification, StateSurgeryClient};
+use ckey::hex::FromHex;
+use ckey::{Address, PlatformAddress};
+use clap::ArgMatches;
+use clogger::{self, LoggerConfig};
+use ctimer::TimerLoop;
+use primitives::{remove_0x_prefix, H256};
+use std::iter;
+use std::str::FromStr;
+use std::sync::Arc;
+
+pub fn run_db_command(global_matches: &ArgMatches<'_>, matches: &ArgMatches<'_>) -> Result<(), String> {
+    let (modification, matches) = match matches.subcommand() {
+        ("set-balance", Some(matches)) => (
+            StateModification::Balance {
+                address: parse_address(matches)?,
+                balance: parse_u64(matches, "balance")?,
+            },
+            matches,
+        ),
+        ("set-seq", Some(matches)) => (
+            StateModification::Seq {
+                address: parse_address(matches)?,
+                seq: parse_u64(matches, "seq")?,
+            },
+            matches,
+        ),
+        ("set-storage", Some(matches)) => {
+            let key = matches.value_of("key").expect("key is required");
+            let value = matches.value_of("value").expect("value is required");
+            (
+                StateModification::ActionData {
+                    key: H256::from_str(remove_0x_prefix(key)).map_err(|e| format!("Invalid key {}: {:?}", key, e))?,
+                    value: remove_0x_prefix(value).from_hex().map_err(|e| format!("Invalid value {}: {}", value, e))?,
+                },
+                matches,
+            )
+        }
+        ("check-state", Some(matches)) => return run_check_state(global_matches, matches),
+        _ => {
+            println!("{}", matches.usage());
+            return Ok(())
+        }
+    };
+
+    let block_id = parse_block_id(matches)?;
+    let (_timer_loop, service) = start_client(global_matches)?;
+
+    let hash = service.client().rewrite_state(block_id, &[modification]).map_err(|e| e.to_string())?;
+    println!("{:?}", hash);
+    Ok(())
+}
+
+fn run_check_state(global_matches: &ArgMatches<'_>, matches: &ArgMatches<'_>) -> Result<(), String> {
+    let block_id = parse_block_id(matches)?;
+    let (_timer_loop, service) = start_client(global_matches)?;
+    let client = service.client();
+
+    let header = client.block_header(&block_id).ok_or_else(|| format!("Unknown block {:?}", block_id))?;
+    let check = client.check_state(block_id).ok_or_else(|| format!("Unknown block {:?}", block_id))?;
+    println!("#{} {}: state root {}", header.number(), header.hash(), header.state_root());
+    println!(
+        "top-level trie: {} nodes, {} items ({} accounts, {} regular accounts, {} action data)",
+        check.top.nodes, check.top.leaves, check.accounts, check.regular_accounts, check.action_data
+    );
+    for (shard_id, shard) in &check.shards {
+        println!("shard #{}: {} nodes, {} items", shard_id, shard.nodes, shard.leaves);
+    }
+    let tries = iter::once(("top-level trie".to_string(), &check.top))
+        .chain(check.shards.iter().map(|(shard_id, shard)| (format!("shard #{}", shard_id), shard)));
+    for (name, trie) in tries {
+        for hash in &trie.missing_nodes {
+            println!("missing node {} in the {}", hash, name);
+        }
+        for hash in &trie.corrupted_nodes {
+            println!("corrupted node {} in the {}", hash, name);
+        }
+    }
+    for inconsistency in &check.inconsistencies {
+        println!("{}", inconsistency);
+    }
+
+    if !check.is_healthy() {
+        return Err("The state is damaged".to_string())
+    }
+    println!("The state is intact");
+    Ok(())
+}
+
+/// Starts the client on the database of the stopped node. The timer loop must outlive the client.
+fn start_client(global_matches: &ArgMatches<'_>) -> Result<(TimerLoop, ClientService), String> {
+    clogger::init(&LoggerConfig::new(0), None).expect("Logger must be successfully initialized");
+
+    let config = load_config(global_matches)?;
+    let chain = config.operating.chain.clone().ok_or_else(|| "chain is not specified".to_string())?;
+    let scheme = chain.scheme()?;
+
+    let timer_loop = TimerLoop::new(1);
+    let client_config: ClientConfig = Default::default();
+    let db = open_db(&config.operating, &scheme, &client_config)?;
+    let miner = Miner::new(config.miner_options()?, &scheme, None, Arc::clone(&db));
+    let service = client_start(&client_config, &timer_loop, db, &scheme, miner)?;
+    Ok((timer_loop, service))
+}
+
+fn parse_block_id(matches: &ArgMatches<'_>) -> Result<BlockId, String> {
+    Ok(match matches.value_of("block") {
+        Some(number) => BlockId::Number(number.parse().map_err(|e| format!("Invalid block number {}: {}", number, e))?),
+        None => BlockId::Latest,
+    })
+}
+
+fn parse_address(matches: &ArgMatches<'_>) -> Result<Address, String> {
+    let address = matches.value_of("address").expect("address is required");
+    PlatformAddress::from_str(address)
+        .map(|address| address.into_address())
+        .map_err(|e| format!("Invalid address {}: {}", address, e))
+}
+
+fn parse_u64(matches: &ArgMatches<'_>, name: &str) -> Result<u64, String> {
+    let value = matches.value_of(name).expect("The argument is required");
+    value.parse().map_err(|e| format!("Invalid {} {}: {}", name, value, e))
+}
@@ -0,0 +1,64 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::config::load_config;
+use crate::run_node::{client_start, open_db};
+use ccore::{BlockId, ClientConfig, Miner, SchemeExportClient};
+use clap::ArgMatches;
+use clogger::{self, LoggerConfig};
+use ctimer::TimerLoop;
+use serde_json::Value;
+use std::sync::Arc;
+
+pub fn run_genesis_command(global_matches: &ArgMatches<'_>, matches: &ArgMatches<'_>) -> Result<(), String> {
+    match matches.subcommand() {
+        ("export", Some(matches)) => export(global_matches, matches),
+        _ => {
+            println!("{}", matches.usage());
+            Ok(())
+        }
+    }
+}
+
+fn export(global_matches: &ArgMatches<'_>, matches: &ArgMatches<'_>) -> Result<(), String> {
+    clogger::init(&LoggerConfig::new(0), None).expect("Logger must be successfully initialized");
+
+    let block_id = match matches.value_of("block") {
+        Some(number) => BlockId::Number(number.parse().map_err(|e| format!("Invalid block number {}: {}", number, e))?),
+        None => BlockId::Latest,
+    };
+
+    let config = load_config(global_matches)?;
+    let chain = config.operating.chain.clone().ok_or_else(|| "chain is not specified".to_string())?;
+    let scheme = chain.scheme()?;
+
+    let timer_loop = TimerLoop::new(1);
+    let client_config: ClientConfig = Default::default();
+    let db = open_db(&config.operating, &scheme, &client_config)?;
+    let miner = Miner::new(config.miner_options()?, &scheme, None, Arc::clone(&db));
+    let service = client_start(&client_config, &timer_loop, db, &scheme, miner)?;
+
+    let mut exported = service
+        .client()
+        .export_genesis(block_id)
+        .ok_or_else(|| "The state of the block is not available".to_string())?;
+    exported
+        .as_object_mut()
+        .expect("The exported scheme is an object")
+        .insert("extends".to_string(), Value::String(chain.to_string()));
+    println!("{}", serde_json::to_string_pretty(&exported).map_err(|e| e.to_string())?);
+    Ok(())
+}
@@ -15,17 +15,38 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 mod account_command;
+mod bench_command;
 mod convert_command;
+mod db_command;
+mod genesis_command;
+mod replay_command;
+mod shadow_fork_command;
+mod testnet_command;
+mod verify_roots_command;
 
 use self::account_command::run_account_command;
+use self::bench_command::run_bench_command;
 use self::convert_command::run_convert_command;
+use self::db_command::run_db_command;
+use self::genesis_command::run_genesis_command;
+use self::replay_command::run_replay_command;
+use self::shadow_fork_command::run_shadow_fork_command;
+use self::testnet_command::run_testnet_command;
+use self::verify_roots_command::run_verify_roots_command;
 use clap::ArgMatches;
 
 pub fn run_subcommand(matches: &ArgMatches<'_>) -> Result<(), String> {
     let subcommand = matches.subcommand.as_ref().unwrap();
     match subcommand.name.as_str() {
         "account" => run_account_command(&subcommand.matches),
+        "bench" => run_bench_command(&subcommand.matches),
         "convert" => run_convert_command(&subcommand.matches),
+        "db" => run_db_command(matches, &subcommand.matches),
+        "genesis" => run_genesis_command(matches, &subcommand.matches),
+        "replay" => run_replay_command(matches, &subcommand.matches),
+        "shadow-fork" => run_shadow_fork_command(matches, &subcommand.matches),
+        "testnet" => run_testnet_command(&subcommand.matches),
+        "verify-roots" => run_verify_roots_command(matches, &subcommand.matches),
         "commit-hash" => {
             println!("{}", env!("VERGEN_SHA"));
             Ok(())
@@ -0,0 +1,78 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::config::load_config;
+use crate::run_node::{client_start, open_db};
+use ccore::{BlockChainTrait, BlockId, ClientConfig, Miner, ReplayClient};
+use clap::ArgMatches;
+use clogger::{self, LoggerConfig};
+use codechain_types::BlockNumber;
+use ctimer::TimerLoop;
+use std::sync::Arc;
+
+pub fn run_replay_command(global_matches: &ArgMatches<'_>, matches: &ArgMatches<'_>) -> Result<(), String> {
+    clogger::init(&LoggerConfig::new(0), None).expect("Logger must be successfully initialized");
+
+    let from = parse_block_number(matches.value_of("from").expect("from is required"))?;
+    let to = matches.value_of("to").map(parse_block_number).transpose()?;
+
+    let config = load_config(global_matches)?;
+    let chain = config.operating.chain.clone().ok_or_else(|| "chain is not specified".to_string())?;
+    let scheme = chain.scheme()?;
+
+    let timer_loop = TimerLoop::new(1);
+    let client_config: ClientConfig = Default::default();
+    let db = open_db(&config.operating, &scheme, &client_config)?;
+    let miner = Miner::new(config.miner_options()?, &scheme, None, Arc::clone(&db));
+    let service = client_start(&client_config, &timer_loop, db, &scheme, miner)?;
+    let client = service.client();
+
+    let to = to.unwrap_or_else(|| client.chain_info().best_block_number);
+    if from == 0 {
+        return Err("The genesis block cannot be replayed".to_string())
+    }
+    if from > to {
+        return Err(format!("Invalid range: {} > {}", from, to))
+    }
+
+    for number in from..=to {
+        let replayed = client
+            .replay_block(BlockId::Number(number))
+            .map_err(|e| format!("Cannot replay block #{}: {}", number, e))?
+            .ok_or_else(|| format!("Block #{} or the state of its parent is not available", number))?;
+        if !replayed.is_diverged() {
+            println!("#{} {:?}: {:?}", replayed.number, replayed.hash, replayed.state_root);
+            continue
+        }
+
+        println!(
+            "#{} {:?}: expected {:?} but replayed {:?}",
+            replayed.number, replayed.hash, replayed.expected_state_root, replayed.state_root
+        );
+        for account in &replayed.divergent_accounts {
+            println!(
+                "  {:?}: expected (balance: {}, seq: {}) but replayed (balance: {}, seq: {})",
+                account.address, account.expected.0, account.expected.1, account.replayed.0, account.replayed.1
+            );
+        }
+        return Err(format!("The state root diverges at block #{}", number))
+    }
+    Ok(())
+}
+
+fn parse_block_number(number: &str) -> Result<BlockNumber, String> {
+    number.parse().map_err(|e| format!("Invalid block number {}: {}", number, e))
+}
@@ -0,0 +1,180 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::config::load_config;
+use crate::run_node::{client_start, open_db};
+use ccore::{BlockChainTrait, BlockId, ClientConfig, Miner, Scheme, StateInfo, COL_STATE, NUM_COLUMNS};
+use clap::ArgMatches;
+use clogger::{self, LoggerConfig};
+use codechain_types::Header;
+use ctimer::TimerLoop;
+use kvdb::{DBTransaction, KeyValueDB};
+use kvdb_rocksdb::{Database, DatabaseConfig};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// The number of state trie nodes written to the fork in a transaction.
+const COPY_BATCH_SIZE: usize = 10_000;
+
+pub fn run_shadow_fork_command(global_matches: &ArgMatches<'_>, matches: &ArgMatches<'_>) -> Result<(), String> {
+    clogger::init(&LoggerConfig::new(0), None).expect("Logger must be successfully initialized");
+
+    let dir = Path::new(matches.value_of("dir").expect("dir is required"));
+    if dir.exists() {
+        return Err(format!("{} already exists", dir.display()))
+    }
+    let block_id = match matches.value_of("block") {
+        Some(number) => BlockId::Number(number.parse().map_err(|e| format!("Invalid block number {}: {}", number, e))?),
+        None => BlockId::Latest,
+    };
+
+    let config = load_config(global_matches)?;
+    let chain = config.operating.chain.clone().ok_or_else(|| "chain is not specified".to_string())?;
+    let scheme = chain.scheme()?;
+
+    let timer_loop = TimerLoop::new(1);
+    let client_config: ClientConfig = Default::default();
+    let db = open_db(&config.operating, &scheme, &client_config)?;
+    let miner = Miner::new(config.miner_options()?, &scheme, None, Arc::clone(&db));
+    let service = client_start(&client_config, &timer_loop, Arc::clone(&db), &scheme, miner)?;
+    let client = service.client();
+
+    let header = client.block_header(&block_id).ok_or_else(|| "The block is not synced yet".to_string())?.decode();
+    if client.state_at(block_id).is_none() {
+        return Err(format!("The state of block #{} is not available", header.number()))
+    }
+
+    let fork = fork_scheme(Scheme::load_json(&chain.to_string())?, &header)?;
+
+    let fork_db_path = dir.join("db");
+    fs::create_dir_all(&fork_db_path).map_err(|e| format!("Cannot create {}: {}", fork_db_path.display(), e))?;
+    let copied = copy_state(&*db, &fork_db_path)?;
+
+    let scheme_path = dir.join("scheme.json");
+    let scheme_json = serde_json::to_string_pretty(&fork).expect("A JSON value can be serialized");
+    fs::write(&scheme_path, scheme_json).map_err(|e| format!("Cannot write {}: {}", scheme_path.display(), e))?;
+
+    let config_path = dir.join("config.toml");
+    let fork_config = format!(
+        r#"[codechain]
+base_path = "{base_path}"
+db_path = "{db_path}"
+chain = "{chain}"
+
+[network]
+disable = true
+
+[snapshot]
+disable = true
+"#,
+        base_path = dir.display(),
+        db_path = fork_db_path.display(),
+        chain = scheme_path.display(),
+    );
+    fs::write(&config_path, fork_config).map_err(|e| format!("Cannot write {}: {}", config_path.display(), e))?;
+
+    println!(
+        "Forked #{} ({:?}) with {} state trie nodes. Run the fork with `foundry --config {}`",
+        header.number(),
+        header.hash(),
+        copied,
+        config_path.display()
+    );
+    Ok(())
+}
+
+/// Makes the scheme of a solo chain whose genesis has the state of the given block of the original scheme.
+fn fork_scheme(mut fork: Value, header: &Header) -> Result<Value, String> {
+    let object = fork.as_object_mut().ok_or_else(|| "The scheme is not an object".to_string())?;
+    let name = object.get("name").and_then(Value::as_str).unwrap_or("Unknown").to_string();
+    object.insert("name".to_string(), json!(format!("{} shadow fork at #{}", name, header.number())));
+    // Keeps the stake handler so that the staking state of the original chain stays usable.
+    object.insert(
+        "engine".to_string(),
+        json!({
+            "solo": {
+                "params": {
+                    "hit": {},
+                    "genesisStakes": {}
+                }
+            }
+        }),
+    );
+    let genesis = object
+        .get_mut("genesis")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| "The scheme has no genesis".to_string())?;
+    genesis.insert("seal".to_string(), json!({ "generic": "0x0" }));
+    genesis.insert("timestamp".to_string(), json!(header.timestamp()));
+    genesis.insert("parentHash".to_string(), json!(format!("{:?}", header.hash())));
+    genesis.insert("stateRoot".to_string(), json!(format!("{:?}", header.state_root())));
+    Ok(fork)
+}
+
+/// Copies the state trie nodes to a new database at the given path. Returns the number of the copied nodes.
+fn copy_state(db: &dyn KeyValueDB, path: &Path) -> Result<usize, String> {
+    let db_config = DatabaseConfig::with_columns(NUM_COLUMNS);
+    let fork_db = Database::open(&db_config, &path.to_str().expect("DB path could not be converted to string."))
+        .map_err(|_e| "Low level database error. Some issue with disk?".to_string())?;
+
+    let mut copied = 0;
+    let mut batch = DBTransaction::new();
+    for (key, value) in db.iter(COL_STATE) {
+        batch.put(COL_STATE, &key, &value);
+        copied += 1;
+        if copied % COPY_BATCH_SIZE == 0 {
+            fork_db.write(batch).map_err(|e| format!("Cannot write the state: {}", e))?;
+            batch = DBTransaction::new();
+        }
+    }
+    fork_db.write(batch).map_err(|e| format!("Cannot write the state: {}", e))?;
+    fork_db.flush().map_err(|e| format!("Cannot flush the state: {}", e))?;
+    Ok(copied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ccore::ForkClient;
+
+    #[test]
+    fn fork_starts_from_the_state_of_the_block() {
+        let scheme = Scheme::new_test_solo();
+        let db: Arc<dyn KeyValueDB> = Arc::new(kvdb_memorydb::create(NUM_COLUMNS.unwrap()));
+        let miner = Miner::new(Default::default(), &scheme, None, Arc::clone(&db));
+        let timer_loop = TimerLoop::new(1);
+        let service = client_start(&ClientConfig::default(), &timer_loop, Arc::clone(&db), &scheme, miner).unwrap();
+        let client = service.client();
+        let hash = client.create_fork(client.chain_info().best_block_hash).unwrap();
+        let header = client.block_header(&BlockId::Hash(hash)).unwrap().decode();
+
+        let fork = fork_scheme(Scheme::load_json("solo").unwrap(), &header).unwrap();
+        let fork = Scheme::load(fork.to_string().as_bytes()).unwrap();
+        assert_eq!(*header.state_root(), fork.state_root());
+        assert_eq!(hash, *fork.genesis_header().parent_hash());
+        assert_eq!(scheme.genesis_params(), fork.genesis_params());
+
+        let path = std::env::temp_dir().join(format!("foundry-shadow-fork-{}", std::process::id()));
+        let copied = copy_state(&*db, &path).unwrap();
+        assert_eq!(db.iter(COL_STATE).count(), copied);
+        let fork_db = Database::open(&DatabaseConfig::with_columns(NUM_COLUMNS), path.to_str().unwrap()).unwrap();
+        assert!(fork_db.get(COL_STATE, header.state_root()).unwrap().is_some());
+        drop(fork_db);
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -0,0 +1,322 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::config::ChainType;
+use crate::constants::{DEFAULT_DB_PATH, DEFAULT_KEYS_PATH};
+use crate::json::PasswordEntry;
+use ccore::AccountProvider;
+use ckey::{Generator, KeyPair, NetworkId, Password, PlatformAddress, Random};
+use ckeystore::accounts_dir::RootDiskDirectory;
+use ckeystore::KeyStore;
+use clap::ArgMatches;
+use ctrlc::CtrlC;
+use parking_lot::{Condvar, Mutex};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const SCHEME_FILE: &str = "scheme.json";
+const CONFIG_FILE: &str = "config.toml";
+const PASSWORD_FILE: &str = "password.json";
+const LOG_FILE: &str = "foundry.log";
+/// The number of ports reserved for each node: network, rpc and ws.
+const PORTS_PER_NODE: u16 = 3;
+/// How long the nodes are given to exit by themselves before they are killed.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct TestnetNode {
+    dir: PathBuf,
+    key_pair: KeyPair,
+    network_port: u16,
+    rpc_port: u16,
+    ws_port: u16,
+}
+
+pub fn run_testnet_command(matches: &ArgMatches<'_>) -> Result<(), String> {
+    let validators: usize = parse_arg(matches, "validators")?;
+    let base_port: u16 = parse_arg(matches, "base-port")?;
+    let dir = PathBuf::from(matches.value_of("dir").expect("dir has a default value"));
+    let keep_data = matches.is_present("keep-data");
+
+    if validators == 0 {
+        return Err("At least one validator is required".to_string())
+    }
+    if base_port as usize + validators * PORTS_PER_NODE as usize > u16::max_value() as usize {
+        return Err(format!("Not enough ports above {} for {} validators", base_port, validators))
+    }
+    if dir.exists() {
+        return Err(format!("{} already exists", dir.display()))
+    }
+
+    let result = prepare_and_run(&dir, validators, base_port);
+    if keep_data {
+        println!("The testnet data is kept in {}", dir.display());
+    } else if let Err(err) = fs::remove_dir_all(&dir) {
+        eprintln!("Cannot remove {}: {}", dir.display(), err);
+    }
+    result
+}
+
+fn prepare_and_run(dir: &Path, validators: usize, base_port: u16) -> Result<(), String> {
+    let password = Password::from_str("").expect("Any string is a valid password");
+    let nodes = testnet_nodes(dir, validators, base_port)?;
+
+    fs::create_dir_all(dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    let scheme_path = dir.join(SCHEME_FILE);
+    write_scheme(&scheme_path, &nodes)?;
+    let network_id =
+        ChainType::Custom(path_to_string(&scheme_path)?).scheme().map(|scheme| scheme.genesis_params().network_id())?;
+
+    for node in &nodes {
+        prepare_node(node, &nodes, &scheme_path, network_id, &password)?;
+        println!(
+            "{}: {} network {} rpc {} ws {}",
+            node.dir.display(),
+            PlatformAddress::new_v1(network_id, node.key_pair.address()),
+            node.network_port,
+            node.rpc_port,
+            node.ws_port
+        );
+    }
+
+    let mut children = Vec::with_capacity(nodes.len());
+    for node in &nodes {
+        match spawn_node(node) {
+            Ok(child) => children.push(child),
+            Err(err) => {
+                // The running nodes got no interrupt, so they are killed right away.
+                shut_down(children, Duration::from_secs(0));
+                return Err(err)
+            }
+        }
+    }
+    println!("{} validators are running. Press Ctrl-C to stop them.", children.len());
+
+    wait_for_exit();
+    shut_down(children, SHUTDOWN_TIMEOUT);
+    Ok(())
+}
+
+fn testnet_nodes(dir: &Path, validators: usize, base_port: u16) -> Result<Vec<TestnetNode>, String> {
+    (0..validators)
+        .map(|index| {
+            let key_pair = Random.generate().map_err(|e| format!("Cannot generate a key: {}", e))?;
+            let network_port = base_port + index as u16 * PORTS_PER_NODE;
+            Ok(TestnetNode {
+                dir: dir.join(format!("node{}", index)),
+                key_pair,
+                network_port,
+                rpc_port: network_port + 1,
+                ws_port: network_port + 2,
+            })
+        })
+        .collect()
+}
+
+fn parse_arg<T: FromStr>(matches: &ArgMatches<'_>, name: &str) -> Result<T, String> {
+    let value = matches.value_of(name).expect("The argument has a default value");
+    value.parse().map_err(|_| format!("Invalid {}: {}", name, value))
+}
+
+fn path_to_string(path: &Path) -> Result<String, String> {
+    path.to_str().map(ToString::to_string).ok_or_else(|| format!("{} is not a valid UTF-8 path", path.display()))
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), String> {
+    fs::write(path, contents).map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+}
+
+fn write_scheme(path: &Path, nodes: &[TestnetNode]) -> Result<(), String> {
+    let validators: Vec<_> = nodes.iter().map(|node| node.key_pair.public()).collect();
+    let scheme = serde_json::json!({
+        "extends": "tendermint",
+        "name": "Testnet",
+        "engine": {
+            "tendermint": {
+                "params": {
+                    "validators": validators
+                }
+            }
+        }
+    });
+    write_file(path, &serde_json::to_string_pretty(&scheme).expect("A JSON value can be serialized"))
+}
+
+fn prepare_node(
+    node: &TestnetNode,
+    nodes: &[TestnetNode],
+    scheme_path: &Path,
+    network_id: NetworkId,
+    password: &Password,
+) -> Result<(), String> {
+    let keys_path = node.dir.join(DEFAULT_KEYS_PATH);
+    let keystore_dir =
+        RootDiskDirectory::create(&keys_path).map_err(|e| format!("Cannot create {}: {}", keys_path.display(), e))?;
+    let keystore = KeyStore::open(Box::new(keystore_dir)).map_err(|e| format!("Cannot open key store: {}", e))?;
+    let ap = AccountProvider::new(keystore);
+    ap.insert_account(*node.key_pair.private(), password).map_err(|e| format!("Cannot insert the key: {}", e))?;
+
+    let address = PlatformAddress::new_v1(network_id, node.key_pair.address());
+    let password_path = node.dir.join(PASSWORD_FILE);
+    let entries = vec![PasswordEntry {
+        address,
+        password: password.clone(),
+    }];
+    write_file(&password_path, &serde_json::to_string_pretty(&entries).expect("Password entries can be serialized"))?;
+
+    let bootstrap_addresses = nodes
+        .iter()
+        .filter(|other| other.network_port != node.network_port)
+        .map(|other| format!("\"127.0.0.1:{}\"", other.network_port))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let config = format!(
+        r#"[codechain]
+base_path = "{base_path}"
+db_path = "{db_path}"
+keys_path = "{keys_path}"
+password_path = "{password_path}"
+chain = "{chain}"
+
+[mining]
+engine_signer = "{address}"
+
+[network]
+port = {network_port}
+bootstrap_addresses = [{bootstrap_addresses}]
+
+[rpc]
+port = {rpc_port}
+
+[ipc]
+disable = true
+
+[ws]
+port = {ws_port}
+
+[snapshot]
+disable = true
+"#,
+        base_path = path_to_string(&node.dir)?,
+        db_path = path_to_string(&node.dir.join(DEFAULT_DB_PATH))?,
+        keys_path = path_to_string(&keys_path)?,
+        password_path = path_to_string(&password_path)?,
+        chain = path_to_string(scheme_path)?,
+        address = address,
+        network_port = node.network_port,
+        bootstrap_addresses = bootstrap_addresses,
+        rpc_port = node.rpc_port,
+        ws_port = node.ws_port,
+    );
+    write_file(&node.dir.join(CONFIG_FILE), &config)
+}
+
+fn spawn_node(node: &TestnetNode) -> Result<Child, String> {
+    let executable = std::env::current_exe().map_err(|e| format!("Cannot find the foundry executable: {}", e))?;
+    let log_path = node.dir.join(LOG_FILE);
+    let log = fs::File::create(&log_path).map_err(|e| format!("Cannot create {}: {}", log_path.display(), e))?;
+    let log_err = log.try_clone().map_err(|e| format!("Cannot open {}: {}", log_path.display(), e))?;
+    Command::new(executable)
+        .arg("--config")
+        .arg(node.dir.join(CONFIG_FILE))
+        .stdin(Stdio::null())
+        .stdout(log)
+        .stderr(log_err)
+        .spawn()
+        .map_err(|e| format!("Cannot start the node in {}: {}", node.dir.display(), e))
+}
+
+fn wait_for_exit() {
+    let exit = Arc::new((Mutex::new(()), Condvar::new()));
+
+    let e = exit.clone();
+    CtrlC::set_handler(move || {
+        e.1.notify_all();
+    });
+
+    let mut l = exit.0.lock();
+    exit.1.wait(&mut l);
+}
+
+/// Waits for the nodes, which get the same interrupt as this process, and kills the ones still running after the timeout.
+fn shut_down(mut children: Vec<Child>, timeout: Duration) {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        let mut running = Vec::with_capacity(children.len());
+        for mut child in children {
+            match child.try_wait() {
+                Ok(Some(_)) => {}
+                _ => running.push(child),
+            }
+        }
+        children = running;
+        if children.is_empty() {
+            return
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    for mut child in children {
+        if let Err(err) = child.kill() {
+            eprintln!("Cannot kill the node {}: {}", child.id(), err);
+        }
+        let _ = child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use ccore::Scheme;
+
+    #[test]
+    fn validators_are_configured_to_find_each_other() {
+        let dir = std::env::temp_dir().join(format!("foundry-testnet-{}", std::process::id()));
+        let nodes = testnet_nodes(&dir, 3, 7000).unwrap();
+        fs::create_dir_all(&dir).unwrap();
+        let scheme_path = dir.join(SCHEME_FILE);
+        write_scheme(&scheme_path, &nodes).unwrap();
+
+        let scheme = Scheme::load_json(scheme_path.to_str().unwrap()).unwrap();
+        let validators = serde_json::to_value(nodes.iter().map(|node| node.key_pair.public()).collect::<Vec<_>>());
+        assert_eq!(validators.unwrap(), scheme["engine"]["tendermint"]["params"]["validators"]);
+
+        let network_id = ChainType::Custom(path_to_string(&scheme_path).unwrap())
+            .scheme()
+            .map(|scheme| scheme.genesis_params().network_id())
+            .unwrap();
+        let password = Password::from_str("").unwrap();
+        for node in &nodes {
+            prepare_node(node, &nodes, &scheme_path, network_id, &password).unwrap();
+        }
+
+        let config = fs::read_to_string(nodes[1].dir.join(CONFIG_FILE)).unwrap();
+        let config: Config = toml::from_str(&config).unwrap();
+        assert_eq!(Some(7003), config.network.port);
+        assert_eq!(Some(7004), config.rpc.port);
+        assert_eq!(Some(7005), config.ws.port);
+        let bootstrap_addresses = vec!["127.0.0.1:7000".to_string(), "127.0.0.1:7006".to_string()];
+        assert_eq!(Some(bootstrap_addresses), config.network.bootstrap_addresses);
+        let signer = PlatformAddress::new_v1(network_id, nodes[1].key_pair.address());
+        assert_eq!(Some(signer), config.mining.engine_signer);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
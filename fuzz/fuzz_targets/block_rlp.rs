@@ -0,0 +1,35 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#![no_main]
+
+use ccore::Block;
+use codechain_core as ccore;
+use codechain_types as ctypes;
+use ctypes::header::Seal;
+use ctypes::Header;
+use libfuzzer_sys::fuzz_target;
+
+// A header or a block as it is received from a peer.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(header) = rlp::decode::<Header>(data) {
+        assert_eq!(header, rlp::decode(&rlp::encode(&header)).expect("An encoded header is decoded"));
+        let _ = header.hash();
+    }
+    if let Ok(block) = rlp::decode::<Block>(data) {
+        assert_eq!(block, rlp::decode(&block.rlp_bytes(&Seal::With)).expect("An encoded block is decoded"));
+    }
+});
@@ -0,0 +1,42 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#![no_main]
+
+use ccore::TendermintMessage;
+use cnetwork::{P2pMessage, SignedP2pMessage};
+use codechain_core as ccore;
+use codechain_network as cnetwork;
+use codechain_sync as csync;
+use csync::BlockSyncMessage;
+use libfuzzer_sys::fuzz_target;
+use rlp::Rlp;
+
+// A message as it is read from the stream of a peer, and the extension messages carried by it.
+fuzz_target!(|data: &[u8]| {
+    let rlp = Rlp::new(data);
+    if let Ok(signed) = rlp.as_val::<SignedP2pMessage>() {
+        let _ = Rlp::new(&signed.message).as_val::<P2pMessage>();
+    }
+    let _ = rlp.as_val::<P2pMessage>();
+
+    if let Ok(message) = rlp.as_val::<BlockSyncMessage>() {
+        assert_eq!(message, rlp::decode(&rlp::encode(&message)).expect("An encoded block sync message is decoded"));
+    }
+    if let Ok(message) = rlp.as_val::<TendermintMessage>() {
+        assert_eq!(message, rlp::decode(&rlp::encode(&message)).expect("An encoded tendermint message is decoded"));
+    }
+});
@@ -0,0 +1,35 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#![no_main]
+
+use cjson::scheme::Scheme;
+use codechain_json as cjson;
+use codechain_types as ctypes;
+use ctypes::{CommonParams, ForkSchedule};
+use std::convert::TryFrom;
+use libfuzzer_sys::fuzz_target;
+
+// A scheme file given by the operator. The base schemes aren't resolved, so the target doesn't read files.
+// The engine isn't built, because it spawns threads.
+fuzz_target!(|data: &[u8]| {
+    let scheme = match Scheme::load(data) {
+        Ok(scheme) => scheme,
+        Err(_) => return,
+    };
+    let _ = CommonParams::from(scheme.params).verify();
+    let _ = scheme.forks.map(ForkSchedule::try_from);
+});
@@ -0,0 +1,31 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#![no_main]
+
+use ccore::{SignedTransaction, UnverifiedTransaction};
+use codechain_core as ccore;
+use libfuzzer_sys::fuzz_target;
+
+// A transaction as it is received from a peer or the RPC.
+fuzz_target!(|data: &[u8]| {
+    let tx = match rlp::decode::<UnverifiedTransaction>(data) {
+        Ok(tx) => tx,
+        Err(_) => return,
+    };
+    assert_eq!(tx, rlp::decode(&rlp::encode(&tx)).expect("An encoded transaction is decoded"));
+    let _ = SignedTransaction::try_new(tx);
+});
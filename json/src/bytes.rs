@@ -152,9 +152,23 @@ impl<'a> Serialize for WithoutPrefix<Bytes> {
     }
 }
 
+impl<'a> Deserialize<'a> for WithoutPrefix<Bytes> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'a>, {
+        Bytes::deserialize(deserializer).map(WithoutPrefix)
+    }
+}
+
+impl WithoutPrefix<Bytes> {
+    pub fn into_inner(self) -> Bytes {
+        self.0
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::bytes::Bytes;
+    use crate::bytes::{Bytes, WithoutPrefix};
     use serde_json;
     use std::result::Result;
 
@@ -214,6 +228,12 @@ mod test {
         assert_eq!(serialized, r#""0123456789abcdef""#);
     }
 
+    #[test]
+    fn bytes_deserialize_without_prefix() {
+        let bytes: WithoutPrefix<Bytes> = serde_json::from_str(r#""0123456789abcdef""#).unwrap();
+        assert_eq!(Bytes(vec![0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef]), bytes.into_inner());
+    }
+
     #[test]
     fn bytes_into() {
         let bytes = Bytes(vec![0xff, 0x11]);
@@ -0,0 +1,233 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Golden vectors checking that the JSON and the RLP encodings round-trip byte-identically.
+//!
+//! A vector file names a type and lists its encodings. Decoding an encoding and encoding it again must give the same
+//! bytes. The JSON encodings are compared in the canonical form: compact, with the keys of the objects sorted.
+
+use crate::bytes::Bytes;
+use rlp::{Decodable, Encodable};
+use rustc_hex::ToHex;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct VectorFile {
+    #[serde(rename = "type")]
+    pub type_name: String,
+    pub vectors: Vec<Vector>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Vector {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rlp: Option<Bytes>,
+}
+
+/// Sorts the keys of the objects recursively and prints the value without whitespace.
+pub fn canonical_json(value: &Value) -> String {
+    fn sorted(value: &Value) -> Value {
+        match value {
+            Value::Object(object) => {
+                let entries: BTreeMap<_, _> = object.iter().map(|(key, value)| (key.clone(), sorted(value))).collect();
+                Value::Object(entries.into_iter().collect::<Map<_, _>>())
+            }
+            Value::Array(values) => Value::Array(values.iter().map(sorted).collect()),
+            value => value.clone(),
+        }
+    }
+    sorted(value).to_string()
+}
+
+pub fn check_json<T: Serialize + DeserializeOwned>(value: &Value) -> Result<(), String> {
+    let decoded: T = serde_json::from_value(value.clone()).map_err(|e| format!("Cannot decode the JSON: {}", e))?;
+    let encoded = serde_json::to_value(&decoded).map_err(|e| format!("Cannot encode the JSON: {}", e))?;
+    let (expected, actual) = (canonical_json(value), canonical_json(&encoded));
+    if expected != actual {
+        return Err(format!("The JSON is encoded to {} instead of {}", actual, expected))
+    }
+    Ok(())
+}
+
+pub fn check_rlp<T: Encodable + Decodable>(bytes: &[u8]) -> Result<(), String> {
+    let decoded: T = rlp::decode(bytes).map_err(|e| format!("Cannot decode the RLP: {:?}", e))?;
+    let encoded = rlp::encode(&decoded);
+    if &*encoded != bytes {
+        return Err(format!("The RLP is encoded to 0x{} instead of 0x{}", encoded.to_hex(), bytes.to_hex()))
+    }
+    Ok(())
+}
+
+type Check<T> = Box<dyn Fn(&T) -> Result<(), String>>;
+
+#[derive(Default)]
+struct Checks {
+    json: Option<Check<Value>>,
+    rlp: Option<Check<[u8]>>,
+}
+
+/// The types checked against the vector files, by their names in the files.
+#[derive(Default)]
+pub struct Registry {
+    types: BTreeMap<String, Checks>,
+}
+
+impl Registry {
+    pub fn json<T: Serialize + DeserializeOwned + 'static>(mut self, type_name: &str) -> Self {
+        self.types.entry(type_name.to_string()).or_default().json = Some(Box::new(check_json::<T>));
+        self
+    }
+
+    pub fn rlp<T: Encodable + Decodable + 'static>(mut self, type_name: &str) -> Self {
+        self.types.entry(type_name.to_string()).or_default().rlp = Some(Box::new(check_rlp::<T>));
+        self
+    }
+
+    /// Checks the vector files in `dir`, and returns the number of the checked vectors.
+    /// Every registered type must have vectors for its encodings, and every vector file must name a registered type.
+    pub fn check_dir(&self, dir: &Path) -> Result<usize, Vec<String>> {
+        let mut failures = Vec::new();
+        let mut files = Vec::new();
+        let entries = fs::read_dir(dir).map_err(|e| vec![format!("Cannot read {}: {}", dir.display(), e)])?;
+        for entry in entries {
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(e) => return Err(vec![format!("Cannot read {}: {}", dir.display(), e)]),
+            };
+            if path.extension().map_or(false, |extension| extension == "json") {
+                files.push(path);
+            }
+        }
+        files.sort();
+
+        let mut checked = 0;
+        let mut covered: BTreeMap<&str, (bool, bool)> = BTreeMap::new();
+        for path in files {
+            let file: VectorFile = match fs::read(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| serde_json::from_slice(&bytes).map_err(|e| e.to_string()))
+            {
+                Ok(file) => file,
+                Err(e) => {
+                    failures.push(format!("{}: {}", path.display(), e));
+                    continue
+                }
+            };
+            let (type_name, checks) = match self.types.get_key_value(&file.type_name) {
+                Some(entry) => entry,
+                None => {
+                    failures.push(format!("{}: {} is not registered", path.display(), file.type_name));
+                    continue
+                }
+            };
+            let coverage = covered.entry(type_name.as_str()).or_default();
+            for vector in &file.vectors {
+                if let Some(json) = &vector.json {
+                    if let Err(e) = check(&checks.json, json) {
+                        failures.push(format!("{} {} (JSON): {}", type_name, vector.name, e));
+                    }
+                    coverage.0 = true;
+                }
+                if let Some(rlp) = &vector.rlp {
+                    if let Err(e) = check(&checks.rlp, &**rlp) {
+                        failures.push(format!("{} {} (RLP): {}", type_name, vector.name, e));
+                    }
+                    coverage.1 = true;
+                }
+                checked += 1;
+            }
+        }
+
+        for (type_name, checks) in &self.types {
+            let (json, rlp) = covered.get(type_name.as_str()).cloned().unwrap_or_default();
+            if checks.json.is_some() && !json {
+                failures.push(format!("{} has no JSON vector", type_name));
+            }
+            if checks.rlp.is_some() && !rlp {
+                failures.push(format!("{} has no RLP vector", type_name));
+            }
+        }
+
+        if failures.is_empty() {
+            Ok(checked)
+        } else {
+            Err(failures)
+        }
+    }
+}
+
+fn check<T: ?Sized>(check: &Option<Check<T>>, encoded: &T) -> Result<(), String> {
+    match check {
+        Some(check) => check(encoded),
+        None => Err("The encoding is not registered".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheme::{
+        Account, BlakePoW, Cuckoo, Engine, Genesis, Lock, NullEngine, Params, Scheme, Seal, Shard, Solo, Tendermint,
+    };
+    use crate::uint::Uint;
+    use std::path::PathBuf;
+
+    #[test]
+    fn canonical_json_sorts_the_keys() {
+        let value: Value = serde_json::from_str(r#"{ "b": [ { "d": 1, "c": null } ], "a": "0x0" }"#).unwrap();
+        assert_eq!(r#"{"a":"0x0","b":[{"c":null,"d":1}]}"#, canonical_json(&value));
+    }
+
+    #[test]
+    fn non_canonical_encoding_is_rejected() {
+        assert_eq!(Ok(()), check_json::<Uint>(&Value::String("0x64".to_string())));
+        assert!(check_json::<Uint>(&Value::String("0x064".to_string())).is_err());
+        assert!(check_json::<Uint>(&Value::from(100)).is_err());
+    }
+
+    #[test]
+    fn scheme_vectors() {
+        let registry = Registry::default()
+            .json::<Account>("Account")
+            .json::<Lock>("Lock")
+            .json::<BlakePoW>("BlakePoW")
+            .json::<Cuckoo>("Cuckoo")
+            .json::<Engine>("Engine")
+            .json::<Genesis>("Genesis")
+            .json::<NullEngine>("NullEngine")
+            .json::<Params>("Params")
+            .json::<Scheme>("Scheme")
+            .json::<Seal>("Seal")
+            .json::<Shard>("Shard")
+            .json::<Solo>("Solo")
+            .json::<Tendermint>("Tendermint");
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("vectors").join("scheme");
+        match registry.check_dir(&dir) {
+            Ok(checked) => assert_ne!(0, checked),
+            Err(failures) => panic!("{}", failures.join("\n")),
+        }
+    }
+}
@@ -19,6 +19,7 @@ extern crate codechain_key as ckey;
 extern crate serde_derive;
 
 pub mod bytes;
+pub mod conformance;
 pub mod hash;
 pub mod scheme;
 pub mod uint;
@@ -17,12 +17,17 @@
 use crate::uint::Uint;
 
 /// Scheme account.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct Account {
     /// Balance.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub balance: Option<Uint>,
     /// Seq.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub seq: Option<Uint>,
+    /// Lock-up schedule of the balance.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lock: Option<Lock>,
 }
 
 impl Account {
@@ -32,11 +37,29 @@ impl Account {
     }
 }
 
+/// Lock-up schedule of a genesis account.
+///
+/// `amount` is locked until `start + cliff`, and then unlocked linearly until `start + period`.
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Lock {
+    /// The amount of the balance that is locked.
+    pub amount: Uint,
+    /// The block number from which the schedule starts. 0 if omitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<Uint>,
+    /// The number of blocks after `start` until the unlocking begins. 0 if omitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cliff: Option<Uint>,
+    /// The number of blocks after `start` until the whole amount is unlocked.
+    pub period: Uint,
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json;
 
-    use super::Account;
+    use super::{Account, Lock};
 
     #[test]
     fn account_deserialization() {
@@ -48,5 +71,28 @@ mod tests {
         assert!(!deserialized.is_empty());
         assert_eq!(deserialized.balance, Some(1.into()));
         assert_eq!(deserialized.seq, Some(0.into()));
+        assert_eq!(deserialized.lock, None);
+    }
+
+    #[test]
+    fn account_with_lock_deserialization() {
+        let s = r#"{
+            "balance": "1000",
+            "lock": {
+                "amount": "600",
+                "cliff": "100",
+                "period": "1000"
+            }
+        }"#;
+        let deserialized: Account = serde_json::from_str(s).unwrap();
+        assert_eq!(
+            deserialized.lock,
+            Some(Lock {
+                amount: 600.into(),
+                start: None,
+                cliff: Some(100.into()),
+                period: 1000.into(),
+            })
+        );
     }
 }
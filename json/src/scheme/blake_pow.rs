@@ -16,17 +16,21 @@
 
 use crate::uint::Uint;
 
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BlakePoWParams {
     /// Block reward.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub block_reward: Option<Uint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub min_score: Option<Uint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub block_interval: Option<Uint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub recommended_confirmation: Option<Uint>,
 }
 
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct BlakePoW {
     pub params: BlakePoWParams,
 }
@@ -16,20 +16,27 @@
 
 use crate::uint::Uint;
 
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CuckooParams {
     /// Block reward.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub block_reward: Option<Uint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub block_interval: Option<Uint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub min_score: Option<Uint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_vertex: Option<Uint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_edge: Option<Uint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub cycle_length: Option<Uint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub recommended_confirmation: Option<Uint>,
 }
 
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct Cuckoo {
     pub params: CuckooParams,
 }
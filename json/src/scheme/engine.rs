@@ -17,7 +17,7 @@
 use super::{NullEngine, Solo, Tendermint};
 
 /// Engine deserialization.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum Engine {
     /// Null engine.
@@ -0,0 +1,195 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use serde::de::Error as _;
+use serde_json::{Error, Value};
+
+/// The key of a scheme which names the base scheme it overrides.
+pub const EXTENDS_KEY: &str = "extends";
+/// The key of a scheme which lists the dotted paths of the keys removed from the base scheme.
+pub const UNSET_KEY: &str = "unset";
+
+/// The maximum length of an `extends` chain. Prevents infinite recursion on cyclic includes.
+const MAX_EXTENDS_DEPTH: usize = 16;
+
+/// Resolves the `extends` key of the given scheme, recursively.
+///
+/// `resolve` maps the value of `extends` to the base scheme. The sections in `scheme` are merged
+/// on top of the base:
+///  - objects are merged key by key,
+///  - `null` removes the key from the base,
+///  - any other value replaces the value of the base.
+///
+/// The formats without `null`, like TOML, remove the keys by listing their paths in `unset`,
+/// e.g. `unset = ["forks.batch"]`.
+pub fn resolve_extends<F>(scheme: Value, mut resolve: F) -> Result<Value, Error>
+where
+    F: FnMut(&str) -> Result<Value, String>, {
+    resolve_extends_with_depth(scheme, &mut resolve, 0)
+}
+
+fn resolve_extends_with_depth<F>(mut scheme: Value, resolve: &mut F, depth: usize) -> Result<Value, Error>
+where
+    F: FnMut(&str) -> Result<Value, String>, {
+    let unset = match scheme.as_object_mut().and_then(|object| object.remove(UNSET_KEY)) {
+        None => Vec::new(),
+        Some(Value::Array(paths)) => paths
+            .into_iter()
+            .map(|path| match path {
+                Value::String(path) => Ok(path),
+                other => Err(Error::custom(format!("`{}` must list strings, but {} given", UNSET_KEY, other))),
+            })
+            .collect::<Result<_, _>>()?,
+        Some(other) => return Err(Error::custom(format!("`{}` must be an array, but {} given", UNSET_KEY, other))),
+    };
+    let base_name = match scheme.as_object_mut().and_then(|object| object.remove(EXTENDS_KEY)) {
+        None if unset.is_empty() => return Ok(scheme),
+        None => return Err(Error::custom(format!("`{}` is given without `{}`", UNSET_KEY, EXTENDS_KEY))),
+        Some(Value::String(base_name)) => base_name,
+        Some(other) => return Err(Error::custom(format!("`{}` must be a string, but {} given", EXTENDS_KEY, other))),
+    };
+    if depth >= MAX_EXTENDS_DEPTH {
+        return Err(Error::custom(format!("Too deep `{}` chain at {}", EXTENDS_KEY, base_name)))
+    }
+    let base = resolve(&base_name).map_err(Error::custom)?;
+    let mut base = resolve_extends_with_depth(base, resolve, depth + 1)?;
+    for path in &unset {
+        remove_path(&mut base, path);
+    }
+    Ok(merge(base, scheme))
+}
+
+/// Removes the value at the dotted `path`, if it exists.
+fn remove_path(value: &mut Value, path: &str) {
+    let mut keys: Vec<_> = path.split('.').collect();
+    let last = keys.pop().expect("Split returns at least one item");
+    if let Some(Value::Object(object)) = keys.into_iter().try_fold(value, |value, key| value.get_mut(key)) {
+        object.remove(last);
+    }
+}
+
+/// Merges `overrides` on top of `base`.
+pub fn merge(base: Value, overrides: Value) -> Value {
+    match (base, overrides) {
+        (Value::Object(mut base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                if value.is_null() {
+                    base.remove(&key);
+                    continue
+                }
+                let merged = match base.remove(&key) {
+                    Some(base_value) => merge(base_value, value),
+                    None => value,
+                };
+                base.insert(key, merged);
+            }
+            Value::Object(base)
+        }
+        (_, overrides) => overrides,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn scheme_without_extends_is_not_changed() {
+        let scheme = json!({"name": "a", "params": {"minPayCost": 10}});
+        let resolved = resolve_extends(scheme.clone(), |_| unreachable!()).unwrap();
+        assert_eq!(scheme, resolved);
+    }
+
+    #[test]
+    fn override_only_specified_fields() {
+        let base = json!({
+            "name": "base",
+            "params": {"minPayCost": 10, "networkID": "tc"},
+            "nodes": ["a", "b"],
+        });
+        let scheme = json!({
+            "extends": "base",
+            "name": "private",
+            "params": {"networkID": "pv"},
+            "nodes": ["c"],
+        });
+        let resolved = resolve_extends(scheme, |name| {
+            assert_eq!("base", name);
+            Ok(base.clone())
+        })
+        .unwrap();
+        assert_eq!(
+            json!({
+                "name": "private",
+                "params": {"minPayCost": 10, "networkID": "pv"},
+                "nodes": ["c"],
+            }),
+            resolved
+        );
+    }
+
+    #[test]
+    fn null_removes_field() {
+        let scheme = json!({"extends": "base", "nodes": null});
+        let resolved = resolve_extends(scheme, |_| Ok(json!({"name": "base", "nodes": ["a"]}))).unwrap();
+        assert_eq!(json!({"name": "base"}), resolved);
+    }
+
+    #[test]
+    fn unset_removes_fields() {
+        let scheme = json!({"extends": "base", "unset": ["nodes", "params.minPayCost", "params.unknown"]});
+        let base = json!({"name": "base", "params": {"minPayCost": 10, "networkID": "tc"}, "nodes": ["a"]});
+        let resolved = resolve_extends(scheme, |_| Ok(base.clone())).unwrap();
+        assert_eq!(json!({"name": "base", "params": {"networkID": "tc"}}), resolved);
+    }
+
+    #[test]
+    fn unset_needs_extends() {
+        assert!(resolve_extends(json!({"unset": ["nodes"]}), |_| unreachable!()).is_err());
+        assert!(resolve_extends(json!({"extends": "base", "unset": "nodes"}), |_| Ok(json!({}))).is_err());
+    }
+
+    #[test]
+    fn nested_extends() {
+        let scheme = json!({"extends": "middle", "c": 3});
+        let resolved = resolve_extends(scheme, |name| match name {
+            "middle" => Ok(json!({"extends": "bottom", "b": 2, "a": 0})),
+            "bottom" => Ok(json!({"a": 1})),
+            _ => unreachable!(),
+        })
+        .unwrap();
+        assert_eq!(json!({"a": 0, "b": 2, "c": 3}), resolved);
+    }
+
+    #[test]
+    fn cyclic_extends_fails() {
+        let scheme = json!({"extends": "self"});
+        assert!(resolve_extends(scheme, |_| Ok(json!({"extends": "self"}))).is_err());
+    }
+
+    #[test]
+    fn extends_must_be_a_string() {
+        let scheme = json!({"extends": 1});
+        assert!(resolve_extends(scheme, |_| unreachable!()).is_err());
+    }
+
+    #[test]
+    fn merge_replaces_non_object_values() {
+        let merged = merge(json!({"a": {"b": 1}}), json!({"a": 2}));
+        assert_eq!(json!({"a": 2}), merged);
+    }
+}
@@ -0,0 +1,40 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::uint::Uint;
+use std::collections::BTreeMap;
+
+/// Named protocol upgrades and the block numbers at which they are activated.
+pub type Forks = BTreeMap<String, Uint>;
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::Forks;
+
+    #[test]
+    fn forks_deserialization() {
+        let s = r#"{
+            "fastTermTransition": 100,
+            "newFeeRule": "0x200"
+        }"#;
+        let deserialized: Forks = serde_json::from_str(s).unwrap();
+        assert_eq!(2, deserialized.len());
+        assert_eq!(Some(&100.into()), deserialized.get("fastTermTransition"));
+        assert_eq!(Some(&0x200.into()), deserialized.get("newFeeRule"));
+    }
+}
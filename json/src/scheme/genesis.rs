@@ -21,7 +21,7 @@ use crate::uint::Uint;
 use ckey::PlatformAddress;
 
 /// Scheme genesis.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Genesis {
     /// Seal.
@@ -29,16 +29,22 @@ pub struct Genesis {
     /// Score. Difficulty in PoW.
     pub score: Uint,
     /// Block author, defaults to 0.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub author: Option<PlatformAddress>,
     /// Block timestamp, defaults to 0.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub timestamp: Option<Uint>,
     /// Parent hash, defaults to 0.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub parent_hash: Option<H256>,
     /// Transactions root.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub transactions_root: Option<H256>,
     /// State root.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub state_root: Option<H256>,
     /// Extra data.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub extra_data: Option<Bytes>,
 }
 
@@ -18,6 +18,8 @@ mod account;
 mod blake_pow;
 mod cuckoo;
 mod engine;
+mod extends;
+mod forks;
 mod genesis;
 mod null_engine;
 mod params;
@@ -29,10 +31,12 @@ mod solo;
 mod state;
 mod tendermint;
 
-pub use self::account::Account;
+pub use self::account::{Account, Lock};
 pub use self::blake_pow::{BlakePoW, BlakePoWParams};
 pub use self::cuckoo::{Cuckoo, CuckooParams};
 pub use self::engine::Engine;
+pub use self::extends::{merge, resolve_extends, EXTENDS_KEY, UNSET_KEY};
+pub use self::forks::Forks;
 pub use self::genesis::Genesis;
 pub use self::null_engine::{NullEngine, NullEngineParams};
 pub use self::params::Params;
@@ -15,17 +15,28 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use crate::uint::Uint;
+use ckey::PlatformAddress;
 
 /// Authority params deserialization.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct NullEngineParams {
     /// Block reward.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub block_reward: Option<Uint>,
+    /// The minimum interval between blocks in seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_interval: Option<Uint>,
+    /// The authors who produce blocks in turn.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author_rotation: Option<Vec<PlatformAddress>>,
+    /// Overrides the maximum body size of the common params.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_body_size: Option<Uint>,
 }
 
 /// Null engine descriptor
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct NullEngine {
     pub params: NullEngineParams,
 }
@@ -33,6 +44,7 @@ pub struct NullEngine {
 #[cfg(test)]
 mod tests {
     use serde_json;
+    use std::str::FromStr;
 
     use super::*;
 
@@ -46,5 +58,34 @@ mod tests {
 
         let deserialized: NullEngine = serde_json::from_str(s).unwrap();
         assert_eq!(deserialized.params.block_reward, Some(0x0d.into()));
+        assert_eq!(deserialized.params.block_interval, None);
+        assert_eq!(deserialized.params.author_rotation, None);
+        assert_eq!(deserialized.params.max_body_size, None);
+    }
+
+    #[test]
+    fn null_engine_deserialization_with_test_params() {
+        let s = r#"{
+            "params": {
+                "blockReward": "0x0d",
+                "blockInterval": 5,
+                "authorRotation": [
+                    "tccqyqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqyca3rwt",
+                    "tccqyqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqgfrhflv"
+                ],
+                "maxBodySize": 1000
+            }
+        }"#;
+
+        let deserialized: NullEngine = serde_json::from_str(s).unwrap();
+        assert_eq!(deserialized.params.block_interval, Some(5.into()));
+        assert_eq!(
+            deserialized.params.author_rotation,
+            Some(vec![
+                PlatformAddress::from_str("tccqyqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqyca3rwt").unwrap(),
+                PlatformAddress::from_str("tccqyqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqgfrhflv").unwrap(),
+            ])
+        );
+        assert_eq!(deserialized.params.max_body_size, Some(1000.into()));
     }
 }
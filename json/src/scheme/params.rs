@@ -54,18 +54,28 @@ pub struct Params {
     /// Snapshot creation period in unit of block numbers.
     pub snapshot_period: Uint,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub term_seconds: Option<Uint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub nomination_expiration: Option<Uint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub custody_period: Option<Uint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub release_period: Option<Uint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_num_of_validators: Option<Uint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub min_num_of_validators: Option<Uint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub delegation_threshold: Option<Uint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub min_deposit: Option<Uint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_candidate_metadata_size: Option<Uint>,
 
     /// A monotonically increasing number to denote the consensus version.
     /// It is increased when we fork.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub era: Option<Uint>,
 }
 
@@ -14,29 +14,42 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use super::{Accounts, Engine, Genesis, Params, Shards};
+use super::extends::resolve_extends;
+use super::{Accounts, Engine, Forks, Genesis, Params, Shards};
+use crate::bytes::Bytes;
+use crate::hash::H256;
 use serde_json;
-use serde_json::Error;
+use serde_json::{Error, Value};
+use std::collections::BTreeMap;
 use std::io::Read;
 
 /// Scheme deserialization.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Scheme {
     /// Scheme name.
     pub name: String,
     /// Special fork name.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub data_dir: Option<String>,
     /// Engine.
     pub engine: Engine,
     /// Scheme params.
     pub params: Params,
+    /// Activation block numbers of protocol upgrades.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forks: Option<Forks>,
     /// Genesis header.
     pub genesis: Genesis,
     /// Genesis state.
     pub accounts: Accounts,
     pub shards: Shards,
+    /// Compressed chunks of the genesis state tries, keyed by their roots.
+    /// They replace the genesis state made from `accounts`, `shards` and the engine.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_chunks: Option<BTreeMap<H256, Bytes>>,
     /// Boot nodes.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub nodes: Option<Vec<String>>,
 }
 
@@ -45,7 +58,16 @@ impl Scheme {
     pub fn load<R>(reader: R) -> Result<Self, Error>
     where
         R: Read, {
-        serde_json::from_reader(reader)
+        Self::load_extended(reader, |base| Err(format!("Cannot resolve the base scheme {}", base)))
+    }
+
+    /// Loads test from json, resolving the base schemes named by `extends` with `resolve`.
+    pub fn load_extended<R, F>(reader: R, resolve: F) -> Result<Self, Error>
+    where
+        R: Read,
+        F: FnMut(&str) -> Result<Value, String>, {
+        let scheme: Value = serde_json::from_reader(reader)?;
+        serde_json::from_value(resolve_extends(scheme, resolve)?)
     }
 }
 
@@ -96,6 +118,9 @@ mod tests {
                 "maxBodySize": 4194304,
                 "snapshotPeriod": 16384
             },
+            "forks": {
+                "fastTermTransition": 100
+            },
             "genesis": {
                 "seal": {
                     "tendermint": {
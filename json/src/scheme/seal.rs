@@ -19,7 +19,7 @@ use crate::hash::H520;
 use crate::uint::Uint;
 
 /// Tendermint seal.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct TendermintSeal {
     /// Seal round.
     pub prev_view: Uint,
@@ -30,7 +30,7 @@ pub struct TendermintSeal {
 }
 
 /// Seal variants.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum Seal {
     /// Tendermint seal.
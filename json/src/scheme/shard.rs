@@ -17,10 +17,12 @@
 use crate::uint::Uint;
 use ckey::PlatformAddress;
 
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct Shard {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub seq: Option<Uint>,
     pub owners: Vec<PlatformAddress>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub users: Option<Vec<PlatformAddress>>,
 }
 
@@ -19,24 +19,27 @@ use ckey::PlatformAddress;
 use std::collections::HashMap;
 
 /// Solo params deserialization.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SoloParams {
     /// Block reward.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub block_reward: Option<Uint>,
     #[serde(flatten)]
     pub action_handlers: SoloActionHandlersParams,
 }
 
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SoloActionHandlersParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub hit: Option<HashMap<(), ()>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub genesis_stakes: Option<HashMap<PlatformAddress, u64>>,
 }
 
 /// Solo engine deserialization.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct Solo {
     pub params: SoloParams,
 }
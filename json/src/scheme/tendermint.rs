@@ -19,37 +19,54 @@ use ckey::{PlatformAddress, Public};
 use std::collections::HashMap;
 
 /// Tendermint params deserialization.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TendermintParams {
     /// Valid validators.
     pub validators: Vec<Public>,
     /// Propose step timeout in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout_propose: Option<Uint>,
     /// Propose step timeout delta in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout_propose_delta: Option<Uint>,
     /// Prevote step timeout in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout_prevote: Option<Uint>,
     /// Prevote step timeout delta in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout_prevote_delta: Option<Uint>,
     /// Precommit step timeout in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout_precommit: Option<Uint>,
     /// Precommit step timeout delta in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout_precommit_delta: Option<Uint>,
     /// Commit step timeout in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout_commit: Option<Uint>,
     /// Reward per block.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub block_reward: Option<Uint>,
     /// How much tokens are distributed at Genesis?
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub genesis_stakes: Option<HashMap<PlatformAddress, u64>>,
     /// allowed past time gap in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub allowed_past_timegap: Option<Uint>,
     /// allowed future time gap in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub allowed_future_timegap: Option<Uint>,
+    /// Percentage of the missed proposals and votes in a term that jails a validator.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_miss_rate: Option<Uint>,
+    /// The number of blocks in a term before the miss rate is checked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_miss_rate_blocks: Option<Uint>,
 }
 
 /// Tendermint engine deserialization.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct Tendermint {
     pub params: TendermintParams,
 }
@@ -38,6 +38,7 @@ pub enum Error {
     Bech32InvalidData(u8),
     Bech32MixedCase,
     Bech32UnknownHRP,
+    InvalidTypedData(String),
     Custom(String),
 }
 
@@ -63,6 +64,7 @@ impl fmt::Display for Error {
             Error::Bech32InvalidData(_) => "Invalid data point".into(),
             Error::Bech32MixedCase => "Mixed-case strings not allowed".into(),
             Error::Bech32UnknownHRP => "Unknown human-readable part".into(),
+            Error::InvalidTypedData(ref detail) => format!("Invalid typed data: {}", detail),
             Error::Custom(ref s) => s.clone(),
         };
 
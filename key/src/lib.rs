@@ -18,6 +18,9 @@ extern crate codechain_crypto as crypto;
 #[macro_use]
 extern crate lazy_static;
 extern crate bech32;
+extern crate libc;
+#[macro_use]
+extern crate log;
 extern crate never_type;
 extern crate parking_lot;
 extern crate primitives;
@@ -41,8 +44,10 @@ mod network;
 mod password;
 mod platform_address;
 mod private;
+mod protected;
 mod random;
 mod schnorr;
+mod typed_data;
 
 pub use crate::address::Address;
 pub use crate::ecdsa::{
@@ -56,10 +61,12 @@ pub use crate::network::NetworkId;
 pub use crate::password::Password;
 pub use crate::platform_address::PlatformAddress;
 pub use crate::private::Private;
+pub use crate::protected::{scrub, Protected};
 pub use crate::random::Random;
 pub use crate::schnorr::{
     recover_schnorr, sign_schnorr, verify_schnorr, verify_schnorr_address, SchnorrSignature, SCHNORR_SIGNATURE_LENGTH,
 };
+pub use crate::typed_data::{TypedData, TypedDataDomain, TypedField};
 use primitives::{H256, H512};
 pub use rustc_serialize::hex;
 
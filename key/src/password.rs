@@ -14,10 +14,11 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use crate::protected::scrub;
 use crypto::Password as CryptoPassword;
 use never_type::Never;
+use std::fmt;
 use std::str::FromStr;
-use std::{fmt, ptr};
 
 #[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Password(String);
@@ -37,11 +38,7 @@ impl Password {
 // Custom drop impl to zero out memory.
 impl Drop for Password {
     fn drop(&mut self) {
-        unsafe {
-            for byte_ref in self.0.as_mut_vec() {
-                ptr::write_volatile(byte_ref, 0)
-            }
-        }
+        scrub(unsafe { self.0.as_mut_vec() })
     }
 }
 
@@ -0,0 +1,198 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::alloc::{self, Layout};
+use std::ops::{Deref, DerefMut};
+use std::ptr::{self, NonNull};
+use std::sync::Once;
+use std::{fmt, mem};
+
+/// Overwrites `bytes` with zeros in a way the optimizer cannot elide.
+pub fn scrub(bytes: &mut [u8]) {
+    for byte in bytes.iter_mut() {
+        unsafe { ptr::write_volatile(byte, 0) };
+    }
+}
+
+/// A heap cell for secret material.
+///
+/// The value lives on its own page, which is locked in memory and excluded from core dumps where the
+/// platform allows it, so it is never written to swap. The page is scrubbed before it is released.
+///
+/// Build the value in place, through `default` and `DerefMut`, or move it in with `new`, which resets the source.
+pub struct Protected<T: Copy> {
+    ptr: NonNull<T>,
+    layout: Layout,
+}
+
+unsafe impl<T: Copy + Send> Send for Protected<T> {}
+unsafe impl<T: Copy + Sync> Sync for Protected<T> {}
+
+impl<T: Copy + Default> Protected<T> {
+    /// Copies the value into the protected page and resets the source to the default value.
+    pub fn new(value: &mut T) -> Self {
+        let protected = Self::allocate();
+        unsafe {
+            ptr::copy_nonoverlapping(value, protected.ptr.as_ptr(), 1);
+            ptr::write_volatile(value, T::default());
+        }
+        protected
+    }
+}
+
+impl<T: Copy> Protected<T> {
+    // The page is zeroed, so it must be initialized before it's read.
+    fn allocate() -> Self {
+        let size = round_up(mem::size_of::<T>().max(1), page_size());
+        let align = mem::align_of::<T>().max(page_size());
+        let layout = Layout::from_size_align(size, align).expect("Page size is a power of two");
+        let ptr = unsafe { alloc::alloc_zeroed(layout) } as *mut T;
+        let ptr = NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        unsafe { lock(ptr.as_ptr() as *mut u8, layout.size()) };
+        Protected {
+            ptr,
+            layout,
+        }
+    }
+}
+
+impl<T: Copy + Default> Default for Protected<T> {
+    fn default() -> Self {
+        let protected = Self::allocate();
+        unsafe { ptr::write(protected.ptr.as_ptr(), T::default()) };
+        protected
+    }
+}
+
+impl<T: Copy> Deref for Protected<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T: Copy> DerefMut for Protected<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<T: Copy> Clone for Protected<T> {
+    fn clone(&self) -> Self {
+        let cloned = Self::allocate();
+        unsafe { ptr::copy_nonoverlapping(self.ptr.as_ptr(), cloned.ptr.as_ptr(), 1) };
+        cloned
+    }
+}
+
+impl<T: Copy> fmt::Debug for Protected<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Protected(******)")
+    }
+}
+
+impl<T: Copy> Drop for Protected<T> {
+    fn drop(&mut self) {
+        let base = self.ptr.as_ptr() as *mut u8;
+        unsafe {
+            scrub(std::slice::from_raw_parts_mut(base, self.layout.size()));
+            unlock(base, self.layout.size());
+            alloc::dealloc(base, self.layout);
+        }
+    }
+}
+
+fn round_up(size: usize, page: usize) -> usize {
+    (size + page - 1) / page * page
+}
+
+#[cfg(unix)]
+fn page_size() -> usize {
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size > 0 {
+        size as usize
+    } else {
+        4096
+    }
+}
+
+#[cfg(not(unix))]
+fn page_size() -> usize {
+    4096
+}
+
+// Locking may fail when RLIMIT_MEMLOCK is exhausted. The secret is still scrubbed on drop in that case,
+// so the failure is not fatal, but it's reported once since the secrets may be swapped out.
+#[cfg(unix)]
+unsafe fn lock(base: *mut u8, len: usize) {
+    static WARN_LOCK_FAILURE: Once = Once::new();
+    if libc::mlock(base as *const libc::c_void, len) != 0 {
+        let err = std::io::Error::last_os_error();
+        WARN_LOCK_FAILURE.call_once(|| {
+            warn!("Cannot lock the memory of secrets, they may be written to swap: {}. Raise RLIMIT_MEMLOCK.", err)
+        });
+    }
+    #[cfg(target_os = "linux")]
+    libc::madvise(base as *mut libc::c_void, len, libc::MADV_DONTDUMP);
+}
+
+#[cfg(not(unix))]
+unsafe fn lock(_base: *mut u8, _len: usize) {}
+
+#[cfg(unix)]
+unsafe fn unlock(base: *mut u8, len: usize) {
+    libc::munlock(base as *const libc::c_void, len);
+}
+
+#[cfg(not(unix))]
+unsafe fn unlock(_base: *mut u8, _len: usize) {}
+
+#[cfg(test)]
+mod tests {
+    use super::{scrub, Protected};
+    use primitives::H256;
+
+    #[test]
+    fn protected_keeps_value() {
+        let secret = H256::random();
+        let mut source = secret;
+        let mut protected = Protected::new(&mut source);
+        assert_eq!(*protected, secret);
+        assert_eq!(source, H256::zero());
+
+        let cloned = protected.clone();
+        *protected = H256::zero();
+        assert_eq!(*cloned, secret);
+        assert_eq!(*protected, H256::zero());
+    }
+
+    #[test]
+    fn protected_is_built_in_place() {
+        let secret = H256::random();
+        let mut protected = Protected::<H256>::default();
+        assert_eq!(*protected, H256::zero());
+        protected.copy_from_slice(&secret[..]);
+        assert_eq!(*protected, secret);
+    }
+
+    #[test]
+    fn scrub_zeroes_bytes() {
+        let mut bytes = vec![0xffu8; 40];
+        scrub(&mut bytes);
+        assert!(bytes.iter().all(|b| *b == 0));
+    }
+}
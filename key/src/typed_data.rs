@@ -0,0 +1,255 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{Error, NetworkId, PlatformAddress};
+use crypto::blake256;
+use primitives::H256;
+use rustc_hex::FromHex;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+/// The first bytes of the signed message. No RLP encoded transaction starts with them,
+/// so a signature of typed data cannot be used as a signature of a transaction.
+const TYPED_DATA_PREFIX: [u8; 2] = [0x19, 0x01];
+
+const DOMAIN_TYPE: &str = "Domain(string name,string version,string networkId)";
+
+/// A member of a struct type.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TypedField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub field_type: String,
+}
+
+/// The application and the network that a typed message is signed for.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedDataDomain {
+    pub name: String,
+    pub version: String,
+    pub network_id: NetworkId,
+}
+
+impl TypedDataDomain {
+    pub fn separator(&self) -> H256 {
+        let mut encoded = Vec::with_capacity(32 * 4);
+        encoded.extend_from_slice(blake256(DOMAIN_TYPE).as_ref());
+        encoded.extend_from_slice(blake256(&self.name).as_ref());
+        encoded.extend_from_slice(blake256(&self.version).as_ref());
+        encoded.extend_from_slice(blake256(self.network_id.to_string()).as_ref());
+        blake256(&encoded)
+    }
+}
+
+/// A structured message signed off-chain.
+///
+/// The struct types are declared in `types`, and the message is a JSON object of `primary_type`.
+/// The members can be `string`, `bytes`, `bytes32`, `bool`, `uint`(64 bits), `address`(platform address),
+/// the declared struct types, or the arrays of them.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedData {
+    pub types: BTreeMap<String, Vec<TypedField>>,
+    pub primary_type: String,
+    pub domain: TypedDataDomain,
+    pub message: Value,
+}
+
+impl TypedData {
+    /// The message to sign: blake256(0x19 0x01 ‖ the domain separator ‖ the hash of the message).
+    pub fn hash(&self) -> Result<H256, Error> {
+        let mut encoded = Vec::with_capacity(2 + 32 + 32);
+        encoded.extend_from_slice(&TYPED_DATA_PREFIX);
+        encoded.extend_from_slice(self.domain.separator().as_ref());
+        encoded.extend_from_slice(self.hash_struct(&self.primary_type, &self.message)?.as_ref());
+        Ok(blake256(&encoded))
+    }
+
+    /// Encodes the type with the types it depends on, which are sorted by name.
+    pub fn encode_type(&self, type_name: &str) -> Result<String, Error> {
+        let mut dependencies = BTreeSet::new();
+        self.collect_dependencies(type_name, &mut dependencies)?;
+        dependencies.remove(type_name);
+
+        let mut encoded = self.encode_single_type(type_name)?;
+        for dependency in dependencies {
+            encoded.push_str(&self.encode_single_type(&dependency)?);
+        }
+        Ok(encoded)
+    }
+
+    fn fields(&self, type_name: &str) -> Result<&[TypedField], Error> {
+        self.types.get(type_name).map(Vec::as_slice).ok_or_else(|| invalid(format!("{} is not declared", type_name)))
+    }
+
+    fn encode_single_type(&self, type_name: &str) -> Result<String, Error> {
+        let fields: Vec<String> =
+            self.fields(type_name)?.iter().map(|field| format!("{} {}", field.field_type, field.name)).collect();
+        Ok(format!("{}({})", type_name, fields.join(",")))
+    }
+
+    fn collect_dependencies(&self, type_name: &str, found: &mut BTreeSet<String>) -> Result<(), Error> {
+        if found.contains(type_name) {
+            return Ok(())
+        }
+        let fields = self.fields(type_name)?;
+        found.insert(type_name.to_string());
+        for field in fields {
+            let base_type = field.field_type.trim_end_matches("[]");
+            if self.types.contains_key(base_type) {
+                self.collect_dependencies(base_type, found)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn hash_struct(&self, type_name: &str, value: &Value) -> Result<H256, Error> {
+        let object = value.as_object().ok_or_else(|| invalid(format!("{} must be an object", type_name)))?;
+        let mut encoded = blake256(self.encode_type(type_name)?).to_vec();
+        for field in self.fields(type_name)? {
+            let member =
+                object.get(&field.name).ok_or_else(|| invalid(format!("{}.{} is missing", type_name, field.name)))?;
+            encoded.extend_from_slice(self.encode_value(&field.field_type, member)?.as_ref());
+        }
+        Ok(blake256(&encoded))
+    }
+
+    fn encode_value(&self, field_type: &str, value: &Value) -> Result<H256, Error> {
+        if field_type.ends_with("[]") {
+            let element_type = &field_type[..field_type.len() - 2];
+            let elements = value.as_array().ok_or_else(|| invalid(format!("{} must be an array", field_type)))?;
+            let mut encoded = Vec::with_capacity(32 * elements.len());
+            for element in elements {
+                encoded.extend_from_slice(self.encode_value(element_type, element)?.as_ref());
+            }
+            return Ok(blake256(&encoded))
+        }
+        if self.types.contains_key(field_type) {
+            return self.hash_struct(field_type, value)
+        }
+
+        let mismatch = || invalid(format!("{} is not a valid {}", value, field_type));
+        match field_type {
+            "string" => value.as_str().map(blake256).ok_or_else(mismatch),
+            "bytes" => Ok(blake256(parse_hex(value).ok_or_else(mismatch)?)),
+            "bytes32" => {
+                let bytes = parse_hex(value).filter(|bytes| bytes.len() == 32).ok_or_else(mismatch)?;
+                Ok(H256::from_slice(&bytes))
+            }
+            "bool" => value.as_bool().map(|value| encode_u64(value as u64)).ok_or_else(mismatch),
+            "uint" => {
+                let number = match value {
+                    Value::Number(number) => number.as_u64(),
+                    Value::String(hex) if hex.starts_with("0x") => u64::from_str_radix(&hex[2..], 16).ok(),
+                    _ => None,
+                };
+                number.map(encode_u64).ok_or_else(mismatch)
+            }
+            "address" => {
+                let address = value.as_str().ok_or_else(mismatch)?;
+                let address = PlatformAddress::from_str(address)?.try_into_address()?;
+                let mut encoded = [0u8; 32];
+                encoded[12..].copy_from_slice(address.as_ref());
+                Ok(encoded.into())
+            }
+            _ => Err(invalid(format!("{} is not a known type", field_type))),
+        }
+    }
+}
+
+fn invalid(detail: String) -> Error {
+    Error::InvalidTypedData(detail)
+}
+
+fn encode_u64(number: u64) -> H256 {
+    let mut encoded = [0u8; 32];
+    encoded[24..].copy_from_slice(&number.to_be_bytes());
+    encoded.into()
+}
+
+fn parse_hex(value: &Value) -> Option<Vec<u8>> {
+    let hex = value.as_str()?;
+    if !hex.starts_with("0x") {
+        return None
+    }
+    hex[2..].from_hex().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Address;
+
+    fn mail(from: &str, network_id: &str) -> TypedData {
+        serde_json::from_value(serde_json::json!({
+            "types": {
+                "Mail": [
+                    {"name": "from", "type": "Person"},
+                    {"name": "to", "type": "Person[]"},
+                    {"name": "contents", "type": "string"}
+                ],
+                "Person": [
+                    {"name": "name", "type": "string"},
+                    {"name": "wallet", "type": "address"}
+                ]
+            },
+            "primaryType": "Mail",
+            "domain": {
+                "name": "Mail",
+                "version": "1",
+                "networkId": network_id
+            },
+            "message": {
+                "from": {"name": from, "wallet": PlatformAddress::new_v1("tc".into(), Address::default()).to_string()},
+                "to": [{"name": "Bob", "wallet": PlatformAddress::new_v1("tc".into(), Address::from(1)).to_string()}],
+                "contents": "Hello, Bob!"
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn encode_type_appends_the_dependencies_in_order() {
+        assert_eq!(
+            "Mail(Person from,Person[] to,string contents)Person(string name,address wallet)",
+            mail("Alice", "tc").encode_type("Mail").unwrap()
+        );
+    }
+
+    #[test]
+    fn hash_depends_on_the_message_and_the_domain() {
+        let hash = mail("Alice", "tc").hash().unwrap();
+        assert_eq!(hash, mail("Alice", "tc").hash().unwrap());
+        assert_ne!(hash, mail("Carol", "tc").hash().unwrap());
+        assert_ne!(hash, mail("Alice", "cc").hash().unwrap());
+    }
+
+    #[test]
+    fn hash_fails_if_a_member_is_missing() {
+        let mut data = mail("Alice", "tc");
+        data.message.as_object_mut().unwrap().remove("contents");
+        assert_eq!(Err(Error::InvalidTypedData("Mail.contents is missing".to_string())), data.hash());
+    }
+
+    #[test]
+    fn hash_fails_with_an_unknown_type() {
+        let mut data = mail("Alice", "tc");
+        data.types.get_mut("Mail").unwrap()[2].field_type = "text".to_string();
+        assert!(data.hash().is_err());
+    }
+}
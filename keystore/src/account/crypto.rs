@@ -18,7 +18,7 @@ use crate::account::{Aes128Ctr, Cipher, Kdf, Pbkdf2, Prf};
 use crate::random::Random;
 use crate::{json, Error};
 use ccrypto;
-use ckey::{public_to_address, Address, KeyPair, Password, Private, Secret};
+use ckey::{public_to_address, scrub, Address, KeyPair, Password, Private, Protected, Secret};
 use smallvec::SmallVec;
 use std::num::NonZeroU32;
 use std::str;
@@ -112,18 +112,21 @@ impl Crypto {
     }
 
     /// Try to decrypt and convert result to account secret
-    pub fn secret(&self, password: &Password) -> Result<Secret, Error> {
+    pub fn secret(&self, password: &Password) -> Result<Protected<Secret>, Error> {
         if self.ciphertext.len() > 32 {
             return Err(Error::InvalidSecret)
         }
 
-        let secret = self.do_decrypt(password, 32)?;
-        Ok(*Private::from_slice(&secret))
+        let mut plain = self.do_decrypt(password, 32)?;
+        let mut secret = Protected::<Secret>::default();
+        secret.copy_from_slice(&plain);
+        scrub(&mut plain);
+        Ok(secret)
     }
 
     pub fn address(&self, password: &Password) -> Result<Address, Error> {
         let private = self.secret(password)?;
-        Ok(public_to_address(KeyPair::from_private(private.into())?.public()))
+        Ok(public_to_address(KeyPair::from_private(Private::from(*private))?.public()))
     }
 
     /// Try to decrypt and return result as is
@@ -150,7 +153,8 @@ impl Crypto {
             return Err(Error::InvalidPassword)
         }
 
-        let mut plain: SmallVec<[u8; 32]> = SmallVec::from_vec(vec![0; expected_len]);
+        // kept on the heap so that no copy of the plain text is left behind on the stack
+        let mut plain = vec![0; expected_len];
 
         match self.cipher {
             Cipher::Aes128Ctr(ref params) => {
@@ -158,8 +162,13 @@ impl Crypto {
                 debug_assert!(expected_len >= self.ciphertext.len());
 
                 let from = expected_len - self.ciphertext.len();
-                ccrypto::aes::decrypt_128_ctr(&derived_left_bits, &params.iv, &self.ciphertext, &mut plain[from..])?;
-                Ok(plain.into_iter().collect())
+                if let Err(err) =
+                    ccrypto::aes::decrypt_128_ctr(&derived_left_bits, &params.iv, &self.ciphertext, &mut plain[from..])
+                {
+                    scrub(&mut plain);
+                    return Err(err.into())
+                }
+                Ok(plain)
             }
         }
     }
@@ -176,7 +185,7 @@ mod tests {
         let private_key = keypair.private();
         let crypto = Crypto::with_secret(keypair.private(), &"this is sparta".into(), 10240).unwrap();
         let secret = crypto.secret(&"this is sparta".into()).unwrap();
-        assert_eq!(**private_key, secret);
+        assert_eq!(**private_key, *secret);
     }
 
     #[test]
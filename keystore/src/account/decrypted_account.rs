@@ -15,17 +15,20 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use ckey::{
-    sign, sign_schnorr, Error as KeyError, KeyPair, Message, Private, Public, SchnorrSignature, Secret, Signature,
+    sign, sign_schnorr, Error as KeyError, KeyPair, Message, Private, Protected, Public, SchnorrSignature, Secret,
+    Signature,
 };
 
 /// An opaque wrapper for secret.
+///
+/// The secret is kept in locked memory and scrubbed when the account is dropped.
 #[derive(Clone)]
 pub struct DecryptedAccount {
-    secret: Secret,
+    secret: Protected<Secret>,
 }
 
 impl DecryptedAccount {
-    pub fn new(secret: Secret) -> DecryptedAccount {
+    pub fn new(secret: Protected<Secret>) -> DecryptedAccount {
         DecryptedAccount {
             secret,
         }
@@ -33,22 +36,16 @@ impl DecryptedAccount {
 
     /// Sign a message.
     pub fn sign(&self, message: &Message) -> Result<Signature, KeyError> {
-        sign(&Private::from(self.secret), message)
+        sign(&Private::from(*self.secret), message)
     }
 
     /// Sign a message with Schnorr scheme.
     pub fn sign_schnorr(&self, message: &Message) -> Result<SchnorrSignature, KeyError> {
-        sign_schnorr(&Private::from(self.secret), message)
+        sign_schnorr(&Private::from(*self.secret), message)
     }
 
     /// Derive public key.
     pub fn public(&self) -> Result<Public, KeyError> {
-        Ok(*KeyPair::from_private(Private::from(self.secret))?.public())
-    }
-}
-
-impl Drop for DecryptedAccount {
-    fn drop(&mut self) {
-        self.secret = Secret::default();
+        Ok(*KeyPair::from_private(Private::from(*self.secret))?.public())
     }
 }
@@ -20,7 +20,7 @@ use crate::json::{self, OpaqueKeyFile, Uuid};
 use crate::random::Random;
 use crate::{Error, SecretStore, SimpleSecretStore};
 use ccrypto::KEY_ITERATIONS;
-use ckey::{Address, KeyPair, Password, Secret};
+use ckey::{Address, KeyPair, Password, Protected, Secret};
 use parking_lot::{Mutex, RwLock};
 use std::collections::BTreeMap;
 use std::mem;
@@ -107,7 +107,7 @@ impl SecretStore for KeyStore {
         }
 
         let secret = safe_account.crypto.secret(password).map_err(|_| Error::InvalidPassword)?;
-        safe_account.address = KeyPair::from_private(secret.into())?.address();
+        safe_account.address = KeyPair::from_private((*secret).into())?.address();
         self.store.import(safe_account)
     }
 
@@ -127,7 +127,7 @@ impl SecretStore for KeyStore {
         new_password: &Password,
     ) -> Result<(), Error> {
         let secret = self.store.get_verified_account(account, password)?.secret;
-        new_store.insert_account(secret, new_password)?;
+        new_store.insert_account(*secret, new_password)?;
         Ok(())
     }
 
@@ -395,7 +395,7 @@ impl SimpleSecretStore for KeyMultiStore {
 
 struct VerifiedAccount {
     account: SafeAccount,
-    secret: Secret,
+    secret: Protected<Secret>,
 }
 
 #[cfg(test)]
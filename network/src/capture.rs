@@ -0,0 +1,408 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Records the inbound events of the network extensions and feeds them back to reproduce bugs.
+//!
+//! A capture file is a sequence of RLP encoded `CapturedRecord`s.
+
+use crate::{Api, NetworkExtension, NetworkExtensionResult, NodeId};
+use ctimer::TimerToken;
+use parking_lot::Mutex;
+use primitives::Bytes;
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MESSAGE_ID: u8 = 0x01;
+const NODE_ADDED_ID: u8 = 0x02;
+const NODE_REMOVED_ID: u8 = 0x03;
+const TIMEOUT_ID: u8 = 0x04;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CapturedEvent {
+    Message {
+        extension: String,
+        node: NodeId,
+        data: Bytes,
+    },
+    NodeAdded {
+        extension: String,
+        node: NodeId,
+        version: u64,
+    },
+    /// Delivered to all extensions.
+    NodeRemoved {
+        node: NodeId,
+    },
+    Timeout {
+        extension: String,
+        token: TimerToken,
+    },
+}
+
+impl CapturedEvent {
+    fn is_for(&self, name: &str) -> bool {
+        match self {
+            CapturedEvent::Message {
+                extension,
+                ..
+            }
+            | CapturedEvent::NodeAdded {
+                extension,
+                ..
+            }
+            | CapturedEvent::Timeout {
+                extension,
+                ..
+            } => extension == name,
+            CapturedEvent::NodeRemoved {
+                ..
+            } => true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CapturedRecord {
+    /// Milliseconds since the UNIX epoch when the event was received.
+    pub timestamp: u64,
+    pub event: CapturedEvent,
+}
+
+impl Encodable for CapturedRecord {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        match &self.event {
+            CapturedEvent::Message {
+                extension,
+                node,
+                data,
+            } => {
+                s.begin_list(5).append(&self.timestamp).append(&MESSAGE_ID).append(extension).append(node).append(data);
+            }
+            CapturedEvent::NodeAdded {
+                extension,
+                node,
+                version,
+            } => {
+                s.begin_list(5)
+                    .append(&self.timestamp)
+                    .append(&NODE_ADDED_ID)
+                    .append(extension)
+                    .append(node)
+                    .append(version);
+            }
+            CapturedEvent::NodeRemoved {
+                node,
+            } => {
+                s.begin_list(3).append(&self.timestamp).append(&NODE_REMOVED_ID).append(node);
+            }
+            CapturedEvent::Timeout {
+                extension,
+                token,
+            } => {
+                s.begin_list(4).append(&self.timestamp).append(&TIMEOUT_ID).append(extension).append(&(*token as u64));
+            }
+        }
+    }
+}
+
+impl Decodable for CapturedRecord {
+    fn decode(rlp: &Rlp<'_>) -> Result<Self, DecoderError> {
+        let item_count = rlp.item_count()?;
+        let id: u8 = rlp.val_at(1)?;
+        let expected = match id {
+            MESSAGE_ID | NODE_ADDED_ID => 5,
+            NODE_REMOVED_ID => 3,
+            TIMEOUT_ID => 4,
+            _ => return Err(DecoderError::Custom("Unknown captured event")),
+        };
+        if item_count != expected {
+            return Err(DecoderError::RlpIncorrectListLen {
+                got: item_count,
+                expected,
+            })
+        }
+        let event = match id {
+            MESSAGE_ID => CapturedEvent::Message {
+                extension: rlp.val_at(2)?,
+                node: rlp.val_at(3)?,
+                data: rlp.val_at(4)?,
+            },
+            NODE_ADDED_ID => CapturedEvent::NodeAdded {
+                extension: rlp.val_at(2)?,
+                node: rlp.val_at(3)?,
+                version: rlp.val_at(4)?,
+            },
+            NODE_REMOVED_ID => CapturedEvent::NodeRemoved {
+                node: rlp.val_at(2)?,
+            },
+            TIMEOUT_ID => CapturedEvent::Timeout {
+                extension: rlp.val_at(2)?,
+                token: rlp.val_at::<u64>(3)? as TimerToken,
+            },
+            _ => unreachable!(),
+        };
+        Ok(Self {
+            timestamp: rlp.val_at(0)?,
+            event,
+        })
+    }
+}
+
+/// Appends the inbound events of the extensions to a capture file.
+pub struct Capture {
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl Capture {
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self {
+            writer: Mutex::new(BufWriter::new(File::create(path)?)),
+        })
+    }
+
+    pub fn record(&self, event: CapturedEvent) {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_else(|_| Duration::from_secs(0));
+        let record = CapturedRecord {
+            timestamp: timestamp.as_secs() * 1000 + u64::from(timestamp.subsec_millis()),
+            event,
+        };
+        let mut writer = self.writer.lock();
+        // Flush every record so that the capture survives a crash, which is usually what is being reproduced.
+        if let Err(err) = writer.write_all(&record.rlp_bytes()).and_then(|_| writer.flush()) {
+            cwarn!(NETAPI, "Cannot capture {:?}: {}", record.event, err);
+        }
+    }
+}
+
+/// The events read from a capture file.
+pub struct Replay {
+    records: Vec<CapturedRecord>,
+}
+
+impl Replay {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        let mut records = Vec::new();
+        let mut offset = 0;
+        while offset < bytes.len() {
+            let rlp = Rlp::new(&bytes[offset..]);
+            let invalid = |err: DecoderError| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", err));
+            let length = rlp.payload_info().map_err(invalid)?.total();
+            records.push(Rlp::new(&bytes[offset..offset + length]).as_val().map_err(invalid)?);
+            offset += length;
+        }
+        Ok(Self {
+            records,
+        })
+    }
+
+    pub fn records(&self) -> &[CapturedRecord] {
+        &self.records
+    }
+
+    /// Feeds the captured events of the extension to it in the captured order on the current thread.
+    /// Returns the number of the fed events.
+    pub fn feed<T, E>(&self, extension: &mut T) -> usize
+    where
+        T: NetworkExtension<E>,
+        E: Send, {
+        let name = T::name();
+        let mut fed = 0;
+        for record in self.records.iter().filter(|record| record.event.is_for(name)) {
+            match &record.event {
+                CapturedEvent::Message {
+                    node,
+                    data,
+                    ..
+                } => extension.on_message(node, data),
+                CapturedEvent::NodeAdded {
+                    node,
+                    version,
+                    ..
+                } => extension.on_node_added(node, *version),
+                CapturedEvent::NodeRemoved {
+                    node,
+                } => extension.on_node_removed(node),
+                CapturedEvent::Timeout {
+                    token,
+                    ..
+                } => extension.on_timeout(*token),
+            }
+            fed += 1;
+        }
+        fed
+    }
+}
+
+/// The `Api` for an extension under replay.
+/// It keeps the sent messages and ignores the timers because the captured timeouts are replayed instead.
+#[derive(Clone, Default)]
+pub struct ReplayApi {
+    sent: Arc<Mutex<Vec<(NodeId, Arc<Bytes>)>>>,
+}
+
+impl ReplayApi {
+    pub fn sent(&self) -> Vec<(NodeId, Arc<Bytes>)> {
+        self.sent.lock().clone()
+    }
+}
+
+impl Api for ReplayApi {
+    fn send(&self, node: &NodeId, message: Arc<Bytes>) {
+        self.sent.lock().push((*node, message));
+    }
+
+    fn set_timer(&self, _token: TimerToken, _duration: Duration) -> NetworkExtensionResult<()> {
+        Ok(())
+    }
+
+    fn set_timer_once(&self, _token: TimerToken, _duration: Duration) -> NetworkExtensionResult<()> {
+        Ok(())
+    }
+
+    fn clear_timer(&self, _token: TimerToken) -> NetworkExtensionResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use never_type::Never;
+    use rlp::rlp_encode_and_decode_test;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    struct Echo {
+        api: Box<dyn Api>,
+        timeouts: Vec<TimerToken>,
+    }
+
+    impl NetworkExtension<Never> for Echo {
+        fn name() -> &'static str {
+            "echo"
+        }
+
+        fn need_encryption() -> bool {
+            false
+        }
+
+        fn versions() -> &'static [u64] {
+            &[0]
+        }
+
+        fn on_message(&mut self, node: &NodeId, message: &[u8]) {
+            self.api.send(node, Arc::new(message.to_vec()));
+        }
+
+        fn on_timeout(&mut self, token: TimerToken) {
+            self.timeouts.push(token);
+        }
+    }
+
+    fn node(port: u16) -> NodeId {
+        NodeId::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), port)
+    }
+
+    #[test]
+    fn encode_and_decode_records() {
+        let records = vec![
+            CapturedRecord {
+                timestamp: 1,
+                event: CapturedEvent::NodeAdded {
+                    extension: "echo".to_string(),
+                    node: node(3485),
+                    version: 0,
+                },
+            },
+            CapturedRecord {
+                timestamp: 2,
+                event: CapturedEvent::Message {
+                    extension: "echo".to_string(),
+                    node: node(3485),
+                    data: vec![1, 2, 3],
+                },
+            },
+            CapturedRecord {
+                timestamp: 3,
+                event: CapturedEvent::Timeout {
+                    extension: "echo".to_string(),
+                    token: 7,
+                },
+            },
+            CapturedRecord {
+                timestamp: 4,
+                event: CapturedEvent::NodeRemoved {
+                    node: node(3485),
+                },
+            },
+        ];
+        for record in records {
+            rlp_encode_and_decode_test!(record);
+        }
+    }
+
+    #[test]
+    fn replay_feeds_only_the_events_of_the_extension() {
+        let replay = Replay {
+            records: vec![
+                CapturedRecord {
+                    timestamp: 1,
+                    event: CapturedEvent::Message {
+                        extension: "echo".to_string(),
+                        node: node(3485),
+                        data: vec![1],
+                    },
+                },
+                CapturedRecord {
+                    timestamp: 2,
+                    event: CapturedEvent::Message {
+                        extension: "other".to_string(),
+                        node: node(3485),
+                        data: vec![2],
+                    },
+                },
+                CapturedRecord {
+                    timestamp: 3,
+                    event: CapturedEvent::Timeout {
+                        extension: "echo".to_string(),
+                        token: 7,
+                    },
+                },
+                CapturedRecord {
+                    timestamp: 4,
+                    event: CapturedEvent::Message {
+                        extension: "echo".to_string(),
+                        node: node(3486),
+                        data: vec![3],
+                    },
+                },
+            ],
+        };
+        let api = ReplayApi::default();
+        let mut echo = Echo {
+            api: Box::new(api.clone()),
+            timeouts: Vec::new(),
+        };
+
+        assert_eq!(3, replay.feed(&mut echo));
+        assert_eq!(vec![(node(3485), Arc::new(vec![1])), (node(3486), Arc::new(vec![3]))], api.sent());
+        assert_eq!(vec![7], echo.timeouts);
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Fault injection into the inbound extension messages, used to test the liveness of the consensus
+//! and the robustness of the sync under a bad network. The faults are injected only when the crate
+//! is built with the `chaos` feature.
+
+#[cfg(feature = "chaos")]
+use primitives::Bytes;
+#[cfg(feature = "chaos")]
+use rand::Rng;
+#[cfg(feature = "chaos")]
+use std::sync::Arc;
+use std::time::Duration;
+
+/// All faults are off by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ChaosConfig {
+    /// The percentage of the messages to drop.
+    pub drop_percent: u8,
+    /// The percentage of the messages whose payload gets a corrupted byte.
+    pub corrupt_percent: u8,
+    /// The percentage of the messages on which the sender gets disconnected.
+    pub disconnect_percent: u8,
+    /// Each message is delayed by a random duration up to this.
+    pub max_latency: Duration,
+}
+
+impl ChaosConfig {
+    pub fn is_valid(&self) -> bool {
+        self.drop_percent <= 100 && self.corrupt_percent <= 100 && self.disconnect_percent <= 100
+    }
+}
+
+#[cfg(feature = "chaos")]
+pub(crate) enum Verdict {
+    Drop,
+    Disconnect,
+    Deliver {
+        data: Arc<Bytes>,
+        delay: Option<Duration>,
+    },
+}
+
+#[cfg(feature = "chaos")]
+pub(crate) fn judge(config: &ChaosConfig, data: Arc<Bytes>) -> Verdict {
+    let mut rng = rand::thread_rng();
+    let mut hit = |percent: u8| percent > 0 && rng.gen_range(0, 100) < percent;
+    if hit(config.disconnect_percent) {
+        return Verdict::Disconnect
+    }
+    if hit(config.drop_percent) {
+        return Verdict::Drop
+    }
+    let data = if hit(config.corrupt_percent) && !data.is_empty() {
+        let mut corrupted = data.as_ref().clone();
+        let index = rng.gen_range(0, corrupted.len());
+        corrupted[index] ^= rng.gen_range(1, 0xFF);
+        Arc::new(corrupted)
+    } else {
+        data
+    };
+    let max_latency = config.max_latency.as_millis() as u64;
+    let delay = if max_latency > 0 {
+        Some(Duration::from_millis(rng.gen_range(0, max_latency + 1)))
+    } else {
+        None
+    };
+    Verdict::Deliver {
+        data,
+        delay,
+    }
+}
+
+#[cfg(all(test, feature = "chaos"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_fault_by_default() {
+        match judge(&ChaosConfig::default(), Arc::new(vec![1, 2, 3])) {
+            Verdict::Deliver {
+                data,
+                delay: None,
+            } => assert_eq!(vec![1, 2, 3], *data),
+            _ => panic!("The message must be delivered as it is"),
+        }
+    }
+
+    #[test]
+    fn disconnect_precedes_drop() {
+        let config = ChaosConfig {
+            drop_percent: 100,
+            disconnect_percent: 100,
+            ..Default::default()
+        };
+        assert!(matches_disconnect(judge(&config, Arc::new(vec![1]))));
+    }
+
+    #[test]
+    fn corrupt_changes_one_byte() {
+        let config = ChaosConfig {
+            corrupt_percent: 100,
+            max_latency: Duration::from_millis(10),
+            ..Default::default()
+        };
+        match judge(&config, Arc::new(vec![0; 8])) {
+            Verdict::Deliver {
+                data,
+                delay: Some(delay),
+            } => {
+                assert_eq!(1, data.iter().filter(|byte| **byte != 0).count());
+                assert!(delay <= Duration::from_millis(10));
+            }
+            _ => panic!("The message must be delivered with a delay"),
+        }
+    }
+
+    fn matches_disconnect(verdict: Verdict) -> bool {
+        match verdict {
+            Verdict::Disconnect => true,
+            _ => false,
+        }
+    }
+}
@@ -14,6 +14,9 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use crate::capture::{Capture, CapturedEvent};
+#[cfg(feature = "chaos")]
+use crate::chaos::{self, ChaosConfig, Verdict};
 use crate::p2p::Message as P2pMessage;
 use crate::{Api, IntoSocketAddr, NetworkExtension, NetworkExtensionResult, NodeId};
 use cio::IoChannel;
@@ -23,6 +26,8 @@ use parking_lot::{Mutex, RwLock};
 use primitives::Bytes;
 use std::collections::HashMap;
 use std::sync::Arc;
+#[cfg(feature = "chaos")]
+use std::thread;
 use std::thread::{Builder, JoinHandle};
 use std::time::Duration;
 
@@ -80,10 +85,32 @@ struct Extension {
     sender: Mutex<crossbeam::Sender<ExtensionMessage>>,
     quit: Mutex<crossbeam::Sender<()>>,
     join: Mutex<Option<JoinHandle<()>>>,
+    capture: Option<Arc<Capture>>,
+}
+
+impl Extension {
+    fn deliver(&self, capture: Option<&Arc<Capture>>, id: &NodeId, data: Arc<Bytes>) {
+        if let Some(capture) = capture {
+            capture.record(CapturedEvent::Message {
+                extension: self.name.to_string(),
+                node: *id,
+                data: data.to_vec(),
+            });
+        }
+        if let Err(err) = self.sender.lock().send(ExtensionMessage::Message(*id, data)) {
+            cwarn!(NETAPI, "{} cannot message {}: {:?}", self.name, id, err);
+        }
+    }
 }
 
 impl TimeoutHandler for Extension {
     fn on_timeout(&self, token: TimerToken) {
+        if let Some(capture) = &self.capture {
+            capture.record(CapturedEvent::Timeout {
+                extension: self.name.to_string(),
+                token,
+            });
+        }
         if let Err(err) = self.sender.lock().send(ExtensionMessage::Timeout(token)) {
             cwarn!(NETAPI, "{} cannot timeout {}: {:?}", self.name, token, err);
         }
@@ -103,6 +130,9 @@ pub struct Client {
     extensions: RwLock<HashMap<&'static str, Arc<Extension>>>,
     p2p_channel: IoChannel<P2pMessage>,
     timer_loop: TimerLoop,
+    capture: Option<Arc<Capture>>,
+    #[cfg(feature = "chaos")]
+    chaos: RwLock<ChaosConfig>,
 }
 
 impl Client {
@@ -209,6 +239,7 @@ impl Client {
             sender,
             quit: quit_sender.into(),
             join,
+            capture: self.capture.clone(),
         });
         cloned_timer.set_handler(Arc::downgrade(&extension));
         if extensions.insert(name, extension).is_some() {
@@ -218,20 +249,39 @@ impl Client {
         event_sender
     }
 
-    pub fn new(p2p_channel: IoChannel<P2pMessage>, timer_loop: TimerLoop) -> Arc<Self> {
+    pub fn new(p2p_channel: IoChannel<P2pMessage>, timer_loop: TimerLoop, capture: Option<Capture>) -> Arc<Self> {
         Arc::new(Self {
             extensions: RwLock::new(HashMap::new()),
             p2p_channel,
             timer_loop,
+            capture: capture.map(Arc::new),
+            #[cfg(feature = "chaos")]
+            chaos: Default::default(),
         })
     }
 
+    #[cfg(feature = "chaos")]
+    pub fn set_chaos(&self, config: ChaosConfig) {
+        cwarn!(NETAPI, "Chaos is set to {:?}", config);
+        *self.chaos.write() = config;
+    }
+
+    #[cfg(feature = "chaos")]
+    pub fn chaos(&self) -> ChaosConfig {
+        *self.chaos.read()
+    }
+
     pub fn extension_versions(&self) -> Vec<(String, Vec<u64>)> {
         let extensions = self.extensions.read();
         extensions.iter().map(|(name, extension)| ((*name).to_string(), extension.versions.clone())).collect()
     }
 
     pub fn on_node_removed(&self, id: &NodeId) {
+        if let Some(capture) = &self.capture {
+            capture.record(CapturedEvent::NodeRemoved {
+                node: *id,
+            });
+        }
         let extensions = self.extensions.read();
         for (name, extension) in extensions.iter() {
             if let Err(err) = extension.sender.lock().send(ExtensionMessage::NodeRemoved(*id)) {
@@ -243,6 +293,13 @@ impl Client {
     pub fn on_node_added(&self, name: &str, id: &NodeId, version: u64) {
         let extensions = self.extensions.read();
         if let Some(extension) = extensions.get(name) {
+            if let Some(capture) = &self.capture {
+                capture.record(CapturedEvent::NodeAdded {
+                    extension: name.to_string(),
+                    node: *id,
+                    version,
+                });
+            }
             if let Err(err) = extension.sender.lock().send(ExtensionMessage::NodeAdded(*id, version)) {
                 cwarn!(NETAPI, "{} cannot add {}:{}: {:?}", name, id, version, err);
             }
@@ -255,9 +312,39 @@ impl Client {
         let extensions = self.extensions.read();
         if let Some(extension) = extensions.get(name) {
             cdebug!(NETAPI, "`{}` receives {} bytes from {}", name, data.len(), id.into_addr());
-            if let Err(err) = extension.sender.lock().send(ExtensionMessage::Message(*id, data)) {
-                cwarn!(NETAPI, "{} cannot message {}: {:?}", name, id, err);
+            #[cfg(feature = "chaos")]
+            {
+                let config = *self.chaos.read();
+                match chaos::judge(&config, data) {
+                    Verdict::Drop => {
+                        cdebug!(NETAPI, "Chaos drops the message to `{}` from {}", name, id.into_addr());
+                    }
+                    Verdict::Disconnect => {
+                        cdebug!(NETAPI, "Chaos disconnects {}", id.into_addr());
+                        if let Err(err) = self.p2p_channel.send(P2pMessage::Disconnect(id.into_addr())) {
+                            cwarn!(NETAPI, "Cannot disconnect {}: {:?}", id.into_addr(), err);
+                        }
+                    }
+                    Verdict::Deliver {
+                        data,
+                        delay: Some(delay),
+                    } => {
+                        let extension = Arc::clone(extension);
+                        let capture = self.capture.clone();
+                        let id = *id;
+                        thread::spawn(move || {
+                            thread::sleep(delay);
+                            extension.deliver(capture.as_ref(), &id, data);
+                        });
+                    }
+                    Verdict::Deliver {
+                        data,
+                        delay: None,
+                    } => extension.deliver(self.capture.as_ref(), id, data),
+                }
             }
+            #[cfg(not(feature = "chaos"))]
+            extension.deliver(self.capture.as_ref(), id, data);
         } else {
             cwarn!(NETAPI, "{} doesn't exist.", name);
         }
@@ -367,7 +454,7 @@ mod tests {
         let p2p_service = IoService::start("P2P").unwrap();
         let timer_loop = TimerLoop::new(2);
 
-        let client = Client::new(p2p_service.channel(), timer_loop);
+        let client = Client::new(p2p_service.channel(), timer_loop, None);
 
         let node_id1 = SocketAddr::v4(127, 0, 0, 1, 8081).into();
         let node_id5 = SocketAddr::v4(127, 0, 0, 1, 8085).into();
@@ -25,4 +25,7 @@ pub struct Config {
     pub max_peers: usize,
     pub whitelist: Vec<FilterEntry>,
     pub blacklist: Vec<FilterEntry>,
+    /// The network is permissioned if it's given.
+    pub trusted_peers: Option<Vec<SocketAddr>>,
+    pub capture_path: Option<String>,
 }
@@ -15,6 +15,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use crate::addr::SocketAddr;
+use crate::chaos::ChaosConfig;
 use crate::filters::FilterEntry;
 use cidr::IpCidr;
 use ckey::Public;
@@ -47,11 +48,20 @@ pub trait Control: Send + Sync {
     fn get_whitelist(&self) -> Result<(Vec<FilterEntry>, bool), Error>;
     fn get_blacklist(&self) -> Result<(Vec<FilterEntry>, bool), Error>;
 
+    fn add_trusted_peer(&self, addr: SocketAddr) -> Result<(), Error>;
+    fn remove_trusted_peer(&self, addr: &SocketAddr) -> Result<(), Error>;
+    fn get_trusted_peers(&self) -> Result<(Vec<SocketAddr>, bool), Error>;
+
     fn recent_network_usage(&self) -> Result<HashMap<String, usize>, Error>;
+
+    fn set_chaos(&self, config: ChaosConfig) -> Result<(), Error>;
+    fn chaos(&self) -> Result<ChaosConfig, Error>;
 }
 
 #[derive(Clone, Debug)]
 pub enum Error {
     Disabled,
     NotConnected,
+    /// The node is built without the `chaos` feature.
+    ChaosNotEnabled,
 }
@@ -57,6 +57,9 @@ pub trait Api {
 pub trait Extension<Event: Send> {
     fn name() -> &'static str;
     fn need_encryption() -> bool;
+    /// The versions of the messages this extension speaks.
+    /// The highest version both nodes speak is given to `on_node_added`. The extension isn't used with the nodes that
+    /// speak none of them, but the other extensions still are.
     fn versions() -> &'static [u64];
 
     fn on_node_added(&mut self, _node: &NodeId, _version: u64) {}
@@ -15,6 +15,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use super::filter::FilterEntry;
+use crate::NodeId;
 use cidr::IpCidr;
 use std::net::IpAddr;
 
@@ -34,4 +35,11 @@ pub trait Control: Send + Sync {
     fn get_blacklist(&self) -> (Vec<FilterEntry>, bool);
 
     fn is_allowed(&self, addr: &IpAddr) -> bool;
+
+    fn add_trusted_peer(&self, id: NodeId);
+    fn remove_trusted_peer(&self, id: &NodeId);
+    fn get_trusted_peers(&self) -> (Vec<NodeId>, bool);
+
+    /// Every peer is trusted unless the network is permissioned.
+    fn is_trusted(&self, id: &NodeId) -> bool;
 }
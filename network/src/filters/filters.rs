@@ -16,8 +16,10 @@
 
 use super::control::Control;
 use super::filter::{Filter, FilterEntry};
+use crate::NodeId;
 use cidr::IpCidr;
 use parking_lot::RwLock;
+use std::collections::HashSet;
 use std::net::IpAddr;
 use std::sync::Arc;
 
@@ -25,16 +27,26 @@ use std::sync::Arc;
 pub struct Filters {
     whitelist: RwLock<Filter>,
     blacklist: RwLock<Filter>,
+    /// Only the trusted peers can establish sessions if the network is permissioned.
+    permissioned: bool,
+    trusted_peers: RwLock<HashSet<NodeId>>,
 }
 
 impl Filters {
-    pub fn new(whitelist_vector: Vec<FilterEntry>, blacklist_vector: Vec<FilterEntry>) -> Arc<Self> {
+    /// The network is permissioned if the trusted peers are given.
+    pub fn new(
+        whitelist_vector: Vec<FilterEntry>,
+        blacklist_vector: Vec<FilterEntry>,
+        trusted_peers: Option<Vec<NodeId>>,
+    ) -> Arc<Self> {
         let whitelist = Filter::new(whitelist_vector);
         let blacklist = Filter::new(blacklist_vector);
 
         Arc::new(Self {
             whitelist: RwLock::new(whitelist),
             blacklist: RwLock::new(blacklist),
+            permissioned: trusted_peers.is_some(),
+            trusted_peers: RwLock::new(trusted_peers.unwrap_or_default().into_iter().collect()),
         })
     }
 }
@@ -111,4 +123,55 @@ impl Control for Filters {
         }
         true
     }
+
+    fn add_trusted_peer(&self, id: NodeId) {
+        self.trusted_peers.write().insert(id);
+        cinfo!(NETFILTER, "{} is added to the trusted peers", id);
+    }
+
+    fn remove_trusted_peer(&self, id: &NodeId) {
+        self.trusted_peers.write().remove(id);
+        cinfo!(NETFILTER, "{} is removed from the trusted peers", id);
+    }
+
+    fn get_trusted_peers(&self) -> (Vec<NodeId>, bool) {
+        let mut list: Vec<_> = self.trusted_peers.read().iter().cloned().collect();
+        list.sort();
+        (list, self.permissioned)
+    }
+
+    fn is_trusted(&self, id: &NodeId) -> bool {
+        !self.permissioned || self.trusted_peers.read().contains(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SocketAddr;
+
+    #[test]
+    fn every_peer_is_trusted_if_not_permissioned() {
+        let filters = Filters::new(Vec::new(), Vec::new(), None);
+        assert!(filters.is_trusted(&SocketAddr::v4(10, 0, 0, 1, 3485).into()));
+        assert_eq!((Vec::new(), false), filters.get_trusted_peers());
+    }
+
+    #[test]
+    fn only_trusted_peers_are_trusted_if_permissioned() {
+        let trusted: NodeId = SocketAddr::v4(10, 0, 0, 1, 3485).into();
+        let other_port: NodeId = SocketAddr::v4(10, 0, 0, 1, 3486).into();
+        let approved: NodeId = SocketAddr::v4(10, 0, 0, 2, 3485).into();
+        let filters = Filters::new(Vec::new(), Vec::new(), Some(vec![trusted]));
+        assert!(filters.is_trusted(&trusted));
+        assert!(!filters.is_trusted(&other_port));
+        assert!(!filters.is_trusted(&approved));
+
+        filters.add_trusted_peer(approved);
+        assert!(filters.is_trusted(&approved));
+        assert_eq!((vec![trusted, approved], true), filters.get_trusted_peers());
+
+        filters.remove_trusted_peer(&trusted);
+        assert!(!filters.is_trusted(&trusted));
+    }
 }
@@ -21,6 +21,7 @@ extern crate codechain_io as cio;
 extern crate codechain_key as ckey;
 #[macro_use]
 extern crate codechain_logger as clogger;
+extern crate codechain_memory as cmemory;
 extern crate codechain_timer as ctimer;
 #[macro_use]
 extern crate log;
@@ -31,6 +32,8 @@ extern crate kvdb;
 use crossbeam_channel;
 
 mod addr;
+mod capture;
+mod chaos;
 mod client;
 mod config;
 mod extension;
@@ -45,7 +48,11 @@ mod p2p;
 pub mod session;
 
 pub use self::p2p::{Handler, ManagingPeerdb};
+#[cfg(feature = "fuzzing")]
+pub use self::p2p::{P2pMessage, SignedP2pMessage};
 pub use crate::addr::SocketAddr;
+pub use crate::capture::{Capture, CapturedEvent, CapturedRecord, Replay, ReplayApi};
+pub use crate::chaos::ChaosConfig;
 pub use crate::config::Config as NetworkConfig;
 pub use crate::control::{Control as NetworkControl, Error as NetworkControlError};
 pub use crate::extension::{
@@ -18,7 +18,7 @@ use super::connection::{
     EstablishedConnection, IncomingConnection, IncomingMessage, OutgoingConnection, OutgoingMessage,
 };
 use super::listener::Listener;
-use super::{NegotiationMessage, NetworkMessage};
+use super::{negotiate, NegotiationMessage, NetworkMessage};
 use crate::client::Client;
 use crate::session::Session;
 use crate::stream::Stream;
@@ -34,8 +34,7 @@ use primitives::Bytes;
 use rand::prelude::SliceRandom;
 use rand::rngs::OsRng;
 use rand::Rng;
-use std::collections::{BTreeSet, HashMap, VecDeque};
-use std::iter::FromIterator;
+use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -204,6 +203,9 @@ impl Handler {
         if !self.filters.is_allowed(&ip) {
             return Err(format!("New connection to {} is requested. But it's not allowed", ip).into())
         }
+        if !self.filters.is_trusted(&socket_address.into()) {
+            return Err(format!("New connection to {} is requested. But it's not a trusted peer", socket_address).into())
+        }
 
         let initiator_pub_key = if let Some(initiator_pub_key) = self.routing_table.local_public(socket_address) {
             initiator_pub_key
@@ -315,7 +317,12 @@ impl IoHandler<Message> for Handler {
                     current_connections
                 };
 
-                let mut candidates = self.routing_table.candidates();
+                let mut candidates: Vec<_> = self
+                    .routing_table
+                    .candidates()
+                    .into_iter()
+                    .filter(|addr| self.filters.is_trusted(&addr.into()))
+                    .collect();
                 candidates.shuffle(&mut *self.rng.lock());
                 for addr in candidates.into_iter().take(self.min_peers - current_connections) {
                     if let Err(err) = self.connect(io, addr) {
@@ -353,6 +360,7 @@ impl IoHandler<Message> for Handler {
                     .filter(|addr| !self.routing_table.is_establishing_or_established(addr))
                     .filter(|addr| !self.routing_table.is_banned(addr))
                     .filter(|addr| self.filters.is_allowed(&addr.ip()))
+                    .filter(|addr| self.filters.is_trusted(&(*addr).into()))
                     .collect();
                 boots.shuffle(&mut *self.rng.lock());
                 // It tries to connect to 3 of bootstrap nodes.
@@ -482,7 +490,7 @@ impl IoHandler<Message> for Handler {
             }
             Message::ApplyFilters => {
                 for addr in self.routing_table.established_addresses() {
-                    if !self.filters.is_allowed(&addr.ip()) {
+                    if !self.filters.is_allowed(&addr.ip()) || !self.filters.is_trusted(&addr.into()) {
                         if let Some(stream) = self.remote_node_ids_reverse.read().get(&addr.into()) {
                             io.deregister_stream(*stream);
                             cinfo!(NETWORK, "Filter disconnects {}:{}", addr, stream);
@@ -674,21 +682,37 @@ impl IoHandler<Message> for Handler {
                             extension_name,
                             extension_versions,
                         })) => {
-                            let versions = self
+                            let versions = match self
                                 .client
                                 .extension_versions()
                                 .into_iter()
                                 .find(|(name, _)| name == &extension_name)
-                                .map(|(_, versions)| versions)
-                                .ok_or_else(|| format!("{} is not a valid extension name", extension_name))?;
-                            let valid_versions: BTreeSet<u64> = BTreeSet::from_iter(versions.into_iter())
-                                .intersection(&BTreeSet::from_iter(extension_versions.into_iter()))
-                                .cloned()
-                                .collect();
-                            let version = valid_versions
-                                .into_iter()
-                                .last()
-                                .ok_or_else(|| format!("There is no valid version for {}", extension_name))?;
+                            {
+                                Some((_, versions)) => versions,
+                                None => {
+                                    cdebug!(
+                                        NETWORK,
+                                        "{} requested {}, which is not registered",
+                                        con.peer_addr(),
+                                        extension_name
+                                    );
+                                    return Ok(())
+                                }
+                            };
+                            let version = match negotiate(&versions, &extension_versions) {
+                                Some(version) => version,
+                                None => {
+                                    cinfo!(
+                                        NETWORK,
+                                        "{} is not used with {}: {:?} are requested but {:?} are supported",
+                                        extension_name,
+                                        con.peer_addr(),
+                                        extension_versions,
+                                        versions
+                                    );
+                                    return Ok(())
+                                }
+                            };
 
                             let remote_node_id = *self.remote_node_ids.read().get(&stream_token).unwrap_or_else(|| {
                                 unreachable!("Node id for {}:{} must exist", stream_token, con.peer_addr())
@@ -754,6 +778,20 @@ impl IoHandler<Message> for Handler {
                             extension_name,
                             allowed_version,
                         })) => {
+                            let is_requested =
+                                self.client.extension_versions().into_iter().any(|(name, versions)| {
+                                    name == extension_name && versions.contains(&allowed_version)
+                                });
+                            if !is_requested {
+                                cwarn!(
+                                    NETWORK,
+                                    "{} allowed {} v{}, which is not requested",
+                                    con.peer_addr(),
+                                    extension_name,
+                                    allowed_version
+                                );
+                                return Ok(())
+                            }
                             let remote_node_id = *self.remote_node_ids.read().get(&stream_token).unwrap_or_else(|| {
                                 unreachable!("Node id for {}:{} must exist", stream_token, con.peer_addr())
                             });
@@ -788,6 +826,11 @@ impl IoHandler<Message> for Handler {
                                 should_update.store(false, Ordering::SeqCst);
                                 return Err(format!("An invalid network id({}) from {}", network_id, from).into())
                             }
+                            if !self.filters.is_trusted(&from.into()) {
+                                io.deregister_stream(stream_token);
+                                should_update.store(false, Ordering::SeqCst);
+                                return Err(format!("A sync from {} which is not a trusted peer", from).into())
+                            }
                             let network_message_size = if let Some((encrypted_nonce, local_public, session)) =
                                 self.routing_table.set_recipient_establish1(from, initiator_pub_key)?
                             {
@@ -826,6 +869,11 @@ impl IoHandler<Message> for Handler {
                                 io.deregister_stream(stream_token);
                                 return Err(format!("An invalid network id({}) from {}", network_id, from).into())
                             }
+                            if !self.filters.is_trusted(&from.into()) {
+                                should_update.store(false, Ordering::SeqCst);
+                                io.deregister_stream(stream_token);
+                                return Err(format!("A sync from {} which is not a trusted peer", from).into())
+                            }
                             let network_message_size = if let Some((encrypted_nonce, local_public, session)) = self
                                 .routing_table
                                 .set_recipient_establish2(from, recipient_pub_key, initiator_pub_key)?
@@ -1104,6 +1152,13 @@ impl IoHandler<Message> for Handler {
                                 )
                                 .into())
                             }
+                            if !self.filters.is_trusted(&peer_addr.into()) {
+                                return Err(format!(
+                                    "Incoming connection from {} cannot be established because it's not a trusted peer",
+                                    peer_addr
+                                )
+                                .into())
+                            }
                         }
                         self.channel.send(Message::Established {
                             connection,
@@ -1133,6 +1188,13 @@ impl IoHandler<Message> for Handler {
                                 )
                                 .into())
                             }
+                            if !self.filters.is_trusted(&peer_addr.into()) {
+                                return Err(format!(
+                                    "Outgoing connection to {} cannot be established because it's not a trusted peer",
+                                    peer_addr
+                                )
+                                .into())
+                            }
                         }
                         self.channel.send(Message::Established {
                             connection,
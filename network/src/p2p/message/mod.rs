@@ -24,7 +24,7 @@ use primitives::H256;
 
 pub use self::extension::Message as ExtensionMessage;
 pub use self::message::Message;
-pub use self::negotiation::Message as NegotiationMessage;
+pub use self::negotiation::{negotiate, Message as NegotiationMessage};
 pub use self::signed_message::SignedMessage;
 pub use crate::session::Nonce;
 
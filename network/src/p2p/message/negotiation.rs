@@ -18,6 +18,7 @@ use super::Version;
 use super::REQUEST_ID;
 use super::RESPONSE_ID;
 use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+use std::collections::BTreeSet;
 
 #[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub enum Message {
@@ -47,6 +48,14 @@ impl Message {
     }
 }
 
+/// Picks the highest version both sides speak.
+/// The extension is not used in the session if there is none, so that an extension can drop its old versions without
+/// disconnecting the peers that haven't upgraded yet.
+pub fn negotiate(ours: &[Version], theirs: &[Version]) -> Option<Version> {
+    let theirs: BTreeSet<_> = theirs.iter().collect();
+    ours.iter().filter(|version| theirs.contains(version)).max().cloned()
+}
+
 impl Encodable for Message {
     fn rlp_append(&self, s: &mut RlpStream) {
         match self {
@@ -106,4 +115,18 @@ mod tests {
         let extension_name = "some-extension".to_string();
         rlp_encode_and_decode_test!(Message::allowed(extension_name, 2));
     }
+
+    #[test]
+    fn the_highest_common_version_is_negotiated() {
+        assert_eq!(Some(2), negotiate(&[0, 1, 2], &[1, 2, 3]));
+        assert_eq!(Some(1), negotiate(&[1, 0], &[0, 1]));
+        assert_eq!(Some(0), negotiate(&[0], &[0, 1]));
+    }
+
+    #[test]
+    fn nothing_is_negotiated_without_a_common_version() {
+        assert_eq!(None, negotiate(&[0, 1], &[2, 3]));
+        assert_eq!(None, negotiate(&[0, 1], &[]));
+        assert_eq!(None, negotiate(&[], &[0]));
+    }
 }
@@ -75,4 +75,16 @@ mod tests {
         };
         rlp_encode_and_decode_test!(signed);
     }
+
+    #[test]
+    fn truncated_signed_message_is_not_decoded() {
+        let signed = SignedMessage {
+            message: vec![1, 2, 3],
+            signature: Signature::random(),
+        };
+        let encoded = rlp::encode(&signed);
+        for length in 0..encoded.len() {
+            assert!(rlp::decode::<SignedMessage>(&encoded[..length]).is_err(), "{} bytes", length);
+        }
+    }
 }
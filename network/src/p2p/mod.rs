@@ -21,4 +21,6 @@ mod message;
 mod stream;
 
 pub use self::handler::{Handler, ManagingPeerdb, Message};
-use self::message::{ExtensionMessage, Message as NetworkMessage, NegotiationMessage, SignedMessage};
+use self::message::{negotiate, ExtensionMessage, Message as NetworkMessage, NegotiationMessage, SignedMessage};
+#[cfg(feature = "fuzzing")]
+pub use self::message::{Message as P2pMessage, SignedMessage as SignedP2pMessage};
@@ -14,14 +14,17 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use crate::capture::Capture;
 use crate::client::Client;
 use crate::control::{Control, Error as ControlError};
 use crate::filters::{FilterEntry, FiltersControl};
 use crate::routing_table::RoutingTable;
-use crate::{p2p, Api, ManagingPeerdb, NetworkExtension, SocketAddr};
+use crate::stream;
+use crate::{p2p, Api, ChaosConfig, IntoSocketAddr, ManagingPeerdb, NetworkExtension, SocketAddr};
 use cidr::IpCidr;
 use cio::{IoError, IoService};
 use ckey::{NetworkId, Public};
+use cmemory::MemoryConsumer;
 use crossbeam_channel::Sender;
 use ctimer::TimerLoop;
 use std::collections::HashMap;
@@ -47,10 +50,11 @@ impl Service {
         filters_control: Arc<dyn FiltersControl>,
         routing_table: Arc<RoutingTable>,
         peer_db: Box<dyn ManagingPeerdb>,
+        capture: Option<Capture>,
     ) -> Result<Arc<Self>, Error> {
         let p2p = IoService::start("P2P")?;
 
-        let client = Client::new(p2p.channel(), timer_loop);
+        let client = Client::new(p2p.channel(), timer_loop, capture);
 
         let p2p_handler = Arc::new(p2p::Handler::try_new(
             p2p.channel(),
@@ -198,9 +202,57 @@ impl Control for Service {
         Ok(self.filters_control.get_blacklist())
     }
 
+    fn add_trusted_peer(&self, addr: SocketAddr) -> Result<(), ControlError> {
+        self.filters_control.add_trusted_peer(addr.into());
+        Ok(())
+    }
+
+    fn remove_trusted_peer(&self, addr: &SocketAddr) -> Result<(), ControlError> {
+        self.filters_control.remove_trusted_peer(&addr.into());
+        if let Err(err) = self.p2p.send_message(p2p::Message::ApplyFilters) {
+            cerror!(NETWORK, "Error occurred while apply filters: {:?}", err);
+        }
+        Ok(())
+    }
+
+    fn get_trusted_peers(&self) -> Result<(Vec<SocketAddr>, bool), ControlError> {
+        let (list, permissioned) = self.filters_control.get_trusted_peers();
+        Ok((list.into_iter().map(IntoSocketAddr::into_addr).collect(), permissioned))
+    }
+
     fn recent_network_usage(&self) -> Result<HashMap<String, usize>, ControlError> {
         Ok(self.p2p_handler.recent_network_usage())
     }
+
+    #[cfg(feature = "chaos")]
+    fn set_chaos(&self, config: ChaosConfig) -> Result<(), ControlError> {
+        self.client.set_chaos(config);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "chaos"))]
+    fn set_chaos(&self, _config: ChaosConfig) -> Result<(), ControlError> {
+        Err(ControlError::ChaosNotEnabled)
+    }
+
+    #[cfg(feature = "chaos")]
+    fn chaos(&self) -> Result<ChaosConfig, ControlError> {
+        Ok(self.client.chaos())
+    }
+
+    #[cfg(not(feature = "chaos"))]
+    fn chaos(&self) -> Result<ChaosConfig, ControlError> {
+        Err(ControlError::ChaosNotEnabled)
+    }
+}
+
+impl MemoryConsumer for Service {
+    fn memory_usage(&self) -> usize {
+        stream::buffered_bytes()
+    }
+
+    /// The outgoing messages are not dropped. They are sent as fast as the peers receive them.
+    fn restrict_memory(&self, _allowance: Option<usize>) {}
 }
 
 #[derive(Debug)]
@@ -21,8 +21,17 @@ use mio::net::TcpStream;
 use mio::{Poll, PollOpt, Ready, Token};
 use rlp::{Decodable, DecoderError, Encodable, Rlp};
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::{fmt, io, net};
 
+/// The bytes waiting to be sent in all the streams.
+static BUFFERED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// The size of the outgoing messages not sent yet.
+pub fn buffered_bytes() -> usize {
+    BUFFERED_BYTES.load(Ordering::Relaxed)
+}
+
 #[derive(Debug)]
 pub enum Error {
     IoError(io::Error),
@@ -66,7 +75,45 @@ enum ReadRetry {
 struct TryStream<Stream: TryRead + TryWrite + PeerAddr + Shutdown> {
     stream: Stream,
     read: Option<ReadRetry>,
-    write: VecDeque<Vec<u8>>,
+    write: WriteQueue,
+}
+
+/// The messages to send, counted in `BUFFERED_BYTES` until they are sent or dropped.
+#[derive(Default)]
+struct WriteQueue {
+    jobs: VecDeque<Vec<u8>>,
+}
+
+impl WriteQueue {
+    fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    fn push_back(&mut self, job: Vec<u8>) {
+        BUFFERED_BYTES.fetch_add(job.len(), Ordering::Relaxed);
+        self.jobs.push_back(job);
+    }
+
+    fn push_front(&mut self, job: Vec<u8>) {
+        BUFFERED_BYTES.fetch_add(job.len(), Ordering::Relaxed);
+        self.jobs.push_front(job);
+    }
+
+    fn pop_front(&mut self) -> Option<Vec<u8>> {
+        let job = self.jobs.pop_front()?;
+        BUFFERED_BYTES.fetch_sub(job.len(), Ordering::Relaxed);
+        Some(job)
+    }
+
+    fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+impl Drop for WriteQueue {
+    fn drop(&mut self) {
+        self.clear();
+    }
 }
 
 fn parse_len_of_len(bytes: &[u8]) -> usize {
@@ -335,7 +382,7 @@ impl From<TcpStream> for Stream {
             try_stream: TryStream {
                 stream,
                 read: None,
-                write: VecDeque::default(),
+                write: WriteQueue::default(),
             },
         }
     }
@@ -545,7 +592,7 @@ mod tests {
         let mut stream = TryStream {
             stream,
             read: None,
-            write: VecDeque::default(),
+            write: WriteQueue::default(),
         };
         assert_eq!(Some(encoded), stream.read_bytes().unwrap());
     }
@@ -558,7 +605,7 @@ mod tests {
         let mut stream = TryStream {
             stream,
             read: None,
-            write: VecDeque::default(),
+            write: WriteQueue::default(),
         };
         assert_eq!(Some(encoded), stream.read_bytes().unwrap());
     }
@@ -571,7 +618,7 @@ mod tests {
         let mut stream = TryStream {
             stream,
             read: None,
-            write: VecDeque::default(),
+            write: WriteQueue::default(),
         };
         assert_eq!(Some(encoded), stream.read_bytes().unwrap());
     }
@@ -584,7 +631,7 @@ mod tests {
         let mut stream = TryStream {
             stream,
             read: None,
-            write: VecDeque::default(),
+            write: WriteQueue::default(),
         };
         assert_eq!(None, stream.read_bytes().unwrap());
         assert_eq!(Some(encoded), stream.read_bytes().unwrap());
@@ -598,7 +645,7 @@ mod tests {
         let mut stream = TryStream {
             stream,
             read: None,
-            write: VecDeque::default(),
+            write: WriteQueue::default(),
         };
         for i in 0..(encoded.len()) {
             assert_eq!(None, stream.read_bytes().unwrap(), "unexpected result in {}th try", i);
@@ -614,7 +661,7 @@ mod tests {
         let mut stream = TryStream {
             stream,
             read: None,
-            write: VecDeque::default(),
+            write: WriteQueue::default(),
         };
         for i in 0..=encoded.len() {
             assert_eq!(None, stream.read_bytes().unwrap(), "unexpected result in {}th try", i);
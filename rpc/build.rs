@@ -0,0 +1,31 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod openrpc;
+mod rpc_traits;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tonic_build::compile_protos("proto/foundry.proto")?;
+
+    println!("cargo:rerun-if-changed=src/v1/traits");
+    let document = openrpc::generate(Path::new("src/v1/traits"), &env::var("CARGO_PKG_VERSION")?)?;
+    fs::write(Path::new(&env::var("OUT_DIR")?).join("openrpc.json"), document)?;
+    Ok(())
+}
@@ -0,0 +1,139 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Generates the OpenRPC document served by `rpc.discover` from the RPC trait definitions.
+
+use crate::rpc_traits::{parse_methods, split_top_level, strip_wrapper};
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub fn generate(traits_dir: &Path, version: &str) -> io::Result<String> {
+    let mut paths =
+        fs::read_dir(traits_dir)?.map(|entry| entry.map(|entry| entry.path())).collect::<io::Result<Vec<_>>>()?;
+    paths.sort();
+
+    let mut methods = Vec::new();
+    for path in paths {
+        if path.file_name().map_or(true, |name| name == "mod.rs") {
+            continue
+        }
+        methods.extend(parse_methods(&fs::read_to_string(&path)?));
+    }
+    methods.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut schemas = Map::new();
+    let methods: Vec<Value> = methods
+        .into_iter()
+        .map(|method| {
+            let params: Vec<Value> = method
+                .params
+                .iter()
+                .map(|(name, ty)| {
+                    let (required, ty) = match strip_wrapper(ty, "Option") {
+                        Some(inner) => (false, inner),
+                        None => (true, ty.as_str()),
+                    };
+                    json!({
+                        "name": name,
+                        "required": required,
+                        "schema": schema(ty, &mut schemas),
+                    })
+                })
+                .collect();
+            json!({
+                "name": method.name,
+                "summary": method.summary,
+                "params": params,
+                "result": {
+                    "name": "result",
+                    "schema": schema(&method.result, &mut schemas),
+                },
+            })
+        })
+        .collect();
+
+    let document = json!({
+        "openrpc": "1.2.4",
+        "info": {
+            "title": "Foundry JSON-RPC",
+            "version": version,
+        },
+        "methods": methods,
+        "components": {
+            "schemas": schemas,
+        },
+    });
+    Ok(serde_json::to_string_pretty(&document).expect("The document is always serializable"))
+}
+
+fn schema(ty: &str, schemas: &mut Map<String, Value>) -> Value {
+    if let Some(inner) = strip_wrapper(ty, "Option") {
+        return json!({ "oneOf": [schema(inner, schemas), { "type": "null" }] })
+    }
+    if let Some(inner) = strip_wrapper(ty, "Vec") {
+        return json!({ "type": "array", "items": schema(inner, schemas) })
+    }
+    if let Some(inner) = strip_wrapper(ty, "HashMap") {
+        let value = split_top_level(inner).get(1).map_or(json!({}), |value| schema(value, schemas));
+        return json!({ "type": "object", "additionalProperties": value })
+    }
+    if let Some(inner) = strip_wrapper(ty, "WithoutPrefix") {
+        return schema(inner, schemas)
+    }
+    if ty == "()" {
+        return json!({ "type": "null" })
+    }
+    if ty.starts_with('(') && ty.ends_with(')') {
+        let items: Vec<Value> =
+            split_top_level(&ty[1..ty.len() - 1]).into_iter().map(|item| schema(item, schemas)).collect();
+        return json!({ "type": "array", "items": items })
+    }
+    match ty {
+        "bool" => json!({ "type": "boolean" }),
+        "u8" | "u16" | "u32" | "u64" | "usize" | "i32" | "i64" | "BlockNumber" | "ShardId" => {
+            json!({ "type": "integer" })
+        }
+        "f64" => json!({ "type": "number" }),
+        "String" | "Password" | "PlatformAddress" | "NetworkId" | "SocketAddr" => json!({ "type": "string" }),
+        "Value" => json!({}),
+        "BlockNumberOrTag" => {
+            schemas.entry(ty.to_string()).or_insert_with(|| {
+                json!({
+                    "title": ty,
+                    "oneOf": [
+                        { "type": "integer" },
+                        { "type": "string", "enum": ["latest", "finalized", "safe", "pending"] },
+                    ],
+                })
+            });
+            json!({ "$ref": format!("#/components/schemas/{}", ty) })
+        }
+        _ => {
+            let description = match ty {
+                "H160" | "H256" | "H512" | "Public" | "Signature" | "BlockHash" | "TxHash" | "Tracker" | "Uint"
+                | "Bytes" => Some("0x prefixed hexadecimal string"),
+                _ => None,
+            };
+            schemas.entry(ty.to_string()).or_insert_with(|| match description {
+                Some(description) => json!({ "title": ty, "type": "string", "description": description }),
+                None => json!({ "title": ty, "type": "object" }),
+            });
+            json!({ "$ref": format!("#/components/schemas/{}", ty) })
+        }
+    }
+}
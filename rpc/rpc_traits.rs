@@ -0,0 +1,147 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Parses the RPC trait definitions in `src/v1/traits`.
+//! This module is shared by the build scripts of codechain-rpc and foundry-client.
+
+#![allow(dead_code)]
+
+pub struct Method {
+    /// The JSON-RPC method name, e.g. `chain_getBlockByNumber`.
+    pub name: String,
+    /// The name of the Rust function, e.g. `get_block_by_number`.
+    pub function: String,
+    pub summary: String,
+    pub params: Vec<(String, String)>,
+    pub result: String,
+}
+
+/// Returns the name of the trait declared with `#[rpc(server)]`.
+pub fn parse_trait_name(source: &str) -> Option<String> {
+    source.lines().map(str::trim).find(|line| line.starts_with("pub trait ")).and_then(|line| {
+        line["pub trait ".len()..].split(|c: char| !c.is_alphanumeric() && c != '_').next().map(ToString::to_string)
+    })
+}
+
+/// Collects the top level `use` declarations.
+pub fn parse_uses(source: &str) -> Vec<String> {
+    let mut uses = Vec::new();
+    let mut current: Option<String> = None;
+    for line in source.lines() {
+        if let Some(current) = current.as_mut() {
+            current.push(' ');
+            current.push_str(line.trim());
+        } else if line.starts_with("use ") {
+            current = Some(line.trim().to_string());
+        } else {
+            continue
+        }
+        if current.as_ref().map_or(false, |current| current.ends_with(';')) {
+            uses.push(current.take().unwrap());
+        }
+    }
+    uses
+}
+
+/// Collects the methods declared with `#[rpc(name = "...")]` in the source of a trait.
+pub fn parse_methods(source: &str) -> Vec<Method> {
+    let mut methods = Vec::new();
+    let mut docs: Vec<&str> = Vec::new();
+    let mut name: Option<String> = None;
+    let mut signature: Option<String> = None;
+
+    for line in source.lines().map(str::trim) {
+        if let Some(signature) = signature.as_mut() {
+            signature.push_str(line);
+        } else if line.starts_with("///") {
+            docs.push(line.trim_start_matches('/').trim());
+            continue
+        } else if line.starts_with("#[rpc(name = \"") {
+            name = line.split('"').nth(1).map(ToString::to_string);
+            continue
+        } else if line.starts_with("fn ") && name.is_some() {
+            signature = Some(line.to_string());
+        } else {
+            docs.clear();
+            continue
+        }
+
+        if signature.as_ref().map_or(false, |signature| signature.ends_with(';')) {
+            let signature = signature.take().unwrap();
+            if let Some((function, params, result)) = parse_signature(&signature) {
+                methods.push(Method {
+                    name: name.take().unwrap(),
+                    function,
+                    summary: docs.join(" "),
+                    params,
+                    result,
+                });
+            }
+            name = None;
+            docs.clear();
+        }
+    }
+    methods
+}
+
+/// Splits `fn name(&self, a: A, b: B) -> Result<R>;` into the function name, the parameters and the result type.
+fn parse_signature(signature: &str) -> Option<(String, Vec<(String, String)>, String)> {
+    let open = signature.find('(')?;
+    let function = signature["fn ".len()..open].trim().to_string();
+    let arrow = signature.rfind("->")?;
+    let close = signature[..arrow].rfind(')')?;
+    let params = split_top_level(&signature[open + 1..close])
+        .into_iter()
+        .filter(|param| !param.is_empty() && *param != "&self")
+        .filter_map(|param| {
+            let colon = param.find(':')?;
+            Some((param[..colon].trim().to_string(), param[colon + 1..].trim().to_string()))
+        })
+        .collect();
+    let result = signature[arrow + 2..].trim().trim_end_matches(';').trim();
+    // The methods running on the blocking pool return their result as a future.
+    let result = strip_wrapper(result, "Result").or_else(|| strip_wrapper(result, "BoxFuture"))?.to_string();
+    Some((function, params, result))
+}
+
+/// Splits the given list by the commas which are not nested in brackets.
+pub fn split_top_level(list: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, c) in list.char_indices() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(list[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(list[start..].trim());
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+pub fn strip_wrapper<'a>(ty: &'a str, wrapper: &str) -> Option<&'a str> {
+    if ty.starts_with(wrapper) && ty[wrapper.len()..].starts_with('<') && ty.ends_with('>') {
+        Some(ty[wrapper.len() + 1..ty.len() - 1].trim())
+    } else {
+        None
+    }
+}
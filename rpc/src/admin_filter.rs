@@ -0,0 +1,145 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::v1::errors;
+use jsonrpc_core::futures::future::{self, Either};
+use jsonrpc_core::futures::Future;
+use jsonrpc_core::{Call, Error, FutureOutput, FutureResponse, Metadata, Middleware, Output};
+
+/// Rejects the calls to the admin methods.
+/// It's installed on the handlers serving the clients which are not allowed to call them.
+pub struct AdminFilter {
+    rules: Vec<Rule>,
+}
+
+struct Rule {
+    /// A pattern ending with `_` matches all the methods in the namespace. Others match the exact method.
+    pattern: String,
+    rejection: fn(&str) -> Error,
+}
+
+impl AdminFilter {
+    /// Creates a filter passing every call.
+    pub fn none() -> Self {
+        Self {
+            rules: Vec::new(),
+        }
+    }
+
+    /// Rejects the methods which are served only to the TLS clients with a client certificate.
+    pub fn require_client_certificate(self, patterns: &[&str]) -> Self {
+        self.with_rules(patterns, errors::client_certificate_required)
+    }
+
+    /// Rejects the methods which are served only through the admin channel.
+    pub fn require_admin_channel(self, patterns: &[&str]) -> Self {
+        self.with_rules(patterns, errors::admin_channel_required)
+    }
+
+    fn with_rules(mut self, patterns: &[&str], rejection: fn(&str) -> Error) -> Self {
+        self.rules.extend(patterns.iter().map(|pattern| Rule {
+            pattern: pattern.to_string(),
+            rejection,
+        }));
+        self
+    }
+
+    fn rejection(&self, method: &str) -> Option<Error> {
+        self.rules
+            .iter()
+            .find(|rule| {
+                if rule.pattern.ends_with('_') {
+                    method.starts_with(rule.pattern.as_str())
+                } else {
+                    method == rule.pattern
+                }
+            })
+            .map(|rule| (rule.rejection)(method))
+    }
+}
+
+impl<M: Metadata> Middleware<M> for AdminFilter {
+    type Future = FutureResponse;
+    type CallFuture = FutureOutput;
+
+    fn on_call<F, X>(&self, call: Call, meta: M, next: F) -> Either<Self::CallFuture, X>
+    where
+        F: FnOnce(Call, M) -> X + Send,
+        X: Future<Item = Option<Output>, Error = ()> + Send + 'static, {
+        let rejection = match &call {
+            Call::MethodCall(method_call) => self.rejection(&method_call.method),
+            Call::Notification(notification) => self.rejection(&notification.method),
+            Call::Invalid {
+                ..
+            } => None,
+        };
+        match (call, rejection) {
+            (Call::MethodCall(method_call), Some(error)) => {
+                let output = Output::from(Err(error), method_call.id, method_call.jsonrpc);
+                Either::A(Box::new(future::ok::<_, ()>(Some(output))))
+            }
+            (Call::Notification(_), Some(_)) => Either::A(Box::new(future::ok::<_, ()>(None))),
+            (call, _) => Either::B(next(call, meta)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc_core::{MetaIoHandler, Params, Value};
+
+    #[test]
+    fn admin_namespaces_are_rejected() {
+        let mut handler =
+            MetaIoHandler::with_middleware(AdminFilter::none().require_client_certificate(&["account_", "devel_"]));
+        handler.add_method("account_getList", |_params: Params| Ok(Value::Array(Vec::new())));
+        handler.add_method("chain_getBestBlockNumber", |_params: Params| Ok(Value::from(0)));
+
+        let response = handler
+            .handle_request_sync(r#"{"jsonrpc": "2.0", "method": "account_getList", "params": [], "id": 1}"#, ())
+            .unwrap();
+        assert_eq!(
+            r#"{"jsonrpc":"2.0","error":{"code":-32050,"message":"account_getList requires a client certificate"},"id":1}"#,
+            response
+        );
+
+        let response = handler
+            .handle_request_sync(
+                r#"{"jsonrpc": "2.0", "method": "chain_getBestBlockNumber", "params": [], "id": 2}"#,
+                (),
+            )
+            .unwrap();
+        assert_eq!(r#"{"jsonrpc":"2.0","result":0,"id":2}"#, response);
+    }
+
+    #[test]
+    fn pattern_matches_namespace_or_method() {
+        let filter =
+            AdminFilter::none().require_client_certificate(&["net_"]).require_admin_channel(&["account_unlock"]);
+        assert!(filter.rejection("net_connect").is_some());
+        assert!(filter.rejection("network_connect").is_none());
+        assert!(filter.rejection("account_unlock").is_some());
+        assert!(filter.rejection("account_unlockAll").is_none());
+        assert!(AdminFilter::none().rejection("net_connect").is_none());
+    }
+
+    #[test]
+    fn admin_channel_rejection_has_its_own_code() {
+        let filter = AdminFilter::none().require_admin_channel(&["devel_"]);
+        assert_eq!(Some(-32051), filter.rejection("devel_startSealing").map(|error| error.code.code()));
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::v1::errors;
+use jsonrpc_core::futures::future::{self, Either};
+use jsonrpc_core::futures::Future;
+use jsonrpc_core::{Call, Error, FutureOutput, FutureResponse, Metadata, Middleware, Output};
+use parking_lot::RwLock;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// The methods switching the namespaces. They are served even if their namespace is disabled.
+const SWITCH_METHODS: &[&str] = &["devel_enableApi", "devel_disableApi"];
+
+/// Enables and disables the namespaces while the servers run.
+/// The calls to the methods in a disabled namespace are rejected. It's shared by all the servers.
+#[derive(Clone, Default)]
+pub struct ApiSwitch {
+    /// namespace => enabled
+    namespaces: Arc<RwLock<BTreeMap<String, bool>>>,
+}
+
+impl ApiSwitch {
+    pub fn register(&self, namespace: &str, enabled: bool) {
+        self.namespaces.write().insert(namespace.to_string(), enabled);
+    }
+
+    pub fn set_enabled(&self, namespace: &str, enabled: bool) -> Result<(), Error> {
+        match self.namespaces.write().get_mut(namespace) {
+            Some(current) => {
+                *current = enabled;
+                Ok(())
+            }
+            None => Err(errors::unknown_namespace(namespace)),
+        }
+    }
+
+    /// All the registered namespaces, whether they're enabled or not.
+    pub fn namespaces(&self) -> Vec<String> {
+        self.namespaces.read().keys().cloned().collect()
+    }
+
+    pub fn is_enabled(&self, namespace: &str) -> bool {
+        self.namespaces.read().get(namespace).cloned().unwrap_or(false)
+    }
+
+    fn rejection(&self, method: &str) -> Option<Error> {
+        if SWITCH_METHODS.contains(&method) {
+            return None
+        }
+        // The methods without a namespace, such as `ping`, can't be disabled.
+        let underscore = method.find('_')?;
+        match self.namespaces.read().get(&method[..underscore]) {
+            Some(false) => Some(errors::namespace_disabled(method)),
+            _ => None,
+        }
+    }
+}
+
+impl<M: Metadata> Middleware<M> for ApiSwitch {
+    type Future = FutureResponse;
+    type CallFuture = FutureOutput;
+
+    fn on_call<F, X>(&self, call: Call, meta: M, next: F) -> Either<Self::CallFuture, X>
+    where
+        F: FnOnce(Call, M) -> X + Send,
+        X: Future<Item = Option<Output>, Error = ()> + Send + 'static, {
+        let rejection = match &call {
+            Call::MethodCall(method_call) => self.rejection(&method_call.method),
+            Call::Notification(notification) => self.rejection(&notification.method),
+            Call::Invalid {
+                ..
+            } => None,
+        };
+        match (call, rejection) {
+            (Call::MethodCall(method_call), Some(error)) => {
+                let output = Output::from(Err(error), method_call.id, method_call.jsonrpc);
+                Either::A(Box::new(future::ok::<_, ()>(Some(output))))
+            }
+            (Call::Notification(_), Some(_)) => Either::A(Box::new(future::ok::<_, ()>(None))),
+            (call, _) => Either::B(next(call, meta)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc_core::{MetaIoHandler, Params, Value};
+
+    #[test]
+    fn disabled_namespaces_are_rejected() {
+        let switch = ApiSwitch::default();
+        switch.register("devel", false);
+        switch.register("chain", true);
+        let mut handler = MetaIoHandler::with_middleware(switch.clone());
+        handler.add_method("devel_getChaos", |_params: Params| Ok(Value::Bool(true)));
+        handler.add_method("devel_enableApi", |_params: Params| Ok(Value::Null));
+
+        let call = r#"{"jsonrpc": "2.0", "method": "devel_getChaos", "params": [], "id": 1}"#;
+        assert_eq!(
+            r#"{"jsonrpc":"2.0","error":{"code":-32055,"message":"The namespace of devel_getChaos is disabled"},"id":1}"#,
+            handler.handle_request_sync(call, ()).unwrap()
+        );
+        let enable = r#"{"jsonrpc": "2.0", "method": "devel_enableApi", "params": ["devel"], "id": 2}"#;
+        assert_eq!(r#"{"jsonrpc":"2.0","result":null,"id":2}"#, handler.handle_request_sync(enable, ()).unwrap());
+
+        switch.set_enabled("devel", true).unwrap();
+        assert_eq!(r#"{"jsonrpc":"2.0","result":true,"id":1}"#, handler.handle_request_sync(call, ()).unwrap());
+    }
+
+    #[test]
+    fn unknown_namespaces_are_not_switched() {
+        let switch = ApiSwitch::default();
+        switch.register("chain", true);
+        assert!(switch.set_enabled("chian", false).is_err());
+        assert!(switch.rejection("chian_getBestBlockNumber").is_none());
+        assert!(switch.rejection("ping").is_none());
+        assert_eq!(vec!["chain".to_string()], switch.namespaces());
+    }
+}
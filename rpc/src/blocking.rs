@@ -0,0 +1,100 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use jsonrpc_core::futures::sync::oneshot;
+use jsonrpc_core::futures::{future, Future};
+use jsonrpc_core::{BoxFuture, Error, Result};
+use parking_lot::Mutex;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// The threads that run the RPC methods reading the blocks and the states from the database.
+///
+/// Such a method may take long for an old state, so it is run here instead of on the threads
+/// serving the requests, which stay available for the cheap methods.
+#[derive(Clone)]
+pub struct BlockingPool {
+    jobs: Arc<Mutex<Sender<Job>>>,
+}
+
+impl BlockingPool {
+    pub fn new(threads: usize) -> io::Result<Self> {
+        let (jobs, receiver) = channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for index in 0..threads.max(1) {
+            let receiver = Arc::clone(&receiver);
+            thread::Builder::new().name(format!("rpc-blocking-{}", index)).spawn(move || work(&receiver))?;
+        }
+        Ok(Self {
+            jobs: Arc::new(Mutex::new(jobs)),
+        })
+    }
+
+    /// Runs `f` on the pool and returns the future of its result.
+    /// The logs emitted by `f` carry the request id of the caller.
+    pub fn run<T, F>(&self, f: F) -> BoxFuture<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T> + Send + 'static, {
+        let (sender, receiver) = oneshot::channel();
+        let request_id = clogger::request_id();
+        let job = Box::new(move || {
+            // The receiver is gone if the request was cancelled.
+            let _ = sender.send(clogger::with_request_id(request_id, f));
+        });
+        if self.jobs.lock().send(job).is_err() {
+            return Box::new(future::err(Error::internal_error()))
+        }
+        // The sender is dropped without sending only if `f` panicked.
+        Box::new(receiver.then(|result| result.unwrap_or_else(|_| Err(Error::internal_error()))))
+    }
+}
+
+fn work(receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        let job = match receiver.lock().recv() {
+            Ok(job) => job,
+            // All the pools are dropped.
+            Err(_) => return,
+        };
+        // A panicking method must not take down the thread. Its caller gets an internal error.
+        let _ = panic::catch_unwind(AssertUnwindSafe(job));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn result_is_returned_through_the_future() {
+        let pool = BlockingPool::new(2).unwrap();
+        assert_eq!(Ok(3), pool.run(|| Ok(1 + 2)).wait());
+        assert_eq!(Err(Error::invalid_request()), pool.run(|| -> Result<()> { Err(Error::invalid_request()) }).wait());
+    }
+
+    #[test]
+    fn panic_is_returned_as_an_internal_error() {
+        let pool = BlockingPool::new(1).unwrap();
+        assert_eq!(Err(Error::internal_error()), pool.run(|| -> Result<()> { panic!("The method panicked") }).wait());
+        assert_eq!(Ok(()), pool.run(|| Ok(())).wait());
+    }
+}
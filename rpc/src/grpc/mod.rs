@@ -0,0 +1,99 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod notify;
+mod service;
+
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("foundry");
+}
+
+pub use self::notify::NewBlockNotify;
+pub use self::service::FoundryService;
+
+use self::proto::foundry_server::FoundryServer;
+use crate::limits::SubscriptionLimits;
+use ccore::{BlockChainClient, BlocksImported, EngineInfo};
+use crossbeam_channel as crossbeam;
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use tokio::runtime;
+use tokio::sync::oneshot;
+use tonic::transport::Server;
+
+pub struct GrpcServer {
+    shutdown: oneshot::Sender<()>,
+    thread: JoinHandle<()>,
+    stop_notify: crossbeam::Sender<()>,
+    notify_thread: JoinHandle<()>,
+}
+
+impl GrpcServer {
+    /// Stops accepting requests and waits until the server threads end.
+    pub fn close(self) {
+        // The receiver is gone only if the server already stopped.
+        let _ = self.shutdown.send(());
+        if self.thread.join().is_err() {
+            cerror!(RPC, "The gRPC server thread panicked");
+        }
+        drop(self.stop_notify);
+        if self.notify_thread.join().is_err() {
+            cerror!(RPC, "The gRPC notify thread panicked");
+        }
+    }
+}
+
+/// Start gRPC server asynchronously and returns result with `GrpcServer` handle on success or an error.
+/// The blocks of `events` are streamed to the subscribers.
+pub fn start_grpc<C>(
+    addr: &SocketAddr,
+    client: Arc<C>,
+    events: crossbeam::Receiver<BlocksImported>,
+    limits: SubscriptionLimits,
+) -> Result<GrpcServer, io::Error>
+where
+    C: BlockChainClient + EngineInfo + 'static, {
+    // tonic binds the address inside the runtime, so check it here to report the error to the caller.
+    drop(TcpListener::bind(addr)?);
+
+    let mut runtime = runtime::Builder::new().threaded_scheduler().enable_all().thread_name("grpc").build()?;
+    let notify = NewBlockNotify::new(Arc::clone(&client), limits.slow_subscriber);
+    let service = FoundryService::new(client, notify.subscribers(), limits);
+    let (stop_notify, notify_stopped) = crossbeam::bounded(0);
+    let notify_thread =
+        thread::Builder::new().name("grpc notify".to_string()).spawn(move || notify.run(events, notify_stopped))?;
+    let (shutdown, stopped) = oneshot::channel();
+    let addr = *addr;
+    let thread = thread::Builder::new().name("grpc".to_string()).spawn(move || {
+        let server = Server::builder().add_service(FoundryServer::new(service)).serve_with_shutdown(addr, async {
+            // The sender is dropped when the handle is dropped without being closed.
+            let _ = stopped.await;
+        });
+        if let Err(err) = runtime.block_on(server) {
+            cerror!(RPC, "The gRPC server stopped: {}", err);
+        }
+    })?;
+
+    Ok(GrpcServer {
+        shutdown,
+        thread,
+        stop_notify,
+        notify_thread,
+    })
+}
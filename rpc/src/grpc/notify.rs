@@ -0,0 +1,160 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::proto;
+use super::service::block_message;
+use crate::limits::SlowSubscriberPolicy;
+use ccore::{BlockChainClient, BlockId, BlocksImported, EngineInfo};
+use crossbeam_channel as crossbeam;
+use ctypes::BlockHash;
+use parking_lot::Mutex;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tonic::Status;
+
+pub struct Subscriber {
+    pub sender: mpsc::Sender<Result<proto::Block, Status>>,
+    /// The address of the connection which subscribed.
+    pub connection: Option<SocketAddr>,
+}
+
+/// Forwards the blocks enacted to the best chain to the subscribers of `SubscribeNewBlocks`.
+pub struct NewBlockNotify<C> {
+    client: Arc<C>,
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    slow_subscriber: SlowSubscriberPolicy,
+}
+
+impl<C> NewBlockNotify<C> {
+    pub fn new(client: Arc<C>, slow_subscriber: SlowSubscriberPolicy) -> Self {
+        Self {
+            client,
+            subscribers: Default::default(),
+            slow_subscriber,
+        }
+    }
+
+    pub fn subscribers(&self) -> Arc<Mutex<Vec<Subscriber>>> {
+        Arc::clone(&self.subscribers)
+    }
+}
+
+impl<C> NewBlockNotify<C>
+where
+    C: BlockChainClient + EngineInfo, {
+    /// Forwards the blocks of the events until `stop` is closed or the events end.
+    pub fn run(&self, events: crossbeam::Receiver<BlocksImported>, stop: crossbeam::Receiver<()>) {
+        loop {
+            crossbeam::select! {
+                recv(events) -> event => match event {
+                    Ok(event) => self.forward(event.enacted),
+                    Err(crossbeam::RecvError) => return,
+                },
+                recv(stop) -> _ => return,
+            }
+        }
+    }
+
+    fn forward(&self, enacted: Vec<BlockHash>) {
+        let mut subscribers = self.subscribers.lock();
+        if subscribers.is_empty() {
+            return
+        }
+        let network_id = self.client.network_id();
+        for hash in enacted {
+            let block = match self.client.block(&BlockId::Hash(hash)) {
+                Some(block) => block_message(&block, network_id),
+                None => continue,
+            };
+            send(&mut subscribers, &block, self.slow_subscriber);
+        }
+    }
+}
+
+/// Sends the block to the subscribers and removes the ones which are gone.
+/// A subscriber that can't keep up never blocks the import.
+fn send(subscribers: &mut Vec<Subscriber>, block: &proto::Block, slow_subscriber: SlowSubscriberPolicy) {
+    *subscribers = subscribers
+        .drain(..)
+        .filter_map(|mut subscriber| match subscriber.sender.try_send(Ok(block.clone())) {
+            Ok(()) => Some(subscriber),
+            Err(ref err) if err.is_closed() => None,
+            Err(_) => match slow_subscriber {
+                SlowSubscriberPolicy::Drop => {
+                    cdebug!(RPC, "A subscriber of {:?} lost the block #{}", subscriber.connection, block.number);
+                    Some(subscriber)
+                }
+                SlowSubscriberPolicy::Disconnect => {
+                    cinfo!(RPC, "Disconnected a subscriber of {:?}: too slow", subscriber.connection);
+                    None
+                }
+            },
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subscriber(buffer: usize) -> (Subscriber, mpsc::Receiver<Result<proto::Block, Status>>) {
+        let (sender, receiver) = mpsc::channel(buffer);
+        let subscriber = Subscriber {
+            sender,
+            connection: None,
+        };
+        (subscriber, receiver)
+    }
+
+    #[test]
+    fn slow_subscriber_loses_blocks() {
+        let (subscriber, mut receiver) = subscriber(1);
+        let mut subscribers = vec![subscriber];
+        for number in 0..3 {
+            let block = proto::Block {
+                number,
+                ..Default::default()
+            };
+            send(&mut subscribers, &block, SlowSubscriberPolicy::Drop);
+        }
+        assert_eq!(1, subscribers.len());
+        assert_eq!(0, receiver.try_recv().unwrap().unwrap().number);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn slow_subscriber_is_disconnected() {
+        let (slow, _slow_receiver) = subscriber(1);
+        let (fast, mut fast_receiver) = subscriber(4);
+        let mut subscribers = vec![slow, fast];
+        send(&mut subscribers, &proto::Block::default(), SlowSubscriberPolicy::Disconnect);
+        assert_eq!(2, subscribers.len());
+        send(&mut subscribers, &proto::Block::default(), SlowSubscriberPolicy::Disconnect);
+        assert_eq!(1, subscribers.len());
+        assert!(fast_receiver.try_recv().is_ok());
+        assert!(fast_receiver.try_recv().is_ok());
+    }
+
+    #[test]
+    fn closed_subscriber_is_removed() {
+        let (subscriber, receiver) = subscriber(1);
+        let mut subscribers = vec![subscriber];
+        drop(receiver);
+        send(&mut subscribers, &proto::Block::default(), SlowSubscriberPolicy::Drop);
+        assert!(subscribers.is_empty());
+    }
+}
@@ -0,0 +1,271 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::notify::Subscriber;
+use super::proto;
+use super::proto::foundry_server::Foundry;
+use crate::limits::SubscriptionLimits;
+use ccore::{encoded, BlockChainClient, BlockId, EngineInfo, SignedTransaction, UnverifiedTransaction};
+use ckey::{NetworkId, PlatformAddress};
+use ctypes::{BlockHash, TxHash};
+use parking_lot::Mutex;
+use primitives::H256;
+use rlp::Rlp;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tonic::{Request, Response, Status};
+
+pub struct FoundryService<C> {
+    client: Arc<C>,
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    limits: SubscriptionLimits,
+}
+
+impl<C> FoundryService<C> {
+    pub fn new(client: Arc<C>, subscribers: Arc<Mutex<Vec<Subscriber>>>, limits: SubscriptionLimits) -> Self {
+        Self {
+            client,
+            subscribers,
+            limits,
+        }
+    }
+}
+
+impl<C> FoundryService<C>
+where
+    C: BlockChainClient + EngineInfo, {
+    fn block(&self, id: BlockId) -> Result<Response<proto::Block>, Status> {
+        match self.client.block(&id) {
+            Some(block) => Ok(Response::new(block_message(&block, self.client.network_id()))),
+            None => Err(Status::not_found("The block does not exist")),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl<C> Foundry for FoundryService<C>
+where
+    C: BlockChainClient + EngineInfo + 'static, {
+    async fn get_best_block_number(
+        &self,
+        _request: Request<proto::Empty>,
+    ) -> Result<Response<proto::BlockNumber>, Status> {
+        Ok(Response::new(proto::BlockNumber {
+            number: self.client.chain_info().best_block_number,
+        }))
+    }
+
+    async fn get_block_by_number(
+        &self,
+        request: Request<proto::BlockNumber>,
+    ) -> Result<Response<proto::Block>, Status> {
+        self.block(BlockId::Number(request.into_inner().number))
+    }
+
+    async fn get_block_by_hash(&self, request: Request<proto::Hash>) -> Result<Response<proto::Block>, Status> {
+        let hash = h256(&request.into_inner().hash)?;
+        self.block(BlockId::Hash(BlockHash::from(hash)))
+    }
+
+    async fn get_transaction(&self, request: Request<proto::Hash>) -> Result<Response<proto::Transaction>, Status> {
+        let hash = TxHash::from(h256(&request.into_inner().hash)?);
+        let tx =
+            self.client.transaction(&hash.into()).ok_or_else(|| Status::not_found("The transaction does not exist"))?;
+        Ok(Response::new(proto::Transaction {
+            hash: tx.hash().to_vec(),
+            block_hash: tx.block_hash.to_vec(),
+            block_number: tx.block_number,
+            transaction_index: tx.transaction_index as u64,
+            seq: tx.seq,
+            fee: tx.fee,
+            network_id: tx.network_id.to_string(),
+            rlp: rlp::encode(&tx.signed).to_vec(),
+        }))
+    }
+
+    async fn get_account(&self, request: Request<proto::AccountRequest>) -> Result<Response<proto::Account>, Status> {
+        let request = request.into_inner();
+        let address = PlatformAddress::from_str(&request.address)
+            .and_then(|address| address.try_address().map(|address| *address))
+            .map_err(|err| Status::invalid_argument(err.to_string()))?;
+        let block_id = request.block.map(|block| BlockId::Number(block.number)).unwrap_or(BlockId::Latest);
+        let balance = self.client.balance(&address, block_id.into());
+        let seq = self.client.seq(&address, block_id);
+        match (balance, seq) {
+            (Some(balance), Some(seq)) => Ok(Response::new(proto::Account {
+                balance,
+                seq,
+            })),
+            _ => Err(Status::not_found("The state of the block does not exist")),
+        }
+    }
+
+    async fn send_signed_transaction(
+        &self,
+        request: Request<proto::RawTransaction>,
+    ) -> Result<Response<proto::Hash>, Status> {
+        let tx: UnverifiedTransaction = Rlp::new(&request.into_inner().rlp)
+            .as_val()
+            .map_err(|err| Status::invalid_argument(format!("Invalid RLP: {}", err)))?;
+        let signed = SignedTransaction::try_new(tx).map_err(|err| Status::invalid_argument(err.to_string()))?;
+        let hash = signed.hash();
+        self.client.queue_own_transaction(signed).map_err(|err| Status::failed_precondition(err.to_string()))?;
+        Ok(Response::new(proto::Hash {
+            hash: hash.to_vec(),
+        }))
+    }
+
+    type SubscribeNewBlocksStream = mpsc::Receiver<Result<proto::Block, Status>>;
+
+    async fn subscribe_new_blocks(
+        &self,
+        request: Request<proto::Empty>,
+    ) -> Result<Response<Self::SubscribeNewBlocksStream>, Status> {
+        let connection = request.remote_addr();
+        let mut subscribers = self.subscribers.lock();
+        if connection.is_some() {
+            // The closed subscriptions are counted until the next block removes them.
+            let subscriptions = subscribers.iter().filter(|subscriber| subscriber.connection == connection).count();
+            if subscriptions >= self.limits.max_subscriptions_per_connection {
+                return Err(Status::resource_exhausted(format!(
+                    "Too many subscriptions (the limit is {})",
+                    self.limits.max_subscriptions_per_connection
+                )))
+            }
+        }
+        let (sender, receiver) = mpsc::channel(self.limits.buffer);
+        subscribers.push(Subscriber {
+            sender,
+            connection,
+        });
+        Ok(Response::new(receiver))
+    }
+}
+
+pub fn block_message(block: &encoded::Block, network_id: NetworkId) -> proto::Block {
+    let header = block.header_view();
+    proto::Block {
+        hash: header.hash().to_vec(),
+        parent_hash: header.parent_hash().to_vec(),
+        number: header.number(),
+        timestamp: header.timestamp(),
+        author: PlatformAddress::new_v1(network_id, header.author()).to_string(),
+        state_root: header.state_root().to_vec(),
+        transactions_root: header.transactions_root().to_vec(),
+        transaction_hashes: block.transaction_hashes().into_iter().map(|hash| hash.to_vec()).collect(),
+        rlp: block.rlp().as_raw().to_vec(),
+    }
+}
+
+fn h256(bytes: &[u8]) -> Result<H256, Status> {
+    if bytes.len() != 32 {
+        return Err(Status::invalid_argument("A hash must be 32 bytes"))
+    }
+    Ok(H256::from_slice(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::limits::SlowSubscriberPolicy;
+    use ccore::TestBlockChainClient;
+    use ckey::Address;
+    use std::future::Future;
+    use tokio::runtime;
+    use tonic::Code;
+
+    fn service(client: TestBlockChainClient) -> FoundryService<TestBlockChainClient> {
+        let limits = SubscriptionLimits {
+            max_subscriptions_per_connection: 1,
+            buffer: 1,
+            slow_subscriber: SlowSubscriberPolicy::Drop,
+        };
+        FoundryService::new(Arc::new(client), Default::default(), limits)
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        runtime::Builder::new().basic_scheduler().build().unwrap().block_on(future)
+    }
+
+    #[test]
+    fn blocks_are_read_by_number_and_hash() {
+        let client = TestBlockChainClient::new();
+        client.add_blocks(2, 0);
+        let hash = client.block_hash(&BlockId::Number(1)).unwrap();
+        let service = service(client);
+
+        let best = block_on(service.get_best_block_number(Request::new(proto::Empty {}))).unwrap();
+        assert_eq!(2, best.into_inner().number);
+        let by_number = block_on(service.get_block_by_number(Request::new(proto::BlockNumber {
+            number: 1,
+        })))
+        .unwrap()
+        .into_inner();
+        assert_eq!(1, by_number.number);
+        assert_eq!(hash.to_vec(), by_number.hash);
+        let by_hash = block_on(service.get_block_by_hash(Request::new(proto::Hash {
+            hash: hash.to_vec(),
+        })))
+        .unwrap()
+        .into_inner();
+        assert_eq!(by_number, by_hash);
+
+        let missing = block_on(service.get_block_by_number(Request::new(proto::BlockNumber {
+            number: 3,
+        })));
+        assert_eq!(Code::NotFound, missing.unwrap_err().code());
+        let short_hash = block_on(service.get_block_by_hash(Request::new(proto::Hash {
+            hash: vec![0; 31],
+        })));
+        assert_eq!(Code::InvalidArgument, short_hash.unwrap_err().code());
+    }
+
+    #[test]
+    fn account_is_read_from_the_state_of_the_block() {
+        let client = TestBlockChainClient::new();
+        let address = Address::random();
+        client.set_balance(address, 10);
+        client.set_seq(address, 2);
+        let platform_address = PlatformAddress::new_v1(client.network_id(), address).to_string();
+        let service = service(client);
+
+        let latest = block_on(service.get_account(Request::new(proto::AccountRequest {
+            address: platform_address.clone(),
+            block: None,
+        })));
+        assert_eq!(
+            proto::Account {
+                balance: 10,
+                seq: 2,
+            },
+            latest.unwrap().into_inner()
+        );
+        // The test client has only the latest state.
+        let old = block_on(service.get_account(Request::new(proto::AccountRequest {
+            address: platform_address,
+            block: Some(proto::BlockNumber {
+                number: 0,
+            }),
+        })));
+        assert_eq!(Code::NotFound, old.unwrap_err().code());
+        let invalid = block_on(service.get_account(Request::new(proto::AccountRequest {
+            address: "invalid".to_string(),
+            block: None,
+        })));
+        assert_eq!(Code::InvalidArgument, invalid.unwrap_err().code());
+    }
+}
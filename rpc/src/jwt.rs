@@ -0,0 +1,264 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! HS256 JSON Web Tokens authenticating the admin channel.
+//!
+//! The node and its client share a 32 bytes secret in a file as a hex string.
+//! A token is valid only if it's issued within `MAX_CLOCK_SKEW` from the time of the node, so clients issue a new one
+//! for each request.
+
+use hmac::{Hmac, Mac};
+use rustc_hex::{FromHex, ToHex};
+use serde_json::Value;
+use sha2::Sha256;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The allowed difference between `iat` of a token and the time of the node, in seconds.
+pub const MAX_CLOCK_SKEW: u64 = 60;
+
+const SECRET_LENGTH: usize = 32;
+
+#[derive(Clone)]
+pub struct JwtSecret([u8; SECRET_LENGTH]);
+
+#[derive(Debug, PartialEq)]
+pub enum JwtError {
+    Malformed,
+    UnsupportedAlgorithm(String),
+    InvalidSignature,
+    MissingIssuedAt,
+    Stale {
+        issued_at: u64,
+        now: u64,
+    },
+    Expired,
+}
+
+impl fmt::Display for JwtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JwtError::Malformed => write!(f, "The token is malformed"),
+            JwtError::UnsupportedAlgorithm(alg) => write!(f, "The algorithm {} is not supported", alg),
+            JwtError::InvalidSignature => write!(f, "The signature is invalid"),
+            JwtError::MissingIssuedAt => write!(f, "The token doesn't have iat"),
+            JwtError::Stale {
+                issued_at,
+                now,
+            } => write!(f, "The token is issued at {}, which is too far from now({})", issued_at, now),
+            JwtError::Expired => write!(f, "The token is expired"),
+        }
+    }
+}
+
+impl JwtSecret {
+    pub fn new(secret: [u8; SECRET_LENGTH]) -> Self {
+        JwtSecret(secret)
+    }
+
+    /// Reads the hex encoded secret in the file, or creates the file with a random secret if it doesn't exist.
+    /// Only the owner can read the created file, and a file others can read is refused.
+    pub fn load_or_create(path: &Path) -> io::Result<Self> {
+        if !path.exists() {
+            let secret = JwtSecret(rand::random());
+            let mut options = fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            options.mode(0o600);
+            options.open(path)?.write_all(secret.0[..].to_hex().as_bytes())?;
+            return Ok(secret)
+        }
+        #[cfg(unix)]
+        {
+            let mode = fs::metadata(path)?.permissions().mode();
+            if mode & 0o077 != 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!("{} is readable by others({:o}), run `chmod 600` on it", path.display(), mode & 0o777),
+                ))
+            }
+        }
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} must have a {} bytes hex string", path.display(), SECRET_LENGTH),
+            )
+        };
+        let content = fs::read_to_string(path)?;
+        let content = content.trim();
+        let bytes: Vec<u8> = content.trim_start_matches("0x").from_hex().map_err(|_| invalid())?;
+        if bytes.len() != SECRET_LENGTH {
+            return Err(invalid())
+        }
+        let mut secret = [0u8; SECRET_LENGTH];
+        secret.copy_from_slice(&bytes);
+        Ok(JwtSecret(secret))
+    }
+
+    /// Verifies the token at the current time.
+    pub fn verify(&self, token: &str) -> Result<(), JwtError> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).expect("The current time is after the epoch").as_secs();
+        self.verify_at(token, now)
+    }
+
+    pub fn verify_at(&self, token: &str, now: u64) -> Result<(), JwtError> {
+        let mut parts = token.split('.');
+        let (header, claims, signature) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(header), Some(claims), Some(signature), None) => (header, claims, signature),
+            _ => return Err(JwtError::Malformed),
+        };
+
+        let signing_input = &token[..header.len() + 1 + claims.len()];
+
+        let header = decode_json(header)?;
+        match header.get("alg").and_then(Value::as_str) {
+            Some("HS256") => {}
+            Some(alg) => return Err(JwtError::UnsupportedAlgorithm(alg.to_string())),
+            None => return Err(JwtError::Malformed),
+        }
+        let signature = base64::decode_config(signature, base64::URL_SAFE_NO_PAD).map_err(|_| JwtError::Malformed)?;
+        self.mac(signing_input).verify(&signature).map_err(|_| JwtError::InvalidSignature)?;
+
+        let claims = decode_json(claims)?;
+        let issued_at = claims.get("iat").and_then(Value::as_u64).ok_or(JwtError::MissingIssuedAt)?;
+        let skew = if issued_at > now {
+            issued_at - now
+        } else {
+            now - issued_at
+        };
+        if skew > MAX_CLOCK_SKEW {
+            return Err(JwtError::Stale {
+                issued_at,
+                now,
+            })
+        }
+        if let Some(expiration) = claims.get("exp").and_then(Value::as_u64) {
+            if expiration <= now {
+                return Err(JwtError::Expired)
+            }
+        }
+        Ok(())
+    }
+
+    /// Issues a token at the given time. Clients written in Rust can use it to call the admin channel.
+    pub fn issue(&self, issued_at: u64) -> String {
+        let header = base64::encode_config(r#"{"alg":"HS256","typ":"JWT"}"#, base64::URL_SAFE_NO_PAD);
+        let claims = base64::encode_config(&format!(r#"{{"iat":{}}}"#, issued_at), base64::URL_SAFE_NO_PAD);
+        let signing_input = format!("{}.{}", header, claims);
+        let signature = base64::encode_config(&self.mac(&signing_input).result().code(), base64::URL_SAFE_NO_PAD);
+        format!("{}.{}", signing_input, signature)
+    }
+
+    fn mac(&self, signing_input: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_varkey(&self.0).expect("HMAC accepts a key of any length");
+        mac.input(signing_input.as_bytes());
+        mac
+    }
+}
+
+fn decode_json(part: &str) -> Result<Value, JwtError> {
+    let bytes = base64::decode_config(part, base64::URL_SAFE_NO_PAD).map_err(|_| JwtError::Malformed)?;
+    serde_json::from_slice(&bytes).map_err(|_| JwtError::Malformed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_580_000_000;
+
+    fn secret() -> JwtSecret {
+        JwtSecret::new([7; SECRET_LENGTH])
+    }
+
+    #[test]
+    fn issued_token_is_valid() {
+        let token = secret().issue(NOW);
+        assert_eq!(Ok(()), secret().verify_at(&token, NOW));
+        assert_eq!(Ok(()), secret().verify_at(&token, NOW + MAX_CLOCK_SKEW));
+        assert_eq!(Ok(()), secret().verify_at(&token, NOW - MAX_CLOCK_SKEW));
+    }
+
+    #[test]
+    fn stale_token_is_rejected() {
+        let token = secret().issue(NOW);
+        assert_eq!(
+            Err(JwtError::Stale {
+                issued_at: NOW,
+                now: NOW + MAX_CLOCK_SKEW + 1,
+            }),
+            secret().verify_at(&token, NOW + MAX_CLOCK_SKEW + 1)
+        );
+    }
+
+    #[test]
+    fn token_signed_with_another_secret_is_rejected() {
+        let token = JwtSecret::new([8; SECRET_LENGTH]).issue(NOW);
+        assert_eq!(Err(JwtError::InvalidSignature), secret().verify_at(&token, NOW));
+    }
+
+    #[test]
+    fn none_algorithm_is_rejected() {
+        let header = base64::encode_config(r#"{"alg":"none"}"#, base64::URL_SAFE_NO_PAD);
+        let claims = base64::encode_config(&format!(r#"{{"iat":{}}}"#, NOW), base64::URL_SAFE_NO_PAD);
+        let token = format!("{}.{}.", header, claims);
+        assert_eq!(Err(JwtError::UnsupportedAlgorithm("none".to_string())), secret().verify_at(&token, NOW));
+    }
+
+    #[test]
+    fn malformed_token_is_rejected() {
+        assert_eq!(Err(JwtError::Malformed), secret().verify_at("", NOW));
+        assert_eq!(Err(JwtError::Malformed), secret().verify_at("a.b", NOW));
+        assert_eq!(Err(JwtError::Malformed), secret().verify_at("a.b.c.d", NOW));
+    }
+
+    fn secret_path(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("jwt-secret-{}-{}", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn created_secret_is_readable_only_by_the_owner() {
+        let path = secret_path("created");
+        let created = JwtSecret::load_or_create(&path).unwrap();
+        assert_eq!(0o600, fs::metadata(&path).unwrap().permissions().mode() & 0o777);
+
+        let loaded = JwtSecret::load_or_create(&path).unwrap();
+        assert_eq!(created.0, loaded.0);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn secret_readable_by_others_is_refused() {
+        let path = secret_path("readable");
+        fs::write(&path, [7u8; SECRET_LENGTH][..].to_hex()).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        let err = JwtSecret::load_or_create(&path).err().unwrap();
+        assert_eq!(io::ErrorKind::PermissionDenied, err.kind());
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!([7u8; SECRET_LENGTH], JwtSecret::load_or_create(&path).unwrap().0);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -18,6 +18,7 @@ extern crate codechain_core as ccore;
 extern crate codechain_json as cjson;
 extern crate codechain_key as ckey;
 extern crate codechain_keystore as ckeystore;
+#[macro_use]
 extern crate codechain_logger as clogger;
 extern crate codechain_network as cnetwork;
 extern crate codechain_state as cstate;
@@ -35,12 +36,23 @@ use cidr;
 pub use jsonrpc_core;
 use jsonrpc_http_server;
 
+mod admin_filter;
+mod api_switch;
+mod blocking;
+pub mod grpc;
+pub mod jwt;
+mod limits;
+mod relay;
+mod request_id;
+mod response_cache;
+pub mod rest;
 pub mod rpc_server;
+mod subscription;
 pub mod v1;
 
 pub use rustc_serialize::hex;
 
-pub use jsonrpc_core::{Compatibility, Error, MetaIoHandler, Middleware, Params, Value};
+pub use jsonrpc_core::{Compatibility, Error, MetaIoHandler, Metadata, Middleware, Params, Value};
 
 pub use jsonrpc_http_server::Server as HttpServer;
 pub use rpc_server::start_http;
@@ -50,3 +62,17 @@ pub use rpc_server::start_ipc;
 
 pub use jsonrpc_ws_server::{Error as WsError, Server as WsServer};
 pub use rpc_server::start_ws;
+
+pub use grpc::{start_grpc, GrpcServer};
+pub use relay::{start_relay, start_tls, RelayServer, TlsConfig};
+pub use request_id::RequestIdMiddleware;
+pub use rest::{start_rest, RestServer};
+
+pub use admin_filter::AdminFilter;
+pub use api_switch::ApiSwitch;
+pub use blocking::BlockingPool;
+pub use jwt::JwtSecret;
+pub use limits::{Connection, InFlightLimit, RpcLimits, SlowSubscriberPolicy, SubscriptionLimits};
+pub use response_cache::{ResponseCache, ResponseCacheNotify};
+pub use rpc_server::start_authenticated_http;
+pub use subscription::{start_ws_notify, WsNotify, WsSession, WsSubscriptions};
@@ -0,0 +1,264 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::subscription::WsSession;
+use crate::v1::errors;
+use jsonrpc_core::futures::future::{self, Either};
+use jsonrpc_core::futures::Future;
+use jsonrpc_core::{Call, FutureOutput, FutureResponse, Metadata, Middleware, Output, Request, Response};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The limits protecting the node from the clients exhausting its memory or file descriptors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RpcLimits {
+    /// The maximum size of an HTTP request body or a WebSocket frame in bytes.
+    pub max_payload_size: usize,
+    /// The maximum number of calls of a connection processed at the same time.
+    pub max_in_flight_requests: usize,
+    /// A connection is closed if the client sends nothing for this duration.
+    pub read_timeout: Duration,
+    /// A connection is closed if the client doesn't receive a response in this duration.
+    pub write_timeout: Duration,
+}
+
+impl Default for RpcLimits {
+    fn default() -> Self {
+        Self {
+            max_payload_size: 5 * 1024 * 1024,
+            max_in_flight_requests: 64,
+            read_timeout: Duration::from_secs(300),
+            write_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// What happens to a subscriber whose buffer is full when a new block comes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SlowSubscriberPolicy {
+    /// The subscriber loses the block.
+    Drop,
+    /// The subscriber is disconnected.
+    Disconnect,
+}
+
+impl FromStr for SlowSubscriberPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "drop" => Ok(SlowSubscriberPolicy::Drop),
+            "disconnect" => Ok(SlowSubscriberPolicy::Disconnect),
+            _ => Err(format!("{} is not a slow subscriber policy. It must be drop or disconnect", s)),
+        }
+    }
+}
+
+/// The limits keeping the subscribers from buffering the blocks without bound.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SubscriptionLimits {
+    /// The maximum number of the subscriptions of a connection.
+    pub max_subscriptions_per_connection: usize,
+    /// The number of blocks buffered for a subscriber.
+    pub buffer: usize,
+    pub slow_subscriber: SlowSubscriberPolicy,
+}
+
+impl Default for SubscriptionLimits {
+    fn default() -> Self {
+        Self {
+            max_subscriptions_per_connection: 16,
+            buffer: 128,
+            slow_subscriber: SlowSubscriberPolicy::Drop,
+        }
+    }
+}
+
+/// The metadata shared by the requests of a connection.
+#[derive(Clone, Debug, Default)]
+pub struct Connection {
+    in_flight: Option<Arc<AtomicUsize>>,
+    request_id: Option<String>,
+    session: Option<WsSession>,
+}
+
+impl Connection {
+    /// The metadata of a connection whose requests are processed concurrently.
+    /// The calls in flight are counted across its requests.
+    pub fn concurrent() -> Self {
+        Self {
+            in_flight: Some(Arc::new(AtomicUsize::new(0))),
+            request_id: None,
+            session: None,
+        }
+    }
+
+    /// The metadata of a WebSocket session, whose requests are processed concurrently.
+    pub fn with_session(session: WsSession) -> Self {
+        Self {
+            in_flight: Some(Arc::new(AtomicUsize::new(0))),
+            request_id: None,
+            session: Some(session),
+        }
+    }
+
+    /// The metadata of an HTTP request carrying the id given by the client.
+    pub fn with_request_id(request_id: Option<String>) -> Self {
+        Self {
+            in_flight: None,
+            request_id,
+            session: None,
+        }
+    }
+
+    /// The id given by the client to correlate its request with the logs of the node.
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_ref().map(String::as_str)
+    }
+
+    /// The WebSocket session to which the notifications of the subscriptions are sent.
+    pub fn session(&self) -> Option<&WsSession> {
+        self.session.as_ref()
+    }
+}
+
+impl Metadata for Connection {}
+
+/// Rejects the requests which make the calls in flight of a connection exceed the limit.
+/// The connections without a counter process their requests one by one,
+/// so only the number of calls in a batch is limited for them.
+pub struct InFlightLimit {
+    max_in_flight_requests: usize,
+}
+
+impl InFlightLimit {
+    pub fn new(max_in_flight_requests: usize) -> Self {
+        Self {
+            max_in_flight_requests,
+        }
+    }
+
+    fn reject(&self, request: Request) -> Option<Response> {
+        let output = |call: Call| match call {
+            Call::MethodCall(method_call) => Some(Output::from(
+                Err(errors::too_many_requests(self.max_in_flight_requests)),
+                method_call.id,
+                method_call.jsonrpc,
+            )),
+            _ => None,
+        };
+        match request {
+            Request::Single(call) => output(call).map(Response::Single),
+            Request::Batch(calls) => {
+                let outputs: Vec<_> = calls.into_iter().filter_map(output).collect();
+                if outputs.is_empty() {
+                    None
+                } else {
+                    Some(Response::Batch(outputs))
+                }
+            }
+        }
+    }
+}
+
+impl Middleware<Connection> for InFlightLimit {
+    type Future = FutureResponse;
+    type CallFuture = FutureOutput;
+
+    fn on_request<F, X>(&self, request: Request, meta: Connection, next: F) -> Either<Self::Future, X>
+    where
+        F: FnOnce(Request, Connection) -> X + Send,
+        X: Future<Item = Option<Response>, Error = ()> + Send + 'static, {
+        let calls = match &request {
+            Request::Single(_) => 1,
+            Request::Batch(calls) => calls.len(),
+        };
+        let in_flight = meta.in_flight.clone();
+        let accepted = match &in_flight {
+            Some(in_flight) => {
+                let previous = in_flight.fetch_add(calls, Ordering::SeqCst);
+                if previous + calls > self.max_in_flight_requests {
+                    in_flight.fetch_sub(calls, Ordering::SeqCst);
+                    false
+                } else {
+                    true
+                }
+            }
+            None => calls <= self.max_in_flight_requests,
+        };
+        if !accepted {
+            cdebug!(RPC, "Rejected a request of {} calls: too many calls in flight", calls);
+            return Either::A(Box::new(future::ok(self.reject(request))))
+        }
+        match in_flight {
+            Some(in_flight) => Either::A(Box::new(next(request, meta).then(move |response| {
+                in_flight.fetch_sub(calls, Ordering::SeqCst);
+                response
+            }))),
+            None => Either::B(next(request, meta)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc_core::{MetaIoHandler, Params, Value};
+
+    fn handler(max_in_flight_requests: usize) -> MetaIoHandler<Connection, InFlightLimit> {
+        let mut handler = MetaIoHandler::with_middleware(InFlightLimit::new(max_in_flight_requests));
+        handler.add_method("ping", |_params: Params| Ok(Value::String("pong".to_string())));
+        handler
+    }
+
+    #[test]
+    fn large_batch_is_rejected() {
+        let handler = handler(1);
+        let request =
+            r#"[{"jsonrpc": "2.0", "method": "ping", "id": 1}, {"jsonrpc": "2.0", "method": "ping", "id": 2}]"#;
+        let response = handler.handle_request_sync(request, Connection::default()).unwrap();
+        assert_eq!(
+            r#"[{"jsonrpc":"2.0","error":{"code":-32052,"message":"Too many calls in flight (the limit is 1)"},"id":1},{"jsonrpc":"2.0","error":{"code":-32052,"message":"Too many calls in flight (the limit is 1)"},"id":2}]"#,
+            response
+        );
+
+        let request = r#"{"jsonrpc": "2.0", "method": "ping", "id": 3}"#;
+        let response = handler.handle_request_sync(request, Connection::default()).unwrap();
+        assert_eq!(r#"{"jsonrpc":"2.0","result":"pong","id":3}"#, response);
+    }
+
+    #[test]
+    fn calls_in_flight_are_counted_per_connection() {
+        let handler = handler(2);
+        let request = r#"{"jsonrpc": "2.0", "method": "ping", "id": 1}"#;
+
+        let busy = Connection::concurrent();
+        busy.in_flight.as_ref().unwrap().store(2, Ordering::SeqCst);
+        let response = handler.handle_request_sync(request, busy.clone()).unwrap();
+        assert_eq!(
+            r#"{"jsonrpc":"2.0","error":{"code":-32052,"message":"Too many calls in flight (the limit is 2)"},"id":1}"#,
+            response
+        );
+        assert_eq!(2, busy.in_flight.as_ref().unwrap().load(Ordering::SeqCst));
+
+        let idle = Connection::concurrent();
+        let response = handler.handle_request_sync(request, idle.clone()).unwrap();
+        assert_eq!(r#"{"jsonrpc":"2.0","result":"pong","id":1}"#, response);
+        assert_eq!(0, idle.in_flight.as_ref().unwrap().load(Ordering::SeqCst));
+    }
+}
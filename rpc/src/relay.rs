@@ -0,0 +1,226 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::limits::RpcLimits;
+use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod, SslVerifyMode};
+use openssl::x509::X509Name;
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime;
+use tokio::sync::oneshot;
+use tokio::time::timeout;
+use tokio_openssl::SslStream;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TlsConfig {
+    /// The PEM file of the certificate chain of the server.
+    pub cert_path: PathBuf,
+    /// The PEM file of the private key of the server, in PKCS #8 or PKCS #1.
+    pub key_path: PathBuf,
+    /// The PEM file of the CA certificates issuing the client certificates.
+    /// The clients are not asked for a certificate if it's not given.
+    pub client_ca_path: Option<PathBuf>,
+}
+
+pub struct RelayServer {
+    shutdown: oneshot::Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl RelayServer {
+    /// Stops accepting connections and waits until the server thread ends.
+    pub fn close(self) {
+        // The receiver is gone only if the server already stopped.
+        let _ = self.shutdown.send(());
+        if self.thread.join().is_err() {
+            cerror!(RPC, "The relay server thread panicked");
+        }
+    }
+}
+
+/// Start relay server asynchronously and returns result with `RelayServer` handle on success or an error.
+///
+/// The server relays the connections to a plain server listening on `backend`,
+/// closing the connections whose client doesn't read or write in time.
+pub fn start_relay(addr: &SocketAddr, backend: SocketAddr, limits: &RpcLimits) -> Result<RelayServer, io::Error> {
+    serve(addr, None, backend, None, limits)
+}
+
+/// Start TLS server asynchronously and returns result with `RelayServer` handle on success or an error.
+///
+/// The server terminates TLS and relays the decrypted stream to a plain server listening on `backend`.
+/// If `admin_backend` is given, the connections authenticated with a client certificate are relayed to it instead.
+pub fn start_tls(
+    addr: &SocketAddr,
+    config: &TlsConfig,
+    backend: SocketAddr,
+    admin_backend: Option<SocketAddr>,
+    limits: &RpcLimits,
+) -> Result<RelayServer, io::Error> {
+    let acceptor = server_config(config)?;
+    serve(addr, Some(acceptor), backend, admin_backend, limits)
+}
+
+fn serve(
+    addr: &SocketAddr,
+    acceptor: Option<SslAcceptor>,
+    backend: SocketAddr,
+    admin_backend: Option<SocketAddr>,
+    limits: &RpcLimits,
+) -> Result<RelayServer, io::Error> {
+    let timeouts = Timeouts {
+        read: limits.read_timeout,
+        write: limits.write_timeout,
+    };
+    let mut runtime = runtime::Builder::new().threaded_scheduler().enable_all().thread_name("relay").build()?;
+    let listener = std::net::TcpListener::bind(addr)?;
+    let mut listener = runtime.enter(|| TcpListener::from_std(listener))?;
+
+    let (shutdown, stopped) = oneshot::channel::<()>();
+    let thread = thread::Builder::new().name("relay".to_string()).spawn(move || {
+        runtime.block_on(async move {
+            let serve = async {
+                loop {
+                    let (stream, peer) = match listener.accept().await {
+                        Ok(accepted) => accepted,
+                        Err(err) => {
+                            cwarn!(RPC, "Cannot accept a connection: {}", err);
+                            continue
+                        }
+                    };
+                    let acceptor = acceptor.clone();
+                    tokio::spawn(async move {
+                        let result = match acceptor {
+                            Some(acceptor) => relay_tls(acceptor, stream, backend, admin_backend, timeouts).await,
+                            None => relay(stream, backend, timeouts).await,
+                        };
+                        if let Err(err) = result {
+                            cdebug!(RPC, "The connection from {} is closed: {}", peer, err);
+                        }
+                    });
+                }
+            };
+            tokio::select! {
+                _ = serve => {}
+                // The sender is dropped when the handle is dropped without being closed.
+                _ = stopped => {}
+            }
+        });
+    })?;
+
+    Ok(RelayServer {
+        shutdown,
+        thread,
+    })
+}
+
+#[derive(Clone, Copy)]
+struct Timeouts {
+    read: Duration,
+    write: Duration,
+}
+
+async fn relay_tls(
+    acceptor: SslAcceptor,
+    stream: TcpStream,
+    backend: SocketAddr,
+    admin_backend: Option<SocketAddr>,
+    timeouts: Timeouts,
+) -> io::Result<()> {
+    let stream = with_timeout(Some(timeouts.read), handshake(&acceptor, stream)).await?;
+    // The verifier rejects the handshake if the certificate is not issued by the client CA,
+    // so a certificate here is an authenticated one.
+    let authenticated = stream.ssl().peer_certificate().is_some();
+    let target = match admin_backend {
+        Some(admin_backend) if authenticated => admin_backend,
+        _ => backend,
+    };
+    relay(stream, target, timeouts).await
+}
+
+async fn relay<S: AsyncRead + AsyncWrite>(stream: S, target: SocketAddr, timeouts: Timeouts) -> io::Result<()> {
+    let mut upstream = TcpStream::connect(target).await?;
+
+    let (mut client_read, mut client_write) = tokio::io::split(stream);
+    let (mut upstream_read, mut upstream_write) = upstream.split();
+    // The backend is trusted, so only the client side is bounded by the timeouts.
+    let to_upstream = copy(&mut client_read, &mut upstream_write, Some(timeouts.read), None);
+    let to_client = copy(&mut upstream_read, &mut client_write, None, Some(timeouts.write));
+    tokio::try_join!(to_upstream, to_client)?;
+    Ok(())
+}
+
+/// Copies the stream until EOF. It fails if a read or a write doesn't finish in the given time.
+async fn copy<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+) -> io::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin, {
+    let mut buf = [0u8; 8 * 1024];
+    loop {
+        let read = with_timeout(read_timeout, reader.read(&mut buf)).await?;
+        if read == 0 {
+            return with_timeout(write_timeout, writer.shutdown()).await
+        }
+        with_timeout(write_timeout, writer.write_all(&buf[..read])).await?;
+    }
+}
+
+async fn with_timeout<T>(
+    duration: Option<Duration>,
+    future: impl std::future::Future<Output = io::Result<T>>,
+) -> io::Result<T> {
+    match duration {
+        Some(duration) => timeout(duration, future).await.map_err(|_| io::Error::from(io::ErrorKind::TimedOut))?,
+        None => future.await,
+    }
+}
+
+fn server_config(config: &TlsConfig) -> io::Result<SslAcceptor> {
+    let mut builder =
+        SslAcceptor::mozilla_intermediate(SslMethod::tls()).map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+    builder.set_certificate_chain_file(&config.cert_path).map_err(|err| invalid_data(&config.cert_path, err))?;
+    builder
+        .set_private_key_file(&config.key_path, SslFiletype::PEM)
+        .and_then(|_| builder.check_private_key())
+        .map_err(|err| invalid_data(&config.key_path, err))?;
+    if let Some(path) = &config.client_ca_path {
+        builder.set_ca_file(path).map_err(|err| invalid_data(path, err))?;
+        builder.set_client_ca_list(X509Name::load_client_ca_file(path).map_err(|err| invalid_data(path, err))?);
+        // The clients without a certificate are still accepted, as anonymous ones.
+        builder.set_verify(SslVerifyMode::PEER);
+    }
+    Ok(builder.build())
+}
+
+async fn handshake(acceptor: &SslAcceptor, stream: TcpStream) -> io::Result<SslStream<TcpStream>> {
+    tokio_openssl::accept(acceptor, stream)
+        .await
+        .map_err(|err| io::Error::new(io::ErrorKind::ConnectionAborted, err.to_string()))
+}
+
+fn invalid_data(path: &Path, err: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), err.to_string()))
+}
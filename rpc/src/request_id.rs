@@ -0,0 +1,107 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::limits::Connection;
+use jsonrpc_core::futures::future::Either;
+use jsonrpc_core::futures::Future;
+use jsonrpc_core::{Call, FutureOutput, FutureResponse, Middleware, Output};
+
+/// The header with which an HTTP client names its request.
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+/// The longer ids are ignored, so a client can't flood the logs through them.
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// Returns the id given in the `X-Request-Id` header if it is short and printable.
+pub fn parse_request_id(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() || value.len() > MAX_REQUEST_ID_LENGTH || !value.chars().all(|c| c.is_ascii_graphic()) {
+        return None
+    }
+    Some(value.to_string())
+}
+
+/// Attaches a request id to the logs emitted while servicing each call.
+///
+/// The id given by the client is shared by all the calls of its request. Otherwise every call gets its own id.
+/// The methods run on the `BlockingPool` carry the id to the pool threads.
+pub struct RequestIdMiddleware;
+
+impl Middleware<Connection> for RequestIdMiddleware {
+    type Future = FutureResponse;
+    type CallFuture = FutureOutput;
+
+    fn on_call<F, X>(&self, call: Call, meta: Connection, next: F) -> Either<Self::CallFuture, X>
+    where
+        F: FnOnce(Call, Connection) -> X + Send,
+        X: Future<Item = Option<Output>, Error = ()> + Send + 'static, {
+        let request_id = meta.request_id().map(str::to_string).unwrap_or_else(generate_request_id);
+        Either::B(clogger::with_request_id(Some(request_id), || next(call, meta)))
+    }
+}
+
+fn generate_request_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BlockingPool;
+    use jsonrpc_core::{MetaIoHandler, Params, Value};
+
+    fn handler() -> MetaIoHandler<Connection, RequestIdMiddleware> {
+        let mut handler = MetaIoHandler::with_middleware(RequestIdMiddleware);
+        handler.add_method("requestId", |_params: Params| Ok(Value::from(clogger::request_id().unwrap_or_default())));
+        let pool = BlockingPool::new(1).unwrap();
+        handler.add_method("blockingRequestId", move |_params: Params| {
+            pool.run(|| Ok(Value::from(clogger::request_id().unwrap_or_default())))
+        });
+        handler
+    }
+
+    #[test]
+    fn given_request_id_is_attached() {
+        let handler = handler();
+        let request = r#"[{"jsonrpc": "2.0", "method": "requestId", "id": 1}, {"jsonrpc": "2.0", "method": "blockingRequestId", "id": 2}]"#;
+        let response =
+            handler.handle_request_sync(request, Connection::with_request_id(Some("client-1".to_string()))).unwrap();
+        assert_eq!(
+            r#"[{"jsonrpc":"2.0","result":"client-1","id":1},{"jsonrpc":"2.0","result":"client-1","id":2}]"#,
+            response
+        );
+        assert_eq!(None, clogger::request_id());
+    }
+
+    #[test]
+    fn request_id_is_generated_per_call() {
+        let handler = handler();
+        let request = r#"[{"jsonrpc": "2.0", "method": "requestId", "id": 1}, {"jsonrpc": "2.0", "method": "requestId", "id": 2}]"#;
+        let response = handler.handle_request_sync(request, Connection::default()).unwrap();
+        let outputs: Vec<Value> = serde_json::from_str(&response).unwrap();
+        let first = outputs[0]["result"].as_str().unwrap();
+        let second = outputs[1]["result"].as_str().unwrap();
+        assert_eq!(16, first.len());
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn invalid_request_id_is_ignored() {
+        assert_eq!(Some("abc-123".to_string()), parse_request_id(" abc-123 "));
+        assert_eq!(None, parse_request_id(""));
+        assert_eq!(None, parse_request_id("two words"));
+        assert_eq!(None, parse_request_id(&"a".repeat(MAX_REQUEST_ID_LENGTH + 1)));
+    }
+}
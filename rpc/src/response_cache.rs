@@ -0,0 +1,259 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ccore::{BlockChainTrait, BlockId, ChainNotify, EngineInfo};
+use ctypes::BlockHash;
+use jsonrpc_core::futures::future::{self, Either};
+use jsonrpc_core::futures::Future;
+use jsonrpc_core::{Call, FutureOutput, FutureResponse, Metadata, Middleware, Output, Params, Value};
+use lru_cache::LruCache;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// The methods whose result is determined by the block given as a parameter, with the index of the parameter.
+const CACHEABLE_METHODS: &[(&str, usize)] = &[
+    ("chain_getBlockHash", 0),
+    ("chain_getBlockByNumber", 0),
+    ("chain_getBlockExecutionSummary", 0),
+    ("chain_getMiningReward", 0),
+    ("chain_getSeq", 1),
+    ("chain_getBalance", 1),
+    ("chain_getLockedBalance", 1),
+    ("chain_resolveName", 1),
+    ("chain_getNameOf", 1),
+    ("chain_getRegularKey", 1),
+    ("chain_getRegularKeyOwner", 1),
+    ("chain_getNumberOfShards", 0),
+    ("chain_getShardRoot", 1),
+    ("chain_getShardOwners", 1),
+    ("chain_getShardUsers", 1),
+    ("chain_getCommonParams", 0),
+    ("chain_getTermMetadata", 0),
+    ("chain_getMetadataSeq", 0),
+    ("stake_getBalance", 1),
+    ("stake_getDelegations", 1),
+    ("stake_getValidators", 0),
+    ("stake_getCandidate", 1),
+    ("stake_getCommission", 1),
+];
+
+/// Answers the calls querying a finalized block from the results of the same calls made before.
+/// The results of the blocks above the finality point are not cached, and the results cached at or above
+/// the first block of a new branch are dropped when the chain is reorganized below the finality point.
+#[derive(Clone)]
+pub struct ResponseCache {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    /// (method, params) => (block number, result)
+    entries: Mutex<LruCache<(String, String), (u64, Value)>>,
+    finalized: AtomicU64,
+}
+
+impl ResponseCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                entries: Mutex::new(LruCache::new(capacity)),
+                finalized: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// The blocks at or below `number` are final.
+    pub fn set_finalized(&self, number: u64) {
+        self.inner.finalized.store(number, Ordering::SeqCst);
+    }
+
+    /// Drops the results of the blocks at or above `number`.
+    pub fn invalidate_from(&self, number: u64) {
+        let mut entries = self.inner.entries.lock();
+        let invalidated: Vec<_> = entries
+            .iter()
+            .filter(|(_, (block_number, _))| *block_number >= number)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in invalidated {
+            entries.remove(&key);
+        }
+    }
+
+    /// Returns the key and the queried block number if the result of the call can be cached.
+    fn cacheable(&self, method: &str, params: &Params) -> Option<((String, String), u64)> {
+        let index = CACHEABLE_METHODS.iter().find(|(name, _)| *name == method).map(|(_, index)| *index)?;
+        let block_number = match params {
+            Params::Array(values) => values.get(index)?.as_u64()?,
+            _ => return None,
+        };
+        if block_number > self.inner.finalized.load(Ordering::SeqCst) {
+            return None
+        }
+        let key = (method.to_string(), serde_json::to_string(params).ok()?);
+        Some((key, block_number))
+    }
+
+    fn get(&self, key: &(String, String)) -> Option<Value> {
+        self.inner.entries.lock().get_mut(key).map(|(_, result)| result.clone())
+    }
+
+    fn insert(&self, key: (String, String), block_number: u64, result: Value) {
+        // The block may have been replaced while the call was processed.
+        if block_number <= self.inner.finalized.load(Ordering::SeqCst) {
+            self.inner.entries.lock().insert(key, (block_number, result));
+        }
+    }
+}
+
+impl<M: Metadata> Middleware<M> for ResponseCache {
+    type Future = FutureResponse;
+    type CallFuture = FutureOutput;
+
+    fn on_call<F, X>(&self, call: Call, meta: M, next: F) -> Either<Self::CallFuture, X>
+    where
+        F: FnOnce(Call, M) -> X + Send,
+        X: Future<Item = Option<Output>, Error = ()> + Send + 'static, {
+        let cacheable = match &call {
+            Call::MethodCall(method_call) => self
+                .cacheable(&method_call.method, &method_call.params)
+                .map(|(key, block_number)| (key, block_number, method_call.id.clone(), method_call.jsonrpc)),
+            _ => None,
+        };
+        let (key, block_number) = match cacheable {
+            Some((key, block_number, id, jsonrpc)) => match self.get(&key) {
+                Some(result) => {
+                    let output = Output::from(Ok(result), id, jsonrpc);
+                    return Either::A(Box::new(future::ok::<_, ()>(Some(output))))
+                }
+                None => (key, block_number),
+            },
+            None => return Either::B(next(call, meta)),
+        };
+
+        let cache = self.clone();
+        Either::A(Box::new(next(call, meta).map(move |output| {
+            if let Some(Output::Success(success)) = &output {
+                if !success.result.is_null() {
+                    cache.insert(key, block_number, success.result.clone());
+                }
+            }
+            output
+        })))
+    }
+}
+
+/// Moves the finality point of the cache as the blocks are imported.
+pub struct ResponseCacheNotify<C> {
+    cache: ResponseCache,
+    client: Arc<C>,
+}
+
+impl<C> ResponseCacheNotify<C> {
+    pub fn new(cache: ResponseCache, client: Arc<C>) -> Self {
+        Self {
+            cache,
+            client,
+        }
+    }
+}
+
+impl<C> ChainNotify for ResponseCacheNotify<C>
+where
+    C: BlockChainTrait + EngineInfo,
+{
+    fn new_blocks(
+        &self,
+        _imported: Vec<BlockHash>,
+        _invalid: Vec<BlockHash>,
+        enacted: Vec<BlockHash>,
+        _sealed: Vec<BlockHash>,
+    ) {
+        let client = &self.client;
+        let first_enacted = enacted.iter().filter_map(|hash| client.block_number(&BlockId::Hash(*hash))).min();
+        if let Some(first_enacted) = first_enacted {
+            if first_enacted <= self.cache.inner.finalized.load(Ordering::SeqCst) {
+                cwarn!(RPC, "The chain is reorganized from #{} below the finality point", first_enacted);
+                self.cache.invalidate_from(first_enacted);
+            }
+        }
+        let best_block_number = client.chain_info().best_block_number;
+        let confirmation = u64::from(client.recommended_confirmation());
+        self.cache.set_finalized(best_block_number.saturating_sub(confirmation));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Connection;
+    use jsonrpc_core::MetaIoHandler;
+    use std::sync::atomic::AtomicUsize;
+
+    fn handler(cache: ResponseCache, calls: Arc<AtomicUsize>) -> MetaIoHandler<Connection, ResponseCache> {
+        let mut handler = MetaIoHandler::with_middleware(cache);
+        handler.add_method("chain_getBlockHash", move |_params: Params| {
+            let count = calls.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(Value::from(count))
+        });
+        handler
+    }
+
+    fn call(handler: &MetaIoHandler<Connection, ResponseCache>, block_number: u64) -> String {
+        let request =
+            format!(r#"{{"jsonrpc": "2.0", "method": "chain_getBlockHash", "params": [{}], "id": 1}}"#, block_number);
+        handler.handle_request_sync(&request, Connection::default()).unwrap()
+    }
+
+    #[test]
+    fn finalized_blocks_are_answered_from_the_cache() {
+        let cache = ResponseCache::new(16);
+        cache.set_finalized(10);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let handler = handler(cache, Arc::clone(&calls));
+
+        assert_eq!(r#"{"jsonrpc":"2.0","result":1,"id":1}"#, call(&handler, 5));
+        assert_eq!(r#"{"jsonrpc":"2.0","result":1,"id":1}"#, call(&handler, 5));
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn blocks_above_the_finality_point_are_not_cached() {
+        let cache = ResponseCache::new(16);
+        cache.set_finalized(10);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let handler = handler(cache, Arc::clone(&calls));
+
+        call(&handler, 11);
+        call(&handler, 11);
+        assert_eq!(2, calls.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn invalidated_results_are_queried_again() {
+        let cache = ResponseCache::new(16);
+        cache.set_finalized(10);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let handler = handler(cache.clone(), Arc::clone(&calls));
+
+        call(&handler, 4);
+        call(&handler, 8);
+        cache.invalidate_from(6);
+        call(&handler, 4);
+        assert_eq!(r#"{"jsonrpc":"2.0","result":3,"id":1}"#, call(&handler, 8));
+        assert_eq!(3, calls.load(Ordering::SeqCst));
+    }
+}
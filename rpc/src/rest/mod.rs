@@ -0,0 +1,198 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod openapi;
+
+use crate::v1::types::BlockNumberOrTag;
+use crate::v1::{Chain, Mempool};
+use ccore::MaintenanceClient;
+use hyper::server::conn::AddrIncoming;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use jsonrpc_core::futures::Future;
+use jsonrpc_core::{BoxFuture, Error, ErrorCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use tokio::sync::oneshot;
+use tokio::{runtime, task};
+
+const BLOCK_NUMBER_ERROR: &str = "The block must be a number or one of latest, finalized, safe and pending";
+
+pub struct RestServer {
+    shutdown: oneshot::Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl RestServer {
+    /// Stops accepting requests and waits until the server thread ends.
+    pub fn close(self) {
+        // The receiver is gone only if the server already stopped.
+        let _ = self.shutdown.send(());
+        if self.thread.join().is_err() {
+            cerror!(RPC, "The REST server thread panicked");
+        }
+    }
+}
+
+/// Start REST server asynchronously and returns result with `RestServer` handle on success or an error.
+///
+/// The requests are served by the given JSON-RPC handlers, so the responses have the same format as the JSON-RPC.
+/// `/ready` reports whether the node is out of maintenance.
+pub fn start_rest<C, M, N>(addr: &SocketAddr, chain: C, mempool: M, node: Arc<N>) -> Result<RestServer, io::Error>
+where
+    C: Chain,
+    M: Mempool,
+    N: MaintenanceClient + 'static, {
+    let mut runtime = runtime::Builder::new().threaded_scheduler().enable_all().thread_name("rest").build()?;
+    let listener = TcpListener::bind(addr)?;
+    let builder: hyper::server::Builder<AddrIncoming> = runtime
+        .enter(|| Server::from_tcp(listener))
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+
+    let handlers = Arc::new((chain, mempool, node));
+    let make_service = make_service_fn(move |_| {
+        let handlers = Arc::clone(&handlers);
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let handlers = Arc::clone(&handlers);
+                async move { Ok::<_, Infallible>(route(&handlers.0, &handlers.1, &*handlers.2, request).await) }
+            }))
+        }
+    });
+
+    let (shutdown, stopped) = oneshot::channel();
+    let thread = thread::Builder::new().name("rest".to_string()).spawn(move || {
+        let server = builder.serve(make_service).with_graceful_shutdown(async {
+            // The sender is dropped when the handle is dropped without being closed.
+            let _ = stopped.await;
+        });
+        if let Err(err) = runtime.block_on(server) {
+            cerror!(RPC, "The REST server stopped: {}", err);
+        }
+    })?;
+
+    Ok(RestServer {
+        shutdown,
+        thread,
+    })
+}
+
+async fn route<C: Chain, M: Mempool, N: MaintenanceClient>(
+    chain: &C,
+    mempool: &M,
+    node: &N,
+    request: Request<Body>,
+) -> Response<Body> {
+    let method = request.method().clone();
+    let segments: Vec<String> =
+        request.uri().path().split('/').filter(|segment| !segment.is_empty()).map(ToString::to_string).collect();
+    let path: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let query = request.uri().query().unwrap_or("").to_string();
+
+    let result = match (method, path.as_slice()) {
+        (Method::GET, ["openapi.json"]) => Ok(openapi::document()),
+        (Method::GET, ["ready"]) => {
+            let maintenance = node.is_in_maintenance();
+            let status = if maintenance {
+                StatusCode::SERVICE_UNAVAILABLE
+            } else {
+                StatusCode::OK
+            };
+            return respond(status, json!({ "ready": !maintenance, "maintenance": maintenance }))
+        }
+        (Method::GET, ["blocks", number]) => match number.parse() {
+            Ok(number) => to_json(wait(chain.get_block_by_number(number))),
+            Err(_) => Err(Error::invalid_params(BLOCK_NUMBER_ERROR)),
+        },
+        (Method::GET, ["tx", hash]) => parse(hash).and_then(|hash| to_json(chain.get_transaction(hash))),
+        (Method::GET, ["accounts", address]) => block_number(&query).and_then(|block_number| {
+            let address = parse(address)?;
+            let balance = wait(chain.get_balance(address, block_number))?;
+            let seq = wait(chain.get_seq(address, block_number))?;
+            Ok(match (balance, seq) {
+                (Some(balance), Some(seq)) => json!({ "balance": balance, "seq": seq }),
+                _ => Value::Null,
+            })
+        }),
+        (Method::POST, ["tx"]) => match hyper::body::to_bytes(request.into_body()).await {
+            Ok(body) => serde_json::from_slice::<Value>(&body)
+                .ok()
+                .and_then(|body| body.get("transaction").cloned())
+                .ok_or_else(|| Error::invalid_params("The body must be {\"transaction\": <hex encoded transaction>}"))
+                .and_then(from_value)
+                .and_then(|raw| to_json(mempool.send_signed_transaction(raw)))
+                .map(|hash| json!({ "hash": hash })),
+            Err(err) => Err(Error::invalid_params(format!("Cannot read the body: {}", err))),
+        },
+        _ => return respond(StatusCode::NOT_FOUND, json!({ "message": "Not found" })),
+    };
+
+    match result {
+        Ok(Value::Null) => respond(StatusCode::NOT_FOUND, json!({ "message": "Not found" })),
+        Ok(value) => respond(StatusCode::OK, value),
+        Err(err) => {
+            let status = match err.code {
+                ErrorCode::InvalidParams | ErrorCode::InvalidRequest | ErrorCode::ParseError => StatusCode::BAD_REQUEST,
+                ErrorCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+                _ => StatusCode::UNPROCESSABLE_ENTITY,
+            };
+            respond(status, serde_json::to_value(err).expect("Error is always serializable"))
+        }
+    }
+}
+
+fn respond(status: StatusCode, body: Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .expect("The response is always valid")
+}
+
+/// Waits for the method running on the blocking pool without stalling the other requests on this thread.
+fn wait<T>(future: BoxFuture<T>) -> jsonrpc_core::Result<T> {
+    task::block_in_place(|| future.wait())
+}
+
+fn to_json<T: Serialize>(result: jsonrpc_core::Result<T>) -> jsonrpc_core::Result<Value> {
+    result.map(|value| serde_json::to_value(value).expect("RPC results are always serializable"))
+}
+
+/// Parses a path segment the same way as a JSON-RPC string parameter.
+fn parse<T: DeserializeOwned>(segment: &str) -> jsonrpc_core::Result<T> {
+    from_value(Value::String(segment.to_string()))
+}
+
+fn from_value<T: DeserializeOwned>(value: Value) -> jsonrpc_core::Result<T> {
+    serde_json::from_value(value).map_err(|err| Error::invalid_params(err.to_string()))
+}
+
+fn block_number(query: &str) -> jsonrpc_core::Result<Option<BlockNumberOrTag>> {
+    for pair in query.split('&') {
+        let mut pair = pair.splitn(2, '=');
+        if pair.next() == Some("block") {
+            let number = pair.next().unwrap_or("");
+            return number.parse().map(Some).map_err(|_| Error::invalid_params(BLOCK_NUMBER_ERROR))
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,172 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use serde_json::{json, Value};
+
+/// The OpenAPI document describing the REST endpoints.
+pub fn document() -> Value {
+    json!({
+        "openapi": "3.0.0",
+        "info": {
+            "title": "Foundry REST",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": {
+            "/blocks/{number}": {
+                "get": {
+                    "summary": "Gets the block with the given number or tag. Same as chain_getBlockByNumber.",
+                    "parameters": [path_parameter("number", block_number())],
+                    "responses": {
+                        "200": json_response("The block", json!({ "type": "object" })),
+                        "400": error(),
+                        "404": not_found(),
+                    },
+                },
+            },
+            "/tx/{hash}": {
+                "get": {
+                    "summary": "Gets the transaction with the given hash. Same as chain_getTransaction.",
+                    "parameters": [path_parameter("hash", hex_string())],
+                    "responses": {
+                        "200": json_response("The transaction", json!({ "type": "object" })),
+                        "400": error(),
+                        "404": not_found(),
+                    },
+                },
+            },
+            "/accounts/{address}": {
+                "get": {
+                    "summary": "Gets the balance and the seq of the account at the given block or at the latest block.",
+                    "parameters": [
+                        path_parameter("address", json!({ "type": "string" })),
+                        {
+                            "name": "block",
+                            "in": "query",
+                            "required": false,
+                            "schema": block_number(),
+                        },
+                    ],
+                    "responses": {
+                        "200": json_response("The account", json!({
+                            "type": "object",
+                            "properties": {
+                                "balance": hex_string(),
+                                "seq": { "type": "integer" },
+                            },
+                        })),
+                        "400": error(),
+                        "404": not_found(),
+                    },
+                },
+            },
+            "/ready": {
+                "get": {
+                    "summary": "Reports whether the node serves as usual. It's not ready while it's in maintenance.",
+                    "responses": {
+                        "200": json_response("The node is ready", readiness()),
+                        "503": json_response("The node is in maintenance", readiness()),
+                    },
+                },
+            },
+            "/tx": {
+                "post": {
+                    "summary": "Sends the signed transaction. Same as mempool_sendSignedTransaction.",
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "properties": { "transaction": hex_string() },
+                                    "required": ["transaction"],
+                                },
+                            },
+                        },
+                    },
+                    "responses": {
+                        "200": json_response("The hash of the transaction", json!({
+                            "type": "object",
+                            "properties": { "hash": hex_string() },
+                        })),
+                        "400": error(),
+                        "422": error(),
+                    },
+                },
+            },
+        },
+        "components": {
+            "responses": {
+                "Error": json_response("The JSON-RPC error object", json!({
+                    "type": "object",
+                    "properties": {
+                        "code": { "type": "integer" },
+                        "message": { "type": "string" },
+                        "data": {},
+                    },
+                })),
+            },
+        },
+    })
+}
+
+fn readiness() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "ready": { "type": "boolean" },
+            "maintenance": { "type": "boolean" },
+        },
+    })
+}
+
+fn path_parameter(name: &str, schema: Value) -> Value {
+    json!({
+        "name": name,
+        "in": "path",
+        "required": true,
+        "schema": schema,
+    })
+}
+
+fn json_response(description: &str, schema: Value) -> Value {
+    json!({
+        "description": description,
+        "content": {
+            "application/json": { "schema": schema },
+        },
+    })
+}
+
+fn block_number() -> Value {
+    json!({
+        "oneOf": [
+            { "type": "integer" },
+            { "type": "string", "enum": ["latest", "finalized", "safe", "pending"] },
+        ],
+    })
+}
+
+fn hex_string() -> Value {
+    json!({ "type": "string", "description": "0x prefixed hexadecimal string" })
+}
+
+fn error() -> Value {
+    json!({ "$ref": "#/components/responses/Error" })
+}
+
+fn not_found() -> Value {
+    json!({ "description": "The requested item does not exist" })
+}
@@ -15,23 +15,35 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 // TODO: panic handler
+use crate::jwt::JwtSecret;
+use crate::limits::Connection;
+use crate::request_id::{parse_request_id, REQUEST_ID_HEADER};
+use crate::subscription::{WsSession, WsSubscriptions};
 use jsonrpc_core;
-use jsonrpc_http_server::{self, Host, Server as HttpServer, ServerBuilder as HttpServerBuilder};
+use jsonrpc_http_server::hyper::header::{HeaderValue, AUTHORIZATION};
+use jsonrpc_http_server::hyper::{self, StatusCode};
+use jsonrpc_http_server::{
+    self, Host, RequestMiddleware, RequestMiddlewareAction, Response, Server as HttpServer,
+    ServerBuilder as HttpServerBuilder,
+};
 use jsonrpc_ipc_server::{Server as IpcServer, ServerBuilder as IpcServerBuilder};
-use jsonrpc_ws_server::{Error as WsError, Server as WsServer, ServerBuilder as WsServerBuilder};
+use jsonrpc_ws_server::ws::CloseCode;
+use jsonrpc_ws_server::{Error as WsError, RequestContext, Server as WsServer, ServerBuilder as WsServerBuilder};
 use std::default::Default;
 use std::io;
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 /// Start http server asynchronously and returns result with `Server` handle on success or an error.
-pub fn start_http<M: jsonrpc_core::Metadata>(
+///
+/// The id given in the `X-Request-Id` header is attached to the logs of the request.
+pub fn start_http(
     addr: &SocketAddr,
     cors_domains: Option<Vec<String>>,
     allowed_hosts: Option<Vec<String>>,
-    handler: jsonrpc_core::MetaIoHandler<M, impl jsonrpc_core::Middleware<M>>,
-) -> Result<HttpServer, io::Error>
-where
-    M: Default, {
+    max_request_body_size: usize,
+    handler: jsonrpc_core::MetaIoHandler<Connection, impl jsonrpc_core::Middleware<Connection>>,
+) -> Result<HttpServer, io::Error> {
     let cors_domains = cors_domains.map(|domains| {
         domains
             .into_iter()
@@ -44,11 +56,67 @@ where
     });
 
     HttpServerBuilder::new(handler)
+        .meta_extractor(extract_request_id)
         .cors(cors_domains.into())
         .allowed_hosts(allowed_hosts.map(|hosts| hosts.into_iter().map(Host::from).collect()).into())
+        .max_request_body_size(max_request_body_size)
+        .start_http(addr)
+}
+
+/// Start http server which serves only the requests with a JWT signed by the secret, and returns result with `Server`
+/// handle on success or an error.
+///
+/// The token is given in the `Authorization: Bearer <token>` header.
+pub fn start_authenticated_http(
+    addr: &SocketAddr,
+    secret: JwtSecret,
+    handler: jsonrpc_core::MetaIoHandler<Connection, impl jsonrpc_core::Middleware<Connection>>,
+) -> Result<HttpServer, io::Error> {
+    HttpServerBuilder::new(handler)
+        .meta_extractor(extract_request_id)
+        .request_middleware(JwtAuthentication {
+            secret,
+        })
         .start_http(addr)
 }
 
+fn extract_request_id(request: &hyper::Request<hyper::Body>) -> Connection {
+    let request_id =
+        request.headers().get(REQUEST_ID_HEADER).and_then(|value| value.to_str().ok()).and_then(parse_request_id);
+    Connection::with_request_id(request_id)
+}
+
+struct JwtAuthentication {
+    secret: JwtSecret,
+}
+
+impl RequestMiddleware for JwtAuthentication {
+    fn on_request(&self, request: hyper::Request<hyper::Body>) -> RequestMiddlewareAction {
+        let token = request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| value.starts_with("Bearer "))
+            .map(|value| value["Bearer ".len()..].trim());
+        let result = match token {
+            Some(token) => self.secret.verify(token).map_err(|err| err.to_string()),
+            None => Err("The bearer token is required".to_string()),
+        };
+        match result {
+            Ok(()) => RequestMiddlewareAction::Proceed {
+                should_continue_on_invalid_cors: false,
+                request,
+            },
+            Err(message) => Response {
+                code: StatusCode::UNAUTHORIZED,
+                content_type: HeaderValue::from_static("text/plain; charset=utf-8"),
+                content: format!("{}\n", message),
+            }
+            .into(),
+        }
+    }
+}
+
 /// Start ipc server asynchronously and returns result with `Server` handle on success or an error.
 pub fn start_ipc<M: jsonrpc_core::Metadata>(
     addr: &str,
@@ -60,13 +128,36 @@ where
 }
 
 /// Start WS server and return `Server` handle.
-pub fn start_ws<M: jsonrpc_core::Metadata>(
+///
+/// The requests of a session are processed concurrently, so each session has its own counter of the calls in flight.
+/// The sessions subscribe to the blocks through `chain_subscribe`, within the limits of `subscriptions`.
+pub fn start_ws(
     addr: &SocketAddr,
-    handler: jsonrpc_core::MetaIoHandler<M, impl jsonrpc_core::Middleware<M>>,
+    mut handler: jsonrpc_core::MetaIoHandler<Connection, impl jsonrpc_core::Middleware<Connection>>,
     max_connections: usize,
-) -> Result<WsServer, WsError>
-where
-    M: Default, {
-    // FIXME: Add Hosts, Origins and Session States
-    WsServerBuilder::new(handler).max_connections(max_connections).start(addr)
+    max_payload_size: usize,
+    subscriptions: &Arc<WsSubscriptions>,
+) -> Result<WsServer, WsError> {
+    WsSubscriptions::extend_api(subscriptions, &mut handler);
+    // FIXME: Add Hosts and Origins
+    WsServerBuilder::with_meta_extractor(handler, extract_session)
+        .max_connections(max_connections)
+        .max_payload(max_payload_size)
+        .start(addr)
+}
+
+fn extract_session(context: &RequestContext) -> Connection {
+    let executor = context.executor.clone();
+    let out = context.out.clone();
+    let session = WsSession::new(
+        context.session_id,
+        context.sender(),
+        move |future| executor.spawn(future),
+        move || {
+            if let Err(err) = out.close(CloseCode::Policy) {
+                cdebug!(RPC, "Cannot close a WebSocket session: {:?}", err);
+            }
+        },
+    );
+    Connection::with_session(session)
 }
@@ -0,0 +1,328 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The block subscriptions of the WebSocket sessions.
+//!
+//! A client subscribes with `chain_subscribe(["newBlocks"])` and receives the blocks enacted to the best chain as
+//! `chain_subscription` notifications until it calls `chain_unsubscribe([id])`.
+
+use crate::limits::{Connection, SlowSubscriberPolicy, SubscriptionLimits};
+use crate::v1::errors;
+use crate::v1::types::Block;
+use ccore::{BlockChainClient, BlockId, BlocksImported, EngineInfo};
+use crossbeam_channel as crossbeam;
+use ctypes::BlockHash;
+use jsonrpc_core::futures::future::{self, Future};
+use jsonrpc_core::futures::sync::mpsc;
+use jsonrpc_core::futures::{Sink, Stream};
+use jsonrpc_core::{MetaIoHandler, Middleware, Params, Value};
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::{fmt, io};
+
+type Forward = Box<dyn Future<Item = (), Error = ()> + Send>;
+
+/// The WebSocket session of a connection, to which the notifications of its subscriptions are sent.
+#[derive(Clone)]
+pub struct WsSession {
+    id: u64,
+    sink: mpsc::Sender<String>,
+    spawn: Arc<dyn Fn(Forward) + Send + Sync>,
+    close: Arc<dyn Fn() + Send + Sync>,
+}
+
+impl WsSession {
+    /// `spawn` runs a future on the event loop of the session, and `close` closes the connection.
+    pub fn new(
+        id: u64,
+        sink: mpsc::Sender<String>,
+        spawn: impl Fn(Forward) + Send + Sync + 'static,
+        close: impl Fn() + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            id,
+            sink,
+            spawn: Arc::new(spawn),
+            close: Arc::new(close),
+        }
+    }
+}
+
+impl fmt::Debug for WsSession {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "WsSession({})", self.id)
+    }
+}
+
+struct Subscriber {
+    id: String,
+    session: u64,
+    sender: mpsc::Sender<String>,
+    close: Arc<dyn Fn() + Send + Sync>,
+}
+
+/// The subscriptions of all the sessions of the WebSocket servers.
+pub struct WsSubscriptions {
+    limits: SubscriptionLimits,
+    subscribers: Mutex<Vec<Subscriber>>,
+    next_id: AtomicUsize,
+}
+
+impl WsSubscriptions {
+    pub fn new(limits: SubscriptionLimits) -> Self {
+        Self {
+            limits,
+            subscribers: Default::default(),
+            next_id: AtomicUsize::new(1),
+        }
+    }
+
+    /// Adds `chain_subscribe` and `chain_unsubscribe` to the handler of a WebSocket server.
+    pub fn extend_api(this: &Arc<Self>, handler: &mut MetaIoHandler<Connection, impl Middleware<Connection>>) {
+        let subscriptions = Arc::clone(this);
+        handler.add_method_with_meta("chain_subscribe", move |params: Params, connection: Connection| {
+            let result = params.parse::<(String,)>().and_then(|(kind,)| {
+                if kind != "newBlocks" {
+                    return Err(errors::unknown_subscription(&kind))
+                }
+                let session = connection.session().expect("The WebSocket connections have a session");
+                subscriptions.subscribe(session).map(Value::String)
+            });
+            future::result(result)
+        });
+        let subscriptions = Arc::clone(this);
+        handler.add_method_with_meta("chain_unsubscribe", move |params: Params, connection: Connection| {
+            let result = params.parse::<(String,)>().map(|(id,)| {
+                let session = connection.session().expect("The WebSocket connections have a session");
+                Value::Bool(subscriptions.unsubscribe(session, &id))
+            });
+            future::result(result)
+        });
+    }
+
+    fn subscribe(&self, session: &WsSession) -> Result<String, jsonrpc_core::Error> {
+        let mut subscribers = self.subscribers.lock();
+        // The subscriptions of a closed session are removed by the next block.
+        let subscriptions = subscribers.iter().filter(|subscriber| subscriber.session == session.id).count();
+        if subscriptions >= self.limits.max_subscriptions_per_connection {
+            return Err(errors::too_many_subscriptions(self.limits.max_subscriptions_per_connection))
+        }
+        let id = format!("0x{:x}", self.next_id.fetch_add(1, Ordering::SeqCst));
+        // The channel holds one more message for its sender.
+        let (sender, receiver) = mpsc::channel(self.limits.buffer - 1);
+        let sink = session.sink.clone().sink_map_err(|_| ());
+        (session.spawn)(Box::new(receiver.forward(sink).map(|_| ())));
+        subscribers.push(Subscriber {
+            id: id.clone(),
+            session: session.id,
+            sender,
+            close: Arc::clone(&session.close),
+        });
+        Ok(id)
+    }
+
+    fn unsubscribe(&self, session: &WsSession, id: &str) -> bool {
+        let mut subscribers = self.subscribers.lock();
+        let before = subscribers.len();
+        subscribers.retain(|subscriber| subscriber.session != session.id || subscriber.id != id);
+        subscribers.len() != before
+    }
+
+    /// Notifies the blocks of the events until `stop` is closed or the events end.
+    pub fn run<C>(&self, client: &C, events: crossbeam::Receiver<BlocksImported>, stop: crossbeam::Receiver<()>)
+    where
+        C: BlockChainClient + EngineInfo, {
+        loop {
+            crossbeam::select! {
+                recv(events) -> event => match event {
+                    Ok(event) => self.forward(client, event.enacted),
+                    Err(crossbeam::RecvError) => return,
+                },
+                recv(stop) -> _ => return,
+            }
+        }
+    }
+
+    fn forward<C>(&self, client: &C, enacted: Vec<BlockHash>)
+    where
+        C: BlockChainClient + EngineInfo, {
+        if self.subscribers.lock().is_empty() {
+            return
+        }
+        let network_id = client.network_id();
+        for hash in enacted {
+            let block = match client.block(&BlockId::Hash(hash)) {
+                Some(block) => Block::from_core(block.decode(), network_id),
+                None => continue,
+            };
+            self.send(&serde_json::to_value(block).expect("A block is serializable"));
+        }
+    }
+
+    /// Sends the block to the subscribers and removes the ones which are gone.
+    /// A subscriber that can't keep up never blocks the import.
+    fn send(&self, block: &Value) {
+        let slow_subscriber = self.limits.slow_subscriber;
+        let mut subscribers = self.subscribers.lock();
+        *subscribers = subscribers
+            .drain(..)
+            .filter_map(|mut subscriber| {
+                let notification = serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "method": "chain_subscription",
+                    "params": {
+                        "subscription": subscriber.id,
+                        "result": block,
+                    },
+                });
+                match subscriber.sender.try_send(notification.to_string()) {
+                    Ok(()) => Some(subscriber),
+                    Err(ref err) if err.is_disconnected() => None,
+                    Err(_) => match slow_subscriber {
+                        SlowSubscriberPolicy::Drop => {
+                            cdebug!(RPC, "The subscription {} lost a block", subscriber.id);
+                            Some(subscriber)
+                        }
+                        SlowSubscriberPolicy::Disconnect => {
+                            cinfo!(RPC, "Disconnected the session of the subscription {}: too slow", subscriber.id);
+                            (subscriber.close)();
+                            None
+                        }
+                    },
+                }
+            })
+            .collect();
+    }
+}
+
+/// The thread notifying the blocks to the subscriptions of the WebSocket sessions.
+pub struct WsNotify {
+    stop: crossbeam::Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl WsNotify {
+    /// Waits until the thread ends.
+    pub fn close(self) {
+        drop(self.stop);
+        if self.thread.join().is_err() {
+            cerror!(RPC, "The WebSocket notify thread panicked");
+        }
+    }
+}
+
+/// Starts the thread notifying the blocks of `events` to the subscriptions.
+pub fn start_ws_notify<C>(
+    client: Arc<C>,
+    events: crossbeam::Receiver<BlocksImported>,
+    subscriptions: Arc<WsSubscriptions>,
+) -> Result<WsNotify, io::Error>
+where
+    C: BlockChainClient + EngineInfo + 'static, {
+    let (stop, stopped) = crossbeam::bounded(0);
+    let thread = thread::Builder::new()
+        .name("ws notify".to_string())
+        .spawn(move || subscriptions.run(&*client, events, stopped))?;
+    Ok(WsNotify {
+        stop,
+        thread,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+
+    fn limits(buffer: usize, slow_subscriber: SlowSubscriberPolicy) -> SubscriptionLimits {
+        SubscriptionLimits {
+            max_subscriptions_per_connection: 2,
+            buffer,
+            slow_subscriber,
+        }
+    }
+
+    // The forwarders are kept but never run, so the notifications stay in the buffers.
+    fn stalled_session(id: u64, closed: Arc<AtomicBool>) -> (WsSession, Arc<Mutex<Vec<Forward>>>) {
+        let (sink, _) = mpsc::channel(0);
+        let forwards: Arc<Mutex<Vec<Forward>>> = Default::default();
+        let kept = Arc::clone(&forwards);
+        let session = WsSession::new(
+            id,
+            sink,
+            move |forward| kept.lock().push(forward),
+            move || closed.store(true, Ordering::SeqCst),
+        );
+        (session, forwards)
+    }
+
+    #[test]
+    fn subscriptions_of_a_session_are_limited() {
+        let subscriptions = WsSubscriptions::new(limits(1, SlowSubscriberPolicy::Drop));
+        let (session, _forwards) = stalled_session(1, Default::default());
+        let (other, _other_forwards) = stalled_session(2, Default::default());
+
+        let first = subscriptions.subscribe(&session).unwrap();
+        subscriptions.subscribe(&session).unwrap();
+        assert!(subscriptions.subscribe(&session).is_err());
+        assert!(subscriptions.subscribe(&other).is_ok());
+
+        assert!(!subscriptions.unsubscribe(&other, &first));
+        assert!(subscriptions.unsubscribe(&session, &first));
+        assert!(subscriptions.subscribe(&session).is_ok());
+    }
+
+    #[test]
+    fn slow_subscriber_loses_blocks() {
+        let subscriptions = WsSubscriptions::new(limits(1, SlowSubscriberPolicy::Drop));
+        let closed = Arc::new(AtomicBool::new(false));
+        let (session, _forwards) = stalled_session(1, Arc::clone(&closed));
+        subscriptions.subscribe(&session).unwrap();
+
+        subscriptions.send(&Value::from(1));
+        subscriptions.send(&Value::from(2));
+        assert_eq!(1, subscriptions.subscribers.lock().len());
+        assert!(!closed.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn slow_subscriber_is_disconnected() {
+        let subscriptions = WsSubscriptions::new(limits(2, SlowSubscriberPolicy::Disconnect));
+        let closed = Arc::new(AtomicBool::new(false));
+        let (session, _forwards) = stalled_session(1, Arc::clone(&closed));
+        subscriptions.subscribe(&session).unwrap();
+
+        subscriptions.send(&Value::from(1));
+        subscriptions.send(&Value::from(2));
+        assert_eq!(1, subscriptions.subscribers.lock().len());
+        subscriptions.send(&Value::from(3));
+        assert!(subscriptions.subscribers.lock().is_empty());
+        assert!(closed.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn subscriber_of_a_closed_session_is_removed() {
+        let subscriptions = WsSubscriptions::new(limits(1, SlowSubscriberPolicy::Drop));
+        let (session, forwards) = stalled_session(1, Default::default());
+        subscriptions.subscribe(&session).unwrap();
+        forwards.lock().clear();
+
+        subscriptions.send(&Value::from(1));
+        assert!(subscriptions.subscribers.lock().is_empty());
+    }
+}
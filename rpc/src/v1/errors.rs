@@ -47,6 +47,7 @@ mod codes {
     pub const CORE_ERROR: i64 = -32010;
     pub const NETWORK_DISABLED: i64 = -32014;
     pub const NETWORK_CANNOT_DISCONNECT_NOT_CONNECTED_ERROR: i64 = -32015;
+    pub const NETWORK_CHAOS_NOT_ENABLED: i64 = -32017;
     pub const ACCOUNT_PROVIDER_ERROR: i64 = -32016;
     pub const VERIFICATION_FAILED: i64 = -32030;
     pub const ALREADY_IMPORTED: i64 = -32031;
@@ -64,6 +65,14 @@ mod codes {
     pub const SHARD_TRANSACTION_ONLY_IN_EXECUTE_TRANSACITON: i64 = -32047;
     pub const STATE_NOT_EXIST: i64 = -32048;
     pub const ACTION_DATA_HANDLER_NOT_FOUND: i64 = -32049;
+    pub const CLIENT_CERTIFICATE_REQUIRED: i64 = -32050;
+    pub const ADMIN_CHANNEL_REQUIRED: i64 = -32051;
+    pub const TOO_MANY_REQUESTS: i64 = -32052;
+    pub const LOW_DISK_SPACE: i64 = -32053;
+    pub const MAINTENANCE: i64 = -32054;
+    pub const NAMESPACE_DISABLED: i64 = -32055;
+    pub const REJECTED: i64 = -32056;
+    pub const TOO_MANY_SUBSCRIPTIONS: i64 = -32057;
     pub const UNKNOWN_ERROR: i64 = -32099;
 }
 
@@ -114,6 +123,21 @@ pub fn transaction_core<T: Into<CoreError>>(error: T) -> Error {
             message: "Invalid NetworkId".into(),
             data: Some(Value::String(format!("{:?}", error))),
         },
+        CoreError::LowDiskSpace(level) => Error {
+            code: ErrorCode::ServerError(codes::LOW_DISK_SPACE),
+            message: "Low Disk Space".into(),
+            data: Some(Value::String(format!("The disk space of the node is {}", level))),
+        },
+        CoreError::Maintenance => Error {
+            code: ErrorCode::ServerError(codes::MAINTENANCE),
+            message: "Maintenance".into(),
+            data: Some(Value::String("The node is in maintenance".to_string())),
+        },
+        CoreError::Rejected(reason) => Error {
+            code: ErrorCode::ServerError(codes::REJECTED),
+            message: "Rejected".into(),
+            data: Some(Value::String(reason)),
+        },
         CoreError::History(error @ HistoryError::TransactionAlreadyImported) => Error {
             code: ErrorCode::ServerError(codes::ALREADY_IMPORTED),
             message: "Already Imported".into(),
@@ -218,6 +242,11 @@ pub fn network_control(error: &NetworkControlError) -> Error {
             message: "Network is diabled.".into(),
             data: None,
         },
+        NetworkControlError::ChaosNotEnabled => Error {
+            code: ErrorCode::ServerError(codes::NETWORK_CHAOS_NOT_ENABLED),
+            message: "The node is built without the chaos feature.".into(),
+            data: None,
+        },
     }
 }
 
@@ -245,6 +274,70 @@ pub fn invalid_custom_action(err: String) -> Error {
     }
 }
 
+pub fn client_certificate_required(method: &str) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::CLIENT_CERTIFICATE_REQUIRED),
+        message: format!("{} requires a client certificate", method),
+        data: None,
+    }
+}
+
+pub fn admin_channel_required(method: &str) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::ADMIN_CHANNEL_REQUIRED),
+        message: format!("{} is served only through the admin channel", method),
+        data: None,
+    }
+}
+
+pub fn namespace_disabled(method: &str) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::NAMESPACE_DISABLED),
+        message: format!("The namespace of {} is disabled", method),
+        data: None,
+    }
+}
+
+pub fn unknown_namespace(namespace: &str) -> Error {
+    Error {
+        code: ErrorCode::InvalidParams,
+        message: format!("Unknown namespace: {}", namespace),
+        data: None,
+    }
+}
+
+pub fn unknown_subscription(kind: &str) -> Error {
+    Error {
+        code: ErrorCode::InvalidParams,
+        message: format!("Unknown subscription: {}", kind),
+        data: None,
+    }
+}
+
+pub fn too_many_subscriptions(max_subscriptions: usize) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::TOO_MANY_SUBSCRIPTIONS),
+        message: format!("Too many subscriptions (the limit is {})", max_subscriptions),
+        data: None,
+    }
+}
+
+pub fn too_many_requests(max_in_flight_requests: usize) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::TOO_MANY_REQUESTS),
+        message: format!("Too many calls in flight (the limit is {})", max_in_flight_requests),
+        data: None,
+    }
+}
+
+pub fn transactions_not_mined(count: usize) -> Error {
+    Error {
+        code: ErrorCode::InternalError,
+        message: format!("{} transactions are not mined before the timeout", count),
+        data: None,
+    }
+}
+
 pub fn io(error: std::io::Error) -> Error {
     Error {
         code: ErrorCode::InternalError,
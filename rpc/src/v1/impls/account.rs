@@ -18,7 +18,7 @@ use super::super::errors::{self, account_provider};
 use super::super::traits::Account;
 use super::super::types::{SendTransactionResult, UnsignedTransaction};
 use ccore::{AccountData, AccountProvider, EngineInfo, MinerService, MiningBlockChainClient, TermInfo};
-use ckey::{Password, PlatformAddress, Signature};
+use ckey::{Password, PlatformAddress, Signature, TypedData};
 use ctypes::transaction::IncompleteTransaction;
 use jsonrpc_core::Result;
 use parking_lot::Mutex;
@@ -84,6 +84,16 @@ where
             .map_err(account_provider)
     }
 
+    fn sign_typed_data(
+        &self,
+        typed_data: TypedData,
+        address: PlatformAddress,
+        passphrase: Option<Password>,
+    ) -> Result<Signature> {
+        let message_digest = typed_data.hash().map_err(errors::core)?;
+        self.sign(message_digest, address, passphrase)
+    }
+
     fn send_transaction(
         &self,
         tx: UnsignedTransaction,
@@ -16,36 +16,64 @@
 
 use super::super::errors;
 use super::super::traits::Chain;
-use super::super::types::{Block, BlockNumberAndHash, Transaction, UnsignedTransaction};
-use ccore::{AccountData, BlockId, EngineInfo, ExecuteClient, MiningBlockChainClient, Shard, TermInfo};
+use super::super::types::{
+    AccountInfo, Block, BlockExecutionSummary, BlockNumberAndHash, BlockNumberOrTag, SeqInfo, Transaction,
+    TransactionExecution, UnsignedTransaction,
+};
+use crate::BlockingPool;
+use ccore::{
+    AccountData, BlockId, EngineInfo, ExecuteClient, MiningBlockChainClient, ReplayClient, Shard, StateInfo, TermInfo,
+    UnverifiedTransaction,
+};
+use cjson::bytes::Bytes;
 use cjson::scheme::Params;
 use cjson::uint::Uint;
-use ckey::{public_to_address, NetworkId, PlatformAddress, Public};
-use cstate::FindActionHandler;
-use ctypes::transaction::Action;
+use ckey::{public_to_address, NetworkId, PlatformAddress, Public, Signature};
+use cstate::{name_registry, FindActionHandler, TopStateView};
+use ctypes::transaction::{Action, IncompleteTransaction};
 use ctypes::{BlockHash, BlockNumber, ShardId, Tracker, TxHash};
-use jsonrpc_core::Result;
+use jsonrpc_core::{BoxFuture, Error, Result};
 use primitives::H256;
+use rlp::Rlp;
 use std::convert::TryFrom;
 use std::sync::Arc;
 
+/// The number of the accounts that `chain_getAccountsInfo` reads at once.
+const MAX_ACCOUNTS_PER_QUERY: usize = 256;
+
 pub struct ChainClient<C>
 where
     C: MiningBlockChainClient + Shard + ExecuteClient + EngineInfo, {
     client: Arc<C>,
+    pool: BlockingPool,
 }
 
 impl<C> ChainClient<C>
 where
     C: MiningBlockChainClient + Shard + AccountData + ExecuteClient + EngineInfo,
 {
-    pub fn new(client: Arc<C>) -> Self {
+    pub fn new(client: Arc<C>, pool: BlockingPool) -> Self {
         ChainClient {
             client,
+            pool,
         }
     }
 }
 
+impl<C> ChainClient<C>
+where
+    C: MiningBlockChainClient + Shard + AccountData + ExecuteClient + EngineInfo + 'static,
+{
+    /// Runs `f`, which reads the blocks or the states, on the blocking pool.
+    fn blocking<T, F>(&self, f: F) -> BoxFuture<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&C) -> Result<T> + Send + 'static, {
+        let client = Arc::clone(&self.client);
+        self.pool.run(move || f(&client))
+    }
+}
+
 impl<C> Chain for ChainClient<C>
 where
     C: MiningBlockChainClient
@@ -55,6 +83,8 @@ where
         + EngineInfo
         + FindActionHandler
         + TermInfo
+        + StateInfo
+        + ReplayClient
         + 'static,
 {
     fn get_transaction(&self, transaction_hash: TxHash) -> Result<Option<Transaction>> {
@@ -70,6 +100,31 @@ where
         }))
     }
 
+    fn get_transaction_fee_payer(&self, transaction_hash: TxHash) -> Result<Option<PlatformAddress>> {
+        let id = transaction_hash.into();
+        let mut tx = match self.client.transaction(&id) {
+            Some(tx) => tx,
+            None => return Ok(None),
+        };
+        let fee_payer = match tx.recover_sponsor_public().map_err(errors::transaction_core)? {
+            Some(sponsor_public) => public_to_address(&sponsor_public),
+            None => public_to_address(&tx.signer()),
+        };
+        Ok(Some(PlatformAddress::new_v1(tx.network_id, fee_payer)))
+    }
+
+    fn get_fee_payer_message(&self, signed_transaction: Bytes) -> Result<H256> {
+        let tx: UnverifiedTransaction = Rlp::new(&signed_transaction).as_val().map_err(|e| errors::rlp(&e))?;
+        Ok(tx.sponsor_message())
+    }
+
+    fn attach_fee_payer(&self, signed_transaction: Bytes, fee_payer_signature: Signature) -> Result<Bytes> {
+        let tx: UnverifiedTransaction = Rlp::new(&signed_transaction).as_val().map_err(|e| errors::rlp(&e))?;
+        let tx = tx.with_sponsor(fee_payer_signature);
+        tx.recover_sponsor_public().map_err(errors::transaction_core)?;
+        Ok(rlp::encode(&tx).to_vec().into())
+    }
+
     fn contains_transaction(&self, transaction_hash: TxHash) -> Result<bool> {
         Ok(self.client.transaction_block(&transaction_hash.into()).is_some())
     }
@@ -82,65 +137,192 @@ where
         Ok(self.client.transaction_by_tracker(&tracker).map(From::from))
     }
 
-    fn get_seq(&self, address: PlatformAddress, block_number: Option<u64>) -> Result<Option<u64>> {
-        let block_id = block_number.map(BlockId::Number).unwrap_or(BlockId::Latest);
-        let address = address.try_address().map_err(errors::core)?;
-        Ok(self.client.seq(address, block_id))
+    fn get_seq(&self, address: PlatformAddress, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<u64>> {
+        self.blocking(move |client| {
+            let address = address.try_address().map_err(errors::core)?;
+            let state = block_number.unwrap_or_default().state(client);
+            Ok(state.and_then(|state| state.seq(address).ok()))
+        })
+    }
+
+    fn get_seq_info(&self, address: PlatformAddress) -> BoxFuture<SeqInfo> {
+        self.blocking(move |client| {
+            let address = address.try_address().map_err(errors::core)?;
+            let seq = client.seq(address, BlockId::Latest).ok_or_else(errors::state_not_exist)?;
+            Ok(SeqInfo::new(seq, &client.pooled_transactions_of(address)))
+        })
     }
 
-    fn get_balance(&self, aaddress: PlatformAddress, block_number: Option<u64>) -> Result<Option<Uint>> {
-        let block_id = block_number.map(BlockId::Number).unwrap_or(BlockId::Latest);
-        let address = aaddress.try_address().map_err(errors::core)?;
-        Ok(self.client.balance(address, block_id.into()).map(Into::into))
+    fn get_balance(
+        &self,
+        aaddress: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<Uint>> {
+        self.blocking(move |client| {
+            let address = aaddress.try_address().map_err(errors::core)?;
+            let state = block_number.unwrap_or_default().state_or_block(client);
+            Ok(state.and_then(|state| client.balance(address, state)).map(Into::into))
+        })
     }
 
-    fn get_regular_key(&self, address: PlatformAddress, block_number: Option<u64>) -> Result<Option<Public>> {
-        let block_id = block_number.map(BlockId::Number).unwrap_or(BlockId::Latest);
-        let address = address.try_address().map_err(errors::core)?;
-        Ok(self.client.regular_key(address, block_id.into()))
+    fn get_locked_balance(
+        &self,
+        address: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<Uint>> {
+        self.blocking(move |client| {
+            let address = address.try_address().map_err(errors::core)?;
+            let block = block_number.unwrap_or_default();
+            let state = block.state(client);
+            Ok(state.and_then(|state| state.locked_balance(address, block.number(client)).ok()).map(Into::into))
+        })
     }
 
-    fn get_regular_key_owner(&self, public: Public, block_number: Option<u64>) -> Result<Option<PlatformAddress>> {
-        let block_id = block_number.map(BlockId::Number).unwrap_or(BlockId::Latest);
-        Ok(self.client.regular_key_owner(&public_to_address(&public), block_id.into()).and_then(|address| {
-            let network_id = self.client.network_id();
-            Some(PlatformAddress::new_v1(network_id, address))
-        }))
+    fn get_accounts_info(
+        &self,
+        addresses: Vec<PlatformAddress>,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<Vec<AccountInfo>>> {
+        self.blocking(move |client| {
+            if addresses.len() > MAX_ACCOUNTS_PER_QUERY {
+                return Err(Error::invalid_params(format!(
+                    "Cannot read more than {} accounts at once",
+                    MAX_ACCOUNTS_PER_QUERY
+                )))
+            }
+            let block = block_number.unwrap_or_default();
+            let state = match block.state(client) {
+                Some(state) => state,
+                None => return Ok(None),
+            };
+            let block_number = block.number(client);
+            let mut accounts = Vec::with_capacity(addresses.len());
+            for platform_address in addresses {
+                let address = platform_address.try_address().map_err(errors::core)?;
+                accounts.push(AccountInfo {
+                    address: platform_address,
+                    balance: state.balance(address).map_err(errors::transaction_core)?.into(),
+                    locked_balance: state
+                        .locked_balance(address, block_number)
+                        .map_err(errors::transaction_core)?
+                        .into(),
+                    seq: state.seq(address).map_err(errors::transaction_core)?,
+                    regular_key: state.regular_key(address).map_err(errors::transaction_core)?,
+                });
+            }
+            Ok(Some(accounts))
+        })
+    }
+
+    fn resolve_name(&self, name: String, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<PlatformAddress>> {
+        self.blocking(move |client| {
+            let state = match block_number.unwrap_or_default().state(client) {
+                Some(state) => state,
+                None => return Ok(None),
+            };
+            let owner = name_registry::resolve_name(&state, &name).map_err(errors::transaction_core)?;
+            Ok(owner.map(|owner| PlatformAddress::new_v1(client.network_id(), owner)))
+        })
+    }
+
+    fn get_name_of(
+        &self,
+        address: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<String>> {
+        self.blocking(move |client| {
+            let address = address.try_address().map_err(errors::core)?;
+            let state = match block_number.unwrap_or_default().state(client) {
+                Some(state) => state,
+                None => return Ok(None),
+            };
+            name_registry::reverse_name(&state, address).map_err(errors::transaction_core)
+        })
+    }
+
+    fn get_regular_key(
+        &self,
+        address: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<Public>> {
+        self.blocking(move |client| {
+            let address = address.try_address().map_err(errors::core)?;
+            let state = block_number.unwrap_or_default().state_or_block(client);
+            Ok(state.and_then(|state| client.regular_key(address, state)))
+        })
+    }
+
+    fn get_regular_key_owner(
+        &self,
+        public: Public,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<PlatformAddress>> {
+        self.blocking(move |client| {
+            let state = block_number.unwrap_or_default().state_or_block(client);
+            Ok(state.and_then(|state| client.regular_key_owner(&public_to_address(&public), state)).and_then(
+                |address| {
+                    let network_id = client.network_id();
+                    Some(PlatformAddress::new_v1(network_id, address))
+                },
+            ))
+        })
     }
 
     fn get_genesis_accounts(&self) -> Result<Vec<PlatformAddress>> {
         Ok(self.client.genesis_accounts())
     }
 
-    fn get_number_of_shards(&self, block_number: Option<u64>) -> Result<Option<ShardId>> {
-        let block_id = block_number.map(BlockId::Number).unwrap_or(BlockId::Latest);
-        Ok(self.client.number_of_shards(block_id.into()))
+    fn get_number_of_shards(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<ShardId>> {
+        self.blocking(move |client| {
+            let state = block_number.unwrap_or_default().state_or_block(client);
+            Ok(state.and_then(|state| client.number_of_shards(state)))
+        })
     }
 
-    fn get_shard_id_by_hash(&self, create_shard_tx_hash: TxHash, block_number: Option<u64>) -> Result<Option<ShardId>> {
-        let block_id = block_number.map(BlockId::Number).unwrap_or(BlockId::Latest);
-        Ok(self.client.shard_id_by_hash(&create_shard_tx_hash, block_id.into()))
+    fn get_shard_id_by_hash(
+        &self,
+        create_shard_tx_hash: TxHash,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<ShardId>> {
+        self.blocking(move |client| {
+            let state = block_number.unwrap_or_default().state_or_block(client);
+            Ok(state.and_then(|state| client.shard_id_by_hash(&create_shard_tx_hash, state)))
+        })
     }
 
-    fn get_shard_root(&self, shard_id: ShardId, block_number: Option<u64>) -> Result<Option<H256>> {
-        let block_id = block_number.map(BlockId::Number).unwrap_or(BlockId::Latest);
-        Ok(self.client.shard_root(shard_id, block_id.into()))
+    fn get_shard_root(&self, shard_id: ShardId, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<H256>> {
+        self.blocking(move |client| {
+            let state = block_number.unwrap_or_default().state_or_block(client);
+            Ok(state.and_then(|state| client.shard_root(shard_id, state)))
+        })
     }
 
-    fn get_shard_owners(&self, shard_id: ShardId, block_number: Option<u64>) -> Result<Option<Vec<PlatformAddress>>> {
-        let block_id = block_number.map(BlockId::Number).unwrap_or(BlockId::Latest);
-        Ok(self.client.shard_owners(shard_id, block_id.into()).map(|owners| {
-            let network_id = self.client.network_id();
-            owners.into_iter().map(|owner| PlatformAddress::new_v1(network_id, owner)).collect()
-        }))
+    fn get_shard_owners(
+        &self,
+        shard_id: ShardId,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<Vec<PlatformAddress>>> {
+        self.blocking(move |client| {
+            let state = block_number.unwrap_or_default().state_or_block(client);
+            Ok(state.and_then(|state| client.shard_owners(shard_id, state)).map(|owners| {
+                let network_id = client.network_id();
+                owners.into_iter().map(|owner| PlatformAddress::new_v1(network_id, owner)).collect()
+            }))
+        })
     }
 
-    fn get_shard_users(&self, shard_id: ShardId, block_number: Option<u64>) -> Result<Option<Vec<PlatformAddress>>> {
-        let block_id = block_number.map(BlockId::Number).unwrap_or(BlockId::Latest);
-        Ok(self.client.shard_users(shard_id, block_id.into()).map(|users| {
-            let network_id = self.client.network_id();
-            users.into_iter().map(|user| PlatformAddress::new_v1(network_id, user)).collect()
-        }))
+    fn get_shard_users(
+        &self,
+        shard_id: ShardId,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<Vec<PlatformAddress>>> {
+        self.blocking(move |client| {
+            let state = block_number.unwrap_or_default().state_or_block(client);
+            Ok(state.and_then(|state| client.shard_users(shard_id, state)).map(|users| {
+                let network_id = client.network_id();
+                users.into_iter().map(|user| PlatformAddress::new_v1(network_id, user)).collect()
+            }))
+        })
     }
 
     fn get_best_block_number(&self) -> Result<BlockNumber> {
@@ -155,101 +337,179 @@ where
         })
     }
 
-    fn get_block_hash(&self, block_number: u64) -> Result<Option<BlockHash>> {
-        Ok(self.client.block_hash(&BlockId::Number(block_number)))
+    fn get_block_hash(&self, block_number: BlockNumberOrTag) -> Result<Option<BlockHash>> {
+        Ok(block_number.block_id(&*self.client).and_then(|id| self.client.block_hash(&id)))
     }
 
-    fn get_block_by_number(&self, block_number: u64) -> Result<Option<Block>> {
-        let id = BlockId::Number(block_number);
-        Ok(self.client.block(&id).map(|block| Block::from_core(block.decode(), self.client.network_id())))
+    fn get_block_by_number(&self, block_number: BlockNumberOrTag) -> BoxFuture<Option<Block>> {
+        self.blocking(move |client| {
+            let id = match block_number.block_id(client) {
+                Some(id) => id,
+                None => return Ok(None),
+            };
+            Ok(client.block(&id).map(|block| Block::from_core(block.decode(), client.network_id())))
+        })
     }
 
-    fn get_block_by_hash(&self, block_hash: BlockHash) -> Result<Option<Block>> {
-        let id = BlockId::Hash(block_hash);
-        Ok(self.client.block(&id).map(|block| {
-            let block = block.decode();
-            Block::from_core(block, self.client.network_id())
-        }))
+    fn get_block_by_hash(&self, block_hash: BlockHash) -> BoxFuture<Option<Block>> {
+        self.blocking(move |client| {
+            let id = BlockId::Hash(block_hash);
+            Ok(client.block(&id).map(|block| {
+                let block = block.decode();
+                Block::from_core(block, client.network_id())
+            }))
+        })
+    }
+
+    fn get_block_execution_summary(&self, block_number: BlockNumberOrTag) -> BoxFuture<Option<BlockExecutionSummary>> {
+        self.blocking(move |client| {
+            let id = match block_number.block_id(client) {
+                Some(id) => id,
+                None => return Ok(None),
+            };
+            let summary = client.block_execution_summary(id).map_err(errors::transaction_core)?;
+            Ok(summary.map(|summary| BlockExecutionSummary::from_core(&summary, client.network_id())))
+        })
     }
 
     fn get_block_transaction_count_by_hash(&self, block_hash: BlockHash) -> Result<Option<usize>> {
         Ok(self.client.block(&BlockId::Hash(block_hash)).map(|block| block.transactions_count()))
     }
 
-    fn get_min_transaction_fee(&self, action_type: String, block_number: Option<u64>) -> Result<Option<u64>> {
-        if block_number == Some(0) {
-            return Ok(None)
-        }
-        // Unlike other RPCs, use the latest parameters if the block number is `null`.
-        let block_id = block_number.map(|n| (n - 1).into()).unwrap_or(BlockId::Latest);
-        if let Some(common_parameters) = self.client.common_params(block_id) {
-            Ok(match action_type.as_str() {
-                "mintAsset" => Some(common_parameters.min_asset_mint_cost()),
-                "transferAsset" => Some(common_parameters.min_asset_transfer_cost()),
-                "changeAssetScheme" => Some(common_parameters.min_asset_scheme_change_cost()),
-                "increaseAssetSupply" => Some(common_parameters.min_asset_supply_increase_cost()),
-                "unwrapCCC" => Some(common_parameters.min_asset_unwrap_ccc_cost()),
-                "pay" => Some(common_parameters.min_pay_transaction_cost()),
-                "setRegularKey" => Some(common_parameters.min_set_regular_key_transaction_cost()),
-                "createShard" => Some(common_parameters.min_create_shard_transaction_cost()),
-                "setShardOwners" => Some(common_parameters.min_set_shard_owners_transaction_cost()),
-                "setShardUsers" => Some(common_parameters.min_set_shard_users_transaction_cost()),
-                "wrapCCC" => Some(common_parameters.min_wrap_ccc_transaction_cost()),
-                "custom" => Some(common_parameters.min_custom_transaction_cost()),
-
-                _ => None,
-            })
-        } else {
-            Ok(None)
-        }
+    fn get_min_transaction_fee(
+        &self,
+        action_type: String,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<u64>> {
+        self.blocking(move |client| {
+            // Unlike other RPCs, use the latest parameters if the block number is `null`.
+            let block_id = match block_number.map(|block_number| block_number.number(client)) {
+                Some(0) => return Ok(None),
+                Some(n) => (n - 1).into(),
+                None => BlockId::Latest,
+            };
+            if let Some(common_parameters) = client.common_params(block_id) {
+                Ok(match action_type.as_str() {
+                    "mintAsset" => Some(common_parameters.min_asset_mint_cost()),
+                    "transferAsset" => Some(common_parameters.min_asset_transfer_cost()),
+                    "changeAssetScheme" => Some(common_parameters.min_asset_scheme_change_cost()),
+                    "increaseAssetSupply" => Some(common_parameters.min_asset_supply_increase_cost()),
+                    "unwrapCCC" => Some(common_parameters.min_asset_unwrap_ccc_cost()),
+                    "pay" => Some(common_parameters.min_pay_transaction_cost()),
+                    "setRegularKey" => Some(common_parameters.min_set_regular_key_transaction_cost()),
+                    "createShard" => Some(common_parameters.min_create_shard_transaction_cost()),
+                    "setShardOwners" | "terminateShard" => {
+                        Some(common_parameters.min_set_shard_owners_transaction_cost())
+                    }
+                    "setShardUsers" => Some(common_parameters.min_set_shard_users_transaction_cost()),
+                    "wrapCCC" => Some(common_parameters.min_wrap_ccc_transaction_cost()),
+                    "custom" => Some(common_parameters.min_custom_transaction_cost()),
+
+                    _ => None,
+                })
+            } else {
+                Ok(None)
+            }
+        })
     }
 
-    fn get_mining_reward(&self, block_number: u64) -> Result<Option<u64>> {
-        Ok(self.client.mining_reward(block_number))
+    fn get_mining_reward(&self, block_number: BlockNumberOrTag) -> Result<Option<u64>> {
+        Ok(self.client.mining_reward(block_number.number(&*self.client)))
     }
 
     fn get_network_id(&self) -> Result<NetworkId> {
         Ok(self.client.network_id())
     }
 
-    fn get_common_params(&self, block_number: Option<u64>) -> Result<Option<Params>> {
-        let block_id = block_number.map(BlockId::Number).unwrap_or(BlockId::Latest);
-        Ok(self.client.common_params(block_id).map(Params::from))
+    fn get_common_params(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<Params>> {
+        self.blocking(move |client| {
+            let block_id = match block_number.unwrap_or_default().block_id(client) {
+                Some(block_id) => block_id,
+                None => return Ok(None),
+            };
+            Ok(client.common_params(block_id).map(Params::from))
+        })
     }
 
-    fn get_term_metadata(&self, block_number: Option<u64>) -> Result<Option<(u64, u64)>> {
-        let block_id = block_number.map(BlockId::Number).unwrap_or(BlockId::Latest);
-        let last_term_finished_block_num = self.client.last_term_finished_block_num(block_id);
-        let current_term_id = self.client.current_term_id(block_id);
-        match (last_term_finished_block_num, current_term_id) {
-            (Some(last_term_finished_block_num), Some(current_term_id)) => {
-                Ok(Some((last_term_finished_block_num, current_term_id)))
+    fn get_term_metadata(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<(u64, u64)>> {
+        self.blocking(move |client| {
+            let block_id = match block_number.unwrap_or_default().block_id(client) {
+                Some(block_id) => block_id,
+                None => return Ok(None),
+            };
+            let last_term_finished_block_num = client.last_term_finished_block_num(block_id);
+            let current_term_id = client.current_term_id(block_id);
+            match (last_term_finished_block_num, current_term_id) {
+                (Some(last_term_finished_block_num), Some(current_term_id)) => {
+                    Ok(Some((last_term_finished_block_num, current_term_id)))
+                }
+                (None, None) => Ok(None),
+                _ => unreachable!(),
             }
-            (None, None) => Ok(None),
-            _ => unreachable!(),
-        }
+        })
     }
 
-    fn get_metadata_seq(&self, block_number: Option<u64>) -> Result<Option<u64>> {
-        let block_id = block_number.map(BlockId::Number).unwrap_or(BlockId::Latest);
-        Ok(self.client.metadata_seq(block_id))
+    fn get_metadata_seq(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<u64>> {
+        self.blocking(move |client| {
+            let block_id = match block_number.unwrap_or_default().block_id(client) {
+                Some(block_id) => block_id,
+                None => return Ok(None),
+            };
+            Ok(client.metadata_seq(block_id))
+        })
     }
 
-    fn get_possible_authors(&self, block_number: Option<u64>) -> Result<Option<Vec<PlatformAddress>>> {
-        Ok(self.client.possible_authors(block_number).map_err(errors::core)?)
+    fn get_possible_authors(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<Vec<PlatformAddress>>> {
+        self.blocking(move |client| {
+            let block_number = block_number.map(|block_number| block_number.number(client));
+            Ok(client.possible_authors(block_number).map_err(errors::core)?)
+        })
     }
 
-    fn execute_transaction(&self, tx: UnsignedTransaction, sender: PlatformAddress) -> Result<Option<String>> {
-        let sender_address = sender.try_address().map_err(errors::core)?;
-        let action = Action::try_from(tx.action).map_err(errors::conversion)?;
-        if let Some(transaction) = action.shard_transaction() {
-            let result = self.client.execute_transaction(&transaction, sender_address);
-            match result {
-                Ok(()) => Ok(None),
-                Err(err) => Ok(Some(err.to_string())),
+    fn execute_transaction(&self, tx: UnsignedTransaction, sender: PlatformAddress) -> BoxFuture<Option<String>> {
+        self.blocking(move |client| {
+            let sender_address = sender.try_address().map_err(errors::core)?;
+            let action = Action::try_from(tx.action).map_err(errors::conversion)?;
+            if let Some(transaction) = action.shard_transaction() {
+                let result = client.execute_transaction(&transaction, sender_address);
+                match result {
+                    Ok(()) => Ok(None),
+                    Err(err) => Ok(Some(err.to_string())),
+                }
+            } else {
+                Err(errors::shard_transaction_only())
             }
-        } else {
-            Err(errors::shard_transaction_only())
-        }
+        })
+    }
+
+    fn dry_run_transaction(
+        &self,
+        tx: UnsignedTransaction,
+        block_number: Option<BlockNumberOrTag>,
+        signer_public: Public,
+    ) -> BoxFuture<Option<TransactionExecution>> {
+        self.blocking(move |client| {
+            let block_id = match block_number.unwrap_or_default().block_id(client) {
+                Some(block_id) => block_id,
+                None => return Ok(None),
+            };
+            let (incomplete, seq) = <(IncompleteTransaction, Option<u64>)>::try_from(tx)?;
+            let seq = match seq {
+                Some(seq) => seq,
+                None => {
+                    let signer = public_to_address(&signer_public);
+                    let fee_payer = client.regular_key_owner(&signer, block_id.into()).unwrap_or(signer);
+                    match client.seq(&fee_payer, block_id) {
+                        Some(seq) => seq,
+                        None => return Ok(None),
+                    }
+                }
+            };
+            let tx = incomplete.complete(seq);
+            let network_id = client.network_id();
+            Ok(client
+                .dry_run_transaction(&tx, &signer_public, block_id)
+                .map(|execution| TransactionExecution::from_core(execution, network_id)))
+        })
     }
 }
@@ -0,0 +1,55 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::super::traits::Checkpoint;
+use super::super::types::SignedCheckpoint;
+use ccore::SignedCheckpoint as CoreSignedCheckpoint;
+use cnetwork::{unbounded_event_callback, EventSender};
+use csync::CheckpointEvent;
+use jsonrpc_core::Result;
+
+pub struct CheckpointClient {
+    checkpoint: EventSender<CheckpointEvent>,
+}
+
+impl CheckpointClient {
+    pub fn new(checkpoint: EventSender<CheckpointEvent>) -> Self {
+        CheckpointClient {
+            checkpoint,
+        }
+    }
+
+    /// The kept checkpoints from the latest one.
+    fn checkpoints(&self) -> Vec<CoreSignedCheckpoint> {
+        let (sender, receiver) = unbounded_event_callback();
+        self.checkpoint.send(CheckpointEvent::GetCheckpoints(sender)).unwrap();
+        receiver.iter().collect()
+    }
+}
+
+impl Checkpoint for CheckpointClient {
+    fn get_latest(&self) -> Result<Option<SignedCheckpoint>> {
+        Ok(self.checkpoints().into_iter().next().map(Into::into))
+    }
+
+    fn get_checkpoint(&self, block_number: u64) -> Result<Option<SignedCheckpoint>> {
+        Ok(self
+            .checkpoints()
+            .into_iter()
+            .find(|signed| signed.checkpoint.number == block_number)
+            .map(Into::into))
+    }
+}
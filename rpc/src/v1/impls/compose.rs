@@ -0,0 +1,75 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::super::errors;
+use super::super::types::{ComposedTransaction, SendTransactionResult};
+use ccore::{AccountData, AccountProvider, EngineInfo, MinerService, MiningBlockChainClient, TermInfo};
+use cjson::uint::Uint;
+use ckey::{Password, PlatformAddress};
+use ctypes::transaction::{Action, IncompleteTransaction};
+use jsonrpc_core::{Error, Result};
+use parking_lot::Mutex;
+use rlp::RlpStream;
+
+/// Composes a transaction with the given action. Signs and sends it if the signer is given.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn compose<C, M>(
+    client: &C,
+    miner: &M,
+    account_provider: &AccountProvider,
+    action: Action,
+    fee: Uint,
+    seq: Option<u64>,
+    signer: Option<PlatformAddress>,
+    passphrase: Option<Password>,
+) -> Result<ComposedTransaction>
+where
+    C: EngineInfo + MiningBlockChainClient + AccountData + TermInfo,
+    M: MinerService, {
+    let tx = IncompleteTransaction {
+        fee: fee.into(),
+        network_id: client.network_id(),
+        action,
+    };
+    let (tx, sent) = match signer {
+        Some(signer) => {
+            lazy_static! {
+                static ref LOCK: Mutex<()> = Mutex::new(());
+            }
+            let _guard = LOCK.lock();
+            let (hash, seq) = miner
+                .import_incomplete_transaction(client, account_provider, tx.clone(), signer, passphrase, seq)
+                .map_err(errors::core)?;
+            (
+                tx.complete(seq),
+                Some(SendTransactionResult {
+                    hash,
+                    seq,
+                }),
+            )
+        }
+        None => {
+            let seq = seq.ok_or_else(|| Error::invalid_params("seq is required to compose an unsigned transaction"))?;
+            (tx.complete(seq), None)
+        }
+    };
+    let mut s = RlpStream::new();
+    tx.rlp_append_unsigned(&mut s);
+    Ok(ComposedTransaction {
+        unsigned: s.out().into(),
+        sent,
+    })
+}
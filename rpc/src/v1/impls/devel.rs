@@ -16,54 +16,93 @@
 
 use super::super::errors;
 use super::super::traits::Devel;
-use super::super::types::TPSTestSetting;
+use super::super::types::{BlockNumberOrTag, ChaosSetting, TPSTestProfile, TPSTestReport, TPSTestSetting};
+use crate::{ApiSwitch, BlockingPool};
 use ccore::{
-    BlockId, DatabaseClient, EngineClient, EngineInfo, MinerService, MiningBlockChainClient, SignedTransaction,
-    SnapshotClient, TermInfo, COL_STATE,
+    BlockId, DatabaseClient, EngineClient, EngineInfo, ForkClient, MaintenanceClient, MinerService,
+    MiningBlockChainClient, ReplayClient, SchemeExportClient, SignedTransaction, SnapshotClient, TermInfo, COL_STATE,
 };
 use cjson::bytes::Bytes;
-use ckey::{Address, KeyPair, Private};
-use cnetwork::{unbounded_event_callback, EventSender, IntoSocketAddr};
+use ckey::{Address, Generator, KeyPair, Private, Random};
+use cnetwork::{
+    unbounded_event_callback, ChaosConfig, EventSender, IntoSocketAddr, NetworkControl, NetworkControlError,
+};
 use csync::BlockSyncEvent;
 use ctypes::transaction::{Action, Transaction};
 use ctypes::BlockHash;
-use jsonrpc_core::Result;
+use jsonrpc_core::{BoxFuture, Error, Result};
 use kvdb::KeyValueDB;
 use primitives::H256;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rlp::Rlp;
+use serde_json::Value;
 use std::net::SocketAddr;
 use std::ops::Deref;
 use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::vec::Vec;
 use time::PreciseTime;
 
+/// The seconds that the TPS test waits for its transactions to be mined, unless the setting has it.
+const DEFAULT_TPS_TEST_TIMEOUT: u64 = 600;
+
 pub struct DevelClient<C, M> {
     client: Arc<C>,
     db: Arc<dyn KeyValueDB>,
     miner: Arc<M>,
     block_sync: Option<EventSender<BlockSyncEvent>>,
+    network_control: Option<Arc<dyn NetworkControl>>,
+    api_switch: ApiSwitch,
+    pool: BlockingPool,
 }
 
 impl<C, M> DevelClient<C, M>
 where
     C: DatabaseClient,
 {
-    pub fn new(client: Arc<C>, miner: Arc<M>, block_sync: Option<EventSender<BlockSyncEvent>>) -> Self {
+    pub fn new(
+        client: Arc<C>,
+        miner: Arc<M>,
+        block_sync: Option<EventSender<BlockSyncEvent>>,
+        network_control: Option<Arc<dyn NetworkControl>>,
+        api_switch: ApiSwitch,
+        pool: BlockingPool,
+    ) -> Self {
         let db = client.database();
         Self {
             client,
             db,
             miner,
             block_sync,
+            network_control,
+            api_switch,
+            pool,
         }
     }
+
+    fn network_control(&self) -> Result<&dyn NetworkControl> {
+        self.network_control
+            .as_ref()
+            .map(AsRef::as_ref)
+            .ok_or_else(|| errors::network_control(&NetworkControlError::Disabled))
+    }
 }
 
 impl<C, M> Devel for DevelClient<C, M>
 where
-    C: DatabaseClient + EngineInfo + EngineClient + MiningBlockChainClient + TermInfo + SnapshotClient + 'static,
+    C: DatabaseClient
+        + EngineInfo
+        + EngineClient
+        + MiningBlockChainClient
+        + TermInfo
+        + SnapshotClient
+        + SchemeExportClient
+        + ForkClient
+        + MaintenanceClient
+        + ReplayClient
+        + 'static,
     M: MinerService + 'static,
 {
     fn get_state_trie_keys(&self, offset: usize, limit: usize) -> Result<Vec<H256>> {
@@ -126,7 +165,87 @@ where
         Ok(())
     }
 
+    fn export_scheme(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<Value>> {
+        let client = Arc::clone(&self.client);
+        // All the state tries are walked, so the export runs on the blocking pool.
+        self.pool.run(move || {
+            let block_id = match block_number.unwrap_or_default().block_id(&*client) {
+                Some(block_id) => block_id,
+                None => return Ok(None),
+            };
+            Ok(client.export_genesis(block_id))
+        })
+    }
+
+    fn create_fork(&self, parent_hash: BlockHash) -> Result<BlockHash> {
+        self.client.create_fork(parent_hash).map_err(errors::core)
+    }
+
+    fn reorg_to(&self, hash: BlockHash) -> Result<()> {
+        self.client.reorg_to(hash).map_err(errors::core)
+    }
+
+    fn set_chaos(&self, setting: ChaosSetting) -> Result<()> {
+        let config: ChaosConfig = setting.into();
+        if !config.is_valid() {
+            return Err(Error::invalid_params("The percentages must not exceed 100"))
+        }
+        self.network_control()?.set_chaos(config).map_err(|e| errors::network_control(&e))
+    }
+
+    fn get_chaos(&self) -> Result<ChaosSetting> {
+        self.network_control()?.chaos().map(Into::into).map_err(|e| errors::network_control(&e))
+    }
+
+    fn set_maintenance_mode(&self, on: bool) -> Result<()> {
+        self.client.set_maintenance_mode(on);
+        Ok(())
+    }
+
+    fn get_maintenance_mode(&self) -> Result<bool> {
+        Ok(self.client.is_in_maintenance())
+    }
+
+    fn get_block_witness(&self, block_number: BlockNumberOrTag) -> Result<Option<Vec<Bytes>>> {
+        let block_id = match block_number.block_id(&*self.client) {
+            Some(block_id) => block_id,
+            None => return Ok(None),
+        };
+        let witness = self.client.block_witness(block_id).map_err(errors::core)?;
+        Ok(witness.map(|nodes| nodes.into_iter().map(Bytes::from).collect()))
+    }
+
+    fn verify_block_witness(&self, block_number: BlockNumberOrTag, witness: Vec<Bytes>) -> Result<Option<bool>> {
+        let block_id = match block_number.block_id(&*self.client) {
+            Some(block_id) => block_id,
+            None => return Ok(None),
+        };
+        let expected_state_root = match self.client.block_header(&block_id) {
+            Some(header) => header.state_root(),
+            None => return Ok(None),
+        };
+        let witness: Vec<_> = witness.into_iter().map(Bytes::into_vec).collect();
+        let state_root = self.client.execute_with_witness(block_id, &witness).map_err(errors::core)?;
+        Ok(state_root.map(|state_root| state_root == expected_state_root))
+    }
+
+    fn enable_api(&self, namespace: String) -> Result<()> {
+        self.api_switch.set_enabled(&namespace, true)?;
+        cinfo!(RPC, "The {} namespace is enabled", namespace);
+        Ok(())
+    }
+
+    fn disable_api(&self, namespace: String) -> Result<()> {
+        self.api_switch.set_enabled(&namespace, false)?;
+        cinfo!(RPC, "The {} namespace is disabled", namespace);
+        Ok(())
+    }
+
     fn test_tps(&self, setting: TPSTestSetting) -> Result<f64> {
+        Ok(self.run_tps_test(setting)?.tps)
+    }
+
+    fn run_tps_test(&self, setting: TPSTestSetting) -> Result<TPSTestReport> {
         let common_params = self.client.common_params(BlockId::Latest).unwrap();
         let pay_fee = common_params.min_pay_transaction_cost();
         let network_id = common_params.network_id();
@@ -135,64 +254,175 @@ where
         let genesis_secret: Private = "ede1d4ccb4ec9a8bbbae9a13db3f4a7b56ea04189be86ac3a6a439d9a0a1addd".into();
         let genesis_keypair = KeyPair::from_private(genesis_secret).map_err(errors::transaction_core)?;
 
-        let base_seq = self.client.seq(&genesis_keypair.address(), BlockId::Latest).unwrap();
-
-        // Helper macros
-        macro_rules! pay_tx {
-            ($seq:expr, $address:expr) => {
-                pay_tx!($seq, $address, 1)
-            };
-            ($seq:expr, $address:expr, $quantity: expr) => {
-                Transaction {
-                    seq: $seq,
-                    fee: pay_fee,
-                    network_id,
-                    action: Action::Pay {
-                        receiver: $address,
-                        quantity: $quantity,
-                    },
-                }
-            };
-        }
-
         // Helper functions
         fn sign_tx(tx: Transaction, key_pair: &KeyPair) -> SignedTransaction {
             SignedTransaction::new_with_sign(tx, key_pair.private())
         }
 
-        fn tps(count: u64, start_time: PreciseTime, end_time: PreciseTime) -> f64 {
-            f64::from(count as u32) * 1000.0_f64 / f64::from(start_time.to(end_time).num_milliseconds() as i32)
+        fn elapsed_ms(start_time: PreciseTime, end_time: PreciseTime) -> u64 {
+            start_time.to(end_time).num_milliseconds() as u64
+        }
+
+        fn percentile(sorted: &[u64], p: usize) -> u64 {
+            sorted[(sorted.len() * p / 100).min(sorted.len() - 1)]
         }
 
         // Main
         let count = setting.count;
         if count == 0 {
-            return Ok(0.0)
+            return Ok(TPSTestReport {
+                count,
+                elapsed: 0,
+                tps: 0.0,
+                latency_p50: 0,
+                latency_p90: 0,
+                latency_p99: 0,
+                latency_max: 0,
+            })
         }
-        let transactions = {
-            let mut transactions = Vec::with_capacity(count as usize);
-            for i in 0..count {
-                let address = Address::random();
-                let tx = sign_tx(pay_tx!(base_seq + i, address), &genesis_keypair);
-                transactions.push(tx);
-            }
-            transactions
-        };
+        let deadline = Instant::now() + Duration::from_secs(setting.timeout.unwrap_or(DEFAULT_TPS_TEST_TIMEOUT));
+        let mut rng = StdRng::seed_from_u64(setting.seed);
 
-        let last_hash = transactions.last().unwrap().hash();
+        let senders = match setting.senders {
+            None => vec![genesis_keypair],
+            Some(senders) => (0..senders.max(1)).map(|_| Random.generate().unwrap()).collect(),
+        };
+        // ShardStore has no minimum cost, so every transaction pays the minimum fee of Pay.
+        let actions = (0..count)
+            .map(|i| {
+                let store = match setting.profile {
+                    TPSTestProfile::PayOnly => false,
+                    TPSTestProfile::ShardHeavy => true,
+                    TPSTestProfile::Mixed => rng.gen(),
+                };
+                if store {
+                    Action::ShardStore {
+                        network_id,
+                        shard_id: 0,
+                        content: format!("{}-{}", setting.seed, i),
+                    }
+                } else {
+                    Action::Pay {
+                        receiver: Address::random(),
+                        quantity: 1,
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
 
-        let first_transaction = transactions[0].clone();
-        for tx in transactions.into_iter().skip(1) {
-            self.client.queue_own_transaction(tx).map_err(errors::transaction_core)?;
+        if setting.senders.is_some() {
+            let mut costs = vec![0; senders.len()];
+            for (i, action) in actions.iter().enumerate() {
+                let quantity = match action {
+                    Action::Pay {
+                        quantity,
+                        ..
+                    } => *quantity,
+                    _ => 0,
+                };
+                costs[i % senders.len()] += pay_fee + quantity;
+            }
+            let base_seq = self.client.seq(&genesis_keypair.address(), BlockId::Latest).unwrap();
+            let funds = senders
+                .iter()
+                .zip(costs)
+                .enumerate()
+                .map(|(i, (sender, cost))| {
+                    sign_tx(
+                        Transaction {
+                            seq: base_seq + i as u64,
+                            fee: pay_fee,
+                            network_id,
+                            action: Action::Pay {
+                                receiver: sender.address(),
+                                quantity: cost,
+                            },
+                        },
+                        &genesis_keypair,
+                    )
+                })
+                .collect::<Vec<_>>();
+            let mut hashes = funds.iter().map(|tx| tx.hash()).collect::<Vec<_>>();
+            for tx in funds {
+                self.client.queue_own_transaction(tx).map_err(errors::transaction_core)?;
+            }
+            while !hashes.is_empty() {
+                hashes.retain(|hash| self.client.transaction(&(*hash).into()).is_none());
+                if hashes.is_empty() {
+                    break
+                }
+                if Instant::now() > deadline {
+                    return Err(errors::transactions_not_mined(hashes.len()))
+                }
+                thread::sleep(Duration::from_millis(50));
+            }
         }
+        let mut seqs = senders
+            .iter()
+            .map(|sender| self.client.seq(&sender.address(), BlockId::Latest).unwrap())
+            .collect::<Vec<_>>();
+
+        let transactions = actions
+            .into_iter()
+            .enumerate()
+            .map(|(i, action)| {
+                let index = i % senders.len();
+                let tx = Transaction {
+                    seq: seqs[index],
+                    fee: pay_fee,
+                    network_id,
+                    action,
+                };
+                seqs[index] += 1;
+                sign_tx(tx, &senders[index])
+            })
+            .collect::<Vec<_>>();
+
         let start_time = PreciseTime::now();
-        self.client.queue_own_transaction(first_transaction).map_err(errors::transaction_core)?;
-        while !self.client.is_pending_queue_empty() {
-            thread::sleep(Duration::from_millis(50));
+        let mut pending = Vec::with_capacity(transactions.len());
+        for (i, tx) in transactions.into_iter().enumerate() {
+            if let Some(rate) = setting.target_rate {
+                let scheduled = i as u64 * 1000 / rate.max(1);
+                let elapsed = elapsed_ms(start_time, PreciseTime::now());
+                if scheduled > elapsed {
+                    thread::sleep(Duration::from_millis(scheduled - elapsed));
+                }
+            }
+            let hash = tx.hash();
+            self.client.queue_own_transaction(tx).map_err(errors::transaction_core)?;
+            pending.push((hash, PreciseTime::now()));
         }
-        while self.client.transaction(&last_hash.into()).is_none() {}
 
+        let mut latencies = Vec::with_capacity(pending.len());
+        while !pending.is_empty() {
+            let now = PreciseTime::now();
+            pending.retain(|(hash, queued_time)| {
+                if self.client.transaction(&(*hash).into()).is_none() {
+                    return true
+                }
+                latencies.push(elapsed_ms(*queued_time, now));
+                false
+            });
+            if pending.is_empty() {
+                break
+            }
+            if Instant::now() > deadline {
+                return Err(errors::transactions_not_mined(pending.len()))
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
         let end_time = PreciseTime::now();
-        Ok(tps(count, start_time, end_time))
+
+        latencies.sort();
+        let elapsed = elapsed_ms(start_time, end_time).max(1);
+        Ok(TPSTestReport {
+            count,
+            elapsed,
+            tps: count as f64 * 1000.0 / elapsed as f64,
+            latency_p50: percentile(&latencies, 50),
+            latency_p90: percentile(&latencies, 90),
+            latency_p99: percentile(&latencies, 99),
+            latency_max: *latencies.last().unwrap(),
+        })
     }
 }
@@ -0,0 +1,66 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::super::traits::Discovery;
+use jsonrpc_core::{Result, Value};
+
+/// The OpenRPC document generated from the RPC traits at build time.
+pub const OPENRPC_DOCUMENT: &str = include_str!(concat!(env!("OUT_DIR"), "/openrpc.json"));
+
+#[derive(Default)]
+pub struct DiscoveryClient {}
+
+impl DiscoveryClient {
+    pub fn new() -> Self {
+        DiscoveryClient {}
+    }
+}
+
+impl Discovery for DiscoveryClient {
+    fn discover(&self) -> Result<Value> {
+        Ok(serde_json::from_str(OPENRPC_DOCUMENT).expect("The generated OpenRPC document is valid JSON"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn method(document: &Value, name: &str) -> Value {
+        let methods = document["methods"].as_array().expect("The methods are an array");
+        methods.iter().find(|method| method["name"] == json!(name)).cloned().unwrap_or_else(|| panic!("No {}", name))
+    }
+
+    #[test]
+    fn document_describes_the_methods_of_the_traits() {
+        let document = DiscoveryClient::new().discover().unwrap();
+        assert_eq!(json!(env!("CARGO_PKG_VERSION")), document["info"]["version"]);
+
+        let discover = method(&document, "rpc.discover");
+        assert_eq!(json!([]), discover["params"]);
+        assert_eq!(json!({}), discover["result"]["schema"]);
+
+        // The methods running on the blocking pool are described too.
+        let get_block = method(&document, "chain_getBlockByNumber");
+        assert_eq!(json!("Gets block with given number."), get_block["summary"]);
+        let param = &get_block["params"][0];
+        assert_eq!((json!("block_number"), json!(true)), (param["name"].clone(), param["required"].clone()));
+        assert_eq!(json!("#/components/schemas/BlockNumberOrTag"), param["schema"]["$ref"]);
+        assert_eq!(json!({"type": "null"}), get_block["result"]["schema"]["oneOf"][1]);
+        assert!(document["components"]["schemas"]["BlockNumberOrTag"]["oneOf"].is_array());
+    }
+}
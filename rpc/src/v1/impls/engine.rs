@@ -16,19 +16,34 @@
 
 use super::super::errors;
 use super::super::traits::Engine;
-use ccore::{BlockId, EngineInfo, MinerService, StateInfo};
+use super::super::types::{
+    BlockNumberOrTag, ClockDrift, ComposedTransaction, Duties, Prisoner, StakeValidator, TermInfo,
+};
+use super::compose::compose;
+use crate::BlockingPool;
+use ccore::stake::{self, Action as StakeAction, Banned, Jail, NextValidators, CUSTOM_ACTION_HANDLER_ID};
+use ccore::{AccountData, AccountProvider, BlockId, EngineInfo, MinerService, MiningBlockChainClient, StateInfo};
 use cjson::bytes::{Bytes, WithoutPrefix};
-use ckey::PlatformAddress;
-use cstate::FindActionHandler;
-use jsonrpc_core::Result;
+use cjson::uint::Uint;
+use ckey::{Password, PlatformAddress};
+use cstate::{FindActionHandler, TopStateView};
+use ctypes::transaction::Action;
+use jsonrpc_core::{BoxFuture, Error, Result};
+use rlp::Encodable;
 use std::sync::Arc;
 
+/// The number of the blocks predicted by `engine_getUpcomingDuties` by default.
+const DEFAULT_DUTY_BLOCKS: u64 = 100;
+const MAX_DUTY_BLOCKS: u64 = 1000;
+
 pub struct EngineClient<C, M>
 where
     C: EngineInfo + StateInfo + FindActionHandler,
     M: MinerService, {
+    account_provider: Arc<AccountProvider>,
     client: Arc<C>,
     miner: Arc<M>,
+    pool: BlockingPool,
 }
 
 impl<C, M> EngineClient<C, M>
@@ -36,21 +51,41 @@ where
     C: EngineInfo + StateInfo + FindActionHandler,
     M: MinerService,
 {
-    pub fn new(client: Arc<C>, miner: Arc<M>) -> Self {
+    pub fn new(account_provider: Arc<AccountProvider>, client: Arc<C>, miner: Arc<M>, pool: BlockingPool) -> Self {
         Self {
+            account_provider,
             client,
             miner,
+            pool,
+        }
+    }
+}
+
+/// Finds the block that closed the term, which is the first block whose term id is greater than `term_id`.
+fn term_end_block<C: ccore::TermInfo>(client: &C, term_id: u64, from: u64, best: u64) -> Option<u64> {
+    let term_id_at = |number: u64| client.current_term_id(BlockId::Number(number)).unwrap_or(term_id);
+    if term_id_at(best) <= term_id {
+        return None
+    }
+    let (mut low, mut high) = (from, best);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if term_id_at(mid) > term_id {
+            high = mid;
+        } else {
+            low = mid + 1;
         }
     }
+    Some(high)
 }
 
 impl<C, M> Engine for EngineClient<C, M>
 where
-    C: EngineInfo + StateInfo + FindActionHandler + 'static,
+    C: EngineInfo + StateInfo + FindActionHandler + MiningBlockChainClient + AccountData + ccore::TermInfo + 'static,
     M: MinerService + 'static,
 {
-    fn get_block_reward(&self, block_number: u64) -> Result<u64> {
-        Ok(self.client.block_reward(block_number))
+    fn get_block_reward(&self, block_number: BlockNumberOrTag) -> Result<u64> {
+        Ok(self.client.block_reward(block_number.number(&*self.client)))
     }
 
     fn get_coinbase(&self) -> Result<Option<PlatformAddress>> {
@@ -68,11 +103,24 @@ where
         Ok(self.client.recommended_confirmation())
     }
 
+    fn get_clock_drift(&self) -> Result<Option<ClockDrift>> {
+        Ok(self.client.clock_drift().map(ClockDrift::from))
+    }
+
+    fn get_upcoming_duties(&self, count: Option<u64>) -> Result<Option<Duties>> {
+        let count = count.unwrap_or(DEFAULT_DUTY_BLOCKS);
+        if count > MAX_DUTY_BLOCKS {
+            return Err(Error::invalid_params(format!("Cannot predict more than {} blocks", MAX_DUTY_BLOCKS)))
+        }
+        let network_id = self.client.network_id();
+        Ok(self.client.duties(count as usize).map(|duties| Duties::from_core(duties, network_id)))
+    }
+
     fn get_custom_action_data(
         &self,
         handler_id: u64,
         key_fragment: Bytes,
-        block_number: Option<u64>,
+        block_number: Option<BlockNumberOrTag>,
     ) -> Result<Option<WithoutPrefix<Bytes>>> {
         let handler = self.client.find_action_handler_for(handler_id).ok_or_else(|| {
             errors::invalid_custom_action(format!(
@@ -80,8 +128,7 @@ where
                 handler_id
             ))
         })?;
-        let block_id = block_number.map(BlockId::Number).unwrap_or(BlockId::Latest);
-        let state = self.client.state_at(block_id).ok_or_else(errors::state_not_exist)?;
+        let state = block_number.unwrap_or_default().state(&*self.client).ok_or_else(errors::state_not_exist)?;
 
         match handler.query(&key_fragment, &state) {
             Ok(Some(action_data)) => Ok(Some(Bytes::new(action_data).into_without_prefix())),
@@ -89,4 +136,101 @@ where
             Err(e) => Err(errors::transaction_core(e)),
         }
     }
+
+    fn get_term_info(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<TermInfo>> {
+        let client = Arc::clone(&self.client);
+        self.pool.run(move || {
+            let best_block_number = client.chain_info().best_block_number;
+            let block = block_number.unwrap_or_default();
+            let block_number = block.number(&*client);
+            let mut state = match block.state(&*client) {
+                Some(state) => state,
+                None => return Ok(None),
+            };
+            let metadata = state.metadata().map_err(errors::transaction_core)?.expect("Metadata must exist");
+            let term_id = metadata.current_term_id();
+            let network_id = client.network_id();
+
+            let banned = Banned::load_from_state(&state).map_err(errors::transaction_core)?;
+            let jail = Jail::load_from_state(&state).map_err(errors::transaction_core)?;
+            let banned = banned.iter().map(|address| PlatformAddress::new_v1(network_id, *address)).collect();
+            let jailed = jail.prisoners().cloned().map(|prisoner| Prisoner::from_core(prisoner, network_id)).collect();
+
+            // The election is simulated on the copied state, which is never committed.
+            let next_validators = if term_id != 0 && metadata.params().is_some() {
+                stake::on_term_close(&mut state, block_number, &[]).map_err(errors::transaction_core)?;
+                let validators = NextValidators::load_from_state(&state).map_err(errors::transaction_core)?;
+                Some(validators.iter().map(|validator| StakeValidator::from_core(validator, network_id)).collect())
+            } else {
+                None
+            };
+
+            Ok(Some(TermInfo {
+                term_id,
+                start_block: metadata.last_term_finished_block_num() + 1,
+                end_block: term_end_block(client.as_ref(), term_id, block_number + 1, best_block_number),
+                banned,
+                jailed,
+                next_validators,
+            }))
+        })
+    }
+
+    fn unjail(
+        &self,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction> {
+        let action = Action::Custom {
+            handler_id: CUSTOM_ACTION_HANDLER_ID,
+            bytes: StakeAction::Unjail.rlp_bytes(),
+        };
+        compose(
+            self.client.as_ref(),
+            self.miner.as_ref(),
+            self.account_provider.as_ref(),
+            action,
+            fee,
+            seq,
+            signer,
+            passphrase,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ctypes::{BlockNumber, CommonParams};
+
+    /// Every term has 10 blocks.
+    struct Terms;
+
+    impl ccore::TermInfo for Terms {
+        fn last_term_finished_block_num(&self, _id: BlockId) -> Option<BlockNumber> {
+            unimplemented!()
+        }
+
+        fn current_term_id(&self, id: BlockId) -> Option<u64> {
+            match id {
+                BlockId::Number(number) => Some(number / 10),
+                _ => None,
+            }
+        }
+
+        fn term_common_params(&self, _id: BlockId) -> Option<CommonParams> {
+            unimplemented!()
+        }
+    }
+
+    #[test]
+    fn term_ends_at_the_first_block_of_the_next_term() {
+        assert_eq!(Some(10), term_end_block(&Terms, 0, 1, 25));
+        assert_eq!(Some(20), term_end_block(&Terms, 1, 15, 25));
+        assert_eq!(Some(20), term_end_block(&Terms, 1, 20, 20));
+        assert_eq!(None, term_end_block(&Terms, 1, 15, 19));
+        assert_eq!(None, term_end_block(&Terms, 2, 21, 25));
+    }
 }
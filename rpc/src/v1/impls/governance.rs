@@ -0,0 +1,158 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::super::errors;
+use super::super::traits::Governance;
+use super::super::types::{BlockNumberOrTag, ComposedTransaction, Proposal, VoteOption};
+use super::compose::compose;
+use crate::BlockingPool;
+use ccore::stake::{Action as StakeAction, Proposal as CoreProposal, Proposals, CUSTOM_ACTION_HANDLER_ID};
+use ccore::{AccountData, AccountProvider, EngineInfo, MinerService, MiningBlockChainClient, StateInfo, TermInfo};
+use cjson::uint::Uint;
+use ckey::{Password, PlatformAddress};
+use cstate::TopLevelState;
+use ctypes::transaction::Action;
+use jsonrpc_core::{BoxFuture, Result};
+use rlp::Encodable;
+use std::sync::Arc;
+
+pub struct GovernanceClient<C, M> {
+    account_provider: Arc<AccountProvider>,
+    client: Arc<C>,
+    miner: Arc<M>,
+    pool: BlockingPool,
+}
+
+impl<C, M> GovernanceClient<C, M> {
+    pub fn new(account_provider: Arc<AccountProvider>, client: Arc<C>, miner: Arc<M>, pool: BlockingPool) -> Self {
+        GovernanceClient {
+            account_provider,
+            client,
+            miner,
+            pool,
+        }
+    }
+}
+
+impl<C, M> GovernanceClient<C, M>
+where
+    C: EngineInfo + MiningBlockChainClient + AccountData + TermInfo + StateInfo + 'static,
+    M: MinerService + 'static,
+{
+    fn compose(
+        &self,
+        action: StakeAction,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction> {
+        let action = Action::Custom {
+            handler_id: CUSTOM_ACTION_HANDLER_ID,
+            bytes: action.rlp_bytes(),
+        };
+        compose(
+            self.client.as_ref(),
+            self.miner.as_ref(),
+            self.account_provider.as_ref(),
+            action,
+            fee,
+            seq,
+            signer,
+            passphrase,
+        )
+    }
+
+    /// Runs `f` with the state of the given block on the blocking pool.
+    fn with_state<T, F>(&self, block_number: Option<BlockNumberOrTag>, f: F) -> BoxFuture<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&C, &TopLevelState) -> Result<T> + Send + 'static, {
+        let client = Arc::clone(&self.client);
+        self.pool.run(move || {
+            let state = block_number.unwrap_or_default().state(&*client).ok_or_else(errors::state_not_exist)?;
+            f(&client, &state)
+        })
+    }
+}
+
+impl<C, M> Governance for GovernanceClient<C, M>
+where
+    C: EngineInfo + MiningBlockChainClient + AccountData + TermInfo + StateInfo + 'static,
+    M: MinerService + 'static,
+{
+    fn get_proposal_count(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<u64> {
+        self.with_state(block_number, |_, state| {
+            let proposals = Proposals::load_from_state(state).map_err(errors::transaction_core)?;
+            Ok(proposals.count())
+        })
+    }
+
+    fn get_proposal(&self, id: u64, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<Proposal>> {
+        self.with_state(block_number, move |client, state| {
+            let proposal = CoreProposal::load_from_state(state, id).map_err(errors::transaction_core)?;
+            let network_id = client.network_id();
+            Ok(proposal.map(|proposal| Proposal::from_core(id, proposal, network_id)))
+        })
+    }
+
+    fn get_open_proposals(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Vec<Proposal>> {
+        self.with_state(block_number, |client, state| {
+            let proposals = Proposals::load_from_state(state).map_err(errors::transaction_core)?;
+            let network_id = client.network_id();
+            let mut result = Vec::new();
+            for id in proposals.open() {
+                let proposal = CoreProposal::load_from_state(state, *id)
+                    .map_err(errors::transaction_core)?
+                    .expect("Open proposals exist");
+                result.push(Proposal::from_core(*id, proposal, network_id));
+            }
+            Ok(result)
+        })
+    }
+
+    fn propose(
+        &self,
+        text: String,
+        deadline: u64,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction> {
+        let action = StakeAction::CreateProposal {
+            text,
+            deadline,
+        };
+        self.compose(action, fee, seq, signer, passphrase)
+    }
+
+    fn vote(
+        &self,
+        id: u64,
+        option: VoteOption,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction> {
+        let action = StakeAction::VoteProposal {
+            id,
+            option: option.into(),
+        };
+        self.compose(action, fee, seq, signer, passphrase)
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::super::traits::Ibc;
+use super::super::types::{BlockNumberOrTag, Commit, CommitmentProof, ConsensusState};
+use ccore::{validator_set_hash, BlockChainClient, BlockId, EngineInfo, LightClientSupport};
+use jsonrpc_core::Result;
+use primitives::H256;
+use std::sync::Arc;
+
+pub struct IbcClient<C>
+where
+    C: BlockChainClient + LightClientSupport, {
+    client: Arc<C>,
+}
+
+impl<C> IbcClient<C>
+where
+    C: BlockChainClient + LightClientSupport,
+{
+    pub fn new(client: Arc<C>) -> Self {
+        IbcClient {
+            client,
+        }
+    }
+}
+
+impl<C> Ibc for IbcClient<C>
+where
+    C: BlockChainClient + EngineInfo + LightClientSupport + 'static,
+{
+    fn get_consensus_state(&self, block_number: Option<BlockNumberOrTag>) -> Result<Option<ConsensusState>> {
+        let header =
+            match block_number.unwrap_or_default().block_id(&*self.client).and_then(|id| self.client.block_header(&id))
+            {
+                Some(header) => header,
+                None => return Ok(None),
+            };
+        let validators = match self.client.commit_validators(BlockId::Hash(header.hash())) {
+            Some(validators) => validators,
+            None => return Ok(None),
+        };
+        Ok(Some(ConsensusState {
+            number: header.number(),
+            hash: header.hash(),
+            timestamp: header.timestamp(),
+            state_root: header.state_root(),
+            validator_set_hash: validator_set_hash(&validators),
+            validators: validators.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    fn get_commit(&self, block_number: BlockNumberOrTag) -> Result<Option<Commit>> {
+        Ok(block_number.block_id(&*self.client).and_then(|id| self.client.commit(id)).map(Into::into))
+    }
+
+    fn get_commitment_proof(
+        &self,
+        key: H256,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> Result<Option<CommitmentProof>> {
+        let header =
+            match block_number.unwrap_or_default().block_id(&*self.client).and_then(|id| self.client.block_header(&id))
+            {
+                Some(header) => header,
+                None => return Ok(None),
+            };
+        Ok(self
+            .client
+            .action_data_proof(&key, BlockId::Hash(header.hash()))
+            .map(|proof| CommitmentProof::new(header.state_root(), proof)))
+    }
+}
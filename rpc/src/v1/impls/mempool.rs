@@ -16,7 +16,7 @@
 
 use super::super::errors;
 use super::super::traits::Mempool;
-use super::super::types::{MemPoolMinFees, PendingTransactions};
+use super::super::types::{MemPoolContent, MemPoolMinFees, PendingTransactions, Transaction};
 use ccore::{BlockChainClient, EngineInfo, MiningBlockChainClient, SignedTransaction};
 use cjson::bytes::Bytes;
 use ckey::{Address, PlatformAddress};
@@ -74,6 +74,11 @@ where
         Ok(())
     }
 
+    fn cancel_transaction(&self, address: PlatformAddress, seq: u64) -> Result<Option<TxHash>> {
+        let address = address.try_into_address().map_err(errors::core)?;
+        Ok(self.client.cancel_pending_transaction(&address, seq))
+    }
+
     fn get_pending_transactions(&self, from: Option<u64>, to: Option<u64>) -> Result<PendingTransactions> {
         Ok(self.client.ready_transactions(from.unwrap_or(0)..to.unwrap_or(::std::u64::MAX)).into())
     }
@@ -82,6 +87,16 @@ where
         Ok(self.client.count_pending_transactions(from.unwrap_or(0)..to.unwrap_or(::std::u64::MAX)))
     }
 
+    fn get_scheduled_transactions(&self) -> Result<Vec<Transaction>> {
+        Ok(self.client.scheduled_transactions().into_iter().map(From::from).collect())
+    }
+
+    fn get_content(&self) -> Result<MemPoolContent> {
+        Ok(MemPoolContent::new(self.client.mem_pool_content(), self.client.invalid_transactions_of_peers(), |action| {
+            self.client.base_fee(action)
+        }))
+    }
+
     fn get_banned_accounts(&self) -> Result<Vec<PlatformAddress>> {
         let malicious_user_vec = self.client.get_malicious_users();
         let network_id = self.client.network_id();
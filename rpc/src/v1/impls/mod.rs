@@ -16,16 +16,31 @@
 
 mod account;
 mod chain;
+mod checkpoint;
+mod compose;
 mod devel;
+mod discovery;
 mod engine;
+mod governance;
+mod ibc;
 mod mempool;
 mod net;
+mod node;
+mod shard;
 mod snapshot;
+mod stake;
 
 pub use self::account::AccountClient;
 pub use self::chain::ChainClient;
+pub use self::checkpoint::CheckpointClient;
 pub use self::devel::DevelClient;
+pub use self::discovery::{DiscoveryClient, OPENRPC_DOCUMENT};
 pub use self::engine::EngineClient;
+pub use self::governance::GovernanceClient;
+pub use self::ibc::IbcClient;
 pub use self::mempool::MempoolClient;
 pub use self::net::NetClient;
+pub use self::node::NodeClient;
+pub use self::shard::ShardClient;
 pub use self::snapshot::SnapshotClient;
+pub use self::stake::StakeClient;
@@ -16,7 +16,7 @@
 
 use super::super::errors;
 use super::super::traits::Net;
-use super::super::types::FilterStatus;
+use super::super::types::{FilterStatus, TrustedPeers};
 use cidr::IpCidr;
 use ckey::Public;
 use cnetwork::{NetworkControl, SocketAddr};
@@ -126,6 +126,24 @@ impl Net for NetClient {
         })
     }
 
+    fn add_trusted_peer(&self, address: IpAddr, port: u16) -> Result<()> {
+        self.network_control.add_trusted_peer(SocketAddr::new(address, port)).map_err(|e| errors::network_control(&e))
+    }
+
+    fn remove_trusted_peer(&self, address: IpAddr, port: u16) -> Result<()> {
+        self.network_control
+            .remove_trusted_peer(&SocketAddr::new(address, port))
+            .map_err(|e| errors::network_control(&e))
+    }
+
+    fn get_trusted_peers(&self) -> Result<TrustedPeers> {
+        let (list, permissioned) = self.network_control.get_trusted_peers().map_err(|e| errors::network_control(&e))?;
+        Ok(TrustedPeers {
+            list: list.into_iter().map(Into::into).collect(),
+            permissioned,
+        })
+    }
+
     fn recent_network_usage(&self) -> Result<HashMap<String, usize>> {
         Ok(self.network_control.recent_network_usage().map_err(|e| errors::network_control(&e))?)
     }
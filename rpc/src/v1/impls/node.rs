@@ -0,0 +1,72 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::super::traits::Node;
+use super::super::types::NodeInfo;
+use crate::ApiSwitch;
+use jsonrpc_core::Result;
+
+pub struct NodeClient {
+    info: NodeInfo,
+    api_switch: ApiSwitch,
+}
+
+impl NodeClient {
+    pub fn new(info: NodeInfo, api_switch: ApiSwitch) -> Self {
+        NodeClient {
+            info,
+            api_switch,
+        }
+    }
+}
+
+impl Node for NodeClient {
+    fn get_info(&self) -> Result<NodeInfo> {
+        let mut info = self.info.clone();
+        info.namespaces.retain(|namespace| self.api_switch.is_enabled(namespace));
+        Ok(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::types::NodeFeatures;
+    use super::*;
+
+    #[test]
+    fn info_has_only_the_enabled_namespaces() {
+        let api_switch = ApiSwitch::default();
+        api_switch.register("chain", true);
+        api_switch.register("devel", false);
+        let info = NodeInfo {
+            version: "1.0.0".to_string(),
+            commit_hash: "0".repeat(40),
+            chain_type: "solo".to_string(),
+            network_id: "tc".into(),
+            features: NodeFeatures {
+                archive: true,
+                indexes: Vec::new(),
+                snapshot: false,
+            },
+            namespaces: vec!["chain".to_string(), "devel".to_string()],
+        };
+        let client = NodeClient::new(info, api_switch.clone());
+        assert_eq!(vec!["chain".to_string()], client.get_info().unwrap().namespaces);
+
+        api_switch.set_enabled("devel", true).unwrap();
+        assert_eq!(vec!["chain".to_string(), "devel".to_string()], client.get_info().unwrap().namespaces);
+    }
+}
@@ -0,0 +1,188 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::super::errors;
+use super::super::traits::Shard;
+use super::super::types::{BlockNumberOrTag, ComposedTransaction, ShardInfo, ShardProof};
+use super::compose::compose;
+use crate::BlockingPool;
+use ccore::{AccountData, AccountProvider, EngineInfo, MinerService, MiningBlockChainClient, StateInfo, TermInfo};
+use cjson::uint::Uint;
+use ckey::{Address, Password, PlatformAddress};
+use cstate::TopStateView;
+use ctypes::transaction::Action;
+use ctypes::ShardId;
+use jsonrpc_core::{BoxFuture, Result};
+use primitives::H256;
+use std::sync::Arc;
+
+pub struct ShardClient<C, M> {
+    account_provider: Arc<AccountProvider>,
+    client: Arc<C>,
+    miner: Arc<M>,
+    pool: BlockingPool,
+}
+
+impl<C, M> ShardClient<C, M> {
+    pub fn new(account_provider: Arc<AccountProvider>, client: Arc<C>, miner: Arc<M>, pool: BlockingPool) -> Self {
+        ShardClient {
+            account_provider,
+            client,
+            miner,
+            pool,
+        }
+    }
+}
+
+impl<C, M> ShardClient<C, M>
+where
+    C: EngineInfo + MiningBlockChainClient + AccountData + TermInfo + 'static,
+    M: MinerService + 'static,
+{
+    fn compose(
+        &self,
+        action: Action,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction> {
+        compose(
+            self.client.as_ref(),
+            self.miner.as_ref(),
+            self.account_provider.as_ref(),
+            action,
+            fee,
+            seq,
+            signer,
+            passphrase,
+        )
+    }
+
+    /// Runs `f`, which reads the states, on the blocking pool.
+    fn blocking<T, F>(&self, f: F) -> BoxFuture<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&C) -> Result<T> + Send + 'static, {
+        let client = Arc::clone(&self.client);
+        self.pool.run(move || f(&client))
+    }
+
+    fn into_addresses(addresses: Vec<PlatformAddress>) -> Result<Vec<Address>> {
+        addresses.into_iter().map(|address| address.try_into_address().map_err(errors::core)).collect()
+    }
+}
+
+impl<C, M> Shard for ShardClient<C, M>
+where
+    C: EngineInfo + MiningBlockChainClient + AccountData + TermInfo + StateInfo + ccore::Shard + 'static,
+    M: MinerService + 'static,
+{
+    fn create(
+        &self,
+        users: Vec<PlatformAddress>,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction> {
+        let action = Action::CreateShard {
+            users: Self::into_addresses(users)?,
+        };
+        self.compose(action, fee, seq, signer, passphrase)
+    }
+
+    fn set_owners(
+        &self,
+        shard_id: ShardId,
+        owners: Vec<PlatformAddress>,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction> {
+        let action = Action::SetShardOwners {
+            shard_id,
+            owners: Self::into_addresses(owners)?,
+        };
+        self.compose(action, fee, seq, signer, passphrase)
+    }
+
+    fn set_users(
+        &self,
+        shard_id: ShardId,
+        users: Vec<PlatformAddress>,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction> {
+        let action = Action::SetShardUsers {
+            shard_id,
+            users: Self::into_addresses(users)?,
+        };
+        self.compose(action, fee, seq, signer, passphrase)
+    }
+
+    fn terminate(
+        &self,
+        shard_id: ShardId,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction> {
+        let action = Action::TerminateShard {
+            shard_id,
+        };
+        self.compose(action, fee, seq, signer, passphrase)
+    }
+
+    fn get_info(&self, shard_id: ShardId, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<ShardInfo>> {
+        self.blocking(move |client| {
+            let state = match block_number.unwrap_or_default().state(client) {
+                Some(state) => state,
+                None => return Ok(None),
+            };
+            // A single read keeps the root, the owners and the users consistent.
+            let shard = match state.shard(shard_id).map_err(errors::transaction_core)? {
+                Some(shard) => shard,
+                None => return Ok(None),
+            };
+            let network_id = client.network_id();
+            Ok(Some(ShardInfo {
+                root: *shard.root(),
+                owners: shard.owners().iter().map(|owner| PlatformAddress::new_v1(network_id, *owner)).collect(),
+                users: shard.users().iter().map(|user| PlatformAddress::new_v1(network_id, *user)).collect(),
+            }))
+        })
+    }
+
+    fn get_proof(
+        &self,
+        shard_id: ShardId,
+        key: H256,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<ShardProof>> {
+        self.blocking(move |client| {
+            let state = match block_number.unwrap_or_default().state(client) {
+                Some(state) => state,
+                None => return Ok(None),
+            };
+            Ok(state.shard_proof(shard_id, &key).map_err(errors::transaction_core)?.map(Into::into))
+        })
+    }
+}
@@ -16,12 +16,14 @@
 
 use super::super::errors;
 use super::super::traits::Snapshot;
-use super::super::types::BlockNumberAndHash;
+use super::super::types::{BlockNumberAndHash, PublishedSnapshot};
 use ccore::{BlockChainClient, BlockId};
-use ctypes::BlockHash;
+use csync::snapshot::Publication;
+use ctypes::{BlockHash, BlockNumber};
 use jsonrpc_core::Result;
 use primitives::H256;
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -49,37 +51,68 @@ where
     C: BlockChainClient + 'static,
 {
     fn get_snapshot_list(&self) -> Result<Vec<BlockNumberAndHash>> {
-        if let Some(snapshot_path) = &self.snapshot_path {
-            let mut result = Vec::new();
-            for entry in fs::read_dir(snapshot_path).map_err(errors::io)? {
-                let entry = entry.map_err(errors::io)?;
+        let mut result: Vec<_> = self
+            .snapshots()?
+            .into_iter()
+            .map(|(number, hash, _)| BlockNumberAndHash {
+                number,
+                hash,
+            })
+            .collect();
+        result.sort_unstable_by(|a, b| b.number.cmp(&a.number));
+        Ok(result)
+    }
+
+    fn get_published_snapshots(&self) -> Result<Vec<PublishedSnapshot>> {
+        let mut result: Vec<_> = self
+            .snapshots()?
+            .into_iter()
+            .filter_map(|(number, hash, path)| {
+                Publication::read(&path).map(|publication| PublishedSnapshot {
+                    number,
+                    hash,
+                    location: publication.location,
+                })
+            })
+            .collect();
+        result.sort_unstable_by(|a, b| b.number.cmp(&a.number));
+        Ok(result)
+    }
+}
+
+impl<C> SnapshotClient<C>
+where
+    C: BlockChainClient,
+{
+    /// The snapshot directories of the known blocks.
+    fn snapshots(&self) -> Result<Vec<(BlockNumber, BlockHash, PathBuf)>> {
+        let snapshot_path = match &self.snapshot_path {
+            Some(snapshot_path) => Path::new(snapshot_path),
+            None => return Ok(Vec::new()),
+        };
+        let mut result = Vec::new();
+        for entry in fs::read_dir(snapshot_path).map_err(errors::io)? {
+            let entry = entry.map_err(errors::io)?;
 
-                // Check if the entry is a directory
-                let file_type = entry.file_type().map_err(errors::io)?;
-                if !file_type.is_dir() {
-                    continue
-                }
+            // Check if the entry is a directory
+            let file_type = entry.file_type().map_err(errors::io)?;
+            if !file_type.is_dir() {
+                continue
+            }
 
-                let path = entry.path();
-                let name = match path.file_name().expect("Directories always have file name").to_str() {
-                    Some(n) => n,
-                    None => continue,
-                };
-                let hash = match H256::from_str(name) {
-                    Ok(h) => BlockHash::from(h),
-                    Err(_) => continue,
-                };
-                if let Some(number) = self.client.block_number(&BlockId::Hash(hash)) {
-                    result.push(BlockNumberAndHash {
-                        number,
-                        hash,
-                    });
-                }
+            let path = entry.path();
+            let name = match path.file_name().expect("Directories always have file name").to_str() {
+                Some(n) => n,
+                None => continue,
+            };
+            let hash = match H256::from_str(name) {
+                Ok(h) => BlockHash::from(h),
+                Err(_) => continue,
+            };
+            if let Some(number) = self.client.block_number(&BlockId::Hash(hash)) {
+                result.push((number, hash, path));
             }
-            result.sort_unstable_by(|a, b| b.number.cmp(&a.number));
-            Ok(result)
-        } else {
-            Ok(Vec::new())
         }
+        Ok(result)
     }
 }
@@ -0,0 +1,356 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::super::errors;
+use super::super::traits::Stake;
+use super::super::types::{
+    BlockNumberOrTag, Candidate, Commission, ComposedTransaction, Delegation, LockedDeposit, Misses, PendingRewards,
+    Prisoner, SessionKey, StakeEvent, StakeValidator, TermHistory,
+};
+use super::compose::compose;
+use crate::BlockingPool;
+use ccore::stake::{
+    Action as StakeAction, Candidates, Commission as CoreCommission, CurrentValidators, Delegation as CoreDelegation,
+    History, IntermediateRewards, Jail, Misses as CoreMisses, SessionKey as CoreSessionKey, StakeAccount,
+    CUSTOM_ACTION_HANDLER_ID,
+};
+use ccore::{AccountData, AccountProvider, EngineInfo, MinerService, MiningBlockChainClient, StateInfo, TermInfo};
+use cjson::uint::Uint;
+use ckey::{Password, PlatformAddress, Public};
+use cstate::TopLevelState;
+use ctypes::transaction::Action;
+use jsonrpc_core::{BoxFuture, Error, Result};
+use rlp::Encodable;
+use std::sync::Arc;
+
+/// The number of the terms that `stake_getHistory` reads at once.
+const MAX_HISTORY_TERMS: u64 = 1000;
+
+pub struct StakeClient<C, M> {
+    account_provider: Arc<AccountProvider>,
+    client: Arc<C>,
+    miner: Arc<M>,
+    pool: BlockingPool,
+}
+
+impl<C, M> StakeClient<C, M> {
+    pub fn new(account_provider: Arc<AccountProvider>, client: Arc<C>, miner: Arc<M>, pool: BlockingPool) -> Self {
+        StakeClient {
+            account_provider,
+            client,
+            miner,
+            pool,
+        }
+    }
+}
+
+impl<C, M> StakeClient<C, M>
+where
+    C: EngineInfo + MiningBlockChainClient + AccountData + TermInfo + StateInfo + 'static,
+    M: MinerService + 'static,
+{
+    fn compose(
+        &self,
+        action: StakeAction,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction> {
+        let action = Action::Custom {
+            handler_id: CUSTOM_ACTION_HANDLER_ID,
+            bytes: action.rlp_bytes(),
+        };
+        compose(
+            self.client.as_ref(),
+            self.miner.as_ref(),
+            self.account_provider.as_ref(),
+            action,
+            fee,
+            seq,
+            signer,
+            passphrase,
+        )
+    }
+
+    /// Runs `f` with the state of the given block on the blocking pool.
+    fn with_state<T, F>(&self, block_number: Option<BlockNumberOrTag>, f: F) -> BoxFuture<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&C, &TopLevelState) -> Result<T> + Send + 'static, {
+        let client = Arc::clone(&self.client);
+        self.pool.run(move || {
+            let state = block_number.unwrap_or_default().state(&*client).ok_or_else(errors::state_not_exist)?;
+            f(&client, &state)
+        })
+    }
+}
+
+impl<C, M> Stake for StakeClient<C, M>
+where
+    C: EngineInfo + MiningBlockChainClient + AccountData + TermInfo + StateInfo + 'static,
+    M: MinerService + 'static,
+{
+    fn get_balance(&self, address: PlatformAddress, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Uint> {
+        self.with_state(block_number, move |_, state| {
+            let address = address.try_address().map_err(errors::core)?;
+            let account = StakeAccount::load_from_state(state, address).map_err(errors::transaction_core)?;
+            Ok(account.balance.into())
+        })
+    }
+
+    fn get_delegations(
+        &self,
+        delegator: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Vec<Delegation>> {
+        self.with_state(block_number, move |client, state| {
+            let delegator = delegator.try_address().map_err(errors::core)?;
+            let delegation = CoreDelegation::load_from_state(state, delegator).map_err(errors::transaction_core)?;
+            let network_id = client.network_id();
+            Ok(delegation
+                .iter()
+                .map(|(delegatee, quantity)| Delegation {
+                    delegatee: PlatformAddress::new_v1(network_id, *delegatee),
+                    quantity: (*quantity).into(),
+                })
+                .collect())
+        })
+    }
+
+    fn get_validators(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Vec<StakeValidator>> {
+        self.with_state(block_number, |client, state| {
+            let validators = CurrentValidators::load_from_state(state).map_err(errors::transaction_core)?;
+            let network_id = client.network_id();
+            Ok(validators.iter().map(|validator| StakeValidator::from_core(validator, network_id)).collect())
+        })
+    }
+
+    fn get_candidate(
+        &self,
+        address: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<Candidate>> {
+        self.with_state(block_number, move |client, state| {
+            let address = address.try_address().map_err(errors::core)?;
+            let candidates = Candidates::load_from_state(state).map_err(errors::transaction_core)?;
+            let network_id = client.network_id();
+            Ok(candidates.get_candidate(address).cloned().map(|candidate| Candidate::from_core(candidate, network_id)))
+        })
+    }
+
+    fn get_prisoner(
+        &self,
+        address: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<Prisoner>> {
+        self.with_state(block_number, move |client, state| {
+            let address = address.try_address().map_err(errors::core)?;
+            let jail = Jail::load_from_state(state).map_err(errors::transaction_core)?;
+            let network_id = client.network_id();
+            Ok(jail.get_prisoner(address).cloned().map(|prisoner| Prisoner::from_core(prisoner, network_id)))
+        })
+    }
+
+    fn get_commission(
+        &self,
+        address: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<Commission>> {
+        self.with_state(block_number, move |client, state| {
+            let address = address.try_address().map_err(errors::core)?;
+            let commission = CoreCommission::load_from_state(state, address).map_err(errors::transaction_core)?;
+            let network_id = client.network_id();
+            Ok(commission.map(|commission| Commission {
+                rate: commission.rate,
+                destination: PlatformAddress::new_v1(network_id, commission.destination),
+            }))
+        })
+    }
+
+    fn get_session_key(
+        &self,
+        address: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<SessionKey>> {
+        self.with_state(block_number, move |_, state| {
+            let address = address.try_address().map_err(errors::core)?;
+            let session_key = CoreSessionKey::load_from_state(state, address).map_err(errors::transaction_core)?;
+            Ok(session_key.map(|session_key| SessionKey {
+                pubkey: session_key.pubkey,
+                expires_at: session_key.expires_at,
+            }))
+        })
+    }
+
+    fn get_misses(&self, address: PlatformAddress, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Misses> {
+        self.with_state(block_number, move |_, state| {
+            let address = address.try_address().map_err(errors::core)?;
+            let misses = CoreMisses::load_from_state(state).map_err(errors::transaction_core)?;
+            let record = misses.get(address);
+            Ok(Misses {
+                blocks: misses.blocks(),
+                proposed: record.proposed,
+                missed_proposals: record.missed_proposals,
+                missed_votes: record.missed_votes,
+                rate: record.rate(misses.blocks()),
+            })
+        })
+    }
+
+    fn get_pending_rewards(
+        &self,
+        address: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<PendingRewards> {
+        self.with_state(block_number, move |_, state| {
+            let address = address.try_address().map_err(errors::core)?;
+            let rewards = IntermediateRewards::load_from_state(state).map_err(errors::transaction_core)?;
+            Ok(PendingRewards {
+                current: rewards.current().get(address).cloned().unwrap_or(0).into(),
+                calculated: rewards.calculated().get(address).cloned().unwrap_or(0).into(),
+            })
+        })
+    }
+
+    fn get_locked_deposits(
+        &self,
+        address: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Vec<LockedDeposit>> {
+        self.with_state(block_number, move |_, state| {
+            let address = address.try_address().map_err(errors::core)?;
+            let candidates = Candidates::load_from_state(state).map_err(errors::transaction_core)?;
+            let jail = Jail::load_from_state(state).map_err(errors::transaction_core)?;
+            let deposit = candidates.get_candidate(address).map(LockedDeposit::from_candidate);
+            let jailed_deposit = jail.get_prisoner(address).map(LockedDeposit::from_prisoner);
+            Ok(deposit.into_iter().chain(jailed_deposit).collect())
+        })
+    }
+
+    fn get_history(
+        &self,
+        address: PlatformAddress,
+        from_term: u64,
+        to_term: u64,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Vec<TermHistory>> {
+        self.with_state(block_number, move |client, state| {
+            if from_term > to_term {
+                return Err(Error::invalid_params("fromTerm must not be greater than toTerm"))
+            }
+            if to_term - from_term >= MAX_HISTORY_TERMS {
+                return Err(Error::invalid_params(format!("Cannot read more than {} terms at once", MAX_HISTORY_TERMS)))
+            }
+            let address = address.try_address().map_err(errors::core)?;
+            let network_id = client.network_id();
+            let mut result = Vec::new();
+            for term in from_term..=to_term {
+                let history = History::load_from_state(state, address, term).map_err(errors::transaction_core)?;
+                if history.is_empty() {
+                    continue
+                }
+                result.push(TermHistory {
+                    term,
+                    events: history.into_iter().map(|event| StakeEvent::from_core(event, network_id)).collect(),
+                });
+            }
+            Ok(result)
+        })
+    }
+
+    fn delegate(
+        &self,
+        delegatee: PlatformAddress,
+        quantity: Uint,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction> {
+        let action = StakeAction::DelegateCCS {
+            address: delegatee.try_into_address().map_err(errors::core)?,
+            quantity: quantity.into(),
+        };
+        self.compose(action, fee, seq, signer, passphrase)
+    }
+
+    fn revoke(
+        &self,
+        delegatee: PlatformAddress,
+        quantity: Uint,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction> {
+        let action = StakeAction::Revoke {
+            address: delegatee.try_into_address().map_err(errors::core)?,
+            quantity: quantity.into(),
+        };
+        self.compose(action, fee, seq, signer, passphrase)
+    }
+
+    fn redelegate(
+        &self,
+        prev_delegatee: PlatformAddress,
+        next_delegatee: PlatformAddress,
+        quantity: Uint,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction> {
+        let action = StakeAction::Redelegate {
+            prev_delegatee: prev_delegatee.try_into_address().map_err(errors::core)?,
+            next_delegatee: next_delegatee.try_into_address().map_err(errors::core)?,
+            quantity: quantity.into(),
+        };
+        self.compose(action, fee, seq, signer, passphrase)
+    }
+
+    fn set_commission(
+        &self,
+        rate: u64,
+        destination: PlatformAddress,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction> {
+        let action = StakeAction::SetCommission {
+            rate,
+            destination: destination.try_into_address().map_err(errors::core)?,
+        };
+        self.compose(action, fee, seq, signer, passphrase)
+    }
+
+    fn set_session_key(
+        &self,
+        pubkey: Public,
+        expires_at: u64,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction> {
+        let action = StakeAction::SetSessionKey {
+            pubkey,
+            expires_at,
+        };
+        self.compose(action, fee, seq, signer, passphrase)
+    }
+}
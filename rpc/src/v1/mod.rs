@@ -14,10 +14,10 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-mod errors;
+pub(crate) mod errors;
 mod impls;
 mod traits;
-mod types;
+pub mod types;
 
 pub use self::impls::*;
 pub use self::traits::*;
@@ -15,7 +15,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use super::super::types::{SendTransactionResult, UnsignedTransaction};
-use ckey::{Password, PlatformAddress, Signature};
+use ckey::{Password, PlatformAddress, Signature, TypedData};
 use jsonrpc_core::Result;
 use primitives::H256;
 
@@ -41,6 +41,15 @@ pub trait Account {
     #[rpc(name = "account_sign")]
     fn sign(&self, message_digest: H256, address: PlatformAddress, passphrase: Option<Password>) -> Result<Signature>;
 
+    /// Calculates the account's signature for a given typed data
+    #[rpc(name = "account_signTypedData")]
+    fn sign_typed_data(
+        &self,
+        typed_data: TypedData,
+        address: PlatformAddress,
+        passphrase: Option<Password>,
+    ) -> Result<Signature>;
+
     /// Sends a transaction with a signature of the account
     #[rpc(name = "account_sendTransaction")]
     fn send_transaction(
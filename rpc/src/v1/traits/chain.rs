@@ -14,12 +14,16 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use super::super::types::{Block, BlockNumberAndHash, Transaction, UnsignedTransaction};
+use super::super::types::{
+    AccountInfo, Block, BlockExecutionSummary, BlockNumberAndHash, BlockNumberOrTag, SeqInfo, Transaction,
+    TransactionExecution, UnsignedTransaction,
+};
+use cjson::bytes::Bytes;
 use cjson::scheme::Params;
 use cjson::uint::Uint;
-use ckey::{NetworkId, PlatformAddress, Public};
+use ckey::{NetworkId, PlatformAddress, Public, Signature};
 use ctypes::{BlockHash, BlockNumber, ShardId, Tracker, TxHash};
-use jsonrpc_core::Result;
+use jsonrpc_core::{BoxFuture, Result};
 use primitives::H256;
 
 #[rpc(server)]
@@ -32,6 +36,18 @@ pub trait Chain {
     #[rpc(name = "chain_getTransactionSigner")]
     fn get_transaction_signer(&self, transaction_hash: TxHash) -> Result<Option<PlatformAddress>>;
 
+    /// Gets the sponsor paying the fee of transaction with given hash.
+    #[rpc(name = "chain_getTransactionFeePayer")]
+    fn get_transaction_fee_payer(&self, transaction_hash: TxHash) -> Result<Option<PlatformAddress>>;
+
+    /// Gets the message that the sponsor signs to pay the fee of the signed transaction.
+    #[rpc(name = "chain_getFeePayerMessage")]
+    fn get_fee_payer_message(&self, signed_transaction: Bytes) -> Result<H256>;
+
+    /// Attaches the signature of the sponsor to the signed transaction.
+    #[rpc(name = "chain_attachFeePayer")]
+    fn attach_fee_payer(&self, signed_transaction: Bytes, fee_payer_signature: Signature) -> Result<Bytes>;
+
     /// Query whether the chain has the transaction with given transaction hash.
     #[rpc(name = "chain_containsTransaction")]
     fn contains_transaction(&self, transaction_hash: TxHash) -> Result<bool>;
@@ -45,19 +61,59 @@ pub trait Chain {
 
     /// Gets seq with given account.
     #[rpc(name = "chain_getSeq")]
-    fn get_seq(&self, address: PlatformAddress, block_number: Option<u64>) -> Result<Option<u64>>;
+    fn get_seq(&self, address: PlatformAddress, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<u64>>;
+
+    /// Gets the latest seq of the account with the seqs used by the pooled transactions.
+    #[rpc(name = "chain_getSeqInfo")]
+    fn get_seq_info(&self, address: PlatformAddress) -> BoxFuture<SeqInfo>;
 
     /// Gets balance with given account.
     #[rpc(name = "chain_getBalance")]
-    fn get_balance(&self, address: PlatformAddress, block_number: Option<u64>) -> Result<Option<Uint>>;
+    fn get_balance(&self, address: PlatformAddress, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<Uint>>;
+
+    /// Gets the balance of given account which cannot be spent yet.
+    #[rpc(name = "chain_getLockedBalance")]
+    fn get_locked_balance(
+        &self,
+        address: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<Uint>>;
+
+    /// Gets the balances, the seqs and the regular keys of the accounts from the same state.
+    #[rpc(name = "chain_getAccountsInfo")]
+    fn get_accounts_info(
+        &self,
+        addresses: Vec<PlatformAddress>,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<Vec<AccountInfo>>>;
+
+    /// Gets the owner of the registered name.
+    #[rpc(name = "chain_resolveName")]
+    fn resolve_name(&self, name: String, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<PlatformAddress>>;
+
+    /// Gets the name that the account chose to be known by.
+    #[rpc(name = "chain_getNameOf")]
+    fn get_name_of(
+        &self,
+        address: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<String>>;
 
     /// Gets regular key with given account
     #[rpc(name = "chain_getRegularKey")]
-    fn get_regular_key(&self, address: PlatformAddress, block_number: Option<u64>) -> Result<Option<Public>>;
+    fn get_regular_key(
+        &self,
+        address: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<Public>>;
 
     /// Gets the owner of given regular key.
     #[rpc(name = "chain_getRegularKeyOwner")]
-    fn get_regular_key_owner(&self, public: Public, block_number: Option<u64>) -> Result<Option<PlatformAddress>>;
+    fn get_regular_key_owner(
+        &self,
+        public: Public,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<PlatformAddress>>;
 
     /// Gets the genesis accounts
     #[rpc(name = "chain_getGenesisAccounts")]
@@ -65,23 +121,35 @@ pub trait Chain {
 
     /// Gets the number of shards
     #[rpc(name = "chain_getNumberOfShards")]
-    fn get_number_of_shards(&self, block_number: Option<u64>) -> Result<Option<ShardId>>;
+    fn get_number_of_shards(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<ShardId>>;
 
     /// Gets shard id
     #[rpc(name = "chain_getShardIdByHash")]
-    fn get_shard_id_by_hash(&self, create_shard_tx_hash: TxHash, block_number: Option<u64>) -> Result<Option<ShardId>>;
+    fn get_shard_id_by_hash(
+        &self,
+        create_shard_tx_hash: TxHash,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<ShardId>>;
 
     /// Gets shard root
     #[rpc(name = "chain_getShardRoot")]
-    fn get_shard_root(&self, shard_id: ShardId, block_number: Option<u64>) -> Result<Option<H256>>;
+    fn get_shard_root(&self, shard_id: ShardId, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<H256>>;
 
     /// Gets shard owners
     #[rpc(name = "chain_getShardOwners")]
-    fn get_shard_owners(&self, shard_id: ShardId, block_number: Option<u64>) -> Result<Option<Vec<PlatformAddress>>>;
+    fn get_shard_owners(
+        &self,
+        shard_id: ShardId,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<Vec<PlatformAddress>>>;
 
     /// Gets shard users
     #[rpc(name = "chain_getShardUsers")]
-    fn get_shard_users(&self, shard_id: ShardId, block_number: Option<u64>) -> Result<Option<Vec<PlatformAddress>>>;
+    fn get_shard_users(
+        &self,
+        shard_id: ShardId,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<Vec<PlatformAddress>>>;
 
     /// Gets number of best block.
     #[rpc(name = "chain_getBestBlockNumber")]
@@ -93,15 +161,19 @@ pub trait Chain {
 
     /// Gets the hash of the block with given number.
     #[rpc(name = "chain_getBlockHash")]
-    fn get_block_hash(&self, block_number: u64) -> Result<Option<BlockHash>>;
+    fn get_block_hash(&self, block_number: BlockNumberOrTag) -> Result<Option<BlockHash>>;
 
     /// Gets block with given number.
     #[rpc(name = "chain_getBlockByNumber")]
-    fn get_block_by_number(&self, block_number: u64) -> Result<Option<Block>>;
+    fn get_block_by_number(&self, block_number: BlockNumberOrTag) -> BoxFuture<Option<Block>>;
 
     /// Gets block with given hash.
     #[rpc(name = "chain_getBlockByHash")]
-    fn get_block_by_hash(&self, block_hash: BlockHash) -> Result<Option<Block>>;
+    fn get_block_by_hash(&self, block_hash: BlockHash) -> BoxFuture<Option<Block>>;
+
+    /// Gets the execution summary of each transaction in the block.
+    #[rpc(name = "chain_getBlockExecutionSummary")]
+    fn get_block_execution_summary(&self, block_number: BlockNumberOrTag) -> BoxFuture<Option<BlockExecutionSummary>>;
 
     ///Gets the count of transactions in a block with given hash.
     #[rpc(name = "chain_getBlockTransactionCountByHash")]
@@ -109,11 +181,15 @@ pub trait Chain {
 
     ///Gets the minimum transaction fee of the given name.
     #[rpc(name = "chain_getMinTransactionFee")]
-    fn get_min_transaction_fee(&self, action_type: String, block_number: Option<u64>) -> Result<Option<u64>>;
+    fn get_min_transaction_fee(
+        &self,
+        action_type: String,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<u64>>;
 
     /// Gets the mining given block number
     #[rpc(name = "chain_getMiningReward")]
-    fn get_mining_reward(&self, block_number: u64) -> Result<Option<u64>>;
+    fn get_mining_reward(&self, block_number: BlockNumberOrTag) -> Result<Option<u64>>;
 
     /// Return the network id that is used in this chain.
     #[rpc(name = "chain_getNetworkId")]
@@ -121,21 +197,30 @@ pub trait Chain {
 
     /// Return common params at given block number
     #[rpc(name = "chain_getCommonParams")]
-    fn get_common_params(&self, block_number: Option<u64>) -> Result<Option<Params>>;
+    fn get_common_params(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<Params>>;
 
     /// Return the current term id at given block number
     #[rpc(name = "chain_getTermMetadata")]
-    fn get_term_metadata(&self, block_number: Option<u64>) -> Result<Option<(u64, u64)>>;
+    fn get_term_metadata(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<(u64, u64)>>;
 
     /// Return the current metadata seq at given block number
     #[rpc(name = "chain_getMetadataSeq")]
-    fn get_metadata_seq(&self, block_number: Option<u64>) -> Result<Option<u64>>;
+    fn get_metadata_seq(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<u64>>;
 
     /// Return the valid block authors
     #[rpc(name = "chain_getPossibleAuthors")]
-    fn get_possible_authors(&self, block_number: Option<u64>) -> Result<Option<Vec<PlatformAddress>>>;
+    fn get_possible_authors(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<Vec<PlatformAddress>>>;
 
     /// Execute Transactions
     #[rpc(name = "chain_executeTransaction")]
-    fn execute_transaction(&self, tx: UnsignedTransaction, sender: PlatformAddress) -> Result<Option<String>>;
+    fn execute_transaction(&self, tx: UnsignedTransaction, sender: PlatformAddress) -> BoxFuture<Option<String>>;
+
+    /// Executes the transaction on the state of the given block without persisting the result
+    #[rpc(name = "chain_dryRunTransaction")]
+    fn dry_run_transaction(
+        &self,
+        tx: UnsignedTransaction,
+        block_number: Option<BlockNumberOrTag>,
+        signer_public: Public,
+    ) -> BoxFuture<Option<TransactionExecution>>;
 }
@@ -0,0 +1,29 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::super::types::SignedCheckpoint;
+use jsonrpc_core::Result;
+
+#[rpc(server)]
+pub trait Checkpoint {
+    /// Gets the latest checkpoint with the signatures collected from its validators.
+    #[rpc(name = "checkpoint_getLatest")]
+    fn get_latest(&self) -> Result<Option<SignedCheckpoint>>;
+
+    /// Gets the checkpoint at the block if it's one of the recent checkpoints kept by the node.
+    #[rpc(name = "checkpoint_getCheckpoint")]
+    fn get_checkpoint(&self, block_number: u64) -> Result<Option<SignedCheckpoint>>;
+}
@@ -14,11 +14,12 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use super::super::types::TPSTestSetting;
+use super::super::types::{BlockNumberOrTag, ChaosSetting, TPSTestReport, TPSTestSetting};
 use cjson::bytes::Bytes;
 use ctypes::BlockHash;
-use jsonrpc_core::Result;
+use jsonrpc_core::{BoxFuture, Result};
 use primitives::H256;
+use serde_json::Value;
 use std::net::SocketAddr;
 
 #[rpc(server)]
@@ -49,4 +50,40 @@ pub trait Devel {
 
     #[rpc(name = "devel_testTPS")]
     fn test_tps(&self, setting: TPSTestSetting) -> Result<f64>;
+
+    #[rpc(name = "devel_runTPSTest")]
+    fn run_tps_test(&self, setting: TPSTestSetting) -> Result<TPSTestReport>;
+
+    #[rpc(name = "devel_exportScheme")]
+    fn export_scheme(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<Value>>;
+
+    #[rpc(name = "devel_createFork")]
+    fn create_fork(&self, parent_hash: BlockHash) -> Result<BlockHash>;
+
+    #[rpc(name = "devel_reorgTo")]
+    fn reorg_to(&self, hash: BlockHash) -> Result<()>;
+
+    #[rpc(name = "devel_setChaos")]
+    fn set_chaos(&self, setting: ChaosSetting) -> Result<()>;
+
+    #[rpc(name = "devel_getChaos")]
+    fn get_chaos(&self) -> Result<ChaosSetting>;
+
+    #[rpc(name = "devel_setMaintenanceMode")]
+    fn set_maintenance_mode(&self, on: bool) -> Result<()>;
+
+    #[rpc(name = "devel_getMaintenanceMode")]
+    fn get_maintenance_mode(&self) -> Result<bool>;
+
+    #[rpc(name = "devel_getBlockWitness")]
+    fn get_block_witness(&self, block_number: BlockNumberOrTag) -> Result<Option<Vec<Bytes>>>;
+
+    #[rpc(name = "devel_verifyBlockWitness")]
+    fn verify_block_witness(&self, block_number: BlockNumberOrTag, witness: Vec<Bytes>) -> Result<Option<bool>>;
+
+    #[rpc(name = "devel_enableApi")]
+    fn enable_api(&self, namespace: String) -> Result<()>;
+
+    #[rpc(name = "devel_disableApi")]
+    fn disable_api(&self, namespace: String) -> Result<()>;
 }
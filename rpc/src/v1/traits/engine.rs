@@ -14,15 +14,17 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use super::super::types::{BlockNumberOrTag, ClockDrift, ComposedTransaction, Duties, TermInfo};
 use cjson::bytes::{Bytes, WithoutPrefix};
-use ckey::PlatformAddress;
-use jsonrpc_core::Result;
+use cjson::uint::Uint;
+use ckey::{Password, PlatformAddress};
+use jsonrpc_core::{BoxFuture, Result};
 
 #[rpc(server)]
 pub trait Engine {
     /// Gets the reward of the given block number
     #[rpc(name = "engine_getBlockReward")]
-    fn get_block_reward(&self, block_number: u64) -> Result<u64>;
+    fn get_block_reward(&self, block_number: BlockNumberOrTag) -> Result<u64>;
 
     /// Gets coinbase's account id
     #[rpc(name = "engine_getCoinbase")]
@@ -32,12 +34,34 @@ pub trait Engine {
     #[rpc(name = "engine_getRecommendedConfirmation")]
     fn get_recommended_confirmation(&self) -> Result<u32>;
 
+    /// Gets the drift of the local clock from the timestamps of the recent proposals of the other validators
+    #[rpc(name = "engine_getClockDrift")]
+    fn get_clock_drift(&self) -> Result<Option<ClockDrift>>;
+
+    /// Gets the blocks that the signer proposes among the next blocks, and the proposal slots it missed
+    #[rpc(name = "engine_getUpcomingDuties")]
+    fn get_upcoming_duties(&self, count: Option<u64>) -> Result<Option<Duties>>;
+
     /// Gets custom action data for given custom action handler id and rlp encoded key.
     #[rpc(name = "engine_getCustomActionData")]
     fn get_custom_action_data(
         &self,
         handler_id: u64,
         key_fragment: Bytes,
-        block_number: Option<u64>,
+        block_number: Option<BlockNumberOrTag>,
     ) -> Result<Option<WithoutPrefix<Bytes>>>;
+
+    /// Gets the term of the given block, the banned and jailed accounts, and the validators of the next term
+    #[rpc(name = "engine_getTermInfo")]
+    fn get_term_info(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<TermInfo>>;
+
+    /// Composes a transaction returning the jailed signer to the candidates. Signs and sends it if the signer is given.
+    #[rpc(name = "engine_unjail")]
+    fn unjail(
+        &self,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction>;
 }
@@ -0,0 +1,59 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::super::types::{BlockNumberOrTag, ComposedTransaction, Proposal, VoteOption};
+use cjson::uint::Uint;
+use ckey::{Password, PlatformAddress};
+use jsonrpc_core::{BoxFuture, Result};
+
+#[rpc(server)]
+pub trait Governance {
+    /// Gets the number of the proposals ever created. The ids of the proposals are less than it.
+    #[rpc(name = "gov_getProposalCount")]
+    fn get_proposal_count(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<u64>;
+
+    /// Gets the proposal with its votes, and the tally if the deadline has passed
+    #[rpc(name = "gov_getProposal")]
+    fn get_proposal(&self, id: u64, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<Proposal>>;
+
+    /// Gets the proposals that are not tallied yet
+    #[rpc(name = "gov_getOpenProposals")]
+    fn get_open_proposals(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Vec<Proposal>>;
+
+    /// Composes a transaction creating a proposal. Signs and sends it if the signer is given.
+    #[rpc(name = "gov_propose")]
+    fn propose(
+        &self,
+        text: String,
+        deadline: u64,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction>;
+
+    /// Composes a transaction voting on the proposal. Signs and sends it if the signer is given.
+    #[rpc(name = "gov_vote")]
+    fn vote(
+        &self,
+        id: u64,
+        option: VoteOption,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction>;
+}
@@ -0,0 +1,38 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::super::types::{BlockNumberOrTag, Commit, CommitmentProof, ConsensusState};
+use jsonrpc_core::Result;
+use primitives::H256;
+
+#[rpc(server)]
+pub trait Ibc {
+    /// Gets the header summary and the validators signing the commit of the block.
+    #[rpc(name = "ibc_getConsensusState")]
+    fn get_consensus_state(&self, block_number: Option<BlockNumberOrTag>) -> Result<Option<ConsensusState>>;
+
+    /// Gets the precommits finalizing the block, which exist after its child is imported.
+    #[rpc(name = "ibc_getCommit")]
+    fn get_commit(&self, block_number: BlockNumberOrTag) -> Result<Option<Commit>>;
+
+    /// Gets the proof of the action data at the key against the state root of the block.
+    #[rpc(name = "ibc_getCommitmentProof")]
+    fn get_commitment_proof(
+        &self,
+        key: H256,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> Result<Option<CommitmentProof>>;
+}
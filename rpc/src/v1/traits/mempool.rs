@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use super::super::types::{MemPoolMinFees, PendingTransactions};
+use super::super::types::{MemPoolContent, MemPoolMinFees, PendingTransactions, Transaction};
 use cjson::bytes::Bytes;
 use ckey::PlatformAddress;
 use ctypes::{Tracker, TxHash};
@@ -38,6 +38,10 @@ pub trait Mempool {
     #[rpc(name = "mempool_deleteAllPendingTransactions")]
     fn delete_all_pending_transactions(&self) -> Result<()>;
 
+    /// Removes the pending transaction of the local account with the given seq.
+    #[rpc(name = "mempool_cancelTransaction")]
+    fn cancel_transaction(&self, address: PlatformAddress, seq: u64) -> Result<Option<TxHash>>;
+
     /// Gets transactions in the current mem pool.
     #[rpc(name = "mempool_getPendingTransactions")]
     fn get_pending_transactions(&self, from: Option<u64>, to: Option<u64>) -> Result<PendingTransactions>;
@@ -46,6 +50,14 @@ pub trait Mempool {
     #[rpc(name = "mempool_getPendingTransactionsCount")]
     fn get_pending_transactions_count(&self, from: Option<u64>, to: Option<u64>) -> Result<usize>;
 
+    /// Gets the transactions waiting for their activation block in the mem pool.
+    #[rpc(name = "mempool_getScheduledTransactions")]
+    fn get_scheduled_transactions(&self) -> Result<Vec<Transaction>>;
+
+    /// Gets all the transactions in the mem pool with where they came from.
+    #[rpc(name = "mempool_getContent")]
+    fn get_content(&self) -> Result<MemPoolContent>;
+
     #[rpc(name = "mempool_getBannedAccounts")]
     fn get_banned_accounts(&self) -> Result<Vec<PlatformAddress>>;
 
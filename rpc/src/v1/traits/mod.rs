@@ -16,16 +16,30 @@
 
 mod account;
 mod chain;
+mod checkpoint;
 mod devel;
+mod discovery;
 mod engine;
+mod governance;
+mod ibc;
 mod mempool;
 mod net;
+mod node;
+mod shard;
 mod snapshot;
+mod stake;
 
 pub use self::account::Account;
 pub use self::chain::Chain;
+pub use self::checkpoint::Checkpoint;
 pub use self::devel::Devel;
+pub use self::discovery::Discovery;
 pub use self::engine::Engine;
+pub use self::governance::Governance;
+pub use self::ibc::Ibc;
 pub use self::mempool::Mempool;
 pub use self::net::Net;
+pub use self::node::Node;
+pub use self::shard::Shard;
 pub use self::snapshot::Snapshot;
+pub use self::stake::Stake;
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use super::super::types::FilterStatus;
+use super::super::types::{FilterStatus, TrustedPeers};
 use cidr::IpCidr;
 use ckey::Public;
 use jsonrpc_core::Result;
@@ -77,6 +77,15 @@ pub trait Net {
     #[rpc(name = "net_getBlacklist")]
     fn get_blacklist(&self) -> Result<FilterStatus>;
 
+    #[rpc(name = "net_addTrustedPeer")]
+    fn add_trusted_peer(&self, addr: IpAddr, port: u16) -> Result<()>;
+
+    #[rpc(name = "net_removeTrustedPeer")]
+    fn remove_trusted_peer(&self, addr: IpAddr, port: u16) -> Result<()>;
+
+    #[rpc(name = "net_getTrustedPeers")]
+    fn get_trusted_peers(&self) -> Result<TrustedPeers>;
+
     #[rpc(name = "net_recentNetworkUsage")]
     fn recent_network_usage(&self) -> Result<HashMap<String, usize>>;
 }
@@ -0,0 +1,84 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::super::types::{BlockNumberOrTag, ComposedTransaction, ShardInfo, ShardProof};
+use cjson::uint::Uint;
+use ckey::{Password, PlatformAddress};
+use ctypes::ShardId;
+use jsonrpc_core::{BoxFuture, Result};
+use primitives::H256;
+
+#[rpc(server)]
+pub trait Shard {
+    /// Composes a transaction creating a shard. Signs and sends it if the signer is given.
+    #[rpc(name = "shard_create")]
+    fn create(
+        &self,
+        users: Vec<PlatformAddress>,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction>;
+
+    /// Composes a transaction changing the owners of the shard. Signs and sends it if the signer is given.
+    #[rpc(name = "shard_setOwners")]
+    fn set_owners(
+        &self,
+        shard_id: ShardId,
+        owners: Vec<PlatformAddress>,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction>;
+
+    /// Composes a transaction changing the users of the shard. Signs and sends it if the signer is given.
+    #[rpc(name = "shard_setUsers")]
+    fn set_users(
+        &self,
+        shard_id: ShardId,
+        users: Vec<PlatformAddress>,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction>;
+
+    /// Composes a transaction terminating the shard. Signs and sends it if the signer is given.
+    #[rpc(name = "shard_terminate")]
+    fn terminate(
+        &self,
+        shard_id: ShardId,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction>;
+
+    /// Gets the root, owners and users of the shard
+    #[rpc(name = "shard_getInfo")]
+    fn get_info(&self, shard_id: ShardId, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Option<ShardInfo>>;
+
+    /// Gets the proof of the value at the key in the shard against the state root
+    #[rpc(name = "shard_getProof")]
+    fn get_proof(
+        &self,
+        shard_id: ShardId,
+        key: H256,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<ShardProof>>;
+}
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use super::super::types::BlockNumberAndHash;
+use super::super::types::{BlockNumberAndHash, PublishedSnapshot};
 use jsonrpc_core::Result;
 
 #[rpc(server)]
@@ -22,4 +22,8 @@ pub trait Snapshot {
     /// Gets list of block numbers and block hashes of the snapshots.
     #[rpc(name = "snapshot_getList")]
     fn get_snapshot_list(&self) -> Result<Vec<BlockNumberAndHash>>;
+
+    /// Gets the snapshots published to S3 or IPFS and where they are.
+    #[rpc(name = "snapshot_getPublished")]
+    fn get_published_snapshots(&self) -> Result<Vec<PublishedSnapshot>>;
 }
@@ -0,0 +1,166 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::super::types::{
+    BlockNumberOrTag, Candidate, Commission, ComposedTransaction, Delegation, LockedDeposit, Misses, PendingRewards,
+    Prisoner, SessionKey, StakeValidator, TermHistory,
+};
+use cjson::uint::Uint;
+use ckey::{Password, PlatformAddress, Public};
+use jsonrpc_core::{BoxFuture, Result};
+
+#[rpc(server)]
+pub trait Stake {
+    /// Gets the undelegated stake balance of the account
+    #[rpc(name = "stake_getBalance")]
+    fn get_balance(&self, address: PlatformAddress, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Uint>;
+
+    /// Gets the stakes delegated by the account
+    #[rpc(name = "stake_getDelegations")]
+    fn get_delegations(
+        &self,
+        delegator: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Vec<Delegation>>;
+
+    /// Gets the validators of the current term with their delegations and deposits
+    #[rpc(name = "stake_getValidators")]
+    fn get_validators(&self, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Vec<StakeValidator>>;
+
+    /// Gets the deposit and the nomination of the candidate
+    #[rpc(name = "stake_getCandidate")]
+    fn get_candidate(
+        &self,
+        address: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<Candidate>>;
+
+    /// Gets the deposit and the release schedule of the jailed account
+    #[rpc(name = "stake_getPrisoner")]
+    fn get_prisoner(
+        &self,
+        address: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<Prisoner>>;
+
+    /// Gets the commission of the validator. `null` means the validator takes the whole rewards.
+    #[rpc(name = "stake_getCommission")]
+    fn get_commission(
+        &self,
+        address: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<Commission>>;
+
+    /// Gets the session key signing the consensus messages for the validator
+    #[rpc(name = "stake_getSessionKey")]
+    fn get_session_key(
+        &self,
+        address: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Option<SessionKey>>;
+
+    /// Gets the proposals and the votes that the validator missed in the current term
+    #[rpc(name = "stake_getMisses")]
+    fn get_misses(&self, address: PlatformAddress, block_number: Option<BlockNumberOrTag>) -> BoxFuture<Misses>;
+
+    /// Gets the block rewards of the author that are not paid yet
+    #[rpc(name = "stake_getPendingRewards")]
+    fn get_pending_rewards(
+        &self,
+        address: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<PendingRewards>;
+
+    /// Gets the deposits of the account that are locked until the end of a term
+    #[rpc(name = "stake_getLockedDeposits")]
+    fn get_locked_deposits(
+        &self,
+        address: PlatformAddress,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Vec<LockedDeposit>>;
+
+    /// Gets the delegations, the revocations and the rewards of the account from `from_term` to `to_term`
+    #[rpc(name = "stake_getHistory")]
+    fn get_history(
+        &self,
+        address: PlatformAddress,
+        from_term: u64,
+        to_term: u64,
+        block_number: Option<BlockNumberOrTag>,
+    ) -> BoxFuture<Vec<TermHistory>>;
+
+    /// Composes a transaction delegating stakes. Signs and sends it if the signer is given.
+    #[rpc(name = "stake_delegate")]
+    fn delegate(
+        &self,
+        delegatee: PlatformAddress,
+        quantity: Uint,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction>;
+
+    /// Composes a transaction revoking delegated stakes. Signs and sends it if the signer is given.
+    #[rpc(name = "stake_revoke")]
+    fn revoke(
+        &self,
+        delegatee: PlatformAddress,
+        quantity: Uint,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction>;
+
+    /// Composes a transaction moving delegated stakes to another delegatee. Signs and sends it if the signer is given.
+    #[rpc(name = "stake_redelegate")]
+    fn redelegate(
+        &self,
+        prev_delegatee: PlatformAddress,
+        next_delegatee: PlatformAddress,
+        quantity: Uint,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction>;
+
+    /// Composes a transaction setting the commission of the signer. Signs and sends it if the signer is given.
+    #[rpc(name = "stake_setCommission")]
+    fn set_commission(
+        &self,
+        rate: u64,
+        destination: PlatformAddress,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction>;
+
+    /// Composes a transaction setting the session key of the signer. Signs and sends it if the signer is given.
+    /// The validator signs it without the signer to keep its key offline.
+    #[rpc(name = "stake_setSessionKey")]
+    fn set_session_key(
+        &self,
+        pubkey: Public,
+        expires_at: u64,
+        fee: Uint,
+        seq: Option<u64>,
+        signer: Option<PlatformAddress>,
+        passphrase: Option<Password>,
+    ) -> Result<ComposedTransaction>;
+}
@@ -22,7 +22,7 @@ use ctypes::{ShardId, Tracker};
 use primitives::Bytes;
 use std::convert::TryFrom;
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase", tag = "type")]
 pub enum Action {
     Pay {
@@ -46,6 +46,10 @@ pub enum Action {
         users: Vec<PlatformAddress>,
     },
     #[serde(rename_all = "camelCase")]
+    TerminateShard {
+        shard_id: ShardId,
+    },
+    #[serde(rename_all = "camelCase")]
     Custom {
         handler_id: Uint,
         bytes: Bytes,
@@ -55,9 +59,17 @@ pub enum Action {
         shard_id: ShardId,
         content: String,
     },
+    Batch {
+        actions: Vec<Action>,
+    },
+    Scheduled {
+        activation: u64,
+        action: Box<Action>,
+    },
+    BurnSeq,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase", tag = "type")]
 pub enum ActionWithTracker {
     Pay {
@@ -81,6 +93,10 @@ pub enum ActionWithTracker {
         users: Vec<PlatformAddress>,
     },
     #[serde(rename_all = "camelCase")]
+    TerminateShard {
+        shard_id: ShardId,
+    },
+    #[serde(rename_all = "camelCase")]
     Custom {
         handler_id: Uint,
         bytes: Bytes,
@@ -91,6 +107,14 @@ pub enum ActionWithTracker {
         content: String,
         tracker: Tracker,
     },
+    Batch {
+        actions: Vec<ActionWithTracker>,
+    },
+    Scheduled {
+        activation: u64,
+        action: Box<ActionWithTracker>,
+    },
+    BurnSeq,
 }
 
 impl ActionWithTracker {
@@ -131,6 +155,11 @@ impl ActionWithTracker {
                 shard_id,
                 users: users.into_iter().map(|user| PlatformAddress::new_v1(network_id, user)).collect(),
             },
+            ActionType::TerminateShard {
+                shard_id,
+            } => ActionWithTracker::TerminateShard {
+                shard_id,
+            },
             ActionType::Custom {
                 handler_id,
                 bytes,
@@ -148,6 +177,19 @@ impl ActionWithTracker {
                 content,
                 tracker: tracker.unwrap(),
             },
+            ActionType::Batch {
+                actions,
+            } => ActionWithTracker::Batch {
+                actions: actions.into_iter().map(|action| ActionWithTracker::from_core(action, network_id)).collect(),
+            },
+            ActionType::Scheduled {
+                activation,
+                action,
+            } => ActionWithTracker::Scheduled {
+                activation,
+                action: Box::new(ActionWithTracker::from_core(*action, network_id)),
+            },
+            ActionType::BurnSeq => ActionWithTracker::BurnSeq,
         }
     }
 }
@@ -196,6 +238,11 @@ impl TryFrom<Action> for ActionType {
                     users: users?,
                 }
             }
+            Action::TerminateShard {
+                shard_id,
+            } => ActionType::TerminateShard {
+                shard_id,
+            },
             Action::Custom {
                 handler_id,
                 bytes,
@@ -212,6 +259,19 @@ impl TryFrom<Action> for ActionType {
                 shard_id,
                 content,
             },
+            Action::Batch {
+                actions,
+            } => ActionType::Batch {
+                actions: actions.into_iter().map(ActionType::try_from).collect::<Result<_, _>>()?,
+            },
+            Action::Scheduled {
+                activation,
+                action,
+            } => ActionType::Scheduled {
+                activation,
+                action: Box::new(ActionType::try_from(*action)?),
+            },
+            Action::BurnSeq => ActionType::BurnSeq,
         })
     }
 }
@@ -20,24 +20,24 @@ use ckey::{NetworkId, PlatformAddress};
 use ctypes::{BlockHash, BlockNumber};
 use primitives::{H256, U256};
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Block {
-    parent_hash: BlockHash,
-    timestamp: u64,
-    number: u64,
-    author: PlatformAddress,
+    pub parent_hash: BlockHash,
+    pub timestamp: u64,
+    pub number: u64,
+    pub author: PlatformAddress,
 
-    extra_data: Vec<u8>,
+    pub extra_data: Vec<u8>,
 
-    transactions_root: H256,
-    state_root: H256,
+    pub transactions_root: H256,
+    pub state_root: H256,
 
-    score: U256,
-    seal: Vec<Vec<u8>>,
+    pub score: U256,
+    pub seal: Vec<Vec<u8>>,
 
-    hash: BlockHash,
-    transactions: Vec<Transaction>,
+    pub hash: BlockHash,
+    pub transactions: Vec<Transaction>,
 }
 
 impl Block {
@@ -72,9 +72,18 @@ impl Block {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BlockNumberAndHash {
     pub number: BlockNumber,
     pub hash: BlockHash,
 }
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishedSnapshot {
+    pub number: BlockNumber,
+    pub hash: BlockHash,
+    /// `s3://<bucket>/<prefix>` or `ipfs://<cid>`
+    pub location: String,
+}
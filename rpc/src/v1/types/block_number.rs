@@ -0,0 +1,197 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ccore::{BlockChainTrait, BlockId, EngineInfo, StateInfo, StateOrBlock};
+use cstate::{TopLevelState, TopStateView};
+use ctypes::BlockNumber;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// A block given by its number or by a tag that is resolved when the call is processed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlockNumberOrTag {
+    Number(BlockNumber),
+    /// The best block.
+    Latest,
+    /// The best block that is not expected to be reverted, `engine_getRecommendedConfirmation` blocks below the best block.
+    Finalized,
+    /// The parent of the best block.
+    Safe,
+    /// The block that would be built on the best block from the ready transactions in the mem pool.
+    /// It has a state but no header.
+    Pending,
+}
+
+impl Default for BlockNumberOrTag {
+    fn default() -> Self {
+        BlockNumberOrTag::Latest
+    }
+}
+
+impl BlockNumberOrTag {
+    fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "latest" => Some(BlockNumberOrTag::Latest),
+            "finalized" => Some(BlockNumberOrTag::Finalized),
+            "safe" => Some(BlockNumberOrTag::Safe),
+            "pending" => Some(BlockNumberOrTag::Pending),
+            _ => None,
+        }
+    }
+
+    /// The number of the block. The pending block is numbered after the best block.
+    pub fn number<C: BlockChainTrait + EngineInfo>(self, client: &C) -> BlockNumber {
+        let best_block_number = client.chain_info().best_block_number;
+        match self {
+            BlockNumberOrTag::Number(number) => number,
+            BlockNumberOrTag::Latest => best_block_number,
+            BlockNumberOrTag::Finalized => {
+                best_block_number.saturating_sub(u64::from(client.recommended_confirmation()))
+            }
+            BlockNumberOrTag::Safe => best_block_number.saturating_sub(1),
+            BlockNumberOrTag::Pending => best_block_number + 1,
+        }
+    }
+
+    /// The id of the block, or None for the pending block.
+    pub fn block_id<C: BlockChainTrait + EngineInfo>(self, client: &C) -> Option<BlockId> {
+        match self {
+            BlockNumberOrTag::Latest => Some(BlockId::Latest),
+            BlockNumberOrTag::Pending => None,
+            _ => Some(BlockId::Number(self.number(client))),
+        }
+    }
+
+    /// The state of the block. The state of the pending block is built on the mem pool.
+    pub fn state<C: BlockChainTrait + EngineInfo + StateInfo>(self, client: &C) -> Option<TopLevelState> {
+        match self.block_id(client) {
+            Some(block_id) => client.state_at(block_id),
+            None => client.pending_state(),
+        }
+    }
+
+    /// Same as `state`, but leaves loading the state of a sealed block to the client.
+    pub fn state_or_block<C: BlockChainTrait + EngineInfo + StateInfo>(self, client: &C) -> Option<StateOrBlock> {
+        match self.block_id(client) {
+            Some(block_id) => Some(block_id.into()),
+            None => client.pending_state().map(|state| (Box::new(state) as Box<dyn TopStateView>).into()),
+        }
+    }
+}
+
+impl Serialize for BlockNumberOrTag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            BlockNumberOrTag::Number(number) => serializer.serialize_u64(*number),
+            BlockNumberOrTag::Latest => serializer.serialize_str("latest"),
+            BlockNumberOrTag::Finalized => serializer.serialize_str("finalized"),
+            BlockNumberOrTag::Safe => serializer.serialize_str("safe"),
+            BlockNumberOrTag::Pending => serializer.serialize_str("pending"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BlockNumberOrTag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(BlockNumberOrTagVisitor)
+    }
+}
+
+struct BlockNumberOrTagVisitor;
+
+impl<'de> Visitor<'de> for BlockNumberOrTagVisitor {
+    type Value = BlockNumberOrTag;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a block number or one of \"latest\", \"finalized\", \"safe\" and \"pending\"")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        Ok(BlockNumberOrTag::Number(value))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        BlockNumberOrTag::from_tag(value).ok_or_else(|| de::Error::invalid_value(de::Unexpected::Str(value), &self))
+    }
+}
+
+/// Parses the block given in a URL, where a number isn't distinguished from a string.
+impl FromStr for BlockNumberOrTag {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse() {
+            Ok(number) => Ok(BlockNumberOrTag::Number(number)),
+            Err(_) => BlockNumberOrTag::from_tag(s).ok_or(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ccore::TestBlockChainClient;
+
+    #[test]
+    fn deserialize_numbers_and_tags() {
+        let parsed: Vec<BlockNumberOrTag> =
+            serde_json::from_str(r#"[0, 12, "latest", "finalized", "safe", "pending"]"#).unwrap();
+        assert_eq!(parsed, vec![
+            BlockNumberOrTag::Number(0),
+            BlockNumberOrTag::Number(12),
+            BlockNumberOrTag::Latest,
+            BlockNumberOrTag::Finalized,
+            BlockNumberOrTag::Safe,
+            BlockNumberOrTag::Pending,
+        ]);
+    }
+
+    #[test]
+    fn reject_unknown_tags_and_negative_numbers() {
+        assert!(serde_json::from_str::<BlockNumberOrTag>(r#""earliest""#).is_err());
+        assert!(serde_json::from_str::<BlockNumberOrTag>("-1").is_err());
+    }
+
+    #[test]
+    fn parse_numbers_and_tags_in_urls() {
+        assert_eq!(Ok(BlockNumberOrTag::Number(7)), "7".parse());
+        assert_eq!(Ok(BlockNumberOrTag::Finalized), "finalized".parse());
+        assert_eq!(Err(()), "7th".parse::<BlockNumberOrTag>());
+    }
+
+    #[test]
+    fn serialize_back_to_the_same_form() {
+        let serialized = serde_json::to_string(&[BlockNumberOrTag::Number(3), BlockNumberOrTag::Pending]).unwrap();
+        assert_eq!(r#"[3,"pending"]"#, serialized);
+    }
+
+    #[test]
+    fn tags_are_resolved_against_the_best_block() {
+        let client = TestBlockChainClient::new();
+        client.add_blocks(10, 0);
+        let best_block_number = client.chain_info().best_block_number;
+        let confirmation = u64::from(client.recommended_confirmation());
+
+        assert_eq!(best_block_number, BlockNumberOrTag::Latest.number(&client));
+        assert_eq!(best_block_number.saturating_sub(confirmation), BlockNumberOrTag::Finalized.number(&client));
+        assert_eq!(best_block_number - 1, BlockNumberOrTag::Safe.number(&client));
+        assert_eq!(best_block_number + 1, BlockNumberOrTag::Pending.number(&client));
+        assert_eq!(Some(BlockId::Latest), BlockNumberOrTag::Latest.block_id(&client));
+        assert_eq!(None, BlockNumberOrTag::Pending.block_id(&client));
+    }
+}
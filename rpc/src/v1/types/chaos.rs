@@ -0,0 +1,54 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use cnetwork::ChaosConfig;
+use std::time::Duration;
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChaosSetting {
+    #[serde(default)]
+    pub drop_percent: u8,
+    #[serde(default)]
+    pub corrupt_percent: u8,
+    #[serde(default)]
+    pub disconnect_percent: u8,
+    /// Each inbound message is delayed by a random number of milliseconds up to this.
+    #[serde(default)]
+    pub max_latency_ms: u64,
+}
+
+impl From<ChaosConfig> for ChaosSetting {
+    fn from(config: ChaosConfig) -> Self {
+        Self {
+            drop_percent: config.drop_percent,
+            corrupt_percent: config.corrupt_percent,
+            disconnect_percent: config.disconnect_percent,
+            max_latency_ms: config.max_latency.as_millis() as u64,
+        }
+    }
+}
+
+impl From<ChaosSetting> for ChaosConfig {
+    fn from(setting: ChaosSetting) -> Self {
+        Self {
+            drop_percent: setting.drop_percent,
+            corrupt_percent: setting.corrupt_percent,
+            disconnect_percent: setting.disconnect_percent,
+            max_latency: Duration::from_millis(setting.max_latency_ms),
+        }
+    }
+}
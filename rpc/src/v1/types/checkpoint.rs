@@ -0,0 +1,67 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{CommitSignature, CommitValidator};
+use ccore::SignedCheckpoint as CoreSignedCheckpoint;
+use ctypes::BlockHash;
+use primitives::H256;
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedCheckpoint {
+    pub number: u64,
+    pub block_hash: BlockHash,
+    pub state_root: H256,
+    /// The hash of the validators finalizing the block.
+    pub validator_set_hash: H256,
+    /// The message signed by the validators.
+    pub hash: H256,
+    pub validators: Vec<CommitValidator>,
+    pub signatures: Vec<CommitSignature>,
+    /// The voting power of the validators which signed the checkpoint.
+    pub voting_power: u64,
+    pub total_voting_power: u64,
+    /// Whether more than 2/3 of the voting power signed the checkpoint.
+    pub is_final: bool,
+}
+
+impl From<CoreSignedCheckpoint> for SignedCheckpoint {
+    fn from(signed: CoreSignedCheckpoint) -> Self {
+        let voting_power = signed.voting_power();
+        let total_voting_power = signed.total_voting_power();
+        let is_final = signed.is_final();
+        let checkpoint = signed.checkpoint;
+        SignedCheckpoint {
+            number: checkpoint.number,
+            block_hash: checkpoint.block_hash,
+            state_root: checkpoint.state_root,
+            validator_set_hash: checkpoint.validator_set_hash,
+            hash: checkpoint.hash(),
+            validators: signed.validators.into_iter().map(Into::into).collect(),
+            signatures: signed
+                .signatures
+                .into_iter()
+                .map(|(index, signature)| CommitSignature {
+                    index,
+                    signature,
+                })
+                .collect(),
+            voting_power,
+            total_voting_power,
+            is_final,
+        }
+    }
+}
@@ -0,0 +1,41 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ccore::ClockDrift as CoreClockDrift;
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClockDrift {
+    /// The timestamps of the recent proposals minus the local time in seconds.
+    /// It is positive if the local clock is behind the other validators.
+    pub drift: i64,
+    /// The number of the proposals that the drift is estimated from.
+    pub samples: usize,
+    /// In milliseconds.
+    pub max_drift: u64,
+    pub exceeded: bool,
+}
+
+impl From<CoreClockDrift> for ClockDrift {
+    fn from(drift: CoreClockDrift) -> Self {
+        Self {
+            drift: drift.drift,
+            samples: drift.samples,
+            max_drift: drift.max_drift.as_millis() as u64,
+            exceeded: drift.is_exceeded(),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{
+    AccountInfo, BlockNumberAndHash, ClockDrift, MemPoolMinFees, NodeFeatures, NodeInfo, PublishedSnapshot, SeqInfo,
+    SeqSlot,
+};
+use cjson::conformance::Registry;
+use ctypes::{BlockHash, Tracker, TxHash};
+use std::path::PathBuf;
+
+#[test]
+fn rpc_vectors() {
+    let registry = Registry::default()
+        .json::<AccountInfo>("AccountInfo")
+        .json::<BlockNumberAndHash>("BlockNumberAndHash")
+        .json::<ClockDrift>("ClockDrift")
+        .json::<MemPoolMinFees>("MemPoolMinFees")
+        .json::<NodeFeatures>("NodeFeatures")
+        .json::<NodeInfo>("NodeInfo")
+        .json::<PublishedSnapshot>("PublishedSnapshot")
+        .json::<SeqInfo>("SeqInfo")
+        .json::<SeqSlot>("SeqSlot")
+        .json::<BlockHash>("BlockHash")
+        .rlp::<BlockHash>("BlockHash")
+        .json::<TxHash>("TxHash")
+        .rlp::<TxHash>("TxHash")
+        .json::<Tracker>("Tracker")
+        .rlp::<Tracker>("Tracker");
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../json/vectors/rpc");
+    match registry.check_dir(&dir) {
+        Ok(checked) => assert_ne!(0, checked),
+        Err(failures) => panic!("{}", failures.join("\n")),
+    }
+}
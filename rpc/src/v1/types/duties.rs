@@ -0,0 +1,56 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ccore::{Duties as CoreDuties, Slot as CoreSlot};
+use ckey::{NetworkId, PlatformAddress};
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Slot {
+    pub block_number: u64,
+    pub view: u64,
+}
+
+impl From<CoreSlot> for Slot {
+    fn from(slot: CoreSlot) -> Self {
+        Self {
+            block_number: slot.block_number,
+            view: slot.view,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Duties {
+    pub proposer: PlatformAddress,
+    /// The blocks that the proposer proposes at view 0.
+    pub upcoming: Vec<u64>,
+    /// The number of the slots missed since the node started.
+    pub missed: u64,
+    pub last_missed: Option<Slot>,
+}
+
+impl Duties {
+    pub fn from_core(duties: CoreDuties, network_id: NetworkId) -> Self {
+        Self {
+            proposer: PlatformAddress::new_v1(network_id, duties.proposer),
+            upcoming: duties.upcoming,
+            missed: duties.missed,
+            last_missed: duties.last_missed.map(Slot::from),
+        }
+    }
+}
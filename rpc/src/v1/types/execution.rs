@@ -0,0 +1,161 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ccore::{
+    AccountChange as CoreAccountChange, BlockExecutionSummary as CoreBlockExecutionSummary,
+    TransactionExecution as CoreTransactionExecution, TransactionSummary as CoreTransactionSummary,
+};
+use cjson::uint::Uint;
+use ckey::{NetworkId, PlatformAddress};
+use ctypes::transaction::Action;
+use ctypes::{BlockHash, TxHash};
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionExecution {
+    pub success: bool,
+    /// The reason of the failure
+    pub error: Option<String>,
+    pub fee: Uint,
+    pub changed_accounts: Vec<AccountChange>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockExecutionSummary {
+    pub number: u64,
+    pub hash: BlockHash,
+    pub transactions: Vec<TransactionSummary>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionSummary {
+    pub hash: TxHash,
+    pub action_type: String,
+    pub success: bool,
+    /// The reason of the failure
+    pub error: Option<String>,
+    pub fee: Uint,
+    pub touched_accounts: Vec<PlatformAddress>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountChange {
+    pub address: PlatformAddress,
+    pub balance_before: Uint,
+    pub balance_after: Uint,
+    pub seq_before: u64,
+    pub seq_after: u64,
+}
+
+impl TransactionExecution {
+    pub fn from_core(execution: CoreTransactionExecution, network_id: NetworkId) -> Self {
+        TransactionExecution {
+            success: execution.result.is_ok(),
+            error: execution.result.err().map(|err| err.to_string()),
+            fee: execution.fee.into(),
+            changed_accounts: execution
+                .changed_accounts
+                .into_iter()
+                .map(|change| AccountChange::from_core(change, network_id))
+                .collect(),
+        }
+    }
+}
+
+impl BlockExecutionSummary {
+    pub fn from_core(summary: &CoreBlockExecutionSummary, network_id: NetworkId) -> Self {
+        BlockExecutionSummary {
+            number: summary.number,
+            hash: summary.hash,
+            transactions: summary
+                .transactions
+                .iter()
+                .map(|transaction| TransactionSummary::from_core(transaction, network_id))
+                .collect(),
+        }
+    }
+}
+
+impl TransactionSummary {
+    fn from_core(summary: &CoreTransactionSummary, network_id: NetworkId) -> Self {
+        TransactionSummary {
+            hash: summary.hash,
+            action_type: action_type(&summary.action).to_string(),
+            success: summary.error.is_none(),
+            error: summary.error.clone(),
+            fee: summary.fee.into(),
+            touched_accounts: summary
+                .touched_accounts
+                .iter()
+                .map(|address| PlatformAddress::new_v1(network_id, *address))
+                .collect(),
+        }
+    }
+}
+
+/// The `type` of the action in JSON.
+fn action_type(action: &Action) -> &'static str {
+    match action {
+        Action::Pay {
+            ..
+        } => "pay",
+        Action::SetRegularKey {
+            ..
+        } => "setRegularKey",
+        Action::CreateShard {
+            ..
+        } => "createShard",
+        Action::SetShardOwners {
+            ..
+        } => "setShardOwners",
+        Action::SetShardUsers {
+            ..
+        } => "setShardUsers",
+        Action::TerminateShard {
+            ..
+        } => "terminateShard",
+        Action::Custom {
+            ..
+        } => "custom",
+        Action::ShardStore {
+            ..
+        } => "shardStore",
+        Action::Batch {
+            ..
+        } => "batch",
+        Action::Scheduled {
+            ..
+        } => "scheduled",
+        Action::BurnSeq => "burnSeq",
+    }
+}
+
+impl AccountChange {
+    fn from_core(change: CoreAccountChange, network_id: NetworkId) -> Self {
+        let (balance_before, seq_before) = change.before;
+        let (balance_after, seq_after) = change.after;
+        AccountChange {
+            address: PlatformAddress::new_v1(network_id, change.address),
+            balance_before: balance_before.into(),
+            balance_after: balance_after.into(),
+            seq_before,
+            seq_after,
+        }
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ccore::stake::{Proposal as CoreProposal, Tally as CoreTally, VoteOption as CoreVoteOption};
+use cjson::uint::Uint;
+use ckey::{NetworkId, PlatformAddress};
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VoteOption {
+    Yes,
+    No,
+    Abstain,
+}
+
+impl From<CoreVoteOption> for VoteOption {
+    fn from(option: CoreVoteOption) -> Self {
+        match option {
+            CoreVoteOption::Yes => VoteOption::Yes,
+            CoreVoteOption::No => VoteOption::No,
+            CoreVoteOption::Abstain => VoteOption::Abstain,
+        }
+    }
+}
+
+impl From<VoteOption> for CoreVoteOption {
+    fn from(option: VoteOption) -> Self {
+        match option {
+            VoteOption::Yes => CoreVoteOption::Yes,
+            VoteOption::No => CoreVoteOption::No,
+            VoteOption::Abstain => CoreVoteOption::Abstain,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Vote {
+    pub voter: PlatformAddress,
+    pub option: VoteOption,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tally {
+    pub yes: Uint,
+    pub no: Uint,
+    pub abstain: Uint,
+    pub total: Uint,
+}
+
+impl From<CoreTally> for Tally {
+    fn from(tally: CoreTally) -> Self {
+        Self {
+            yes: tally.yes.into(),
+            no: tally.no.into(),
+            abstain: tally.abstain.into(),
+            total: tally.total.into(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: PlatformAddress,
+    pub text: String,
+    pub deadline: u64,
+    pub votes: Vec<Vote>,
+    /// Exists only after the deadline.
+    pub tally: Option<Tally>,
+}
+
+impl Proposal {
+    pub fn from_core(id: u64, proposal: CoreProposal, network_id: NetworkId) -> Self {
+        Self {
+            id,
+            proposer: PlatformAddress::new_v1(network_id, proposal.proposer),
+            votes: proposal
+                .votes()
+                .map(|(voter, option)| Vote {
+                    voter: PlatformAddress::new_v1(network_id, *voter),
+                    option: (*option).into(),
+                })
+                .collect(),
+            tally: proposal.tally().cloned().map(Tally::from),
+            text: proposal.text,
+            deadline: proposal.deadline,
+        }
+    }
+}
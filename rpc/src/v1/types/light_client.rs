@@ -0,0 +1,104 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ccore::{Commit as CoreCommit, CommitValidator as CoreCommitValidator};
+use cjson::bytes::Bytes;
+use ckey::{Public, SchnorrSignature};
+use cstate::ActionDataProof;
+use ctypes::header::Seal;
+use ctypes::BlockHash;
+use primitives::H256;
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitValidator {
+    pub pubkey: Public,
+    pub voting_power: u64,
+}
+
+impl From<CoreCommitValidator> for CommitValidator {
+    fn from(validator: CoreCommitValidator) -> Self {
+        CommitValidator {
+            pubkey: validator.pubkey,
+            voting_power: validator.voting_power,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsensusState {
+    pub number: u64,
+    pub hash: BlockHash,
+    pub timestamp: u64,
+    pub state_root: H256,
+    /// The hash of the validators signing the commit of the block.
+    pub validator_set_hash: H256,
+    pub validators: Vec<CommitValidator>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitSignature {
+    pub index: usize,
+    pub signature: SchnorrSignature,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Commit {
+    /// The RLP encoded header with the seal.
+    pub header: Bytes,
+    pub hash: BlockHash,
+    pub view: u64,
+    pub signatures: Vec<CommitSignature>,
+}
+
+impl From<CoreCommit> for Commit {
+    fn from(commit: CoreCommit) -> Self {
+        Commit {
+            header: commit.header.rlp(&Seal::With).into(),
+            hash: commit.header.hash(),
+            view: commit.view,
+            signatures: commit
+                .signatures
+                .into_iter()
+                .map(|(index, signature)| CommitSignature {
+                    index,
+                    signature,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitmentProof {
+    pub state_root: H256,
+    pub nodes: Vec<Bytes>,
+    pub value: Option<Bytes>,
+}
+
+impl CommitmentProof {
+    pub fn new(state_root: H256, proof: ActionDataProof) -> Self {
+        CommitmentProof {
+            state_root,
+            nodes: proof.nodes.into_iter().map(Into::into).collect(),
+            value: proof.value.map(Into::into),
+        }
+    }
+}
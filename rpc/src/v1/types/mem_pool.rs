@@ -14,21 +14,26 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-#[derive(Debug, Serialize)]
+use super::Transaction;
+use cnetwork::{IntoSocketAddr, NodeId};
+use ctypes::transaction::Action;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MemPoolMinFees {
-    min_pay_transaction_cost: u64,
-    min_set_regular_key_transaction_cost: u64,
-    min_create_shard_transaction_cost: u64,
-    min_set_shard_owners_transaction_cost: u64,
-    min_set_shard_users_transaction_cost: u64,
-    min_wrap_ccc_transaction_cost: u64,
-    min_custom_transaction_cost: u64,
-    min_asset_mint_cost: u64,
-    min_asset_transfer_cost: u64,
-    min_asset_scheme_change_cost: u64,
-    min_asset_supply_increase_cost: u64,
-    min_asset_unwrap_ccc_cost: u64,
+    pub min_pay_transaction_cost: u64,
+    pub min_set_regular_key_transaction_cost: u64,
+    pub min_create_shard_transaction_cost: u64,
+    pub min_set_shard_owners_transaction_cost: u64,
+    pub min_set_shard_users_transaction_cost: u64,
+    pub min_wrap_ccc_transaction_cost: u64,
+    pub min_custom_transaction_cost: u64,
+    pub min_asset_mint_cost: u64,
+    pub min_asset_transfer_cost: u64,
+    pub min_asset_scheme_change_cost: u64,
+    pub min_asset_supply_increase_cost: u64,
+    pub min_asset_unwrap_ccc_cost: u64,
 }
 
 impl From<ccore::MemPoolMinFees> for MemPoolMinFees {
@@ -49,3 +54,80 @@ impl From<ccore::MemPoolMinFees> for MemPoolMinFees {
         }
     }
 }
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PooledTransaction {
+    pub transaction: Transaction,
+    /// The base fee of the actions at the best block
+    pub base_fee: u64,
+    /// The rest of the fee, which orders the transactions in the pool
+    pub tip: u64,
+    /// Whether the signer is a local account, whose transactions are prioritized
+    pub local: bool,
+    /// One of "rpc", "peer", "retracted" and "unknown"
+    pub source: String,
+    /// The address of the peer that propagated the transaction
+    pub peer: Option<String>,
+}
+
+impl PooledTransaction {
+    pub fn new(input: ccore::MemPoolInput, base_fee: u64) -> Self {
+        let (source, peer) = match input.source {
+            ccore::TxSource::Rpc => ("rpc", None),
+            ccore::TxSource::Peer(peer) => ("peer", Some(peer_address(peer))),
+            ccore::TxSource::Retracted => ("retracted", None),
+            ccore::TxSource::Unknown => ("unknown", None),
+        };
+        let tip = input.transaction.fee.saturating_sub(base_fee);
+        Self {
+            transaction: input.transaction.into(),
+            base_fee,
+            tip,
+            local: input.origin.is_local(),
+            source: source.to_string(),
+            peer,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemPoolContent {
+    pub pending: Vec<PooledTransaction>,
+    pub future: Vec<PooledTransaction>,
+    pub scheduled: Vec<PooledTransaction>,
+    /// peer address => the number of its transactions that proved invalid
+    pub invalid_transactions_of_peers: BTreeMap<String, usize>,
+}
+
+impl MemPoolContent {
+    pub fn new(
+        content: ccore::MemPoolContent,
+        invalid_transactions_of_peers: Vec<(NodeId, usize)>,
+        base_fee: impl Fn(&Action) -> u64,
+    ) -> Self {
+        let pooled = |inputs: Vec<ccore::MemPoolInput>| -> Vec<PooledTransaction> {
+            inputs
+                .into_iter()
+                .map(|input| {
+                    let base_fee = base_fee(&input.transaction.action);
+                    PooledTransaction::new(input, base_fee)
+                })
+                .collect()
+        };
+        Self {
+            pending: pooled(content.pending),
+            future: pooled(content.future),
+            scheduled: pooled(content.scheduled),
+            invalid_transactions_of_peers: invalid_transactions_of_peers
+                .into_iter()
+                .map(|(peer, count)| (peer_address(peer), count))
+                .collect(),
+        }
+    }
+}
+
+fn peer_address(peer: NodeId) -> String {
+    peer.into_addr().to_string()
+}
@@ -14,17 +14,50 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+mod account_info;
 mod action;
 mod block;
+mod block_number;
+mod chaos;
+mod checkpoint;
+mod clock_drift;
+#[cfg(test)]
+mod conformance;
+mod duties;
+mod execution;
+mod governance;
+mod light_client;
 mod mem_pool;
+mod node_info;
+mod seq_info;
+mod shard;
+mod stake;
+mod tps_test;
 mod transaction;
 mod unsigned_transaction;
 mod work;
 
+pub use self::account_info::AccountInfo;
 pub use self::action::{Action, ActionWithTracker};
 pub use self::block::Block;
-pub use self::block::BlockNumberAndHash;
-pub use self::mem_pool::MemPoolMinFees;
+pub use self::block::{BlockNumberAndHash, PublishedSnapshot};
+pub use self::block_number::BlockNumberOrTag;
+pub use self::chaos::ChaosSetting;
+pub use self::checkpoint::SignedCheckpoint;
+pub use self::clock_drift::ClockDrift;
+pub use self::duties::Duties;
+pub use self::execution::{BlockExecutionSummary, TransactionExecution, TransactionSummary};
+pub use self::governance::{Proposal, Tally, Vote, VoteOption};
+pub use self::light_client::{Commit, CommitSignature, CommitValidator, CommitmentProof, ConsensusState};
+pub use self::mem_pool::{MemPoolContent, MemPoolMinFees, PooledTransaction};
+pub use self::node_info::{NodeFeatures, NodeInfo};
+pub use self::seq_info::{SeqInfo, SeqSlot};
+pub use self::shard::{ComposedTransaction, ShardInfo, ShardProof};
+pub use self::stake::{
+    Candidate, Commission, Delegation, LockedDeposit, Misses, PendingRewards, Prisoner, SessionKey, StakeEvent,
+    StakeValidator, TermHistory, TermInfo,
+};
+pub use self::tps_test::{TPSTestProfile, TPSTestReport, TPSTestSetting};
 pub use self::transaction::{PendingTransactions, Transaction};
 pub use self::unsigned_transaction::UnsignedTransaction;
 pub use self::work::Work;
@@ -37,15 +70,14 @@ pub struct FilterStatus {
     pub enabled: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrustedPeers {
+    pub list: Vec<::std::net::SocketAddr>,
+    pub permissioned: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SendTransactionResult {
     pub hash: TxHash,
     pub seq: u64,
 }
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct TPSTestSetting {
-    pub count: u64,
-    pub seed: u64,
-}
@@ -0,0 +1,41 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ckey::NetworkId;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeInfo {
+    pub version: String,
+    pub commit_hash: String,
+    /// The chain given by `--chain`, which is a file path for a custom chain.
+    pub chain_type: String,
+    pub network_id: NetworkId,
+    pub features: NodeFeatures,
+    /// The namespaces of the methods that the node serves, such as `chain` and `devel`.
+    pub namespaces: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeFeatures {
+    /// The states of all the blocks are kept, so the states can be read at any block.
+    pub archive: bool,
+    /// The indexes that the node keeps to find the transactions.
+    pub indexes: Vec<String>,
+    /// The node takes the snapshots and serves them to the peers.
+    pub snapshot: bool,
+}
@@ -0,0 +1,70 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ccore::SignedTransaction;
+use ctypes::TxHash;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeqInfo {
+    /// The seq of the latest state, which the next transaction should use.
+    pub seq: u64,
+    pub highest_pooled_seq: Option<u64>,
+    /// The seqs between `seq` and `highest_pooled_seq`, with the pooled transactions using them.
+    pub slots: Vec<SeqSlot>,
+    /// The seqs that no pooled transaction uses. The transactions after the first gap cannot be mined.
+    pub gaps: Vec<u64>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeqSlot {
+    pub seq: u64,
+    pub transactions: Vec<TxHash>,
+}
+
+impl SeqInfo {
+    pub fn new(seq: u64, pooled: &[SignedTransaction]) -> Self {
+        let mut by_seq: BTreeMap<u64, Vec<TxHash>> = BTreeMap::new();
+        for tx in pooled.iter().filter(|tx| tx.seq >= seq) {
+            by_seq.entry(tx.seq).or_default().push(tx.hash());
+        }
+        let highest_pooled_seq = by_seq.keys().next_back().cloned();
+
+        let mut slots = Vec::new();
+        let mut gaps = Vec::new();
+        if let Some(highest) = highest_pooled_seq {
+            for slot in seq..=highest {
+                let transactions = by_seq.remove(&slot).unwrap_or_default();
+                if transactions.is_empty() {
+                    gaps.push(slot);
+                }
+                slots.push(SeqSlot {
+                    seq: slot,
+                    transactions,
+                });
+            }
+        }
+
+        Self {
+            seq,
+            highest_pooled_seq,
+            slots,
+            gaps,
+        }
+    }
+}
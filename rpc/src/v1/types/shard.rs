@@ -0,0 +1,58 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::SendTransactionResult;
+use cjson::bytes::Bytes;
+use ckey::PlatformAddress;
+use cstate::ShardProof as CoreShardProof;
+use primitives::H256;
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShardInfo {
+    pub root: H256,
+    pub owners: Vec<PlatformAddress>,
+    pub users: Vec<PlatformAddress>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComposedTransaction {
+    /// The RLP encoded unsigned transaction.
+    pub unsigned: Bytes,
+    /// Exists only if the transaction is signed and sent.
+    pub sent: Option<SendTransactionResult>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShardProof {
+    pub shard_nodes: Vec<Bytes>,
+    pub shard_root: H256,
+    pub value_nodes: Vec<Bytes>,
+    pub value: Option<Bytes>,
+}
+
+impl From<CoreShardProof> for ShardProof {
+    fn from(proof: CoreShardProof) -> Self {
+        ShardProof {
+            shard_nodes: proof.shard_nodes.into_iter().map(Into::into).collect(),
+            shard_root: proof.shard_root,
+            value_nodes: proof.value_nodes.into_iter().map(Into::into).collect(),
+            value: proof.value.map(Into::into),
+        }
+    }
+}
@@ -0,0 +1,285 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ccore::stake::{
+    Candidate as CoreCandidate, Prisoner as CorePrisoner, StakeEvent as CoreStakeEvent, Validator as CoreValidator,
+};
+use cjson::bytes::Bytes;
+use cjson::uint::Uint;
+use ckey::{public_to_address, NetworkId, PlatformAddress, Public};
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Delegation {
+    pub delegatee: PlatformAddress,
+    pub quantity: Uint,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StakeValidator {
+    pub address: PlatformAddress,
+    pub pubkey: Public,
+    pub delegation: Uint,
+    pub deposit: Uint,
+}
+
+impl StakeValidator {
+    pub fn from_core(validator: &CoreValidator, network_id: NetworkId) -> Self {
+        Self {
+            address: PlatformAddress::new_v1(network_id, public_to_address(validator.pubkey())),
+            pubkey: *validator.pubkey(),
+            delegation: validator.delegation().into(),
+            deposit: validator.deposit().into(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Candidate {
+    pub address: PlatformAddress,
+    pub pubkey: Public,
+    pub deposit: Uint,
+    pub nomination_ends_at: u64,
+    pub metadata: Bytes,
+}
+
+impl Candidate {
+    pub fn from_core(candidate: CoreCandidate, network_id: NetworkId) -> Self {
+        Self {
+            address: PlatformAddress::new_v1(network_id, public_to_address(&candidate.pubkey)),
+            pubkey: candidate.pubkey,
+            deposit: candidate.deposit.into(),
+            nomination_ends_at: candidate.nomination_ends_at,
+            metadata: candidate.metadata.into(),
+        }
+    }
+}
+
+/// A jailed candidate. The deposit is returned at the end of the term `releasedAt`.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Prisoner {
+    pub address: PlatformAddress,
+    pub deposit: Uint,
+    pub custody_until: u64,
+    pub released_at: u64,
+}
+
+impl Prisoner {
+    pub fn from_core(prisoner: CorePrisoner, network_id: NetworkId) -> Self {
+        Self {
+            address: PlatformAddress::new_v1(network_id, prisoner.address),
+            deposit: prisoner.deposit.into(),
+            custody_until: prisoner.custody_until,
+            released_at: prisoner.released_at,
+        }
+    }
+}
+
+/// A deposit locked until the end of the term `unlocksAt`, when it's returned to the balance without a transaction.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum LockedDeposit {
+    /// The nomination is extended while the candidate is elected, and so is the deposit.
+    #[serde(rename_all = "camelCase")]
+    Deposit {
+        quantity: Uint,
+        unlocks_at: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    JailedDeposit {
+        quantity: Uint,
+        unlocks_at: u64,
+    },
+}
+
+impl LockedDeposit {
+    pub fn from_candidate(candidate: &CoreCandidate) -> Self {
+        LockedDeposit::Deposit {
+            quantity: candidate.deposit.into(),
+            unlocks_at: candidate.nomination_ends_at,
+        }
+    }
+
+    pub fn from_prisoner(prisoner: &CorePrisoner) -> Self {
+        LockedDeposit::JailedDeposit {
+            quantity: prisoner.deposit.into(),
+            unlocks_at: prisoner.released_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Commission {
+    /// Percentage of the rewards that the validator takes.
+    pub rate: u64,
+    pub destination: PlatformAddress,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionKey {
+    pub pubkey: Public,
+    /// The last block whose votes are signed with the key.
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TermInfo {
+    pub term_id: u64,
+    pub start_block: u64,
+    /// Exists only if the term has ended.
+    pub end_block: Option<u64>,
+    pub banned: Vec<PlatformAddress>,
+    pub jailed: Vec<Prisoner>,
+    /// The validators that would be elected if the term closed at the block.
+    pub next_validators: Option<Vec<StakeValidator>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum StakeEvent {
+    Delegated {
+        delegatee: PlatformAddress,
+        quantity: Uint,
+    },
+    Revoked {
+        delegatee: PlatformAddress,
+        quantity: Uint,
+    },
+    #[serde(rename_all = "camelCase")]
+    Redelegated {
+        prev_delegatee: PlatformAddress,
+        next_delegatee: PlatformAddress,
+        quantity: Uint,
+    },
+    Reverted {
+        delegatee: PlatformAddress,
+        quantity: Uint,
+    },
+    Rewarded {
+        validator: PlatformAddress,
+        quantity: Uint,
+    },
+}
+
+impl StakeEvent {
+    pub fn from_core(event: CoreStakeEvent, network_id: NetworkId) -> Self {
+        let address = |address| PlatformAddress::new_v1(network_id, address);
+        match event {
+            CoreStakeEvent::Delegated {
+                delegatee,
+                quantity,
+            } => StakeEvent::Delegated {
+                delegatee: address(delegatee),
+                quantity: quantity.into(),
+            },
+            CoreStakeEvent::Revoked {
+                delegatee,
+                quantity,
+            } => StakeEvent::Revoked {
+                delegatee: address(delegatee),
+                quantity: quantity.into(),
+            },
+            CoreStakeEvent::Redelegated {
+                prev_delegatee,
+                next_delegatee,
+                quantity,
+            } => StakeEvent::Redelegated {
+                prev_delegatee: address(prev_delegatee),
+                next_delegatee: address(next_delegatee),
+                quantity: quantity.into(),
+            },
+            CoreStakeEvent::Reverted {
+                delegatee,
+                quantity,
+            } => StakeEvent::Reverted {
+                delegatee: address(delegatee),
+                quantity: quantity.into(),
+            },
+            CoreStakeEvent::Rewarded {
+                validator,
+                quantity,
+            } => StakeEvent::Rewarded {
+                validator: address(validator),
+                quantity: quantity.into(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TermHistory {
+    pub term: u64,
+    pub events: Vec<StakeEvent>,
+}
+
+/// The proposals and the votes that the validator missed in the current term.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Misses {
+    /// The number of blocks checked in the current term.
+    pub blocks: u64,
+    pub proposed: u64,
+    pub missed_proposals: u64,
+    pub missed_votes: u64,
+    /// The percentage of the missed ones among the proposals and the votes.
+    pub rate: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingRewards {
+    /// The rewards accumulated in the current term.
+    pub current: Uint,
+    /// The rewards of the previous term, paid at the end of the current term.
+    pub calculated: Uint,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckey::Address;
+
+    #[test]
+    fn locked_deposits_have_their_type_and_unlock_term() {
+        let candidate = CoreCandidate {
+            pubkey: Public::default(),
+            deposit: 10_000,
+            nomination_ends_at: 7,
+            metadata: Vec::new(),
+        };
+        let prisoner = CorePrisoner {
+            address: Address::default(),
+            deposit: 20_000,
+            custody_until: 9,
+            released_at: 12,
+        };
+        assert_eq!(
+            r#"{"type":"deposit","quantity":"0x2710","unlocksAt":7}"#,
+            serde_json::to_string(&LockedDeposit::from_candidate(&candidate)).unwrap()
+        );
+        assert_eq!(
+            r#"{"type":"jailedDeposit","quantity":"0x4e20","unlocksAt":12}"#,
+            serde_json::to_string(&LockedDeposit::from_prisoner(&prisoner)).unwrap()
+        );
+    }
+}
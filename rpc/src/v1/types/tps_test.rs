@@ -0,0 +1,61 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TPSTestSetting {
+    pub count: u64,
+    pub seed: u64,
+    #[serde(default)]
+    pub profile: TPSTestProfile,
+    /// The number of accounts sending transactions. The genesis account sends all of them if omitted.
+    #[serde(default)]
+    pub senders: Option<u64>,
+    /// The number of transactions queued per second. All transactions are queued at once if omitted.
+    #[serde(default)]
+    pub target_rate: Option<u64>,
+    /// The seconds to wait for the transactions to be mined. The test fails after it.
+    #[serde(default)]
+    pub timeout: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TPSTestProfile {
+    PayOnly,
+    ShardHeavy,
+    Mixed,
+}
+
+impl Default for TPSTestProfile {
+    fn default() -> Self {
+        TPSTestProfile::PayOnly
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TPSTestReport {
+    pub count: u64,
+    /// Milliseconds from queueing the first transaction to including the last one
+    pub elapsed: u64,
+    pub tps: f64,
+    /// Milliseconds from queueing a transaction to including it
+    pub latency_p50: u64,
+    pub latency_p90: u64,
+    pub latency_p99: u64,
+    pub latency_max: u64,
+}
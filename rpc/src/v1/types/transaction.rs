@@ -20,7 +20,7 @@ use cjson::uint::Uint;
 use ckey::{NetworkId, Signature};
 use ctypes::{BlockHash, TxHash};
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Transaction {
     pub block_number: Option<u64>,
@@ -35,11 +35,11 @@ pub struct Transaction {
     pub sig: Signature,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PendingTransactions {
-    transactions: Vec<Transaction>,
-    last_timestamp: Option<u64>,
+    pub transactions: Vec<Transaction>,
+    pub last_timestamp: Option<u64>,
 }
 
 impl From<PendingSignedTransactions> for PendingTransactions {
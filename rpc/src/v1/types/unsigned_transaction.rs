@@ -22,7 +22,7 @@ use ctypes::transaction::IncompleteTransaction;
 use jsonrpc_core::Error;
 use std::convert::{TryFrom, TryInto};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UnsignedTransaction {
     pub seq: Option<u64>,
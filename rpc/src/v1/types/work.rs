@@ -16,7 +16,7 @@
 
 use primitives::{H256, U256};
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Work {
     pub pow_hash: H256,
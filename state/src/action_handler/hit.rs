@@ -18,7 +18,7 @@ use super::{ActionDataKeyBuilder, ActionHandler};
 use crate::{StateResult, TopLevelState, TopState, TopStateView};
 use ckey::{Address, Public};
 use ctypes::errors::SyntaxError;
-use ctypes::{CommonParams, Header};
+use ctypes::{BlockNumber, CommonParams, Header};
 use primitives::H256;
 use rlp::{self, Decodable, Encodable, Rlp};
 
@@ -70,6 +70,7 @@ impl ActionHandler for HitHandler {
         state: &mut TopLevelState,
         _sender: &Address,
         _sender_pubkey: &Public,
+        _current_block_number: BlockNumber,
     ) -> StateResult<()> {
         let address = self.hit_count();
         let action = HitAction::decode(&Rlp::new(bytes)).expect("Verification passed");
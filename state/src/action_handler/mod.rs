@@ -15,13 +15,14 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 mod hit;
+pub mod name_registry;
 
 use super::TopStateView;
 use crate::{StateResult, TopLevelState};
 use ccrypto::blake256;
 use ckey::{Address, Public};
 use ctypes::errors::SyntaxError;
-use ctypes::{CommonParams, Header};
+use ctypes::{BlockNumber, CommonParams, Header};
 use primitives::H256;
 use rlp::{Encodable, RlpStream};
 use std::convert::From;
@@ -36,6 +37,7 @@ pub trait ActionHandler: Send + Sync {
         state: &mut TopLevelState,
         fee_payer: &Address,
         sender_pubkey: &Public,
+        current_block_number: BlockNumber,
     ) -> StateResult<()>;
     fn verify(&self, bytes: &[u8], common_params: &CommonParams) -> Result<(), SyntaxError>;
 
@@ -93,6 +95,7 @@ impl ActionDataKeyBuilder {
 }
 
 pub use self::hit::HitHandler;
+pub use self::name_registry::NameRegistry;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,360 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{ActionDataKeyBuilder, ActionHandler};
+use crate::{StateResult, TopLevelState, TopState, TopStateView};
+use ckey::{Address, Public};
+use ctypes::errors::{RuntimeError, SyntaxError};
+use ctypes::{BlockNumber, CommonParams, Header};
+use primitives::H256;
+use rlp::{self, Decodable, DecoderError, Encodable, Rlp, RlpStream};
+
+pub const NAME_REGISTRY_HANDLER_ID: u64 = 3;
+
+pub const MIN_NAME_LENGTH: usize = 3;
+pub const MAX_NAME_LENGTH: usize = 64;
+
+#[derive(Clone, Copy)]
+#[repr(u8)]
+enum ActionTag {
+    Register = 1,
+    Transfer = 2,
+    SetReverse = 3,
+}
+
+impl Encodable for ActionTag {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.append_single_value(&(*self as u8));
+    }
+}
+
+impl Decodable for ActionTag {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        let tag = rlp.as_val()?;
+        match tag {
+            1u8 => Ok(ActionTag::Register),
+            2 => Ok(ActionTag::Transfer),
+            3 => Ok(ActionTag::SetReverse),
+            _ => Err(DecoderError::Custom("Unexpected ActionTag Value")),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum NameAction {
+    /// Registers the unowned name to the sender.
+    /// It becomes the reverse record of the sender if the sender doesn't have one.
+    Register {
+        name: String,
+    },
+    /// Gives the name of the sender to the receiver.
+    Transfer {
+        name: String,
+        receiver: Address,
+    },
+    /// Makes the name of the sender be the reverse record of the sender.
+    SetReverse {
+        name: String,
+    },
+}
+
+impl NameAction {
+    fn name(&self) -> &str {
+        match self {
+            NameAction::Register {
+                name,
+            }
+            | NameAction::Transfer {
+                name,
+                ..
+            }
+            | NameAction::SetReverse {
+                name,
+            } => name,
+        }
+    }
+}
+
+impl Encodable for NameAction {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        match self {
+            NameAction::Register {
+                name,
+            } => {
+                s.begin_list(2).append(&ActionTag::Register).append(name);
+            }
+            NameAction::Transfer {
+                name,
+                receiver,
+            } => {
+                s.begin_list(3).append(&ActionTag::Transfer).append(name).append(receiver);
+            }
+            NameAction::SetReverse {
+                name,
+            } => {
+                s.begin_list(2).append(&ActionTag::SetReverse).append(name);
+            }
+        }
+    }
+}
+
+impl Decodable for NameAction {
+    fn decode(rlp: &Rlp<'_>) -> Result<Self, DecoderError> {
+        let tag = rlp.val_at(0)?;
+        let expected = match tag {
+            ActionTag::Register | ActionTag::SetReverse => 2,
+            ActionTag::Transfer => 3,
+        };
+        let item_count = rlp.item_count()?;
+        if item_count != expected {
+            return Err(DecoderError::RlpInvalidLength {
+                expected,
+                got: item_count,
+            })
+        }
+        match tag {
+            ActionTag::Register => Ok(NameAction::Register {
+                name: rlp.val_at(1)?,
+            }),
+            ActionTag::Transfer => Ok(NameAction::Transfer {
+                name: rlp.val_at(1)?,
+                receiver: rlp.val_at(2)?,
+            }),
+            ActionTag::SetReverse => Ok(NameAction::SetReverse {
+                name: rlp.val_at(1)?,
+            }),
+        }
+    }
+}
+
+/// Names consist of lowercase alphanumerics and hyphens, and do not start or end with a hyphen.
+pub fn is_valid_name(name: &str) -> bool {
+    (MIN_NAME_LENGTH..=MAX_NAME_LENGTH).contains(&name.len())
+        && name.bytes().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == b'-')
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+}
+
+pub fn name_key(name: &str) -> H256 {
+    ActionDataKeyBuilder::new(NAME_REGISTRY_HANDLER_ID, 2).append(&"Name").append(&name).into_key()
+}
+
+pub fn reverse_key(address: &Address) -> H256 {
+    ActionDataKeyBuilder::new(NAME_REGISTRY_HANDLER_ID, 2).append(&"Reverse").append(address).into_key()
+}
+
+/// Returns the owner of the name.
+pub fn resolve_name(state: &TopLevelState, name: &str) -> StateResult<Option<Address>> {
+    Ok(state.action_data(&name_key(name))?.map(|data| rlp::decode(&data).expect("Owner must be an address")))
+}
+
+/// Returns the name that the address chose to be known by.
+pub fn reverse_name(state: &TopLevelState, address: &Address) -> StateResult<Option<String>> {
+    Ok(state.action_data(&reverse_key(address))?.map(|data| rlp::decode(&data).expect("Reverse record must be a name")))
+}
+
+#[derive(Clone, Default)]
+pub struct NameRegistry {}
+
+impl NameRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn owned_name(state: &TopLevelState, name: &str, sender: &Address) -> StateResult<()> {
+    match resolve_name(state, name)? {
+        Some(owner) if owner == *sender => Ok(()),
+        Some(_) => {
+            Err(RuntimeError::FailedToHandleCustomAction(format!("{} is owned by another account", name)).into())
+        }
+        None => Err(RuntimeError::FailedToHandleCustomAction(format!("{} is not registered", name)).into()),
+    }
+}
+
+impl ActionHandler for NameRegistry {
+    fn name(&self) -> &'static str {
+        "name registry"
+    }
+
+    fn handler_id(&self) -> u64 {
+        NAME_REGISTRY_HANDLER_ID
+    }
+
+    fn init(&self, _state: &mut TopLevelState) -> StateResult<()> {
+        Ok(())
+    }
+
+    /// `bytes` must be valid encoding of NameAction
+    fn execute(
+        &self,
+        bytes: &[u8],
+        state: &mut TopLevelState,
+        sender: &Address,
+        _sender_pubkey: &Public,
+        _current_block_number: BlockNumber,
+    ) -> StateResult<()> {
+        let action = NameAction::decode(&Rlp::new(bytes)).expect("Verification passed");
+        match action {
+            NameAction::Register {
+                name,
+            } => {
+                if resolve_name(state, &name)?.is_some() {
+                    return Err(
+                        RuntimeError::FailedToHandleCustomAction(format!("{} is already registered", name)).into()
+                    )
+                }
+                state.update_action_data(&name_key(&name), sender.rlp_bytes().to_vec())?;
+                if reverse_name(state, sender)?.is_none() {
+                    state.update_action_data(&reverse_key(sender), name.rlp_bytes().to_vec())?;
+                }
+            }
+            NameAction::Transfer {
+                name,
+                receiver,
+            } => {
+                owned_name(state, &name, sender)?;
+                state.update_action_data(&name_key(&name), receiver.rlp_bytes().to_vec())?;
+                if reverse_name(state, sender)?.as_ref() == Some(&name) {
+                    state.remove_action_data(&reverse_key(sender));
+                }
+                if reverse_name(state, &receiver)?.is_none() {
+                    state.update_action_data(&reverse_key(&receiver), name.rlp_bytes().to_vec())?;
+                }
+            }
+            NameAction::SetReverse {
+                name,
+            } => {
+                owned_name(state, &name, sender)?;
+                state.update_action_data(&reverse_key(sender), name.rlp_bytes().to_vec())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn verify(&self, bytes: &[u8], _params: &CommonParams) -> Result<(), SyntaxError> {
+        let action =
+            NameAction::decode(&Rlp::new(bytes)).map_err(|err| SyntaxError::InvalidCustomAction(err.to_string()))?;
+        if !is_valid_name(action.name()) {
+            return Err(SyntaxError::InvalidCustomAction(format!("{} is not a valid name", action.name())))
+        }
+        Ok(())
+    }
+
+    fn on_close_block(&self, _state: &mut TopLevelState, _header: &Header) -> StateResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helpers::get_temp_state;
+    use rlp::rlp_encode_and_decode_test;
+
+    fn execute(state: &mut TopLevelState, sender: &Address, action: NameAction) -> StateResult<()> {
+        let bytes = action.rlp_bytes();
+        NameRegistry::new().verify(&bytes, &CommonParams::default_for_test()).unwrap();
+        NameRegistry::new().execute(&bytes, state, sender, &Public::default(), 1)
+    }
+
+    fn register(name: &str) -> NameAction {
+        NameAction::Register {
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn encode_and_decode_name_actions() {
+        let actions = vec![
+            register("alice"),
+            NameAction::Transfer {
+                name: "alice".to_string(),
+                receiver: Address::random(),
+            },
+            NameAction::SetReverse {
+                name: "alice".to_string(),
+            },
+        ];
+        for action in actions {
+            rlp_encode_and_decode_test!(action);
+        }
+    }
+
+    #[test]
+    fn verify_rejects_invalid_names() {
+        for name in &["al", "Alice", "-alice", "alice-", "al ice", &"a".repeat(MAX_NAME_LENGTH + 1)] {
+            let bytes = register(name).rlp_bytes();
+            assert!(NameRegistry::new().verify(&bytes, &CommonParams::default_for_test()).is_err(), "{}", name);
+        }
+        assert!(is_valid_name("alice-01"));
+    }
+
+    #[test]
+    fn register_sets_the_owner_and_the_reverse_record() {
+        let mut state = get_temp_state();
+        let alice = Address::random();
+
+        execute(&mut state, &alice, register("alice")).unwrap();
+        execute(&mut state, &alice, register("alice-2")).unwrap();
+        assert_eq!(Some(alice), resolve_name(&state, "alice").unwrap());
+        assert_eq!(Some(alice), resolve_name(&state, "alice-2").unwrap());
+        assert_eq!(Some("alice".to_string()), reverse_name(&state, &alice).unwrap());
+
+        let bob = Address::random();
+        assert!(execute(&mut state, &bob, register("alice")).is_err());
+        assert_eq!(Some(alice), resolve_name(&state, "alice").unwrap());
+    }
+
+    #[test]
+    fn transfer_moves_the_name_and_the_reverse_record() {
+        let mut state = get_temp_state();
+        let alice = Address::random();
+        let bob = Address::random();
+        execute(&mut state, &alice, register("alice")).unwrap();
+
+        let transfer = NameAction::Transfer {
+            name: "alice".to_string(),
+            receiver: bob,
+        };
+        assert!(execute(&mut state, &bob, transfer).is_err());
+
+        let transfer = NameAction::Transfer {
+            name: "alice".to_string(),
+            receiver: bob,
+        };
+        execute(&mut state, &alice, transfer).unwrap();
+        assert_eq!(Some(bob), resolve_name(&state, "alice").unwrap());
+        assert_eq!(None, reverse_name(&state, &alice).unwrap());
+        assert_eq!(Some("alice".to_string()), reverse_name(&state, &bob).unwrap());
+    }
+
+    #[test]
+    fn only_the_owner_can_set_the_reverse_record() {
+        let mut state = get_temp_state();
+        let alice = Address::random();
+        let bob = Address::random();
+        execute(&mut state, &alice, register("alice")).unwrap();
+        execute(&mut state, &alice, register("alice-2")).unwrap();
+
+        let set_reverse = |name: &str| NameAction::SetReverse {
+            name: name.to_string(),
+        };
+        assert!(execute(&mut state, &bob, set_reverse("alice-2")).is_err());
+        execute(&mut state, &alice, set_reverse("alice-2")).unwrap();
+        assert_eq!(Some("alice-2".to_string()), reverse_name(&state, &alice).unwrap());
+    }
+}
@@ -17,6 +17,7 @@
 use super::lru_cache::LruCache;
 use super::{ShardCache, TopCache};
 use crate::{Account, ActionData, Metadata, RegularAccount, Shard, ShardText};
+use ckey::Address;
 use ctypes::ShardId;
 use std::collections::{HashMap, HashSet};
 
@@ -53,6 +54,11 @@ impl GlobalCache {
         )
     }
 
+    /// The addresses of the cached accounts, from the least recently used.
+    pub fn hot_accounts(&self) -> Vec<Address> {
+        self.account.iter().map(|(addr, _)| *addr).collect()
+    }
+
     fn shard_cache(&self, shard_id: ShardId) -> ShardCache {
         ShardCache::new(
             self.shard_text
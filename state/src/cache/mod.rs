@@ -26,8 +26,8 @@ mod write_back;
 
 pub use self::global_cache::GlobalCache;
 pub use self::shard_cache::ShardCache;
-pub use self::top_cache::TopCache;
-pub use self::write_back::WriteBack;
+pub use self::top_cache::{TopAccess, TopCache, TopChanges};
+pub use self::write_back::{Access, Changes, WriteBack};
 
 pub trait CacheableItem: Clone + Default + fmt::Debug + Decodable + Encodable {
     type Address: AsRef<[u8]> + Clone + Copy + fmt::Debug + Eq + Hash;
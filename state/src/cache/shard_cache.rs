@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use super::WriteBack;
+use super::{Access, Changes, WriteBack};
 use crate::{ShardText, ShardTextAddress};
 use merkle_trie::{Result as TrieResult, Trie, TrieMut};
 use std::cell::RefMut;
@@ -42,6 +42,22 @@ impl ShardCache {
         self.text.revert_to_checkpoint();
     }
 
+    pub fn start_recording(&self) {
+        self.text.start_recording();
+    }
+
+    pub fn finish_recording(&self) -> Access<ShardTextAddress> {
+        self.text.finish_recording()
+    }
+
+    pub fn changes(&self, access: &Access<ShardTextAddress>) -> Changes<ShardTextAddress, ShardText> {
+        self.text.changes(access)
+    }
+
+    pub fn apply_changes(&self, changes: Changes<ShardTextAddress, ShardText>) {
+        self.text.apply_changes(changes);
+    }
+
     pub fn commit(&mut self, trie: &mut dyn TrieMut) -> TrieResult<()> {
         self.text.commit(trie)?;
         Ok(())
@@ -57,6 +73,10 @@ impl ShardCache {
         self.text.get(a, db)
     }
 
+    pub fn prefetch_shard_text(&self, a: &ShardTextAddress, text: Option<ShardText>) {
+        self.text.prefetch(a, text)
+    }
+
     pub fn shard_text_mut(&self, a: &ShardTextAddress, db: &dyn Trie) -> TrieResult<RefMut<'_, ShardText>> {
         self.text.get_mut(a, db)
     }
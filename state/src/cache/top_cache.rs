@@ -14,15 +14,73 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use super::WriteBack;
+use super::{Access, Changes, WriteBack};
 use crate::{
     Account, ActionData, Metadata, MetadataAddress, RegularAccount, RegularAccountAddress, Shard, ShardAddress,
+    ShardTextAddress,
 };
 use ckey::Address;
 use merkle_trie::{Result as TrieResult, Trie, TrieMut};
 use primitives::H256;
 use std::cell::RefMut;
 
+/// The addresses accessed in the top level state and the shard states while recording.
+#[derive(Debug, Default)]
+pub struct TopAccess {
+    account: Access<Address>,
+    regular_account: Access<RegularAccountAddress>,
+    metadata: Access<MetadataAddress>,
+    shard: Access<ShardAddress>,
+    action_data: Access<H256>,
+    /// The texts of all the shards, whose addresses are distinct across the shards.
+    shard_text: Access<ShardTextAddress>,
+}
+
+impl TopAccess {
+    /// Returns true if an address accessed in `self` is written in `earlier`.
+    pub fn depends_on(&self, earlier: &Self) -> bool {
+        self.account.depends_on(&earlier.account)
+            || self.regular_account.depends_on(&earlier.regular_account)
+            || self.metadata.depends_on(&earlier.metadata)
+            || self.shard.depends_on(&earlier.shard)
+            || self.action_data.depends_on(&earlier.action_data)
+            || self.shard_text.depends_on(&earlier.shard_text)
+    }
+
+    pub fn extend(&mut self, other: Self) {
+        self.account.extend(other.account);
+        self.regular_account.extend(other.regular_account);
+        self.metadata.extend(other.metadata);
+        self.shard.extend(other.shard);
+        self.action_data.extend(other.action_data);
+        self.shard_text.extend(other.shard_text);
+    }
+
+    pub fn extend_shard_text(&mut self, shard_text: Access<ShardTextAddress>) {
+        self.shard_text.extend(shard_text);
+    }
+
+    pub fn shard_text(&self) -> &Access<ShardTextAddress> {
+        &self.shard_text
+    }
+
+    /// The accounts written while recording, in ascending order.
+    pub fn written_accounts(&self) -> Vec<Address> {
+        let mut accounts: Vec<Address> = self.account.writes().cloned().collect();
+        accounts.sort();
+        accounts
+    }
+}
+
+/// The written entries of the top level state, taken from a `TopCache` to be applied to another one.
+pub struct TopChanges {
+    account: Changes<Address, Account>,
+    regular_account: Changes<RegularAccountAddress, RegularAccount>,
+    metadata: Changes<MetadataAddress, Metadata>,
+    shard: Changes<ShardAddress, Shard>,
+    action_data: Changes<H256, ActionData>,
+}
+
 pub struct TopCache {
     account: WriteBack<Account>,
     regular_account: WriteBack<RegularAccount>,
@@ -72,6 +130,47 @@ impl TopCache {
         self.action_data.revert_to_checkpoint();
     }
 
+    pub fn start_recording(&self) {
+        self.account.start_recording();
+        self.regular_account.start_recording();
+        self.metadata.start_recording();
+        self.shard.start_recording();
+        self.action_data.start_recording();
+    }
+
+    pub fn finish_recording(&self) -> TopAccess {
+        TopAccess {
+            account: self.account.finish_recording(),
+            regular_account: self.regular_account.finish_recording(),
+            metadata: self.metadata.finish_recording(),
+            shard: self.shard.finish_recording(),
+            action_data: self.action_data.finish_recording(),
+            shard_text: Default::default(),
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.account.is_recording()
+    }
+
+    pub fn changes(&self, access: &TopAccess) -> TopChanges {
+        TopChanges {
+            account: self.account.changes(&access.account),
+            regular_account: self.regular_account.changes(&access.regular_account),
+            metadata: self.metadata.changes(&access.metadata),
+            shard: self.shard.changes(&access.shard),
+            action_data: self.action_data.changes(&access.action_data),
+        }
+    }
+
+    pub fn apply_changes(&self, changes: TopChanges) {
+        self.account.apply_changes(changes.account);
+        self.regular_account.apply_changes(changes.regular_account);
+        self.metadata.apply_changes(changes.metadata);
+        self.shard.apply_changes(changes.shard);
+        self.action_data.apply_changes(changes.action_data);
+    }
+
     pub fn commit<'db>(&mut self, trie: &mut (dyn TrieMut + 'db)) -> TrieResult<()> {
         self.account.commit(trie)?;
         self.regular_account.commit(trie)?;
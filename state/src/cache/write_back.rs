@@ -18,10 +18,12 @@ use super::CacheableItem;
 use merkle_trie::{Result as TrieResult, Trie, TrieMut};
 use std::cell::{RefCell, RefMut};
 use std::collections::hash_map::Entry as HashMapEntry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::AsRef;
 use std::fmt;
+use std::hash::Hash;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::vec::Vec;
 
 static TOUCHED_COUNT: AtomicUsize = AtomicUsize::new(0);
@@ -29,14 +31,29 @@ fn touched_count() -> usize {
     TOUCHED_COUNT.fetch_add(1, Ordering::SeqCst)
 }
 
-#[derive(Clone, Debug)]
-struct Entry<Item>
+#[derive(Debug)]
+pub struct Entry<Item>
 where
     Item: CacheableItem, {
     item: Option<Item>,
     is_dirty: bool,
     /// Touched time
-    touched: usize,
+    ///
+    /// Reading an entry updates it, so it is atomic to let the clones of a cache share the entries.
+    touched: AtomicUsize,
+}
+
+impl<Item> Clone for Entry<Item>
+where
+    Item: CacheableItem,
+{
+    fn clone(&self) -> Self {
+        Self {
+            item: self.item.clone(),
+            is_dirty: self.is_dirty,
+            touched: AtomicUsize::new(self.touched()),
+        }
+    }
 }
 
 // Account cache item. Contains account data and
@@ -50,7 +67,7 @@ where
         Self {
             item,
             is_dirty: true,
-            touched: touched_count(),
+            touched: AtomicUsize::new(touched_count()),
         }
     }
 
@@ -64,18 +81,75 @@ where
         Self {
             item,
             is_dirty: false,
-            touched,
+            touched: AtomicUsize::new(touched),
         }
     }
+
+    fn touched(&self) -> usize {
+        self.touched.load(Ordering::Relaxed)
+    }
+
+    fn touch(&self) {
+        self.touched.store(touched_count(), Ordering::Relaxed);
+    }
+}
+
+/// The addresses read and written while a `WriteBack` is recording.
+#[derive(Debug)]
+pub struct Access<Address>
+where
+    Address: Eq + Hash, {
+    reads: HashSet<Address>,
+    writes: HashSet<Address>,
 }
 
+impl<Address> Access<Address>
+where
+    Address: Eq + Hash,
+{
+    /// Returns true if an address accessed in `self` is written in `earlier`.
+    pub fn depends_on(&self, earlier: &Self) -> bool {
+        self.reads.iter().chain(self.writes.iter()).any(|address| earlier.writes.contains(address))
+    }
+
+    pub fn extend(&mut self, other: Self) {
+        self.reads.extend(other.reads);
+        self.writes.extend(other.writes);
+    }
+
+    pub fn writes(&self) -> impl Iterator<Item = &Address> {
+        self.writes.iter()
+    }
+}
+
+impl<Address> Default for Access<Address>
+where
+    Address: Eq + Hash,
+{
+    fn default() -> Self {
+        Self {
+            reads: Default::default(),
+            writes: Default::default(),
+        }
+    }
+}
+
+/// The entries of the written addresses, which can be moved to another `WriteBack`.
+pub type Changes<Address, Item> = Vec<(Address, Entry<Item>)>;
+
 type CheckPoints<Address, Item> = Vec<HashMap<Address, Option<Entry<Item>>>>;
 pub struct WriteBack<Item>
 where
     Item: CacheableItem, {
-    cache: RefCell<HashMap<Item::Address, Entry<Item>>>,
+    // Shared by the clones until one of them is modified, so cloning a cache is cheap.
+    cache: RefCell<Arc<HashMap<Item::Address, Entry<Item>>>>,
+    // The clean entries read from the trie and not in `cache`.
+    // They are kept apart so that reading an address doesn't copy the shared cache.
+    read_cache: RefCell<HashMap<Item::Address, Entry<Item>>>,
     // The original item is preserved in
     checkpoints: RefCell<CheckPoints<Item::Address, Item>>,
+    // Unlike the cache, it is not affected by reverting checkpoints.
+    recording: RefCell<Option<Access<Item::Address>>>,
 }
 
 impl<Item> WriteBack<Item>
@@ -85,7 +159,9 @@ where
     pub fn new() -> Self {
         Self {
             cache: Default::default(),
+            read_cache: Default::default(),
             checkpoints: Default::default(),
+            recording: Default::default(),
         }
     }
 
@@ -126,9 +202,10 @@ where
 
     pub fn revert_to_checkpoint(&mut self) {
         if let Some(mut checkpoint) = self.checkpoints.get_mut().pop() {
+            let cache = Arc::make_mut(self.cache.get_mut());
             for (k, v) in checkpoint.drain() {
                 match v {
-                    Some(v) => match self.cache.get_mut().entry(k) {
+                    Some(v) => match cache.entry(k) {
                         HashMapEntry::Occupied(mut e) => {
                             *e.get_mut() = v;
                         }
@@ -137,7 +214,7 @@ where
                         }
                     },
                     None => {
-                        if let HashMapEntry::Occupied(e) = self.cache.get_mut().entry(k) {
+                        if let HashMapEntry::Occupied(e) = cache.entry(k) {
                             if e.get().is_dirty {
                                 e.remove();
                             }
@@ -155,7 +232,8 @@ where
         // In all other cases item is read as clean first, and after that made
         // dirty in and added to the checkpoint with `note_cache`.
         let is_dirty = item.is_dirty;
-        let old_value = self.cache.borrow_mut().insert(*address, item);
+        self.read_cache.borrow_mut().remove(address);
+        let old_value = self.cache_mut().insert(*address, item);
         if !is_dirty {
             return
         }
@@ -165,9 +243,53 @@ where
     }
 
     pub fn remove(&self, address: &Item::Address) {
+        self.record_write(address);
         self.insert(address, Entry::<Item>::new_dirty(None))
     }
 
+    /// Starts recording the addresses that are read and written.
+    pub fn start_recording(&self) {
+        *self.recording.borrow_mut() = Some(Default::default());
+    }
+
+    /// Stops recording and returns the addresses accessed since `start_recording`.
+    pub fn finish_recording(&self) -> Access<Item::Address> {
+        self.recording.borrow_mut().take().unwrap_or_default()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.borrow().is_some()
+    }
+
+    fn record_read(&self, address: &Item::Address) {
+        if let Some(access) = self.recording.borrow_mut().as_mut() {
+            access.reads.insert(*address);
+        }
+    }
+
+    fn record_write(&self, address: &Item::Address) {
+        if let Some(access) = self.recording.borrow_mut().as_mut() {
+            access.writes.insert(*address);
+        }
+    }
+
+    /// Returns the entries of the addresses written in `access`.
+    pub fn changes(&self, access: &Access<Item::Address>) -> Changes<Item::Address, Item> {
+        let cache = self.cache.borrow();
+        access.writes.iter().filter_map(|address| cache.get(address).map(|entry| (*address, entry.clone()))).collect()
+    }
+
+    /// Overwrites the entries with the changes taken from another `WriteBack` on the same state.
+    pub fn apply_changes(&self, changes: Changes<Item::Address, Item>) {
+        for (address, entry) in changes {
+            if entry.is_dirty {
+                self.note(&address);
+            }
+            self.read_cache.borrow_mut().remove(&address);
+            self.cache_mut().insert(address, entry);
+        }
+    }
+
     fn note(&self, address: &Item::Address) {
         if let Some(ref mut checkpoint) = self.checkpoints.borrow_mut().last_mut() {
             checkpoint.entry(*address).or_insert_with(|| self.cache.borrow().get(address).cloned());
@@ -175,7 +297,10 @@ where
     }
 
     pub fn commit<'db>(&mut self, trie: &mut (dyn TrieMut + 'db)) -> TrieResult<()> {
-        let mut cache = self.cache.borrow_mut();
+        if self.cache.get_mut().values().all(|entry| !entry.is_dirty) {
+            return Ok(())
+        }
+        let cache = Arc::make_mut(self.cache.get_mut());
         for (address, ref mut a) in cache.iter_mut().filter(|&(_, ref a)| a.is_dirty) {
             a.is_dirty = false;
             match &a.item {
@@ -194,30 +319,51 @@ where
     /// First searches for account in the local, then the shared cache.
     /// Populates local cache if nothing found.
     pub fn get(&self, a: &Item::Address, db: &dyn Trie) -> TrieResult<Option<Item>> {
+        self.record_read(a);
         // check local cache first
-        if let Some(cached_item) = self.cache.borrow_mut().get_mut(a) {
-            cached_item.touched = touched_count();
+        if let Some(cached_item) = self.cache.borrow().get(a) {
+            cached_item.touch();
+            return Ok(cached_item.item.clone())
+        }
+        if let Some(cached_item) = self.read_cache.borrow().get(a) {
+            cached_item.touch();
             return Ok(cached_item.item.clone())
         }
 
-        // not found in the cache, get from the DB and insert into cache
+        // not found in the cache, get from the DB and insert into the read cache
         let maybe_item = db.get(a.as_ref())?.map(|bytes| ::rlp::decode::<Item>(&bytes).unwrap());
-        self.insert(a, Entry::<Item>::new_clean(maybe_item.clone()));
+        self.read_cache.borrow_mut().insert(*a, Entry::<Item>::new_clean(maybe_item.clone()));
         Ok(maybe_item)
     }
 
+    /// Inserts the item read from the trie in advance, unless `a` is already in the cache.
+    pub fn prefetch(&self, a: &Item::Address, item: Option<Item>) {
+        if self.cache.borrow().contains_key(a) {
+            return
+        }
+        self.read_cache.borrow_mut().entry(*a).or_insert_with(|| Entry::<Item>::new_clean(item));
+    }
+
     /// Pull item `a` in our cache from the trie DB.
     /// If it doesn't exist, make item equal the evaluation of `default`.
     pub fn get_mut(&self, a: &Item::Address, db: &dyn Trie) -> TrieResult<RefMut<'_, Item>> {
+        self.record_write(a);
         let contains_key = self.cache.borrow().contains_key(a);
         if !contains_key {
-            let maybe_item = db.get(a.as_ref())?.map(|bytes| ::rlp::decode::<Item>(&bytes).unwrap());
-            self.insert(a, Entry::<Item>::new_clean(maybe_item));
+            let read = self.read_cache.borrow_mut().remove(a);
+            let entry = match read {
+                Some(entry) => entry,
+                None => {
+                    let maybe_item = db.get(a.as_ref())?.map(|bytes| ::rlp::decode::<Item>(&bytes).unwrap());
+                    Entry::<Item>::new_clean(maybe_item)
+                }
+            };
+            self.insert(a, entry);
         }
         self.note(a);
 
         // at this point the entry is guaranteed to be in the cache.
-        Ok(RefMut::map(self.cache.borrow_mut(), |c| {
+        Ok(RefMut::map(self.cache_mut(), |c| {
             let entry = c.get_mut(a).expect("entry known to exist in the cache; qed");
 
             match &mut entry.item {
@@ -227,15 +373,20 @@ where
 
             // set the dirty flag after changing data.
             entry.is_dirty = true;
-            entry.touched = touched_count();
+            *entry.touched.get_mut() = touched_count();
             entry.item.as_mut().expect("Required item must always exist; qed")
         }))
     }
 
     pub fn create<F: FnOnce() -> Item>(&self, a: &Item::Address, f: F) -> TrieResult<Item> {
-        if let Some(cached) = self.cache.borrow().get(a) {
-            assert!(cached.item.is_none());
+        {
+            let cache = self.cache.borrow();
+            let read_cache = self.read_cache.borrow();
+            if let Some(cached) = cache.get(a).or_else(|| read_cache.get(a)) {
+                assert!(cached.item.is_none());
+            }
         }
+        self.record_write(a);
         let item = f();
         self.insert(a, Entry::<Item>::new_dirty(Some(item.clone())));
 
@@ -244,21 +395,26 @@ where
 
     pub fn items(&self) -> Vec<(usize, Item::Address, Option<Item>)> {
         let cache = self.cache.borrow();
+        let read_cache = self.read_cache.borrow();
         cache
             .iter()
+            .chain(read_cache.iter())
             .map(|(addr, entry)| {
                 if entry.is_dirty {
                     unreachable!("The cache must be committed before called items")
                 } else {
-                    (entry.touched, *addr, entry.item.clone())
+                    (entry.touched(), *addr, entry.item.clone())
                 }
             })
             .collect()
     }
 
+    fn cache_mut(&self) -> RefMut<'_, HashMap<Item::Address, Entry<Item>>> {
+        RefMut::map(self.cache.borrow_mut(), |cache| Arc::make_mut(cache))
+    }
+
     fn len(&self) -> usize {
-        let cache = self.cache.borrow();
-        cache.len()
+        self.cache.borrow().len() + self.read_cache.borrow().len()
     }
 }
 
@@ -278,8 +434,62 @@ where
     fn clone(&self) -> Self {
         assert_eq!(0, self.checkpoints.borrow().len());
         Self {
-            cache: self.cache.clone(),
+            cache: RefCell::new(Arc::clone(&self.cache.borrow())),
+            read_cache: self.read_cache.clone(),
             checkpoints: RefCell::new(vec![]),
+            recording: Default::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Account, StateDB};
+    use cdb::AsHashDB;
+    use ckey::Address;
+    use merkle_trie::TrieFactory;
+    use primitives::H256;
+
+    #[test]
+    fn clones_share_the_entries_until_modified() {
+        let address = Address::random();
+        let original = WriteBack::<Account>::new_with_iter(vec![(address, Account::new(10, 0))].into_iter());
+        let cloned = original.clone();
+        assert!(Arc::ptr_eq(&original.cache.borrow(), &cloned.cache.borrow()));
+
+        cloned.remove(&address);
+        assert!(!Arc::ptr_eq(&original.cache.borrow(), &cloned.cache.borrow()));
+        assert_eq!(Some(10), original.cache.borrow()[&address].item.as_ref().map(Account::balance));
+        assert!(cloned.cache.borrow()[&address].item.is_none());
+    }
+
+    #[test]
+    fn reading_a_missing_address_does_not_copy_the_shared_entries() {
+        let mut db = StateDB::new_with_memorydb();
+        let mut root = H256::new();
+        let _ = TrieFactory::create(db.as_hashdb_mut(), &mut root);
+        let trie = TrieFactory::readonly(db.as_hashdb(), &root).unwrap();
+
+        let address = Address::random();
+        let original = WriteBack::<Account>::new_with_iter(vec![(address, Account::new(10, 0))].into_iter());
+        let cloned = original.clone();
+        assert_eq!(Ok(None), cloned.get(&Address::random(), &trie));
+        assert!(Arc::ptr_eq(&original.cache.borrow(), &cloned.cache.borrow()));
+
+        cloned.get_mut(&address, &trie).unwrap().set_balance(20);
+        assert!(!Arc::ptr_eq(&original.cache.borrow(), &cloned.cache.borrow()));
+        assert_eq!(Ok(Some(10)), original.get(&address, &trie).map(|account| account.map(|account| account.balance())));
+    }
+
+    #[test]
+    fn only_the_written_addresses_are_recorded_as_writes() {
+        let written = Address::random();
+        let cache = WriteBack::<Account>::new_with_iter(vec![(written, Account::new(10, 0))].into_iter());
+
+        cache.start_recording();
+        cache.remove(&written);
+        let access = cache.finish_recording();
+        assert_eq!(vec![&written], access.writes().collect::<Vec<_>>());
+    }
+}
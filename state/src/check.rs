@@ -0,0 +1,264 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::item::Prefix;
+use crate::{Metadata, Shard, ShardAddress};
+use ccrypto::{blake256, BLAKE_NULL_RLP};
+use cdb::HashDB;
+use ctypes::ShardId;
+use merkle_trie::{Trie, TrieFactory};
+use primitives::H256;
+use rlp::{DecoderError, Rlp};
+
+const LEAF_ITEMS: usize = 2;
+const BRANCH_ITEMS: usize = 17;
+
+/// What was found while walking every node of a trie.
+#[derive(Debug, Default, PartialEq)]
+pub struct TrieCheck {
+    pub nodes: usize,
+    pub leaves: usize,
+    /// Nodes referenced by their parents but absent from the database.
+    pub missing_nodes: Vec<H256>,
+    /// Nodes whose content doesn't match their hash or can't be decoded.
+    pub corrupted_nodes: Vec<H256>,
+}
+
+impl TrieCheck {
+    pub fn is_intact(&self) -> bool {
+        self.missing_nodes.is_empty() && self.corrupted_nodes.is_empty()
+    }
+}
+
+/// What was found in the top-level trie and the shard tries of a state.
+#[derive(Debug, Default)]
+pub struct StateCheck {
+    pub top: TrieCheck,
+    pub accounts: usize,
+    pub regular_accounts: usize,
+    pub action_data: usize,
+    pub shards: Vec<(ShardId, TrieCheck)>,
+    /// Items that can't be decoded or disagree with each other.
+    pub inconsistencies: Vec<String>,
+}
+
+impl StateCheck {
+    pub fn is_healthy(&self) -> bool {
+        self.top.is_intact()
+            && self.shards.iter().all(|(_, shard)| shard.is_intact())
+            && self.inconsistencies.is_empty()
+    }
+}
+
+/// Walks the state at `root`, verifying the hash of every node, and cross-checks the metadata with the shards.
+pub fn check_state(db: &dyn HashDB, root: H256) -> StateCheck {
+    let mut accounts = 0;
+    let mut regular_accounts = 0;
+    let mut action_data = 0;
+    let mut metadata = Vec::new();
+    let mut shard_items = 0;
+    let mut unknown_items = 0;
+    let top = walk_trie(db, root, |value| match prefix(value) {
+        Some(prefix) if prefix == Prefix::Account as u8 => accounts += 1,
+        Some(prefix) if prefix == Prefix::RegularAccount as u8 => regular_accounts += 1,
+        Some(prefix) if prefix == Prefix::Metadata as u8 => metadata.push(value.to_vec()),
+        Some(prefix) if prefix == Prefix::Shard as u8 => shard_items += 1,
+        Some(_) => unknown_items += 1,
+        None => action_data += 1,
+    });
+
+    let mut check = StateCheck {
+        top,
+        accounts,
+        regular_accounts,
+        action_data,
+        ..Default::default()
+    };
+    if unknown_items != 0 {
+        check.inconsistencies.push(format!("{} items of the top-level trie have unknown prefixes", unknown_items));
+    }
+    let metadata = match metadata.as_slice() {
+        [metadata] => match rlp::decode::<Metadata>(metadata) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                check.inconsistencies.push(format!("The metadata cannot be decoded: {}", e));
+                return check
+            }
+        },
+        [] if check.top.is_intact() => {
+            check.inconsistencies.push("The metadata is missing".to_string());
+            return check
+        }
+        // The metadata may be under a missing node.
+        [] => return check,
+        _ => {
+            check.inconsistencies.push(format!("There are {} metadata items", metadata.len()));
+            return check
+        }
+    };
+
+    // The terminated shards are still counted in the metadata.
+    let number_of_shards = *metadata.number_of_shards();
+    if check.top.is_intact() && shard_items > usize::from(number_of_shards) {
+        check
+            .inconsistencies
+            .push(format!("The metadata counts {} shards but the trie has {}", number_of_shards, shard_items));
+    }
+    for shard_id in 0..number_of_shards {
+        let shard = match shard(db, root, shard_id) {
+            Ok(Some(shard)) => shard,
+            // The shard is terminated.
+            Ok(None) => continue,
+            Err(e) => {
+                check.inconsistencies.push(format!("Shard #{} cannot be read: {}", shard_id, e));
+                continue
+            }
+        };
+        let mut unknown_items = 0;
+        let shard_check = walk_trie(db, *shard.root(), |value| {
+            if prefix(value) != Some(Prefix::ShardText as u8) {
+                unknown_items += 1;
+            }
+        });
+        if unknown_items != 0 {
+            check.inconsistencies.push(format!("{} items of shard #{} have unknown prefixes", unknown_items, shard_id));
+        }
+        check.shards.push((shard_id, shard_check));
+    }
+    check
+}
+
+fn shard(db: &dyn HashDB, root: H256, shard_id: ShardId) -> Result<Option<Shard>, String> {
+    let trie = TrieFactory::readonly(db, &root).map_err(|e| e.to_string())?;
+    match trie.get(&*ShardAddress::new(shard_id)).map_err(|e| e.to_string())? {
+        Some(value) => rlp::decode(&value).map(Some).map_err(|e| e.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// Visits every node reachable from `root` and calls `on_leaf` with the value of every leaf.
+fn walk_trie(db: &dyn HashDB, root: H256, mut on_leaf: impl FnMut(&[u8])) -> TrieCheck {
+    let mut check = TrieCheck::default();
+    if root == BLAKE_NULL_RLP {
+        return check
+    }
+
+    let mut pending = vec![root];
+    while let Some(hash) = pending.pop() {
+        let node = match db.get(&hash) {
+            Some(node) => node,
+            None => {
+                check.missing_nodes.push(hash);
+                continue
+            }
+        };
+        check.nodes += 1;
+        if blake256(&node) != hash {
+            check.corrupted_nodes.push(hash);
+            continue
+        }
+
+        let rlp = Rlp::new(&node);
+        let decoded = match rlp.item_count() {
+            Ok(LEAF_ITEMS) => rlp.at(1).and_then(|value| value.data()).map(|value| {
+                check.leaves += 1;
+                on_leaf(value);
+            }),
+            Ok(BRANCH_ITEMS) => children(&rlp).map(|children| pending.extend(children)),
+            Ok(_) => Err(DecoderError::RlpIncorrectListLen),
+            Err(e) => Err(e),
+        };
+        if decoded.is_err() {
+            check.corrupted_nodes.push(hash);
+        }
+    }
+    check
+}
+
+fn children(branch: &Rlp<'_>) -> Result<Vec<H256>, DecoderError> {
+    let mut children = Vec::new();
+    for index in 1..BRANCH_ITEMS {
+        let child = branch.at(index)?;
+        if !child.is_empty() {
+            children.push(child.as_val()?);
+        }
+    }
+    Ok(children)
+}
+
+/// The prefix of an item, or `None` if the value isn't a prefixed item, like action data.
+fn prefix(value: &[u8]) -> Option<u8> {
+    let rlp = Rlp::new(value);
+    if !rlp.is_list() {
+        return None
+    }
+    rlp.val_at(0).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helpers::{get_temp_state, get_temp_state_with_metadata};
+    use crate::{StateWithCache, TopState};
+    use cdb::{AsHashDB, DBValue};
+    use ckey::Address;
+    use merkle_trie::TrieMut;
+    use rlp::Encodable;
+
+    #[test]
+    fn healthy_state() {
+        let mut state = get_temp_state_with_metadata();
+        state.add_balance(&Address::random(), 10).unwrap();
+        state.add_balance(&Address::random(), 20).unwrap();
+        let (mut db, mut root) = state.commit_and_into_db().unwrap();
+        {
+            let mut trie = TrieFactory::from_existing(db.as_hashdb_mut(), &mut root).unwrap();
+            trie.insert(&*ShardAddress::new(0), &Shard::new(BLAKE_NULL_RLP, vec![], vec![]).rlp_bytes()).unwrap();
+        }
+
+        let check = check_state(db.as_hashdb(), root);
+        assert!(check.is_healthy(), "{:?}", check);
+        assert_eq!(2, check.accounts);
+        assert_eq!(4, check.top.leaves);
+        assert_eq!(vec![(0, TrieCheck::default())], check.shards);
+    }
+
+    #[test]
+    fn terminated_shard() {
+        let state = get_temp_state_with_metadata();
+        let (db, root) = state.commit_and_into_db().unwrap();
+
+        let check = check_state(db.as_hashdb(), root);
+        assert!(check.is_healthy(), "{:?}", check);
+        assert!(check.shards.is_empty());
+    }
+
+    #[test]
+    fn missing_and_corrupted_nodes() {
+        let mut state = get_temp_state();
+        state.add_balance(&Address::random(), 10).unwrap();
+        let (mut db, root) = state.commit_and_into_db().unwrap();
+
+        db.as_hashdb_mut().remove(&root);
+        let check = check_state(db.as_hashdb(), root);
+        assert_eq!(vec![root], check.top.missing_nodes);
+        assert!(!check.is_healthy());
+
+        db.as_hashdb_mut().emplace(root, DBValue::from_slice(b"garbage"));
+        let check = check_state(db.as_hashdb(), root);
+        assert_eq!(vec![root], check.top.corrupted_nodes);
+    }
+}
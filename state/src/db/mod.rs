@@ -14,6 +14,11 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+mod node_cache;
+mod recorder;
 mod state_db;
+mod witness;
 
+pub use self::recorder::Recorder;
 pub use self::state_db::StateDB;
+pub use self::witness::Witness;
@@ -0,0 +1,141 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use cdb::DBValue;
+use lru_cache::LruCache;
+use parking_lot::Mutex;
+use primitives::H256;
+use std::sync::Arc;
+
+/// Size-bounded cache of trie nodes, shared by all the `StateDB`s cloned from the same one.
+///
+/// A node is addressed by the hash of its contents, so a cached node never becomes stale and the
+/// cache outlives the blocks that read it.
+#[derive(Clone)]
+pub struct NodeCache {
+    inner: Arc<Mutex<Inner>>,
+}
+
+struct Inner {
+    nodes: LruCache<H256, DBValue>,
+    size: usize,
+    capacity: usize,
+    /// The capacity lowered to keep the memory budget.
+    restriction: Option<usize>,
+}
+
+impl Inner {
+    fn capacity(&self) -> usize {
+        self.restriction.map_or(self.capacity, |restriction| restriction.min(self.capacity))
+    }
+
+    fn evict(&mut self) {
+        while self.size > self.capacity() {
+            let (_, evicted) = self.nodes.remove_lru().expect("The size is not zero");
+            self.size -= evicted.len();
+        }
+    }
+}
+
+impl NodeCache {
+    /// Creates a cache that holds at most `capacity` bytes of nodes.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                nodes: LruCache::new(usize::max_value()),
+                size: 0,
+                capacity,
+                restriction: None,
+            })),
+        }
+    }
+
+    pub fn get(&self, key: &H256) -> Option<DBValue> {
+        self.inner.lock().nodes.get_mut(key).cloned()
+    }
+
+    pub fn insert(&self, key: H256, value: DBValue) {
+        let mut inner = self.inner.lock();
+        if value.len() > inner.capacity() {
+            return
+        }
+        inner.size += value.len();
+        if let Some(old) = inner.nodes.insert(key, value) {
+            inner.size -= old.len();
+        }
+        inner.evict();
+    }
+
+    /// Evicts the nodes to keep at most `capacity` bytes until it is called again.
+    /// `None` restores the capacity given at the creation.
+    pub fn restrict(&self, capacity: Option<usize>) {
+        let mut inner = self.inner.lock();
+        inner.restriction = capacity;
+        inner.evict();
+    }
+
+    /// The total size of the cached nodes in bytes.
+    pub fn size(&self) -> usize {
+        self.inner.lock().size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn least_recently_used_nodes_are_evicted() {
+        let cache = NodeCache::new(10);
+        let (first, second, third) = (H256::random(), H256::random(), H256::random());
+        cache.insert(first, DBValue::from_slice(&[1; 4]));
+        cache.insert(second, DBValue::from_slice(&[2; 4]));
+        assert!(cache.get(&first).is_some());
+
+        cache.insert(third, DBValue::from_slice(&[3; 4]));
+        assert_eq!(8, cache.size());
+        assert!(cache.get(&first).is_some());
+        assert_eq!(None, cache.get(&second));
+        assert!(cache.get(&third).is_some());
+    }
+
+    #[test]
+    fn restriction_evicts_the_nodes_until_lifted() {
+        let cache = NodeCache::new(10);
+        let (first, second, third) = (H256::random(), H256::random(), H256::random());
+        cache.insert(first, DBValue::from_slice(&[1; 4]));
+        cache.insert(second, DBValue::from_slice(&[2; 4]));
+
+        cache.restrict(Some(4));
+        assert_eq!(4, cache.size());
+        assert_eq!(None, cache.get(&first));
+        cache.insert(third, DBValue::from_slice(&[3; 4]));
+        assert_eq!(None, cache.get(&second));
+
+        cache.restrict(None);
+        cache.insert(first, DBValue::from_slice(&[1; 4]));
+        assert_eq!(8, cache.size());
+    }
+
+    #[test]
+    fn node_larger_than_the_capacity_is_not_cached() {
+        let cache = NodeCache::new(10);
+        let key = H256::random();
+        cache.insert(key, DBValue::from_slice(&[1; 11]));
+        assert_eq!(None, cache.get(&key));
+        assert_eq!(0, cache.size());
+    }
+}
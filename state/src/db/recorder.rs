@@ -0,0 +1,80 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use cdb::{AsHashDB, DBValue, HashDB};
+use parking_lot::Mutex;
+use primitives::H256;
+use std::collections::HashMap;
+
+/// Read-only `HashDB` which records the trie nodes read through it.
+///
+/// The nodes recorded while looking up a key are the proof of the value against the trie root.
+pub struct Recorder<'db> {
+    db: &'db dyn HashDB,
+    nodes: Mutex<Vec<DBValue>>,
+}
+
+impl<'db> Recorder<'db> {
+    pub fn new(db: &'db dyn HashDB) -> Self {
+        Self {
+            db,
+            nodes: Default::default(),
+        }
+    }
+
+    /// Returns the nodes recorded so far and clears them.
+    pub fn drain(&self) -> Vec<DBValue> {
+        self.nodes.lock().drain(..).collect()
+    }
+}
+
+impl<'db> HashDB for Recorder<'db> {
+    fn keys(&self) -> HashMap<H256, i32> {
+        self.db.keys()
+    }
+
+    fn get(&self, key: &H256) -> Option<DBValue> {
+        let value = self.db.get(key)?;
+        self.nodes.lock().push(value.clone());
+        Some(value)
+    }
+
+    fn contains(&self, key: &H256) -> bool {
+        self.db.contains(key)
+    }
+
+    fn insert(&mut self, _value: &[u8]) -> H256 {
+        unreachable!("Recorder is read-only")
+    }
+
+    fn emplace(&mut self, _key: H256, _value: DBValue) {
+        unreachable!("Recorder is read-only")
+    }
+
+    fn remove(&mut self, _key: &H256) {
+        unreachable!("Recorder is read-only")
+    }
+}
+
+impl<'db> AsHashDB for Recorder<'db> {
+    fn as_hashdb(&self) -> &dyn HashDB {
+        self
+    }
+
+    fn as_hashdb_mut(&mut self) -> &mut dyn HashDB {
+        self
+    }
+}
@@ -30,22 +30,35 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+use super::node_cache::NodeCache;
+use super::witness::Witness;
 use crate::cache::{GlobalCache, ShardCache, TopCache};
 use crate::impls::TopLevelState;
-use cdb::{new_journaldb, Algorithm, AsHashDB, DatabaseError, HashDB, JournalDB};
+use cdb::{new_journaldb, Algorithm, AsHashDB, DBValue, DatabaseError, HashDB, JournalDB};
+use ckey::Address;
 use ctypes::ShardId;
 use kvdb::DBTransaction;
 use kvdb_memorydb;
-use primitives::H256;
-use std::collections::HashMap;
+use primitives::{Bytes, H256};
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 
+/// The number of the recent states whose caches are kept as overlays.
+const OVERLAY_DEPTH: usize = 16;
+
 /// State database abstraction.
 pub struct StateDB {
     /// Backing database.
     db: Box<dyn JournalDB>,
     cache: GlobalCache,
     current_hash: Option<H256>,
+    /// Trie nodes read from `db`, kept across blocks.
+    node_cache: Option<NodeCache>,
+    /// Records the trie nodes read through this and its clones.
+    witness: Option<Witness>,
+    /// The caches of the recent states by their roots, from the oldest. A reorg within them restores the cache of the
+    /// new best state instead of reading the items from the trie again.
+    overlays: VecDeque<(H256, GlobalCache)>,
 }
 
 impl StateDB {
@@ -55,6 +68,17 @@ impl StateDB {
             db,
             cache: Default::default(),
             current_hash: None,
+            node_cache: None,
+            witness: None,
+            overlays: VecDeque::new(),
+        }
+    }
+
+    /// Create a new instance wrapping `JournalDB`, which caches up to `node_cache_size` bytes of trie nodes.
+    pub fn new_with_node_cache(db: Box<dyn JournalDB>, node_cache_size: usize) -> StateDB {
+        StateDB {
+            node_cache: Some(NodeCache::new(node_cache_size)),
+            ..Self::new(db)
         }
     }
 
@@ -64,6 +88,15 @@ impl StateDB {
         Self::new(db)
     }
 
+    /// Creates a state database holding only the trie nodes of a witness.
+    pub fn from_witness(nodes: &[Bytes]) -> Self {
+        let mut db = Self::new_with_memorydb();
+        for node in nodes {
+            db.insert(node);
+        }
+        db
+    }
+
     /// Journal all recent operations under the given era and ID.
     pub fn journal_under(&mut self, batch: &mut DBTransaction, now: u64, id: H256) -> Result<u32, DatabaseError> {
         let records = self.db.journal_under(batch, now, &id)?;
@@ -84,14 +117,49 @@ impl StateDB {
         self.cache.shard_caches()
     }
 
+    /// The addresses of the accounts in the cache, from the least recently used.
+    pub fn hot_accounts(&self) -> Vec<Address> {
+        self.cache.hot_accounts()
+    }
+
     pub fn override_state(&mut self, state: &TopLevelState) {
+        let root = state.root();
         self.cache.override_cache(state.top_cache(), state.shard_caches());
-        self.current_hash = Some(state.root());
+        self.current_hash = Some(root);
+
+        self.overlays.retain(|(hash, _)| *hash != root);
+        if self.overlays.len() == OVERLAY_DEPTH {
+            self.overlays.pop_front();
+        }
+        self.overlays.push_back((root, self.cache.clone()));
     }
 
+    fn overlay(&self, root: &H256) -> Option<&GlobalCache> {
+        self.overlays.iter().rev().find(|(hash, _)| hash == root).map(|(_, cache)| cache)
+    }
+
+    /// Replaces the cache with the one of `root` if it's one of the recent states, or clears it.
+    /// Returns true if the cache is replaced.
+    pub fn revert_to(&mut self, root: &H256) -> bool {
+        match self.overlay(root).cloned() {
+            Some(cache) => {
+                self.cache = cache;
+                self.current_hash = Some(*root);
+                true
+            }
+            None => {
+                self.clear_cache();
+                false
+            }
+        }
+    }
+
+    /// The clone has the cache of `hash` if it's the current state or one of the recent states.
     pub fn clone(&self, hash: &H256) -> Self {
         let (cache, current_hash) = if self.current_hash.as_ref() == Some(hash) {
             (self.cache.clone(), self.current_hash)
+        } else if let Some(cache) = self.overlay(hash) {
+            (cache.clone(), Some(*hash))
         } else {
             (Default::default(), None)
         };
@@ -100,22 +168,102 @@ impl StateDB {
             db: self.db.boxed_clone(),
             cache,
             current_hash,
+            node_cache: self.node_cache.clone(),
+            witness: self.witness.clone(),
+            overlays: VecDeque::new(),
+        }
+    }
+
+    /// Clones the backing database without the cache of the state items.
+    pub fn clone_without_cache(&self) -> Self {
+        Self {
+            node_cache: self.node_cache.clone(),
+            witness: self.witness.clone(),
+            ..Self::new(self.db.boxed_clone())
+        }
+    }
+
+    /// The size of the trie nodes cached across blocks in bytes.
+    pub fn node_cache_size(&self) -> usize {
+        self.node_cache.as_ref().map_or(0, NodeCache::size)
+    }
+
+    /// Lowers the capacity of the trie node cache shared by the clones, or restores it if `capacity` is `None`.
+    pub fn restrict_node_cache(&self, capacity: Option<usize>) {
+        if let Some(node_cache) = &self.node_cache {
+            node_cache.restrict(capacity);
         }
     }
 
     pub fn clear_cache(&mut self) {
         self.cache.clear();
     }
+
+    /// Records the trie nodes read from now on to `witness`.
+    /// Use it on a clone without the cache since the cached items are not read from the trie.
+    pub fn record_witness(&mut self, witness: Witness) {
+        self.witness = Some(witness);
+    }
+
+    fn get_node(&self, key: &H256) -> Option<DBValue> {
+        let node_cache = match &self.node_cache {
+            Some(node_cache) => node_cache,
+            None => return self.db.as_hashdb().get(key),
+        };
+        if let Some(value) = node_cache.get(key) {
+            return Some(value)
+        }
+        let value = self.db.as_hashdb().get(key)?;
+        node_cache.insert(*key, value.clone());
+        Some(value)
+    }
+}
+
+impl HashDB for StateDB {
+    fn keys(&self) -> HashMap<H256, i32> {
+        self.db.as_hashdb().keys()
+    }
+
+    fn get(&self, key: &H256) -> Option<DBValue> {
+        let value = self.get_node(key)?;
+        if let Some(witness) = &self.witness {
+            witness.record_read(key, &value);
+        }
+        Some(value)
+    }
+
+    fn contains(&self, key: &H256) -> bool {
+        self.db.as_hashdb().contains(key)
+    }
+
+    fn insert(&mut self, value: &[u8]) -> H256 {
+        let key = self.db.as_hashdb_mut().insert(value);
+        if let Some(witness) = &self.witness {
+            witness.record_created(key);
+        }
+        key
+    }
+
+    fn emplace(&mut self, key: H256, value: DBValue) {
+        if let Some(witness) = &self.witness {
+            witness.record_created(key);
+        }
+        self.db.as_hashdb_mut().emplace(key, value)
+    }
+
+    fn remove(&mut self, key: &H256) {
+        self.db.as_hashdb_mut().remove(key)
+    }
 }
 
 impl AsHashDB for StateDB {
     /// Conversion method to interpret self as `HashDB` reference
     fn as_hashdb(&self) -> &dyn HashDB {
-        self.db.as_hashdb()
+        self
     }
 
     /// Conversion method to interpret self as mutable `HashDB` reference
     fn as_hashdb_mut(&mut self) -> &mut dyn HashDB {
-        self.db.as_hashdb_mut()
+        self
     }
 }
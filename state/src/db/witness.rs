@@ -0,0 +1,54 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use cdb::DBValue;
+use parking_lot::Mutex;
+use primitives::{Bytes, H256};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+/// The trie nodes read while executing a block.
+///
+/// They are enough to execute the block again without the rest of the state.
+/// The nodes created by the execution are not recorded since the execution creates them again.
+#[derive(Clone, Default)]
+pub struct Witness {
+    inner: Arc<Mutex<WitnessInner>>,
+}
+
+#[derive(Default)]
+struct WitnessInner {
+    read: BTreeMap<H256, DBValue>,
+    created: HashSet<H256>,
+}
+
+impl Witness {
+    pub(super) fn record_read(&self, key: &H256, value: &DBValue) {
+        let mut inner = self.inner.lock();
+        if !inner.created.contains(key) {
+            inner.read.entry(*key).or_insert_with(|| value.clone());
+        }
+    }
+
+    pub(super) fn record_created(&self, key: H256) {
+        self.inner.lock().created.insert(key);
+    }
+
+    /// Returns the recorded nodes in the order of their hashes.
+    pub fn nodes(&self) -> Vec<Bytes> {
+        self.inner.lock().read.values().map(|node| node.to_vec()).collect()
+    }
+}
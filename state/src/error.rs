@@ -16,12 +16,15 @@
 
 use ctypes::errors::RuntimeError;
 use merkle_trie::TrieError;
+use rlp::DecoderError;
 use std::fmt;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Error {
     Trie(TrieError),
     Runtime(RuntimeError),
+    /// An item in the trie cannot be decoded.
+    Rlp(DecoderError),
 }
 
 impl fmt::Display for Error {
@@ -29,6 +32,7 @@ impl fmt::Display for Error {
         match self {
             Error::Trie(err) => err.fmt(f),
             Error::Runtime(err) => err.fmt(f),
+            Error::Rlp(err) => err.fmt(f),
         }
     }
 }
@@ -44,3 +48,9 @@ impl From<RuntimeError> for Error {
         Error::Runtime(err)
     }
 }
+
+impl From<DecoderError> for Error {
+    fn from(err: DecoderError) -> Self {
+        Error::Rlp(err)
+    }
+}
@@ -21,4 +21,4 @@ mod shard_level;
 mod top_level;
 
 pub use self::shard_level::ShardLevelState;
-pub use self::top_level::TopLevelState;
+pub use self::top_level::{AccountProof, ActionDataProof, ShardProof, Speculation, TopLevelState};
@@ -35,12 +35,13 @@
 //! Unconfirmed sub-states are managed with `checkpoint`s which may be canonicalized
 //! or rolled back.
 
-use crate::cache::{ShardCache, TopCache};
+use crate::cache::{Changes, ShardCache, TopAccess, TopCache, TopChanges};
 use crate::checkpoint::{CheckpointId, StateWithCheckpoint};
+use crate::db::Recorder;
 use crate::traits::{ShardState, ShardStateView, StateWithCache, TopState, TopStateView};
 use crate::{
     Account, ActionData, FindActionHandler, Metadata, MetadataAddress, RegularAccount, RegularAccountAddress, Shard,
-    ShardAddress, ShardLevelState, StateDB, StateResult,
+    ShardAddress, ShardLevelState, ShardText, ShardTextAddress, StateDB, StateResult,
 };
 use ccrypto::BLAKE_NULL_RLP;
 use cdb::{AsHashDB, DatabaseError};
@@ -53,8 +54,9 @@ use ctypes::Tracker;
 use ctypes::{BlockNumber, CommonParams, ShardId, TxHash};
 use cvm::ChainTimeInfo;
 use kvdb::DBTransaction;
-use merkle_trie::{Result as TrieResult, TrieError, TrieFactory};
+use merkle_trie::{Result as TrieResult, Trie, TrieError, TrieFactory};
 use primitives::{Bytes, H256};
+use rayon::prelude::*;
 use std::cell::{RefCell, RefMut};
 use std::collections::HashMap;
 
@@ -176,8 +178,56 @@ impl StateWithCache for TopLevelState {
     }
 }
 
+/// Proof of a value in a shard against the state root.
+#[derive(Debug, PartialEq)]
+pub struct ShardProof {
+    /// The nodes of the top-level trie on the path to the shard entry.
+    pub shard_nodes: Vec<Bytes>,
+    /// The root of the shard trie, which is in the shard entry.
+    pub shard_root: H256,
+    /// The nodes of the shard trie on the path to the key.
+    pub value_nodes: Vec<Bytes>,
+    /// `None` if the shard doesn't have the key.
+    pub value: Option<Bytes>,
+}
+
+/// Proof of an action data against the state root.
+#[derive(Debug, PartialEq)]
+pub struct ActionDataProof {
+    /// The nodes of the top-level trie on the path to the key.
+    pub nodes: Vec<Bytes>,
+    /// `None` if the state doesn't have the key.
+    pub value: Option<Bytes>,
+}
+
+/// Proof of an account against the state root.
+#[derive(Debug, PartialEq)]
+pub struct AccountProof {
+    /// The nodes of the top-level trie on the path to the account.
+    pub nodes: Vec<Bytes>,
+    /// `None` if the account doesn't exist.
+    pub value: Option<Bytes>,
+}
+
+/// A transaction executed against a snapshot of the state by `TopLevelState::speculate`.
+pub struct Speculation {
+    result: StateResult<()>,
+    access: TopAccess,
+    changes: TopChanges,
+    shard_changes: Vec<(ShardId, Changes<ShardTextAddress, ShardText>)>,
+}
+
+impl Speculation {
+    /// The addresses that the transaction read and wrote.
+    pub fn access(&self) -> &TopAccess {
+        &self.access
+    }
+}
+
 const FEE_CHECKPOINT: CheckpointId = 123;
 const ACTION_CHECKPOINT: CheckpointId = 130;
+const SHARD_TRANSACTION_CHECKPOINT: CheckpointId = 140;
+const SPECULATION_CHECKPOINT: CheckpointId = 150;
 
 impl StateWithCheckpoint for TopLevelState {
     fn create_checkpoint(&mut self, id: CheckpointId) {
@@ -215,6 +265,29 @@ impl StateWithCheckpoint for TopLevelState {
     }
 }
 
+/// Returns the cache of the shard, creating it if it isn't loaded yet.
+///
+/// A newly created cache has as many checkpoints as the state, so reverting a checkpoint that was
+/// created before the shard was loaded also reverts the changes on the shard. It also starts
+/// recording if the state is recording.
+fn shard_cache_mut(
+    shard_caches: &mut HashMap<ShardId, ShardCache>,
+    checkpoint_depth: usize,
+    is_recording: bool,
+    shard_id: ShardId,
+) -> &mut ShardCache {
+    shard_caches.entry(shard_id).or_insert_with(|| {
+        let mut cache = ShardCache::default();
+        for _ in 0..checkpoint_depth {
+            cache.checkpoint();
+        }
+        if is_recording {
+            cache.start_recording();
+        }
+        cache
+    })
+}
+
 impl TopLevelState {
     /// Creates new state with existing state root
     pub fn from_existing(db: StateDB, root: H256) -> Result<Self, TrieError> {
@@ -247,12 +320,38 @@ impl TopLevelState {
         parent_block_number: BlockNumber,
         parent_block_timestamp: u64,
         current_block_timestamp: u64,
+    ) -> StateResult<()> {
+        self.apply_sponsored(
+            tx,
+            signed_hash,
+            signer_public,
+            None,
+            client,
+            parent_block_number,
+            parent_block_timestamp,
+            current_block_timestamp,
+        )
+    }
+
+    /// Execute a given transaction like `apply`, but charges the fee to the sponsor if it is given.
+    #[allow(clippy::too_many_arguments)]
+    pub fn apply_sponsored<C: ChainTimeInfo + FindActionHandler>(
+        &mut self,
+        tx: &Transaction,
+        signed_hash: &TxHash,
+        signer_public: &Public,
+        sponsor_public: Option<&Public>,
+        client: &C,
+        parent_block_number: BlockNumber,
+        parent_block_timestamp: u64,
+        current_block_timestamp: u64,
     ) -> StateResult<()> {
         self.create_checkpoint(FEE_CHECKPOINT);
         let result = self.apply_internal(
             tx,
             signed_hash,
             signer_public,
+            sponsor_public,
             client,
             parent_block_number,
             parent_block_timestamp,
@@ -269,7 +368,13 @@ impl TopLevelState {
         result
     }
 
-    fn apply_internal<C: ChainTimeInfo + FindActionHandler>(
+    /// Executes a given transaction without leaving any change, recording the addresses it accessed.
+    ///
+    /// The payments, the regular keys and the shard stores can be executed this way, so `None` is
+    /// returned for the others. The shard texts are recorded along with the top level state, so the
+    /// stores to different shards don't depend on each other. The result stays valid as long as none
+    /// of the accessed addresses is written before it is merged.
+    pub fn speculate<C: ChainTimeInfo + FindActionHandler>(
         &mut self,
         tx: &Transaction,
         signed_hash: &TxHash,
@@ -278,20 +383,107 @@ impl TopLevelState {
         parent_block_number: BlockNumber,
         parent_block_timestamp: u64,
         current_block_timestamp: u64,
-    ) -> StateResult<()> {
-        let fee_payer = if self.regular_account_exists_and_not_null(signer_public)? {
-            let regular_account = self.get_regular_account_mut(signer_public)?;
-            public_to_address(&regular_account.owner_public())
-        } else {
-            let address = public_to_address(signer_public);
+    ) -> Option<Speculation> {
+        match tx.action {
+            Action::Pay {
+                ..
+            }
+            | Action::SetRegularKey {
+                ..
+            }
+            | Action::ShardStore {
+                ..
+            } => {}
+            _ => return None,
+        }
 
-            if !tx.is_master_key_allowed() {
-                let account = self.get_account_mut(&address)?;
-                if account.regular_key().is_some() {
-                    return Err(RuntimeError::CannotUseMasterKey.into())
-                }
+        self.create_checkpoint(SPECULATION_CHECKPOINT);
+        self.start_recording();
+        let result = self.apply(
+            tx,
+            signed_hash,
+            signer_public,
+            client,
+            parent_block_number,
+            parent_block_timestamp,
+            current_block_timestamp,
+        );
+        let access = self.finish_recording();
+        let changes = self.top_cache.changes(&access);
+        let shard_changes = self
+            .shard_caches
+            .iter()
+            .map(|(shard_id, cache)| (*shard_id, cache.changes(access.shard_text())))
+            .filter(|(_, changes)| !changes.is_empty())
+            .collect();
+        self.revert_to_checkpoint(SPECULATION_CHECKPOINT);
+        Some(Speculation {
+            result,
+            access,
+            changes,
+            shard_changes,
+        })
+    }
+
+    /// Applies the changes of a transaction speculated on a snapshot of this state and returns its result.
+    pub fn merge_speculation(&mut self, speculation: Speculation) -> (StateResult<()>, TopAccess) {
+        self.top_cache.apply_changes(speculation.changes);
+        let (depth, is_recording) = (self.id_of_checkpoints.len(), self.top_cache.is_recording());
+        for (shard_id, changes) in speculation.shard_changes {
+            shard_cache_mut(&mut self.shard_caches, depth, is_recording, shard_id).apply_changes(changes);
+        }
+        (speculation.result, speculation.access)
+    }
+
+    /// Starts recording the addresses that are read and written in the top level state and the shard states.
+    pub fn start_recording(&self) {
+        self.top_cache.start_recording();
+        for cache in self.shard_caches.values() {
+            cache.start_recording();
+        }
+    }
+
+    pub fn finish_recording(&self) -> TopAccess {
+        let mut access = self.top_cache.finish_recording();
+        for cache in self.shard_caches.values() {
+            access.extend_shard_text(cache.finish_recording());
+        }
+        access
+    }
+
+    /// Returns the account that the key signs for.
+    /// A regular key signs for its owner, and a master key cannot be used if a regular key is set.
+    fn signing_account(&mut self, public: &Public, is_master_key_allowed: bool) -> StateResult<Address> {
+        if self.regular_account_exists_and_not_null(public)? {
+            let regular_account = self.get_regular_account_mut(public)?;
+            return Ok(public_to_address(&regular_account.owner_public()))
+        }
+        let address = public_to_address(public);
+        if !is_master_key_allowed {
+            let account = self.get_account_mut(&address)?;
+            if account.regular_key().is_some() {
+                return Err(RuntimeError::CannotUseMasterKey.into())
             }
-            address
+        }
+        Ok(address)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn apply_internal<C: ChainTimeInfo + FindActionHandler>(
+        &mut self,
+        tx: &Transaction,
+        signed_hash: &TxHash,
+        signer_public: &Public,
+        sponsor_public: Option<&Public>,
+        client: &C,
+        parent_block_number: BlockNumber,
+        parent_block_timestamp: u64,
+        current_block_timestamp: u64,
+    ) -> StateResult<()> {
+        let fee_payer = self.signing_account(signer_public, tx.is_master_key_allowed())?;
+        let sponsor = match sponsor_public {
+            Some(sponsor_public) => self.signing_account(sponsor_public, false)?,
+            None => fee_payer,
         };
         let seq = self.seq(&fee_payer)?;
 
@@ -306,22 +498,26 @@ impl TopLevelState {
         let fee = tx.fee;
 
         self.inc_seq(&fee_payer)?;
-        self.sub_balance(&fee_payer, fee)?;
+        self.sub_balance(&sponsor, fee)?;
+        let block_number = parent_block_number + 1;
+        self.check_locked_balance(&sponsor, block_number)?;
 
         // The failed transaction also must pay the fee and increase seq.
         self.create_checkpoint(ACTION_CHECKPOINT);
-        let result = self.apply_action(
-            &tx.action,
-            tx.network_id,
-            tx.hash(),
-            signed_hash,
-            &fee_payer,
-            signer_public,
-            client,
-            parent_block_number,
-            parent_block_timestamp,
-            current_block_timestamp,
-        );
+        let result = self
+            .apply_action(
+                &tx.action,
+                tx.network_id,
+                tx.hash(),
+                signed_hash,
+                &fee_payer,
+                signer_public,
+                client,
+                parent_block_number,
+                parent_block_timestamp,
+                current_block_timestamp,
+            )
+            .and_then(|()| self.check_locked_balance(&fee_payer, block_number));
         match &result {
             Ok(()) => {
                 self.discard_checkpoint(ACTION_CHECKPOINT);
@@ -333,19 +529,36 @@ impl TopLevelState {
         result
     }
 
+    fn check_locked_balance(&self, address: &Address, block_number: BlockNumber) -> StateResult<()> {
+        let locked = self.locked_balance(address, block_number)?;
+        if locked == 0 {
+            return Ok(())
+        }
+        let balance = self.balance(address)?;
+        if balance < locked {
+            return Err(RuntimeError::InsufficientUnlockedBalance {
+                address: *address,
+                balance,
+                locked,
+            }
+            .into())
+        }
+        Ok(())
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn apply_action<C: ChainTimeInfo + FindActionHandler>(
         &mut self,
         action: &Action,
         network_id: NetworkId,
-        _tx_hash: TxHash,
+        tx_hash: TxHash,
         signed_hash: &TxHash,
         fee_payer: &Address,
         signer_public: &Public,
         client: &C,
         parent_block_number: BlockNumber,
         parent_block_timestamp: u64,
-        _current_block_timestamp: u64,
+        current_block_timestamp: u64,
     ) -> StateResult<()> {
         let (transaction, approvers) = match action {
             Action::ShardStore {
@@ -390,14 +603,73 @@ impl TopLevelState {
                 self.change_shard_users(*shard_id, users, fee_payer)?;
                 return Ok(())
             }
+            Action::TerminateShard {
+                shard_id,
+            } => {
+                self.terminate_shard(*shard_id, fee_payer)?;
+                return Ok(())
+            }
             Action::Custom {
                 handler_id,
                 bytes,
             } => {
                 let handler = client.find_action_handler_for(*handler_id).expect("Unknown custom parsel applied!");
-                handler.execute(bytes, self, fee_payer, signer_public)?;
+                handler.execute(bytes, self, fee_payer, signer_public, parent_block_number + 1)?;
+                return Ok(())
+            }
+            Action::Batch {
+                actions,
+            } => {
+                // The partial effects are reverted with ACTION_CHECKPOINT when any action fails.
+                for (index, action) in actions.iter().enumerate() {
+                    self.apply_action(
+                        action,
+                        network_id,
+                        tx_hash,
+                        signed_hash,
+                        fee_payer,
+                        signer_public,
+                        client,
+                        parent_block_number,
+                        parent_block_timestamp,
+                        current_block_timestamp,
+                    )
+                    .map_err(|err| {
+                        ctrace!(STATE, "The action {} of the batch failed: {:?}", index, err);
+                        err
+                    })?;
+                }
+                return Ok(())
+            }
+            Action::BurnSeq => {
+                // The seq and the fee are already consumed.
                 return Ok(())
             }
+            Action::Scheduled {
+                activation,
+                action,
+            } => {
+                let block_number = parent_block_number + 1;
+                if block_number < *activation {
+                    return Err(RuntimeError::NotActivatedYet {
+                        activation: *activation,
+                        block_number,
+                    }
+                    .into())
+                }
+                return self.apply_action(
+                    action,
+                    network_id,
+                    tx_hash,
+                    signed_hash,
+                    fee_payer,
+                    signer_public,
+                    client,
+                    parent_block_number,
+                    parent_block_timestamp,
+                    current_block_timestamp,
+                )
+            }
         };
         self.apply_shard_transaction(
             &transaction,
@@ -409,6 +681,8 @@ impl TopLevelState {
         )
     }
 
+    /// Applies the transaction to its related shards, which must all exist before any of them is touched.
+    /// The changes are made under one checkpoint, so they are reverted if the transaction fails.
     pub fn apply_shard_transaction<C: ChainTimeInfo>(
         &mut self,
         transaction: &ShardTransaction,
@@ -418,39 +692,62 @@ impl TopLevelState {
         parent_block_number: BlockNumber,
         parent_block_timestamp: u64,
     ) -> StateResult<()> {
-        for shard_id in transaction.related_shards() {
+        let prepared = transaction
+            .related_shards()
+            .into_iter()
+            .map(|shard_id| {
+                let shard_root = self.shard_root(shard_id)?.ok_or_else(|| RuntimeError::InvalidShardId(shard_id))?;
+                let shard_users = self.shard_users(shard_id)?.expect("Shard must exist");
+                Ok((shard_id, shard_root, shard_users))
+            })
+            .collect::<StateResult<Vec<_>>>()?;
+
+        self.create_checkpoint(SHARD_TRANSACTION_CHECKPOINT);
+        let result = prepared.into_iter().try_for_each(|(shard_id, shard_root, shard_users)| {
             self.apply_shard_transaction_to_shard(
                 transaction,
                 shard_id,
+                shard_root,
+                &shard_users,
                 sender,
                 approvers,
                 client,
                 parent_block_number,
                 parent_block_timestamp,
-            )?;
+            )
+        });
+        match &result {
+            Ok(()) => {
+                self.discard_checkpoint(SHARD_TRANSACTION_CHECKPOINT);
+            }
+            Err(_) => {
+                ctrace!(STATE, "Shard transaction {:?} is aborted", transaction.tracker());
+                self.revert_to_checkpoint(SHARD_TRANSACTION_CHECKPOINT);
+            }
         }
-        Ok(())
+        result
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn apply_shard_transaction_to_shard<C: ChainTimeInfo>(
         &mut self,
         transaction: &ShardTransaction,
         shard_id: ShardId,
+        shard_root: H256,
+        shard_users: &[Address],
         sender: &Address,
         approvers: &[Address],
         client: &C,
         parent_block_number: BlockNumber,
         parent_block_timestamp: u64,
     ) -> StateResult<()> {
-        let shard_root = self.shard_root(shard_id)?.ok_or_else(|| RuntimeError::InvalidShardId(shard_id))?;
-        let shard_users = self.shard_users(shard_id)?.expect("Shard must exist");
-
-        let shard_cache = self.shard_caches.entry(shard_id).or_default();
+        let (depth, is_recording) = (self.id_of_checkpoints.len(), self.top_cache.is_recording());
+        let shard_cache = shard_cache_mut(&mut self.shard_caches, depth, is_recording, shard_id);
         let mut shard_level_state = ShardLevelState::from_existing(shard_id, &mut self.db, shard_root, shard_cache)?;
         shard_level_state.apply(
             &transaction,
             sender,
-            &shard_users,
+            shard_users,
             approvers,
             client,
             parent_block_number,
@@ -458,6 +755,54 @@ impl TopLevelState {
         )
     }
 
+    /// Reads the shard texts that the transactions touch into the shard caches in advance.
+    ///
+    /// Shard tries are disjoint, so the shards are read in parallel on the rayon thread pool.
+    /// The transactions are applied afterward in order, reading the texts from the caches.
+    pub fn prefetch_shard_texts(&mut self, transactions: &[ShardTransaction]) -> StateResult<()> {
+        let mut addresses: HashMap<ShardId, Vec<ShardTextAddress>> = HashMap::new();
+        for transaction in transactions {
+            let tracker = transaction.tracker();
+            for shard_id in transaction.related_shards() {
+                addresses.entry(shard_id).or_default().push(ShardTextAddress::new(tracker, shard_id));
+            }
+        }
+        if addresses.len() < 2 {
+            return Ok(())
+        }
+
+        let mut jobs = Vec::with_capacity(addresses.len());
+        for (shard_id, addresses) in addresses {
+            // The transactions to a missing shard fail without reading it.
+            if let Some(shard_root) = self.shard_root(shard_id)? {
+                jobs.push((shard_id, shard_root, self.db.borrow().clone_without_cache(), addresses));
+            }
+        }
+        let prefetched = jobs
+            .into_par_iter()
+            .map(|(shard_id, shard_root, db, addresses)| -> StateResult<_> {
+                let trie = TrieFactory::readonly(db.as_hashdb(), &shard_root)?;
+                let texts = addresses
+                    .into_iter()
+                    .map(|address| {
+                        let bytes = trie.get(address.as_ref())?;
+                        let text = bytes.map(|bytes| rlp::decode::<ShardText>(&bytes)).transpose()?;
+                        Ok((address, text))
+                    })
+                    .collect::<StateResult<Vec<_>>>()?;
+                Ok((shard_id, texts))
+            })
+            .collect::<StateResult<Vec<_>>>()?;
+        let (depth, is_recording) = (self.id_of_checkpoints.len(), self.top_cache.is_recording());
+        for (shard_id, texts) in prefetched {
+            let shard_cache = shard_cache_mut(&mut self.shard_caches, depth, is_recording, shard_id);
+            for (address, text) in texts {
+                shard_cache.prefetch_shard_text(&address, text);
+            }
+        }
+        Ok(())
+    }
+
     fn create_shard_level_state(
         &mut self,
         shard_id: ShardId,
@@ -466,7 +811,8 @@ impl TopLevelState {
     ) -> StateResult<()> {
         const DEFAULT_SHARD_ROOT: H256 = BLAKE_NULL_RLP;
         {
-            let shard_cache = self.shard_caches.entry(shard_id).or_default();
+            let (depth, is_recording) = (self.id_of_checkpoints.len(), self.top_cache.is_recording());
+            let shard_cache = shard_cache_mut(&mut self.shard_caches, depth, is_recording, shard_id);
             ShardLevelState::from_existing(shard_id, &mut self.db, DEFAULT_SHARD_ROOT, shard_cache)?;
         }
 
@@ -520,6 +866,60 @@ impl TopLevelState {
     pub fn top_cache(&self) -> &TopCache {
         &self.top_cache
     }
+
+    /// Returns the proof of the value at `key` in the shard, or `None` if the shard doesn't exist.
+    ///
+    /// The proof has two levels: the shard entry against the state root, and the value against the
+    /// shard root. The changes that are not committed yet are not included.
+    pub fn shard_proof(&self, shard_id: ShardId, key: &H256) -> TrieResult<Option<ShardProof>> {
+        let db = self.db.borrow();
+        let recorder = Recorder::new(db.as_hashdb());
+
+        let shard_address = ShardAddress::new(shard_id);
+        let shard = match TrieFactory::readonly(&recorder, &self.root)?.get(shard_address.as_ref())? {
+            Some(shard) => rlp::decode::<Shard>(&shard).unwrap(),
+            None => return Ok(None),
+        };
+        let shard_nodes = recorder.drain();
+
+        let shard_root = *shard.root();
+        let value = TrieFactory::readonly(&recorder, &shard_root)?.get(key.as_ref())?;
+        let value_nodes = recorder.drain();
+
+        Ok(Some(ShardProof {
+            shard_nodes: shard_nodes.into_iter().map(|node| node.to_vec()).collect(),
+            shard_root,
+            value_nodes: value_nodes.into_iter().map(|node| node.to_vec()).collect(),
+            value: value.map(|value| value.to_vec()),
+        }))
+    }
+
+    /// Returns the proof of the action data at `key`, which is an absence proof if the key doesn't exist.
+    ///
+    /// The changes that are not committed yet are not included.
+    pub fn action_data_proof(&self, key: &H256) -> TrieResult<ActionDataProof> {
+        let db = self.db.borrow();
+        let recorder = Recorder::new(db.as_hashdb());
+        let value = TrieFactory::readonly(&recorder, &self.root)?.get(key.as_ref())?;
+        Ok(ActionDataProof {
+            nodes: recorder.drain().into_iter().map(|node| node.to_vec()).collect(),
+            value: value.map(|value| value.to_vec()),
+        })
+    }
+
+    /// Returns the proof of the account, which is an absence proof if the account doesn't exist.
+    ///
+    /// The changes that are not committed yet are not included.
+    pub fn account_proof(&self, a: &Address) -> TrieResult<AccountProof> {
+        let db = self.db.borrow();
+        let recorder = Recorder::new(db.as_hashdb());
+        let value = TrieFactory::readonly(&recorder, &self.root)?.get(a.as_ref())?;
+        Ok(AccountProof {
+            nodes: recorder.drain().into_iter().map(|node| node.to_vec()).collect(),
+            value: value.map(|value| value.to_vec()),
+        })
+    }
+
     pub fn shard_caches(&self) -> &HashMap<ShardId, ShardCache> {
         &self.shard_caches
     }
@@ -528,13 +928,12 @@ impl TopLevelState {
         self.root
     }
 
-    #[cfg(test)]
-    fn set_balance(&mut self, a: &Address, balance: u64) -> TrieResult<()> {
+    pub fn set_balance(&mut self, a: &Address, balance: u64) -> TrieResult<()> {
         self.get_account_mut(a)?.set_balance(balance);
         Ok(())
     }
-    #[cfg(test)]
-    fn set_seq(&mut self, a: &Address, seq: u64) -> TrieResult<()> {
+
+    pub fn set_seq(&mut self, a: &Address, seq: u64) -> TrieResult<()> {
         self.get_account_mut(a)?.set_seq(seq);
         Ok(())
     }
@@ -671,6 +1070,18 @@ impl TopState for TopLevelState {
         self.set_shard_users(shard_id, users.to_vec())
     }
 
+    fn terminate_shard(&mut self, shard_id: ShardId, sender: &Address) -> StateResult<()> {
+        let owners = self.shard_owners(shard_id)?.ok_or_else(|| RuntimeError::InvalidShardId(shard_id))?;
+        if !owners.contains(sender) {
+            return Err(RuntimeError::InsufficientPermission.into())
+        }
+
+        ctrace!(STATE, "shard({}) terminated", shard_id);
+        // The texts are left in the shard trie, which is no longer reachable from the state root.
+        self.top_cache.remove_shard(&ShardAddress::new(shard_id));
+        Ok(())
+    }
+
     fn set_shard_root(&mut self, shard_id: ShardId, new_root: H256) -> StateResult<()> {
         let mut shard = self.get_shard_mut(shard_id)?;
         shard.set_root(new_root);
@@ -738,6 +1149,53 @@ impl TopState for TopLevelState {
         Ok(())
     }
 
+    fn schedule_params(
+        &mut self,
+        metadata_seq: u64,
+        activation: BlockNumber,
+        params: CommonParams,
+        current_block_number: BlockNumber,
+    ) -> StateResult<()> {
+        let mut metadata = self.get_metadata_mut()?;
+        if metadata.seq() != metadata_seq {
+            return Err(RuntimeError::InvalidSeq(Mismatch {
+                found: metadata_seq,
+                expected: metadata.seq(),
+            })
+            .into())
+        }
+        if activation <= current_block_number {
+            return Err(RuntimeError::FailedToHandleCustomAction(format!(
+                "The activation block {} must be later than the current block {}",
+                activation, current_block_number
+            ))
+            .into())
+        }
+        if let Some((pending, _)) = metadata.scheduled_params() {
+            return Err(RuntimeError::FailedToHandleCustomAction(format!(
+                "Another change of the params is already scheduled at block {}",
+                pending
+            ))
+            .into())
+        }
+
+        metadata.schedule_params(activation, params);
+        metadata.increase_seq();
+        Ok(())
+    }
+
+    fn activate_scheduled_params(&mut self, block_number: BlockNumber) -> StateResult<()> {
+        let activated = match self.metadata()? {
+            Some(metadata) => metadata.scheduled_params().map_or(false, |(activation, _)| activation <= block_number),
+            None => false,
+        };
+        if activated {
+            let mut metadata = self.get_metadata_mut()?;
+            metadata.activate_scheduled_params(block_number);
+        }
+        Ok(())
+    }
+
     fn update_term_params(&mut self) -> StateResult<()> {
         let mut metadata = self.get_metadata_mut()?;
         metadata.update_term_params();
@@ -760,6 +1218,7 @@ mod tests_state {
 
     use super::*;
     use crate::tests::helpers::{empty_top_state, get_memory_db, get_temp_state, get_temp_state_db};
+    use crate::Witness;
 
     #[test]
     fn work_when_cloned() {
@@ -882,6 +1341,34 @@ mod tests_state {
         assert_eq!(Ok(1), state.seq(&a));
     }
 
+    #[test]
+    fn cache_of_a_recent_state_is_restored() {
+        let a = Address::random();
+        let mut db = get_temp_state_db();
+        let cached_balance = |db: &StateDB, root: &H256| {
+            let accounts = db.clone(root).top_cache().cached_accounts();
+            let account = accounts.into_iter().find(|(address, _)| *address == a).and_then(|(_, account)| account);
+            account.map(|account| account.balance())
+        };
+
+        let mut state = empty_top_state(db.clone(&H256::zero()));
+        assert_eq!(Ok(()), state.add_balance(&a, 10));
+        let first = state.commit().unwrap();
+        db.override_state(&state);
+        assert_eq!(Ok(()), state.add_balance(&a, 10));
+        let second = state.commit().unwrap();
+        db.override_state(&state);
+
+        assert_eq!(Some(10), cached_balance(&db, &first));
+        assert_eq!(Some(20), cached_balance(&db, &second));
+        assert_eq!(None, cached_balance(&db, &H256::random()));
+
+        assert!(db.revert_to(&first));
+        assert_eq!(Some(10), cached_balance(&db, &first));
+        assert!(!db.revert_to(&H256::random()));
+        assert!(db.top_cache().cached_accounts().is_empty());
+    }
+
     #[test]
     fn remove() {
         let a = Address::default();
@@ -1094,6 +1581,30 @@ mod tests_state {
         let mut state = get_temp_state();
         assert_eq!(Ok(BLAKE_NULL_RLP), state.commit());
     }
+
+    #[test]
+    fn changes_are_applied_again_with_only_the_witness() {
+        let a = Address::random();
+        let b = Address::random();
+        let (db, root) = {
+            let mut state = get_temp_state();
+            assert_eq!(Ok(()), state.add_balance(&a, 100));
+            assert_eq!(Ok(()), state.add_balance(&b, 100));
+            state.commit_and_into_db().unwrap()
+        };
+
+        let witness = Witness::default();
+        let mut recording_db = db.clone_without_cache();
+        recording_db.record_witness(witness.clone());
+        let mut state = TopLevelState::from_existing(recording_db, root).unwrap();
+        assert_eq!(Ok(()), state.add_balance(&a, 20));
+        let expected_root = state.commit().unwrap();
+
+        let mut stateless = TopLevelState::from_existing(StateDB::from_witness(&witness.nodes()), root).unwrap();
+        assert_eq!(Ok(100), stateless.balance(&a));
+        assert_eq!(Ok(()), stateless.add_balance(&a, 20));
+        assert_eq!(Ok(expected_root), stateless.commit());
+    }
 }
 
 #[cfg(test)]
@@ -1103,7 +1614,9 @@ mod tests_tx {
 
     use super::*;
     use crate::tests::helpers::{get_temp_state, get_test_client};
-    use crate::StateError;
+    use crate::{StateError, Vesting};
+    use ccrypto::blake256;
+    use rlp::Encodable;
 
     fn address() -> (Address, Public, Private) {
         let keypair = Random.generate().unwrap();
@@ -1171,6 +1684,226 @@ mod tests_tx {
         ]);
     }
 
+    #[test]
+    fn apply_sponsored_pay_charges_the_fee_to_the_sponsor() {
+        let mut state = get_temp_state();
+
+        let (sender, sender_public, _) = address();
+        let (sponsor, sponsor_public, _) = address();
+        set_top_level_state!(state, [
+            (account: sender => balance: 10),
+            (account: sponsor => balance: 20)
+        ]);
+
+        let receiver = 1u64.into();
+        let tx = transaction!(fee: 5, pay!(receiver, 10));
+        assert_eq!(
+            Ok(()),
+            state.apply_sponsored(
+                &tx,
+                &H256::random().into(),
+                &sender_public,
+                Some(&sponsor_public),
+                &get_test_client(),
+                0,
+                0,
+                0
+            )
+        );
+
+        check_top_level_state!(state, [
+            (account: sender => (seq: 1, balance: 0)),
+            (account: sponsor => (seq: 0, balance: 15)),
+            (account: receiver => (seq: 0, balance: 10))
+        ]);
+    }
+
+    #[test]
+    fn apply_sponsored_fails_if_the_sponsor_cannot_pay_the_fee() {
+        let mut state = get_temp_state();
+
+        let (sender, sender_public, _) = address();
+        let (sponsor, sponsor_public, _) = address();
+        set_top_level_state!(state, [
+            (account: sender => balance: 20),
+            (account: sponsor => balance: 4)
+        ]);
+
+        let tx = transaction!(fee: 5, pay!(1u64.into(), 10));
+        assert_eq!(
+            Err(RuntimeError::InsufficientBalance {
+                address: sponsor,
+                balance: 4,
+                cost: 5,
+            }
+            .into()),
+            state.apply_sponsored(
+                &tx,
+                &H256::random().into(),
+                &sender_public,
+                Some(&sponsor_public),
+                &get_test_client(),
+                0,
+                0,
+                0
+            )
+        );
+
+        check_top_level_state!(state, [
+            (account: sender => (seq: 0, balance: 20)),
+            (account: sponsor => (seq: 0, balance: 4))
+        ]);
+    }
+
+    #[test]
+    fn speculated_pay_is_applied_when_merged() {
+        let mut state = get_temp_state();
+
+        let (sender, sender_public, _) = address();
+        set_top_level_state!(state, [(account: sender => balance: 20)]);
+
+        let receiver = 1u64.into();
+        let tx = transaction!(fee: 5, pay!(receiver, 10));
+        let speculation =
+            state.speculate(&tx, &H256::random().into(), &sender_public, &get_test_client(), 0, 0, 0).unwrap();
+        check_top_level_state!(state, [
+            (account: sender => (seq: 0, balance: 20)),
+            (account: receiver => (seq: 0, balance: 0))
+        ]);
+
+        let (result, _) = state.merge_speculation(speculation);
+        assert_eq!(Ok(()), result);
+        check_top_level_state!(state, [
+            (account: sender => (seq: 1, balance: 5)),
+            (account: receiver => (seq: 0, balance: 10))
+        ]);
+    }
+
+    #[test]
+    fn payments_to_the_same_receiver_conflict() {
+        let mut state = get_temp_state();
+
+        let (sender1, sender1_public, _) = address();
+        let (sender2, sender2_public, _) = address();
+        let (sender3, sender3_public, _) = address();
+        set_top_level_state!(state, [
+            (account: sender1 => balance: 20),
+            (account: sender2 => balance: 20),
+            (account: sender3 => balance: 20)
+        ]);
+
+        let receiver = 1u64.into();
+        let client = get_test_client();
+        let tx1 = transaction!(fee: 5, pay!(receiver, 10));
+        let tx2 = transaction!(fee: 5, pay!(receiver, 10));
+        let tx3 = transaction!(fee: 5, pay!(2u64.into(), 10));
+        let first = state.clone().speculate(&tx1, &H256::random().into(), &sender1_public, &client, 0, 0, 0).unwrap();
+        let second = state.clone().speculate(&tx2, &H256::random().into(), &sender2_public, &client, 0, 0, 0).unwrap();
+        let third = state.clone().speculate(&tx3, &H256::random().into(), &sender3_public, &client, 0, 0, 0).unwrap();
+
+        assert!(second.access().depends_on(first.access()));
+        assert!(!third.access().depends_on(first.access()));
+    }
+
+    #[test]
+    fn stores_to_different_shards_are_speculated_independently() {
+        let mut state = get_temp_state();
+
+        let (sender1, sender1_public, _) = address();
+        let (sender2, sender2_public, _) = address();
+        let (sender3, sender3_public, _) = address();
+        set_top_level_state!(state, [
+            (account: sender1 => balance: 20),
+            (account: sender2 => balance: 20),
+            (account: sender3 => balance: 20)
+        ]);
+        state.create_shard_level_state(0, vec![sender1], vec![]).unwrap();
+        state.create_shard_level_state(1, vec![sender2], vec![]).unwrap();
+
+        let store = |shard_id, content: &str| Action::ShardStore {
+            network_id: "tc".into(),
+            shard_id,
+            content: content.to_string(),
+        };
+        let client = get_test_client();
+        let tx1 = transaction!(fee: 5, store(0, "first"));
+        let tx2 = transaction!(fee: 5, store(1, "second"));
+        let tx3 = transaction!(fee: 5, store(0, "first"));
+        let first = state.clone().speculate(&tx1, &H256::random().into(), &sender1_public, &client, 0, 0, 0).unwrap();
+        let second = state.clone().speculate(&tx2, &H256::random().into(), &sender2_public, &client, 0, 0, 0).unwrap();
+        let third = state.clone().speculate(&tx3, &H256::random().into(), &sender3_public, &client, 0, 0, 0).unwrap();
+        assert!(!second.access().depends_on(first.access()));
+        assert!(third.access().depends_on(first.access()));
+
+        assert_eq!(Ok(()), state.merge_speculation(first).0);
+        assert_eq!(Ok(()), state.merge_speculation(second).0);
+        let first_tracker = Option::<ShardTransaction>::from(tx1.action).unwrap().tracker();
+        let second_tracker = Option::<ShardTransaction>::from(tx2.action).unwrap().tracker();
+        assert_eq!(Ok(Some(ShardText::new("first"))), state.shard_text(0, first_tracker));
+        assert_eq!(Ok(Some(ShardText::new("second"))), state.shard_text(1, second_tracker));
+        check_top_level_state!(state, [
+            (account: sender1 => (seq: 1, balance: 15)),
+            (account: sender2 => (seq: 1, balance: 15))
+        ]);
+    }
+
+    #[test]
+    fn creating_a_shard_is_not_speculated() {
+        let mut state = get_temp_state();
+
+        let (sender, sender_public, _) = address();
+        set_top_level_state!(state, [(account: sender => balance: 20)]);
+
+        let tx = transaction!(fee: 5, Action::CreateShard { users: vec![] });
+        assert!(state.speculate(&tx, &H256::random().into(), &sender_public, &get_test_client(), 0, 0, 0).is_none());
+    }
+
+    #[test]
+    fn cannot_spend_locked_balance() {
+        let mut state = get_temp_state();
+
+        let (sender, sender_public, _) = address();
+        set_top_level_state!(state, [(account: sender => balance: 20)]);
+        let vesting = Vesting::new(15, 0, 10, 20);
+        assert_eq!(Ok(()), state.update_action_data(&Vesting::key(&sender), vesting.rlp_bytes()));
+
+        let receiver = 1u64.into();
+        let tx = transaction!(fee: 5, pay!(receiver, 10));
+        assert_eq!(
+            Err(RuntimeError::InsufficientUnlockedBalance {
+                address: sender,
+                balance: 5,
+                locked: 15,
+            }
+            .into()),
+            state.apply(&tx, &H256::random().into(), &sender_public, &get_test_client(), 0, 0, 0)
+        );
+
+        check_top_level_state!(state, [
+            (account: sender => (seq: 1, balance: 15)),
+            (account: receiver)
+        ]);
+    }
+
+    #[test]
+    fn can_spend_unlocked_balance() {
+        let mut state = get_temp_state();
+
+        let (sender, sender_public, _) = address();
+        set_top_level_state!(state, [(account: sender => balance: 20)]);
+        let vesting = Vesting::new(15, 0, 10, 20);
+        assert_eq!(Ok(()), state.update_action_data(&Vesting::key(&sender), vesting.rlp_bytes()));
+
+        let receiver = 1u64.into();
+        let tx = transaction!(fee: 5, pay!(receiver, 10));
+        assert_eq!(Ok(()), state.apply(&tx, &H256::random().into(), &sender_public, &get_test_client(), 19, 0, 0));
+
+        check_top_level_state!(state, [
+            (account: sender => (seq: 1, balance: 5)),
+            (account: receiver => (seq: 0, balance: 10))
+        ]);
+    }
+
     #[test]
     fn apply_set_regular_key() {
         let mut state = get_temp_state();
@@ -1421,6 +2154,106 @@ mod tests_tx {
         ]);
     }
 
+    #[test]
+    fn apply_batch() {
+        let mut state = get_temp_state();
+        let (sender, sender_public, _) = address();
+        set_top_level_state!(state, [
+            (account: sender => balance: 20)
+        ]);
+
+        let receiver1 = 1u64.into();
+        let receiver2 = 2u64.into();
+        let tx = transaction!(fee: 5, Action::Batch {
+            actions: vec![pay!(receiver1, 10), pay!(receiver2, 3)],
+        });
+        assert_eq!(Ok(()), state.apply(&tx, &H256::random().into(), &sender_public, &get_test_client(), 0, 0, 0));
+
+        check_top_level_state!(state, [
+            (account: sender => (seq: 1, balance: 2)),
+            (account: receiver1 => (seq: 0, balance: 10)),
+            (account: receiver2 => (seq: 0, balance: 3))
+        ]);
+    }
+
+    #[test]
+    fn apply_batch_reverts_all_the_actions_if_one_fails() {
+        let mut state = get_temp_state();
+        let (sender, sender_public, _) = address();
+        set_top_level_state!(state, [
+            (account: sender => balance: 20)
+        ]);
+
+        let receiver1 = 1u64.into();
+        let receiver2 = 2u64.into();
+        let tx = transaction!(fee: 5, Action::Batch {
+            actions: vec![pay!(receiver1, 10), pay!(receiver2, 10)],
+        });
+        assert_eq!(
+            Err(RuntimeError::InsufficientBalance {
+                address: sender,
+                balance: 5,
+                cost: 10,
+            }
+            .into()),
+            state.apply(&tx, &H256::random().into(), &sender_public, &get_test_client(), 0, 0, 0)
+        );
+
+        check_top_level_state!(state, [
+            (account: sender => (seq: 0, balance: 20)),
+            (account: receiver1 => (seq: 0, balance: 0)),
+            (account: receiver2 => (seq: 0, balance: 0))
+        ]);
+    }
+
+    #[test]
+    fn apply_burn_seq_only_consumes_the_seq_and_the_fee() {
+        let mut state = get_temp_state();
+        let (sender, sender_public, _) = address();
+        set_top_level_state!(state, [
+            (account: sender => balance: 20)
+        ]);
+
+        let tx = transaction!(fee: 5, Action::BurnSeq);
+        assert_eq!(Ok(()), state.apply(&tx, &H256::random().into(), &sender_public, &get_test_client(), 0, 0, 0));
+        check_top_level_state!(state, [
+            (account: sender => (seq: 1, balance: 15))
+        ]);
+    }
+
+    #[test]
+    fn apply_scheduled_pay_from_the_activation_block() {
+        let mut state = get_temp_state();
+        let (sender, sender_public, _) = address();
+        set_top_level_state!(state, [
+            (account: sender => balance: 20)
+        ]);
+
+        let receiver = 1u64.into();
+        let tx = transaction!(fee: 5, Action::Scheduled {
+            activation: 10,
+            action: Box::new(pay!(receiver, 10)),
+        });
+        assert_eq!(
+            Err(RuntimeError::NotActivatedYet {
+                activation: 10,
+                block_number: 9,
+            }
+            .into()),
+            state.apply(&tx, &H256::random().into(), &sender_public, &get_test_client(), 8, 0, 0)
+        );
+        check_top_level_state!(state, [
+            (account: sender => (seq: 0, balance: 20)),
+            (account: receiver => (seq: 0, balance: 0))
+        ]);
+
+        assert_eq!(Ok(()), state.apply(&tx, &H256::random().into(), &sender_public, &get_test_client(), 9, 0, 0));
+        check_top_level_state!(state, [
+            (account: sender => (seq: 1, balance: 5)),
+            (account: receiver => (seq: 0, balance: 10))
+        ]);
+    }
+
     #[test]
     fn get_invalid_shard_root() {
         let state = get_temp_state();
@@ -1437,6 +2270,131 @@ mod tests_tx {
         check_top_level_state!(state, [(shard_text: (shard_id, Tracker::from(H256::random())))]);
     }
 
+    #[test]
+    fn shard_transaction_to_invalid_shard_does_not_touch_any_shard() {
+        let mut state = get_temp_state();
+        let (sender, ..) = address();
+
+        let transaction = ShardTransaction::ShardStore {
+            network_id: "tc".into(),
+            shard_id: 3,
+            content: "text".to_string(),
+        };
+        assert_eq!(
+            Err(RuntimeError::InvalidShardId(3).into()),
+            state.apply_shard_transaction(&transaction, &sender, &[], &get_test_client(), 0, 0)
+        );
+        assert!(state.shard_caches().is_empty());
+    }
+
+    #[test]
+    fn revert_shard_changes_made_after_the_checkpoint_on_a_shard_loaded_later() {
+        let mut state = get_temp_state();
+        let (sender, ..) = address();
+        state.create_shard_level_state(0, vec![sender], vec![]).unwrap();
+        let (db, root) = state.commit_and_into_db().unwrap();
+        let mut state = TopLevelState::from_existing(db, root).unwrap();
+
+        let transaction = ShardTransaction::ShardStore {
+            network_id: "tc".into(),
+            shard_id: 0,
+            content: "text".to_string(),
+        };
+        let tracker = transaction.tracker();
+
+        state.create_checkpoint(0);
+        assert_eq!(Ok(()), state.apply_shard_transaction(&transaction, &sender, &[], &get_test_client(), 0, 0));
+        assert!(state.shard_text(0, tracker).unwrap().is_some());
+        state.revert_to_checkpoint(0);
+
+        check_top_level_state!(state, [(shard_text: (0, tracker))]);
+    }
+
+    #[test]
+    fn prefetch_shard_texts_of_multiple_shards() {
+        let mut state = get_temp_state();
+        let (sender, ..) = address();
+        state.create_shard_level_state(0, vec![sender], vec![]).unwrap();
+        state.create_shard_level_state(1, vec![sender], vec![]).unwrap();
+
+        let stored = ShardTransaction::ShardStore {
+            network_id: "tc".into(),
+            shard_id: 0,
+            content: "stored".to_string(),
+        };
+        assert_eq!(Ok(()), state.apply_shard_transaction(&stored, &sender, &[], &get_test_client(), 0, 0));
+        let (db, root) = state.commit_and_into_db().unwrap();
+        let mut state = TopLevelState::from_existing(db, root).unwrap();
+
+        let new = ShardTransaction::ShardStore {
+            network_id: "tc".into(),
+            shard_id: 1,
+            content: "new".to_string(),
+        };
+        assert_eq!(Ok(()), state.prefetch_shard_texts(&[stored.clone(), new.clone()]));
+        assert!(state.shard_caches().contains_key(&0));
+        assert!(state.shard_caches().contains_key(&1));
+
+        assert_eq!(Ok(Some(ShardText::new("stored"))), state.shard_text(0, stored.tracker()));
+        check_top_level_state!(state, [(shard_text: (1, new.tracker()))]);
+    }
+
+    #[test]
+    fn shard_proof_starts_from_the_state_root() {
+        let mut state = get_temp_state();
+        let (sender, ..) = address();
+        state.create_shard_level_state(0, vec![sender], vec![]).unwrap();
+
+        let transaction = ShardTransaction::ShardStore {
+            network_id: "tc".into(),
+            shard_id: 0,
+            content: "text".to_string(),
+        };
+        assert_eq!(Ok(()), state.apply_shard_transaction(&transaction, &sender, &[], &get_test_client(), 0, 0));
+        let root = state.commit().unwrap();
+
+        let key: H256 = ShardTextAddress::new(transaction.tracker(), 0).into();
+        let proof = state.shard_proof(0, &key).unwrap().unwrap();
+        assert_eq!(root, blake256(&proof.shard_nodes[0]));
+        assert_eq!(Ok(Some(proof.shard_root)), state.shard_root(0));
+        assert_eq!(proof.shard_root, blake256(&proof.value_nodes[0]));
+        assert_eq!(Some(ShardText::new("text").rlp_bytes()), proof.value);
+
+        assert_eq!(Ok(None), state.shard_proof(1, &key));
+    }
+
+    #[test]
+    fn action_data_proof_starts_from_the_state_root() {
+        let mut state = get_temp_state();
+        let key = H256::random();
+        state.update_action_data(&key, vec![1, 2, 3]).unwrap();
+        let root = state.commit().unwrap();
+
+        let proof = state.action_data_proof(&key).unwrap();
+        assert_eq!(root, blake256(&proof.nodes[0]));
+        assert_eq!(Some(ActionData::from(vec![1, 2, 3]).rlp_bytes()), proof.value);
+
+        let absence = state.action_data_proof(&H256::random()).unwrap();
+        assert_eq!(root, blake256(&absence.nodes[0]));
+        assert_eq!(None, absence.value);
+    }
+
+    #[test]
+    fn account_proof_starts_from_the_state_root() {
+        let mut state = get_temp_state();
+        let (sender, ..) = address();
+        set_top_level_state!(state, [(account: sender => balance: 20)]);
+        let root = state.commit().unwrap();
+
+        let proof = state.account_proof(&sender).unwrap();
+        assert_eq!(root, blake256(&proof.nodes[0]));
+        assert_eq!(Some(Account::new(20, 0).rlp_bytes()), proof.value);
+
+        let absence = state.account_proof(&Address::random()).unwrap();
+        assert_eq!(root, blake256(&absence.nodes[0]));
+        assert_eq!(None, absence.value);
+    }
+
     #[test]
     #[allow(clippy::cognitive_complexity)]
     fn apply_create_shard() {
@@ -1787,6 +2745,63 @@ mod tests_tx {
         ]);
     }
 
+    #[test]
+    fn terminate_shard() {
+        let (sender, sender_public, _) = address();
+
+        let shard_id = 0;
+        let mut state = get_temp_state();
+        set_top_level_state!(state, [
+            (account: sender => balance: 100),
+            (shard: shard_id => owners: [sender]),
+            (metadata: shards: 1)
+        ]);
+
+        let tx = transaction!(fee: 5, Action::TerminateShard { shard_id });
+        assert_eq!(Ok(()), state.apply(&tx, &H256::random().into(), &sender_public, &get_test_client(), 0, 0, 0));
+
+        check_top_level_state!(state, [
+            (account: sender => (seq: 1, balance: 100 - 5)),
+            (shard: shard_id)
+        ]);
+        assert_eq!(Ok(1), state.number_of_shards());
+
+        let transaction = ShardTransaction::ShardStore {
+            network_id: "tc".into(),
+            shard_id,
+            content: "text".to_string(),
+        };
+        assert_eq!(
+            Err(RuntimeError::InvalidShardId(shard_id).into()),
+            state.apply_shard_transaction(&transaction, &sender, &[], &get_test_client(), 0, 0)
+        );
+    }
+
+    #[test]
+    fn only_owner_can_terminate_shard() {
+        let (sender, sender_public, _) = address();
+        let owner = Address::random();
+
+        let shard_id = 0;
+        let mut state = get_temp_state();
+        set_top_level_state!(state, [
+            (account: sender => balance: 100),
+            (account: owner => balance: 100),
+            (shard: shard_id => owners: [owner]),
+            (metadata: shards: 1)
+        ]);
+
+        let tx = transaction!(fee: 5, Action::TerminateShard { shard_id });
+        assert_eq!(
+            Err(RuntimeError::InsufficientPermission.into()),
+            state.apply(&tx, &H256::random().into(), &sender_public, &get_test_client(), 0, 0, 0)
+        );
+        check_top_level_state!(state, [
+            (account: sender => (seq: 0, balance: 100)),
+            (shard: shard_id => owners: [owner])
+        ]);
+    }
+
     #[test]
     fn regular_account_cannot_be_shard_owner() {
         let (sender, sender_public, _) = address();
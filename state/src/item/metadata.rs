@@ -15,7 +15,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use crate::CacheableItem;
-use ctypes::{CommonParams, ShardId, TxHash};
+use ctypes::{BlockNumber, CommonParams, ShardId, TxHash};
 use primitives::H256;
 use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
 
@@ -34,6 +34,7 @@ pub struct Metadata {
     seq: u64,
     params: Option<CommonParams>,
     term_params: Option<CommonParams>,
+    scheduled_params: Option<(BlockNumber, CommonParams)>,
 }
 
 impl Metadata {
@@ -46,6 +47,7 @@ impl Metadata {
             seq: 0,
             params: None,
             term_params: None,
+            scheduled_params: None,
         }
     }
 
@@ -102,6 +104,28 @@ impl Metadata {
         self.term_params = self.params;
     }
 
+    /// The params which will be applied at the end of the given block.
+    pub fn scheduled_params(&self) -> Option<(BlockNumber, &CommonParams)> {
+        self.scheduled_params.as_ref().map(|(activation, params)| (*activation, params))
+    }
+
+    pub fn schedule_params(&mut self, activation: BlockNumber, params: CommonParams) {
+        self.scheduled_params = Some((activation, params));
+    }
+
+    /// Replaces the params with the scheduled ones if they are activated at the given block.
+    /// Returns true if the params are changed.
+    pub fn activate_scheduled_params(&mut self, block_number: BlockNumber) -> bool {
+        match self.scheduled_params {
+            Some((activation, params)) if activation <= block_number => {
+                self.params = Some(params);
+                self.scheduled_params = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
     pub fn increase_term_id(&mut self, last_term_finished_block_num: u64) {
         assert!(self.term.last_term_finished_block_num < last_term_finished_block_num);
         self.term.last_term_finished_block_num = last_term_finished_block_num;
@@ -137,13 +161,33 @@ const INITIAL_LEN: usize = 4;
 const TERM_LEN: usize = INITIAL_LEN + 2;
 const PARAMS_LEN: usize = TERM_LEN + 2;
 const TERM_PARAMS_LEN: usize = PARAMS_LEN + 1;
-const VALID_LEN: &[usize] = &[INITIAL_LEN, TERM_LEN, PARAMS_LEN, TERM_PARAMS_LEN];
+const SCHEDULED_PARAMS_LEN: usize = TERM_PARAMS_LEN + 2;
+const VALID_LEN: &[usize] = &[INITIAL_LEN, TERM_LEN, PARAMS_LEN, TERM_PARAMS_LEN, SCHEDULED_PARAMS_LEN];
 
 impl Encodable for Metadata {
     fn rlp_append(&self, s: &mut RlpStream) {
         let term_changed = self.term != Default::default();
         let params_changed = self.seq != 0;
         let term_params_exist = self.term_params.is_some();
+        let scheduled_params_exist = self.scheduled_params.is_some();
+
+        if scheduled_params_exist {
+            // The params can be scheduled before they are changed,
+            // so the params and the term params are encoded as empty lists if they don't exist.
+            s.begin_list(SCHEDULED_PARAMS_LEN)
+                .append(&PREFIX)
+                .append(&self.number_of_shards)
+                .append(&self.number_of_initial_shards)
+                .append_list(&self.hashes)
+                .append(&self.term.last_term_finished_block_num)
+                .append(&self.term.current_term_id)
+                .append(&self.seq);
+            append_optional_params(s, self.params.as_ref());
+            append_optional_params(s, self.term_params.as_ref());
+            let (activation, params) = self.scheduled_params.as_ref().unwrap();
+            s.append(activation).append(params);
+            return
+        }
 
         let len = if term_params_exist {
             if !params_changed {
@@ -183,6 +227,24 @@ impl Encodable for Metadata {
     }
 }
 
+fn append_optional_params(s: &mut RlpStream, params: Option<&CommonParams>) {
+    match params {
+        Some(params) => {
+            s.append(params);
+        }
+        None => {
+            s.begin_list(0);
+        }
+    }
+}
+
+fn decode_optional_params(rlp: &Rlp<'_>) -> Result<Option<CommonParams>, DecoderError> {
+    if rlp.is_empty() {
+        return Ok(None)
+    }
+    rlp.as_val().map(Some)
+}
+
 impl Decodable for Metadata {
     fn decode(rlp: &Rlp<'_>) -> Result<Self, DecoderError> {
         let item_count = rlp.item_count()?;
@@ -211,18 +273,28 @@ impl Decodable for Metadata {
             TermMetadata::default()
         };
 
-        let (seq, params) = if item_count >= PARAMS_LEN {
+        let (seq, params) = if item_count >= SCHEDULED_PARAMS_LEN {
+            (rlp.val_at(6)?, decode_optional_params(&rlp.at(7)?)?)
+        } else if item_count >= PARAMS_LEN {
             (rlp.val_at(6)?, Some(rlp.val_at(7)?))
         } else {
             Default::default()
         };
 
-        let term_params = if item_count >= TERM_PARAMS_LEN {
+        let term_params = if item_count >= SCHEDULED_PARAMS_LEN {
+            decode_optional_params(&rlp.at(8)?)?
+        } else if item_count >= TERM_PARAMS_LEN {
             Some(rlp.val_at(8)?)
         } else {
             Default::default()
         };
 
+        let scheduled_params = if item_count >= SCHEDULED_PARAMS_LEN {
+            Some((rlp.val_at(9)?, rlp.val_at(10)?))
+        } else {
+            None
+        };
+
         Ok(Self {
             number_of_shards,
             number_of_initial_shards,
@@ -231,6 +303,7 @@ impl Decodable for Metadata {
             seq,
             params,
             term_params,
+            scheduled_params,
         })
     }
 }
@@ -297,6 +370,7 @@ mod tests {
             seq: 0,
             params: None,
             term_params: None,
+            scheduled_params: None,
         };
         let mut rlp = RlpStream::new_list(4);
         rlp.append(&PREFIX).append(&10u16).append(&1u16).append_list::<H256, H256>(&[]);
@@ -313,6 +387,7 @@ mod tests {
             seq: 3,
             params: Some(CommonParams::default_for_test()),
             term_params: Some(CommonParams::default_for_test()),
+            scheduled_params: None,
         };
         rlp_encode_and_decode_test!(metadata);
     }
@@ -330,6 +405,7 @@ mod tests {
             seq: 0,
             params: None,
             term_params: None,
+            scheduled_params: None,
         };
         rlp_encode_and_decode_test!(metadata);
     }
@@ -347,7 +423,49 @@ mod tests {
             seq: 3,
             params: Some(CommonParams::default_for_test()),
             term_params: Some(CommonParams::default_for_test()),
+            scheduled_params: None,
         };
         rlp_encode_and_decode_test!(metadata);
     }
+
+    #[test]
+    fn metadata_with_scheduled_params_without_seq() {
+        let mut metadata = Metadata::new(10);
+        metadata.schedule_params(100, CommonParams::default_for_test());
+        metadata.increase_seq();
+        rlp_encode_and_decode_test!(metadata);
+    }
+
+    #[test]
+    fn metadata_with_scheduled_params_and_term_params() {
+        let metadata = Metadata {
+            number_of_shards: 10,
+            number_of_initial_shards: 1,
+            hashes: vec![],
+            term: TermMetadata {
+                last_term_finished_block_num: 1,
+                current_term_id: 100,
+            },
+            seq: 3,
+            params: Some(CommonParams::default_for_test()),
+            term_params: Some(CommonParams::default_for_test()),
+            scheduled_params: Some((100, CommonParams::default_for_test())),
+        };
+        rlp_encode_and_decode_test!(metadata);
+    }
+
+    #[test]
+    fn scheduled_params_are_activated_at_the_activation_block() {
+        let mut metadata = Metadata::new(10);
+        let params = CommonParams::default_for_test();
+        metadata.schedule_params(100, params);
+
+        assert!(!metadata.activate_scheduled_params(99));
+        assert_eq!(None, metadata.params());
+        assert_eq!(Some((100, &params)), metadata.scheduled_params());
+
+        assert!(metadata.activate_scheduled_params(100));
+        assert_eq!(Some(&params), metadata.params());
+        assert_eq!(None, metadata.scheduled_params());
+    }
 }
@@ -23,10 +23,11 @@ pub mod dummy_shard_text;
 pub mod metadata;
 pub mod regular_account;
 pub mod shard;
+pub mod vesting;
 
 #[derive(Clone, Copy)]
 #[repr(u8)]
-enum Prefix {
+pub(crate) enum Prefix {
     Account = b'C',
     Shard = b'H',
     Metadata = b'M',
@@ -0,0 +1,127 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ccrypto::blake256;
+use ckey::Address;
+use ctypes::BlockNumber;
+use primitives::H256;
+use rlp::RlpStream;
+
+/// Lock-up schedule of the balance of an account.
+///
+/// The whole `amount` is locked until `start + cliff`. After that, it's unlocked linearly until `start + period`.
+/// A cliff-only lock is a schedule whose `cliff` equals to `period`.
+/// It's stored in the action data of the top level state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct Vesting {
+    amount: u64,
+    start: BlockNumber,
+    cliff: u64,
+    period: u64,
+}
+
+impl Vesting {
+    pub fn new(amount: u64, start: BlockNumber, cliff: u64, period: u64) -> Self {
+        Self {
+            amount,
+            start,
+            cliff,
+            period,
+        }
+    }
+
+    /// The key of the action data where the lock-up schedule of the account is stored.
+    pub fn key(address: &Address) -> H256 {
+        let mut rlp = RlpStream::new_list(2);
+        rlp.append(&"Vesting");
+        rlp.append(address);
+        blake256(rlp.as_raw())
+    }
+
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+
+    pub fn start(&self) -> BlockNumber {
+        self.start
+    }
+
+    pub fn cliff(&self) -> u64 {
+        self.cliff
+    }
+
+    pub fn period(&self) -> u64 {
+        self.period
+    }
+
+    /// Returns the amount that cannot be spent at the given block.
+    pub fn locked_amount(&self, block_number: BlockNumber) -> u64 {
+        let elapsed = block_number.saturating_sub(self.start);
+        if elapsed < self.cliff {
+            return self.amount
+        }
+        if elapsed >= self.period {
+            return 0
+        }
+        let unlocked = u128::from(self.amount) * u128::from(elapsed) / u128::from(self.period);
+        self.amount - unlocked as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rlp::rlp_encode_and_decode_test;
+
+    #[test]
+    fn encode_and_decode() {
+        rlp_encode_and_decode_test!(Vesting::new(100, 10, 20, 30));
+    }
+
+    #[test]
+    fn linear_vesting() {
+        let vesting = Vesting::new(1000, 100, 0, 100);
+        assert_eq!(1000, vesting.locked_amount(0));
+        assert_eq!(1000, vesting.locked_amount(100));
+        assert_eq!(990, vesting.locked_amount(101));
+        assert_eq!(500, vesting.locked_amount(150));
+        assert_eq!(10, vesting.locked_amount(199));
+        assert_eq!(0, vesting.locked_amount(200));
+        assert_eq!(0, vesting.locked_amount(::std::u64::MAX));
+    }
+
+    #[test]
+    fn cliff_vesting() {
+        let vesting = Vesting::new(1000, 0, 50, 50);
+        assert_eq!(1000, vesting.locked_amount(0));
+        assert_eq!(1000, vesting.locked_amount(49));
+        assert_eq!(0, vesting.locked_amount(50));
+    }
+
+    #[test]
+    fn linear_vesting_after_cliff() {
+        let vesting = Vesting::new(1000, 0, 25, 100);
+        assert_eq!(1000, vesting.locked_amount(24));
+        assert_eq!(750, vesting.locked_amount(25));
+        assert_eq!(0, vesting.locked_amount(100));
+    }
+
+    #[test]
+    fn no_overflow() {
+        let vesting = Vesting::new(::std::u64::MAX, 0, 0, ::std::u64::MAX);
+        assert_eq!(::std::u64::MAX - 1, vesting.locked_amount(1));
+    }
+}
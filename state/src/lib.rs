@@ -24,12 +24,15 @@ extern crate codechain_vm as cvm;
 #[macro_use]
 extern crate log;
 #[cfg(test)]
+extern crate proptest;
+#[cfg(test)]
 extern crate rustc_hex;
 #[macro_use]
 extern crate rlp_derive;
 
 mod action_handler;
 mod cache;
+mod check;
 mod checkpoint;
 mod db;
 mod error;
@@ -39,17 +42,22 @@ mod traits;
 
 pub mod tests;
 
-pub use crate::action_handler::{ActionDataKeyBuilder, ActionHandler, FindActionHandler, HitHandler};
+pub use crate::action_handler::{
+    name_registry, ActionDataKeyBuilder, ActionHandler, FindActionHandler, HitHandler, NameRegistry,
+};
+pub use crate::cache::TopAccess;
+pub use crate::check::{check_state, StateCheck, TrieCheck};
 pub use crate::checkpoint::{CheckpointId, StateWithCheckpoint};
-pub use crate::db::StateDB;
+pub use crate::db::{StateDB, Witness};
 pub use crate::error::Error as StateError;
-pub use crate::impls::{ShardLevelState, TopLevelState};
+pub use crate::impls::{AccountProof, ActionDataProof, ShardLevelState, ShardProof, Speculation, TopLevelState};
 pub use crate::item::account::Account;
 pub use crate::item::action_data::ActionData;
 pub use crate::item::dummy_shard_text::{ShardText, ShardTextAddress};
 pub use crate::item::metadata::{Metadata, MetadataAddress};
 pub use crate::item::regular_account::{RegularAccount, RegularAccountAddress};
 pub use crate::item::shard::{Shard, ShardAddress};
+pub use crate::item::vesting::Vesting;
 pub use crate::traits::{ShardState, ShardStateView, StateWithCache, TopState, TopStateView};
 
 use crate::cache::CacheableItem;
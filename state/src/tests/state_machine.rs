@@ -0,0 +1,277 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Applies random sequences of actions, interleaved with checkpoints, to the state and checks its invariants.
+//!
+//! Every action is also applied to a plain model of the accounts and the action data, and the state must agree
+//! with the model after each action. The committed trie must agree with the cache, and the root must be the same
+//! as the one made by applying only the actions that are not reverted.
+
+use ckey::Address;
+use primitives::{Bytes, H256};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use std::collections::HashMap;
+
+use super::helpers::get_temp_state;
+use crate::{Account, ActionData, StateWithCheckpoint, TopLevelState, TopState, TopStateView};
+
+const ACCOUNTS: usize = 4;
+const KEYS: u8 = 4;
+
+#[derive(Clone, Debug)]
+pub enum Action {
+    Mint {
+        account: usize,
+        amount: u64,
+    },
+    /// Fails if the balance is not enough.
+    Burn {
+        account: usize,
+        amount: u64,
+    },
+    /// Fails if the balance of the sender is not enough.
+    Transfer {
+        from: usize,
+        to: usize,
+        amount: u64,
+    },
+    IncSeq {
+        account: usize,
+    },
+    UpdateActionData {
+        key: u8,
+        data: Bytes,
+    },
+    RemoveActionData {
+        key: u8,
+    },
+    Checkpoint,
+    /// Does nothing if there is no checkpoint.
+    Revert,
+    /// Does nothing if there is no checkpoint.
+    Discard,
+    /// Does nothing if there is a checkpoint.
+    Commit,
+}
+
+pub fn action() -> impl Strategy<Value = Action> {
+    let account = 0..ACCOUNTS;
+    let amount = 0..1_000u64;
+    prop_oneof![
+        (account.clone(), amount.clone()).prop_map(|(account, amount)| Action::Mint {
+            account,
+            amount,
+        }),
+        (account.clone(), amount.clone()).prop_map(|(account, amount)| Action::Burn {
+            account,
+            amount,
+        }),
+        (account.clone(), account.clone(), amount).prop_map(|(from, to, amount)| Action::Transfer {
+            from,
+            to,
+            amount,
+        }),
+        account.prop_map(|account| Action::IncSeq {
+            account,
+        }),
+        (0..KEYS, vec(any::<u8>(), 1..8)).prop_map(|(key, data)| Action::UpdateActionData {
+            key,
+            data,
+        }),
+        (0..KEYS).prop_map(|key| Action::RemoveActionData {
+            key,
+        }),
+        Just(Action::Checkpoint),
+        Just(Action::Revert),
+        Just(Action::Discard),
+        Just(Action::Commit),
+    ]
+}
+
+fn address(account: usize) -> Address {
+    Address::from(account as u64 + 1)
+}
+
+fn action_data_key(key: u8) -> H256 {
+    H256::from(u64::from(key) + 1)
+}
+
+/// What the state is expected to be.
+#[derive(Clone, Default)]
+struct Model {
+    balances: [u64; ACCOUNTS],
+    seqs: [u64; ACCOUNTS],
+    action_data: HashMap<u8, Bytes>,
+    /// The sum of the minted amounts minus the sum of the burnt amounts.
+    supply: u64,
+}
+
+/// Applies the actions with the checkpoints. Returns the actions which are not reverted.
+pub fn run(actions: &[Action]) -> (TopLevelState, Vec<Action>) {
+    let mut state = get_temp_state();
+    let mut model = Model::default();
+    // The model and the applied actions at each checkpoint.
+    let mut checkpoints: Vec<(Model, Vec<Action>)> = Vec::new();
+    let mut applied = Vec::new();
+
+    for action in actions {
+        match action {
+            Action::Checkpoint => {
+                state.create_checkpoint(checkpoints.len());
+                checkpoints.push((model.clone(), applied.clone()));
+            }
+            Action::Revert => {
+                if let Some((saved, saved_applied)) = checkpoints.pop() {
+                    state.revert_to_checkpoint(checkpoints.len());
+                    model = saved;
+                    applied = saved_applied;
+                }
+            }
+            Action::Discard => {
+                if checkpoints.pop().is_some() {
+                    state.discard_checkpoint(checkpoints.len());
+                }
+            }
+            Action::Commit => {
+                if checkpoints.is_empty() {
+                    state.commit().unwrap();
+                    check_trie(&state, &model);
+                    applied.push(action.clone());
+                }
+            }
+            _ => {
+                apply(&mut state, &mut model, action);
+                applied.push(action.clone());
+            }
+        }
+        check_model(&state, &model);
+    }
+
+    while !checkpoints.is_empty() {
+        checkpoints.pop();
+        state.discard_checkpoint(checkpoints.len());
+    }
+    state.commit().unwrap();
+    check_trie(&state, &model);
+    (state, applied)
+}
+
+fn apply(state: &mut TopLevelState, model: &mut Model, action: &Action) {
+    match action {
+        Action::Mint {
+            account,
+            amount,
+        } => {
+            state.add_balance(&address(*account), *amount).unwrap();
+            model.balances[*account] += amount;
+            model.supply += amount;
+        }
+        Action::Burn {
+            account,
+            amount,
+        } => {
+            let result = state.sub_balance(&address(*account), *amount);
+            if model.balances[*account] < *amount {
+                assert!(result.is_err());
+            } else {
+                assert_eq!(Ok(()), result);
+                model.balances[*account] -= amount;
+                model.supply -= amount;
+            }
+        }
+        Action::Transfer {
+            from,
+            to,
+            amount,
+        } => {
+            let result = state.transfer_balance(&address(*from), &address(*to), *amount);
+            if model.balances[*from] < *amount {
+                assert!(result.is_err());
+            } else {
+                assert_eq!(Ok(()), result);
+                model.balances[*from] -= amount;
+                model.balances[*to] += amount;
+            }
+        }
+        Action::IncSeq {
+            account,
+        } => {
+            state.inc_seq(&address(*account)).unwrap();
+            model.seqs[*account] += 1;
+        }
+        Action::UpdateActionData {
+            key,
+            data,
+        } => {
+            state.update_action_data(&action_data_key(*key), data.clone()).unwrap();
+            model.action_data.insert(*key, data.clone());
+        }
+        Action::RemoveActionData {
+            key,
+        } => {
+            state.remove_action_data(&action_data_key(*key));
+            model.action_data.remove(key);
+        }
+        Action::Checkpoint | Action::Revert | Action::Discard | Action::Commit => unreachable!(),
+    }
+}
+
+/// The state agrees with the model, and the transfers don't change the supply.
+fn check_model(state: &TopLevelState, model: &Model) {
+    let mut supply = 0;
+    for account in 0..ACCOUNTS {
+        let balance = state.balance(&address(account)).unwrap();
+        assert_eq!(model.balances[account], balance, "balance of account {}", account);
+        assert_eq!(model.seqs[account], state.seq(&address(account)).unwrap(), "seq of account {}", account);
+        supply += balance;
+    }
+    assert_eq!(model.supply, supply);
+    for key in 0..KEYS {
+        let data = state.action_data(&action_data_key(key)).unwrap().map(|data| data.to_vec());
+        assert_eq!(model.action_data.get(&key).cloned(), data, "action data at {}", key);
+    }
+}
+
+/// The committed trie, which is read without the cache, agrees with the model.
+fn check_trie(state: &TopLevelState, model: &Model) {
+    for account in 0..ACCOUNTS {
+        let proof = state.account_proof(&address(account)).unwrap();
+        let account_in_trie = proof.value.map(|value| rlp::decode::<Account>(&value).unwrap()).unwrap_or_default();
+        assert_eq!(model.balances[account], account_in_trie.balance(), "balance of account {} in the trie", account);
+        assert_eq!(model.seqs[account], account_in_trie.seq(), "seq of account {} in the trie", account);
+    }
+    for key in 0..KEYS {
+        let proof = state.action_data_proof(&action_data_key(key)).unwrap();
+        let data = proof.value.map(|value| rlp::decode::<ActionData>(&value).unwrap().to_vec());
+        assert_eq!(model.action_data.get(&key).cloned(), data, "action data at {} in the trie", key);
+    }
+}
+
+proptest! {
+    #[test]
+    fn random_actions_keep_the_invariants(actions in vec(action(), 1..64)) {
+        let (state, applied) = run(&actions);
+
+        // The reverted actions leave nothing behind.
+        let (replayed, _) = run(&applied);
+        prop_assert_eq!(state.root(), replayed.root());
+
+        // The same actions make the same root.
+        let (again, _) = run(&actions);
+        prop_assert_eq!(state.root(), again.root());
+    }
+}
@@ -14,7 +14,9 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{Account, ActionData, CacheableItem, Metadata, RegularAccount, Shard, ShardText, StateDB, StateResult};
+use crate::{
+    Account, ActionData, CacheableItem, Metadata, RegularAccount, Shard, ShardText, StateDB, StateResult, Vesting,
+};
 use ckey::{public_to_address, Address, Public};
 use ctypes::transaction::ShardTransaction;
 use ctypes::{BlockNumber, CommonParams, ShardId, Tracker, TxHash};
@@ -38,6 +40,18 @@ pub trait TopStateView {
         Ok(self.account(a)?.map_or(0, |account| account.balance()))
     }
 
+    /// Get the lock-up schedule of account `a`.
+    fn vesting(&self, a: &Address) -> TrieResult<Option<Vesting>> {
+        Ok(self
+            .action_data(&Vesting::key(a))?
+            .map(|data| ::rlp::decode(&data).expect("Vesting must be stored in a valid format")))
+    }
+
+    /// Get the balance of account `a` which cannot be spent at the given block.
+    fn locked_balance(&self, a: &Address, block_number: BlockNumber) -> TrieResult<u64> {
+        Ok(self.vesting(a)?.map_or(0, |vesting| vesting.locked_amount(block_number)))
+    }
+
     fn account_exists(&self, a: &Address) -> TrieResult<bool> {
         // Bloom filter does not contain empty accounts, so it is important here to
         // check if account exists in the database directly before EIP-161 is in effect.
@@ -151,6 +165,7 @@ pub trait TopState {
     fn create_shard(&mut self, fee_payer: &Address, tx_hash: TxHash, users: Vec<Address>) -> StateResult<()>;
     fn change_shard_owners(&mut self, shard_id: ShardId, owners: &[Address], sender: &Address) -> StateResult<()>;
     fn change_shard_users(&mut self, shard_id: ShardId, users: &[Address], sender: &Address) -> StateResult<()>;
+    fn terminate_shard(&mut self, shard_id: ShardId, sender: &Address) -> StateResult<()>;
 
     fn set_shard_root(&mut self, shard_id: ShardId, new_root: H256) -> StateResult<()>;
     fn set_shard_owners(&mut self, shard_id: ShardId, new_owners: Vec<Address>) -> StateResult<()>;
@@ -162,6 +177,14 @@ pub trait TopState {
     fn remove_action_data(&mut self, key: &H256);
 
     fn update_params(&mut self, metadata_seq: u64, params: CommonParams) -> StateResult<()>;
+    fn schedule_params(
+        &mut self,
+        metadata_seq: u64,
+        activation: BlockNumber,
+        params: CommonParams,
+        current_block_number: BlockNumber,
+    ) -> StateResult<()>;
+    fn activate_scheduled_params(&mut self, block_number: BlockNumber) -> StateResult<()>;
     fn update_term_params(&mut self) -> StateResult<()>;
 }
 
@@ -0,0 +1,171 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ccore::{Block, UnverifiedTransaction};
+use cnetwork::NodeId;
+use codechain_crypto::{blake256, BLAKE_NULL_RLP};
+use ctypes::{BlockHash, BlockNumber, Header, TxHash};
+use merkle_trie::skewed_merkle_root;
+use rlp::Encodable;
+use std::collections::HashMap;
+
+/// Identifies a transaction in a compact block.
+///
+/// It's salted with the block hash, so the collisions can't be prepared before the block is made.
+pub fn short_id(block_hash: &BlockHash, tx_hash: &TxHash) -> u64 {
+    let mut input = block_hash.to_vec();
+    input.extend(tx_hash.to_vec());
+    let hash = blake256(&input);
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash[..8]);
+    u64::from_be_bytes(bytes)
+}
+
+/// A block announced by its header and the short ids of its transactions.
+/// The transactions are filled from the mem pool, and the missing ones are requested to the announcer.
+pub struct PendingCompactBlock {
+    from: NodeId,
+    header: Header,
+    transactions: Vec<Option<UnverifiedTransaction>>,
+}
+
+impl PendingCompactBlock {
+    pub fn new(
+        from: NodeId,
+        header: Header,
+        short_ids: &[u64],
+        mem_pool: impl IntoIterator<Item = UnverifiedTransaction>,
+    ) -> Self {
+        let hash = header.hash();
+        let mut by_short_id: HashMap<u64, UnverifiedTransaction> =
+            mem_pool.into_iter().map(|tx| (short_id(&hash, &tx.hash()), tx)).collect();
+        let transactions = short_ids.iter().map(|id| by_short_id.remove(id)).collect();
+        Self {
+            from,
+            header,
+            transactions,
+        }
+    }
+
+    pub fn from(&self) -> &NodeId {
+        &self.from
+    }
+
+    pub fn hash(&self) -> BlockHash {
+        self.header.hash()
+    }
+
+    pub fn number(&self) -> BlockNumber {
+        self.header.number()
+    }
+
+    /// The indexes of the transactions not found in the mem pool.
+    pub fn missing(&self) -> Vec<u64> {
+        (0..self.transactions.len() as u64).filter(|i| self.transactions[*i as usize].is_none()).collect()
+    }
+
+    /// Fills the transactions received for the missing indexes.
+    pub fn fill(&mut self, indexes: &[u64], transactions: Vec<UnverifiedTransaction>) -> bool {
+        if indexes.len() != transactions.len() {
+            return false
+        }
+        for (index, tx) in indexes.iter().zip(transactions) {
+            match self.transactions.get_mut(*index as usize) {
+                Some(slot) => *slot = Some(tx),
+                None => return false,
+            }
+        }
+        true
+    }
+
+    /// Returns the block if all the transactions are filled and they match the transactions root.
+    /// A mismatch means that a short id collided, so the block should be downloaded as usual.
+    pub fn into_block(self) -> Option<Block> {
+        let transactions: Vec<_> = self.transactions.into_iter().collect::<Option<_>>()?;
+        let transactions_root = skewed_merkle_root(BLAKE_NULL_RLP, transactions.iter().map(Encodable::rlp_bytes));
+        if *self.header.transactions_root() != transactions_root {
+            return None
+        }
+        Some(Block {
+            header: self.header,
+            transactions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckey::Signature;
+    use ctypes::transaction::{Action, Transaction};
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn transaction(seq: u64) -> UnverifiedTransaction {
+        UnverifiedTransaction::new(
+            Transaction {
+                seq,
+                fee: 10,
+                action: Action::CreateShard {
+                    users: vec![],
+                },
+                network_id: "tc".into(),
+            },
+            Signature::default(),
+        )
+    }
+
+    fn node_id() -> NodeId {
+        NodeId::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 3485)
+    }
+
+    fn header_of(transactions: &[UnverifiedTransaction]) -> Header {
+        let mut header = Header::default();
+        header.set_transactions_root(skewed_merkle_root(
+            BLAKE_NULL_RLP,
+            transactions.iter().map(Encodable::rlp_bytes),
+        ));
+        header
+    }
+
+    #[test]
+    fn block_is_rebuilt_from_the_mem_pool_and_the_missing_transactions() {
+        let transactions: Vec<_> = (0..3).map(transaction).collect();
+        let header = header_of(&transactions);
+        let hash = header.hash();
+        let short_ids: Vec<_> = transactions.iter().map(|tx| short_id(&hash, &tx.hash())).collect();
+
+        let mem_pool = vec![transactions[2].clone(), transaction(10), transactions[0].clone()];
+        let mut pending = PendingCompactBlock::new(node_id(), header, &short_ids, mem_pool);
+        assert_eq!(vec![1], pending.missing());
+
+        assert!(!pending.fill(&[5], vec![transactions[1].clone()]));
+        assert!(pending.fill(&[1], vec![transactions[1].clone()]));
+        assert!(pending.missing().is_empty());
+        assert_eq!(transactions, pending.into_block().unwrap().transactions);
+    }
+
+    #[test]
+    fn block_is_not_rebuilt_if_the_transactions_root_differs() {
+        let transactions: Vec<_> = (0..2).map(transaction).collect();
+        let header = header_of(&transactions);
+        let hash = header.hash();
+        let short_ids = vec![short_id(&hash, &transactions[1].hash()), short_id(&hash, &transactions[0].hash())];
+
+        let pending = PendingCompactBlock::new(node_id(), header, &short_ids, transactions);
+        assert!(pending.missing().is_empty());
+        assert!(pending.into_block().is_none());
+    }
+}
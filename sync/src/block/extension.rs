@@ -14,13 +14,14 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use super::compact::{short_id, PendingCompactBlock};
 use super::downloader::{BodyDownloader, HeaderDownloader};
 use super::message::{Message, RequestMessage, ResponseMessage};
 use crate::snapshot::snapshot_path;
 use ccore::encoded::Header as EncodedHeader;
 use ccore::{
     Block, BlockChainClient, BlockChainTrait, BlockId, BlockImportError, BlockStatus, ChainNotify, Client, ImportBlock,
-    ImportError, StateInfo, UnverifiedTransaction,
+    ImportError, PeerEvent, StateInfo, UnverifiedTransaction,
 };
 use cdb::AsHashDB;
 use cnetwork::{Api, EventSender, IntoSocketAddr, NetworkExtension, NodeId};
@@ -54,6 +55,9 @@ const SYNC_EXPIRE_TOKEN_END: TimerToken = SYNC_EXPIRE_TOKEN_BEGIN + SYNC_EXPIRE_
 const SYNC_TIMER_INTERVAL: u64 = 1000;
 const SYNC_EXPIRE_REQUEST_INTERVAL: u64 = 15000;
 
+/// The first version of the extension that understands the compact blocks.
+const COMPACT_BLOCK_VERSION: u64 = 1;
+
 #[derive(Debug, PartialEq)]
 pub struct TokenInfo {
     node_id: NodeId,
@@ -108,7 +112,8 @@ impl State {
         let metadata = top_state.metadata().unwrap().expect("Metadata must exist for the snapshot block");
         let shard_num = *metadata.number_of_shards();
         let empty_shard = (0..shard_num).find_map(|n| {
-            let shard_root = top_state.shard_root(n).unwrap().expect("Shard root must exist");
+            // A terminated shard has no root.
+            let shard_root = top_state.shard_root(n).unwrap()?;
             let trie = TrieFactory::readonly(state_db.as_hashdb(), &shard_root);
             if !trie.map(|t| t.is_complete()).unwrap_or(false) {
                 Some((n, shard_root))
@@ -150,12 +155,7 @@ impl State {
                 let state_root = header.state_root();
                 let state_db = client.state_db().read();
                 let top_state = TopLevelState::from_existing(state_db.clone(&state_root), state_root).unwrap();
-                let shard_root = top_state.shard_root(0).unwrap().expect("Shard 0 always exists");
-                State::SnapshotShardChunk {
-                    block: *block,
-                    shard_id: 0,
-                    restore: SnapshotRestore::new(shard_root),
-                }
+                State::first_shard_chunk(*block, &top_state, 0)
             }
             State::SnapshotShardChunk {
                 block,
@@ -163,26 +163,30 @@ impl State {
                 ..
             } => {
                 let top_state = client.state_at((*block).into()).expect("State at the snapshot header must exist");
-                let metadata = top_state.metadata().unwrap().expect("Metadata must exist for snapshot block");
-                let shard_num = *metadata.number_of_shards();
-                if shard_id + 1 == shard_num {
-                    State::Full
-                } else {
-                    let next_shard = shard_id + 1;
-                    let shard_root = top_state
-                        .shard_root(next_shard)
-                        .expect("Top level state must be valid")
-                        .expect("Shard root must exist");
-                    State::SnapshotShardChunk {
-                        block: *block,
-                        shard_id: next_shard,
-                        restore: SnapshotRestore::new(shard_root),
-                    }
-                }
+                State::first_shard_chunk(*block, &top_state, shard_id + 1)
             }
             State::Full => State::Full,
         }
     }
+
+    /// Returns the state downloading the first shard whose id is not less than `from`.
+    fn first_shard_chunk(block: BlockHash, top_state: &TopLevelState, from: ShardId) -> Self {
+        let metadata = top_state.metadata().unwrap().expect("Metadata must exist for snapshot block");
+        let shard_num = *metadata.number_of_shards();
+        // A terminated shard has no root.
+        let next = (from..shard_num).find_map(|shard_id| {
+            let shard_root = top_state.shard_root(shard_id).expect("Top level state must be valid")?;
+            Some((shard_id, shard_root))
+        });
+        match next {
+            Some((shard_id, shard_root)) => State::SnapshotShardChunk {
+                block,
+                shard_id,
+                restore: SnapshotRestore::new(shard_root),
+            },
+            None => State::Full,
+        }
+    }
 }
 
 pub struct Extension {
@@ -199,6 +203,9 @@ pub struct Extension {
     last_request: u64,
     seq: u64,
     snapshot_dir: Option<String>,
+    compact_block_relay: bool,
+    compact_peers: HashSet<NodeId>,
+    pending_compact_blocks: HashMap<BlockHash, PendingCompactBlock>,
 }
 
 impl Extension {
@@ -207,6 +214,7 @@ impl Extension {
         api: Box<dyn Api>,
         snapshot_target: Option<(H256, u64)>,
         snapshot_dir: Option<String>,
+        compact_block_relay: bool,
     ) -> Extension {
         api.set_timer(SYNC_TIMER_TOKEN, Duration::from_millis(SYNC_TIMER_INTERVAL)).expect("Timer set succeeds");
 
@@ -243,6 +251,9 @@ impl Extension {
             last_request: Default::default(),
             seq: Default::default(),
             snapshot_dir,
+            compact_block_relay,
+            compact_peers: Default::default(),
+            pending_compact_blocks: Default::default(),
         }
     }
 
@@ -333,6 +344,45 @@ impl Extension {
         }
     }
 
+    fn send_transactions_request(&mut self, id: &NodeId, request: RequestMessage) {
+        if let Some(requests) = self.requests.get_mut(id) {
+            ctrace!(SYNC, "Send transactions request to {}", id);
+            let request_id = self.last_request;
+            self.last_request += 1;
+            requests.push((request_id, request.clone()));
+            self.api.send(id, Arc::new(Message::Request(request_id, request).rlp_bytes()));
+        }
+    }
+
+    fn send_compact_block_broadcast(&self, imported: &[BlockHash]) {
+        if !self.compact_block_relay || discriminant(&self.state) != discriminant(&State::Full) {
+            return
+        }
+
+        let best_hash = self.client.chain_info().best_proposal_block_hash;
+        if !imported.contains(&best_hash) {
+            return
+        }
+        let block = match self.client.block(&BlockId::Hash(best_hash)) {
+            Some(block) => block,
+            None => return,
+        };
+        let short_ids = block.transaction_hashes().iter().map(|tx_hash| short_id(&best_hash, tx_hash)).collect();
+        let message = Arc::new(
+            Message::CompactBlock {
+                header: block.decode_header(),
+                short_ids,
+            }
+            .rlp_bytes(),
+        );
+        for id in self.compact_peers.iter() {
+            let has_block = self.header_downloaders.get(id).map_or(false, |peer| peer.best_hash() == best_hash);
+            if !has_block {
+                self.api.send(id, Arc::clone(&message));
+            }
+        }
+    }
+
     fn send_body_request(&mut self, id: &NodeId) {
         if let Some(downloader) = self.header_downloaders.get(&id) {
             if self.client.block_status(&BlockId::Hash(downloader.best_hash())) == BlockStatus::InChain {
@@ -354,6 +404,10 @@ impl Extension {
                 cdebug!(SYNC, "Wait body response");
                 return
             }
+            if self.client.queue_info().is_full() {
+                cdebug!(SYNC, "Wait until the verification queue is drained");
+                return
+            }
 
             if let Some(request) = self.body_downloader.create_request() {
                 cdebug!(SYNC, "Request body to {} {:?}", id, request);
@@ -469,16 +523,19 @@ impl NetworkExtension<Event> for Extension {
     }
 
     fn versions() -> &'static [u64] {
-        const VERSIONS: &[u64] = &[0];
+        const VERSIONS: &[u64] = &[0, COMPACT_BLOCK_VERSION];
         &VERSIONS
     }
 
-    fn on_node_added(&mut self, id: &NodeId, _version: u64) {
+    fn on_node_added(&mut self, id: &NodeId, version: u64) {
         cinfo!(SYNC, "New peer detected #{}", id);
         self.send_status(id);
 
         let t = self.connected_nodes.insert(*id);
         debug_assert!(t, "{} is already added to peer list", id);
+        if version >= COMPACT_BLOCK_VERSION {
+            self.compact_peers.insert(*id);
+        }
 
         let token = self.token_generator.gen().expect("Token generator is full");
         let token_info = TokenInfo {
@@ -493,6 +550,8 @@ impl NetworkExtension<Event> for Extension {
         let t = self.tokens.insert(*id, token);
         debug_assert_eq!(None, t);
         debug_assert!(t.is_none());
+
+        self.client.events().publish(PeerEvent::Connected(*id));
     }
 
     fn on_node_removed(&mut self, id: &NodeId) {
@@ -500,6 +559,8 @@ impl NetworkExtension<Event> for Extension {
             cinfo!(SYNC, "Peer removed #{}", id);
 
             self.header_downloaders.remove(id);
+            self.compact_peers.remove(id);
+            self.pending_compact_blocks.retain(|_, block| block.from() != id);
 
             for (_, request) in self.requests.remove(id).into_iter().flatten() {
                 if let RequestMessage::Bodies(hashes) = request {
@@ -512,6 +573,8 @@ impl NetworkExtension<Event> for Extension {
                 self.tokens_info.remove(&token);
                 self.token_generator.restore(token);
             }
+
+            self.client.events().publish(PeerEvent::Disconnected(*id));
         }
     }
 
@@ -529,6 +592,10 @@ impl NetworkExtension<Event> for Extension {
                     best_hash,
                     genesis_hash,
                 } => self.on_peer_status(id, seq, best_hash, genesis_hash),
+                Message::CompactBlock {
+                    header,
+                    short_ids,
+                } => self.on_compact_block(id, header, short_ids),
                 Message::Request(request_id, request) => self.on_peer_request(id, request_id, request),
                 Message::Response(request_id, response) => self.on_peer_response(id, request_id, response),
             }
@@ -724,8 +791,33 @@ impl Extension {
     fn new_blocks(&mut self, imported: Vec<BlockHash>, invalid: Vec<BlockHash>) {
         self.body_downloader.remove_target(&imported);
         self.body_downloader.remove_target(&invalid);
+        self.drop_stale_compact_blocks();
 
         self.send_status_broadcast();
+        self.send_compact_block_broadcast(&imported);
+    }
+
+    /// Drops the compact blocks that can't be imported anymore and their transactions requests.
+    fn drop_stale_compact_blocks(&mut self) {
+        let best_number = self.client.chain_info().best_block_number;
+        let stale: Vec<_> = self
+            .pending_compact_blocks
+            .values()
+            .filter(|block| block.number() <= best_number)
+            .map(PendingCompactBlock::hash)
+            .collect();
+        if stale.is_empty() {
+            return
+        }
+        for hash in &stale {
+            self.pending_compact_blocks.remove(hash);
+        }
+        for requests in self.requests.values_mut() {
+            requests.retain(|(_, request)| match request {
+                RequestMessage::Transactions(hash, _) => !stale.contains(hash),
+                _ => true,
+            });
+        }
     }
 }
 
@@ -749,7 +841,55 @@ impl Extension {
                 e.insert(HeaderDownloader::new(self.client.clone(), seq, best_hash));
             }
         }
-        cinfo!(SYNC, "Peer #{} status update: seq: {}, best_hash: {}", from, seq, best_hash);
+        cdebug!(SYNC, "Peer #{} status update: seq: {}, best_hash: {}", from, seq, best_hash);
+    }
+
+    fn on_compact_block(&mut self, from: &NodeId, header: Header, short_ids: Vec<u64>) {
+        if !self.compact_block_relay || discriminant(&self.state) != discriminant(&State::Full) {
+            return
+        }
+
+        let hash = header.hash();
+        if self.pending_compact_blocks.contains_key(&hash)
+            || self.client.block_status(&BlockId::Hash(hash)) != BlockStatus::Unknown
+        {
+            return
+        }
+        if self.client.block_status(&BlockId::Hash(*header.parent_hash())) != BlockStatus::InChain {
+            // The block will be downloaded with its ancestors.
+            cdebug!(SYNC, "Parent of the compact block #{}({}) is not imported yet", header.number(), hash);
+            return
+        }
+
+        let mem_pool = self.client.ready_transactions(0..::std::u64::MAX).transactions.into_iter().map(Into::into);
+        let block = PendingCompactBlock::new(*from, header, &short_ids, mem_pool);
+        let missing = block.missing();
+        if missing.is_empty() {
+            self.import_compact_block(block);
+            return
+        }
+        cdebug!(SYNC, "Request {} missing transactions of the compact block({}) to {}", missing.len(), hash, from);
+        self.pending_compact_blocks.insert(hash, block);
+        self.send_transactions_request(from, RequestMessage::Transactions(hash, missing));
+    }
+
+    fn import_compact_block(&mut self, block: PendingCompactBlock) {
+        let hash = block.hash();
+        let block = match block.into_block() {
+            Some(block) => block,
+            None => {
+                // The block will be downloaded as usual.
+                cdebug!(SYNC, "Cannot rebuild the compact block({}) from the short ids", hash);
+                return
+            }
+        };
+        cdebug!(SYNC, "Compact block rebuilt for #{}({})", block.header.number(), hash);
+        match self.client.import_block(block.rlp_bytes(&Seal::With)) {
+            Ok(_)
+            | Err(BlockImportError::Import(ImportError::AlreadyInChain))
+            | Err(BlockImportError::Import(ImportError::AlreadyQueued)) => {}
+            Err(err) => cwarn!(SYNC, "Cannot import block({}): {:?}", hash, err),
+        }
     }
 
     fn on_peer_request(&self, from: &NodeId, id: u64, request: RequestMessage) {
@@ -775,6 +915,10 @@ impl Extension {
                 ctrace!(SYNC, "Received body request from {}", from);
                 self.create_bodies_response(hashes)
             }
+            RequestMessage::Transactions(hash, indexes) => {
+                ctrace!(SYNC, "Received transactions request from {}", from);
+                self.create_transactions_response(hash, indexes)
+            }
             RequestMessage::StateChunk(block_hash, chunk_root) => {
                 self.create_state_chunk_response(block_hash, chunk_root)
             }
@@ -789,6 +933,7 @@ impl Extension {
                 ..
             } => true,
             RequestMessage::Bodies(hashes) => !hashes.is_empty(),
+            RequestMessage::Transactions(_, indexes) => !indexes.is_empty(),
             RequestMessage::StateChunk {
                 ..
             } => true,
@@ -829,6 +974,13 @@ impl Extension {
         ResponseMessage::Bodies(bodies)
     }
 
+    /// Responds nothing if the block is unknown or any of the indexes is out of its body.
+    fn create_transactions_response(&self, hash: BlockHash, indexes: Vec<u64>) -> ResponseMessage {
+        let body = self.client.block_body(&BlockId::Hash(hash)).map(|body| body.transactions()).unwrap_or_default();
+        let transactions = indexes.into_iter().map(|index| body.get(index as usize).cloned()).collect::<Option<_>>();
+        ResponseMessage::Transactions(transactions.unwrap_or_default())
+    }
+
     fn create_state_chunk_response(&self, hash: BlockHash, chunk_roots: Vec<H256>) -> ResponseMessage {
         let mut result = Vec::new();
         for root in chunk_roots {
@@ -898,6 +1050,14 @@ impl Extension {
                     self.dismiss_request(from, id);
                     self.on_chunk_response(from, &roots, &chunks);
                 }
+                ResponseMessage::Transactions(transactions) => {
+                    let (hash, indexes) = match request {
+                        RequestMessage::Transactions(hash, indexes) => (hash, indexes),
+                        _ => unreachable!(),
+                    };
+                    self.dismiss_request(from, id);
+                    self.on_transactions_response(hash, &indexes, transactions);
+                }
             }
         }
     }
@@ -934,22 +1094,20 @@ impl Extension {
                     );
                     return false
                 }
-                for body in bodies {
-                    for tx in body {
-                        let is_valid = match &tx.action {
-                            Action::Custom {
-                                handler_id,
-                                ..
-                            } => self.client.find_action_handler_for(*handler_id).is_some(),
-                            _ => true,
-                        };
-                        if !is_valid {
-                            cwarn!(SYNC, "Received transaction has some invalid actions");
-                            return false
-                        }
-                    }
+                bodies.iter().all(|body| self.has_valid_actions(body))
+            }
+            (RequestMessage::Transactions(_, indexes), ResponseMessage::Transactions(transactions)) => {
+                // An empty response means that the peer doesn't have them.
+                if !transactions.is_empty() && indexes.len() != transactions.len() {
+                    cwarn!(
+                        SYNC,
+                        "Received transactions' length({}) is not same with the requested indexes({})",
+                        transactions.len(),
+                        indexes.len()
+                    );
+                    return false
                 }
-                true
+                self.has_valid_actions(transactions)
             }
             (RequestMessage::StateChunk(_, roots), ResponseMessage::StateChunk(chunks)) => {
                 // Check length
@@ -962,6 +1120,23 @@ impl Extension {
         }
     }
 
+    fn has_valid_actions(&self, transactions: &[UnverifiedTransaction]) -> bool {
+        for tx in transactions {
+            let is_valid = tx.action.flatten().iter().all(|action| match action {
+                Action::Custom {
+                    handler_id,
+                    ..
+                } => self.client.find_action_handler_for(*handler_id).is_some(),
+                _ => true,
+            });
+            if !is_valid {
+                cwarn!(SYNC, "Received transaction has some invalid actions");
+                return false
+            }
+        }
+        true
+    }
+
     fn on_header_response(&mut self, from: &NodeId, headers: &[Header]) {
         ctrace!(SYNC, "Received header response from({}) with length({})", from, headers.len());
         match self.state {
@@ -1122,6 +1297,19 @@ impl Extension {
         }
     }
 
+    fn on_transactions_response(&mut self, hash: BlockHash, indexes: &[u64], transactions: Vec<UnverifiedTransaction>) {
+        ctrace!(SYNC, "Received transactions response with length({}) for {}", transactions.len(), hash);
+        let mut block = match self.pending_compact_blocks.remove(&hash) {
+            Some(block) => block,
+            None => return,
+        };
+        if !block.fill(indexes, transactions) {
+            cdebug!(SYNC, "Missing transactions of the compact block({}) are not received", hash);
+            return
+        }
+        self.import_compact_block(block);
+    }
+
     fn on_chunk_response(&mut self, from: &NodeId, roots: &[H256], chunks: &[Vec<u8>]) {
         assert_eq!(roots.len(), chunks.len());
         let (block, restore) = match self.state {
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use ctypes::BlockHash;
+use ctypes::{BlockHash, Header};
 use primitives::U256;
 use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
 
@@ -32,6 +32,9 @@ pub enum MessageID {
     Headers = 0x03,
     GetBodies = 0x04,
     Bodies = 0x05,
+    CompactBlock = 0x06,
+    GetTransactions = 0x07,
+    Transactions = 0x08,
     GetStateChunk = 0x0a,
     StateChunk = 0x0b,
 }
@@ -51,6 +54,9 @@ impl Decodable for MessageID {
             0x03 => Ok(MessageID::Headers),
             0x04 => Ok(MessageID::GetBodies),
             0x05 => Ok(MessageID::Bodies),
+            0x06 => Ok(MessageID::CompactBlock),
+            0x07 => Ok(MessageID::GetTransactions),
+            0x08 => Ok(MessageID::Transactions),
             0x0a => Ok(MessageID::GetStateChunk),
             0x0b => Ok(MessageID::StateChunk),
             _ => Err(DecoderError::Custom("Unexpected MessageID Value")),
@@ -65,6 +71,11 @@ pub enum Message {
         best_hash: BlockHash,
         genesis_hash: BlockHash,
     },
+    /// Announces a new block with the short ids of its transactions instead of its body.
+    CompactBlock {
+        header: Header,
+        short_ids: Vec<u64>,
+    },
     Request(u64, RequestMessage),
     Response(u64, ResponseMessage),
 }
@@ -85,6 +96,17 @@ impl Encodable for Message {
                 s.append(best_hash);
                 s.append(genesis_hash);
             }
+            Message::CompactBlock {
+                header,
+                short_ids,
+            } => {
+                s.begin_list(2);
+                s.append(&MessageID::CompactBlock);
+
+                s.begin_list(2);
+                s.append(header);
+                s.append_list(short_ids);
+            }
             Message::Request(request_id, request) => {
                 s.begin_list(3);
                 s.append(&request.message_id());
@@ -129,6 +151,29 @@ impl Decodable for Message {
                     genesis_hash: message.val_at(2)?,
                 })
             }
+            MessageID::CompactBlock => {
+                let item_count = rlp.item_count()?;
+                if item_count != 2 {
+                    return Err(DecoderError::RlpIncorrectListLen {
+                        got: item_count,
+                        expected: 2,
+                    })
+                }
+                let message = rlp.at(1)?;
+
+                let message_item_count = message.item_count()?;
+                if message_item_count != 2 {
+                    return Err(DecoderError::RlpIncorrectListLen {
+                        expected: 2,
+                        got: message_item_count,
+                    })
+                }
+
+                Ok(Message::CompactBlock {
+                    header: message.val_at(0)?,
+                    short_ids: message.list_at(1)?,
+                })
+            }
             _ => {
                 let item_count = rlp.item_count()?;
                 if item_count != 3 {
@@ -140,11 +185,14 @@ impl Decodable for Message {
                 let request_id = rlp.val_at(1)?;
                 let message = rlp.at(2)?;
                 match id {
-                    MessageID::GetHeaders | MessageID::GetBodies | MessageID::GetStateChunk => {
+                    MessageID::GetHeaders
+                    | MessageID::GetBodies
+                    | MessageID::GetTransactions
+                    | MessageID::GetStateChunk => {
                         Ok(Message::Request(request_id, RequestMessage::decode(id, &message)?))
                     }
 
-                    MessageID::Headers | MessageID::Bodies | MessageID::StateChunk => {
+                    MessageID::Headers | MessageID::Bodies | MessageID::Transactions | MessageID::StateChunk => {
                         Ok(Message::Response(request_id, ResponseMessage::decode(id, &message)?))
                     }
                     _ => Err(DecoderError::Custom("Unknown message id detected")),
@@ -175,4 +223,26 @@ mod tests {
         let request_id = 10;
         rlp_encode_and_decode_test!(Message::Request(request_id, RequestMessage::Bodies(vec![])));
     }
+
+    #[test]
+    fn compact_block_message_rlp() {
+        let header = Header::default();
+        header.hash();
+        rlp_encode_and_decode_test!(Message::CompactBlock {
+            header,
+            short_ids: vec![1, 2, 3],
+        });
+    }
+
+    #[test]
+    fn truncated_message_is_not_decoded() {
+        let message = Message::CompactBlock {
+            header: Header::default(),
+            short_ids: vec![1, 2, 3],
+        };
+        let encoded = rlp::encode(&message);
+        for length in 0..encoded.len() {
+            assert!(rlp::decode::<Message>(&encoded[..length]).is_err(), "{} bytes", length);
+        }
+    }
 }
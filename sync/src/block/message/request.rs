@@ -26,6 +26,8 @@ pub enum RequestMessage {
         max_count: u64,
     },
     Bodies(Vec<BlockHash>),
+    /// The transactions at the indexes in the body of the block.
+    Transactions(BlockHash, Vec<u64>),
     StateChunk(BlockHash, Vec<H256>),
 }
 
@@ -43,6 +45,11 @@ impl Encodable for RequestMessage {
             RequestMessage::Bodies(hashes) => {
                 s.append_list(hashes);
             }
+            RequestMessage::Transactions(block_hash, indexes) => {
+                s.begin_list(2);
+                s.append(block_hash);
+                s.append_list(indexes);
+            }
             RequestMessage::StateChunk(block_hash, merkle_roots) => {
                 s.begin_list(2);
                 s.append(block_hash);
@@ -59,6 +66,7 @@ impl RequestMessage {
                 ..
             } => MessageID::GetHeaders,
             RequestMessage::Bodies(..) => MessageID::GetBodies,
+            RequestMessage::Transactions(..) => MessageID::GetTransactions,
             RequestMessage::StateChunk {
                 ..
             } => MessageID::GetStateChunk,
@@ -81,6 +89,16 @@ impl RequestMessage {
                 }
             }
             MessageID::GetBodies => RequestMessage::Bodies(rlp.as_list()?),
+            MessageID::GetTransactions => {
+                let item_count = rlp.item_count()?;
+                if item_count != 2 {
+                    return Err(DecoderError::RlpIncorrectListLen {
+                        got: item_count,
+                        expected: 2,
+                    })
+                }
+                RequestMessage::Transactions(rlp.val_at(0)?, rlp.list_at(1)?)
+            }
             MessageID::GetStateChunk => {
                 let item_count = rlp.item_count()?;
                 if item_count != 2 {
@@ -125,6 +143,12 @@ mod tests {
         assert_eq!(message, decode_bytes(message.message_id(), message.rlp_bytes().as_ref()));
     }
 
+    #[test]
+    fn request_transactions_message_rlp() {
+        let message = RequestMessage::Transactions(H256::default().into(), vec![0, 3]);
+        assert_eq!(message, decode_bytes(message.message_id(), message.rlp_bytes().as_ref()));
+    }
+
     #[test]
     fn request_state_chunk_message_rlp() {
         let message = RequestMessage::StateChunk(H256::default().into(), vec![H256::default()]);
@@ -25,6 +25,7 @@ use super::MessageID;
 pub enum ResponseMessage {
     Headers(Vec<Header>),
     Bodies(Vec<Vec<UnverifiedTransaction>>),
+    Transactions(Vec<UnverifiedTransaction>),
     StateChunk(Vec<Vec<u8>>),
 }
 
@@ -53,6 +54,9 @@ impl Encodable for ResponseMessage {
 
                 s.append(&compressed);
             }
+            ResponseMessage::Transactions(transactions) => {
+                s.append_list(transactions);
+            }
             ResponseMessage::StateChunk(chunks) => {
                 s.append_list::<Vec<u8>, Vec<u8>>(chunks);
             }
@@ -67,6 +71,7 @@ impl ResponseMessage {
                 ..
             } => MessageID::Headers,
             ResponseMessage::Bodies(..) => MessageID::Bodies,
+            ResponseMessage::Transactions(..) => MessageID::Transactions,
             ResponseMessage::StateChunk {
                 ..
             } => MessageID::StateChunk,
@@ -103,6 +108,7 @@ impl ResponseMessage {
                 }
                 ResponseMessage::Bodies(bodies)
             }
+            MessageID::Transactions => ResponseMessage::Transactions(rlp.as_list()?),
             MessageID::StateChunk => ResponseMessage::StateChunk(rlp.as_list()?),
             _ => return Err(DecoderError::Custom("Unknown message id detected")),
         };
@@ -14,8 +14,11 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+mod compact;
 mod downloader;
 mod extension;
 mod message;
 
 pub use self::extension::{BlockSyncSender, Event as BlockSyncEvent, Extension as BlockSyncExtension};
+#[cfg(feature = "fuzzing")]
+pub use self::message::Message as BlockSyncMessage;
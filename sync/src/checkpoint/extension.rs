@@ -0,0 +1,231 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::message::Message;
+use ccore::{
+    BlockChainTrait, BlockId, Checkpoint, CheckpointSignature, Client, EngineSigner, LightClientSupport,
+    SignedCheckpoint,
+};
+use cnetwork::{Api, EventSender, NetworkExtension, NodeId};
+use ctimer::TimerToken;
+use ctypes::BlockNumber;
+use rlp::{Encodable, Rlp};
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+const CHECKPOINT_TIMER_TOKEN: TimerToken = 0;
+const CHECKPOINT_TIMER_INTERVAL: u64 = 1000;
+/// The number of the recent checkpoints kept and served.
+const MAX_CHECKPOINTS: usize = 16;
+/// The number of the signatures kept for the checkpoints which are not made locally yet.
+const MAX_FUTURE_SIGNATURES: usize = 1024;
+
+pub struct Extension {
+    /// Sorted by the block number.
+    checkpoints: VecDeque<SignedCheckpoint>,
+    future_signatures: Vec<CheckpointSignature>,
+    peers: HashSet<NodeId>,
+    client: Arc<Client>,
+    signer: Option<EngineSigner>,
+    interval: BlockNumber,
+    api: Box<dyn Api>,
+}
+
+impl Extension {
+    /// Makes a checkpoint at every `interval` blocks, which is signed by `signer` if it's one of the validators.
+    pub fn new(client: Arc<Client>, signer: Option<EngineSigner>, interval: BlockNumber, api: Box<dyn Api>) -> Self {
+        assert_ne!(0, interval);
+        api.set_timer(CHECKPOINT_TIMER_TOKEN, Duration::from_millis(CHECKPOINT_TIMER_INTERVAL))
+            .expect("Timer set succeeds");
+        Extension {
+            checkpoints: Default::default(),
+            future_signatures: Default::default(),
+            peers: Default::default(),
+            client,
+            signer,
+            interval,
+            api,
+        }
+    }
+}
+
+impl NetworkExtension<Event> for Extension {
+    fn name() -> &'static str {
+        "checkpoint-propagation"
+    }
+    fn need_encryption() -> bool {
+        false
+    }
+
+    fn versions() -> &'static [u64] {
+        const VERSIONS: &[u64] = &[0];
+        &VERSIONS
+    }
+
+    fn on_node_added(&mut self, id: &NodeId, _version: u64) {
+        self.peers.insert(*id);
+        if let Some(latest) = self.checkpoints.back() {
+            let signatures = latest
+                .signatures
+                .iter()
+                .map(|(index, signature)| CheckpointSignature {
+                    checkpoint: latest.checkpoint.clone(),
+                    signer: latest.validators[*index].pubkey,
+                    signature: *signature,
+                })
+                .collect();
+            self.api.send(id, Arc::new(Message::Signatures(signatures).rlp_bytes()));
+        }
+    }
+
+    fn on_node_removed(&mut self, id: &NodeId) {
+        self.peers.remove(id);
+    }
+
+    fn on_message(&mut self, id: &NodeId, data: &[u8]) {
+        if let Ok(received_message) = Rlp::new(data).as_val() {
+            match received_message {
+                Message::Signatures(signatures) => {
+                    let added: Vec<_> =
+                        signatures.into_iter().filter(|signature| self.add_signature(id, signature)).collect();
+                    if !added.is_empty() {
+                        self.broadcast(added, Some(id));
+                    }
+                }
+            }
+        } else {
+            cwarn!(CHECKPOINT, "Invalid message from peer {}", id);
+        }
+    }
+
+    fn on_timeout(&mut self, timer: TimerToken) {
+        match timer {
+            CHECKPOINT_TIMER_TOKEN => self.make_checkpoint(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn on_event(&mut self, event: Event) {
+        match event {
+            Event::GetCheckpoints(channel) => {
+                for checkpoint in self.checkpoints.iter().rev() {
+                    channel.send(checkpoint.clone()).unwrap();
+                }
+            }
+        }
+    }
+}
+
+pub enum Event {
+    /// Gets the kept checkpoints from the latest one.
+    GetCheckpoints(EventSender<SignedCheckpoint>),
+}
+
+impl Extension {
+    fn make_checkpoint(&mut self) {
+        let best_number = self.client.chain_info().best_block_number;
+        let number = best_number - best_number % self.interval;
+        if number == 0 || self.checkpoints.back().map_or(false, |latest| latest.checkpoint.number >= number) {
+            return
+        }
+        let header = match self.client.block_header(&BlockId::Number(number)) {
+            Some(header) => header.decode(),
+            None => return,
+        };
+        // Only the engines with validators make checkpoints.
+        let validators = match self.client.commit_validators(BlockId::Number(number)) {
+            Some(validators) => validators,
+            None => return,
+        };
+        let mut signed = SignedCheckpoint::new(Checkpoint::new(&header, &validators), validators);
+        let own_signature = self.sign(&mut signed);
+
+        let (received, future) = self
+            .future_signatures
+            .drain(..)
+            .partition::<Vec<_>, _>(|signature| signature.checkpoint.number <= number);
+        self.future_signatures = future;
+        for signature in received.into_iter().filter(|signature| signature.checkpoint == signed.checkpoint) {
+            signed.add_signature(&signature.signer, signature.signature);
+        }
+
+        cinfo!(
+            CHECKPOINT,
+            "Checkpoint #{}({}) is made with {} of {} voting power",
+            number,
+            signed.checkpoint.block_hash,
+            signed.voting_power(),
+            signed.total_voting_power()
+        );
+        self.checkpoints.push_back(signed);
+        if self.checkpoints.len() > MAX_CHECKPOINTS {
+            self.checkpoints.pop_front();
+        }
+        if let Some(signature) = own_signature {
+            self.broadcast(vec![signature], None);
+        }
+    }
+
+    fn sign(&self, signed: &mut SignedCheckpoint) -> Option<CheckpointSignature> {
+        let signer = self.signer.as_ref()?;
+        let public = *signer.public()?;
+        if signed.validators.iter().all(|validator| validator.pubkey != public) {
+            return None
+        }
+        let signature = match signer.sign(signed.checkpoint.hash()) {
+            Ok(signature) => signature,
+            Err(err) => {
+                cwarn!(CHECKPOINT, "Cannot sign the checkpoint #{}: {}", signed.checkpoint.number, err);
+                return None
+            }
+        };
+        signed.add_signature(&public, signature);
+        Some(CheckpointSignature {
+            checkpoint: signed.checkpoint.clone(),
+            signer: public,
+            signature,
+        })
+    }
+
+    /// Returns true if the signature is new and valid, which should be relayed to the other peers.
+    fn add_signature(&mut self, from: &NodeId, signature: &CheckpointSignature) -> bool {
+        let number = signature.checkpoint.number;
+        match self.checkpoints.iter_mut().find(|signed| signed.checkpoint.number == number) {
+            Some(signed) if signed.checkpoint == signature.checkpoint => {
+                signed.add_signature(&signature.signer, signature.signature)
+            }
+            Some(_) => {
+                cwarn!(CHECKPOINT, "Peer {} sent a signature on a different checkpoint #{}", from, number);
+                false
+            }
+            None => {
+                let latest = self.checkpoints.back().map_or(0, |latest| latest.checkpoint.number);
+                if number > latest && self.future_signatures.len() < MAX_FUTURE_SIGNATURES {
+                    self.future_signatures.push(signature.clone());
+                }
+                false
+            }
+        }
+    }
+
+    fn broadcast(&self, signatures: Vec<CheckpointSignature>, except: Option<&NodeId>) {
+        let message = Arc::new(Message::Signatures(signatures).rlp_bytes());
+        for id in self.peers.iter().filter(|id| Some(*id) != except) {
+            self.api.send(id, Arc::clone(&message));
+        }
+    }
+}
@@ -0,0 +1,67 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ccore::CheckpointSignature;
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+
+#[derive(Debug, PartialEq)]
+pub enum Message {
+    Signatures(Vec<CheckpointSignature>),
+}
+
+impl Encodable for Message {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        match self {
+            Message::Signatures(signatures) => {
+                s.append_list(signatures);
+            }
+        }
+    }
+}
+
+impl Decodable for Message {
+    fn decode(rlp: &Rlp<'_>) -> Result<Self, DecoderError> {
+        Ok(Message::Signatures(rlp.as_list()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlp::rlp_encode_and_decode_test;
+
+    use ccore::{Checkpoint, CheckpointSignature};
+    use ckey::{Public, SchnorrSignature};
+    use primitives::H256;
+
+    use super::Message;
+
+    #[test]
+    fn signatures_message_rlp() {
+        rlp_encode_and_decode_test!(Message::Signatures(Vec::new()));
+
+        let signature = CheckpointSignature {
+            checkpoint: Checkpoint {
+                number: 100,
+                block_hash: H256::random().into(),
+                state_root: H256::random(),
+                validator_set_hash: H256::random(),
+            },
+            signer: Public::random(),
+            signature: SchnorrSignature::default(),
+        };
+        rlp_encode_and_decode_test!(Message::Signatures(vec![signature]));
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::message::Message;
+use ccore::{Client, FraudProof, FraudProofClient};
+use cnetwork::{Api, NetworkExtension, NodeId};
+use ctimer::TimerToken;
+use ctypes::BlockHash;
+use never_type::Never;
+use primitives::Bytes;
+use rlp::{Encodable, Rlp};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+const BROADCAST_TIMER_TOKEN: TimerToken = 0;
+const BROADCAST_TIMER_INTERVAL: u64 = 1000;
+/// Verifying a proof executes the block, so a peer can't make the node verify many proofs at once.
+const MAX_PROOFS_PER_MESSAGE: usize = 4;
+
+/// Gossips the proofs of the invalid blocks, so the other nodes reject them without executing them.
+pub struct Extension {
+    /// The blocks proven to be invalid, whose proofs are already sent to the peers.
+    known: HashSet<BlockHash>,
+    peers: HashSet<NodeId>,
+    client: Arc<Client>,
+    api: Box<dyn Api>,
+}
+
+impl Extension {
+    pub fn new(client: Arc<Client>, api: Box<dyn Api>) -> Self {
+        api.set_timer(BROADCAST_TIMER_TOKEN, Duration::from_millis(BROADCAST_TIMER_INTERVAL))
+            .expect("Timer set succeeds");
+        Extension {
+            known: Default::default(),
+            peers: Default::default(),
+            client,
+            api,
+        }
+    }
+}
+
+impl NetworkExtension<Never> for Extension {
+    fn name() -> &'static str {
+        "fraud-proof-propagation"
+    }
+    fn need_encryption() -> bool {
+        false
+    }
+
+    fn versions() -> &'static [u64] {
+        const VERSIONS: &[u64] = &[0];
+        &VERSIONS
+    }
+
+    fn on_node_added(&mut self, id: &NodeId, _version: u64) {
+        self.peers.insert(*id);
+        for message in messages(&self.client.fraud_proofs()) {
+            self.api.send(id, message);
+        }
+    }
+
+    fn on_node_removed(&mut self, id: &NodeId) {
+        self.peers.remove(id);
+    }
+
+    fn on_message(&mut self, id: &NodeId, data: &[u8]) {
+        if let Ok(received_message) = Rlp::new(data).as_val() {
+            match received_message {
+                Message::FraudProofs(proofs) => {
+                    if proofs.len() > MAX_PROOFS_PER_MESSAGE {
+                        cwarn!(SYNC, "Peer {} sent {} fraud proofs at once", id, proofs.len());
+                        return
+                    }
+                    let accepted: Vec<_> =
+                        proofs.into_iter().filter(|proof| self.import_fraud_proof(id, proof)).collect();
+                    if !accepted.is_empty() {
+                        self.broadcast(accepted, Some(id));
+                    }
+                }
+            }
+        } else {
+            cwarn!(SYNC, "Invalid message from peer {}", id);
+        }
+    }
+
+    fn on_timeout(&mut self, timer: TimerToken) {
+        match timer {
+            BROADCAST_TIMER_TOKEN => {
+                let proofs: Vec<_> = self
+                    .client
+                    .fraud_proofs()
+                    .into_iter()
+                    .filter(|proof| self.known.insert(proof.block_hash()))
+                    .collect();
+                if !proofs.is_empty() {
+                    self.broadcast(proofs, None);
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Extension {
+    /// Returns true if the proof is new and valid, which should be relayed to the other peers.
+    fn import_fraud_proof(&mut self, from: &NodeId, proof: &FraudProof) -> bool {
+        let block_hash = proof.block_hash();
+        if self.known.contains(&block_hash) {
+            return false
+        }
+        match self.client.import_fraud_proof(proof.clone()) {
+            Ok(true) => {
+                self.known.insert(block_hash);
+                true
+            }
+            Ok(false) => false,
+            Err(err) => {
+                cwarn!(SYNC, "Peer {} sent an invalid fraud proof of {}: {}", from, block_hash, err);
+                false
+            }
+        }
+    }
+
+    fn broadcast(&self, proofs: Vec<FraudProof>, except: Option<&NodeId>) {
+        for message in messages(&proofs) {
+            for id in self.peers.iter().filter(|id| Some(*id) != except) {
+                self.api.send(id, Arc::clone(&message));
+            }
+        }
+    }
+}
+
+fn messages(proofs: &[FraudProof]) -> Vec<Arc<Bytes>> {
+    proofs
+        .chunks(MAX_PROOFS_PER_MESSAGE)
+        .map(|chunk| Arc::new(Message::FraudProofs(chunk.to_vec()).rlp_bytes()))
+        .collect()
+}
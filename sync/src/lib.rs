@@ -16,6 +16,7 @@
 
 extern crate codechain_core as ccore;
 extern crate codechain_db as cdb;
+extern crate codechain_key as ckey;
 #[macro_use]
 extern crate codechain_logger as clogger;
 extern crate codechain_network as cnetwork;
@@ -26,17 +27,24 @@ extern crate codechain_types as ctypes;
 extern crate kvdb_memorydb;
 #[macro_use]
 extern crate log;
+#[macro_use]
+extern crate serde_derive;
 #[cfg(test)]
 extern crate tempfile;
 #[cfg(test)]
 extern crate trie_standardmap;
 
 mod block;
+mod checkpoint;
+mod fraud_proof;
+mod light;
 pub mod snapshot;
 mod transaction;
 
 pub use crate::block::{BlockSyncEvent, BlockSyncExtension, BlockSyncSender};
+#[cfg(feature = "fuzzing")]
+pub use crate::block::BlockSyncMessage;
+pub use crate::checkpoint::{CheckpointEvent, CheckpointExtension};
+pub use crate::fraud_proof::FraudProofExtension;
+pub use crate::light::LightServerExtension;
 pub use crate::transaction::TransactionSyncExtension;
-
-#[cfg(test)]
-extern crate codechain_key as ckey;
@@ -0,0 +1,188 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::message::{Message, Proof, RequestMessage, ResponseMessage};
+use ccore::{BlockChainClient, BlockChainTrait, BlockId, Client, LightClientSupport, Shard, TransactionId};
+use cnetwork::{Api, NetworkExtension, NodeId};
+use ctimer::TimerToken;
+use never_type::Never;
+use rlp::{Encodable, Rlp};
+use std::cmp::min;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+const REFILL_TIMER_TOKEN: TimerToken = 0;
+const REFILL_TIMER_INTERVAL: u64 = 1000;
+/// The budget of a peer, which bounds the burst of its requests.
+const MAX_BUDGET: u64 = 1000;
+/// The budget restored to every peer at each `REFILL_TIMER_INTERVAL`.
+const REFILL_PER_INTERVAL: u64 = 100;
+const MAX_HEADERS: u64 = 128;
+const HEADER_COST: u64 = 1;
+const STATE_PROOF_COST: u64 = 20;
+/// A transaction proof carries the whole body of the block.
+const TRANSACTION_PROOF_COST: u64 = 50;
+
+/// Answers the requests of light clients, which follow the headers and read the state through proofs.
+pub struct Extension {
+    /// The remaining budget of each peer.
+    peers: HashMap<NodeId, u64>,
+    client: Arc<Client>,
+    api: Box<dyn Api>,
+}
+
+impl Extension {
+    pub fn new(client: Arc<Client>, api: Box<dyn Api>) -> Self {
+        api.set_timer(REFILL_TIMER_TOKEN, Duration::from_millis(REFILL_TIMER_INTERVAL)).expect("Timer set succeeds");
+        Extension {
+            peers: Default::default(),
+            client,
+            api,
+        }
+    }
+}
+
+impl NetworkExtension<Never> for Extension {
+    fn name() -> &'static str {
+        "light-protocol"
+    }
+    fn need_encryption() -> bool {
+        false
+    }
+
+    fn versions() -> &'static [u64] {
+        const VERSIONS: &[u64] = &[0];
+        &VERSIONS
+    }
+
+    fn on_node_added(&mut self, id: &NodeId, _version: u64) {
+        self.peers.insert(*id, MAX_BUDGET);
+    }
+
+    fn on_node_removed(&mut self, id: &NodeId) {
+        self.peers.remove(id);
+    }
+
+    fn on_message(&mut self, id: &NodeId, data: &[u8]) {
+        match Rlp::new(data).as_val() {
+            Ok(Message::Request(request_id, request)) => self.on_request(id, request_id, request),
+            Ok(Message::Response(..)) => cdebug!(LIGHT, "Unexpected response from peer {}", id),
+            Err(_) => cwarn!(LIGHT, "Invalid message from peer {}", id),
+        }
+    }
+
+    fn on_timeout(&mut self, timer: TimerToken) {
+        match timer {
+            REFILL_TIMER_TOKEN => {
+                for budget in self.peers.values_mut() {
+                    *budget = min(MAX_BUDGET, *budget + REFILL_PER_INTERVAL);
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Extension {
+    fn on_request(&mut self, from: &NodeId, request_id: u64, request: RequestMessage) {
+        let budget = match self.peers.get_mut(from) {
+            Some(budget) => budget,
+            None => {
+                cwarn!(LIGHT, "Request from invalid peer #{} received", from);
+                return
+            }
+        };
+        let cost = cost(&request);
+        let response = if *budget < cost {
+            cdebug!(LIGHT, "Peer {} ran out of its budget", from);
+            ResponseMessage::Busy
+        } else {
+            *budget -= cost;
+            self.create_response(request)
+        };
+        self.api.send(from, Arc::new(Message::Response(request_id, response).rlp_bytes()));
+    }
+
+    fn create_response(&self, request: RequestMessage) -> ResponseMessage {
+        match request {
+            RequestMessage::Headers {
+                start_number,
+                max_count,
+            } => {
+                let headers = (start_number..start_number.saturating_add(min(max_count, MAX_HEADERS)))
+                    .map(|number| self.client.block_header(&BlockId::Number(number)))
+                    .take_while(Option::is_some)
+                    .map(|header| header.expect("take_while guarantees existance of item").decode())
+                    .collect();
+                ResponseMessage::Headers(headers)
+            }
+            RequestMessage::AccountProof {
+                block_hash,
+                address,
+            } => match self.client.account_proof(&address, BlockId::Hash(block_hash)) {
+                Some(proof) => ResponseMessage::AccountProof(Proof {
+                    nodes: proof.nodes,
+                    value: proof.value,
+                }),
+                None => ResponseMessage::NotFound,
+            },
+            RequestMessage::TransactionProof(tx_hash) => {
+                let location = self.client.transaction(&TransactionId::Hash(tx_hash));
+                let body = location.as_ref().and_then(|tx| self.client.block_body(&BlockId::Hash(tx.block_hash)));
+                match (location, body) {
+                    (Some(tx), Some(body)) => ResponseMessage::TransactionProof {
+                        block_hash: tx.block_hash,
+                        index: tx.transaction_index as u64,
+                        transactions: body.transactions(),
+                    },
+                    _ => ResponseMessage::NotFound,
+                }
+            }
+            RequestMessage::ShardProof {
+                block_hash,
+                shard_id,
+                key,
+            } => match self.client.shard_proof(shard_id, &key, BlockId::Hash(block_hash)) {
+                Some(proof) => ResponseMessage::ShardProof {
+                    shard_nodes: proof.shard_nodes,
+                    shard_root: proof.shard_root,
+                    value: Proof {
+                        nodes: proof.value_nodes,
+                        value: proof.value,
+                    },
+                },
+                None => ResponseMessage::NotFound,
+            },
+        }
+    }
+}
+
+fn cost(request: &RequestMessage) -> u64 {
+    match request {
+        RequestMessage::Headers {
+            max_count,
+            ..
+        } => HEADER_COST * min(*max_count, MAX_HEADERS),
+        RequestMessage::AccountProof {
+            ..
+        }
+        | RequestMessage::ShardProof {
+            ..
+        } => STATE_PROOF_COST,
+        RequestMessage::TransactionProof(..) => TRANSACTION_PROOF_COST,
+    }
+}
@@ -0,0 +1,430 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ccore::UnverifiedTransaction;
+use ckey::Address;
+use ctypes::{BlockHash, BlockNumber, Header, ShardId, TxHash};
+use primitives::{Bytes, H256};
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+
+#[derive(Clone, Copy)]
+#[repr(u8)]
+pub enum MessageID {
+    GetHeaders = 0x01,
+    Headers = 0x02,
+    GetAccountProof = 0x03,
+    AccountProof = 0x04,
+    GetTransactionProof = 0x05,
+    TransactionProof = 0x06,
+    GetShardProof = 0x07,
+    ShardProof = 0x08,
+    NotFound = 0x09,
+    Busy = 0x0a,
+}
+
+impl Encodable for MessageID {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.append_single_value(&(*self as u8));
+    }
+}
+
+impl Decodable for MessageID {
+    fn decode(rlp: &Rlp<'_>) -> Result<Self, DecoderError> {
+        let tag = rlp.as_val()?;
+        match tag {
+            0x01u8 => Ok(MessageID::GetHeaders),
+            0x02 => Ok(MessageID::Headers),
+            0x03 => Ok(MessageID::GetAccountProof),
+            0x04 => Ok(MessageID::AccountProof),
+            0x05 => Ok(MessageID::GetTransactionProof),
+            0x06 => Ok(MessageID::TransactionProof),
+            0x07 => Ok(MessageID::GetShardProof),
+            0x08 => Ok(MessageID::ShardProof),
+            0x09 => Ok(MessageID::NotFound),
+            0x0a => Ok(MessageID::Busy),
+            _ => Err(DecoderError::Custom("Unexpected MessageID Value")),
+        }
+    }
+}
+
+/// The nodes of a trie on the path to a key and the value at the key.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Proof {
+    pub nodes: Vec<Bytes>,
+    /// `None` if the trie doesn't have the key.
+    pub value: Option<Bytes>,
+}
+
+impl Encodable for Proof {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(2);
+        s.append_list::<Bytes, Bytes>(&self.nodes);
+        match &self.value {
+            Some(value) => {
+                s.begin_list(1);
+                s.append(value);
+            }
+            None => {
+                s.begin_list(0);
+            }
+        }
+    }
+}
+
+impl Decodable for Proof {
+    fn decode(rlp: &Rlp<'_>) -> Result<Self, DecoderError> {
+        let item_count = rlp.item_count()?;
+        if item_count != 2 {
+            return Err(DecoderError::RlpIncorrectListLen {
+                got: item_count,
+                expected: 2,
+            })
+        }
+        let mut value: Vec<Bytes> = rlp.list_at(1)?;
+        if value.len() > 1 {
+            return Err(DecoderError::RlpIncorrectListLen {
+                got: value.len(),
+                expected: 1,
+            })
+        }
+        Ok(Proof {
+            nodes: rlp.list_at(0)?,
+            value: value.pop(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RequestMessage {
+    Headers {
+        start_number: BlockNumber,
+        max_count: u64,
+    },
+    AccountProof {
+        block_hash: BlockHash,
+        address: Address,
+    },
+    /// The transactions of the block including the transaction.
+    TransactionProof(TxHash),
+    ShardProof {
+        block_hash: BlockHash,
+        shard_id: ShardId,
+        key: H256,
+    },
+}
+
+impl Encodable for RequestMessage {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        match self {
+            RequestMessage::Headers {
+                start_number,
+                max_count,
+            } => {
+                s.begin_list(2);
+                s.append(start_number);
+                s.append(max_count);
+            }
+            RequestMessage::AccountProof {
+                block_hash,
+                address,
+            } => {
+                s.begin_list(2);
+                s.append(block_hash);
+                s.append(address);
+            }
+            RequestMessage::TransactionProof(tx_hash) => {
+                s.begin_list(1);
+                s.append(tx_hash);
+            }
+            RequestMessage::ShardProof {
+                block_hash,
+                shard_id,
+                key,
+            } => {
+                s.begin_list(3);
+                s.append(block_hash);
+                s.append(shard_id);
+                s.append(key);
+            }
+        }
+    }
+}
+
+impl RequestMessage {
+    pub fn message_id(&self) -> MessageID {
+        match self {
+            RequestMessage::Headers {
+                ..
+            } => MessageID::GetHeaders,
+            RequestMessage::AccountProof {
+                ..
+            } => MessageID::GetAccountProof,
+            RequestMessage::TransactionProof(..) => MessageID::GetTransactionProof,
+            RequestMessage::ShardProof {
+                ..
+            } => MessageID::GetShardProof,
+        }
+    }
+
+    pub fn decode(id: MessageID, rlp: &Rlp<'_>) -> Result<Self, DecoderError> {
+        let message = match id {
+            MessageID::GetHeaders => {
+                check_item_count(rlp, 2)?;
+                RequestMessage::Headers {
+                    start_number: rlp.val_at(0)?,
+                    max_count: rlp.val_at(1)?,
+                }
+            }
+            MessageID::GetAccountProof => {
+                check_item_count(rlp, 2)?;
+                RequestMessage::AccountProof {
+                    block_hash: rlp.val_at(0)?,
+                    address: rlp.val_at(1)?,
+                }
+            }
+            MessageID::GetTransactionProof => {
+                check_item_count(rlp, 1)?;
+                RequestMessage::TransactionProof(rlp.val_at(0)?)
+            }
+            MessageID::GetShardProof => {
+                check_item_count(rlp, 3)?;
+                RequestMessage::ShardProof {
+                    block_hash: rlp.val_at(0)?,
+                    shard_id: rlp.val_at(1)?,
+                    key: rlp.val_at(2)?,
+                }
+            }
+            _ => return Err(DecoderError::Custom("Unknown message id detected")),
+        };
+        Ok(message)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ResponseMessage {
+    Headers(Vec<Header>),
+    AccountProof(Proof),
+    /// The transactions of the block, which are verified against the transactions root of its header.
+    TransactionProof {
+        block_hash: BlockHash,
+        index: u64,
+        transactions: Vec<UnverifiedTransaction>,
+    },
+    /// The nodes on the path to the shard entry from the state root, and the proof of the value against the shard root.
+    ShardProof {
+        shard_nodes: Vec<Bytes>,
+        shard_root: H256,
+        value: Proof,
+    },
+    /// The requested block, transaction or shard is not known to the server.
+    NotFound,
+    /// The request exceeds the budget of the peer, which should retry later.
+    Busy,
+}
+
+impl Encodable for ResponseMessage {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        match self {
+            ResponseMessage::Headers(headers) => {
+                s.append_list(headers);
+            }
+            ResponseMessage::AccountProof(proof) => {
+                s.append(proof);
+            }
+            ResponseMessage::TransactionProof {
+                block_hash,
+                index,
+                transactions,
+            } => {
+                s.begin_list(3);
+                s.append(block_hash);
+                s.append(index);
+                s.append_list(transactions);
+            }
+            ResponseMessage::ShardProof {
+                shard_nodes,
+                shard_root,
+                value,
+            } => {
+                s.begin_list(3);
+                s.append_list::<Bytes, Bytes>(shard_nodes);
+                s.append(shard_root);
+                s.append(value);
+            }
+            ResponseMessage::NotFound | ResponseMessage::Busy => {
+                s.begin_list(0);
+            }
+        }
+    }
+}
+
+impl ResponseMessage {
+    pub fn message_id(&self) -> MessageID {
+        match self {
+            ResponseMessage::Headers(..) => MessageID::Headers,
+            ResponseMessage::AccountProof(..) => MessageID::AccountProof,
+            ResponseMessage::TransactionProof {
+                ..
+            } => MessageID::TransactionProof,
+            ResponseMessage::ShardProof {
+                ..
+            } => MessageID::ShardProof,
+            ResponseMessage::NotFound => MessageID::NotFound,
+            ResponseMessage::Busy => MessageID::Busy,
+        }
+    }
+
+    pub fn decode(id: MessageID, rlp: &Rlp<'_>) -> Result<Self, DecoderError> {
+        let message = match id {
+            MessageID::Headers => ResponseMessage::Headers(rlp.as_list()?),
+            MessageID::AccountProof => ResponseMessage::AccountProof(rlp.as_val()?),
+            MessageID::TransactionProof => {
+                check_item_count(rlp, 3)?;
+                ResponseMessage::TransactionProof {
+                    block_hash: rlp.val_at(0)?,
+                    index: rlp.val_at(1)?,
+                    transactions: rlp.list_at(2)?,
+                }
+            }
+            MessageID::ShardProof => {
+                check_item_count(rlp, 3)?;
+                ResponseMessage::ShardProof {
+                    shard_nodes: rlp.list_at(0)?,
+                    shard_root: rlp.val_at(1)?,
+                    value: rlp.val_at(2)?,
+                }
+            }
+            MessageID::NotFound => {
+                check_item_count(rlp, 0)?;
+                ResponseMessage::NotFound
+            }
+            MessageID::Busy => {
+                check_item_count(rlp, 0)?;
+                ResponseMessage::Busy
+            }
+            _ => return Err(DecoderError::Custom("Unknown message id detected")),
+        };
+        Ok(message)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Message {
+    Request(u64, RequestMessage),
+    Response(u64, ResponseMessage),
+}
+
+impl Encodable for Message {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        match self {
+            Message::Request(request_id, request) => {
+                s.begin_list(3);
+                s.append(&request.message_id());
+                s.append(request_id);
+                s.append(request);
+            }
+            Message::Response(response_id, response) => {
+                s.begin_list(3);
+                s.append(&response.message_id());
+                s.append(response_id);
+                s.append(response);
+            }
+        }
+    }
+}
+
+impl Decodable for Message {
+    fn decode(rlp: &Rlp<'_>) -> Result<Self, DecoderError> {
+        check_item_count(rlp, 3)?;
+        let id = rlp.val_at(0)?;
+        let request_id = rlp.val_at(1)?;
+        let message = rlp.at(2)?;
+        match id {
+            MessageID::GetHeaders
+            | MessageID::GetAccountProof
+            | MessageID::GetTransactionProof
+            | MessageID::GetShardProof => Ok(Message::Request(request_id, RequestMessage::decode(id, &message)?)),
+            _ => Ok(Message::Response(request_id, ResponseMessage::decode(id, &message)?)),
+        }
+    }
+}
+
+fn check_item_count(rlp: &Rlp<'_>, expected: usize) -> Result<(), DecoderError> {
+    let item_count = rlp.item_count()?;
+    if item_count != expected {
+        return Err(DecoderError::RlpIncorrectListLen {
+            got: item_count,
+            expected,
+        })
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rlp::rlp_encode_and_decode_test;
+
+    use ckey::Address;
+    use ctypes::Header;
+    use primitives::H256;
+
+    use super::{Message, Proof, RequestMessage, ResponseMessage};
+
+    #[test]
+    fn request_messages_rlp() {
+        rlp_encode_and_decode_test!(Message::Request(1, RequestMessage::Headers {
+            start_number: 100,
+            max_count: 10,
+        }));
+        rlp_encode_and_decode_test!(Message::Request(2, RequestMessage::AccountProof {
+            block_hash: H256::random().into(),
+            address: Address::random(),
+        }));
+        rlp_encode_and_decode_test!(Message::Request(3, RequestMessage::TransactionProof(H256::random().into())));
+        rlp_encode_and_decode_test!(Message::Request(4, RequestMessage::ShardProof {
+            block_hash: H256::random().into(),
+            shard_id: 1,
+            key: H256::random(),
+        }));
+    }
+
+    #[test]
+    fn response_messages_rlp() {
+        rlp_encode_and_decode_test!(Message::Response(1, ResponseMessage::Headers(vec![Header::default()])));
+        rlp_encode_and_decode_test!(Message::Response(
+            2,
+            ResponseMessage::AccountProof(Proof {
+                nodes: vec![vec![1, 2], vec![3]],
+                value: None,
+            })
+        ));
+        rlp_encode_and_decode_test!(Message::Response(3, ResponseMessage::TransactionProof {
+            block_hash: H256::random().into(),
+            index: 0,
+            transactions: Vec::new(),
+        }));
+        rlp_encode_and_decode_test!(Message::Response(4, ResponseMessage::ShardProof {
+            shard_nodes: vec![vec![1], vec![2]],
+            shard_root: H256::random(),
+            value: Proof {
+                nodes: Vec::new(),
+                value: Some(Vec::new()),
+            },
+        }));
+        rlp_encode_and_decode_test!(Message::Response(5, ResponseMessage::NotFound));
+        rlp_encode_and_decode_test!(Message::Response(6, ResponseMessage::Busy));
+    }
+}
@@ -14,6 +14,10 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+mod publish;
+
+pub use self::publish::{ChunkEntry, Manifest, Publication, Publisher, S3Target, MANIFEST_FILE, PUBLICATION_FILE};
+
 use ccore::snapshot_notify::{NotifyReceiverSource, ReceiverCanceller};
 use ccore::{BlockChainClient, BlockChainTrait, BlockId, Client};
 use cdb::{AsHashDB, HashDB};
@@ -51,6 +55,7 @@ impl Service {
         notify_receiver_source: NotifyReceiverSource,
         root_dir: String,
         expiration: Option<u64>,
+        publisher: Option<Publisher>,
     ) -> Self {
         let NotifyReceiverSource(canceller, receiver) = notify_receiver_source;
         let join_handle = spawn(move || {
@@ -63,7 +68,7 @@ impl Service {
                     cerror!(SYNC, "There isn't corresponding header for the requested block hash: {}", block_hash,);
                     continue
                 };
-                {
+                let is_ready = {
                     let db_lock = client.state_db().read();
                     if let Err(err) = snapshot(&db_lock, block_hash, state_root, &root_dir) {
                         cerror!(
@@ -73,8 +78,17 @@ impl Service {
                             state_root,
                             err
                         );
+                        false
                     } else {
-                        cinfo!(SYNC, "Snapshot is ready for block: {}", block_hash)
+                        cinfo!(SYNC, "Snapshot is ready for block: {}", block_hash);
+                        true
+                    }
+                };
+
+                // The upload may take long, so it's done after the state DB lock is released.
+                if is_ready {
+                    if let Err(err) = publish(&client, &root_dir, block_hash, state_root, publisher.as_ref()) {
+                        cerror!(SYNC, "Snapshot publishing failed for block: {}, err: {}", block_hash, err);
                     }
                 }
 
@@ -103,12 +117,39 @@ fn snapshot(db: &StateDB, block_hash: BlockHash, root: H256, dir: &str) -> Resul
         let shard_num = *metadata.number_of_shards();
         (0..shard_num).map(|n| top_state.shard_root(n))
     };
+    // A terminated shard has no root.
     for sr in shard_roots {
-        snapshot_trie(db.as_hashdb(), block_hash, sr?.expect("Shard root must exist"), dir)?;
+        if let Some(sr) = sr? {
+            snapshot_trie(db.as_hashdb(), block_hash, sr, dir)?;
+        }
     }
     Ok(())
 }
 
+/// Writes the manifest of the snapshot, and uploads the snapshot if the publisher is given.
+fn publish(
+    client: &Client,
+    root_dir: &str,
+    block_hash: BlockHash,
+    state_root: H256,
+    publisher: Option<&Publisher>,
+) -> Result<(), String> {
+    let dir = snapshot_dir(root_dir, &block_hash);
+    let block_number =
+        client.block_number(&BlockId::Hash(block_hash)).ok_or_else(|| format!("Unknown block {}", block_hash))?;
+    let manifest = Manifest::build(&dir, block_hash, block_number, state_root).map_err(|err| err.to_string())?;
+    manifest.write(&dir).map_err(|err| err.to_string())?;
+
+    let publisher = match publisher {
+        Some(publisher) => publisher,
+        None => return Ok(()),
+    };
+    let publication = publisher.publish(&dir, &manifest)?;
+    publication.write(&dir).map_err(|err| err.to_string())?;
+    cinfo!(SYNC, "Snapshot of block {} is published to {}", block_hash, publication.location);
+    Ok(())
+}
+
 fn snapshot_trie(db: &dyn HashDB, block_hash: BlockHash, root: H256, root_dir: &str) -> Result<(), SnapshotError> {
     let snapshot_dir = snapshot_dir(root_dir, &block_hash);
     fs::create_dir_all(snapshot_dir)?;
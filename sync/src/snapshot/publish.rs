@@ -0,0 +1,321 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ctypes::{BlockHash, BlockNumber};
+use hmac::{Hmac, Mac};
+use hyper::client::HttpConnector;
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE, HOST};
+use hyper::{Body, Client, Request, Uri};
+use hyper_tls::HttpsConnector;
+use primitives::H256;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::Path;
+use tokio::runtime::Builder;
+
+/// The file listing the chunks of a snapshot with their checksums.
+pub const MANIFEST_FILE: &str = "manifest.json";
+/// The file recording where a snapshot is published.
+pub const PUBLICATION_FILE: &str = "publication.json";
+
+type HttpsClient = Client<HttpsConnector<HttpConnector>>;
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub block_hash: BlockHash,
+    pub block_number: BlockNumber,
+    pub state_root: H256,
+    pub chunks: Vec<ChunkEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ChunkEntry {
+    /// The file name of the chunk, which is its root
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+impl Manifest {
+    /// Lists the chunk files in the snapshot directory with their checksums.
+    pub fn build(dir: &Path, block_hash: BlockHash, block_number: BlockNumber, state_root: H256) -> io::Result<Self> {
+        let mut chunks = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue
+            }
+            let name = match entry.file_name().into_string() {
+                Ok(name) => name,
+                Err(_) => continue,
+            };
+            if name == MANIFEST_FILE || name == PUBLICATION_FILE {
+                continue
+            }
+            let bytes = fs::read(entry.path())?;
+            chunks.push(ChunkEntry {
+                name,
+                size: bytes.len() as u64,
+                sha256: sha256_hex(&bytes),
+            });
+        }
+        chunks.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(Self {
+            block_hash,
+            block_number,
+            state_root,
+            chunks,
+        })
+    }
+
+    pub fn write(&self, dir: &Path) -> io::Result<()> {
+        let json = serde_json::to_vec_pretty(self).expect("Manifest is always serializable");
+        fs::write(dir.join(MANIFEST_FILE), json)
+    }
+}
+
+/// Where a snapshot is published.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Publication {
+    /// `s3://<bucket>/<block hash>` or `ipfs://<CID>`
+    pub location: String,
+}
+
+impl Publication {
+    pub fn write(&self, dir: &Path) -> io::Result<()> {
+        let json = serde_json::to_vec_pretty(self).expect("Publication is always serializable");
+        fs::write(dir.join(PUBLICATION_FILE), json)
+    }
+
+    /// Returns `None` if the snapshot in `dir` isn't published.
+    pub fn read(dir: &Path) -> Option<Self> {
+        let json = fs::read(dir.join(PUBLICATION_FILE)).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct S3Target {
+    /// e.g. `https://s3.us-east-1.amazonaws.com`. The bucket is addressed in the path.
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    pub access_key: String,
+    pub secret_key: String,
+}
+
+/// Uploads the snapshots so that others can bootstrap from them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Publisher {
+    S3(S3Target),
+    /// The URL of the HTTP API of an IPFS node, e.g. `http://127.0.0.1:5001`
+    Ipfs(String),
+}
+
+impl Publisher {
+    /// Uploads the chunks and the manifest of the snapshot in `dir`, and returns where they are published.
+    pub fn publish(&self, dir: &Path, manifest: &Manifest) -> Result<Publication, String> {
+        let mut files = Vec::with_capacity(manifest.chunks.len() + 1);
+        for chunk in &manifest.chunks {
+            let bytes = fs::read(dir.join(&chunk.name)).map_err(|err| format!("Cannot read {}: {}", chunk.name, err))?;
+            if sha256_hex(&bytes) != chunk.sha256 {
+                return Err(format!("The chunk {} is modified after the manifest is written", chunk.name))
+            }
+            files.push((chunk.name.clone(), bytes));
+        }
+        files.push((MANIFEST_FILE.to_string(), serde_json::to_vec_pretty(manifest).expect("Manifest is serializable")));
+
+        let mut runtime = Builder::new().basic_scheduler().enable_all().build().map_err(|err| err.to_string())?;
+        let client: HttpsClient = Client::builder().build(HttpsConnector::new());
+        let location = runtime.block_on(async {
+            match self {
+                Publisher::S3(target) => upload_to_s3(&client, target, &manifest.block_hash, files).await,
+                Publisher::Ipfs(api) => add_to_ipfs(&client, api, files).await,
+            }
+        })?;
+        Ok(Publication {
+            location,
+        })
+    }
+}
+
+async fn upload_to_s3(
+    client: &HttpsClient,
+    target: &S3Target,
+    block_hash: &BlockHash,
+    files: Vec<(String, Vec<u8>)>,
+) -> Result<String, String> {
+    let prefix = format!("{:x}", **block_hash);
+    for (name, bytes) in files {
+        let path = format!("/{}/{}/{}", target.bucket, prefix, name);
+        let uri: Uri = format!("{}{}", target.endpoint.trim_end_matches('/'), path)
+            .parse()
+            .map_err(|err| format!("Invalid S3 endpoint {}: {}", target.endpoint, err))?;
+        let host = uri.authority().ok_or_else(|| format!("Invalid S3 endpoint {}", target.endpoint))?.to_string();
+        let amz_date = time::strftime("%Y%m%dT%H%M%SZ", &time::now_utc()).expect("The format is valid");
+        let payload_hash = sha256_hex(&bytes);
+        let authorization = s3_authorization(target, &host, &path, &payload_hash, &amz_date);
+        let request = Request::put(uri)
+            .header(HOST, host)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date)
+            .header(AUTHORIZATION, authorization)
+            .body(Body::from(bytes))
+            .map_err(|err| err.to_string())?;
+        send(client, request).await.map_err(|err| format!("Cannot upload {} to S3: {}", name, err))?;
+    }
+    Ok(format!("s3://{}/{}", target.bucket, prefix))
+}
+
+/// The AWS Signature Version 4 of a PUT request to S3.
+fn s3_authorization(target: &S3Target, host: &str, path: &str, payload_hash: &str, amz_date: &str) -> String {
+    const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+    let date = &amz_date[..8];
+    let canonical_request = format!(
+        "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+        path, host, payload_hash, amz_date, SIGNED_HEADERS, payload_hash
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, target.region);
+    let string_to_sign =
+        format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, scope, sha256_hex(canonical_request.as_bytes()));
+
+    let key = signing_key(&target.secret_key, date, &target.region, "s3");
+    let signature = hmac_sha256(&key, string_to_sign.as_bytes());
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        target.access_key,
+        scope,
+        SIGNED_HEADERS,
+        to_hex(&signature)
+    )
+}
+
+fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let date_key = hmac_sha256(format!("AWS4{}", secret_key).as_bytes(), date.as_bytes());
+    let region_key = hmac_sha256(&date_key, region.as_bytes());
+    let service_key = hmac_sha256(&region_key, service.as_bytes());
+    hmac_sha256(&service_key, b"aws4_request")
+}
+
+/// Adds the files to IPFS wrapped in a directory, and returns the location of the directory.
+async fn add_to_ipfs(client: &HttpsClient, api: &str, files: Vec<(String, Vec<u8>)>) -> Result<String, String> {
+    const BOUNDARY: &str = "foundry-snapshot-boundary";
+    let mut body = Vec::new();
+    for (name, bytes) in files {
+        let header = format!(
+            concat!(
+                "--{}\r\n",
+                "Content-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n",
+                "Content-Type: application/octet-stream\r\n\r\n"
+            ),
+            BOUNDARY, name
+        );
+        body.extend_from_slice(header.as_bytes());
+        body.extend_from_slice(&bytes);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", BOUNDARY).as_bytes());
+
+    let uri: Uri = format!("{}/api/v0/add?wrap-with-directory=true&pin=true", api.trim_end_matches('/'))
+        .parse()
+        .map_err(|err| format!("Invalid IPFS API {}: {}", api, err))?;
+    let request = Request::post(uri)
+        .header(CONTENT_TYPE, format!("multipart/form-data; boundary={}", BOUNDARY))
+        .body(Body::from(body))
+        .map_err(|err| err.to_string())?;
+    let response = send(client, request).await.map_err(|err| format!("Cannot add the snapshot to IPFS: {}", err))?;
+
+    // The response has a JSON object per line. The wrapping directory has an empty name.
+    let directory = String::from_utf8_lossy(&response)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|entry| entry["Name"] == "")
+        .and_then(|entry| entry["Hash"].as_str().map(ToString::to_string))
+        .ok_or_else(|| "IPFS didn't return the hash of the snapshot directory".to_string())?;
+    Ok(format!("ipfs://{}", directory))
+}
+
+async fn send(client: &HttpsClient, request: Request<Body>) -> Result<Vec<u8>, String> {
+    let response = client.request(request).await.map_err(|err| err.to_string())?;
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.map_err(|err| err.to_string())?;
+    if !status.is_success() {
+        return Err(format!("HTTP status {}: {}", status, String::from_utf8_lossy(&body)))
+    }
+    Ok(body.to_vec())
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_varkey(key).expect("HMAC accepts a key of any length");
+    mac.input(data);
+    mac.result().code().to_vec()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    to_hex(&Sha256::digest(data))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn manifest_lists_the_chunks_with_their_checksums() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("bb"), b"second").unwrap();
+        fs::write(dir.path().join("aa"), b"").unwrap();
+        Publication {
+            location: "ipfs://Qm".to_string(),
+        }
+        .write(dir.path())
+        .unwrap();
+
+        let manifest = Manifest::build(dir.path(), BlockHash::default(), 3, H256::default()).unwrap();
+        manifest.write(dir.path()).unwrap();
+        let rebuilt = Manifest::build(dir.path(), BlockHash::default(), 3, H256::default()).unwrap();
+        assert_eq!(manifest, rebuilt);
+        assert_eq!(
+            vec![
+                ChunkEntry {
+                    name: "aa".to_string(),
+                    size: 0,
+                    sha256: "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855".to_string(),
+                },
+                ChunkEntry {
+                    name: "bb".to_string(),
+                    size: 6,
+                    sha256: sha256_hex(b"second"),
+                },
+            ],
+            manifest.chunks
+        );
+        assert_eq!(Some("ipfs://Qm".to_string()), Publication::read(dir.path()).map(|p| p.location));
+    }
+
+    #[test]
+    fn s3_signing_key_follows_the_aws_example() {
+        // https://docs.aws.amazon.com/general/latest/gr/signature-v4-examples.html
+        let key = signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam");
+        assert_eq!("f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d", to_hex(&key));
+    }
+}
@@ -0,0 +1,133 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ctypes::TxHash;
+
+const HASHES: usize = 4;
+
+/// The hashes of the transactions that a peer is known to have.
+///
+/// It keeps two generations of `capacity` hashes, and the older one is dropped when the newer one is full. So it
+/// remembers at least the latest `capacity` hashes in a fixed size. A false positive only means the transaction isn't
+/// announced to the peer, which gets it from the other peers.
+pub struct RollingBloom {
+    capacity: usize,
+    current: Generation,
+    previous: Generation,
+}
+
+struct Generation {
+    bits: Vec<u64>,
+    count: usize,
+}
+
+impl Generation {
+    fn new(words: usize) -> Self {
+        Self {
+            bits: vec![0; words],
+            count: 0,
+        }
+    }
+
+    fn positions<'a>(&self, hash: &'a TxHash) -> impl Iterator<Item = usize> + 'a {
+        let len = self.bits.len() * 64;
+        // The hashes are already uniform, so their bytes are used as the hash functions.
+        (0..HASHES).map(move |i| {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(&hash[i * 8..(i + 1) * 8]);
+            (u64::from_le_bytes(word) % len as u64) as usize
+        })
+    }
+
+    fn insert(&mut self, hash: &TxHash) {
+        for position in self.positions(hash) {
+            self.bits[position / 64] |= 1 << (position % 64);
+        }
+        self.count += 1;
+    }
+
+    fn contains(&self, hash: &TxHash) -> bool {
+        self.positions(hash).all(|position| self.bits[position / 64] & (1 << (position % 64)) != 0)
+    }
+
+    fn clear(&mut self) {
+        self.bits.iter_mut().for_each(|word| *word = 0);
+        self.count = 0;
+    }
+}
+
+impl RollingBloom {
+    /// Each generation has 10 bits for a hash, which gives about 1% of false positives.
+    pub fn new(capacity: usize) -> Self {
+        let words = (capacity * 10 + 63) / 64;
+        Self {
+            capacity,
+            current: Generation::new(words.max(1)),
+            previous: Generation::new(words.max(1)),
+        }
+    }
+
+    pub fn insert(&mut self, hash: &TxHash) {
+        if self.current.count >= self.capacity {
+            std::mem::swap(&mut self.current, &mut self.previous);
+            self.current.clear();
+        }
+        self.current.insert(hash);
+    }
+
+    pub fn contains(&self, hash: &TxHash) -> bool {
+        self.current.contains(hash) || self.previous.contains(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use primitives::H256;
+
+    #[test]
+    fn inserted_hashes_are_contained() {
+        let mut bloom = RollingBloom::new(100);
+        let hashes: Vec<TxHash> = (0..100).map(|_| H256::random().into()).collect();
+        for hash in &hashes {
+            bloom.insert(hash);
+        }
+        assert!(hashes.iter().all(|hash| bloom.contains(hash)));
+    }
+
+    #[test]
+    fn the_latest_capacity_hashes_are_remembered() {
+        let mut bloom = RollingBloom::new(100);
+        let hashes: Vec<TxHash> = (0..1000).map(|_| H256::random().into()).collect();
+        for hash in &hashes {
+            bloom.insert(hash);
+        }
+        assert!(hashes[900..].iter().all(|hash| bloom.contains(hash)));
+
+        let forgotten = hashes[..700].iter().filter(|hash| !bloom.contains(hash)).count();
+        assert!(forgotten > 650, "Only {} of the old hashes are forgotten", forgotten);
+    }
+
+    #[test]
+    fn false_positives_are_rare() {
+        let mut bloom = RollingBloom::new(1000);
+        for _ in 0..1000 {
+            bloom.insert(&H256::random().into());
+        }
+        let false_positives = (0..10_000).filter(|_| bloom.contains(&H256::random().into())).count();
+        assert!(false_positives < 500, "{} false positives", false_positives);
+    }
+}
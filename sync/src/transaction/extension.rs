@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use super::bloom::RollingBloom;
 use super::message::Message;
 use ccore::{BlockChainClient, UnverifiedTransaction};
 use cnetwork::{Api, NetworkExtension, NodeId};
@@ -23,11 +24,17 @@ use never_type::Never;
 use rlp::{Encodable, Rlp};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 const BROADCAST_TIMER_TOKEN: TimerToken = 0;
 const BROADCAST_TIMER_INTERVAL: u64 = 1000;
 const MAX_HISTORY_SIZE: usize = 100_000;
+/// The number of the latest transactions that a peer is remembered to have.
+const PEER_HISTORY_SIZE: usize = 50_000;
+/// The number of the hashes in an announce or a request message.
+const MAX_HASHES: usize = 4096;
+/// An announced transaction is requested again if it doesn't come in this time.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Default)]
 struct KnownTxs {
@@ -41,7 +48,9 @@ impl KnownTxs {
         self.history_set.insert(hash);
         self.history_queue.push_back(hash);
         if self.history_queue.len() > MAX_HISTORY_SIZE {
-            self.history_queue.pop_front();
+            if let Some(oldest) = self.history_queue.pop_front() {
+                self.history_set.remove(&oldest);
+            }
         }
     }
 
@@ -50,9 +59,17 @@ impl KnownTxs {
     }
 }
 
+struct Peer {
+    /// The peers of version 0 are pushed the transactions. The others are announced the hashes and pull the bodies.
+    version: u64,
+    known: RollingBloom,
+}
+
 pub struct Extension {
     known_txs: KnownTxs,
-    peers: HashMap<NodeId, KnownTxs>,
+    peers: HashMap<NodeId, Peer>,
+    /// The announced transactions that are requested, and when they are requested.
+    requested: HashMap<TxHash, Instant>,
     client: Arc<dyn BlockChainClient>,
     api: Box<dyn Api>,
 }
@@ -64,6 +81,7 @@ impl Extension {
         Extension {
             known_txs: Default::default(),
             peers: Default::default(),
+            requested: Default::default(),
             client,
             api,
         }
@@ -79,12 +97,18 @@ impl NetworkExtension<Never> for Extension {
     }
 
     fn versions() -> &'static [u64] {
-        const VERSIONS: &[u64] = &[0];
+        const VERSIONS: &[u64] = &[0, 1];
         &VERSIONS
     }
 
-    fn on_node_added(&mut self, token: &NodeId, _version: u64) {
-        self.peers.insert(*token, KnownTxs::default());
+    fn on_node_added(&mut self, token: &NodeId, version: u64) {
+        self.peers.insert(
+            *token,
+            Peer {
+                version,
+                known: RollingBloom::new(PEER_HISTORY_SIZE),
+            },
+        );
     }
     fn on_node_removed(&mut self, token: &NodeId) {
         self.peers.remove(token);
@@ -93,41 +117,9 @@ impl NetworkExtension<Never> for Extension {
     fn on_message(&mut self, token: &NodeId, data: &[u8]) {
         if let Ok(received_message) = Rlp::new(data).as_val() {
             match received_message {
-                Message::Transactions(transactions) => {
-                    let transactions: Vec<_> = {
-                        transactions
-                            .into_iter()
-                            .filter(|tx| {
-                                let hash = tx.hash();
-                                if self.known_txs.contains(&hash) {
-                                    false
-                                } else {
-                                    self.known_txs.push(hash);
-                                    true
-                                }
-                            })
-                            .collect()
-                    };
-
-                    self.client.queue_transactions(
-                        transactions.iter().map(|unverified| unverified.rlp_bytes().to_vec()).collect(),
-                        *token,
-                    );
-                    if let Some(peer) = self.peers.get_mut(token) {
-                        let transactions: Vec<_> = transactions
-                            .iter()
-                            .map(UnverifiedTransaction::hash)
-                            .filter(|tx_hash| !peer.contains(tx_hash))
-                            .collect();
-                        for unverified in transactions.iter() {
-                            peer.push(*unverified);
-                        }
-                        cinfo!(SYNC_TX, "Receive {} transactions from {}", transactions.len(), token);
-                        ctrace!(SYNC_TX, "Receive {:?}", transactions);
-                    } else {
-                        cwarn!(SYNC_TX, "Message from {} but it's already removed", token);
-                    }
-                }
+                Message::Transactions(transactions) => self.on_transactions(token, transactions),
+                Message::Announce(hashes) => self.on_announce(token, hashes),
+                Message::Request(hashes) => self.on_request(token, hashes),
             }
         } else {
             cwarn!(SYNC_TX, "Invalid message from peer {}", token);
@@ -136,35 +128,149 @@ impl NetworkExtension<Never> for Extension {
 
     fn on_timeout(&mut self, timer: TimerToken) {
         match timer {
-            BROADCAST_TIMER_TOKEN => self.random_broadcast(),
+            BROADCAST_TIMER_TOKEN => {
+                let now = Instant::now();
+                self.requested.retain(|_, requested_at| now < *requested_at + REQUEST_TIMEOUT);
+                self.random_broadcast()
+            }
             _ => unreachable!(),
         }
     }
 }
 
 impl Extension {
+    fn on_transactions(&mut self, token: &NodeId, transactions: Vec<UnverifiedTransaction>) {
+        let transactions: Vec<_> = {
+            transactions
+                .into_iter()
+                .filter(|tx| {
+                    let hash = tx.hash();
+                    self.requested.remove(&hash);
+                    if self.known_txs.contains(&hash) {
+                        false
+                    } else {
+                        self.known_txs.push(hash);
+                        true
+                    }
+                })
+                .collect()
+        };
+
+        self.client.queue_transactions(
+            transactions.iter().map(|unverified| unverified.rlp_bytes().to_vec()).collect(),
+            *token,
+        );
+        if let Some(peer) = self.peers.get_mut(token) {
+            let transactions: Vec<_> = transactions.iter().map(UnverifiedTransaction::hash).collect();
+            for hash in transactions.iter() {
+                peer.known.insert(hash);
+            }
+            cinfo!(SYNC_TX, "Receive {} transactions from {}", transactions.len(), token);
+            ctrace!(SYNC_TX, "Receive {:?}", transactions);
+        } else {
+            cwarn!(SYNC_TX, "Message from {} but it's already removed", token);
+        }
+    }
+
+    fn on_announce(&mut self, token: &NodeId, mut hashes: Vec<TxHash>) {
+        let peer = match self.peers.get_mut(token) {
+            Some(peer) if peer.version > 0 => peer,
+            Some(_) => {
+                cwarn!(SYNC_TX, "{} announced transactions in version 0", token);
+                return
+            }
+            None => {
+                cwarn!(SYNC_TX, "Message from {} but it's already removed", token);
+                return
+            }
+        };
+        hashes.truncate(MAX_HASHES);
+        for hash in &hashes {
+            peer.known.insert(hash);
+        }
+
+        let now = Instant::now();
+        let mut wanted = Vec::new();
+        for hash in hashes {
+            if self.known_txs.contains(&hash) || self.requested.contains_key(&hash) {
+                continue
+            }
+            self.requested.insert(hash, now);
+            wanted.push(hash);
+        }
+        if wanted.is_empty() {
+            return
+        }
+        cdebug!(SYNC_TX, "Request {} transactions from {}", wanted.len(), token);
+        self.api.send(token, Arc::new(Message::Request(wanted).rlp_bytes()));
+    }
+
+    fn on_request(&mut self, token: &NodeId, mut hashes: Vec<TxHash>) {
+        let peer = match self.peers.get_mut(token) {
+            Some(peer) if peer.version > 0 => peer,
+            Some(_) => {
+                cwarn!(SYNC_TX, "{} requested transactions in version 0", token);
+                return
+            }
+            None => {
+                cwarn!(SYNC_TX, "Message from {} but it's already removed", token);
+                return
+            }
+        };
+        hashes.truncate(MAX_HASHES);
+        let wanted: HashSet<_> = hashes.into_iter().collect();
+        let transactions: Vec<_> = self
+            .client
+            .ready_transactions(0..(::std::u64::MAX))
+            .transactions
+            .into_iter()
+            .filter(|tx| wanted.contains(&tx.hash()))
+            .map(|signed| signed.deconstruct().0)
+            .collect();
+        if transactions.is_empty() {
+            return
+        }
+        for tx in &transactions {
+            peer.known.insert(&tx.hash());
+        }
+        cinfo!(SYNC_TX, "Send {} requested transactions to {}", transactions.len(), token);
+        self.api.send(token, Arc::new(Message::Transactions(transactions).rlp_bytes()));
+    }
+
     fn random_broadcast(&mut self) {
         let transactions = self.client.ready_transactions(0..(::std::u64::MAX)).transactions;
         if transactions.is_empty() {
             ctrace!(SYNC_TX, "No transactions to propagate");
             return
         }
+        // The own transactions are known not to request them when the peers announce them back.
+        for tx in &transactions {
+            let hash = tx.hash();
+            if !self.known_txs.contains(&hash) {
+                self.known_txs.push(hash);
+            }
+        }
         for (token, peer) in &mut self.peers {
-            let unsent: Vec<_> = transactions
-                .iter()
-                .filter(|tx| !peer.contains(&tx.hash()))
-                .map(|signed| signed.clone().deconstruct().0)
-                .collect();
+            let unsent: Vec<_> = transactions.iter().filter(|tx| !peer.known.contains(&tx.hash())).collect();
             if unsent.is_empty() {
                 continue
             }
-            let unsent_hashes = unsent.iter().map(UnverifiedTransaction::hash).collect::<Vec<_>>();
+            let unsent_hashes = unsent.iter().map(|tx| tx.hash()).collect::<Vec<_>>();
             for h in unsent_hashes.iter() {
-                peer.push(*h);
+                peer.known.insert(h);
+            }
+            if peer.version == 0 {
+                cinfo!(SYNC_TX, "Send {} transactions to {}", unsent.len(), token);
+                ctrace!(SYNC_TX, "Send {:?}", unsent_hashes);
+                let unsent = unsent.into_iter().map(|signed| signed.clone().deconstruct().0).collect();
+                self.api.send(token, Arc::new(Message::Transactions(unsent).rlp_bytes()));
+            } else {
+                cinfo!(SYNC_TX, "Announce {} transactions to {}", unsent_hashes.len(), token);
+                ctrace!(SYNC_TX, "Announce {:?}", unsent_hashes);
+                for hashes in unsent_hashes.chunks(MAX_HASHES) {
+                    self.api.send(token, Arc::new(Message::Announce(hashes.to_vec()).rlp_bytes()));
+                }
             }
-            cinfo!(SYNC_TX, "Send {} transactions to {}", unsent.len(), token);
-            ctrace!(SYNC_TX, "Send {:?}", unsent_hashes);
-            self.api.send(token, Arc::new(Message::Transactions(unsent).rlp_bytes()));
         }
     }
 }
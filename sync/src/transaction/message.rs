@@ -15,11 +15,19 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use ccore::UnverifiedTransaction;
+use ctypes::TxHash;
 use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
 
+const MESSAGE_ID_ANNOUNCE: u8 = 0x01;
+const MESSAGE_ID_REQUEST: u8 = 0x02;
+
 #[derive(Debug, PartialEq)]
 pub enum Message {
     Transactions(Vec<UnverifiedTransaction>),
+    /// The hashes of the transactions that the sender has. Since version 1.
+    Announce(Vec<TxHash>),
+    /// The hashes of the announced transactions that the sender wants. Since version 1.
+    Request(Vec<TxHash>),
 }
 
 impl Encodable for Message {
@@ -40,12 +48,29 @@ impl Encodable for Message {
 
                 s.append(&compressed)
             }
+            Message::Announce(hashes) => s.begin_list(2).append(&MESSAGE_ID_ANNOUNCE).append_list(hashes),
+            Message::Request(hashes) => s.begin_list(2).append(&MESSAGE_ID_REQUEST).append_list(hashes),
         };
     }
 }
 
 impl Decodable for Message {
     fn decode(rlp: &Rlp<'_>) -> Result<Self, DecoderError> {
+        // The transactions are kept as a string to be understood by version 0.
+        if rlp.is_list() {
+            let item_count = rlp.item_count()?;
+            if item_count != 2 {
+                return Err(DecoderError::RlpIncorrectListLen {
+                    got: item_count,
+                    expected: 2,
+                })
+            }
+            return match rlp.val_at(0)? {
+                MESSAGE_ID_ANNOUNCE => Ok(Message::Announce(rlp.list_at(1)?)),
+                MESSAGE_ID_REQUEST => Ok(Message::Request(rlp.list_at(1)?)),
+                _ => Err(DecoderError::Custom("Unknown message id detected")),
+            }
+        }
         let compressed: Vec<u8> = rlp.as_val()?;
         let uncompressed = {
             // TODO: Cache the Decoder object
@@ -68,6 +93,7 @@ mod tests {
     use ccore::UnverifiedTransaction;
     use ckey::{Address, Signature};
     use ctypes::transaction::{Action, Transaction};
+    use primitives::H256;
 
     use super::Message;
 
@@ -92,4 +118,11 @@ mod tests {
 
         rlp_encode_and_decode_test!(Message::Transactions(vec![tx]));
     }
+
+    #[test]
+    fn announce_and_request_message_rlp() {
+        rlp_encode_and_decode_test!(Message::Announce(Vec::new()));
+        rlp_encode_and_decode_test!(Message::Announce(vec![H256::random().into(), H256::random().into()]));
+        rlp_encode_and_decode_test!(Message::Request(vec![H256::random().into()]));
+    }
 }
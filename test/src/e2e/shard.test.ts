@@ -420,7 +420,7 @@ describe.skip("CreateShard", function() {
     });
 });
 
-describe.skip("Cannot create shard without allow-create-shard flag", function() {
+describe("Cannot create shard without allow-create-shard flag", function() {
     let node: CodeChain;
     before(async function() {
         node = new CodeChain();
@@ -445,7 +445,9 @@ describe.skip("Cannot create shard without allow-create-shard flag", function()
                 null
             ])
         ).be.null;
-        expect(node.sdk.rpc.chain.sendSignedTransaction(tx)).be.rejected;
+        await expect(node.sdk.rpc.chain.sendSignedTransaction(tx)).rejectedWith(
+            /Rejected/
+        );
         expect(await node.sdk.rpc.chain.containsTransaction(tx.hash())).be
             .false;
         expect(await node.sdk.rpc.chain.getTransaction(tx.hash())).be.null;
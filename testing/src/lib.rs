@@ -0,0 +1,462 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Runs a solo node in the current process, so that integration tests can
+//! use the node through typed handles or in-memory JSON-RPC calls.
+
+extern crate codechain_core as ccore;
+extern crate codechain_key as ckey;
+extern crate codechain_rpc as crpc;
+extern crate codechain_timer as ctimer;
+extern crate codechain_types as ctypes;
+
+use ccore::{
+    AccountProvider, BlockChainClient, Client, ClientConfig, ClientService, Miner, MinerOptions, MinerService, Scheme,
+    SignedTransaction, NUM_COLUMNS,
+};
+use ckey::{Address, KeyPair, Private};
+use crpc::{BlockingPool, MetaIoHandler, Value};
+use ctimer::TimerLoop;
+use ctypes::TxHash;
+use kvdb::KeyValueDB;
+use serde_json::json;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The secret of the account which has all the balance in the genesis of the solo scheme.
+const GENESIS_SECRET: &str = "ede1d4ccb4ec9a8bbbae9a13db3f4a7b56ea04189be86ac3a6a439d9a0a1addd";
+
+/// A node using the solo engine and an in-memory database.
+/// The node stops when it's dropped.
+pub struct TestNode {
+    rpc: MetaIoHandler<()>,
+    account_provider: Arc<AccountProvider>,
+    miner: Arc<Miner>,
+    service: ClientService,
+    _timer_loop: TimerLoop,
+}
+
+impl TestNode {
+    pub fn start() -> Result<Self, String> {
+        Self::start_with_options(Default::default())
+    }
+
+    pub fn start_with_options(miner_options: MinerOptions) -> Result<Self, String> {
+        let scheme = Scheme::new_test_solo();
+        let db: Arc<dyn KeyValueDB> = Arc::new(kvdb_memorydb::create(NUM_COLUMNS.unwrap_or(0)));
+        let account_provider = AccountProvider::transient_provider();
+        let miner = Miner::new(miner_options, &scheme, Some(Arc::clone(&account_provider)), Arc::clone(&db));
+        miner.set_author(Address::default()).expect("set_author never fails when Solo is used");
+
+        let timer_loop = TimerLoop::new(1);
+        let reseal_timer = timer_loop.new_timer_with_name("Client reseal timer");
+        let service =
+            ClientService::start(&ClientConfig::default(), &scheme, db, Arc::clone(&miner), reseal_timer.clone())
+                .map_err(|e| format!("Client service error: {}", e))?;
+        let client = service.client();
+        reseal_timer.set_handler(Arc::downgrade(&client));
+        miner.recover_from_db(client.as_ref());
+        scheme.engine.register_chain_notify(client.as_ref());
+
+        let blocking_pool = BlockingPool::new(2).map_err(|e| format!("Cannot start the RPC threads: {}", e))?;
+        let mut rpc = MetaIoHandler::default();
+        {
+            use crpc::v1::*;
+            rpc.extend_with(ChainClient::new(Arc::clone(&client), blocking_pool.clone()).to_delegate());
+            rpc.extend_with(MempoolClient::new(Arc::clone(&client)).to_delegate());
+            rpc.extend_with(
+                DevelClient::new(
+                    Arc::clone(&client),
+                    Arc::clone(&miner),
+                    None,
+                    None,
+                    Default::default(),
+                    blocking_pool.clone(),
+                )
+                .to_delegate(),
+            );
+            rpc.extend_with(
+                EngineClient::new(
+                    Arc::clone(&account_provider),
+                    Arc::clone(&client),
+                    Arc::clone(&miner),
+                    blocking_pool.clone(),
+                )
+                .to_delegate(),
+            );
+            rpc.extend_with(
+                GovernanceClient::new(
+                    Arc::clone(&account_provider),
+                    Arc::clone(&client),
+                    Arc::clone(&miner),
+                    blocking_pool.clone(),
+                )
+                .to_delegate(),
+            );
+            rpc.extend_with(
+                AccountClient::new(Arc::clone(&account_provider), Arc::clone(&client), Arc::clone(&miner))
+                    .to_delegate(),
+            );
+            rpc.extend_with(
+                ShardClient::new(
+                    Arc::clone(&account_provider),
+                    Arc::clone(&client),
+                    Arc::clone(&miner),
+                    blocking_pool.clone(),
+                )
+                .to_delegate(),
+            );
+            rpc.extend_with(
+                StakeClient::new(Arc::clone(&account_provider), Arc::clone(&client), Arc::clone(&miner), blocking_pool)
+                    .to_delegate(),
+            );
+        }
+
+        Ok(Self {
+            rpc,
+            account_provider,
+            miner,
+            service,
+            _timer_loop: timer_loop,
+        })
+    }
+
+    pub fn client(&self) -> Arc<Client> {
+        self.service.client()
+    }
+
+    pub fn miner(&self) -> Arc<Miner> {
+        Arc::clone(&self.miner)
+    }
+
+    pub fn account_provider(&self) -> Arc<AccountProvider> {
+        Arc::clone(&self.account_provider)
+    }
+
+    /// The account which has all the balance at the genesis.
+    pub fn genesis_keypair(&self) -> KeyPair {
+        let secret: Private = GENESIS_SECRET.into();
+        KeyPair::from_private(secret).expect("The genesis secret is valid")
+    }
+
+    /// Calls the JSON-RPC method without any transport.
+    /// Returns the `error` object of the response as `Err`.
+    pub fn rpc(&self, method: &str, params: Value) -> Result<Value, Value> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": 1,
+        });
+        let response =
+            self.rpc.handle_request_sync(&request.to_string(), ()).expect("A request with id has a response");
+        let mut response: Value = serde_json::from_str(&response).expect("The response is a valid JSON");
+        match response.get_mut("error") {
+            Some(error) => Err(error.take()),
+            None => Ok(response["result"].take()),
+        }
+    }
+
+    /// Queues the transaction as an own transaction. The solo engine seals it immediately.
+    pub fn send_transaction(&self, tx: SignedTransaction) -> Result<TxHash, String> {
+        let hash = tx.hash();
+        self.client().queue_own_transaction(tx).map_err(|e| e.to_string())?;
+        Ok(hash)
+    }
+
+    /// Waits until the transaction is included in a block.
+    /// Returns false if it's not included in the given time.
+    pub fn wait_for_transaction(&self, hash: TxHash, timeout: Duration) -> bool {
+        let client = self.client();
+        let start = Instant::now();
+        while client.transaction(&hash.into()).is_none() {
+            if start.elapsed() > timeout {
+                return false
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ccore::{AccountData, BlockChainTrait, EngineInfo, Error, ExecuteClient};
+    use ckey::PlatformAddress;
+    use crpc::start_rest;
+    use crpc::v1::{ChainClient, MempoolClient};
+    use ctypes::transaction::{Action, ShardTransaction, Transaction};
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpListener, TcpStream};
+
+    #[test]
+    fn pay_transaction_is_sealed() {
+        let node = TestNode::start().unwrap();
+        assert_eq!(Ok(json!(0)), node.rpc("chain_getBestBlockNumber", json!([])));
+
+        let genesis = node.genesis_keypair();
+        let tx = Transaction {
+            seq: 0,
+            fee: 10,
+            network_id: node.client().network_id(),
+            action: Action::Pay {
+                receiver: Address::random(),
+                quantity: 1,
+            },
+        };
+        let hash = node.send_transaction(SignedTransaction::new_with_sign(tx, genesis.private())).unwrap();
+        assert!(node.wait_for_transaction(hash, Duration::from_secs(10)));
+        assert!(node.client().chain_info().best_block_number >= 1);
+    }
+
+    #[test]
+    fn unknown_method_is_an_error() {
+        let node = TestNode::start().unwrap();
+        assert!(node.rpc("unknown_method", json!([])).is_err());
+    }
+
+    fn send_and_wait(node: &TestNode, method: &str, params: Value) {
+        let composed = node.rpc(method, params).unwrap();
+        let hash = serde_json::from_value(composed["sent"]["hash"].clone()).unwrap();
+        assert!(node.wait_for_transaction(hash, Duration::from_secs(10)), "{} is not sealed", method);
+    }
+
+    #[test]
+    fn terminated_shard_has_no_info() {
+        let node = TestNode::start().unwrap();
+        let genesis = node.genesis_keypair();
+        node.account_provider().insert_account(*genesis.private(), &"password".into()).unwrap();
+        let owner = PlatformAddress::new_v1(node.client().network_id(), genesis.address()).to_string();
+
+        send_and_wait(&node, "shard_create", json!([[], "0xa", null, owner, "password"]));
+        let shard_id = node.rpc("chain_getNumberOfShards", json!([null])).unwrap().as_u64().unwrap() - 1;
+        let info = node.rpc("shard_getInfo", json!([shard_id, null])).unwrap();
+        assert_eq!(json!([owner]), info["owners"]);
+
+        send_and_wait(&node, "shard_terminate", json!([shard_id, "0xa", null, owner, "password"]));
+        assert_eq!(Ok(Value::Null), node.rpc("shard_getInfo", json!([shard_id, null])));
+        let key = format!("0x{}", "0".repeat(64));
+        assert_eq!(Ok(Value::Null), node.rpc("shard_getProof", json!([shard_id, key, null])));
+    }
+
+    #[test]
+    fn unknown_shard_has_no_info() {
+        let node = TestNode::start().unwrap();
+        assert_eq!(Ok(Value::Null), node.rpc("shard_getInfo", json!([100, null])));
+    }
+
+    #[test]
+    fn stake_namespace_reads_the_genesis_stakes() {
+        let node = TestNode::start().unwrap();
+        let network_id = node.client().network_id();
+        // The genesis account has 70000 of the genesis stakes.
+        let holder = PlatformAddress::new_v1(network_id, node.genesis_keypair().address()).to_string();
+        assert_eq!(Ok(json!("0x11170")), node.rpc("stake_getBalance", json!([holder, null])));
+        assert_eq!(Ok(json!([])), node.rpc("stake_getDelegations", json!([holder, null])));
+        assert_eq!(Ok(Value::Null), node.rpc("stake_getCandidate", json!([holder, null])));
+        assert_eq!(Ok(Value::Null), node.rpc("stake_getPrisoner", json!([holder, null])));
+        let rewards = node.rpc("stake_getPendingRewards", json!([holder, null]));
+        assert_eq!(Ok(json!({"current": "0x0", "calculated": "0x0"})), rewards);
+
+        let other = PlatformAddress::new_v1(network_id, Address::random()).to_string();
+        assert_eq!(Ok(json!("0x0")), node.rpc("stake_getBalance", json!([other, 0])));
+        assert!(node.rpc("stake_getBalance", json!([holder, 100])).is_err());
+    }
+
+    #[test]
+    fn term_info_of_the_first_term() {
+        let node = TestNode::start().unwrap();
+        let info = node.rpc("engine_getTermInfo", json!([null])).unwrap();
+        assert_eq!(json!(0), info["termId"]);
+        assert_eq!(Value::Null, info["endBlock"]);
+        assert_eq!((json!([]), json!([])), (info["banned"].clone(), info["jailed"].clone()));
+        // No election happens before the first term closes.
+        assert_eq!(Value::Null, info["nextValidators"]);
+        assert_eq!(Ok(Value::Null), node.rpc("engine_getTermInfo", json!([100])));
+    }
+
+    #[test]
+    fn accounts_are_read_from_one_state() {
+        let node = TestNode::start().unwrap();
+        let network_id = node.client().network_id();
+        let genesis = node.genesis_keypair().address();
+        let addresses =
+            [PlatformAddress::new_v1(network_id, genesis), PlatformAddress::new_v1(network_id, Address::random())];
+        let accounts = node.rpc("chain_getAccountsInfo", json!([addresses, 0])).unwrap();
+        let balance = format!("0x{:x}", node.client().latest_balance(&genesis));
+        assert_eq!(
+            json!([
+                {"address": addresses[0], "balance": balance, "lockedBalance": "0x0", "seq": 0, "regularKey": null},
+                {"address": addresses[1], "balance": "0x0", "lockedBalance": "0x0", "seq": 0, "regularKey": null},
+            ]),
+            accounts
+        );
+
+        assert_eq!(Ok(Value::Null), node.rpc("chain_getAccountsInfo", json!([addresses, 100])));
+        let too_many = vec![addresses[1]; 257];
+        assert!(node.rpc("chain_getAccountsInfo", json!([too_many, null])).is_err());
+    }
+
+    #[test]
+    fn account_without_a_deposit_has_no_locked_deposits() {
+        let node = TestNode::start().unwrap();
+        let address = PlatformAddress::new_v1(node.client().network_id(), node.genesis_keypair().address());
+        assert_eq!(Ok(json!([])), node.rpc("stake_getLockedDeposits", json!([address.to_string(), null])));
+        assert!(node.rpc("stake_getPendingWithdrawals", json!([address.to_string(), null])).is_err());
+    }
+
+    #[test]
+    fn execute_transaction_accepts_only_shard_transactions() {
+        let node = TestNode::start().unwrap();
+        let network_id = node.client().network_id();
+        let sender = PlatformAddress::new_v1(network_id, node.genesis_keypair().address());
+        let action = json!({"type": "pay", "receiver": sender, "quantity": "0x1"});
+        let pay = json!({"fee": "0xa", "networkId": network_id, "action": action});
+        let error = node.rpc("chain_executeTransaction", json!([pay, sender])).unwrap_err();
+        assert_eq!(json!(-32047), error["code"]);
+
+        let store = ShardTransaction::ShardStore {
+            network_id,
+            shard_id: 100,
+            content: "text".to_string(),
+        };
+        assert!(node.client().execute_transaction(&store, &node.genesis_keypair().address()).is_err());
+    }
+
+    #[test]
+    fn dry_run_transaction_reports_the_changes_without_persisting_them() {
+        let node = TestNode::start().unwrap();
+        let network_id = node.client().network_id();
+        let genesis = node.genesis_keypair();
+        let receiver = PlatformAddress::new_v1(network_id, Address::random());
+        let action = json!({"type": "pay", "receiver": receiver, "quantity": "0x1"});
+        let pay = json!({"fee": "0xa", "networkId": network_id, "action": action});
+
+        let execution = node.rpc("chain_dryRunTransaction", json!([pay, null, genesis.public()])).unwrap();
+        assert_eq!(json!(true), execution["success"]);
+        assert_eq!(Value::Null, execution["error"]);
+        assert_eq!(json!("0xa"), execution["fee"]);
+        let changes = execution["changedAccounts"].as_array().unwrap();
+        assert_eq!(2, changes.len());
+        let received = changes.iter().find(|change| change["address"] == json!(receiver)).unwrap();
+        assert_eq!(json!("0x0"), received["balanceBefore"]);
+        assert_eq!(json!("0x1"), received["balanceAfter"]);
+        let sent = changes.iter().find(|change| change["address"] != json!(receiver)).unwrap();
+        assert_eq!((json!(0), json!(1)), (sent["seqBefore"].clone(), sent["seqAfter"].clone()));
+
+        assert_eq!(0, node.client().latest_seq(&genesis.address()));
+        assert_eq!(0, node.client().latest_balance(receiver.address()));
+        assert_eq!(Ok(Value::Null), node.rpc("chain_dryRunTransaction", json!([pay, 100, genesis.public()])));
+    }
+
+    #[test]
+    fn tps_test_funds_the_senders_for_every_transaction() {
+        let node = TestNode::start().unwrap();
+        let setting = json!({"count": 12, "seed": 1, "profile": "mixed", "senders": 3, "timeout": 60});
+        let report = node.rpc("devel_runTPSTest", json!([setting])).unwrap();
+        assert_eq!(json!(12), report["count"]);
+        assert!(report["latencyMax"].as_u64().is_some());
+    }
+
+    #[test]
+    fn tps_test_fails_when_the_transactions_are_not_mined_in_time() {
+        let node = TestNode::start_with_options(MinerOptions {
+            reseal_on_own_transaction: false,
+            reseal_on_external_transaction: false,
+            no_reseal_timer: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let setting = json!({"count": 2, "seed": 0, "senders": 2, "timeout": 1});
+        let error = node.rpc("devel_runTPSTest", json!([setting])).unwrap_err();
+        assert_eq!(json!("2 transactions are not mined before the timeout"), error["message"]);
+    }
+
+    fn http_get(addr: &SocketAddr, path: &str) -> (u16, Value) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response.split(' ').nth(1).unwrap().parse().unwrap();
+        let body = &response[response.find("\r\n\r\n").unwrap() + 4..];
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn rest_gateway_serves_the_common_read_paths() {
+        let node = TestNode::start().unwrap();
+        let client = node.client();
+        let blocking_pool = BlockingPool::new(1).unwrap();
+        let chain = ChainClient::new(Arc::clone(&client), blocking_pool);
+        let mempool = MempoolClient::new(Arc::clone(&client));
+        let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let server = start_rest(&addr, chain, mempool, Arc::clone(&client)).unwrap();
+
+        let (status, block) = http_get(&addr, "/blocks/0");
+        assert_eq!((200, json!(0)), (status, block["number"].clone()));
+        assert_eq!(404, http_get(&addr, "/blocks/1").0);
+        assert_eq!(400, http_get(&addr, "/blocks/first").0);
+        assert_eq!(404, http_get(&addr, "/unknown").0);
+
+        let address = PlatformAddress::new_v1(client.network_id(), node.genesis_keypair().address());
+        let (status, account) = http_get(&addr, &format!("/accounts/{}?block=0", address));
+        assert_eq!(200, status);
+        let balance = client.latest_balance(&node.genesis_keypair().address());
+        assert_eq!(json!({"balance": format!("0x{:x}", balance), "seq": 0}), account);
+        assert_eq!(400, http_get(&addr, &format!("/accounts/{}?block=first", address)).0);
+
+        assert_eq!((200, json!({"ready": true, "maintenance": false})), http_get(&addr, "/ready"));
+        server.close();
+    }
+
+    #[test]
+    fn node_in_maintenance_refuses_transactions() {
+        let node = TestNode::start().unwrap();
+        let client = node.client();
+        let blocking_pool = BlockingPool::new(1).unwrap();
+        let chain = ChainClient::new(Arc::clone(&client), blocking_pool);
+        let mempool = MempoolClient::new(Arc::clone(&client));
+        let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let server = start_rest(&addr, chain, mempool, Arc::clone(&client)).unwrap();
+
+        let genesis = node.genesis_keypair();
+        let tx = Transaction {
+            seq: 0,
+            fee: 10,
+            network_id: client.network_id(),
+            action: Action::Pay {
+                receiver: Address::random(),
+                quantity: 1,
+            },
+        };
+        let tx = SignedTransaction::new_with_sign(tx, genesis.private());
+
+        assert_eq!(Ok(Value::Null), node.rpc("devel_setMaintenanceMode", json!([true])));
+        assert_eq!(Ok(json!(true)), node.rpc("devel_getMaintenanceMode", json!([])));
+        assert_eq!((503, json!({"ready": false, "maintenance": true})), http_get(&addr, "/ready"));
+        assert_eq!(Err(Error::Maintenance.to_string()), node.send_transaction(tx.clone()));
+        assert!(client.pooled_transactions_of(&genesis.address()).is_empty());
+        assert_eq!(0, client.chain_info().best_block_number);
+
+        assert_eq!(Ok(Value::Null), node.rpc("devel_setMaintenanceMode", json!([false])));
+        assert_eq!((200, json!({"ready": true, "maintenance": false})), http_get(&addr, "/ready"));
+        let hash = node.send_transaction(tx).unwrap();
+        assert!(node.wait_for_transaction(hash, Duration::from_secs(10)));
+        server.close();
+    }
+}
@@ -53,6 +53,17 @@ pub enum Error {
         idx: usize,
         parent_height: u64,
     },
+    /// The balance after the transaction is less than the locked balance
+    InsufficientUnlockedBalance {
+        address: Address,
+        balance: u64,
+        locked: u64,
+    },
+    /// The scheduled transaction is included before its activation block
+    NotActivatedYet {
+        activation: u64,
+        block_number: u64,
+    },
 }
 
 #[derive(Clone, Copy)]
@@ -72,6 +83,8 @@ enum ErrorID {
     SignatureOfInvalid = 12,
     InsufficientStakes = 13,
     InvalidValidatorIndex = 14,
+    InsufficientUnlockedBalance = 15,
+    NotActivatedYet = 16,
 }
 
 impl Encodable for ErrorID {
@@ -98,6 +111,8 @@ impl Decodable for ErrorID {
             12 => Ok(ErrorID::SignatureOfInvalid),
             13 => Ok(ErrorID::InsufficientStakes),
             14 => Ok(ErrorID::InvalidValidatorIndex),
+            15 => Ok(ErrorID::InsufficientUnlockedBalance),
+            16 => Ok(ErrorID::NotActivatedYet),
             _ => Err(DecoderError::Custom("Unexpected ActionTag Value")),
         }
     }
@@ -123,6 +138,8 @@ impl TaggedRlp for RlpHelper {
             ErrorID::SignatureOfInvalid => 2,
             ErrorID::InsufficientStakes => 3,
             ErrorID::InvalidValidatorIndex => 3,
+            ErrorID::InsufficientUnlockedBalance => 4,
+            ErrorID::NotActivatedYet => 3,
         })
     }
 }
@@ -165,6 +182,18 @@ impl Encodable for Error {
                 idx,
                 parent_height,
             } => RlpHelper::new_tagged_list(s, ErrorID::InvalidValidatorIndex).append(idx).append(parent_height),
+            Error::InsufficientUnlockedBalance {
+                address,
+                balance,
+                locked,
+            } => RlpHelper::new_tagged_list(s, ErrorID::InsufficientUnlockedBalance)
+                .append(address)
+                .append(balance)
+                .append(locked),
+            Error::NotActivatedYet {
+                activation,
+                block_number,
+            } => RlpHelper::new_tagged_list(s, ErrorID::NotActivatedYet).append(activation).append(block_number),
         };
     }
 }
@@ -200,6 +229,15 @@ impl Decodable for Error {
                 idx: rlp.val_at(1)?,
                 parent_height: rlp.val_at(2)?,
             },
+            ErrorID::InsufficientUnlockedBalance => Error::InsufficientUnlockedBalance {
+                address: rlp.val_at(1)?,
+                balance: rlp.val_at(2)?,
+                locked: rlp.val_at(3)?,
+            },
+            ErrorID::NotActivatedYet => Error::NotActivatedYet {
+                activation: rlp.val_at(1)?,
+                block_number: rlp.val_at(2)?,
+            },
         };
         RlpHelper::check_size(rlp, tag)?;
         Ok(error)
@@ -239,6 +277,15 @@ impl Display for Error {
                 idx,
                 parent_height,
             } => write!(f, "The validator index {} is invalid at the parent hash {}", idx, parent_height),
+            Error::InsufficientUnlockedBalance {
+                address,
+                balance,
+                locked,
+            } => write!(f, "{} has only {:?} but {:?} is locked", address, balance, locked),
+            Error::NotActivatedYet {
+                activation,
+                block_number,
+            } => write!(f, "The transaction is scheduled at {} but the block number is {}", activation, block_number),
         }
     }
 }
@@ -35,6 +35,12 @@ pub enum Error {
     /// Invalid network ID given.
     InvalidNetworkId(NetworkId),
     InvalidApproval(String),
+    /// The batch is empty, too long or nested.
+    InvalidBatch(String),
+    /// A scheduled action wraps another scheduled action.
+    NestedScheduledAction,
+    /// The fork enabling the action is not active yet.
+    InactiveAction(String),
     /// Max metadata size is exceeded.
     MetadataTooBig,
     TextContentTooBig,
@@ -52,6 +58,9 @@ enum ErrorID {
     TextContentTooBig = 6,
     TxIsTooBig = 7,
     InvalidCustomAction = 8,
+    InvalidBatch = 9,
+    NestedScheduledAction = 10,
+    InactiveAction = 11,
 }
 
 impl Encodable for ErrorID {
@@ -72,6 +81,9 @@ impl Decodable for ErrorID {
             6 => Ok(ErrorID::TextContentTooBig),
             7 => Ok(ErrorID::TxIsTooBig),
             8 => Ok(ErrorID::InvalidCustomAction),
+            9 => Ok(ErrorID::InvalidBatch),
+            10 => Ok(ErrorID::NestedScheduledAction),
+            11 => Ok(ErrorID::InactiveAction),
             _ => Err(DecoderError::Custom("Unexpected ErrorID Value")),
         }
     }
@@ -89,6 +101,9 @@ impl TaggedRlp for RlpHelper {
             ErrorID::InvalidCustomAction => 2,
             ErrorID::InvalidNetworkID => 2,
             ErrorID::InvalidApproval => 2,
+            ErrorID::InvalidBatch => 2,
+            ErrorID::NestedScheduledAction => 1,
+            ErrorID::InactiveAction => 2,
             ErrorID::MetadataTooBig => 1,
             ErrorID::TextContentTooBig => 1,
             ErrorID::TxIsTooBig => 1,
@@ -111,6 +126,9 @@ impl Encodable for Error {
                 RlpHelper::new_tagged_list(s, ErrorID::InvalidNetworkID).append(network_id)
             }
             Error::InvalidApproval(err) => RlpHelper::new_tagged_list(s, ErrorID::InvalidApproval).append(err),
+            Error::InvalidBatch(err) => RlpHelper::new_tagged_list(s, ErrorID::InvalidBatch).append(err),
+            Error::NestedScheduledAction => RlpHelper::new_tagged_list(s, ErrorID::NestedScheduledAction),
+            Error::InactiveAction(fork) => RlpHelper::new_tagged_list(s, ErrorID::InactiveAction).append(fork),
             Error::MetadataTooBig => RlpHelper::new_tagged_list(s, ErrorID::MetadataTooBig),
             Error::TextContentTooBig => RlpHelper::new_tagged_list(s, ErrorID::TextContentTooBig),
             Error::TransactionIsTooBig => RlpHelper::new_tagged_list(s, ErrorID::TxIsTooBig),
@@ -130,6 +148,9 @@ impl Decodable for Error {
             ErrorID::InvalidCustomAction => Error::InvalidCustomAction(rlp.val_at(1)?),
             ErrorID::InvalidNetworkID => Error::InvalidNetworkId(rlp.val_at(1)?),
             ErrorID::InvalidApproval => Error::InvalidApproval(rlp.val_at(1)?),
+            ErrorID::InvalidBatch => Error::InvalidBatch(rlp.val_at(1)?),
+            ErrorID::NestedScheduledAction => Error::NestedScheduledAction,
+            ErrorID::InactiveAction => Error::InactiveAction(rlp.val_at(1)?),
             ErrorID::MetadataTooBig => Error::MetadataTooBig,
             ErrorID::TextContentTooBig => Error::TextContentTooBig,
             ErrorID::TxIsTooBig => Error::TransactionIsTooBig,
@@ -150,6 +171,9 @@ impl Display for Error {
             Error::InvalidCustomAction(err) => write!(f, "Invalid custom action: {}", err),
             Error::InvalidNetworkId(network_id) => write!(f, "{} is an invalid network id", network_id),
             Error::InvalidApproval(err) => write!(f, "Transaction has an invalid approval :{}", err),
+            Error::InvalidBatch(err) => write!(f, "Invalid batch: {}", err),
+            Error::NestedScheduledAction => write!(f, "A scheduled action cannot include a scheduled action"),
+            Error::InactiveAction(fork) => write!(f, "The action is not enabled before the {} fork", fork),
             Error::MetadataTooBig => write!(f, "Metadata size is too big."),
             Error::TextContentTooBig => write!(f, "The content of the text is too big"),
             Error::TransactionIsTooBig => write!(f, "Transaction size exceeded the body size limit"),
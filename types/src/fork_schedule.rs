@@ -0,0 +1,150 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::BlockNumber;
+use cjson::scheme::Forks;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+/// Enables the batch action.
+pub const BATCH_FORK: &str = "batch";
+/// Enables the scheduled action.
+pub const SCHEDULED_ACTION_FORK: &str = "scheduledAction";
+/// Enables the burnSeq action.
+pub const BURN_SEQ_FORK: &str = "burnSeq";
+/// Enables the terminateShard action.
+pub const TERMINATE_SHARD_FORK: &str = "terminateShard";
+/// Enables the unjail action of the stake and jails the validators that miss too many proposals and votes.
+pub const LIVENESS_FORK: &str = "liveness";
+/// Enables the setCommission action of the stake and splits the rewards by the commission.
+pub const COMMISSION_FORK: &str = "commission";
+/// Enables the createProposal and voteProposal actions of the stake.
+pub const PROPOSAL_FORK: &str = "proposal";
+/// Enables the setSessionKey action of the stake.
+pub const SESSION_KEY_FORK: &str = "sessionKey";
+/// Enables the scheduleParams action of the stake and counts a repeated signature of changeParams once.
+pub const SCHEDULE_PARAMS_FORK: &str = "scheduleParams";
+
+const KNOWN_FORKS: [&str; 9] = [
+    BATCH_FORK,
+    SCHEDULED_ACTION_FORK,
+    BURN_SEQ_FORK,
+    TERMINATE_SHARD_FORK,
+    LIVENESS_FORK,
+    COMMISSION_FORK,
+    PROPOSAL_FORK,
+    SESSION_KEY_FORK,
+    SCHEDULE_PARAMS_FORK,
+];
+
+/// Activation schedule of the named protocol upgrades.
+///
+/// A behavior change guarded by a fork is applied to the blocks whose number is greater than or equal to
+/// the activation block number of the fork.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ForkSchedule {
+    activations: BTreeMap<String, BlockNumber>,
+}
+
+impl ForkSchedule {
+    /// Returns the activation block number of the given fork, if scheduled.
+    pub fn activation_block(&self, name: &str) -> Option<BlockNumber> {
+        self.activations.get(name).cloned()
+    }
+
+    /// Returns true if the given fork is active at the given block.
+    pub fn is_active(&self, name: &str, block_number: BlockNumber) -> bool {
+        self.activation_block(name).map_or(false, |activation| activation <= block_number)
+    }
+
+    /// Returns the names of the forks active at the given block.
+    pub fn active_forks(&self, block_number: BlockNumber) -> Vec<&str> {
+        self.activations
+            .iter()
+            .filter(|(_, activation)| **activation <= block_number)
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, BlockNumber)> {
+        self.activations.iter().map(|(name, activation)| (name.as_str(), *activation))
+    }
+}
+
+impl TryFrom<Forks> for ForkSchedule {
+    type Error = String;
+
+    fn try_from(forks: Forks) -> Result<Self, Self::Error> {
+        if let Some(name) = forks.keys().find(|name| !KNOWN_FORKS.contains(&name.as_str())) {
+            return Err(format!("Unknown fork {}", name))
+        }
+        Ok(Self {
+            activations: forks.into_iter().map(|(name, activation)| (name, activation.into())).collect(),
+        })
+    }
+}
+
+impl From<ForkSchedule> for Forks {
+    fn from(schedule: ForkSchedule) -> Self {
+        schedule.activations.into_iter().map(|(name, activation)| (name, activation.into())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule() -> ForkSchedule {
+        let mut forks = Forks::new();
+        forks.insert(BATCH_FORK.to_string(), 10.into());
+        forks.insert(BURN_SEQ_FORK.to_string(), 20.into());
+        ForkSchedule::try_from(forks).unwrap()
+    }
+
+    #[test]
+    fn fork_is_active_from_its_activation_block() {
+        let schedule = schedule();
+        assert!(!schedule.is_active(BATCH_FORK, 9));
+        assert!(schedule.is_active(BATCH_FORK, 10));
+        assert!(schedule.is_active(BATCH_FORK, 11));
+        assert!(!schedule.is_active(BURN_SEQ_FORK, 19));
+        assert!(schedule.is_active(BURN_SEQ_FORK, 20));
+    }
+
+    #[test]
+    fn unscheduled_fork_is_never_active() {
+        let schedule = schedule();
+        assert_eq!(None, schedule.activation_block(SCHEDULED_ACTION_FORK));
+        assert!(!schedule.is_active(SCHEDULED_ACTION_FORK, ::std::u64::MAX));
+        assert!(!ForkSchedule::default().is_active(BATCH_FORK, ::std::u64::MAX));
+    }
+
+    #[test]
+    fn active_forks() {
+        let schedule = schedule();
+        assert!(schedule.active_forks(0).is_empty());
+        assert_eq!(vec![BATCH_FORK], schedule.active_forks(15));
+        assert_eq!(vec![BATCH_FORK, BURN_SEQ_FORK], schedule.active_forks(20));
+    }
+
+    #[test]
+    fn unknown_fork_is_rejected() {
+        let mut forks = Forks::new();
+        forks.insert(BATCH_FORK.to_string(), 10.into());
+        forks.insert("fastTermTransition".to_string(), 20.into());
+        assert_eq!(Err("Unknown fork fastTermTransition".to_string()), ForkSchedule::try_from(forks));
+    }
+}
@@ -26,6 +26,7 @@ extern crate serde_json;
 
 mod block_hash;
 mod common_params;
+mod fork_schedule;
 mod tracker;
 mod tx_hash;
 
@@ -39,6 +40,10 @@ pub type ShardId = u16;
 
 pub use block_hash::BlockHash;
 pub use common_params::CommonParams;
+pub use fork_schedule::{
+    ForkSchedule, BATCH_FORK, BURN_SEQ_FORK, COMMISSION_FORK, LIVENESS_FORK, PROPOSAL_FORK, SCHEDULED_ACTION_FORK,
+    SCHEDULE_PARAMS_FORK, SESSION_KEY_FORK, TERMINATE_SHARD_FORK,
+};
 pub use header::Header;
 pub use tracker::Tracker;
 pub use tx_hash::TxHash;
@@ -16,11 +16,30 @@
 
 use crate::errors::SyntaxError;
 use crate::transaction::ShardTransaction;
-use crate::{CommonParams, ShardId, Tracker};
+use crate::{BlockNumber, CommonParams, ShardId, Tracker};
 use ccrypto::Blake;
 use ckey::{recover, Address, NetworkId, Public, Signature};
 use primitives::{Bytes, H256};
 use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+use std::slice;
+
+/// The maximum number of the actions in a batch.
+pub const MAX_BATCH_SIZE: usize = 32;
+
+/// The names of the action types, as they are in JSON.
+pub const ACTION_TYPE_NAMES: &[&str] = &[
+    "pay",
+    "setRegularKey",
+    "createShard",
+    "setShardOwners",
+    "setShardUsers",
+    "terminateShard",
+    "custom",
+    "shardStore",
+    "batch",
+    "scheduled",
+    "burnSeq",
+];
 
 #[derive(Clone, Copy)]
 #[repr(u8)]
@@ -30,6 +49,10 @@ enum ActionTag {
     CreateShard = 0x04,
     SetShardOwners = 0x05,
     SetShardUsers = 0x06,
+    Batch = 0x07,
+    Scheduled = 0x08,
+    BurnSeq = 0x09,
+    TerminateShard = 0x0a,
     ShardStore = 0x19,
     Custom = 0xFF,
 }
@@ -49,6 +72,10 @@ impl Decodable for ActionTag {
             0x04 => Ok(Self::CreateShard),
             0x05 => Ok(Self::SetShardOwners),
             0x06 => Ok(Self::SetShardUsers),
+            0x07 => Ok(Self::Batch),
+            0x08 => Ok(Self::Scheduled),
+            0x09 => Ok(Self::BurnSeq),
+            0x0a => Ok(Self::TerminateShard),
             0x19 => Ok(Self::ShardStore),
             0xFF => Ok(Self::Custom),
             _ => Err(DecoderError::Custom("Unexpected action prefix")),
@@ -77,6 +104,10 @@ pub enum Action {
         shard_id: ShardId,
         users: Vec<Address>,
     },
+    /// Removes the shard with its texts. The shard ID is not reused.
+    TerminateShard {
+        shard_id: ShardId,
+    },
     Custom {
         handler_id: u64,
         bytes: Bytes,
@@ -86,6 +117,20 @@ pub enum Action {
         shard_id: ShardId,
         content: String,
     },
+    /// Applies the actions in order under one fee and one signature.
+    /// If any of them fails, the effects of the others are reverted.
+    Batch {
+        actions: Vec<Action>,
+    },
+    /// Applies the action only in the blocks whose number is not less than the activation.
+    /// The mem pool holds it until the activation block.
+    Scheduled {
+        activation: BlockNumber,
+        action: Box<Action>,
+    },
+    /// Does nothing but consuming the seq and the fee.
+    /// It replaces a pending transaction with the same seq to cancel it.
+    BurnSeq,
 }
 
 impl Action {
@@ -94,6 +139,43 @@ impl Action {
         Blake::blake(rlp)
     }
 
+    /// The name of the type, as it is in JSON.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Action::Pay {
+                ..
+            } => "pay",
+            Action::SetRegularKey {
+                ..
+            } => "setRegularKey",
+            Action::CreateShard {
+                ..
+            } => "createShard",
+            Action::SetShardOwners {
+                ..
+            } => "setShardOwners",
+            Action::SetShardUsers {
+                ..
+            } => "setShardUsers",
+            Action::TerminateShard {
+                ..
+            } => "terminateShard",
+            Action::Custom {
+                ..
+            } => "custom",
+            Action::ShardStore {
+                ..
+            } => "shardStore",
+            Action::Batch {
+                ..
+            } => "batch",
+            Action::Scheduled {
+                ..
+            } => "scheduled",
+            Action::BurnSeq => "burnSeq",
+        }
+    }
+
     pub fn shard_transaction(&self) -> Option<ShardTransaction> {
         match self {
             Action::ShardStore {
@@ -108,10 +190,85 @@ impl Action {
     }
 
     pub fn verify(&self) -> Result<(), SyntaxError> {
+        match self {
+            Action::Batch {
+                actions,
+            } => {
+                if actions.is_empty() {
+                    return Err(SyntaxError::InvalidBatch("The batch is empty".to_string()))
+                }
+                if actions.len() > MAX_BATCH_SIZE {
+                    return Err(SyntaxError::InvalidBatch(format!("The batch has more than {} actions", MAX_BATCH_SIZE)))
+                }
+                for action in actions {
+                    match action {
+                        Action::Batch {
+                            ..
+                        } => return Err(SyntaxError::InvalidBatch("The batch cannot include a batch".to_string())),
+                        Action::Scheduled {
+                            ..
+                        } => {
+                            return Err(SyntaxError::InvalidBatch(
+                                "The batch cannot include a scheduled action".to_string(),
+                            ))
+                        }
+                        _ => action.verify()?,
+                    }
+                }
+            }
+            Action::Scheduled {
+                action,
+                ..
+            } => {
+                if let Action::Scheduled {
+                    ..
+                } = **action
+                {
+                    return Err(SyntaxError::NestedScheduledAction)
+                }
+                action.verify()?;
+            }
+            _ => {}
+        }
         Ok(())
     }
 
+    /// Returns the actions that are applied: the actions in the batch, or the action itself.
+    pub fn flatten(&self) -> &[Action] {
+        match self {
+            Action::Batch {
+                actions,
+            } => actions,
+            Action::Scheduled {
+                action,
+                ..
+            } => action.flatten(),
+            action => slice::from_ref(action),
+        }
+    }
+
+    /// Returns the block number from which the action can be applied, if it is scheduled.
+    pub fn activation(&self) -> Option<BlockNumber> {
+        match self {
+            Action::Scheduled {
+                activation,
+                ..
+            } => Some(*activation),
+            _ => None,
+        }
+    }
+
     pub fn verify_with_params(&self, common_params: &CommonParams) -> Result<(), SyntaxError> {
+        match self {
+            Action::Batch {
+                actions,
+            } => return actions.iter().try_for_each(|action| action.verify_with_params(common_params)),
+            Action::Scheduled {
+                action,
+                ..
+            } => return action.verify_with_params(common_params),
+            _ => {}
+        }
         if let Some(network_id) = self.network_id() {
             let system_network_id = common_params.network_id();
             if network_id != system_network_id {
@@ -211,6 +368,13 @@ impl Encodable for Action {
                 s.append(shard_id);
                 s.append_list(users);
             }
+            Action::TerminateShard {
+                shard_id,
+            } => {
+                s.begin_list(2);
+                s.append(&ActionTag::TerminateShard);
+                s.append(shard_id);
+            }
             Action::Custom {
                 handler_id,
                 bytes,
@@ -231,6 +395,26 @@ impl Encodable for Action {
                 s.append(shard_id);
                 s.append(content);
             }
+            Action::Batch {
+                actions,
+            } => {
+                s.begin_list(2);
+                s.append(&ActionTag::Batch);
+                s.append_list(actions);
+            }
+            Action::Scheduled {
+                activation,
+                action,
+            } => {
+                s.begin_list(3);
+                s.append(&ActionTag::Scheduled);
+                s.append(activation);
+                s.append(&**action);
+            }
+            Action::BurnSeq => {
+                s.begin_list(1);
+                s.append(&ActionTag::BurnSeq);
+            }
         }
     }
 }
@@ -301,6 +485,18 @@ impl Decodable for Action {
                     users: rlp.list_at(2)?,
                 })
             }
+            ActionTag::TerminateShard => {
+                let item_count = rlp.item_count()?;
+                if item_count != 2 {
+                    return Err(DecoderError::RlpIncorrectListLen {
+                        got: item_count,
+                        expected: 2,
+                    })
+                }
+                Ok(Action::TerminateShard {
+                    shard_id: rlp.val_at(1)?,
+                })
+            }
             ActionTag::Custom => {
                 let item_count = rlp.item_count()?;
                 if item_count != 3 {
@@ -328,6 +524,41 @@ impl Decodable for Action {
                     content: rlp.val_at(3)?,
                 })
             }
+            ActionTag::Batch => {
+                let item_count = rlp.item_count()?;
+                if item_count != 2 {
+                    return Err(DecoderError::RlpIncorrectListLen {
+                        got: item_count,
+                        expected: 2,
+                    })
+                }
+                Ok(Action::Batch {
+                    actions: rlp.list_at(1)?,
+                })
+            }
+            ActionTag::Scheduled => {
+                let item_count = rlp.item_count()?;
+                if item_count != 3 {
+                    return Err(DecoderError::RlpIncorrectListLen {
+                        got: item_count,
+                        expected: 3,
+                    })
+                }
+                Ok(Action::Scheduled {
+                    activation: rlp.val_at(1)?,
+                    action: Box::new(rlp.val_at(2)?),
+                })
+            }
+            ActionTag::BurnSeq => {
+                let item_count = rlp.item_count()?;
+                if item_count != 1 {
+                    return Err(DecoderError::RlpIncorrectListLen {
+                        got: item_count,
+                        expected: 1,
+                    })
+                }
+                Ok(Action::BurnSeq)
+            }
         }
     }
 }
@@ -338,6 +569,27 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn type_names_are_listed() {
+        let actions = vec![
+            Action::Pay {
+                receiver: Address::random(),
+                quantity: 300,
+            },
+            Action::CreateShard {
+                users: vec![],
+            },
+            Action::BurnSeq,
+            Action::Scheduled {
+                activation: 10,
+                action: Box::new(Action::BurnSeq),
+            },
+        ];
+        for action in actions {
+            assert!(ACTION_TYPE_NAMES.contains(&action.type_name()), "{} is not listed", action.type_name());
+        }
+    }
+
     #[test]
     fn encode_and_decode_pay_action() {
         rlp_encode_and_decode_test!(Action::Pay {
@@ -346,6 +598,81 @@ mod tests {
         });
     }
 
+    #[test]
+    fn encode_and_decode_batch() {
+        rlp_encode_and_decode_test!(Action::Batch {
+            actions: vec![
+                Action::Pay {
+                    receiver: Address::random(),
+                    quantity: 300,
+                },
+                Action::Custom {
+                    handler_id: 2,
+                    bytes: vec![0xc1, 0x07],
+                },
+            ],
+        });
+    }
+
+    #[test]
+    fn verify_fails_if_the_batch_is_nested() {
+        let action = Action::Batch {
+            actions: vec![Action::Batch {
+                actions: vec![Action::Pay {
+                    receiver: Address::random(),
+                    quantity: 300,
+                }],
+            }],
+        };
+        assert!(action.verify().is_err());
+        assert!(Action::Batch {
+            actions: vec![]
+        }
+        .verify()
+        .is_err());
+    }
+
+    #[test]
+    fn encode_and_decode_scheduled() {
+        rlp_encode_and_decode_test!(Action::Scheduled {
+            activation: 100,
+            action: Box::new(Action::Pay {
+                receiver: Address::random(),
+                quantity: 300,
+            }),
+        });
+    }
+
+    #[test]
+    fn encode_and_decode_burn_seq() {
+        rlp_encode_and_decode_test!(Action::BurnSeq);
+    }
+
+    #[test]
+    fn verify_fails_if_the_scheduled_action_is_nested() {
+        let pay = Action::Pay {
+            receiver: Address::random(),
+            quantity: 300,
+        };
+        let scheduled = Action::Scheduled {
+            activation: 100,
+            action: Box::new(pay.clone()),
+        };
+        assert_eq!(Ok(()), scheduled.verify());
+        assert_eq!(&[pay], scheduled.flatten());
+
+        let nested = Action::Scheduled {
+            activation: 200,
+            action: Box::new(scheduled.clone()),
+        };
+        assert_eq!(Err(SyntaxError::NestedScheduledAction), nested.verify());
+        assert!(Action::Batch {
+            actions: vec![scheduled]
+        }
+        .verify()
+        .is_err());
+    }
+
     #[test]
     fn encode_and_decode_set_shard_owners() {
         rlp_encode_and_decode_test!(Action::SetShardOwners {
@@ -361,4 +688,11 @@ mod tests {
             users: vec![Address::random(), Address::random()],
         });
     }
+
+    #[test]
+    fn encode_and_decode_terminate_shard() {
+        rlp_encode_and_decode_test!(Action::TerminateShard {
+            shard_id: 1,
+        });
+    }
 }
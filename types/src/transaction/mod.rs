@@ -25,7 +25,7 @@ mod timelock;
 #[cfg_attr(feature = "cargo-clippy", allow(clippy::module_inception))]
 mod transaction;
 
-pub use self::action::Action;
+pub use self::action::{Action, ACTION_TYPE_NAMES, MAX_BATCH_SIZE};
 pub use self::asset_out_point::AssetOutPoint;
 pub use self::incomplete_transaction::IncompleteTransaction;
 pub use self::input::AssetTransferInput;
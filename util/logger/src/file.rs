@@ -0,0 +1,326 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use parking_lot::Mutex;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use time;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rotation {
+    Never,
+    Hourly,
+    Daily,
+}
+
+impl Rotation {
+    /// The index of the period `at` belongs to. The file is rotated when it changes.
+    fn period(self, at: SystemTime) -> u64 {
+        let seconds = at.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+        match self {
+            Rotation::Never => 0,
+            Rotation::Hourly => seconds / 3600,
+            Rotation::Daily => seconds / 86400,
+        }
+    }
+}
+
+impl FromStr for Rotation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "never" => Ok(Rotation::Never),
+            "hourly" => Ok(Rotation::Hourly),
+            "daily" => Ok(Rotation::Daily),
+            _ => Err(format!("Invalid rotation {}. It should be one of never, hourly and daily", s)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FileConfig {
+    pub path: PathBuf,
+    /// The file is rotated when it grows over this many bytes.
+    pub max_size: Option<u64>,
+    pub rotation: Rotation,
+    /// Gzips the rotated files.
+    pub compress: bool,
+    /// The number of the rotated files kept. The oldest ones are removed first.
+    pub max_files: Option<usize>,
+    /// The rotated files older than this are removed.
+    pub max_age: Option<Duration>,
+}
+
+struct Current {
+    writer: BufWriter<File>,
+    size: u64,
+    period: u64,
+}
+
+/// The log file rotated by its size or by time.
+pub struct LogFile {
+    config: FileConfig,
+    current: Mutex<Current>,
+    /// Held while the rotated files are compressed and removed, so that the rotations don't race.
+    cleanup: Arc<Mutex<()>>,
+}
+
+impl LogFile {
+    pub fn open(config: FileConfig) -> io::Result<Self> {
+        if let Some(parent) = config.path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        let current = open_current(&config)?;
+        Ok(Self {
+            config,
+            current: Mutex::new(current),
+            cleanup: Default::default(),
+        })
+    }
+
+    pub fn write_line(&self, line: &str) {
+        let mut current = self.current.lock();
+        if self.should_rotate(&current, line.len() as u64 + 1) {
+            if let Err(err) = self.rotate(&mut current) {
+                eprintln!("Cannot rotate the log file {}: {}", self.config.path.display(), err);
+            }
+        }
+        match writeln!(current.writer, "{}", line).and_then(|_| current.writer.flush()) {
+            Ok(()) => current.size += line.len() as u64 + 1,
+            Err(err) => eprintln!("Cannot write to the log file {}: {}", self.config.path.display(), err),
+        }
+    }
+
+    fn should_rotate(&self, current: &Current, incoming: u64) -> bool {
+        if current.size == 0 {
+            return false
+        }
+        let too_large = self.config.max_size.map_or(false, |max_size| current.size + incoming > max_size);
+        too_large || self.config.rotation.period(SystemTime::now()) != current.period
+    }
+
+    fn rotate(&self, current: &mut Current) -> io::Result<()> {
+        current.writer.flush()?;
+        let rotated = self.rotated_path();
+        fs::rename(&self.config.path, &rotated)?;
+        *current = open_current(&self.config)?;
+
+        let config = self.config.clone();
+        let cleanup = Arc::clone(&self.cleanup);
+        thread::Builder::new()
+            .name("log rotation".to_string())
+            .spawn(move || {
+                let _cleanup = cleanup.lock();
+                // A later rotation may have removed it already.
+                if config.compress && rotated.exists() {
+                    if let Err(err) = compress(&rotated) {
+                        eprintln!("Cannot compress the log file {}: {}", rotated.display(), err);
+                    }
+                }
+                if let Err(err) = remove_expired(&config) {
+                    eprintln!("Cannot remove the old log files of {}: {}", config.path.display(), err);
+                }
+            })
+            .map(|_| ())
+    }
+
+    /// `<path>.<timestamp>`, suffixed by a counter if the file is rotated twice in a second.
+    fn rotated_path(&self) -> PathBuf {
+        let timestamp = time::strftime("%Y%m%d-%H%M%S", &time::now_utc()).unwrap();
+        let base = format!("{}.{}", self.config.path.display(), timestamp);
+        // The counter follows the newest file of the second, even if the older ones are removed.
+        let next_counter = rotated_files(&self.config)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|((rotated_at, _), _)| *rotated_at == timestamp)
+            .map(|((_, counter), _)| counter + 1)
+            .max();
+        match next_counter {
+            Some(counter) => PathBuf::from(format!("{}-{}", base, counter)),
+            None => PathBuf::from(base),
+        }
+    }
+}
+
+fn open_current(config: &FileConfig) -> io::Result<Current> {
+    let file = OpenOptions::new().create(true).append(true).open(&config.path)?;
+    let metadata = file.metadata()?;
+    let created = metadata.modified().unwrap_or_else(|_| SystemTime::now());
+    Ok(Current {
+        writer: BufWriter::new(file),
+        size: metadata.len(),
+        period: config.rotation.period(created),
+    })
+}
+
+fn compress(path: &Path) -> io::Result<()> {
+    let compressed = PathBuf::from(format!("{}.gz", path.display()));
+    let mut encoder = GzEncoder::new(File::create(&compressed)?, Compression::default());
+    io::copy(&mut File::open(path)?, &mut encoder)?;
+    encoder.finish()?.sync_all()?;
+    fs::remove_file(path)
+}
+
+/// The rotated files of `config.path` with their orders, the newest first.
+fn rotated_files(config: &FileConfig) -> io::Result<Vec<((String, u64), PathBuf)>> {
+    let file_name = match config.path.file_name().and_then(|name| name.to_str()) {
+        Some(file_name) => format!("{}.", file_name),
+        None => return Ok(Vec::new()),
+    };
+    let dir = match config.path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let mut rotated = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if let Some(name) = entry.file_name().to_str().filter(|name| name.starts_with(&file_name)) {
+            rotated.push((rotation_order(&name[file_name.len()..]), entry.path()));
+        }
+    }
+    rotated.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+    Ok(rotated)
+}
+
+/// Applies the retention policies to the rotated files of `config.path`.
+fn remove_expired(config: &FileConfig) -> io::Result<()> {
+    let now = SystemTime::now();
+    for (index, (_, path)) in rotated_files(config)?.iter().enumerate() {
+        let too_many = config.max_files.map_or(false, |max_files| index >= max_files);
+        let too_old = config.max_age.map_or(false, |max_age| {
+            fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .map(|modified| now.duration_since(modified).map_or(false, |age| age > max_age))
+                .unwrap_or(false)
+        });
+        if too_many || too_old {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Orders the rotated files by the `<timestamp>[-<counter>][.gz]` suffixes of their names.
+/// The counters don't sort as text: `-1` comes before the `.gz` of the first file of the second.
+fn rotation_order(suffix: &str) -> (String, u64) {
+    let suffix = suffix.trim_end_matches(".gz");
+    let timestamp_len = "YYYYmmdd-HHMMSS".len();
+    match (suffix.get(..timestamp_len), suffix.get(timestamp_len..)) {
+        (Some(timestamp), Some(counter)) => {
+            (timestamp.to_string(), counter.trim_start_matches('-').parse().unwrap_or(0))
+        }
+        _ => (suffix.to_string(), 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use std::time::Instant;
+    use tempfile::TempDir;
+
+    fn config(dir: &TempDir, max_size: u64, compress: bool, max_files: Option<usize>) -> FileConfig {
+        FileConfig {
+            path: dir.path().join("foundry.log"),
+            max_size: Some(max_size),
+            rotation: Rotation::Never,
+            compress,
+            max_files,
+            max_age: None,
+        }
+    }
+
+    /// The rotated files, the oldest first.
+    fn rotated(config: &FileConfig) -> Vec<PathBuf> {
+        rotated_files(config).unwrap().into_iter().rev().map(|(_, path)| path).collect()
+    }
+
+    #[test]
+    fn rotation_is_parsed() {
+        assert_eq!(Ok(Rotation::Never), "never".parse());
+        assert_eq!(Ok(Rotation::Hourly), "hourly".parse());
+        assert_eq!(Ok(Rotation::Daily), "daily".parse());
+        assert!("weekly".parse::<Rotation>().is_err());
+    }
+
+    #[test]
+    fn rotated_files_are_ordered_by_their_timestamps_and_counters() {
+        let mut suffixes = vec!["20200102-000000.gz", "20200101-000000-10", "20200101-000000-2.gz", "20200101-000000"];
+        suffixes.sort_unstable_by_key(|suffix| rotation_order(suffix));
+        let expected = vec!["20200101-000000", "20200101-000000-2.gz", "20200101-000000-10", "20200102-000000.gz"];
+        assert_eq!(expected, suffixes);
+    }
+
+    #[test]
+    fn file_is_rotated_when_it_grows_over_the_max_size() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir, 20, false, None);
+        let file = LogFile::open(config.clone()).unwrap();
+        file.write_line("111111111");
+        file.write_line("222222222");
+        assert!(rotated(&config).is_empty());
+
+        file.write_line("333333333");
+        let rotated = rotated(&config);
+        assert_eq!(1, rotated.len());
+        assert_eq!("111111111\n222222222\n", fs::read_to_string(&rotated[0]).unwrap());
+        assert_eq!("333333333\n", fs::read_to_string(dir.path().join("foundry.log")).unwrap());
+    }
+
+    #[test]
+    fn only_the_newest_rotated_files_are_kept_compressed() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir, 10, true, Some(2));
+        let file = LogFile::open(config.clone()).unwrap();
+        for line in &["111111111", "222222222", "333333333", "444444444", "555555555"] {
+            file.write_line(line);
+        }
+
+        // The rotated files are compressed and removed in the background.
+        let start = Instant::now();
+        let rotated = loop {
+            let rotated = rotated(&config);
+            if rotated.len() == 2 && rotated.iter().all(|path| path.extension().map_or(false, |ext| ext == "gz")) {
+                break rotated
+            }
+            assert!(start.elapsed() < Duration::from_secs(10), "{:?}", rotated);
+            thread::sleep(Duration::from_millis(10));
+        };
+        let contents: Vec<_> = rotated
+            .iter()
+            .map(|path| {
+                let mut content = String::new();
+                GzDecoder::new(File::open(path).unwrap()).read_to_string(&mut content).unwrap();
+                content
+            })
+            .collect();
+        assert_eq!(vec!["333333333\n", "444444444\n"], contents);
+        assert_eq!("555555555\n", fs::read_to_string(dir.path().join("foundry.log")).unwrap());
+    }
+}
@@ -15,17 +15,21 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 mod email;
+mod file;
 mod logger;
 mod macros;
+mod request_id;
 mod structured_logger;
 
 use log;
 use log::SetLoggerError;
 
+pub use file::{FileConfig as LogFileConfig, LogFile, Rotation as LogRotation};
 pub use logger::Config as LoggerConfig;
 use logger::Logger;
 
 pub use log::Level;
+pub use request_id::{request_id, with_request_id};
 
 pub fn init(config: &LoggerConfig, email_alarm: Option<EmailAlarm>) -> Result<(), SetLoggerError> {
     let logger = Logger::new(config, email_alarm);
@@ -14,23 +14,27 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{email::EmailAlarm, structured_logger, SLOGGER};
+use crate::{email::EmailAlarm, file::LogFile, request_id, structured_logger, SLOGGER};
 use atty;
 use colored::Colorize;
 use env_logger::filter::{Builder as FilterBuilder, Filter};
 use log::{Level, LevelFilter, Log, Metadata, Record};
 use std::env;
+use std::sync::Arc;
 use std::thread;
 use time;
 
 pub struct Config {
     pub instance_id: usize,
+    /// The logs are written to the file too, if it's given.
+    pub file: Option<Arc<LogFile>>,
 }
 
 impl Config {
     pub fn new(instance_id: usize) -> Self {
         Self {
             instance_id,
+            file: None,
         }
     }
 }
@@ -40,6 +44,7 @@ pub struct Logger {
     filter: Filter,
     stderr_is_tty: bool,
     email_alarm: Option<EmailAlarm>,
+    file: Option<Arc<LogFile>>,
 }
 
 impl Logger {
@@ -58,6 +63,7 @@ impl Logger {
             filter: builder.build(),
             stderr_is_tty,
             email_alarm,
+            file: config.file.clone(),
         }
     }
 
@@ -77,6 +83,7 @@ impl Log for Logger {
             let timestamp = time::strftime("%Y-%m-%d %H:%M:%S.%f %Z", &time::now()).unwrap();
 
             let instance_id = self.instance_id;
+            let plain_timestamp = &timestamp;
             let timestamp = if self.stderr_is_tty {
                 timestamp.bold()
             } else {
@@ -90,10 +97,30 @@ impl Log for Logger {
             let log_level = record.level();
             let log_target = record.target();
             let log_message = record.args();
-            eprintln!(
-                "#{} {} {} {} {}  {}",
-                instance_id, timestamp, colored_thread_name, log_level, log_target, log_message
-            );
+            let request_id = request_id::request_id();
+            match &request_id {
+                Some(request_id) => eprintln!(
+                    "#{} {} {} {} {}  [{}] {}",
+                    instance_id, timestamp, colored_thread_name, log_level, log_target, request_id, log_message
+                ),
+                None => eprintln!(
+                    "#{} {} {} {} {}  {}",
+                    instance_id, timestamp, colored_thread_name, log_level, log_target, log_message
+                ),
+            }
+            if let Some(file) = &self.file {
+                let line = match &request_id {
+                    Some(request_id) => format!(
+                        "#{} {} {} {} {}  [{}] {}",
+                        instance_id, plain_timestamp, thread_name, log_level, log_target, request_id, log_message
+                    ),
+                    None => format!(
+                        "#{} {} {} {} {}  {}",
+                        instance_id, plain_timestamp, thread_name, log_level, log_target, log_message
+                    ),
+                };
+                file.write_line(&line);
+            }
 
             let rfc3339with_nano_second = "%Y-%m-%dT%H:%M:%S.%f%z";
             let timestamp = time::strftime(rfc3339with_nano_second, &time::now()).unwrap();
@@ -104,6 +131,7 @@ impl Log for Logger {
                 message: log_message.to_string(),
                 timestamp,
                 thread_name: thread_name.clone(),
+                request_id,
             });
 
             if log_level == Level::Error {
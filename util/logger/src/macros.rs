@@ -19,6 +19,9 @@ macro_rules! log_target {
     (BLOCKCHAIN) => {
         "blockchain"
     };
+    (CHECKPOINT) => {
+        "checkpoint"
+    };
     (CLIENT) => {
         "client"
     };
@@ -37,9 +40,15 @@ macro_rules! log_target {
     (IO) => {
         "io"
     };
+    (LIGHT) => {
+        "light"
+    };
     (MEM_POOL) => {
         "mem_pool"
     };
+    (MEMORY) => {
+        "memory"
+    };
     (MINER) => {
         "miner"
     };
@@ -0,0 +1,43 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::RefCell;
+
+thread_local! {
+    static REQUEST_ID: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// The id of the request being serviced by the current thread, if any.
+pub fn request_id() -> Option<String> {
+    REQUEST_ID.with(|current| current.borrow().clone())
+}
+
+/// Runs `f` with `request_id` attached to the logs emitted on the current thread.
+/// The previous id is restored when `f` returns.
+pub fn with_request_id<T, F: FnOnce() -> T>(request_id: Option<String>, f: F) -> T {
+    let previous = REQUEST_ID.with(|current| current.replace(request_id));
+    let _restore = Restore(previous);
+    f()
+}
+
+struct Restore(Option<String>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        REQUEST_ID.with(|current| *current.borrow_mut() = previous);
+    }
+}
@@ -40,6 +40,8 @@ pub struct Log {
     pub message: String,
     pub timestamp: String,
     pub thread_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl Log {
@@ -50,8 +52,14 @@ impl Log {
             message,
             timestamp,
             thread_name,
+            request_id,
         } = self;
-        level.len() + target.len() + message.len() + timestamp.len() + thread_name.len()
+        level.len()
+            + target.len()
+            + message.len()
+            + timestamp.len()
+            + thread_name.len()
+            + request_id.as_ref().map_or(0, String::len)
     }
 }
 
@@ -0,0 +1,195 @@
+// Copyright 2020 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ctimer::{TimeoutHandler, TimerToken};
+use parking_lot::Mutex;
+use std::sync::{Arc, Weak};
+
+/// The restrictions are lifted once the consumers use less than this percent of the limit.
+const RELIEF_PERCENT: usize = 75;
+
+/// A part of the node whose memory is accounted against the `MemoryBudget`.
+pub trait MemoryConsumer: Send + Sync {
+    /// The bytes used now.
+    fn memory_usage(&self) -> usize;
+
+    /// Frees the memory to use at most `allowance` bytes, and keeps it so until the next call.
+    /// `None` lifts the restriction, so the consumer may use as much as it is configured to.
+    ///
+    /// A consumer that cannot free its memory ignores it, and the others make room instead.
+    fn restrict_memory(&self, allowance: Option<usize>);
+}
+
+/// Splits `total` bytes between the parts in proportion to their usages.
+pub fn share(usages: &[usize], total: usize) -> Vec<usize> {
+    let sum: usize = usages.iter().sum();
+    usages
+        .iter()
+        .map(|usage| match sum {
+            0 => total / usages.len(),
+            _ => (*usage as u128 * total as u128 / sum as u128) as usize,
+        })
+        .collect()
+}
+
+/// The limit of the memory used by the caches and the queues of the node.
+///
+/// The consumers are checked periodically. When they use more than the limit in total, each of them is
+/// restricted to the share of the limit proportional to its usage.
+pub struct MemoryBudget {
+    limit: usize,
+    consumers: Mutex<Vec<(&'static str, Weak<dyn MemoryConsumer>)>>,
+    restricted: Mutex<bool>,
+}
+
+impl MemoryBudget {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            consumers: Default::default(),
+            restricted: Mutex::new(false),
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn register(&self, name: &'static str, consumer: Weak<dyn MemoryConsumer>) {
+        self.consumers.lock().push((name, consumer));
+    }
+
+    /// The usages of the consumers alive.
+    pub fn usages(&self) -> Vec<(&'static str, usize)> {
+        self.consumers().into_iter().map(|(name, consumer)| (name, consumer.memory_usage())).collect()
+    }
+
+    /// Restricts the consumers if they use more than the limit, and lifts the restrictions if they use
+    /// sufficiently less than it.
+    pub fn enforce(&self) {
+        let consumers = self.consumers();
+        let usages: Vec<usize> = consumers.iter().map(|(_, consumer)| consumer.memory_usage()).collect();
+        let total: usize = usages.iter().sum();
+
+        let mut restricted = self.restricted.lock();
+        if total > self.limit {
+            if !*restricted {
+                let breakdown: Vec<String> =
+                    consumers.iter().zip(&usages).map(|((name, _), usage)| format!("{}: {}", name, usage)).collect();
+                cwarn!(
+                    MEMORY,
+                    "{} bytes are used over the budget of {} bytes ({})",
+                    total,
+                    self.limit,
+                    breakdown.join(", ")
+                );
+            }
+            for ((name, consumer), allowance) in consumers.iter().zip(share(&usages, self.limit)) {
+                cdebug!(MEMORY, "Restrict {} to {} bytes", name, allowance);
+                consumer.restrict_memory(Some(allowance));
+            }
+            *restricted = true;
+        } else if *restricted && total <= self.limit / 100 * RELIEF_PERCENT {
+            cinfo!(MEMORY, "{} bytes are used within the budget of {} bytes", total, self.limit);
+            for (_, consumer) in &consumers {
+                consumer.restrict_memory(None);
+            }
+            *restricted = false;
+        }
+    }
+
+    fn consumers(&self) -> Vec<(&'static str, Arc<dyn MemoryConsumer>)> {
+        let mut consumers = self.consumers.lock();
+        consumers.retain(|(_, consumer)| consumer.upgrade().is_some());
+        consumers.iter().filter_map(|(name, consumer)| Some((*name, consumer.upgrade()?))).collect()
+    }
+}
+
+impl TimeoutHandler for MemoryBudget {
+    fn on_timeout(&self, _token: TimerToken) {
+        self.enforce();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Consumer {
+        usage: Mutex<usize>,
+        allowance: Mutex<Option<usize>>,
+    }
+
+    impl MemoryConsumer for Consumer {
+        fn memory_usage(&self) -> usize {
+            *self.usage.lock()
+        }
+
+        fn restrict_memory(&self, allowance: Option<usize>) {
+            if let Some(allowance) = allowance {
+                let mut usage = self.usage.lock();
+                *usage = (*usage).min(allowance);
+            }
+            *self.allowance.lock() = allowance;
+        }
+    }
+
+    fn consumer(usage: usize) -> Arc<Consumer> {
+        let consumer = Arc::new(Consumer::default());
+        *consumer.usage.lock() = usage;
+        consumer
+    }
+
+    #[test]
+    fn consumers_are_restricted_in_proportion_to_their_usages() {
+        let budget = MemoryBudget::new(100);
+        let (large, small) = (consumer(150), consumer(50));
+        budget.register("large", Arc::downgrade(&large) as Weak<dyn MemoryConsumer>);
+        budget.register("small", Arc::downgrade(&small) as Weak<dyn MemoryConsumer>);
+
+        budget.enforce();
+        assert_eq!(Some(75), *large.allowance.lock());
+        assert_eq!(Some(25), *small.allowance.lock());
+        assert_eq!(vec![("large", 75), ("small", 25)], budget.usages());
+    }
+
+    #[test]
+    fn restrictions_are_lifted_when_the_usage_drops_enough() {
+        let budget = MemoryBudget::new(100);
+        let cache = consumer(120);
+        budget.register("cache", Arc::downgrade(&cache) as Weak<dyn MemoryConsumer>);
+        budget.enforce();
+        assert_eq!(Some(100), *cache.allowance.lock());
+
+        *cache.usage.lock() = 80;
+        budget.enforce();
+        assert_eq!(Some(100), *cache.allowance.lock());
+
+        *cache.usage.lock() = 70;
+        budget.enforce();
+        assert_eq!(None, *cache.allowance.lock());
+    }
+
+    #[test]
+    fn dropped_consumers_are_forgotten() {
+        let budget = MemoryBudget::new(100);
+        let cache = consumer(10);
+        budget.register("cache", Arc::downgrade(&cache) as Weak<dyn MemoryConsumer>);
+        drop(cache);
+        assert_eq!(Vec::<(&str, usize)>::new(), budget.usages());
+    }
+}